@@ -0,0 +1,114 @@
+use crate::level::Level;
+use crate::table::{self, Format};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Common Log Format, optionally followed by the combined format's referer
+/// and user agent (nginx's default), then anything a custom format appends.
+static ACCESS_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(\S+) \S+ (\S+) \[[^\]]+\] "(\S+) (\S+)(?: HTTP/[\d.]+)?" (\d{3}) (\d+|-)(?: "([^"]*)" "([^"]*)")?(.*)$"#)
+        .unwrap()
+});
+/// Request time appended as `rt=0.012`, `request_time=0.012` and the like.
+static LATENCY_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:rt|request_time|upstream_response_time|latency|duration)=(\d+(?:\.\d+)?)").unwrap()
+});
+/// Request time appended as a bare number ending the line.
+static LATENCY_TAIL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)(\d+(?:\.\d+)?)\s*$").unwrap());
+
+/// One request from an Apache or nginx access log.
+#[derive(Debug, PartialEq)]
+pub struct Access<'a> {
+    pub ip: &'a str,
+    pub user: Option<&'a str>,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub status: u16,
+    pub bytes: Option<&'a str>,
+    pub referer: Option<&'a str>,
+    pub agent: Option<&'a str>,
+    pub latency: Option<&'a str>,
+}
+
+/// Whether `--format` lets lines be read as access log records.
+pub fn enabled() -> bool {
+    matches!(table::format(), Format::Auto | Format::Access)
+}
+
+pub fn parse(line: &str) -> Option<Access<'_>> {
+    let caps = ACCESS_LINE.captures(line)?;
+    let known = |i: usize| caps.get(i).map(|m| m.as_str()).filter(|s| !s.is_empty() && *s != "-");
+    let rest = caps.get(9).map_or("", |m| m.as_str());
+    let latency = [&LATENCY_FIELD, &LATENCY_TAIL]
+        .iter()
+        .find_map(|re| Some(re.captures(rest)?.get(1)?.as_str()));
+    Some(Access {
+        ip: caps.get(1)?.as_str(),
+        user: known(2),
+        method: caps.get(3)?.as_str(),
+        path: caps.get(4)?.as_str(),
+        status: caps.get(5)?.as_str().parse().ok()?,
+        bytes: known(6),
+        referer: known(7),
+        agent: known(8),
+        latency,
+    })
+}
+
+impl Access<'_> {
+    /// Server errors as errors, client errors as warnings.
+    pub fn level(&self) -> Level {
+        match self.status {
+            500.. => Level::Error,
+            400..=499 => Level::Warn,
+            _ => Level::Info,
+        }
+    }
+
+    /// The request as `name=value` fields for display and field filters.
+    pub fn fields(&self) -> String {
+        let mut fields = vec![
+            format!("ip={}", self.ip),
+            format!("method={}", self.method),
+            format!("path={}", self.path),
+            format!("status={}", self.status),
+            format!("bytes={}", self.bytes.unwrap_or("0")),
+        ];
+        // Access logs give seconds; shown in ms like the other durations.
+        if let Some(secs) = self.latency.and_then(|l| l.parse::<f64>().ok()) {
+            fields.push(format!("latency_ms={}", (secs * 1e6).round() / 1e3));
+        }
+        if let Some(user) = self.user {
+            fields.push(format!("user={}", user));
+        }
+        if let Some(referer) = self.referer {
+            fields.push(format!("referer={}", referer));
+        }
+        if let Some(agent) = self.agent {
+            fields.push(format!("agent=\"{}\"", agent));
+        }
+        fields.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_access_lines() {
+        let combined = r#"203.0.113.9 - - [31/Jan/2024:14:05:01 +0000] "GET /api/users?id=3 HTTP/1.1" 404 153 "-" "curl/8.4.0" 0.012"#;
+        let access = parse(combined).unwrap();
+        assert_eq!((access.method, access.path, access.status, access.latency), ("GET", "/api/users?id=3", 404, Some("0.012")));
+        assert_eq!(access.level(), Level::Warn);
+        assert_eq!(
+            access.fields(),
+            r#"ip=203.0.113.9 method=GET path=/api/users?id=3 status=404 bytes=153 latency_ms=12 agent="curl/8.4.0""#
+        );
+
+        let common = r#"10.0.0.1 - alice [31/Jan/2024:14:05:02 +0000] "POST /login HTTP/1.0" 502 -"#;
+        let access = parse(common).unwrap();
+        assert_eq!((access.user, access.bytes, access.latency, access.level()), (Some("alice"), None, None, Level::Error));
+        assert!(parse("2024-01-31 14:05:01 INFO GET /api/users 200").is_none());
+    }
+}
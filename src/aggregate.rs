@@ -0,0 +1,233 @@
+use crate::filter::{key_value_pattern, literal_pattern};
+use anyhow::{anyhow, Result};
+use fancy_regex::Regex;
+use std::collections::HashMap;
+
+/// Label of the group of lines that have the value but not the group-by field.
+const NO_GROUP: &str = "(none)";
+
+/// Where a field's value is read from: `key=value`, JSON `"key": value`, or
+/// the first capture group of a regex.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    regex: Regex,
+}
+
+impl Field {
+    /// A plain name is looked up as a logfmt or JSON key; anything with a
+    /// capture group is taken as a regex.
+    pub fn new(spec: &str) -> Result<Self> {
+        let pattern = if spec.contains('(') {
+            spec.to_string()
+        } else {
+            let key = regex::escape(spec);
+            format!(r#"(?<![\w.-])["']?{}["']?\s*[:=]\s*(?:"([^"]*)"|'([^']*)'|([^\s,;}}\]]+))"#, key)
+        };
+        let regex = Regex::new(&pattern).map_err(|e| anyhow!("{}", e))?;
+        if regex.captures_len() < 2 {
+            return Err(anyhow!("'{}' needs a capture group", spec));
+        }
+        Ok(Self {
+            name: spec.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether this is a logfmt/JSON key rather than a regex.
+    pub fn is_key(&self) -> bool {
+        !self.name.contains('(')
+    }
+
+    pub fn value<'a>(&self, content: &'a str) -> Option<&'a str> {
+        let caps = self.regex.captures(content).ok()??;
+        caps.iter().skip(1).flatten().next().map(|m| m.as_str())
+    }
+}
+
+/// `latency_ms` or `latency_ms by endpoint`.
+#[derive(Clone, Debug)]
+pub struct StatsQuery {
+    pub field: Field,
+    pub group_by: Option<Field>,
+}
+
+impl StatsQuery {
+    pub fn parse(arg: &str) -> Result<Self> {
+        let words: Vec<&str> = arg.split_whitespace().collect();
+        match words.as_slice() {
+            [field] => Ok(Self {
+                field: Field::new(field)?,
+                group_by: None,
+            }),
+            [field, "by", group] => Ok(Self {
+                field: Field::new(field)?,
+                group_by: Some(Field::new(group)?),
+            }),
+            _ => Err(anyhow!("Usage: :stats <field> [by <field>]")),
+        }
+    }
+
+    /// Aggregate the field over `lines`, busiest group first.
+    pub fn run<'a>(&self, lines: impl IntoIterator<Item = &'a str>) -> FieldStats {
+        let mut groups: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut unparsed = 0;
+        for content in lines {
+            let Some(raw) = self.field.value(content) else {
+                continue;
+            };
+            let Some(value) = leading_number(raw) else {
+                unparsed += 1;
+                continue;
+            };
+            let group = match &self.group_by {
+                Some(field) => field.value(content).unwrap_or(NO_GROUP),
+                None => "",
+            };
+            groups.entry(group.to_string()).or_default().push(value);
+        }
+        let mut groups: Vec<(String, Summary)> = groups
+            .into_iter()
+            .filter_map(|(name, values)| Some((name, Summary::of(values)?)))
+            .collect();
+        groups.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(&b.0)));
+        FieldStats {
+            title: match &self.group_by {
+                Some(group) => format!("{} by {}", self.field.name, group.name),
+                None => self.field.name.clone(),
+            },
+            groups,
+            unparsed,
+        }
+    }
+}
+
+/// How often each value of a field occurs, most frequent first.
+#[derive(Clone, Debug)]
+pub struct TopValues {
+    pub field: Field,
+    pub values: Vec<(String, usize)>,
+    /// Lines that have the field at all.
+    pub total: usize,
+}
+
+impl TopValues {
+    pub fn count<'a>(field: Field, lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for value in lines.into_iter().filter_map(|content| field.value(content)) {
+            *counts.entry(value.to_string()).or_default() += 1;
+        }
+        let total = counts.values().sum();
+        let mut values: Vec<(String, usize)> = counts.into_iter().collect();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Self { field, values, total }
+    }
+
+    pub fn percent(&self, count: usize) -> f64 {
+        count as f64 * 100.0 / self.total.max(1) as f64
+    }
+
+    /// A filter term for lines where the field has `value`.
+    pub fn filter_term(&self, value: &str) -> String {
+        if self.field.is_key() {
+            key_value_pattern(&self.field.name, value)
+        } else {
+            literal_pattern(value)
+        }
+    }
+}
+
+/// The number a value starts with, so `12.5ms` reads as 12.5.
+fn leading_number(raw: &str) -> Option<f64> {
+    let raw = raw.trim();
+    let end = raw
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map_or(raw.len(), |(i, _)| i);
+    raw[..end].parse().ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Summary {
+    pub fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        // Nearest rank, so every percentile is a value that was seen.
+        let rank = |p: f64| values[((p * values.len() as f64).ceil() as usize).clamp(1, values.len()) - 1];
+        Some(Self {
+            count: values.len(),
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p50: rank(0.50),
+            p95: rank(0.95),
+            p99: rank(0.99),
+        })
+    }
+}
+
+/// What `:stats` found: one summary, or one per group-by value.
+#[derive(Clone, Debug)]
+pub struct FieldStats {
+    pub title: String,
+    pub groups: Vec<(String, Summary)>,
+    /// Lines with the field whose value isn't a number.
+    pub unparsed: usize,
+}
+
+/// Up to 4 decimals, without trailing zeros.
+pub fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_by_group() {
+        let query = StatsQuery::parse("latency_ms by endpoint").unwrap();
+        let lines = [
+            r#"{"endpoint": "/a", "latency_ms": 10}"#,
+            "endpoint=/a latency_ms=30ms",
+            "endpoint=/b latency_ms=5",
+            "latency_ms=7",
+            "latency_ms=n/a endpoint=/b",
+            "no field here",
+        ];
+        let stats = query.run(lines);
+        assert_eq!(stats.title, "latency_ms by endpoint");
+        assert_eq!(stats.unparsed, 1);
+        let groups: Vec<(&str, usize, f64)> = stats.groups.iter().map(|(g, s)| (g.as_str(), s.count, s.mean)).collect();
+        assert_eq!(groups, [("/a", 2, 20.0), ("(none)", 1, 7.0), ("/b", 1, 5.0)]);
+
+        let summary = Summary::of((1..=100).map(f64::from).collect()).unwrap();
+        assert_eq!((summary.p50, summary.p95, summary.p99, summary.max), (50.0, 95.0, 99.0, 100.0));
+        assert_eq!(format_number(2.50), "2.5");
+        assert!(StatsQuery::parse("a by").is_err());
+    }
+
+    #[test]
+    fn test_top_values() {
+        let lines = ["status=200", r#"{"status": 404}"#, "status=200 again", "status=\"200\"", "none"];
+        let top = TopValues::count(Field::new("status").unwrap(), lines);
+        assert_eq!(top.values, [("200".to_string(), 3), ("404".to_string(), 1)]);
+        assert_eq!(top.percent(3), 75.0);
+        let filter = crate::filter::parse_filter(&top.filter_term("200")).unwrap();
+        assert!(filter.matches(r#"{"status":"200"}"#) && filter.matches("status=200 ok"));
+        assert!(!filter.matches("status=2001") && !filter.matches("other=200"));
+    }
+}
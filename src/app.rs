@@ -1,71 +1,430 @@
-use crate::constants::{PREFIX_WIDTH_WITHOUT_TIME, PREFIX_WIDTH_WITH_TIME};
-use crate::core::{FilterState, InputFields, InputMode, ListenState, LogLine, LogState};
-use crate::filter::parse_filter;
-use crate::highlight::{apply_highlights_ratatui, highlight_line};
-use crate::source::SourceEvent;
+use crate::aggregate::{Field, FieldStats, StatsQuery, TopValues};
+use crate::blame;
+use crate::bundle::{bundle_path, Bundle};
+use crate::checkpoint;
+use crate::columns::{self, Column, TimeFormat, MAX_COLUMN_WIDTH, MIN_COLUMN_WIDTH};
+use crate::correlate::{self, Correlation};
+use crate::core::{
+    markers, DetailState, EntityMenu, InputFields, InputMode, ListenState, LogLine, LogState, NoteEditor,
+    Buffers, Pins, Playground, PlaygroundTarget, Selection, Severity, Snooze, Toasts, Why,
+};
+use crate::dedup::{Dedup, DEFAULT_WINDOW_SECS};
+use crate::humanize::Humanize;
+use crate::enrich::Enricher;
+use crate::rdns::PeerNames;
+use crate::entity::find_entities;
+use crate::core::marks::template_filter;
+use crate::filter::{and_clause, field_pattern, literal_pattern, parse_filter, word_pattern};
+use crate::filter_stats::FilterStat;
+use crate::highlight::json_tree::JsonTree;
+use crate::highlight::{apply_highlights_ratatui, highlight_line, pretty_print, token_at, ClickToken};
+use crate::input::TextInput;
+use crate::issue::{self, Record};
+use crate::keymap::Keymap;
+use crate::layout::{self, Layout};
+use crate::macros::{encode_key, MacroPrompt};
+use crate::metrics;
+use crate::picker::{Choice, Picker};
+use crate::pipe::{self, PipeJob, PipeOutput};
+use crate::plugin;
+use crate::replay::{Replay, ReplayHandle};
+use crate::sample::Sampling;
+use crate::session::{SavedNote, Session};
+use crate::source_ref;
+use crate::shutdown::Summary;
+use crate::source::{start_source, FileOptions, LogSource, ReadFrom, Since, SourceEvent};
+use crate::stacktrace;
+use crate::time_display::TimeDisplay;
+use crate::tui::status::Segment;
+use crate::level::{self, Level, LevelRule};
+use crate::prefix::{self, PrefixRule};
 use crate::state::AppState;
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent};
 use fancy_regex::Regex;
-use std::sync::mpsc::Receiver;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::time::Instant;
+use unicode_width::UnicodeWidthChar;
+
+/// Highlighted text runs for one row of a log record.
+pub struct Row {
+    pub runs: Vec<(String, Style)>,
+    /// The separately highlighted segment of the display content this row
+    /// comes from, and the row's byte offset within it. `None` for rows that
+    /// aren't log text, like the stack trace fold marker.
+    pub source: Option<(Range<usize>, usize)>,
+}
+
+/// Where a row of the log view came from, for mapping mouse clicks back to
+/// log text.
+pub struct RowHit {
+    pub y: u16,
+    pub x: u16,
+    pub line_idx: usize,
+    pub segment: Range<usize>,
+    pub offset: usize,
+    pub text: String,
+}
+
+/// Split highlighted runs of `segment` at embedded newlines.
+pub fn split_rows(runs: Vec<(String, Style)>, segment: Range<usize>) -> Vec<Row> {
+    let mut rows = vec![Row {
+        runs: Vec::new(),
+        source: Some((segment.clone(), 0)),
+    }];
+    let mut offset = 0;
+    for (text, style) in runs {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                offset += 1;
+                rows.push(Row {
+                    runs: Vec::new(),
+                    source: Some((segment.clone(), offset)),
+                });
+            }
+            if !part.is_empty() {
+                rows.last_mut().unwrap().runs.push((part.to_string(), style));
+            }
+            offset += part.len();
+        }
+    }
+    rows
+}
 
 pub struct App {
     pub log_state: LogState,
     pub input_fields: InputFields,
-    pub filter_state: FilterState,
     pub listen_state: ListenState,
     pub show_time: bool,
+    /// Draw a lane per thread or process between the columns and the text.
+    pub show_lanes: bool,
+    /// Draw a column ruler above the log text.
+    pub show_ruler: bool,
+    /// Character column on the cursor line, set by clicking or Left/Right.
+    pub cursor_column: usize,
+    pub selection: Option<Selection>,
     pub wrap_lines: bool,
     pub input_mode: InputMode,
     pub source_rx: Receiver<SourceEvent>,
-    pub status_message: Option<String>,
+    pub toasts: Toasts,
     pub show_quit_confirm: bool,
+    /// Text pasted in normal mode, until it is taken as log lines or keys.
+    pub paste_prompt: Option<String>,
+    pub detail: Option<DetailState>,
+    /// Lines whose stack trace is shown in full instead of folded.
+    pub expanded_traces: HashSet<usize>,
+    pub entity_menu: Option<EntityMenu>,
+    /// Rows drawn in the log view last frame.
+    pub row_hits: Vec<RowHit>,
+    pub columns: Vec<Column>,
+    /// Selected row of the column layout popup, when open.
+    pub column_editor: Option<usize>,
+    pub show_watches: bool,
+    pub note_editor: Option<NoteEditor>,
+    pub playground: Option<Playground>,
+    /// Viewing a bundle: settings and notes are not written back.
+    pub read_only: bool,
+    pub replay: Option<ReplayHandle>,
+    pub macros: BTreeMap<char, String>,
+    /// Register being recorded into, and the keys so far.
+    pub recording: Option<(char, String)>,
+    pub macro_prompt: Option<MacroPrompt>,
+    pub keymap: Keymap,
+    /// The `keymap` setting the bindings were built from.
+    pub key_bindings: BTreeMap<String, String>,
+    pub status_segments: Vec<Segment>,
+    pub correlation_ids: Vec<String>,
+    pub editor_command: String,
+    pub issue_url: String,
+    /// Blame `file:line` references in the detail popup.
+    pub git_blame: bool,
+    pub geoip_databases: Vec<String>,
+    pub reverse_dns: bool,
+    pub peer_names: PeerNames,
+    pub level_rules: Vec<LevelRule>,
+    pub prefix_rules: Vec<PrefixRule>,
+    /// The filter `C` replaced with a request ID, put back by the next `C`.
+    pub correlated: Option<String>,
+    pub time_format: TimeFormat,
+    /// Zone and formats of clock and event times.
+    pub time_display: TimeDisplay,
+    /// Selected row of the filter statistics popup, when open.
+    pub filter_stats_popup: Option<usize>,
+    /// Where the minimap was drawn last frame.
+    pub minimap: Option<Rect>,
+    /// Half screens to scroll the log view by (down when positive), carried
+    /// out at the next draw where the wrapped rows are known.
+    pub page_scroll: isize,
+    pub show_plugins: bool,
+    /// Selected bucket of the time outline panel, when open.
+    pub outline_panel: Option<usize>,
+    /// Rows the `:messages` panel is scrolled up from the newest, when open.
+    pub messages_popup: Option<usize>,
+    /// What the last `:stats` found, while its popup is open.
+    pub stats_popup: Option<FieldStats>,
+    /// Groups the `:stats` popup is scrolled down by.
+    pub stats_scroll: usize,
+    /// The `:top` value counts and the selected row, while open.
+    pub top_popup: Option<(TopValues, usize)>,
+    /// A `:pipe` command still running.
+    pub pipe_job: Option<PipeJob>,
+    /// A `:export` to a plugin still running.
+    export_job: Option<Receiver<anyhow::Result<String>>>,
+    /// The last `:pipe` output and how far it is scrolled, while open.
+    pub pipe_popup: Option<(PipeOutput, usize)>,
+    /// The selected row of the `:snoozes` list, while open.
+    pub snooze_popup: Option<usize>,
+    /// The selected row of the fields panel, while open.
+    pub fields_popup: Option<usize>,
+    /// Why a line is shown or hidden, from `?` or `:why`.
+    pub why: Option<Why>,
+    /// Saved layouts, and the name of the one last switched to.
+    pub layouts: BTreeMap<String, Layout>,
+    pub layout: Option<String>,
+    /// The second pane of the split view, while it is open.
+    pub split: Option<SplitPane>,
+    /// Named buffers besides the one in view (`:buffer`).
+    pub buffers: Buffers,
+    /// The `:open` file finder, when open.
+    pub file_picker: Option<Picker>,
+    /// Where `:open` sends a file's lines; `None` for bundles and replays.
+    pub source_tx: Option<SyncSender<SourceEvent>>,
+    /// Set by Ctrl-Z or `:sh`; the event loop hands the terminal back.
+    pub suspend: Option<Suspend>,
+    /// Confirmed quit; the event loop shuts down on its next pass.
+    pub quit: bool,
+    /// Lines received from sources, and discarded by clearing.
+    pub ingested: usize,
+    pub dropped: usize,
+    pub started: Instant,
+}
+
+/// The pane of the split view the keys are not moving; `log_state` holds the
+/// position of the other one.
+#[derive(Clone, Copy, Debug)]
+pub struct SplitPane {
+    /// Buffer line at the bottom of the pane; `None` follows the tail.
+    pub line_idx: Option<usize>,
+    /// The keys move the top pane.
+    pub focus_top: bool,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Suspend {
+    /// Run an interactive shell while the sources keep ingesting.
+    Shell,
+    /// Run this shell command, an editor, the same way.
+    Edit(String),
 }
 
 impl App {
-    pub fn new(source_rx: Receiver<SourceEvent>, listen_port: Option<u16>) -> Self {
-        let state = AppState::load();
+    pub fn new(source_rx: Receiver<SourceEvent>, listen_ports: Vec<u16>) -> Self {
+        let (state, problem) = AppState::load_reporting();
+        let session = Session::load();
+        let mut app = Self::with_state(source_rx, listen_ports, state, session.notes, false);
+        app.log_state.pins = Pins::from_saved(session.pins);
+        if let Some(problem) = problem {
+            app.toasts.warn(problem);
+        }
+        app
+    }
+
+    /// Open a bundle read-only, with the settings it was saved with.
+    pub fn from_bundle(source_rx: Receiver<SourceEvent>, bundle: Bundle) -> Self {
+        level::set_rules(&bundle.settings.level_rules);
+        let lines = bundle.log_lines();
+        let mut app = Self::with_state(source_rx, Vec::new(), bundle.settings, bundle.notes, true);
+        app.log_state.load(lines);
+        app
+    }
+
+    /// Pick up a crash session where it stopped, still taking new lines.
+    pub fn resume(source_rx: Receiver<SourceEvent>, listen_ports: Vec<u16>, bundle: Bundle) -> Self {
+        level::set_rules(&bundle.settings.level_rules);
+        let lines = bundle.log_lines();
+        let mut app = Self::with_state(source_rx, listen_ports, bundle.settings, bundle.notes, false);
+        app.log_state.load(lines);
+        if let Some(idx) = bundle.position {
+            let pos = app.log_state.filtered_indices.binary_search(&idx).unwrap_or_else(|pos| pos);
+            app.log_state.scroll_to(pos);
+        }
+        app.toasts.info(format!("Resumed crash session ({} lines)", app.log_state.lines.len()));
+        app
+    }
+
+    /// The buffer, settings, notes and view position, for the crash file.
+    pub fn crash_bundle(&self) -> Bundle {
+        let mut bundle = Bundle::new(
+            &self.log_state.lines,
+            self.current_state(),
+            self.log_state.notes.to_saved(&self.log_state.lines),
+        );
+        if !self.log_state.follow_tail {
+            bundle.position = self.log_state.filtered_indices.get(self.log_state.get_bottom_line_idx()).copied();
+        }
+        bundle
+    }
+
+
+    fn with_state(
+        source_rx: Receiver<SourceEvent>,
+        listen_ports: Vec<u16>,
+        state: AppState,
+        notes: Vec<SavedNote>,
+        read_only: bool,
+    ) -> Self {
+        let (keymap, keymap_errors) = Keymap::new(&state.keymap);
+        let rule_errors = level::set_rules(&state.level_rules);
+        let prefix_errors = prefix::set_rules(&state.prefix_rules);
+        let time_display = TimeDisplay::new(&state.time_zone, &state.clock_format, &state.event_time_format);
         let mut app = Self {
-            log_state: LogState::default(),
+            log_state: LogState::from_state(&state, notes),
             input_fields: InputFields::from_state(&state),
-            filter_state: FilterState::default(),
-            listen_state: ListenState::new(listen_port),
+            listen_state: ListenState::new(listen_ports),
             show_time: true,
+            show_lanes: false,
+            show_ruler: false,
+            cursor_column: 0,
+            selection: None,
             wrap_lines: state.wrap_lines,
             input_mode: InputMode::Normal,
             source_rx,
-            status_message: None,
+            toasts: Toasts::default(),
             show_quit_confirm: false,
+            paste_prompt: None,
+            detail: None,
+            expanded_traces: HashSet::new(),
+            entity_menu: None,
+            row_hits: Vec::new(),
+            columns: columns::normalize(state.columns.clone()),
+            column_editor: None,
+            show_watches: false,
+            note_editor: None,
+            playground: None,
+            read_only,
+            replay: None,
+            macros: state.macros.clone(),
+            recording: None,
+            macro_prompt: None,
+            keymap,
+            key_bindings: state.keymap.clone(),
+            status_segments: state.status_segments.clone(),
+            correlation_ids: state.correlation_ids.clone(),
+            editor_command: state.editor_command.clone(),
+            issue_url: state.issue_url.clone(),
+            git_blame: state.git_blame,
+            geoip_databases: state.geoip_databases.clone(),
+            reverse_dns: state.reverse_dns,
+            peer_names: PeerNames::new(state.numeric_peers),
+            level_rules: state.level_rules.clone(),
+            prefix_rules: state.prefix_rules.clone(),
+            correlated: None,
+            time_format: state.time_format,
+            time_display: time_display.as_ref().cloned().unwrap_or_default(),
+            filter_stats_popup: None,
+            minimap: None,
+            page_scroll: 0,
+            show_plugins: false,
+            outline_panel: None,
+            messages_popup: None,
+            stats_popup: None,
+            stats_scroll: 0,
+            top_popup: None,
+            pipe_job: None,
+            export_job: None,
+            pipe_popup: None,
+            snooze_popup: None,
+            fields_popup: None,
+            why: None,
+            layouts: state.layouts.clone(),
+            layout: None,
+            split: None,
+            buffers: Buffers::default(),
+            file_picker: None,
+            source_tx: None,
+            suspend: None,
+            quit: false,
+            ingested: 0,
+            dropped: 0,
+            started: Instant::now(),
         };
         app.apply_hide();
+        app.apply_rewrite();
         app.apply_filter();
         app.apply_highlight();
+        app.apply_watch();
+        if !keymap_errors.is_empty() {
+            app.toasts.warn(format!("Keymap: {}", keymap_errors.join(", ")));
+        }
+        if !rule_errors.is_empty() {
+            app.toasts.warn(format!("Bad level rule patterns: {}", rule_errors.join(", ")));
+        }
+        if !prefix_errors.is_empty() {
+            app.toasts.warn(format!("Bad prefix rule patterns: {}", prefix_errors.join(", ")));
+        }
+        if let Err(e) = time_display {
+            app.toasts.warn(format!("{:#}; showing local times", e));
+        }
         app
     }
 
     pub fn poll_source(&mut self) {
+        // New lines must not move the lines being selected.
+        match self.selection {
+            Some(_) => self.log_state.hold_view(),
+            None => {
+                self.log_state.release_view();
+            }
+        }
+        let mut received = false;
+        let live = self.buffers.live(&mut self.log_state);
         while let Ok(event) = self.source_rx.try_recv() {
+            received = true;
             match event {
-                SourceEvent::Line(content) => {
-                    let idx = self.log_state.add_line(content);
-                    if self.matches_filter(idx) {
-                        self.log_state.filtered_indices.push(idx);
-                    }
+                SourceEvent::Line(content, source) => {
+                    live.ingest(content, source);
+                    self.ingested += 1;
                 }
                 SourceEvent::SystemLine(content) => {
-                    let idx = self.log_state.add_line_with_update(content, false);
-                    if self.matches_filter(idx) {
-                        self.log_state.filtered_indices.push(idx);
-                    }
+                    live.ingest_system(content);
                 }
                 SourceEvent::Error(e) => {
-                    self.status_message = Some(format!("Source error: {}", e));
+                    self.toasts.error(format!("Source error: {}", e));
                 }
-                SourceEvent::Connected(_peer) => {
+                SourceEvent::Connected(peer) => {
                     self.listen_state.has_connection = true;
+                    self.listen_state.peers.push(peer);
+                }
+                SourceEvent::Disconnected(peer) => {
+                    if let Some(idx) = self.listen_state.peers.iter().position(|p| *p == peer) {
+                        self.listen_state.peers.remove(idx);
+                    }
                 }
-                SourceEvent::Disconnected(_peer) => {}
+                SourceEvent::Backfill(started) => live.backfill(started),
+                SourceEvent::Checkpoint(path, offset) => checkpoint::save(&path, offset),
             }
         }
+        live.flush_sample();
+        self.poll_pipe();
+        self.poll_export();
+        if self.time_display.check_rollover(&self.log_state.lines) {
+            columns::set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+            columns::set_event_width(&mut self.columns, &self.time_display);
+            self.toasts.info("The log now spans midnight; times show the date");
+        }
+        if self.log_state.expire_snoozes() {
+            self.toasts.info("A snooze ran out; its lines are back");
+        }
+        self.log_state.refresh_enrichment();
+        if received {
+            let live = self.buffers.live_ref(&self.log_state);
+            metrics::record_buffer(live.lines.len(), &live.watches);
+        }
     }
 
     pub fn handle_input_key(&mut self, key_code: KeyCode) -> bool {
@@ -114,90 +473,606 @@ impl App {
                     self.input_mode = InputMode::Normal;
                 }
             }
+            InputMode::RewriteEdit => {
+                self.apply_rewrite();
+                if !self.input_fields.rewrite.has_error() {
+                    self.input_mode = InputMode::Normal;
+                }
+            }
+            InputMode::WatchEdit => {
+                self.apply_watch();
+                if !self.input_fields.watch.has_error() {
+                    self.input_mode = InputMode::Normal;
+                }
+            }
+            InputMode::Command => {
+                let command = std::mem::take(&mut self.input_fields.command).text;
+                self.input_mode = InputMode::Normal;
+                self.run_command(&command);
+            }
             InputMode::Normal => {}
         }
     }
 
-    pub fn get_display_content(&self, line: &LogLine) -> Result<String, String> {
-        self.filter_state.apply_hide(&line.content)
+    pub fn with_replay(&self, f: impl FnOnce(&mut Replay)) {
+        if let Some(mut replay) = self.replay.as_ref().and_then(|r| r.lock().ok()) {
+            f(&mut replay);
+        }
     }
 
-    fn matches_filter(&self, idx: usize) -> bool {
-        if idx >= self.log_state.lines.len() {
+    /// Status of the replay, e.g. `[REPLAY 2× 120 left]`.
+    pub fn replay_label(&self) -> Option<String> {
+        self.replay.as_ref()?.lock().ok().map(|r| r.label())
+    }
+
+    /// Run a `:` command.
+    pub fn run_command(&mut self, command: &str) {
+        let command = command.trim();
+        let (name, arg) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, arg)| (name, arg.trim()));
+        match name {
+            "bundle" if !arg.is_empty() => self.export_bundle(arg),
+            "bundle" => self.toasts.warn("Usage: :bundle <file.lvz>"),
+            "follow" => self.follow_match(arg),
+            "dedup" => self.set_dedup(arg),
+            "humanize" => self.set_humanize(arg),
+            "savefilter" if !arg.is_empty() => self.save_filter(arg),
+            "dropfilter" if !arg.is_empty() => self.drop_filter(arg),
+            "savefilter" | "dropfilter" => self.toasts.warn(format!("Usage: :{} <name>", name)),
+            "sh" | "shell" => self.suspend = Some(Suspend::Shell),
+            "plugins" => self.show_plugins = !self.show_plugins,
+            "messages" => self.messages_popup = Some(0),
+            "stats" => self.field_stats(arg),
+            "top" if !arg.is_empty() => self.top_values(arg),
+            "top" => self.toasts.warn("Usage: :top <field>"),
+            "open" if !arg.is_empty() => self.open_file(PathBuf::from(arg)),
+            "open" if self.source_tx.is_some() => self.file_picker = Some(Picker::files()),
+            "open" => self.toasts.warn("Only live views can open more files"),
+            "export" if !arg.is_empty() => self.export_to_plugin(arg),
+            "export" => self.toasts.warn("Usage: :export <plugin>"),
+            "blame" => self.toggle_git_blame(),
+            "enrich" => self.toggle_enrich(),
+            "numeric" => self.peer_names.numeric = !self.peer_names.numeric,
+            "tint" => self.log_state.filter_state.highlight.level_tint ^= true,
+            "pipe" if arg == "cancel" => {
+                if !self.cancel_pipe() {
+                    self.toasts.warn("No :pipe command is running");
+                }
+            }
+            "pipe" if !arg.is_empty() => self.pipe(arg),
+            "pipe" => self.toasts.warn("Usage: :pipe <command>"),
+            "tz" => self.set_time_zone(arg),
+            "timefmt" => self.set_time_format(arg),
+            "snooze" => self.snooze(arg),
+            "snoozes" | "unsnooze" => self.snooze_popup = Some(0),
+            "fields" => self.show_fields(),
+            "why" => self.show_why(arg),
+            "layout" => self.layout_command(arg),
+            "split" => self.toggle_split(),
+            "unpin" => self.unpin_all(),
+            "marks" => self.marks_command(arg),
+            "buffer" | "buffers" => self.buffer_command(arg),
+            "copy" | "move" if !arg.is_empty() => self.capture(arg, name == "move"),
+            "copy" | "move" => self.toasts.warn(format!("Usage: :{} <buffer>", name)),
+            "sample" => self.set_sampling(arg),
+            "" => {}
+            other => self.toasts.error(format!("Unknown command: {}", other)),
+        }
+    }
+
+    /// Aggregate a numeric field over the lines passing the filter.
+    pub fn field_stats(&mut self, arg: &str) {
+        let query = match StatsQuery::parse(arg) {
+            Ok(query) => query,
+            Err(e) => {
+                self.toasts.error(e.to_string());
+                return;
+            }
+        };
+        let contents = self.shown_contents();
+        let stats = query.run(contents.iter().map(String::as_str));
+        if stats.groups.is_empty() {
+            self.toasts.info(format!("No numeric {} in the shown lines", query.field.name));
+            return;
+        }
+        self.stats_popup = Some(stats);
+        self.stats_scroll = 0;
+    }
+
+    /// The most frequent values of a field among the lines passing the
+    /// filter.
+    pub fn top_values(&mut self, spec: &str) {
+        let field = match Field::new(spec) {
+            Ok(field) => field,
+            Err(e) => {
+                self.toasts.error(e.to_string());
+                return;
+            }
+        };
+        let contents = self.shown_contents();
+        let top = TopValues::count(field, contents.iter().map(String::as_str));
+        if top.values.is_empty() {
+            self.toasts.info(format!("No {} in the shown lines", spec));
+            return;
+        }
+        self.top_popup = Some((top, 0));
+    }
+
+    pub fn select_top_value(&mut self, delta: isize) {
+        if let Some((top, selected)) = self.top_popup.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(top.values.len().saturating_sub(1));
+        }
+    }
+
+    /// Narrow the filter to lines where the `:top` field has the selected
+    /// value.
+    pub fn filter_by_top_value(&mut self) {
+        let Some((top, selected)) = self.top_popup.take() else {
+            return;
+        };
+        if let Some((value, _)) = top.values.get(selected) {
+            self.add_filter_term(&top.filter_term(value));
+        }
+    }
+
+    /// Text of the lines passing the filter, as displayed.
+    /// Send the selection, or every shown line, through a shell command.
+    pub fn pipe(&mut self, command: &str) {
+        if self.pipe_job.is_some() {
+            self.toasts.warn("A :pipe command is still running; Esc or :pipe cancel stops it");
+            return;
+        }
+        let input = match self.selection {
+            Some(_) => self.selected_text().map(|(text, _)| text).unwrap_or_default(),
+            None => self.shown_contents().join("\n"),
+        };
+        self.selection = None;
+        self.pipe_job = Some(pipe::spawn(command, input + "\n"));
+    }
+
+    /// Kill the running `:pipe` command, if any. True when there was one.
+    pub fn cancel_pipe(&mut self) -> bool {
+        let Some(job) = self.pipe_job.take() else {
             return false;
+        };
+        self.toasts.info(format!("Cancelled :pipe {}", job.command));
+        job.cancel();
+        true
+    }
+
+    fn poll_pipe(&mut self) {
+        let Some(result) = self.pipe_job.as_ref().and_then(PipeJob::poll) else {
+            return;
+        };
+        self.pipe_job = None;
+        match result {
+            Ok(output) => self.pipe_popup = Some((output, 0)),
+            Err(e) => self.toasts.error(format!("Pipe failed: {:#}", e)),
+        }
+    }
+
+    pub fn scroll_pipe(&mut self, delta: isize) {
+        if let Some((output, scroll)) = self.pipe_popup.as_mut() {
+            *scroll = scroll.saturating_add_signed(delta).min(output.lines.len().saturating_sub(1));
+        }
+    }
+
+    /// `:snooze [DURATION] [EXPR]`: hide lines matching EXPR, or shaped like
+    /// the cursor line, for DURATION (e.g. 30m) or until restart.
+    pub fn snooze(&mut self, arg: &str) {
+        let (word, rest) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+        // Only a number with a unit is a duration; a bare `404` is an
+        // expression.
+        let has_unit = word.ends_with(|c: char| c.is_ascii_alphabetic());
+        let (duration, expr) = match word.parse::<Since>() {
+            Ok(Since(duration)) if has_unit => (Some(duration), rest.trim()),
+            _ => (None, arg),
+        };
+        let snooze = if expr.is_empty() {
+            let Some(idx) = self.current_line_idx() else {
+                self.toasts.warn("No line to snooze");
+                return;
+            };
+            Snooze::template(&self.log_state.display_content(&self.log_state.lines[idx]), duration)
+        } else {
+            match Snooze::expr(expr, duration) {
+                Ok(snooze) => snooze,
+                Err(e) => {
+                    self.toasts.error(format!("Bad snooze expression: {:#}", e));
+                    return;
+                }
+            }
+        };
+        let shown = self.log_state.filtered_indices.len();
+        let message = format!("Snoozed {} {}", snooze.label(), snooze.remaining(Instant::now()));
+        self.log_state.snooze(snooze);
+        let hidden = shown.saturating_sub(self.log_state.filtered_indices.len());
+        self.toasts.info(format!("{} ({} lines hidden, :snoozes to manage)", message, hidden));
+    }
+
+    pub fn select_snooze(&mut self, delta: isize) {
+        let count = self.log_state.snoozes.list.len();
+        if let Some(selected) = self.snooze_popup.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    /// Wake the snooze selected in the `:snoozes` list.
+    pub fn unsnooze_selected(&mut self) {
+        let Some(selected) = self.snooze_popup else {
+            return;
+        };
+        self.log_state.unsnooze(selected);
+        self.select_snooze(0);
+    }
+
+    /// Explain the cursor line, or buffer line `arg` (counting from 1) so
+    /// hidden lines can be asked about too.
+    pub fn show_why(&mut self, arg: &str) {
+        let idx = match arg.trim() {
+            "" => self.current_line_idx(),
+            n => match n.parse::<usize>() {
+                Ok(n) if (1..=self.log_state.lines.len()).contains(&n) => Some(n - 1),
+                _ => {
+                    self.toasts.error(format!("No line {} (1-{})", n, self.log_state.lines.len()));
+                    return;
+                }
+            },
+        };
+        self.why = idx.and_then(|idx| Why::explain(&self.log_state, idx));
+    }
+
+    pub fn show_fields(&mut self) {
+        if self.log_state.schema.is_empty() {
+            self.toasts.info("No JSON or logfmt fields seen yet");
+            return;
+        }
+        self.fields_popup = Some(0);
+    }
+
+    pub fn select_field(&mut self, delta: isize) {
+        let count = self.log_state.schema.len();
+        if let Some(selected) = self.fields_popup.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    fn selected_field(&self) -> Option<String> {
+        let selected = self.fields_popup?;
+        self.log_state.schema.fields().get(selected).map(|(name, _)| name.to_string())
+    }
+
+    /// Show the selected field as a column, or drop its column.
+    pub fn toggle_field_column(&mut self) {
+        let Some(name) = self.selected_field() else {
+            return;
+        };
+        match columns::toggle_field(&mut self.columns, &name) {
+            true => self.toasts.info(format!("Added column {}", name)),
+            false => self.toasts.info(format!("Removed column {}", name)),
+        }
+    }
+
+    /// Put the selected field's key at the end of the filter and edit it.
+    pub fn insert_field_in_filter(&mut self) {
+        let Some(name) = self.selected_field() else {
+            return;
+        };
+        let key = name.rsplit('.').next().unwrap_or(&name);
+        let term = match key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            true => key.to_string(),
+            false => literal_pattern(key),
+        };
+        let expr = and_clause(&self.input_fields.filter.text, &term);
+        self.input_fields.filter = TextInput::new(expr);
+        self.fields_popup = None;
+        self.input_mode = InputMode::FilterEdit;
+    }
+
+    fn shown_contents(&self) -> Vec<String> {
+        self.log_state
+            .filtered_indices
+            .iter()
+            .map(|&idx| &self.log_state.lines[idx])
+            .filter(|line| !line.system)
+            .map(|line| self.log_state.display_content(line))
+            .collect()
+    }
+
+    pub fn scroll_stats(&mut self, delta: isize) {
+        let last = self.stats_popup.as_ref().map_or(0, |s| s.groups.len().saturating_sub(1));
+        self.stats_scroll = self.stats_scroll.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn handle_file_picker(&mut self, key: KeyEvent) {
+        let Some(picker) = self.file_picker.as_mut() else {
+            return;
+        };
+        match picker.handle_key(key) {
+            Some(Choice::File(path)) => {
+                self.file_picker = None;
+                self.open_file(path);
+            }
+            Some(_) => self.file_picker = None,
+            None => {}
+        }
+    }
+
+    /// Merge all of `path` into the timeline, its lines tagged with the file
+    /// name.
+    pub fn open_file(&mut self, path: PathBuf) {
+        let Some(tx) = self.source_tx.clone() else {
+            self.toasts.warn("Only live views can open more files");
+            return;
+        };
+        if !path.is_file() {
+            self.toasts.error(format!("No such file: {}", path.display()));
+            return;
+        }
+        let line_start = &self.input_fields.line_start.text;
+        let line_start_regex = (!line_start.trim().is_empty())
+            .then(|| Regex::new(line_start).ok().map(Arc::new))
+            .flatten();
+        match start_source(LogSource::File(path.clone(), FileOptions {
+            from: ReadFrom::Start,
+            ..FileOptions::default()
+        }), tx, line_start_regex) {
+            Ok(()) => self.toasts.info(format!("Opened {}", path.display())),
+            Err(e) => self.toasts.error(format!("Cannot open {}: {}", path.display(), e)),
         }
-        let line = &self.log_state.lines[idx];
-        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
-        self.filter_state.matches_filter(&content)
+    }
+
+    /// Open the command line with `:follow ` typed in.
+    pub fn edit_follow_match(&mut self) {
+        self.input_fields.command = TextInput::new("follow ".to_string());
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Pin the view to the latest line matching `expr`, or go back to
+    /// following the tail when `expr` is empty.
+    pub fn follow_match(&mut self, expr: &str) {
+        if expr.is_empty() {
+            self.log_state.scroll_to_end();
+            self.toasts.info("Following tail");
+            return;
+        }
+        match parse_filter(expr) {
+            Ok(expr) => self.log_state.start_follow_match(expr),
+            Err(e) => self.toasts.error(format!("Invalid expression: {}", e)),
+        }
+    }
+
+    /// `Q` starts recording after a register key, or stops the recording.
+    pub fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some((register, keys)) => {
+                self.toasts.info(format!("Recorded @{}", register));
+                self.macros.insert(register, keys);
+                self.save_state();
+            }
+            None => self.macro_prompt = Some(MacroPrompt::Record),
+        }
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, String::new()));
+    }
+
+    pub fn record_key(&mut self, key: KeyEvent) {
+        if let (Some((_, keys)), Some(text)) = (self.recording.as_mut(), encode_key(key)) {
+            keys.push_str(&text);
+        }
+    }
+
+    pub fn export_bundle(&mut self, path: &str) {
+        let path = bundle_path(path);
+        let bundle = Bundle::new(
+            &self.log_state.lines,
+            self.current_state(),
+            self.log_state.notes.to_saved(&self.log_state.lines),
+        );
+        match bundle.write(&path) {
+            Ok(()) => self.toasts.info(format!("Saved bundle {}", path.display())),
+            Err(e) => self.toasts.error(format!("Bundle failed: {:#}", e)),
+        }
+    }
+
+    /// `:export NAME`: hand the filtered lines to an exporter plugin.
+    pub fn export_to_plugin(&mut self, name: &str) {
+        if self.export_job.is_some() {
+            self.toasts.warn("An :export is still running");
+            return;
+        }
+        let lines = self.log_state.filtered_indices.iter().filter_map(|&idx| self.log_state.lines.get(idx));
+        self.export_job = Some(plugin::export(name, lines));
+    }
+
+    fn poll_export(&mut self) {
+        let Some(result) = self.export_job.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.export_job = None;
+        match result {
+            Ok(message) => self.toasts.info(message),
+            Err(e) => self.toasts.error(format!("Export failed: {:#}", e)),
+        }
+    }
+
+    pub fn get_display_content(&self, line: &LogLine) -> Result<String, String> {
+        self.log_state.display(line)
     }
 
     fn save_state(&self) {
-        let state = AppState {
+        if !self.read_only {
+            self.current_state().save();
+        }
+    }
+
+    fn current_state(&self) -> AppState {
+        AppState {
             hide_input: self.input_fields.hide.text.clone(),
             filter_input: self.input_fields.filter.text.clone(),
             highlight_input: self.input_fields.highlight.text.clone(),
             wrap_lines: self.wrap_lines,
             line_start_regex: self.input_fields.line_start.text.clone(),
-        };
-        state.save();
+            rewrite_input: self.input_fields.rewrite.text.clone(),
+            watch_input: self.input_fields.watch.text.clone(),
+            highlight_priorities: self.log_state.filter_state.highlight.priorities,
+            slow_query_ms: self.log_state.filter_state.highlight.slow_query_ms,
+            user_frames: self.log_state.filter_state.highlight.user_frames.clone(),
+            level_tint: self.log_state.filter_state.highlight.level_tint,
+            columns: self.columns.clone(),
+            macros: self.macros.clone(),
+            dedup_window_secs: self.log_state.dedup.as_ref().map(|d| d.window_secs),
+            saved_filters: self.log_state.saved_filters.clone(),
+            time_format: self.time_format,
+            time_zone: self.time_display.zone_name.clone(),
+            clock_format: self.time_display.clock_format.clone(),
+            event_time_format: self.time_display.event_format.clone(),
+            keymap: self.key_bindings.clone(),
+            status_segments: self.status_segments.clone(),
+            correlation_ids: self.correlation_ids.clone(),
+            humanize: self.log_state.filter_state.humanize.as_ref().map(|h| h.spec.clone()),
+            editor_command: self.editor_command.clone(),
+            issue_url: self.issue_url.clone(),
+            git_blame: self.git_blame,
+            level_rules: self.level_rules.clone(),
+            prefix_rules: self.prefix_rules.clone(),
+            layouts: self.layouts.clone(),
+            geoip_databases: self.geoip_databases.clone(),
+            reverse_dns: self.reverse_dns,
+            numeric_peers: self.peer_names.numeric,
+        }
     }
 
     pub fn apply_hide(&mut self) {
-        if self.input_fields.hide.is_empty() {
-            self.filter_state.hide_regex = None;
-            self.input_fields.hide.clear_error();
-        } else {
-            match Regex::new(&self.input_fields.hide.text) {
-                Ok(re) => {
-                    self.filter_state.hide_regex = Some(re);
-                    self.input_fields.hide.clear_error();
-                }
-                Err(e) => {
-                    self.input_fields.hide.set_error(Some(e.to_string()));
+        let result = self.log_state.set_hide(&self.input_fields.hide.text);
+        self.apply_result(InputMode::HideEdit, result);
+    }
+
+    pub fn apply_rewrite(&mut self) {
+        let result = self.log_state.set_rewrite(&self.input_fields.rewrite.text);
+        self.apply_result(InputMode::RewriteEdit, result);
+    }
+
+    pub fn apply_watch(&mut self) {
+        let result = self.log_state.set_watches(&self.input_fields.watch.text);
+        if result.is_ok() {
+            self.show_watches = !self.log_state.watches.is_empty();
+        }
+        self.apply_result(InputMode::WatchEdit, result);
+    }
+
+    pub fn edit_watches(&mut self) {
+        self.show_watches = true;
+        self.input_mode = InputMode::WatchEdit;
+    }
+
+    pub fn toggle_watches(&mut self) {
+        self.show_watches = !self.show_watches;
+    }
+
+    pub fn toggle_raw(&mut self) {
+        self.log_state.toggle_raw();
+    }
+
+    pub fn toggle_epochs(&mut self) {
+        self.log_state.toggle_epochs();
+    }
+
+    pub fn toggle_dedup(&mut self) {
+        self.set_dedup(if self.log_state.dedup.is_some() { "off" } else { "" });
+    }
+
+    /// `:dedup [SECONDS|off]`: hide lines ≥95% similar to one shown within
+    /// the window.
+    pub fn set_dedup(&mut self, arg: &str) {
+        let dedup = match arg {
+            "off" => None,
+            "" => Some(Dedup::new(DEFAULT_WINDOW_SECS)),
+            secs => match secs.parse() {
+                Ok(secs) => Some(Dedup::new(secs)),
+                Err(_) => {
+                    self.toasts.warn("Usage: :dedup [SECONDS|off]");
                     return;
                 }
+            },
+        };
+        self.log_state.set_dedup(dedup);
+        self.toasts.info(match &self.log_state.dedup {
+            Some(d) => format!("Hiding near-duplicates within {}s", d.window_secs),
+            None => "Showing near-duplicates".to_string(),
+        });
+        self.save_state();
+    }
+
+    /// `:sample [1/N|N/s|off]`; alone it reports what was dropped.
+    pub fn set_sampling(&mut self, arg: &str) {
+        if arg.is_empty() {
+            self.toasts.info(match self.log_state.sampler.mode {
+                Sampling::Off => "Not sampling; :sample 1/N or N/s".to_string(),
+                _ => format!("Sampling {} of {} lines", self.log_state.sampler.label(), self.log_state.sampler.seen),
+            });
+            return;
+        }
+        match arg.parse::<Sampling>() {
+            Ok(mode) => {
+                self.log_state.set_sampling(mode);
+                self.toasts.info(match mode {
+                    Sampling::Off => "Keeping every line".to_string(),
+                    mode => format!("Sampling {}", mode),
+                });
             }
+            Err(e) => self.toasts.warn(e.to_string()),
         }
-        self.rebuild_filtered_indices();
-        self.save_state();
     }
 
-    pub fn apply_filter(&mut self) {
-        if self.input_fields.filter.is_empty() {
-            self.filter_state.filter_expr = None;
-            self.input_fields.filter.clear_error();
-        } else {
-            match parse_filter(&self.input_fields.filter.text) {
-                Ok(expr) => {
-                    self.filter_state.filter_expr = Some(expr);
-                    self.input_fields.filter.clear_error();
-                }
+    /// `:humanize [off|replace|alongside] [UNIT=FIELDS ...]`: show byte counts
+    /// and durations of matching fields readably. Alone it toggles.
+    pub fn set_humanize(&mut self, arg: &str) {
+        let humanize = match arg {
+            "off" => None,
+            "" if self.log_state.filter_state.humanize.is_some() => None,
+            spec => match spec.parse::<Humanize>() {
+                Ok(humanize) => Some(humanize),
                 Err(e) => {
-                    self.input_fields.filter.set_error(Some(e.to_string()));
+                    self.toasts.warn(format!("{} (usage: :humanize [off|replace|alongside] [UNIT=FIELDS ...])", e));
                     return;
                 }
-            }
-        }
-        self.rebuild_filtered_indices();
+            },
+        };
+        self.log_state.set_humanize(humanize);
+        self.toasts.info(match &self.log_state.filter_state.humanize {
+            Some(h) if h.replace => "Showing byte sizes and durations humanized",
+            Some(_) => "Showing byte sizes and durations humanized alongside the numbers",
+            None => "Showing byte sizes and durations as logged",
+        });
         self.save_state();
     }
 
+    pub fn apply_filter(&mut self) {
+        let result = self.log_state.set_filter(&self.input_fields.filter.text);
+        self.apply_result(InputMode::FilterEdit, result);
+    }
+
     pub fn apply_highlight(&mut self) {
-        if self.input_fields.highlight.is_empty() {
-            self.filter_state.highlight_expr = None;
-            self.input_fields.highlight.clear_error();
-        } else {
-            match parse_filter(&self.input_fields.highlight.text) {
-                Ok(expr) => {
-                    self.filter_state.highlight_expr = Some(expr);
-                    self.input_fields.highlight.clear_error();
-                }
-                Err(e) => {
-                    self.input_fields.highlight.set_error(Some(e.to_string()));
-                }
-            }
+        let result = self.log_state.set_highlight(&self.input_fields.highlight.text);
+        self.apply_result(InputMode::HighlightEdit, result);
+    }
+
+    /// Show a setting's error under its input, or save the settings.
+    fn apply_result(&mut self, mode: InputMode, result: Result<(), String>) {
+        let failed = result.is_err();
+        if let Err(e) = &result {
+            self.toasts.log(Severity::Warning, format!("{}: {}", mode.label(), e));
+        }
+        if let Some(input) = self.input_fields.get_active_mut(mode) {
+            input.set_error(result.err());
+        }
+        if !failed {
+            self.save_state();
         }
-        self.save_state();
     }
 
     pub fn apply_line_start(&mut self) {
@@ -209,62 +1084,1028 @@ impl App {
                     self.input_fields.line_start.clear_error();
                 }
                 Err(e) => {
-                    self.input_fields.line_start.set_error(Some(e.to_string()));
+                    self.apply_result(InputMode::LineStartEdit, Err(e.to_string()));
                     return;
                 }
             }
         }
         self.save_state();
-        self.status_message = Some("Line start regex saved. Restart to apply.".to_string());
+        self.toasts.info("Line start regex saved. Restart to apply.");
     }
 
-    fn rebuild_filtered_indices(&mut self) {
-        self.log_state.filtered_indices.clear();
-        for i in 0..self.log_state.lines.len() {
-            if self.matches_filter(i) {
-                self.log_state.filtered_indices.push(i);
-            }
+    /// Scroll the `:messages` panel towards older (`delta` > 0) messages.
+    pub fn scroll_messages(&mut self, delta: isize) {
+        let last = self.toasts.messages.len().saturating_sub(1);
+        if let Some(offset) = self.messages_popup.as_mut() {
+            *offset = offset.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Put the column under a click on log text, for the position shown in
+    /// the status bar.
+    pub fn cursor_column_click(&mut self, column: u16, row: u16) {
+        let Some(hit) = self.row_hits.iter().find(|h| h.y == row && column >= h.x) else {
+            return;
+        };
+        let Some(byte) = hit.text.char_indices().nth((column - hit.x) as usize).map(|(i, _)| i) else {
+            return;
+        };
+        let line = &self.log_state.lines[hit.line_idx];
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        if let Some(before) = content.get(..hit.segment.start + hit.offset + byte) {
+            self.cursor_column = before.chars().count();
         }
-        self.log_state.bottom_line_idx = 0;
+    }
+
+    /// Move the column right, stopping at the end of the cursor line.
+    pub fn move_cursor_column(&mut self, by: usize) {
+        let len = self.cursor_position().map_or(0, |(_, _, _, len)| len);
+        self.cursor_column = self.cursor_column.saturating_add(by).min(len);
+    }
+
+    /// The cursor line, the column on it (clamped to its length), that
+    /// column's byte offset and the line's length in characters.
+    pub fn cursor_position(&self) -> Option<(usize, usize, usize, usize)> {
+        let line_idx = self.current_line_idx()?;
+        let line = &self.log_state.lines[line_idx];
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        let len = content.chars().count();
+        let column = self.cursor_column.min(len);
+        let byte = content.char_indices().nth(column).map_or(content.len(), |(i, _)| i);
+        Some((line_idx, column, byte, len))
+    }
+
+    /// Open the `file:line` reference under the cursor column, or the line's
+    /// first one, in the editor.
+    pub fn open_source_ref(&mut self) {
+        let Some((line_idx, _, byte, _)) = self.cursor_position() else {
+            return;
+        };
+        let line = &self.log_state.lines[line_idx];
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        let Some(source) = source_ref::ref_at(&content, byte) else {
+            self.toasts.info("No file:line reference on this line");
+            return;
+        };
+        if !Path::new(&source.path).exists() {
+            self.toasts.warn(format!("{} not found from here", source.path));
+            return;
+        }
+        self.suspend = Some(Suspend::Edit(source_ref::editor_command(&self.editor_command, &source)));
+    }
+
+    /// The `issue_url` filled in for the cursor record, with the `file:line`
+    /// reference under the cursor column or its first one.
+    pub fn issue_url(&mut self) -> Option<String> {
+        if self.issue_url.trim().is_empty() {
+            self.toasts.info("Set issue_url in the settings to file issues from here");
+            return None;
+        }
+        let (line_idx, _, byte, _) = self.cursor_position()?;
+        let line = &self.log_state.lines[line_idx];
+        if line.system {
+            self.toasts.info("Not a log record");
+            return None;
+        }
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        let source = source_ref::ref_at(&content, byte);
+        let record = Record {
+            message: &content,
+            time: line.event_time.unwrap_or(line.timestamp),
+            level: line.level,
+            source: source.as_ref(),
+        };
+        Some(issue::issue_url(&self.issue_url, &record))
+    }
+
+    pub fn scroll_half_pages(&mut self, halves: isize) {
+        self.page_scroll = self.page_scroll.saturating_add(halves);
+    }
+
+    /// Start a line or block selection at the cursor, or end one of that kind.
+    pub fn toggle_selection(&mut self, block: bool) {
+        if self.selection.is_some_and(|s| s.block == block) {
+            self.selection = None;
+            return;
+        }
+        self.selection = self.current_line_idx().map(|anchor| Selection {
+            anchor,
+            anchor_column: self.cursor_column,
+            block,
+        });
+    }
+
+    /// The selected text of the filtered lines, one per line, and how many
+    /// lines that is; just the cursor line without a selection.
+    pub fn selected_text(&self) -> Option<(String, usize)> {
+        let cursor = self.current_line_idx()?;
+        let selection = self.selection.unwrap_or(Selection {
+            anchor: cursor,
+            anchor_column: 0,
+            block: false,
+        });
+        let range = selection.lines(cursor);
+        let parts: Vec<String> = self
+            .log_state
+            .filtered_indices
+            .iter()
+            .filter(|idx| range.contains(idx))
+            .map(|&idx| {
+                let line = &self.log_state.lines[idx];
+                let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+                selection.cut(&content, self.cursor_column).to_string()
+            })
+            .collect();
+        Some((parts.join("\n"), parts.len()))
+    }
+
+    /// Jump to the part of the buffer under a click on the minimap.
+    pub fn minimap_click(&mut self, column: u16, row: u16) {
+        let Some(area) = self.minimap.filter(|a| column == a.x && row >= a.y && row < a.y + a.height) else {
+            return;
+        };
+        let height = area.height as usize;
+        let pos = markers::position_at((row - area.y) as usize, self.log_state.filtered_indices.len(), height);
+        self.log_state.scroll_to(pos + height / 2);
+    }
+
+    /// `:savefilter NAME`: keep the active filter under a name.
+    pub fn save_filter(&mut self, name: &str) {
+        let text = self.input_fields.filter.text.trim().to_string();
+        if text.is_empty() {
+            self.toasts.warn("No filter to save");
+            return;
+        }
+        self.log_state.save_filter(name, text);
+        self.save_state();
+        self.toasts.info(format!("Saved filter '{}'", name));
+    }
+
+    pub fn drop_filter(&mut self, name: &str) {
+        if !self.log_state.drop_filter(name) {
+            self.toasts.warn(format!("No saved filter '{}'", name));
+            return;
+        }
+        self.save_state();
+        self.toasts.info(format!("Dropped filter '{}'", name));
+    }
+
+    /// Open the time outline on the bucket of the bottom line, or close it.
+    pub fn toggle_outline(&mut self) {
+        self.outline_panel = match self.outline_panel {
+            Some(_) => None,
+            None => Some(self.log_state.outline.index_at(self.log_state.get_bottom_line_idx())),
+        };
+    }
+
+    pub fn select_bucket(&mut self, delta: isize) {
+        let count = self.log_state.outline.len();
+        if let Some(selected) = self.outline_panel.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    /// Show the selected bucket's first line at the top of the view.
+    pub fn jump_to_bucket(&mut self, visible_height: usize) {
+        let Some(bucket) = self.outline_panel.and_then(|i| self.log_state.outline.get(i)) else {
+            return;
+        };
+        let pos = bucket.first_pos + visible_height.saturating_sub(1);
+        self.log_state.scroll_to(pos);
+    }
+
+    pub fn cycle_bucket_size(&mut self) {
+        let size = self.log_state.outline.size.next();
+        self.log_state.set_outline_size(size);
+        self.outline_panel = Some(self.log_state.outline.index_at(self.log_state.get_bottom_line_idx()));
+    }
+
+    pub fn toggle_filter_stats(&mut self) {
+        self.filter_stats_popup = match self.filter_stats_popup {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    pub fn select_filter_stat(&mut self, delta: isize) {
+        let count = self.log_state.filter_stats.len();
+        if let Some(selected) = self.filter_stats_popup.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    fn selected_filter_stat(&self) -> Option<&FilterStat> {
+        self.log_state.filter_stats.get(self.filter_stats_popup?)
+    }
+
+    /// Make the selected saved filter the active one.
+    pub fn apply_filter_stat(&mut self) {
+        let Some(text) = self.selected_filter_stat().map(|stat| stat.text.clone()) else {
+            return;
+        };
+        self.filter_stats_popup = None;
+        self.input_fields.filter = TextInput::new(text);
+        self.apply_filter();
+    }
+
+    pub fn drop_selected_filter(&mut self) {
+        if let Some(name) = self.selected_filter_stat().and_then(|stat| stat.name.clone()) {
+            self.drop_filter(&name);
+            self.select_filter_stat(0);
+        }
+    }
+
+    /// Add pasted text to the buffer, a log line per line.
+    pub fn ingest_paste(&mut self, text: &str) {
+        let source: Arc<str> = Arc::from("paste");
+        let mut count = 0;
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            self.log_state.ingest(line.trim_end_matches('\r').to_string(), Some(source.clone()));
+            count += 1;
+        }
+        self.ingested += count;
+        self.toasts.info(format!("Added {} pasted lines", count));
     }
 
     pub fn clear(&mut self) {
-        self.log_state.clear();
-        self.status_message = Some("Cleared".to_string());
+        let cleared = self.log_state.clear();
+        self.dropped += cleared;
+        self.expanded_traces.clear();
+        if self.time_display.dated {
+            self.time_display.dated = false;
+            columns::set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+            columns::set_event_width(&mut self.columns, &self.time_display);
+        }
+        self.toasts.info("Cleared");
     }
 
-    pub fn render_line(&mut self, line: &LogLine) -> Vec<(String, ratatui::style::Style)> {
+    /// Render a log record as one or more rows. Stack traces are folded to
+    /// their first frame unless expanded.
+    pub fn render_line(&mut self, line_idx: usize) -> Vec<Row> {
+        let line = &self.log_state.lines[line_idx];
+        if line.system {
+            let style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+            return vec![Row {
+                runs: vec![(line.content.clone(), style)],
+                source: None,
+            }];
+        }
+        let level = line.level;
         let content = match self.get_display_content(line) {
             Ok(c) => c,
             Err(e) => {
+                let content = line.content.clone();
                 self.input_fields.hide.set_error(Some(format!("Runtime error: {}", e)));
-                line.content.clone()
+                content
+            }
+        };
+
+        let trace = stacktrace::detect(&content, &self.log_state.filter_state.highlight.user_frames);
+        let mut rows = match trace {
+            Some(trace) if !self.expanded_traces.contains(&line_idx) => {
+                let collapsed = trace.collapse(&content);
+                let head = 0..collapsed.head.len();
+                let tail = content.len() - collapsed.tail.len()..content.len();
+                let mut rows = split_rows(self.highlight_text(collapsed.head, level, head.len() <= 500), head);
+                let mut runs = vec![(
+                    format!("▶ {} frames (z)", collapsed.hidden_frames),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                let inside = self.log_state.filter_state.highlight_count(collapsed.folded);
+                if inside > 0 {
+                    runs.push((format!(" · {} matches inside", inside), Style::default().fg(Color::Yellow)));
+                }
+                rows.push(Row { runs, source: None });
+                if !collapsed.tail.is_empty() {
+                    rows.extend(split_rows(self.highlight_text(collapsed.tail, level, tail.len() <= 500), tail));
+                }
+                rows
+            }
+            _ => {
+                let enable_highlight = content.len() <= 500;
+                split_rows(self.highlight_text(&content, level, enable_highlight), 0..content.len())
             }
         };
-        let enable_highlight = content.len() <= 500;
+        let repeats = self.log_state.dedup.as_ref().map_or(0, |d| d.suppressed(line_idx));
+        if repeats > 0 {
+            if let Some(row) = rows.last_mut() {
+                row.runs.push((format!("  +{} similar", repeats), Style::default().fg(Color::Black).bg(Color::DarkGray)));
+            }
+        }
+        if self.log_state.notes.is_expanded(line_idx) {
+            if let Some(note) = self.log_state.notes.get(line_idx) {
+                let style = Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC);
+                rows.extend(note.split('\n').map(|part| Row {
+                    runs: vec![(format!("✎ {}", part), style)],
+                    source: None,
+                }));
+            }
+        }
+        rows
+    }
+
+    pub fn open_entity_menu(&mut self) {
+        let Some(idx) = self.current_line_idx() else {
+            return;
+        };
+        let line = &self.log_state.lines[idx];
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        self.entity_menu = EntityMenu::new(find_entities(&content));
+        if self.entity_menu.is_none() {
+            self.toasts.info("No URL, IP or UUID on this line");
+        }
+    }
+
+    /// Filter to the lines sharing the current line's request or trace ID and
+    /// report their time span, or put the previous filter back.
+    pub fn correlate(&mut self) {
+        if let Some(previous) = self.correlated.take() {
+            self.input_fields.filter = TextInput::new(previous);
+            self.apply_filter();
+            return;
+        }
+        let Some(idx) = self.current_line_idx() else {
+            return;
+        };
+        let patterns = correlate::compile(&self.correlation_ids);
+        let Some(id) = correlate::extract_id(&patterns, &self.log_state.lines[idx].content) else {
+            self.toasts.info("No request or trace ID on this line");
+            return;
+        };
+        self.correlated = Some(self.input_fields.filter.text.clone());
+        self.input_fields.filter = TextInput::new(word_pattern(&id));
+        self.apply_filter();
+        if let Some(found) = Correlation::find(&self.log_state.lines, &id) {
+            self.toasts.info(found.summary());
+        }
+    }
+
+    /// Narrow the filter to lines containing `text` literally.
+    pub fn filter_by(&mut self, text: &str) {
+        self.add_filter_term(&literal_pattern(text));
+    }
+
+    fn add_filter_term(&mut self, term: &str) {
+        let expr = and_clause(&self.input_fields.filter.text, term);
+        self.input_fields.filter = crate::input::TextInput::new(expr);
+        self.apply_filter();
+    }
+
+    /// Add the highlighted token under a mouse click to the filter.
+    pub fn filter_by_click(&mut self, column: u16, row: u16) {
+        let Some(hit) = self.row_hits.iter().find(|h| h.y == row && column >= h.x) else {
+            return;
+        };
+        // Wide characters take two screen columns.
+        let col = (column - hit.x) as usize;
+        let mut width = 0;
+        let Some(byte) = hit.text.char_indices().find_map(|(i, c)| {
+            width += c.width().unwrap_or(0);
+            (width > col).then_some(i)
+        }) else {
+            return;
+        };
+        let line = &self.log_state.lines[hit.line_idx];
+        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+        let Some(segment) = content.get(hit.segment.clone()) else {
+            return;
+        };
+        if hit.offset + byte >= segment.len() {
+            return;
+        }
+        let token = token_at(
+            segment,
+            hit.offset + byte,
+            self.log_state.filter_state.highlight_expr.as_ref(),
+            &self.log_state.filter_state.highlight,
+        );
+        let term = match token {
+            Some(ClickToken::Literal(text)) => literal_pattern(&text),
+            Some(ClickToken::Field { key, value }) => field_pattern(&key, &value),
+            None => return,
+        };
+        self.add_filter_term(&term);
+        self.toasts.info(format!("Filter: {}", self.input_fields.filter.text));
+    }
+
+    pub fn edit_note(&mut self) {
+        let Some(line_idx) = self.current_line_idx() else {
+            return;
+        };
+        let text = self.log_state.notes.get(line_idx).unwrap_or("").to_string();
+        self.note_editor = Some(NoteEditor {
+            line_idx,
+            input: crate::input::TextInput::new(text),
+        });
+    }
+
+    pub fn save_note(&mut self) {
+        let Some(editor) = self.note_editor.take() else {
+            return;
+        };
+        self.log_state.notes.set(editor.line_idx, &editor.input.text);
+        self.save_session();
+        self.log_state.rebuild_markers();
+    }
+
+    /// Try the filter expression against the line under the cursor.
+    pub fn open_playground(&mut self) {
+        let mut playground = Playground::new(PlaygroundTarget::Filter, &self.input_fields.filter.text);
+        if !self.log_state.filtered_indices.is_empty() {
+            let pos = self.log_state.get_bottom_line_idx().min(self.log_state.filtered_indices.len() - 1);
+            playground.set_sample(pos, &self.sample_text(pos));
+        }
+        self.playground = Some(playground);
+    }
+
+    fn sample_text(&self, pos: usize) -> String {
+        let line = &self.log_state.lines[self.log_state.filtered_indices[pos]];
+        self.get_display_content(line).unwrap_or_else(|_| line.content.clone())
+    }
+
+    /// Use the previous (`-1`) or next (`1`) filtered line as the sample.
+    pub fn pick_playground_sample(&mut self, delta: isize) {
+        let count = self.log_state.filtered_indices.len();
+        let Some(playground) = &self.playground else {
+            return;
+        };
+        if count == 0 {
+            return;
+        }
+        let pos = match playground.sample_pos {
+            Some(pos) => pos.saturating_add_signed(delta).min(count - 1),
+            None => count - 1,
+        };
+        let text = self.sample_text(pos);
+        if let Some(playground) = &mut self.playground {
+            playground.set_sample(pos, &text);
+        }
+    }
+
+    pub fn switch_playground_target(&mut self) {
+        let Some(playground) = &mut self.playground else {
+            return;
+        };
+        playground.target = playground.target.other();
+        let text = match playground.target {
+            PlaygroundTarget::Filter => &self.input_fields.filter.text,
+            PlaygroundTarget::Highlight => &self.input_fields.highlight.text,
+        };
+        playground.expr = crate::input::TextInput::new(text.clone());
+    }
+
+    /// Put the expression into its input and apply it.
+    pub fn apply_playground(&mut self) {
+        let Some(playground) = self.playground.take() else {
+            return;
+        };
+        match playground.target {
+            PlaygroundTarget::Filter => {
+                self.input_fields.filter = playground.expr;
+                self.apply_filter();
+            }
+            PlaygroundTarget::Highlight => {
+                self.input_fields.highlight = playground.expr;
+                self.apply_highlight();
+            }
+        }
+    }
+
+    /// Pin the cursor line above the log view, or unpin it.
+    pub fn toggle_pin(&mut self) {
+        let Some(line_idx) = self.current_line_idx() else {
+            return;
+        };
+        match self.log_state.pins.toggle(line_idx) {
+            Ok(true) => self.toasts.info("Pinned (p again to unpin)"),
+            Ok(false) => self.toasts.info("Unpinned"),
+            Err(e) => self.toasts.error(e),
+        }
+        self.save_session();
+    }
+
+    pub fn unpin_all(&mut self) {
+        self.log_state.pins.clear();
+        self.save_session();
+    }
+
+    /// Mark the cursor line for bulk actions, or unmark it.
+    pub fn toggle_mark(&mut self) {
+        let Some(line_idx) = self.current_line_idx() else {
+            return;
+        };
+        self.log_state.marks.toggle(line_idx);
+    }
+
+    /// `:marks [export FILE|filter|clear]`, on the marked lines.
+    pub fn marks_command(&mut self, arg: &str) {
+        let count = self.log_state.marks.len();
+        if count == 0 {
+            self.toasts.info("No marked lines (Space marks the cursor line)");
+            return;
+        }
+        let (action, rest) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+        match (action, rest.trim()) {
+            ("export", "") => self.toasts.warn("Usage: :marks export <file>"),
+            ("export", path) => {
+                let text = self.marked_contents().join("\n") + "\n";
+                match std::fs::write(path, text) {
+                    Ok(()) => self.toasts.info(format!("Saved {} marked lines to {}", count, path)),
+                    Err(e) => self.toasts.error(format!("Export failed: {}", e)),
+                }
+            }
+            ("copy", _) => match crate::clipboard::copy(&self.marked_contents().join("\n"), true) {
+                Ok(method) => self.toasts.info(format!("Copied {} marked lines via {}", count, method)),
+                Err(e) => self.toasts.error(format!("Copy failed: {:#}", e)),
+            },
+            ("filter", _) => {
+                let contents = self.marked_contents();
+                self.input_fields.filter = TextInput::new(template_filter(contents.iter().map(String::as_str)));
+                self.apply_filter();
+            }
+            ("clear", _) => {
+                self.log_state.marks.clear();
+                self.toasts.info(format!("Unmarked {} lines", count));
+            }
+            _ => self.toasts.info(format!("{} marked lines: :marks copy | export <file> | filter | clear", count)),
+        }
+    }
+
+    /// `:buffer [NAME|drop NAME]`: list the buffers, switch to one, or
+    /// drop one.
+    pub fn buffer_command(&mut self, arg: &str) {
+        let result = match arg.split_once(char::is_whitespace) {
+            None if arg.is_empty() => {
+                let list: Vec<String> = self
+                    .buffers
+                    .list(&self.log_state)
+                    .into_iter()
+                    .map(|(name, lines)| {
+                        let current = if name == self.buffers.current { "*" } else { "" };
+                        format!("{}{} ({})", current, name, lines)
+                    })
+                    .collect();
+                self.toasts.info(format!("Buffers: {}", list.join(", ")));
+                Ok(())
+            }
+            None => self.buffers.switch(arg, &mut self.log_state).map(|()| {
+                self.selection = None;
+                self.split = None;
+                self.expanded_traces.clear();
+                self.toasts.info(format!("Buffer {}", arg));
+            }),
+            Some(("drop", name)) => self
+                .buffers
+                .remove(name.trim())
+                .map(|lines| self.toasts.info(format!("Dropped buffer {} ({} lines)", name.trim(), lines))),
+            Some(_) => Err("Usage: :buffer [<name>|drop <name>]".to_string()),
+        };
+        if let Err(e) = result {
+            self.toasts.warn(e);
+        }
+    }
+
+    /// `:copy NAME` / `:move NAME`: put the selected lines (or the marked
+    /// ones, or every shown one) into buffer NAME.
+    pub fn capture(&mut self, name: &str, take: bool) {
+        if name == self.buffers.current {
+            self.toasts.warn(format!("Already in buffer {}", name));
+            return;
+        }
+        let picked: HashSet<usize> = match (self.selection, self.current_line_idx()) {
+            (Some(selection), Some(cursor)) => {
+                let range = selection.lines(cursor);
+                self.log_state.filtered_indices.iter().copied().filter(|idx| range.contains(idx)).collect()
+            }
+            _ if !self.log_state.marks.is_empty() => self.log_state.marks.lines().collect(),
+            _ => self.log_state.filtered_indices.iter().copied().collect(),
+        };
+        if picked.is_empty() {
+            self.toasts.info("No lines to capture");
+            return;
+        }
+        let lines = match take {
+            true => {
+                let (lines, new_idx) = self.log_state.take_lines(|idx| picked.contains(&idx));
+                self.expanded_traces = self
+                    .expanded_traces
+                    .iter()
+                    .filter_map(|&idx| new_idx.get(idx).copied().flatten())
+                    .collect();
+                lines
+            }
+            false => {
+                let mut idxs: Vec<usize> = picked.into_iter().collect();
+                idxs.sort_unstable();
+                idxs.into_iter().map(|idx| self.log_state.lines[idx].clone()).collect()
+            }
+        };
+        let count = lines.len();
+        match self.buffers.append(name, lines, &self.log_state) {
+            Ok(()) => {
+                self.selection = None;
+                let verb = if take { "Moved" } else { "Copied" };
+                self.toasts.info(format!("{} {} lines to buffer {} (:buffer {})", verb, count, name, name));
+            }
+            Err(e) => self.toasts.warn(e),
+        }
+    }
+
+    /// Text of the marked lines, as displayed, in buffer order.
+    fn marked_contents(&self) -> Vec<String> {
+        self.log_state
+            .marks
+            .lines()
+            .map(|idx| {
+                let line = &self.log_state.lines[idx];
+                self.get_display_content(line).unwrap_or_else(|_| line.content.clone())
+            })
+            .collect()
+    }
+
+    /// Show or hide the note under the cursor line.
+    pub fn toggle_note(&mut self) {
+        let Some(line_idx) = self.current_line_idx() else {
+            return;
+        };
+        if self.log_state.notes.get(line_idx).is_none() {
+            self.toasts.info("No note on this line (n to add)");
+        } else {
+            self.log_state.notes.toggle_expanded(line_idx);
+        }
+    }
+
+    /// Stop the sources and write settings and notes out one last time.
+    /// Exports and bundle saves run to completion before a key is handled,
+    /// so none is left half-written by the time this runs.
+    pub fn shutdown(&mut self) -> Summary {
+        self.poll_source();
+        // Sources stop once sending fails; plugins exit when their stdin
+        // closes with the process.
+        self.source_rx = mpsc::channel().1;
+        self.save_state();
+        self.save_session();
+        Summary {
+            ingested: self.ingested,
+            dropped: self.dropped,
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    fn save_session(&self) {
+        if self.read_only {
+            return;
+        }
+        let live = self.buffers.live_ref(&self.log_state);
+        Session {
+            notes: live.notes.to_saved(&live.lines),
+            pins: live.pins.to_saved(&live.lines),
+        }
+        .save();
+    }
+
+    /// Expand or fold the stack trace on the cursor line.
+    pub fn toggle_trace(&mut self) {
+        let Some(idx) = self.current_line_idx() else {
+            return;
+        };
+        let content = &self.log_state.lines[idx].content;
+        if stacktrace::detect(content, "").is_none() {
+            self.toasts.info("No stack trace on this line");
+        } else if !self.expanded_traces.remove(&idx) {
+            self.expanded_traces.insert(idx);
+        }
+    }
+
+    fn highlight_text(&self, content: &str, level: Option<Level>, enable_highlight: bool) -> Vec<(String, Style)> {
         let spans = highlight_line(
-            &content,
-            if enable_highlight { self.filter_state.highlight_expr.as_ref() } else { None },
+            content,
+            level,
+            if enable_highlight { self.log_state.filter_state.highlight_expr.as_ref() } else { None },
             enable_highlight,
             enable_highlight,
+            &self.log_state.filter_state.highlight,
         );
-        apply_highlights_ratatui(&content, &spans)
+        apply_highlights_ratatui(content, &spans)
+    }
+
+    pub fn current_line_idx(&self) -> Option<usize> {
+        self.log_state
+            .filtered_indices
+            .get(self.log_state.get_bottom_line_idx())
+            .copied()
+    }
+
+    pub fn open_detail(&mut self) {
+        self.detail = self.current_line_idx().map(DetailState::new);
+    }
+
+    /// Full content of the detail line, pretty-printed when requested and the
+    /// line contains JSON or XML.
+    pub fn render_detail(&self) -> Option<Vec<Row>> {
+        let detail = self.detail.as_ref()?;
+        let line = self.log_state.lines.get(detail.line_idx)?;
+        let mut rows = match &detail.tree {
+            Some(tree) => tree
+                .rows()
+                .into_iter()
+                .map(|row| Row {
+                    runs: row.parts.into_iter().map(|(text, style)| (text, style.to_ratatui_style())).collect(),
+                    source: None,
+                })
+                .collect(),
+            None => {
+                let pretty = if detail.pretty { pretty_print(&line.content) } else { None };
+                let text = pretty.unwrap_or_else(|| line.content.clone());
+                split_rows(self.highlight_text(&text, line.level, true), 0..text.len())
+            }
+        };
+        if self.git_blame {
+            let dim = Style::default().fg(Color::DarkGray);
+            for (i, blame) in blame::describe_refs(&line.content).into_iter().enumerate() {
+                if i == 0 {
+                    rows.push(Row { runs: Vec::new(), source: None });
+                }
+                rows.push(Row {
+                    runs: vec![(blame, dim)],
+                    source: None,
+                });
+            }
+        }
+        Some(rows)
+    }
+
+    /// Switch the detail popup between the raw line and pretty-printed,
+    /// showing JSON as a foldable tree.
+    pub fn toggle_detail_pretty(&mut self) {
+        let Some(detail) = self.detail.as_mut() else {
+            return;
+        };
+        detail.toggle_pretty();
+        detail.tree = match detail.pretty {
+            true => self.log_state.lines.get(detail.line_idx).and_then(|line| JsonTree::parse(&line.content)),
+            false => None,
+        };
+    }
+
+    pub fn toggle_git_blame(&mut self) {
+        self.git_blame = !self.git_blame;
+        self.toasts.info(if self.git_blame { "Blaming file:line references" } else { "Blame off" });
+        self.save_state();
+    }
+
+    /// `:enrich`: write GeoIP and host name fields after IP addresses, or
+    /// stop.
+    pub fn toggle_enrich(&mut self) {
+        if self.log_state.filter_state.enrich.is_some() {
+            self.log_state.set_enrich(None);
+            self.toasts.info("Enrichment off");
+            return;
+        }
+        match Enricher::open(&self.geoip_databases, self.reverse_dns) {
+            Ok(enricher) => {
+                self.log_state.set_enrich(Some(Arc::new(enricher)));
+                self.toasts.info("Enriching IP addresses");
+            }
+            Err(e) => self.toasts.error(format!("{:#}", e)),
+        }
     }
 
     pub fn toggle_time(&mut self) {
         self.show_time = !self.show_time;
     }
 
+    /// Switch the receipt column between relative, clock and delta times,
+    /// resizing it to fit.
+    pub fn cycle_time_format(&mut self) {
+        self.time_format = self.time_format.next();
+        columns::set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+        self.toasts.info(format!("Receipt times: {}", self.time_format.label()));
+        self.save_state();
+    }
+
+    /// `:tz [ZONE]`: show clock and event times in ZONE; alone it switches
+    /// between local time and UTC.
+    pub fn set_time_zone(&mut self, zone: &str) {
+        let zone = match zone {
+            "" if self.time_display.zone_label() == "local" => "UTC",
+            "" => "local",
+            zone => zone,
+        };
+        if let Err(e) = self.time_display.set_zone(zone) {
+            self.toasts.error(format!("{:#}", e));
+            return;
+        }
+        self.toasts.info(format!("Times in {}", self.time_display.zone_label()));
+        self.save_state();
+    }
+
+    /// `:timefmt clock|event [FORMAT]`: set the strftime format of receipt
+    /// clock or event times, resizing the column; no format is the default.
+    pub fn set_time_format(&mut self, arg: &str) {
+        let (which, format) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+        if let Err(e) = self.time_display.set_format(which, format.trim()) {
+            self.toasts.error(format!("{:#} (usage: :timefmt clock|event [FORMAT])", e));
+            return;
+        }
+        if which == "event" {
+            columns::set_event_width(&mut self.columns, &self.time_display);
+        } else {
+            columns::set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+        }
+        self.save_state();
+    }
+
+    /// Split the log view in two over the same buffer: the top pane stays at
+    /// the cursor line, or the last error when following the tail, and the
+    /// bottom one follows the tail.
+    pub fn toggle_split(&mut self) {
+        if self.split.take().is_some() {
+            return;
+        }
+        let Some(current) = self.current_line_idx() else {
+            self.toasts.info("Nothing to split yet");
+            return;
+        };
+        let last_error = || {
+            let lines = &self.log_state.lines;
+            self.log_state.filtered_indices.iter().rev().copied().find(|&idx| lines[idx].level == Some(Level::Error))
+        };
+        let pinned = match self.log_state.follow_tail {
+            true => last_error().unwrap_or(current),
+            false => current,
+        };
+        self.split = Some(SplitPane {
+            line_idx: Some(pinned),
+            focus_top: false,
+        });
+        self.log_state.scroll_to_end();
+    }
+
+    /// Move the keys to the other pane of the split view.
+    pub fn switch_split_focus(&mut self) {
+        let Some(mut split) = self.split else {
+            return;
+        };
+        let here = self.current_line_idx().filter(|_| !self.log_state.follow_tail);
+        match split.line_idx {
+            None => self.log_state.scroll_to_end(),
+            Some(idx) => {
+                let pos = self.log_state.filtered_indices.partition_point(|&i| i <= idx).saturating_sub(1);
+                self.log_state.scroll_to(pos);
+                self.log_state.follow_tail = false;
+            }
+        }
+        split.line_idx = here;
+        split.focus_top = !split.focus_top;
+        self.split = Some(split);
+    }
+
+    /// Filtered position at the bottom of the pane the keys are not moving.
+    pub fn split_bottom(&self) -> Option<usize> {
+        let split = self.split?;
+        let indices = &self.log_state.filtered_indices;
+        Some(match split.line_idx {
+            None => indices.len().saturating_sub(1),
+            Some(idx) => indices.partition_point(|&i| i <= idx).saturating_sub(1),
+        })
+    }
+
+    /// The columns, panes and toggles on screen now.
+    fn capture_layout(&self) -> Layout {
+        Layout {
+            columns: self.columns.clone(),
+            time_format: self.time_format,
+            show_time: self.show_time,
+            wrap_lines: self.wrap_lines,
+            show_raw: self.log_state.filter_state.show_raw,
+            show_watches: self.show_watches,
+            show_outline: self.outline_panel.is_some(),
+            show_lanes: self.show_lanes,
+            show_ruler: self.show_ruler,
+        }
+    }
+
+    fn apply_layout(&mut self, name: String, layout: Layout) {
+        self.columns = columns::normalize(layout.columns);
+        self.time_format = layout.time_format;
+        columns::set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+        self.show_time = layout.show_time;
+        self.wrap_lines = layout.wrap_lines;
+        if self.log_state.filter_state.show_raw != layout.show_raw {
+            self.log_state.toggle_raw();
+        }
+        self.show_watches = layout.show_watches;
+        if self.outline_panel.is_some() != layout.show_outline {
+            self.toggle_outline();
+        }
+        self.show_lanes = layout.show_lanes;
+        self.log_state.set_lanes(self.show_lanes);
+        self.show_ruler = layout.show_ruler;
+        self.toasts.info(format!("Layout: {}", name));
+        self.layout = Some(name);
+        self.save_state();
+    }
+
+    /// Switch to the next layout by name.
+    pub fn next_layout(&mut self) {
+        let (name, layout) = layout::next(&self.layouts, self.layout.as_deref());
+        self.apply_layout(name, layout);
+    }
+
+    /// `:layout [NAME | save NAME | delete NAME]`: alone it lists the layouts.
+    pub fn layout_command(&mut self, arg: &str) {
+        let mut words = arg.split_whitespace();
+        match (words.next(), words.next()) {
+            (None, _) => {
+                let names: Vec<String> = layout::available(&self.layouts).into_keys().collect();
+                let current = self.layout.as_deref().unwrap_or("none");
+                self.toasts.info(format!("Layouts: {} (current: {})", names.join(", "), current));
+            }
+            (Some("save"), Some(name)) => {
+                self.layouts.insert(name.to_string(), self.capture_layout());
+                self.layout = Some(name.to_string());
+                self.toasts.info(format!("Saved layout {}", name));
+                self.save_state();
+            }
+            (Some("delete"), Some(name)) => match self.layouts.remove(name) {
+                Some(_) => {
+                    self.toasts.info(format!("Deleted layout {}", name));
+                    self.save_state();
+                }
+                None => self.toasts.error(format!("No saved layout {}", name)),
+            },
+            (Some(name), None) => match layout::available(&self.layouts).remove(name) {
+                Some(layout) => self.apply_layout(name.to_string(), layout),
+                None => self.toasts.error(format!("No layout {}", name)),
+            },
+            _ => self.toasts.error("Usage: :layout [NAME | save NAME | delete NAME]"),
+        }
+    }
+
     pub fn toggle_wrap(&mut self) {
         self.wrap_lines = !self.wrap_lines;
         self.save_state();
     }
 
     pub fn prefix_width(&self) -> usize {
-        if self.show_time {
-            PREFIX_WIDTH_WITH_TIME
+        columns::prefix_width(&self.columns, self.show_time) + self.lanes_width()
+    }
+
+    pub fn lanes_width(&self) -> usize {
+        if self.show_lanes {
+            self.log_state.lanes.len() * 2
         } else {
-            PREFIX_WIDTH_WITHOUT_TIME
+            0
+        }
+    }
+
+    pub fn toggle_lanes(&mut self) {
+        self.show_lanes = !self.show_lanes;
+        self.log_state.set_lanes(self.show_lanes);
+        if self.show_lanes && self.log_state.lanes.len() == 0 {
+            self.toasts.info("No thread or PID fields seen yet");
+        }
+    }
+
+    pub fn toggle_column_editor(&mut self) {
+        self.column_editor = match self.column_editor {
+            Some(_) => {
+                self.save_state();
+                None
+            }
+            None => Some(0),
+        };
+    }
+
+    pub fn select_column(&mut self, delta: isize) {
+        if let Some(selected) = self.column_editor.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(self.columns.len() - 1);
+        }
+    }
+
+    /// Move the selected column up or down in display order.
+    pub fn move_column(&mut self, delta: isize) {
+        let Some(selected) = self.column_editor else {
+            return;
+        };
+        let target = selected.saturating_add_signed(delta).min(self.columns.len() - 1);
+        self.columns.swap(selected, target);
+        self.column_editor = Some(target);
+    }
+
+    pub fn toggle_column(&mut self) {
+        if let Some(column) = self.column_editor.and_then(|i| self.columns.get_mut(i)) {
+            column.visible = !column.visible;
+        }
+    }
+
+    pub fn resize_column(&mut self, delta: isize) {
+        if let Some(column) = self.column_editor.and_then(|i| self.columns.get_mut(i)) {
+            column.width = column
+                .width
+                .saturating_add_signed(delta)
+                .clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH);
         }
     }
 }
@@ -0,0 +1,148 @@
+use crate::core::LogState;
+use crate::filter::parse_filter;
+use crate::highlight::highlight_line;
+use crate::level::{self, Level};
+use crate::prefix;
+use crate::state::AppState;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Filter timed when the settings have none.
+const SAMPLE_FILTER: &str = "error || warn || (timeout && !retry)";
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[arg(help = "Log file to measure with")]
+    file: PathBuf,
+
+    #[arg(long = "rounds", default_value_t = 3, help = "Run each stage this many times and report the fastest")]
+    rounds: u32,
+}
+
+/// Per second, like `1.2M`, `340k` or `87`.
+fn per_second(count: f64, elapsed: Duration) -> String {
+    let rate = count / elapsed.as_secs_f64().max(1e-9);
+    match rate {
+        r if r >= 1e6 => format!("{:.1}M", r / 1e6),
+        r if r >= 1e3 => format!("{:.0}k", r / 1e3),
+        r => format!("{:.0}", r),
+    }
+}
+
+/// Fastest of `rounds` runs of `stage`.
+fn fastest(rounds: u32, mut stage: impl FnMut()) -> Duration {
+    (0..rounds.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            stage();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn report(stage: &str, elapsed: Duration, lines: usize, bytes: usize, note: &str) {
+    println!(
+        "{:<10} {:>8.3}s {:>7} lines/s {:>7}B/s  {}",
+        stage,
+        elapsed.as_secs_f64(),
+        per_second(lines as f64, elapsed),
+        per_second(bytes as f64, elapsed),
+        note
+    );
+}
+
+/// `logviewer bench FILE`: time ingestion, filtering and highlighting of the
+/// file's lines with the saved settings.
+pub fn run(args: BenchArgs) -> Result<()> {
+    let text = fs::read_to_string(&args.file).with_context(|| format!("Cannot read {}", args.file.display()))?;
+    let lines: Vec<&str> = text.lines().collect();
+    let bytes = text.len();
+    let (state, state_error) = AppState::load_reporting();
+    if let Some(e) = state_error {
+        eprintln!("{}", e);
+    }
+    for e in level::set_rules(&state.level_rules) {
+        eprintln!("{}", e);
+    }
+    for e in prefix::set_rules(&state.prefix_rules) {
+        eprintln!("{}", e);
+    }
+    let configured = |log_state: &mut LogState| {
+        let settings = [
+            ("hide", &state.hide_input, LogState::set_hide as fn(&mut LogState, &str) -> Result<(), String>),
+            ("filter", &state.filter_input, LogState::set_filter),
+            ("highlight", &state.highlight_input, LogState::set_highlight),
+            ("rewrite", &state.rewrite_input, LogState::set_rewrite),
+            ("watches", &state.watch_input, LogState::set_watches),
+        ];
+        for (name, text, set) in settings {
+            if let Err(e) = set(log_state, text) {
+                eprintln!("Ignoring the saved {}: {}", name, e);
+            }
+        }
+    };
+    println!(
+        "{}: {} lines, {:.1} MB, best of {}",
+        args.file.display(),
+        lines.len(),
+        bytes as f64 / 1e6,
+        args.rounds.max(1)
+    );
+
+    let mut log_state = LogState::from_state(&state, Vec::new());
+    let elapsed = fastest(args.rounds, || {
+        log_state = LogState::from_state(&state, Vec::new());
+        configured(&mut log_state);
+        for line in &lines {
+            log_state.ingest(line.to_string(), None);
+        }
+    });
+    let shown = log_state.filtered_indices.len();
+    report("ingest", elapsed, lines.len(), bytes, &format!("{} shown", shown));
+
+    let elapsed = fastest(args.rounds, || log_state.refilter());
+    report("refilter", elapsed, lines.len(), bytes, "hide, rewrite, filter and dedup over the buffer");
+
+    let displayed: Vec<String> = log_state.lines.iter().map(|line| log_state.display_content(line)).collect();
+    let levels: Vec<Option<Level>> = log_state.lines.iter().map(|line| line.level).collect();
+    let (filter_text, note) = match state.filter_input.trim() {
+        "" => (SAMPLE_FILTER, "no saved filter, timed"),
+        text => (text, "saved filter"),
+    };
+    let expr = parse_filter(filter_text).with_context(|| format!("Bad filter: {}", filter_text))?;
+    let mut matched = 0;
+    let elapsed = fastest(args.rounds, || {
+        matched = displayed.iter().filter(|text| expr.matches(text)).count();
+    });
+    report("filter", elapsed, lines.len(), bytes, &format!("{} {}; {} matched", note, filter_text, matched));
+
+    let filter_state = &log_state.filter_state;
+    let mut spans = 0;
+    let elapsed = fastest(args.rounds, || {
+        spans = displayed
+            .iter()
+            .zip(&levels)
+            .map(|(text, &level)| black_box(highlight_line(text, level, filter_state.highlight_expr.as_ref(), true, true, &filter_state.highlight)).len())
+            .sum::<usize>();
+    });
+    report("highlight", elapsed, lines.len(), bytes, &format!("{} spans", spans));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_second() {
+        let second = Duration::from_secs(1);
+        assert_eq!(per_second(2_500_000.0, second), "2.5M");
+        assert_eq!(per_second(340_400.0, second), "340k");
+        assert_eq!(per_second(87.0, second * 2), "44");
+    }
+}
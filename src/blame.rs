@@ -0,0 +1,118 @@
+use crate::source_ref::find_refs;
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+
+/// Most references of one line blamed, so a long stack trace stays quick.
+const MAX_BLAMED: usize = 5;
+
+/// The commit that last touched a source line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Blame {
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    pub summary: String,
+}
+
+type Cache = HashMap<(String, u32), Option<Blame>>;
+
+/// `git blame` results by file and line, misses included; the code doesn't
+/// change under a running viewer often enough to matter.
+static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(Default::default);
+
+/// Who last changed `line` of `path`, if it is in a git checkout.
+pub fn blame(path: &str, line: u32) -> Option<Blame> {
+    let key = (path.to_string(), line);
+    if let Some(cached) = CACHE.lock().ok()?.get(&key) {
+        return cached.clone();
+    }
+    let found = run_git_blame(Path::new(path), line);
+    CACHE.lock().ok()?.insert(key, found.clone());
+    found
+}
+
+fn run_git_blame(path: &Path, line: u32) -> Option<Blame> {
+    if !path.is_file() {
+        return None;
+    }
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["blame", "--porcelain", "-L", &format!("{0},{0}", line), "--"])
+        .arg(path.file_name()?)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_porcelain(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The commit, author, date and summary from `git blame --porcelain`.
+pub fn parse_porcelain(text: &str) -> Option<Blame> {
+    let mut lines = text.lines();
+    let commit = lines.next()?.split_whitespace().next()?;
+    let mut blame = Blame {
+        commit: commit.chars().take(8).collect(),
+        ..Blame::default()
+    };
+    for line in lines.take_while(|l| !l.starts_with('\t')) {
+        if let Some(author) = line.strip_prefix("author ") {
+            blame.author = author.to_string();
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            let time = time.parse().ok().and_then(|t| DateTime::from_timestamp(t, 0));
+            blame.date = time.map(|t| t.with_timezone(&Local).format("%Y-%m-%d").to_string()).unwrap_or_default();
+        } else if let Some(summary) = line.strip_prefix("summary ") {
+            blame.summary = summary.to_string();
+        }
+    }
+    Some(blame)
+}
+
+impl Blame {
+    pub fn describe(&self) -> String {
+        if self.commit.chars().all(|c| c == '0') {
+            return "not committed yet".to_string();
+        }
+        format!("{} {} {} {}", self.commit, self.author, self.date, self.summary)
+    }
+}
+
+/// `path:line  commit author date summary` for the references in `content`
+/// that git knows, for the detail popup.
+pub fn describe_refs(content: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut described = Vec::new();
+    for source in find_refs(content) {
+        if described.len() >= MAX_BLAMED || seen.contains(&(source.path.clone(), source.line)) {
+            continue;
+        }
+        seen.push((source.path.clone(), source.line));
+        if let Some(blame) = blame(&source.path, source.line) {
+            described.push(format!("{}:{}  {}", source.path, source.line, blame.describe()));
+        }
+    }
+    described
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let text = "3f2a9c1e5b7d0a4c8e6f1b2d3a4c5e6f7a8b9c0d 12 12 1\nauthor Dana Reyes\nauthor-mail <dana@example.com>\nauthor-time 1718123456\nauthor-tz +0000\ncommitter Dana Reyes\nsummary Retry uploads on timeout\nfilename src/upload.rs\n\tlet retries = 3;\n";
+        let blame = parse_porcelain(text).unwrap();
+        assert_eq!((blame.commit.as_str(), blame.author.as_str()), ("3f2a9c1e", "Dana Reyes"));
+        assert_eq!(blame.summary, "Retry uploads on timeout");
+        assert_eq!(blame.date.len(), 10);
+        let uncommitted = parse_porcelain("0000000000000000000000000000000000000000 1 1 1\nauthor Not Committed Yet\n").unwrap();
+        assert_eq!(uncommitted.describe(), "not committed yet");
+    }
+}
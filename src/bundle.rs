@@ -0,0 +1,150 @@
+use crate::core::LogLine;
+use crate::session::SavedNote;
+use crate::state::AppState;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Local, TimeZone};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const BUNDLE_EXTENSION: &str = "lvz";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleLine {
+    /// Receipt time, Unix milliseconds.
+    pub received_ms: i64,
+    pub content: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub system: bool,
+}
+
+/// A shareable snapshot of an investigation: the buffer, the settings it was
+/// viewed with and its notes. Stored as gzip-compressed JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub lines: Vec<BundleLine>,
+    pub settings: AppState,
+    #[serde(default)]
+    pub notes: Vec<SavedNote>,
+    /// Buffer index of the line at the bottom of the view, unless it was
+    /// following the tail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+impl Bundle {
+    pub fn new(lines: &[LogLine], settings: AppState, notes: Vec<SavedNote>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            lines: lines
+                .iter()
+                .map(|l| BundleLine {
+                    received_ms: l.timestamp.timestamp_millis(),
+                    content: l.content.clone(),
+                    source: l.source.as_deref().map(str::to_string),
+                    system: l.system,
+                })
+                .collect(),
+            settings,
+            notes,
+            position: None,
+        }
+    }
+
+    pub fn log_lines(&self) -> Vec<LogLine> {
+        self.lines
+            .iter()
+            .map(|l| {
+                let received = Local
+                    .timestamp_millis_opt(l.received_ms)
+                    .single()
+                    .unwrap_or_else(Local::now);
+                if l.system {
+                    LogLine::system(l.content.clone(), received)
+                } else {
+                    LogLine::new(l.content.clone(), l.source.as_deref().map(Arc::from), received)
+                }
+            })
+            .collect()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Cannot create {}", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let bundle: Bundle = serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+            .with_context(|| format!("{} is not a logviewer bundle", path.display()))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(anyhow!("Bundle version {} is newer than this logviewer", bundle.version));
+        }
+        Ok(bundle)
+    }
+}
+
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == BUNDLE_EXTENSION)
+}
+
+/// `path`, with the bundle extension added when it has none.
+pub fn bundle_path(path: &str) -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::from(path);
+    if path.extension().is_none() {
+        path.set_extension(BUNDLE_EXTENSION);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let now = Local::now();
+        let lines = vec![
+            LogLine::new("ERROR boom".to_string(), Some(Arc::from("app.log")), now),
+            LogLine::new("second".to_string(), None, now),
+        ];
+        let settings = AppState {
+            filter_input: "boom".to_string(),
+            ..AppState::default()
+        };
+        let notes = vec![SavedNote {
+            line: 0,
+            content: "ERROR boom".to_string(),
+            text: "why".to_string(),
+        }];
+        let path = std::env::temp_dir().join(format!("logviewer-test-{}.lvz", std::process::id()));
+        Bundle::new(&lines, settings, notes.clone()).write(&path).unwrap();
+        let bundle = Bundle::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(bundle.settings.filter_input, "boom");
+        assert_eq!(bundle.notes, notes);
+        let restored = bundle.log_lines();
+        assert_eq!(restored[0].content, "ERROR boom");
+        assert_eq!(restored[0].source.as_deref(), Some("app.log"));
+        assert_eq!(restored[0].timestamp.timestamp_millis(), now.timestamp_millis());
+    }
+
+    #[test]
+    fn test_bundle_path() {
+        assert_eq!(bundle_path("out"), std::path::PathBuf::from("out.lvz"));
+        assert_eq!(bundle_path("out.gz"), std::path::PathBuf::from("out.gz"));
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread::{self, JoinHandle};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// First line of a capture file.
+const HEADER: &str = "# logviewer capture v1";
+
+/// A chunk read: when, from whom, and the bytes.
+type Chunk = (i64, Arc<str>, Vec<u8>);
+
+/// Every chunk the listener receives, as it arrived: a
+/// `<unix micros> <byte count> <peer>` line, the bytes, then a newline.
+/// A zero-byte chunk is the client closing the connection. The file is
+/// written on a thread of its own, so reading from clients never waits on
+/// the disk; dropping the capture waits for it to finish.
+pub struct Capture {
+    chunks: Option<Sender<Chunk>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Capture {
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path).map_err(|e| anyhow!("Cannot create {}: {}", path.display(), e))?);
+        writeln!(file, "{}", HEADER)?;
+        file.flush()?;
+        let (tx, rx) = mpsc::channel();
+        let writer = thread::spawn(move || write_chunks(file, rx));
+        Ok(Self {
+            chunks: Some(tx),
+            writer: Some(writer),
+        })
+    }
+
+    fn record(&self, peer: &Arc<str>, data: &[u8]) {
+        if let Some(chunks) = &self.chunks {
+            let _ = chunks.send((Local::now().timestamp_micros(), peer.clone(), data.to_vec()));
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        drop(self.chunks.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Write chunks until every sender is gone, flushing whenever the queue
+/// runs dry.
+fn write_chunks(mut file: BufWriter<File>, chunks: Receiver<Chunk>) {
+    while let Ok(mut chunk) = chunks.recv() {
+        loop {
+            let (micros, peer, data) = &chunk;
+            let written = writeln!(file, "{} {} {}", micros, data.len(), peer)
+                .and_then(|_| file.write_all(data))
+                .and_then(|_| file.write_all(b"\n"));
+            if written.is_err() {
+                return;
+            }
+            match chunks.try_recv() {
+                Ok(next) => chunk = next,
+                Err(_) => break,
+            }
+        }
+        if file.flush().is_err() {
+            return;
+        }
+    }
+}
+
+/// Passes a client's stream through, recording each chunk read from it.
+pub struct Recorder<S> {
+    inner: S,
+    capture: Arc<Capture>,
+    peer: Arc<str>,
+    /// Leave out everything up to the first newline: the client's token.
+    skip_line: bool,
+}
+
+impl<S> Recorder<S> {
+    pub fn new(inner: S, capture: Arc<Capture>, peer: &str, skip_line: bool) -> Self {
+        Self {
+            inner,
+            capture,
+            peer: Arc::from(peer),
+            skip_line,
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Recorder<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &poll {
+            let mut chunk = &buf.filled()[before..];
+            if this.skip_line && !chunk.is_empty() {
+                match chunk.iter().position(|&b| b == b'\n') {
+                    Some(i) => {
+                        this.skip_line = false;
+                        chunk = &chunk[i + 1..];
+                        if chunk.is_empty() {
+                            return poll;
+                        }
+                    }
+                    None => return poll,
+                }
+            }
+            this.capture.record(&this.peer, chunk);
+        }
+        poll
+    }
+}
+
+pub fn is_capture(path: &Path) -> bool {
+    let mut first = String::new();
+    File::open(path).is_ok_and(|f| BufReader::new(f).read_line(&mut first).is_ok()) && first.trim_end() == HEADER
+}
+
+/// The capture's lines, each timed by the chunk that completed it, so a
+/// replay keeps the original pacing. Every peer's bytes are split on their own.
+pub fn read_lines(path: &Path) -> Result<Vec<(String, Option<DateTime<Local>>)>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let mut partial: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut chunk_header = String::new();
+    loop {
+        chunk_header.clear();
+        if reader.read_line(&mut chunk_header)? == 0 {
+            break;
+        }
+        let mut parts = chunk_header.trim_end_matches('\n').splitn(3, ' ');
+        let (Some(micros), Some(len), Some(peer)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(anyhow!("Bad chunk header in {}: {}", path.display(), chunk_header.trim_end()));
+        };
+        let time = micros.parse().ok().and_then(DateTime::from_timestamp_micros).map(|t| t.with_timezone(&Local));
+        // Read what is there rather than trusting the length with memory.
+        let len: u64 = len.parse()?;
+        let mut data = Vec::new();
+        (&mut reader).take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(anyhow!("{} ends inside a chunk", path.display()));
+        }
+        reader.read_exact(&mut [0])?;
+
+        let pending = partial.entry(peer.to_string()).or_default();
+        if data.is_empty() {
+            if !pending.is_empty() {
+                lines.push((String::from_utf8_lossy(pending).into_owned(), time));
+                pending.clear();
+            }
+            continue;
+        }
+        pending.extend_from_slice(&data);
+        while let Some(i) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=i).collect();
+            let text = String::from_utf8_lossy(&line);
+            lines.push((text.trim_end_matches(['\r', '\n']).to_string(), time));
+        }
+    }
+    for pending in partial.into_values().filter(|p| !p.is_empty()) {
+        lines.push((String::from_utf8_lossy(&pending).into_owned(), None));
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let path = std::env::temp_dir().join(format!("logviewer-test-{}.capture", std::process::id()));
+        let capture = Capture::create(&path).unwrap();
+        let (a, b): (Arc<str>, Arc<str>) = (Arc::from("a"), Arc::from("b"));
+        capture.record(&a, b"one\ntw");
+        capture.record(&b, b"other\n");
+        capture.record(&a, b"o\nthree");
+        capture.record(&a, b"");
+        drop(capture);
+        assert!(is_capture(&path));
+        let lines: Vec<String> = read_lines(&path).unwrap().into_iter().map(|(l, _)| l).collect();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(lines, ["one", "other", "two", "three"]);
+    }
+}
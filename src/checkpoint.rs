@@ -0,0 +1,138 @@
+use crate::source::SourceEvent;
+use crate::state::data_path;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const CHECKPOINT_FILE: &str = ".logviewer-offsets";
+
+/// Bytes at the start of a file hashed to tell it apart from a new file that
+/// reuses its inode.
+const HEAD_BYTES: u64 = 256;
+
+/// How often a tailed file's offset is written at most.
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far a file was read, keyed by its identity in `.logviewer-offsets`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Checkpoint {
+    /// For people reading the file; the key identifies the file.
+    path: String,
+    offset: u64,
+    head_len: u64,
+    head: u64,
+}
+
+fn checkpoints() -> &'static Mutex<BTreeMap<String, Checkpoint>> {
+    static CHECKPOINTS: OnceLock<Mutex<BTreeMap<String, Checkpoint>>> = OnceLock::new();
+    CHECKPOINTS.get_or_init(|| {
+        let saved = fs::read_to_string(data_path(CHECKPOINT_FILE, "offsets.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Mutex::new(saved)
+    })
+}
+
+/// Device and inode where there are any, so a renamed file keeps its
+/// checkpoint; the canonical path elsewhere.
+fn file_key(path: &Path) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let meta = fs::metadata(path).ok()?;
+        Some(format!("{}:{}", meta.dev(), meta.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        Some(fs::canonicalize(path).ok()?.to_string_lossy().into_owned())
+    }
+}
+
+/// FNV-1a of the first `len` bytes of `path`; stable across builds, unlike
+/// `DefaultHasher`.
+fn head_hash(path: &Path, len: u64) -> Option<u64> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(len).read_to_end(&mut head).ok()?;
+    if head.len() as u64 != len {
+        return None;
+    }
+    Some(fnv1a(&head))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Where reading `path` left off last time, if it is still the same file
+/// and hasn't been truncated below that.
+pub fn resume_offset(path: &Path) -> Option<u64> {
+    let key = file_key(path)?;
+    let checkpoint = checkpoints().lock().ok()?.get(&key).cloned()?;
+    let len = fs::metadata(path).ok()?.len();
+    let same = head_hash(path, checkpoint.head_len) == Some(checkpoint.head);
+    (same && checkpoint.offset <= len).then_some(checkpoint.offset)
+}
+
+/// Remember that `path` was taken in up to `offset`.
+pub fn save(path: &Path, offset: u64) {
+    let Some(key) = file_key(path) else {
+        return;
+    };
+    let head_len = offset.min(HEAD_BYTES);
+    let Some(head) = head_hash(path, head_len) else {
+        return;
+    };
+    let Ok(mut saved) = checkpoints().lock() else {
+        return;
+    };
+    let checkpoint = Checkpoint {
+        path: path.to_string_lossy().into_owned(),
+        offset,
+        head_len,
+        head,
+    };
+    if saved.get(&key) == Some(&checkpoint) {
+        return;
+    }
+    saved.insert(key, checkpoint);
+    if let Ok(content) = serde_json::to_string_pretty(&*saved) {
+        let _ = fs::write(data_path(CHECKPOINT_FILE, "offsets.json"), content);
+    }
+}
+
+/// Passes a tailed file's offset down the channel behind its lines as it
+/// advances, at most every `SAVE_INTERVAL`, for the consumer to `save`.
+pub struct Tail {
+    path: Arc<Path>,
+    sent: Option<(Instant, u64)>,
+}
+
+impl Tail {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: Arc::from(path),
+            sent: None,
+        }
+    }
+
+    /// Note the offset the records sent so far end at, sending it if the
+    /// last one went long enough ago; call again while idle so the last
+    /// offset gets sent. Returns false once the receiver is gone.
+    pub fn advance(&mut self, offset: u64, tx: &SyncSender<SourceEvent>) -> bool {
+        match self.sent {
+            Some((at, sent)) if sent == offset || at.elapsed() < SAVE_INTERVAL => true,
+            _ => {
+                self.sent = Some((Instant::now(), offset));
+                tx.send(SourceEvent::Checkpoint(self.path.clone(), offset)).is_ok()
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::state::data_path;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Where copies land when neither the system clipboard nor the terminal
+/// takes them.
+const FALLBACK_FILE: &str = ".logviewer-clipboard";
+
+/// How long a clipboard tool gets before the next method is tried.
+const NATIVE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Terminals commonly drop OSC 52 payloads longer than this.
+const MAX_OSC52_BYTES: usize = 100_000;
+
+pub enum Method {
+    Native(&'static str),
+    /// Sent to the terminal; whether it honoured the request is unknown.
+    Osc52,
+    File(PathBuf),
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Native(tool) => write!(f, "{}", tool),
+            Method::Osc52 => write!(f, "OSC 52"),
+            Method::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Copy `text` with the first method that works: a clipboard tool, then
+/// (with `terminal`, i.e. from the TUI) an OSC 52 escape, then the fallback
+/// file. Never waits on a hung tool for longer than `NATIVE_TIMEOUT`.
+pub fn copy(text: &str, terminal: bool) -> Result<Method> {
+    for &(tool, args) in native_tools() {
+        if run_tool(tool, args, text) {
+            return Ok(Method::Native(tool));
+        }
+    }
+    if terminal && text.len() * 4 / 3 < MAX_OSC52_BYTES && io::stdout().is_terminal() && write_osc52(text).is_ok() {
+        return Ok(Method::Osc52);
+    }
+    let path = data_path(FALLBACK_FILE, "clipboard.txt");
+    fs::write(&path, text).with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(Method::File(path))
+}
+
+fn native_tools() -> &'static [(&'static str, &'static [&'static str])] {
+    #[cfg(target_os = "macos")]
+    return &[("pbcopy", &[])];
+    #[cfg(target_os = "windows")]
+    return &[("clip", &[])];
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+        } else if std::env::var_os("DISPLAY").is_some() {
+            &[("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+        } else {
+            &[]
+        }
+    }
+}
+
+fn run_tool(tool: &str, args: &[&str], text: &str) -> bool {
+    let Ok(mut child) = Command::new(tool)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    // Written from another thread so a tool that doesn't read can't block
+    // us past the deadline. Dropping stdin closes it, which is what tells
+    // the tool the text ended.
+    let stdin = child.stdin.take();
+    let text = text.to_string();
+    let (tx, written) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(stdin.is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok()));
+    });
+    let deadline = Instant::now() + NATIVE_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // A daemon the tool left behind may still hold the pipe.
+                let left = deadline.saturating_duration_since(Instant::now());
+                return written.recv_timeout(left).unwrap_or(false) && status.success();
+            }
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                // Killing the tool closes the pipe, so the writer ends too.
+                let _ = child.kill();
+                let _ = child.wait();
+                return false;
+            }
+        }
+    }
+}
+
+fn write_osc52(text: &str) -> io::Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    let mut stdout = io::stdout();
+    if std::env::var_os("TMUX").is_some() {
+        // tmux passes the escape on to the outer terminal only when wrapped.
+        write!(stdout, "\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))?;
+    } else {
+        write!(stdout, "{}", sequence)?;
+    }
+    stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"nc 10.0.0.2 9999"), "bmMgMTAuMC4wLjIgOTk5OQ==");
+    }
+}
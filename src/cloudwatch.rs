@@ -0,0 +1,230 @@
+use crate::source::{Feed, Message};
+use crate::timestamp::with_time;
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// Wait between polls, doubled while CloudWatch throttles up to the max.
+const POLL: Duration = Duration::from_secs(2);
+const POLL_MAX: Duration = Duration::from_secs(60);
+
+/// How far back each poll looks again for events ingested late, which are
+/// told apart from ones already shown by their event ID.
+const LATE_MS: i64 = 30_000;
+
+/// Events a poll reads at most (`--max-items`); a busy group's rest is read
+/// by the next poll, at once, from where this one stopped.
+const MAX_ITEMS: usize = 1000;
+
+/// `--cloudwatch GROUP[:STREAM_PREFIX]` with the AWS profile and region to
+/// use, the CLI's defaults when not given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CloudWatchTarget {
+    pub group: String,
+    pub stream_prefix: Option<String>,
+    pub profile: Option<String>,
+    pub region: Option<String>,
+}
+
+impl FromStr for CloudWatchTarget {
+    type Err = anyhow::Error;
+
+    /// Log group names can't hold `:`, so the first one starts the stream
+    /// prefix.
+    fn from_str(s: &str) -> Result<Self> {
+        let (group, stream_prefix) = match s.split_once(':') {
+            Some((group, prefix)) => (group, Some(prefix.to_string()).filter(|p| !p.is_empty())),
+            None => (s, None),
+        };
+        if group.is_empty() {
+            bail!("Name the log group: --cloudwatch GROUP[:STREAM_PREFIX]");
+        }
+        Ok(Self {
+            group: group.to_string(),
+            stream_prefix,
+            profile: None,
+            region: None,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Event {
+    log_stream_name: String,
+    timestamp: i64,
+    message: String,
+    event_id: String,
+}
+
+#[derive(Deserialize)]
+struct Events {
+    #[serde(default)]
+    events: Vec<Event>,
+    /// Set by the CLI when `--max-items` cut the results short.
+    #[serde(rename = "NextToken")]
+    next_token: Option<String>,
+}
+
+/// Polls a log group with `aws logs filter-log-events`.
+pub struct Poller {
+    target: CloudWatchTarget,
+    /// Events from this time on (ms since the epoch) are asked for.
+    start_ms: i64,
+    /// Events before this were read before this poller was opened.
+    floor_ms: i64,
+    /// IDs of the events shown in the last `LATE_MS` before `start_ms`, with
+    /// their times.
+    seen: HashMap<String, i64>,
+    /// The start time and token of a poll cut short, to continue it.
+    page: Option<(i64, String)>,
+    interval: Duration,
+    first: bool,
+}
+
+impl Poller {
+    /// Poll from `start_ms` on, checking the AWS CLI is there.
+    pub fn open(target: &CloudWatchTarget, start_ms: i64) -> Result<Self> {
+        Command::new("aws")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| anyhow!("Cannot run aws: {}", e))?;
+        Ok(Self {
+            target: target.clone(),
+            start_ms,
+            floor_ms: start_ms,
+            seen: HashMap::new(),
+            page: None,
+            interval: POLL,
+            first: true,
+        })
+    }
+
+    /// Where the next poll starts: where the one cut short did, else
+    /// `LATE_MS` before the newest event.
+    fn query_start(&self) -> i64 {
+        self.page.as_ref().map_or(self.start_ms - LATE_MS, |(start, _)| *start)
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("aws");
+        command
+            .args(["logs", "filter-log-events", "--output", "json", "--log-group-name", &self.target.group])
+            .args(["--start-time", &self.query_start().to_string()])
+            .args(["--max-items", &MAX_ITEMS.to_string()]);
+        if let Some((_, token)) = &self.page {
+            command.args(["--starting-token", token]);
+        }
+        if let Some(prefix) = &self.target.stream_prefix {
+            command.args(["--log-stream-name-prefix", prefix]);
+        }
+        if let Some(profile) = &self.target.profile {
+            command.args(["--profile", profile]);
+        }
+        if let Some(region) = &self.target.region {
+            command.args(["--region", region]);
+        }
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        command
+    }
+
+    /// The events in `json` not shown yet, oldest first, moving the start
+    /// past them. A poll cut short leaves its token for the next.
+    fn take_new(&mut self, json: &str) -> Result<Vec<Message>> {
+        let Events { mut events, next_token } = serde_json::from_str(json)?;
+        self.page = next_token.map(|token| (self.query_start(), token));
+        events.sort_by_key(|event| event.timestamp);
+        let mut messages = Vec::new();
+        for event in events {
+            let old = event.timestamp < self.floor_ms || event.timestamp < self.start_ms - LATE_MS;
+            if old || self.seen.contains_key(&event.event_id) {
+                continue;
+            }
+            self.start_ms = self.start_ms.max(event.timestamp);
+            self.seen.insert(event.event_id, event.timestamp);
+            messages.push(Message {
+                source: event.log_stream_name,
+                content: with_time(event.message.trim_end_matches(['\r', '\n']), event.timestamp),
+            });
+        }
+        let oldest = self.start_ms - LATE_MS;
+        self.seen.retain(|_, timestamp| *timestamp >= oldest);
+        Ok(messages)
+    }
+}
+
+impl Feed for Poller {
+    /// Every poll but the first, and those continuing one cut short, waits
+    /// its interval. A throttled poll comes back empty and the interval
+    /// doubles; a good one resets it.
+    fn next_batch(&mut self, wait: bool) -> Result<Vec<Message>> {
+        if wait && !self.first && self.page.is_none() {
+            thread::sleep(self.interval);
+        }
+        self.first = false;
+        let output = self.command().output().map_err(|e| anyhow!("Cannot run aws: {}", e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("ThrottlingException") || stderr.contains("Rate exceeded") {
+                self.interval = (self.interval * 2).min(POLL_MAX);
+                return Ok(Vec::new());
+            }
+            bail!("{}", stderr.trim());
+        }
+        self.interval = POLL;
+        self.take_new(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn position(&self) -> Option<String> {
+        Some(self.start_ms.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cloudwatch_events() {
+        let target: CloudWatchTarget = "/aws/lambda/api:2024/06/12/[$LATEST]".parse().unwrap();
+        assert_eq!((target.group.as_str(), target.stream_prefix.as_deref()), ("/aws/lambda/api", Some("2024/06/12/[$LATEST]")));
+
+        let mut poller = Poller {
+            target,
+            start_ms: 1_718_186_400_000,
+            floor_ms: 1_718_186_400_000,
+            seen: HashMap::new(),
+            page: None,
+            interval: POLL,
+            first: true,
+        };
+        let json = r#"{"events": [
+            {"logStreamName": "s1", "timestamp": 1718186400500, "message": "INFO started\n", "ingestionTime": 0, "eventId": "b"},
+            {"logStreamName": "s2", "timestamp": 1718186400100, "message": "2024-06-12T10:00:00Z WARN slow", "ingestionTime": 0, "eventId": "a"}
+        ], "searchedLogStreams": []}"#;
+        let texts = |messages: Vec<Message>| messages.into_iter().map(|m| format!("{} {}", m.source, m.content)).collect::<Vec<_>>();
+        assert_eq!(
+            texts(poller.take_new(json).unwrap()),
+            ["s2 2024-06-12T10:00:00Z WARN slow", "s1 2024-06-12T10:00:00.500Z INFO started"]
+        );
+        assert_eq!(poller.position().as_deref(), Some("1718186400500"));
+        // The overlap brings the same events back; only the late one is new.
+        let late = json.replace(r#""eventId": "a"}"#, r#""eventId": "a"}, {"logStreamName": "s3", "timestamp": 1718186400200, "message": "x", "ingestionTime": 0, "eventId": "c"}"#);
+        assert_eq!(texts(poller.take_new(&late).unwrap()), ["s3 2024-06-12T10:00:00.200Z x"]);
+
+        // A poll cut short by --max-items is continued from its token and start.
+        let start = poller.query_start();
+        poller.take_new(r#"{"events": [], "NextToken": "t0k"}"#).unwrap();
+        let args: Vec<String> = poller.command().get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert!(args.windows(2).any(|w| w == ["--starting-token", "t0k"]));
+        assert!(args.windows(2).any(|w| w == ["--start-time".to_string(), start.to_string()]));
+        poller.take_new(r#"{"events": []}"#).unwrap();
+        assert!(!poller.command().get_args().any(|a| a == "--starting-token"));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::core::schema::field_value;
+use crate::core::{format_relative_time, thread_of, LogLine};
+use crate::time_display::TimeDisplay;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// How the receipt time column shows when a line arrived.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// Age, like `-12s`.
+    #[default]
+    Relative,
+    /// Wall clock, by default with milliseconds like `14:03:07.412`.
+    Clock,
+    /// Time since the previous line, like `+35ms`.
+    Delta,
+}
+
+impl TimeFormat {
+    pub fn next(self) -> Self {
+        match self {
+            TimeFormat::Relative => TimeFormat::Clock,
+            TimeFormat::Clock => TimeFormat::Delta,
+            TimeFormat::Delta => TimeFormat::Relative,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeFormat::Relative => "relative",
+            TimeFormat::Clock => "clock",
+            TimeFormat::Delta => "delta",
+        }
+    }
+
+    /// Receipt column width that fits the format.
+    pub fn width(self, display: &TimeDisplay) -> usize {
+        match self {
+            TimeFormat::Relative => 6,
+            TimeFormat::Clock => display.clock_width(),
+            TimeFormat::Delta => 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    LineNumber,
+    ReceiptTime,
+    EventTime,
+    Level,
+    Source,
+    Thread,
+    /// A logfmt or JSON field, added from the fields panel.
+    Field(String),
+}
+
+impl ColumnKind {
+    pub const ALL: [ColumnKind; 6] = [
+        ColumnKind::ReceiptTime,
+        ColumnKind::LineNumber,
+        ColumnKind::EventTime,
+        ColumnKind::Level,
+        ColumnKind::Source,
+        ColumnKind::Thread,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            ColumnKind::LineNumber => "Line number",
+            ColumnKind::ReceiptTime => "Received",
+            ColumnKind::EventTime => "Event time",
+            ColumnKind::Level => "Level",
+            ColumnKind::Source => "Source",
+            ColumnKind::Thread => "Thread",
+            ColumnKind::Field(name) => name,
+        }
+    }
+
+    pub fn is_time(&self) -> bool {
+        matches!(self, ColumnKind::ReceiptTime | ColumnKind::EventTime)
+    }
+
+    fn default_width(&self) -> usize {
+        match self {
+            ColumnKind::LineNumber => 6,
+            ColumnKind::ReceiptTime => 6,
+            ColumnKind::EventTime => 8,
+            ColumnKind::Level => 3,
+            ColumnKind::Source => 12,
+            ColumnKind::Thread => 10,
+            ColumnKind::Field(_) => 12,
+        }
+    }
+
+    fn right_aligned(&self) -> bool {
+        matches!(self, ColumnKind::LineNumber | ColumnKind::ReceiptTime)
+    }
+}
+
+/// One prefix column of the log view. Hidden columns keep their place and
+/// width so toggling them back on restores the layout.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+    pub kind: ColumnKind,
+    pub width: usize,
+    pub visible: bool,
+}
+
+pub const MIN_COLUMN_WIDTH: usize = 1;
+pub const MAX_COLUMN_WIDTH: usize = 40;
+
+pub fn default_columns() -> Vec<Column> {
+    ColumnKind::ALL
+        .iter()
+        .map(|kind| Column {
+            kind: kind.clone(),
+            width: kind.default_width(),
+            visible: matches!(
+                kind,
+                ColumnKind::ReceiptTime | ColumnKind::LineNumber | ColumnKind::Level
+            ),
+        })
+        .collect()
+}
+
+/// Drop duplicate kinds and append any missing ones (hidden), so a hand-edited
+/// or older state file still yields one entry per column.
+pub fn normalize(columns: Vec<Column>) -> Vec<Column> {
+    let mut result: Vec<Column> = Vec::new();
+    for mut column in columns {
+        if result.iter().all(|c| c.kind != column.kind) {
+            column.width = column.width.clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH);
+            result.push(column);
+        }
+    }
+    for kind in ColumnKind::ALL {
+        if result.iter().all(|c| c.kind != kind) {
+            result.push(Column {
+                width: kind.default_width(),
+                kind,
+                visible: false,
+            });
+        }
+    }
+    result
+}
+
+/// The columns to draw, in order. `show_time` off hides the time columns.
+pub fn visible_columns(columns: &[Column], show_time: bool) -> impl Iterator<Item = &Column> {
+    columns
+        .iter()
+        .filter(move |c| c.visible && (show_time || !c.kind.is_time()))
+}
+
+/// Total prefix width in the TUI: each column plus a space, then `│ `.
+pub fn prefix_width(columns: &[Column], show_time: bool) -> usize {
+    visible_columns(columns, show_time).map(|c| c.width + 1).sum::<usize>() + 2
+}
+
+/// Size the receipt column to fit `time_format`.
+pub fn set_receipt_width(columns: &mut [Column], time_format: TimeFormat, display: &TimeDisplay) {
+    if let Some(column) = columns.iter_mut().find(|c| c.kind == ColumnKind::ReceiptTime) {
+        column.width = time_format.width(display);
+    }
+}
+
+/// Size the event time column to fit the event format.
+pub fn set_event_width(columns: &mut [Column], display: &TimeDisplay) {
+    if let Some(column) = columns.iter_mut().find(|c| c.kind == ColumnKind::EventTime) {
+        column.width = display.event_width();
+    }
+}
+
+/// Add a visible column for the field `name`, or drop it if there is one;
+/// true when it was added.
+pub fn toggle_field(columns: &mut Vec<Column>, name: &str) -> bool {
+    let kind = ColumnKind::Field(name.to_string());
+    if let Some(pos) = columns.iter().position(|c| c.kind == kind) {
+        columns.remove(pos);
+        return false;
+    }
+    columns.push(Column {
+        width: kind.default_width(),
+        kind,
+        visible: true,
+    });
+    true
+}
+
+/// Text of `kind`'s cell for line `line_idx` of `lines`.
+pub fn cell_text(kind: &ColumnKind, lines: &[LogLine], line_idx: usize, time_format: TimeFormat, display: &TimeDisplay) -> String {
+    let line = &lines[line_idx];
+    match kind {
+        ColumnKind::LineNumber => (line_idx + 1).to_string(),
+        ColumnKind::ReceiptTime => match time_format {
+            TimeFormat::Relative => format_relative_time(line.timestamp),
+            TimeFormat::Clock => display.clock(line.timestamp),
+            TimeFormat::Delta => match line_idx.checked_sub(1).map(|prev| &lines[prev]) {
+                Some(prev) => format_delta(line.timestamp - prev.timestamp),
+                None => String::new(),
+            },
+        },
+        ColumnKind::EventTime => line.event_time.map(|t| display.event(t)).unwrap_or_default(),
+        ColumnKind::Level => line.level.map(|l| l.badge().to_string()).unwrap_or_default(),
+        ColumnKind::Source => line.source.as_deref().unwrap_or("").to_string(),
+        ColumnKind::Thread => thread_of(&line.content).unwrap_or("").to_string(),
+        ColumnKind::Field(name) => field_value(&line.content, name).unwrap_or_default(),
+    }
+}
+
+fn format_delta(delta: Duration) -> String {
+    let ms = delta.num_milliseconds().max(0);
+    match ms {
+        0..=999 => format!("+{}ms", ms),
+        1_000..=59_999 => format!("+{}.{:03}s", ms / 1000, ms % 1000),
+        60_000..=3_599_999 => format!("+{}m{:02}s", ms / 60_000, ms / 1000 % 60),
+        _ => format!("+{}h{:02}m", ms / 3_600_000, ms / 60_000 % 60),
+    }
+}
+
+/// Pad or truncate `text` to exactly `column.width` terminal cells. Cut text
+/// gets a `…` where it was cut: at the start of a right-aligned column, so
+/// a line number never loses its leading digits unnoticed.
+pub fn fit(text: &str, column: &Column) -> String {
+    let width = column.width;
+    let right = column.kind.right_aligned();
+    let mut text = text.to_string();
+    if text.width() > width && width > 0 {
+        let mut kept = String::new();
+        let mut room = width - 1;
+        let chars: Vec<char> = if right { text.chars().rev().collect() } else { text.chars().collect() };
+        for c in chars {
+            let w = c.width().unwrap_or(0);
+            if w > room {
+                break;
+            }
+            room -= w;
+            kept.push(c);
+        }
+        text = if right { format!("…{}", kept.chars().rev().collect::<String>()) } else { format!("{}…", kept) };
+    } else if width == 0 {
+        text.clear();
+    }
+    let pad = " ".repeat(width.saturating_sub(text.width()));
+    if right {
+        pad + &text
+    } else {
+        text + &pad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_layout() {
+        let columns = default_columns();
+        assert_eq!(prefix_width(&columns, true), 20);
+        assert_eq!(prefix_width(&columns, false), 13);
+    }
+
+    #[test]
+    fn test_level_cell_is_badge() {
+        let lines = [LogLine::new("[warning] disk low".to_string(), None, chrono::Local::now())];
+        let level = Column { kind: ColumnKind::Level, width: 3, visible: true };
+        assert_eq!(fit(&cell_text(&ColumnKind::Level, &lines, 0, TimeFormat::Relative, &TimeDisplay::default()), &level), "WRN");
+    }
+
+    #[test]
+    fn test_receipt_time_formats() {
+        let start = chrono::Local::now();
+        let lines = [
+            LogLine::new("a".to_string(), None, start),
+            LogLine::new("b".to_string(), None, start + Duration::milliseconds(35)),
+            LogLine::new("c".to_string(), None, start + Duration::milliseconds(1285)),
+        ];
+        let display = TimeDisplay::default();
+        let delta = |idx| cell_text(&ColumnKind::ReceiptTime, &lines, idx, TimeFormat::Delta, &display);
+        assert_eq!((delta(0).as_str(), delta(1).as_str(), delta(2).as_str()), ("", "+35ms", "+1.250s"));
+        let clock = cell_text(&ColumnKind::ReceiptTime, &lines, 1, TimeFormat::Clock, &display);
+        assert_eq!(clock.len(), TimeFormat::Clock.width(&display));
+        assert_eq!(format_delta(Duration::seconds(3725)), "+1h02m");
+    }
+
+    #[test]
+    fn test_normalize_dedupes_and_fills() {
+        let columns = normalize(vec![
+            Column { kind: ColumnKind::Level, width: 99, visible: true },
+            Column { kind: ColumnKind::Level, width: 3, visible: false },
+        ]);
+        assert_eq!(columns.len(), ColumnKind::ALL.len());
+        assert_eq!(columns[0], Column { kind: ColumnKind::Level, width: MAX_COLUMN_WIDTH, visible: true });
+        assert!(columns[1..].iter().all(|c| !c.visible));
+    }
+
+    #[test]
+    fn test_fit() {
+        let source = Column { kind: ColumnKind::Source, width: 4, visible: true };
+        assert_eq!(fit("api-server", &source), "api…");
+        assert_eq!(fit("db", &source), "db  ");
+        assert_eq!(fit("日本語", &source), "日… ");
+        assert_eq!(fit("日本", &source), "日本");
+        let num = Column { kind: ColumnKind::LineNumber, width: 3, visible: true };
+        assert_eq!(fit("7", &num), "  7");
+        assert_eq!(fit("12345", &num), "…45");
+    }
+}
@@ -0,0 +1,116 @@
+use crate::core::{LogLine, LogState};
+use std::collections::BTreeMap;
+
+/// The buffer the sources write to.
+pub const MAIN: &str = "main";
+
+/// Named buffers of lines besides the live one, each with its own notes,
+/// marks and scroll position; the one in view sits in `App::log_state`.
+pub struct Buffers {
+    pub current: String,
+    /// The buffers not in view, by name.
+    parked: BTreeMap<String, LogState>,
+}
+
+impl Default for Buffers {
+    fn default() -> Self {
+        Self {
+            current: MAIN.to_string(),
+            parked: BTreeMap::new(),
+        }
+    }
+}
+
+impl Buffers {
+    /// The buffer new lines go to, given the one in view.
+    pub fn live<'a>(&'a mut self, shown: &'a mut LogState) -> &'a mut LogState {
+        match self.parked.get_mut(MAIN) {
+            Some(main) => main,
+            None => shown,
+        }
+    }
+
+    pub fn live_ref<'a>(&'a self, shown: &'a LogState) -> &'a LogState {
+        self.parked.get(MAIN).unwrap_or(shown)
+    }
+
+    /// Every buffer with its line count, in name order.
+    pub fn list(&self, shown: &LogState) -> Vec<(String, usize)> {
+        let mut all: Vec<(String, usize)> = self
+            .parked
+            .iter()
+            .map(|(name, state)| (name.clone(), state.lines.len()))
+            .collect();
+        all.push((self.current.clone(), shown.lines.len()));
+        all.sort();
+        all
+    }
+
+    /// Bring buffer `name` into view with the settings of the one leaving it.
+    pub fn switch(&mut self, name: &str, shown: &mut LogState) -> Result<(), String> {
+        if name == self.current {
+            return Err(format!("Already in buffer {}", name));
+        }
+        let mut next = self
+            .parked
+            .remove(name)
+            .ok_or_else(|| format!("No buffer {}; :copy or :move lines into one", name))?;
+        next.adopt_settings(shown);
+        std::mem::swap(shown, &mut next);
+        self.parked.insert(std::mem::replace(&mut self.current, name.to_string()), next);
+        Ok(())
+    }
+
+    /// Append lines to buffer `name`, making it when it doesn't exist.
+    pub fn append(&mut self, name: &str, lines: Vec<LogLine>, shown: &LogState) -> Result<(), String> {
+        if name == self.current {
+            return Err(format!("Those lines are already in buffer {}", name));
+        }
+        let buffer = self.parked.entry(name.to_string()).or_insert_with(|| {
+            let mut state = LogState::default();
+            state.adopt_settings(shown);
+            state
+        });
+        buffer.load(lines);
+        Ok(())
+    }
+
+    /// Drop a buffer that isn't in view or live; returns how many lines it had.
+    pub fn remove(&mut self, name: &str) -> Result<usize, String> {
+        if name == MAIN || name == self.current {
+            return Err(format!("Can't drop buffer {}", name));
+        }
+        self.parked
+            .remove(name)
+            .map(|state| state.lines.len())
+            .ok_or_else(|| format!("No buffer {}", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_lines_keep_flowing_to_main() {
+        let mut shown = LogState::default();
+        let mut buffers = Buffers::default();
+        shown.ingest("first".to_string(), None);
+        let copied = vec![LogLine::new("copied".to_string(), None, Local::now())];
+        buffers.append("incident", copied, &shown).unwrap();
+        assert!(buffers.append(MAIN, Vec::new(), &shown).is_err());
+
+        buffers.switch("incident", &mut shown).unwrap();
+        assert_eq!(shown.lines[0].content, "copied");
+        buffers.live(&mut shown).ingest("second".to_string(), None);
+        assert_eq!(shown.lines.len(), 1);
+        assert_eq!(buffers.list(&shown), [("incident".to_string(), 1), (MAIN.to_string(), 2)]);
+        assert!(buffers.remove("incident").is_err());
+
+        buffers.switch(MAIN, &mut shown).unwrap();
+        assert_eq!(shown.lines.len(), 2);
+        assert_eq!(buffers.remove("incident"), Ok(1));
+        assert!(buffers.switch("incident", &mut shown).is_err());
+    }
+}
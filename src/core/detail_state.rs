@@ -0,0 +1,116 @@
+use crate::highlight::json_tree::JsonTree;
+use crate::input::TextInput;
+use std::ops::Range;
+
+/// Popup showing a single log line in full, optionally pretty-printed.
+#[derive(Clone, Default)]
+pub struct DetailState {
+    pub line_idx: usize,
+    pub pretty: bool,
+    pub scroll: u16,
+    /// The line's JSON as a foldable tree, while pretty-printing it.
+    pub tree: Option<JsonTree>,
+    /// The `/` query while it is being typed.
+    pub search: Option<TextInput>,
+    /// The query matches are highlighted for.
+    pub query: String,
+    /// Which match `n` / `N` are on.
+    pub current: usize,
+    /// Matches found when last drawn.
+    pub matches: usize,
+    /// Scroll the current match into view on the next draw.
+    pub reveal: bool,
+}
+
+impl DetailState {
+    pub fn new(line_idx: usize) -> Self {
+        Self {
+            line_idx,
+            ..Self::default()
+        }
+    }
+
+    pub fn toggle_pretty(&mut self) {
+        self.pretty = !self.pretty;
+        self.scroll = 0;
+        self.reveal = !self.query.is_empty();
+    }
+
+    pub fn scroll_up(&mut self, amount: u16) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    pub fn scroll_down(&mut self, amount: u16) {
+        self.scroll = self.scroll.saturating_add(amount);
+    }
+
+    pub fn start_search(&mut self) {
+        self.search = Some(TextInput::new(self.query.clone()));
+    }
+
+    /// The query being typed, else the last one.
+    pub fn active_query(&self) -> &str {
+        self.search.as_ref().map_or(&self.query, |input| &input.text)
+    }
+
+    /// Jump to the first match of what was typed so far.
+    pub fn search_changed(&mut self) {
+        self.current = 0;
+        self.reveal = true;
+    }
+
+    pub fn finish_search(&mut self, keep: bool) {
+        if let Some(input) = self.search.take() {
+            if keep {
+                self.query = input.text;
+            }
+        }
+        self.search_changed();
+    }
+
+    /// Move `by` matches, wrapping around.
+    pub fn next_match(&mut self, by: isize) {
+        if self.matches > 0 {
+            self.current = (self.current as isize + by).rem_euclid(self.matches as isize) as usize;
+            self.reveal = true;
+        }
+    }
+}
+
+/// Byte ranges of `query` in `text`; case-insensitive unless the query has
+/// capitals.
+pub fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let (text, query) = match query.chars().any(char::is_uppercase) {
+        true => (text.to_string(), query.to_string()),
+        false => (text.to_ascii_lowercase(), query.to_ascii_lowercase()),
+    };
+    text.match_indices(&query).map(|(start, m)| start..start + m.len()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detail_search() {
+        let text = r#"{"user": "Ana", "userId": 7, "note": "USER left"}"#;
+        assert_eq!(find_matches(text, "user").len(), 3);
+        assert_eq!(find_matches(text, "USER"), vec![38..42]);
+        assert!(find_matches(text, "").is_empty());
+
+        let mut detail = DetailState::new(3);
+        detail.start_search();
+        detail.search.as_mut().unwrap().insert_paste("user");
+        assert_eq!(detail.active_query(), "user");
+        detail.finish_search(true);
+        assert_eq!(detail.query, "user");
+        detail.matches = 3;
+        detail.next_match(-1);
+        assert_eq!(detail.current, 2);
+        detail.next_match(1);
+        assert_eq!(detail.current, 0);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::entity::Entity;
+
+/// Popup listing the entities on the cursor line and the actions for them.
+#[derive(Clone, Debug)]
+pub struct EntityMenu {
+    pub entities: Vec<Entity>,
+    pub selected: usize,
+}
+
+impl EntityMenu {
+    pub fn new(entities: Vec<Entity>) -> Option<Self> {
+        if entities.is_empty() {
+            None
+        } else {
+            Some(Self { entities, selected: 0 })
+        }
+    }
+
+    pub fn selected(&self) -> &Entity {
+        &self.entities[self.selected]
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entities.len() {
+            self.selected += 1;
+        }
+    }
+}
@@ -1,31 +1,81 @@
+use crate::enrich::Enricher;
 use crate::filter::FilterExpr;
+use crate::highlight::HighlightSettings;
+use crate::humanize::Humanize;
+use crate::rewrite::{apply_rules, RewriteRule};
+use crate::timestamp::annotate_epochs;
 use fancy_regex::Regex;
+use std::sync::Arc;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct FilterState {
     pub hide_regex: Option<Regex>,
     pub filter_expr: Option<FilterExpr>,
     pub highlight_expr: Option<FilterExpr>,
+    pub highlight: HighlightSettings,
+    pub rewrite_rules: Vec<RewriteRule>,
+    /// Show lines as received, skipping the rewrite rules.
+    pub show_raw: bool,
+    /// Write the local time after epoch timestamps (`annotate_epochs()`).
+    pub show_epochs: bool,
+    /// Byte and duration fields shown humanized (`:humanize`).
+    pub humanize: Option<Humanize>,
+    /// GeoIP and host name fields written after IP addresses (`:enrich`),
+    /// looked up per line into `LogLine::enrichment`.
+    pub enrich: Option<Arc<Enricher>>,
 }
 
-impl Default for FilterState {
-    fn default() -> Self {
-        Self {
-            hide_regex: None,
-            filter_expr: None,
-            highlight_expr: None,
+impl FilterState {
+    /// The text shown for a line: hide_regex applied, then the rewrite rules
+    /// then byte sizes and durations and epoch times unless raw display is
+    /// on. Filters match this text too, with `LogState::enriched()` applied.
+    pub fn display(&self, content: &str) -> Result<String, String> {
+        let hidden = self.apply_hide(content)?;
+        if self.show_raw {
+            return Ok(hidden);
+        }
+        let mut text = if self.rewrite_rules.is_empty() {
+            hidden
+        } else {
+            apply_rules(&hidden, &self.rewrite_rules)
+        };
+        if let Some(humanize) = &self.humanize {
+            text = humanize.apply(&text);
         }
+        if self.show_epochs {
+            text = annotate_epochs(&text);
+        }
+        Ok(text)
     }
-}
 
-impl FilterState {
     /// Apply hide_regex to content, removing matched portions.
     /// If regex has capture groups, only those groups are removed.
     /// Otherwise, the entire match is removed.
     pub fn apply_hide(&self, content: &str) -> Result<String, String> {
+        let merged = self.hidden_ranges(content)?;
+        if merged.is_empty() {
+            return Ok(content.to_string());
+        }
+
+        let mut result = String::new();
+        let mut pos = 0;
+        for (start, end) in merged {
+            if start > pos && start <= content.len() {
+                result.push_str(&content[pos..start]);
+            }
+            pos = end.min(content.len());
+        }
+        if pos < content.len() {
+            result.push_str(&content[pos..]);
+        }
+        Ok(result)
+    }
+
+    /// Byte ranges of `content` that `apply_hide` removes, sorted and merged.
+    pub fn hidden_ranges(&self, content: &str) -> Result<Vec<(usize, usize)>, String> {
         let re = match &self.hide_regex {
             Some(re) => re,
-            None => return Ok(content.to_string()),
+            None => return Ok(Vec::new()),
         };
 
         let mut ranges_to_remove: Vec<(usize, usize)> = Vec::new();
@@ -56,10 +106,6 @@ impl FilterState {
             }
         }
 
-        if ranges_to_remove.is_empty() {
-            return Ok(content.to_string());
-        }
-
         ranges_to_remove.sort_by_key(|r| r.0);
         let mut merged: Vec<(usize, usize)> = Vec::new();
         for range in ranges_to_remove {
@@ -71,19 +117,7 @@ impl FilterState {
             }
             merged.push(range);
         }
-
-        let mut result = String::new();
-        let mut pos = 0;
-        for (start, end) in merged {
-            if start > pos && start <= content.len() {
-                result.push_str(&content[pos..start]);
-            }
-            pos = end.min(content.len());
-        }
-        if pos < content.len() {
-            result.push_str(&content[pos..]);
-        }
-        Ok(result)
+        Ok(merged)
     }
 
     pub fn matches_filter(&self, content: &str) -> bool {
@@ -92,4 +126,9 @@ impl FilterState {
             None => true,
         }
     }
+
+    /// How many places in `content` the highlight expression matches.
+    pub fn highlight_count(&self, content: &str) -> usize {
+        self.highlight_expr.as_ref().map_or(0, |expr| expr.find_all_matches(content).len())
+    }
 }
@@ -8,6 +8,25 @@ pub enum InputMode {
     FilterEdit,
     HighlightEdit,
     LineStartEdit,
+    RewriteEdit,
+    WatchEdit,
+    /// `:` command line in the status bar.
+    Command,
+}
+
+impl InputMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            InputMode::Normal => "NORMAL",
+            InputMode::HideEdit => "HIDE",
+            InputMode::FilterEdit => "FILTER",
+            InputMode::HighlightEdit => "HIGHLIGHT",
+            InputMode::LineStartEdit => "LINE START",
+            InputMode::RewriteEdit => "REWRITE",
+            InputMode::WatchEdit => "WATCH",
+            InputMode::Command => "COMMAND",
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -16,6 +35,9 @@ pub struct InputFields {
     pub filter: TextInput,
     pub highlight: TextInput,
     pub line_start: TextInput,
+    pub rewrite: TextInput,
+    pub watch: TextInput,
+    pub command: TextInput,
 }
 
 impl InputFields {
@@ -25,6 +47,9 @@ impl InputFields {
             filter: TextInput::new(state.filter_input.clone()),
             highlight: TextInput::new(state.highlight_input.clone()),
             line_start: TextInput::new(state.line_start_regex.clone()),
+            rewrite: TextInput::new(state.rewrite_input.clone()),
+            watch: TextInput::new(state.watch_input.clone()),
+            command: TextInput::default(),
         }
     }
 
@@ -34,6 +59,9 @@ impl InputFields {
             InputMode::FilterEdit => Some(&mut self.filter),
             InputMode::HighlightEdit => Some(&mut self.highlight),
             InputMode::LineStartEdit => Some(&mut self.line_start),
+            InputMode::RewriteEdit => Some(&mut self.rewrite),
+            InputMode::WatchEdit => Some(&mut self.watch),
+            InputMode::Command => Some(&mut self.command),
             InputMode::Normal => None,
         }
     }
@@ -0,0 +1,111 @@
+use super::LogLine;
+use crate::logcat;
+use fancy_regex::Regex;
+use std::sync::LazyLock;
+
+/// Most lanes the lane view draws; threads first seen after that get none.
+pub const MAX_LANES: usize = 8;
+
+/// `thread=worker-2`, `"tid": 4711`, `pid:12`.
+static KEY_VALUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?<![\w-])["']?(?:thread(?:[_-]?(?:name|id))?|tid|pid)["']?\s*[:=]\s*["']?([\w.:@/-]+)"#).unwrap()
+});
+/// Syslog's `sshd[1234]:`.
+static SYSLOG_PID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\w.-]+\[(\d+)\]:").unwrap());
+/// Logback and friends: `[main]`, `[pool-1-thread-3]`, `[tokio-runtime-worker]`.
+static BRACKETED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\[((?:main|[\w.-]*(?:thread|worker|pool|exec|runtime)[\w.-]*))\]").unwrap()
+});
+
+/// The thread or process a line came from, if it names one.
+pub fn thread_of(content: &str) -> Option<&str> {
+    if let Some(entry) = logcat::parse(content) {
+        return Some(entry.tid);
+    }
+    [&KEY_VALUE, &SYSLOG_PID, &BRACKETED].iter().find_map(|re| {
+        let caps = re.captures(content).ok()??;
+        caps.get(1).map(|m| m.as_str())
+    })
+}
+
+/// Threads in the order they first logged, one lane each. Lines are only
+/// looked at while the lanes are shown.
+#[derive(Clone, Default)]
+pub struct Lanes {
+    names: Vec<String>,
+    enabled: bool,
+}
+
+impl Lanes {
+    pub fn observe(&mut self, line: &LogLine) {
+        if !self.enabled || line.system || self.names.len() >= MAX_LANES {
+            return;
+        }
+        if let Some(thread) = thread_of(&line.content) {
+            if !self.names.iter().any(|n| n == thread) {
+                self.names.push(thread.to_string());
+            }
+        }
+    }
+
+    /// Lane of the thread `content` names.
+    pub fn lane(&self, content: &str) -> Option<usize> {
+        let thread = thread_of(content)?;
+        self.names.iter().position(|n| n == thread)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.names.clear();
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start or stop looking at lines; when starting, first find the
+    /// threads of the `lines` already there.
+    pub fn set_enabled(&mut self, enabled: bool, lines: &[LogLine]) {
+        if enabled && !self.enabled {
+            self.enabled = true;
+            self.names.clear();
+            for line in lines {
+                if self.names.len() >= MAX_LANES {
+                    break;
+                }
+                self.observe(line);
+            }
+        }
+        self.enabled = enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_lanes_by_thread() {
+        assert_eq!(thread_of("12:00:01 INFO [pool-1-thread-3] c.a.Job - done"), Some("pool-1-thread-3"));
+        assert_eq!(thread_of("Jan 31 14:05:01 host sshd[1234]: Accepted key"), Some("1234"));
+        assert_eq!(thread_of(r#"{"level":"info","thread":"worker-2","msg":"x"}"#), Some("worker-2"));
+        assert_eq!(thread_of("INFO tid=77 [main] starting"), Some("77"));
+        assert_eq!(thread_of("[2024-01-31] INFO plain line"), None);
+
+        let line = |content: &str| LogLine::new(content.to_string(), None, Local::now());
+        let mut lanes = Lanes::default();
+        lanes.observe(&line("a thread=web"));
+        assert_eq!(lanes.len(), 0);
+        lanes.set_enabled(true, &[line("a thread=web")]);
+        for content in ["b thread=db", "c thread=web", "d no thread"] {
+            lanes.observe(&line(content));
+        }
+        assert_eq!(lanes.len(), 2);
+        assert_eq!(lanes.lane("x thread=db"), Some(1));
+        assert_eq!(lanes.lane("x thread=cache"), None);
+    }
+}
@@ -1,70 +1,154 @@
-use crate::netinfo::{get_network_interfaces, InterfaceInfo};
-use std::net::IpAddr;
+use crate::netinfo::{get_network_interfaces, AddressInfo, InterfaceInfo, Reach};
 
 #[derive(Clone, Copy, PartialEq, Default)]
 pub enum ListenDisplayMode {
     #[default]
     AddrPort,
     NcCommand,
+    /// A PowerShell `ForEach-Object` that sends piped lines, for Windows
+    /// peers without `nc`.
+    PowerShell,
+    /// The selected `addr:port` as a QR code, for grabbing with a phone.
+    Qr,
+}
+
+impl ListenDisplayMode {
+    /// The mode selector line, current mode in brackets.
+    pub fn label(self) -> &'static str {
+        match self {
+            ListenDisplayMode::AddrPort => "[addr:port]  nc command   PowerShell   QR ",
+            ListenDisplayMode::NcCommand => " addr:port  [nc command]  PowerShell   QR ",
+            ListenDisplayMode::PowerShell => " addr:port   nc command  [PowerShell]  QR ",
+            ListenDisplayMode::Qr => " addr:port   nc command   PowerShell  [QR]",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct ListenAddrEntry {
-    pub ip: IpAddr,
+    /// Address with its zone ID, if any.
+    pub host: String,
     pub is_v6: bool,
-    #[allow(dead_code)]
-    pub is_self_assigned: bool,
     pub row: u16,
 }
 
+/// How a peer connects to `host` on `port` in the given display mode.
+pub fn format_listen_addr(host: &str, is_v6: bool, port: u16, mode: ListenDisplayMode) -> String {
+    match mode {
+        ListenDisplayMode::AddrPort | ListenDisplayMode::Qr => {
+            if is_v6 {
+                format!("[{}]:{}", host, port)
+            } else {
+                format!("{}:{}", host, port)
+            }
+        }
+        ListenDisplayMode::NcCommand => {
+            if is_v6 {
+                format!("nc -6 {} {}", host, port)
+            } else {
+                format!("nc {} {}", host, port)
+            }
+        }
+        ListenDisplayMode::PowerShell => format!(
+            "% -b{{$w=[IO.StreamWriter]::new([Net.Sockets.TcpClient]::new('{}',{}).GetStream());$w.AutoFlush=1}}{{$w.WriteLine($_)}}",
+            host, port
+        ),
+    }
+}
+
 pub struct ListenState {
-    pub port: Option<u16>,
+    pub ports: Vec<u16>,
+    /// Which of `ports` the listed addresses are shown with.
+    pub port_idx: usize,
     pub has_connection: bool,
+    /// Source tags of the clients connected right now.
+    pub peers: Vec<String>,
     pub network_interfaces: Vec<InterfaceInfo>,
     pub display_mode: ListenDisplayMode,
     pub addr_list: Vec<ListenAddrEntry>,
     pub selected_idx: usize,
     pub popup_area: Option<(u16, u16, u16, u16)>,
+    /// Also list loopback, link-local and self-assigned addresses.
+    pub show_all: bool,
 }
 
 impl ListenState {
-    pub fn new(port: Option<u16>) -> Self {
-        let network_interfaces = if port.is_some() {
-            get_network_interfaces()
-        } else {
+    pub fn new(ports: Vec<u16>) -> Self {
+        let network_interfaces = if ports.is_empty() {
             Vec::new()
+        } else {
+            get_network_interfaces()
         };
         Self {
-            port,
+            ports,
+            port_idx: 0,
             has_connection: false,
+            peers: Vec::new(),
             network_interfaces,
             display_mode: ListenDisplayMode::default(),
             addr_list: Vec::new(),
             selected_idx: 0,
             popup_area: None,
+            show_all: false,
         }
     }
 
     pub fn show_popup(&self) -> bool {
-        self.port.is_some() && !self.has_connection
+        !self.ports.is_empty() && !self.has_connection
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.ports.get(self.port_idx).copied()
+    }
+
+    pub fn next_port(&mut self) {
+        if !self.ports.is_empty() {
+            self.port_idx = (self.port_idx + 1) % self.ports.len();
+        }
+    }
+
+    /// The port selector line, selected port in brackets.
+    pub fn port_label(&self) -> String {
+        self.ports
+            .iter()
+            .enumerate()
+            .map(|(i, port)| if i == self.port_idx { format!("[{}]", port) } else { format!(" {} ", port) })
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     pub fn toggle_display_mode(&mut self) {
         self.display_mode = match self.display_mode {
             ListenDisplayMode::AddrPort => ListenDisplayMode::NcCommand,
-            ListenDisplayMode::NcCommand => ListenDisplayMode::AddrPort,
+            ListenDisplayMode::NcCommand => ListenDisplayMode::PowerShell,
+            ListenDisplayMode::PowerShell => ListenDisplayMode::Qr,
+            ListenDisplayMode::Qr => ListenDisplayMode::AddrPort,
         };
     }
 
+    pub fn toggle_show_all(&mut self) {
+        self.show_all = !self.show_all;
+        self.selected_idx = 0;
+    }
+
+    /// Interfaces with the addresses to list, skipping interfaces left empty.
+    pub fn visible_interfaces(&self) -> Vec<(&InterfaceInfo, Vec<&AddressInfo>)> {
+        self.network_interfaces
+            .iter()
+            .map(|iface| {
+                let addresses: Vec<&AddressInfo> = iface
+                    .addresses
+                    .iter()
+                    .filter(|a| self.show_all || a.reach == Reach::Network)
+                    .collect();
+                (iface, addresses)
+            })
+            .filter(|(_, addresses)| !addresses.is_empty())
+            .collect()
+    }
+
     fn addr_count(&self) -> usize {
-        if !self.addr_list.is_empty() {
-            self.addr_list.len()
-        } else {
-            self.network_interfaces
-                .iter()
-                .map(|iface| iface.addresses.len())
-                .sum()
-        }
+        self.visible_interfaces().iter().map(|(_, addresses)| addresses.len()).sum()
     }
 
     pub fn select_next(&mut self) {
@@ -82,24 +166,9 @@ impl ListenState {
     }
 
     pub fn get_selected_copy_text(&self) -> Option<String> {
-        let port = self.port?;
+        let port = self.port()?;
         let entry = self.addr_list.get(self.selected_idx)?;
-        Some(match self.display_mode {
-            ListenDisplayMode::AddrPort => {
-                if entry.is_v6 {
-                    format!("[{}]:{}", entry.ip, port)
-                } else {
-                    format!("{}:{}", entry.ip, port)
-                }
-            }
-            ListenDisplayMode::NcCommand => {
-                if entry.is_v6 {
-                    format!("nc -6 {} {}", entry.ip, port)
-                } else {
-                    format!("nc {} {}", entry.ip, port)
-                }
-            }
-        })
+        Some(format_listen_addr(&entry.host, entry.is_v6, port, self.display_mode))
     }
 
     pub fn handle_click(&mut self, x: u16, y: u16) -> Option<String> {
@@ -1,9 +1,72 @@
+use crate::core::{markers, BucketSize, FilterState, Lanes, MarkerIndex, Marks, Notes, Outline, Pins, Schema, Snooze, Snoozes};
+use crate::access;
+use crate::dedup::Dedup;
+use crate::enrich::{self, Annotations, Enricher};
+use crate::sample::{self, Sampler, Sampling};
+use crate::filter::{parse_filter, FilterExpr};
+use crate::filter_stats::FilterStat;
+use crate::humanize::Humanize;
+use crate::level::{self, Level};
+use crate::logcat;
+use crate::metrics;
+use crate::oslog;
+use crate::prefix;
+use crate::rewrite::parse_rules;
+use crate::session::SavedNote;
+use crate::state::AppState;
+use crate::timestamp::parse_event_time;
+use crate::table::{Row, Tables};
+use crate::watch::{parse_watches, Watch};
 use chrono::{DateTime, Local};
+use fancy_regex::Regex;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Clone, PartialEq)]
 pub struct LogLine {
+    /// When the line was received.
     pub timestamp: DateTime<Local>,
     pub content: String,
+    /// File name or peer address the line came from.
+    pub source: Option<Arc<str>>,
+    /// Severity stated by the record's format, else guessed from its words,
+    /// as the `level_rules` adjust it.
+    pub level: Option<Level>,
+    /// When the event happened, parsed from the line.
+    pub event_time: Option<DateTime<Local>>,
+    /// A marker the viewer inserted, like a client connecting; shown
+    /// regardless of filters and never highlighted.
+    pub system: bool,
+    /// What `:enrich` found about the line's IP addresses.
+    pub enrichment: Option<Annotations>,
+}
+
+impl LogLine {
+    pub fn new(content: String, source: Option<Arc<str>>, timestamp: DateTime<Local>) -> Self {
+        Self {
+            timestamp,
+            level: level::classify(&content),
+            event_time: parse_event_time(&content),
+            content,
+            source,
+            system: false,
+            enrichment: None,
+        }
+    }
+
+    pub fn system(content: String, timestamp: DateTime<Local>) -> Self {
+        Self {
+            timestamp,
+            content,
+            source: None,
+            level: None,
+            event_time: None,
+            system: true,
+            enrichment: None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -15,6 +78,13 @@ pub enum TimeAge {
     Days,
 }
 
+/// Receipt time for a line arriving now: never earlier than the last line's,
+/// so a clock step backwards can't reorder the buffer.
+pub fn receipt_time(lines: &[LogLine]) -> DateTime<Local> {
+    let now = Local::now();
+    lines.last().map_or(now, |last| now.max(last.timestamp))
+}
+
 pub fn format_relative_time(timestamp: DateTime<Local>) -> String {
     let now = Local::now();
     let duration = now.signed_duration_since(timestamp);
@@ -53,12 +123,57 @@ pub fn get_time_age(timestamp: DateTime<Local>) -> TimeAge {
     }
 }
 
+/// The buffer and everything derived from it, shared by the TUI and the GUI:
+/// lines go in through `ingest()`, settings through the `set_*` methods, and
+/// `filtered_indices`, `markers`, `filter_stats` and the watches stay in step.
+/// The UIs keep only their own view state on top.
 #[derive(Clone)]
 pub struct LogState {
     pub lines: Vec<LogLine>,
     pub filtered_indices: Vec<usize>,
+    pub filter_state: FilterState,
+    pub watches: Vec<Watch>,
+    pub notes: Notes,
+    pub pins: Pins,
+    /// Lines marked for bulk actions (TUI).
+    pub marks: Marks,
+    /// Hides lines nearly identical to a recently shown one, when on.
+    pub dedup: Option<Dedup>,
+    /// Thins firehose sources before lines reach the buffer.
+    pub sampler: Sampler<LogLine>,
+    /// Kinds of messages hidden for a while on top of the filter.
+    pub snoozes: Snoozes,
+    pub saved_filters: BTreeMap<String, String>,
+    /// Match counts of the active filter, then each saved one.
+    pub filter_stats: Vec<FilterStat>,
+    /// Errors, highlight matches and notes by filtered position.
+    pub markers: MarkerIndex,
+    /// Lines the filter, snoozes or dedup hide that match the highlight.
+    pub hidden_matches: usize,
+    /// Shown lines and errors per minute or hour.
+    pub outline: Outline,
+    /// Threads and processes seen, for the lane view.
+    pub lanes: Lanes,
+    /// Fields seen in the lines, for the fields panel.
+    pub schema: Schema,
+    /// Header rows of sources that are CSV or TSV.
+    pub tables: Tables,
+    /// Source of `filter_state.filter_expr`, counted in `filter_stats`.
+    filter_text: String,
+    /// Host names `filter_state.enrich` had found when the lines were last
+    /// looked up.
+    enrich_answers: Option<usize>,
     pub bottom_line_idx: usize,
     pub follow_tail: bool,
+    /// Where the view was held while following the tail, so releasing it
+    /// can go back to following.
+    held: Option<usize>,
+    /// Keep the latest line matching this expression at the top of the view
+    /// instead of following the tail.
+    pub follow_match: Option<FilterExpr>,
+    /// Position in `filtered_indices` of the latest line matching
+    /// `follow_match`.
+    pub match_pos: Option<usize>,
     pub last_update_time: Option<DateTime<Local>>,
 }
 
@@ -67,40 +182,585 @@ impl Default for LogState {
         Self {
             lines: Vec::new(),
             filtered_indices: Vec::new(),
+            filter_state: FilterState::default(),
+            watches: Vec::new(),
+            notes: Notes::default(),
+            pins: Pins::default(),
+            marks: Marks::default(),
+            dedup: None,
+            sampler: Sampler::new(sample::sampling()),
+            snoozes: Snoozes::default(),
+            saved_filters: BTreeMap::new(),
+            filter_stats: Vec::new(),
+            markers: MarkerIndex::default(),
+            hidden_matches: 0,
+            outline: Outline::default(),
+            lanes: Lanes::default(),
+            schema: Schema::default(),
+            tables: Tables::default(),
+            filter_text: String::new(),
+            enrich_answers: None,
             bottom_line_idx: 0,
             follow_tail: true,
+            held: None,
+            follow_match: None,
+            match_pos: None,
             last_update_time: None,
         }
     }
 }
 
 impl LogState {
-    pub fn add_line(&mut self, content: String) -> usize {
-        self.add_line_with_update(content, true)
+    /// An empty buffer with the saved settings that don't need parsing; the
+    /// UI applies the rest through the `set_*` methods so it can show errors.
+    pub fn from_state(state: &AppState, notes: Vec<SavedNote>) -> Self {
+        Self {
+            filter_state: FilterState {
+                highlight: state.highlight_settings(),
+                humanize: state.humanize.as_deref().and_then(|spec| spec.parse().ok()),
+                ..FilterState::default()
+            },
+            notes: Notes::from_saved(notes),
+            dedup: state.dedup_window_secs.map(Dedup::new),
+            saved_filters: state.saved_filters.clone(),
+            ..Self::default()
+        }
     }
 
-    pub fn add_line_with_update(&mut self, content: String, update_time: bool) -> usize {
-        let now = Local::now();
-        let line = LogLine {
-            timestamp: now,
-            content,
-        };
+    /// The text shown for a line, falling back to the raw content when the
+    /// hide regex fails on it.
+    pub fn display_content(&self, line: &LogLine) -> String {
+        self.display(line).unwrap_or_else(|_| line.content.clone())
+    }
+
+    /// The text shown for a line: a CSV/TSV row, access log request, logcat
+    /// entry or unified log event with its named fields unless raw display is on, then
+    /// `FilterState::display()`.
+    pub fn display(&self, line: &LogLine) -> Result<String, String> {
+        let text = self.filter_state.display(&self.structured(line))?;
+        Ok(self.enriched(line, text))
+    }
+
+    /// `text` with the line's GeoIP and host name fields after its
+    /// addresses, while `:enrich` is on.
+    pub fn enriched(&self, line: &LogLine, text: String) -> String {
+        match (&self.filter_state.enrich, &line.enrichment) {
+            (Some(_), Some(annotations)) if !self.filter_state.show_raw => enrich::annotate(&text, annotations),
+            _ => text,
+        }
+    }
+
+    /// The line before `FilterState::display()`: its named fields when it is
+    /// a known format and raw display is off, otherwise its content.
+    pub fn structured<'a>(&self, line: &'a LogLine) -> Cow<'a, str> {
+        if line.system || self.filter_state.show_raw {
+            return Cow::Borrowed(&line.content);
+        }
+        if let Some(table) = self.tables.get(&line.source) {
+            return Cow::Owned(table.display(&line.content));
+        }
+        if let Some(request) = access::parse(&line.content).filter(|_| access::enabled()) {
+            return Cow::Owned(request.fields());
+        }
+        if let Some(entry) = logcat::parse(&line.content).filter(|_| logcat::enabled()) {
+            return Cow::Owned(entry.fields());
+        }
+        match oslog::parse(&line.content).filter(|_| oslog::enabled()) {
+            Some(entry) => Cow::Owned(entry.fields()),
+            None => Cow::Borrowed(&line.content),
+        }
+    }
+
+    /// Take a line from a source. Returns whether it is shown.
+    pub fn ingest(&mut self, content: String, source: Option<Arc<str>>) -> bool {
+        let now = receipt_time(&self.lines);
+        let content = prefix::strip(content, source.as_deref());
+        match self.tables.observe(&content, &source) {
+            Row::Header(table) => {
+                let header = format!("── columns: {} ──", table.headers.join(", "));
+                return self.push(LogLine::system(header, now));
+            }
+            Row::Confirmed(table) => {
+                let header = format!("── columns: {} ──", table.headers.join(", "));
+                self.push(LogLine::system(header, now));
+            }
+            Row::Plain => {}
+        }
+        for watch in &mut self.watches {
+            watch.observe(&content);
+        }
+        let line = LogLine::new(content, source, now);
+        metrics::record_line(&line);
+        self.last_update_time = Some(now);
+        let dropped = self.sampler.dropped;
+        let kept = self.sampler.offer(line, now.timestamp());
+        metrics::record_sampled_out(self.sampler.dropped - dropped);
+        self.push_all(kept)
+    }
+
+    /// Push every line, returning whether any is shown.
+    fn push_all(&mut self, lines: Vec<LogLine>) -> bool {
+        let mut shown = false;
+        for line in lines {
+            shown |= self.push(line);
+        }
+        shown
+    }
+
+    /// Let in the per-second sample of a second that is over; true when any
+    /// of it is shown.
+    pub fn flush_sample(&mut self) -> bool {
+        let kept = self.sampler.flush(Local::now().timestamp());
+        self.push_all(kept)
+    }
+
+    /// Sample incoming lines from now on, letting in what the old sampler
+    /// still held.
+    pub fn set_sampling(&mut self, mode: Sampling) {
+        let held = self.sampler.flush(i64::MAX);
+        self.sampler = Sampler::new(mode);
+        self.push_all(held);
+    }
+
+    pub fn ingest_system(&mut self, content: String) -> bool {
+        self.push(LogLine::system(content, receipt_time(&self.lines)))
+    }
+
+    /// Append lines read back from a bundle.
+    pub fn load(&mut self, lines: Vec<LogLine>) {
+        for line in lines {
+            for watch in &mut self.watches {
+                watch.observe(&line.content);
+            }
+            self.push(line);
+        }
+    }
+
+    fn push(&mut self, mut line: LogLine) -> bool {
         let idx = self.lines.len();
+        if let Some(enrich) = self.filter_state.enrich.as_ref().filter(|_| !line.system) {
+            line.enrichment = enrich.annotations(&line.content);
+        }
+        self.notes.attach(idx, &line.content);
+        self.pins.attach(idx, &line.content);
+        self.lanes.observe(&line);
+        if !line.system {
+            self.schema.observe(&line.content);
+        }
         self.lines.push(line);
-        if update_time {
-            self.last_update_time = Some(now);
+        self.observe_filter_stats(idx);
+        if !(self.matches_filter(idx) && self.admit(idx)) {
+            if self.matches_highlight(idx) {
+                self.hidden_matches += 1;
+            }
+            return false;
+        }
+        self.filtered_indices.push(idx);
+        let pos = self.filtered_indices.len() - 1;
+        self.markers.push(pos, self.marker_kinds(idx));
+        self.outline.observe(pos, &self.lines[idx]);
+        if self.matches_follow(idx) {
+            self.match_pos = Some(pos);
+        }
+        true
+    }
+
+    fn matches_filter(&self, idx: usize) -> bool {
+        let line = &self.lines[idx];
+        if line.system {
+            return true;
+        }
+        let content = self.display_content(line);
+        self.filter_state.matches_filter(&content) && !self.snoozes.hides(&content)
+    }
+
+    /// Run a filtered line past the near-duplicate check, if it is on.
+    fn admit(&mut self, idx: usize) -> bool {
+        let line = &self.lines[idx];
+        if line.system {
+            return true;
+        }
+        let content = self.display_content(line);
+        let time = line.timestamp;
+        self.dedup.as_mut().is_none_or(|dedup| dedup.admit(idx, &content, time))
+    }
+
+    fn matches_follow(&self, idx: usize) -> bool {
+        let line = &self.lines[idx];
+        match &self.follow_match {
+            Some(expr) if !line.system => expr.matches(&self.display_content(line)),
+            _ => false,
+        }
+    }
+
+    fn find_follow_match(&mut self) {
+        self.match_pos = (0..self.filtered_indices.len())
+            .rev()
+            .find(|&pos| self.matches_follow(self.filtered_indices[pos]));
+    }
+
+    /// Rerun the filter, dedup, statistics and markers over the whole buffer
+    /// after a setting changed.
+    pub fn refilter(&mut self) {
+        let anchor = (!self.follow_tail)
+            .then(|| self.filtered_indices.get(self.bottom_line_idx).copied())
+            .flatten();
+        self.filtered_indices.clear();
+        if let Some(dedup) = &mut self.dedup {
+            dedup.reset();
+        }
+        for idx in 0..self.lines.len() {
+            if self.matches_filter(idx) && self.admit(idx) {
+                self.filtered_indices.push(idx);
+            }
+        }
+        // Keep the line that was at the bottom of the view there, or the last
+        // one before it that is still shown.
+        self.bottom_line_idx = anchor
+            .map_or(0, |anchor| self.filtered_indices.partition_point(|&idx| idx <= anchor).saturating_sub(1));
+        if self.held.is_some() {
+            self.held = Some(self.bottom_line_idx);
         }
-        idx
+        self.find_follow_match();
+        self.rebuild_filter_stats();
+        self.rebuild_markers();
+        self.count_hidden_matches();
+        self.rebuild_outline();
+    }
+
+    pub fn set_outline_size(&mut self, size: BucketSize) {
+        self.outline.size = size;
+        self.rebuild_outline();
+    }
+
+    fn rebuild_outline(&mut self) {
+        self.outline.clear();
+        for (pos, &idx) in self.filtered_indices.iter().enumerate() {
+            self.outline.observe(pos, &self.lines[idx]);
+        }
+    }
+
+    pub fn set_hide(&mut self, text: &str) -> Result<(), String> {
+        self.filter_state.hide_regex = match text.trim() {
+            "" => None,
+            _ => Some(Regex::new(text).map_err(|e| e.to_string())?),
+        };
+        self.refilter();
+        Ok(())
+    }
+
+    pub fn set_filter(&mut self, text: &str) -> Result<(), String> {
+        self.filter_state.filter_expr = match text.trim() {
+            "" => None,
+            _ => Some(parse_filter(text).map_err(|e| e.to_string())?),
+        };
+        self.filter_text = text.trim().to_string();
+        self.refilter();
+        Ok(())
+    }
+
+    /// Highlighting doesn't change what is shown, only the match markers.
+    pub fn set_highlight(&mut self, text: &str) -> Result<(), String> {
+        self.filter_state.highlight_expr = match text.trim() {
+            "" => None,
+            _ => Some(parse_filter(text).map_err(|e| e.to_string())?),
+        };
+        self.rebuild_markers();
+        self.count_hidden_matches();
+        Ok(())
+    }
+
+    pub fn set_rewrite(&mut self, text: &str) -> Result<(), String> {
+        self.filter_state.rewrite_rules = parse_rules(text).map_err(|e| e.to_string())?;
+        self.refilter();
+        Ok(())
+    }
+
+    /// Replace the watches and replay every received line through them.
+    pub fn set_watches(&mut self, text: &str) -> Result<(), String> {
+        let mut watches = parse_watches(text).map_err(|e| e.to_string())?;
+        for line in &self.lines {
+            for watch in &mut watches {
+                watch.observe(&line.content);
+            }
+        }
+        self.watches = watches;
+        Ok(())
+    }
+
+    /// Take over another buffer's view settings (filters, snoozes, dedup,
+    /// watches) and rerun them over this buffer's lines.
+    pub fn adopt_settings(&mut self, other: &LogState) {
+        self.filter_state = other.filter_state.clone();
+        self.filter_text = other.filter_text.clone();
+        self.saved_filters = other.saved_filters.clone();
+        self.snoozes = other.snoozes.clone();
+        self.dedup = other.dedup.as_ref().map(|d| Dedup::new(d.window_secs));
+        self.follow_match = other.follow_match.clone();
+        self.watches = other.watches.clone();
+        for watch in &mut self.watches {
+            watch.reset();
+            for line in &self.lines {
+                watch.observe(&line.content);
+            }
+        }
+        self.set_lanes(other.lanes.enabled());
+        self.enrich_lines();
+        self.refilter();
+    }
+
+    /// Show or hide the thread lanes, finding the threads seen so far when
+    /// they are turned on.
+    pub fn set_lanes(&mut self, shown: bool) {
+        self.lanes.set_enabled(shown, &self.lines);
+    }
+
+    /// Remove the lines `take` picks and return them, with each old index's
+    /// new one (`None` for the lines taken). The rest keep their notes, pins
+    /// and marks, and the settings and sampler stay as they were.
+    pub fn take_lines(&mut self, take: impl Fn(usize) -> bool) -> (Vec<LogLine>, Vec<Option<usize>>) {
+        let mut new_idx = Vec::with_capacity(self.lines.len());
+        let mut kept_count = 0;
+        let (taken, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.lines)
+            .into_iter()
+            .enumerate()
+            .partition(|(idx, _)| {
+                let taken = take(*idx);
+                new_idx.push((!taken).then_some(kept_count));
+                kept_count += usize::from(!taken);
+                taken
+            });
+        let mut notes = std::mem::take(&mut self.notes);
+        let mut pins = std::mem::take(&mut self.pins);
+        let mut marks = std::mem::take(&mut self.marks);
+        let mode = self.sampler.mode;
+        let sampler = std::mem::replace(&mut self.sampler, Sampler::new(mode));
+        self.reset();
+        self.load(kept.into_iter().map(|(_, line)| line).collect());
+        notes.remap(&new_idx);
+        pins.remap(&new_idx);
+        marks.remap(&new_idx);
+        (self.notes, self.pins, self.marks, self.sampler) = (notes, pins, marks, sampler);
+        (taken.into_iter().map(|(_, line)| line).collect(), new_idx)
     }
 
-    pub fn clear(&mut self) {
+    pub fn set_dedup(&mut self, dedup: Option<Dedup>) {
+        self.dedup = dedup;
+        self.refilter();
+    }
+
+    pub fn snooze(&mut self, snooze: Snooze) {
+        self.snoozes.list.push(snooze);
+        self.refilter();
+    }
+
+    pub fn unsnooze(&mut self, idx: usize) {
+        if idx < self.snoozes.list.len() {
+            self.snoozes.list.remove(idx);
+            self.refilter();
+        }
+    }
+
+    /// Wake the snoozes whose time is up; true when any did.
+    pub fn expire_snoozes(&mut self) -> bool {
+        let expired = self.snoozes.expire(Instant::now());
+        if expired {
+            self.refilter();
+        }
+        expired
+    }
+
+    pub fn set_humanize(&mut self, humanize: Option<Humanize>) {
+        self.filter_state.humanize = humanize;
+        self.refilter();
+    }
+
+    pub fn toggle_raw(&mut self) {
+        self.filter_state.show_raw = !self.filter_state.show_raw;
+        self.refilter();
+    }
+
+    pub fn set_enrich(&mut self, enrich: Option<Arc<Enricher>>) {
+        self.filter_state.enrich = enrich;
+        self.enrich_lines();
+        self.refilter();
+    }
+
+    /// Look the lines' addresses up again once reverse DNS has found more
+    /// host names, so filters see them. True when it did.
+    pub fn refresh_enrichment(&mut self) -> bool {
+        if !self.enrichment_stale() {
+            return false;
+        }
+        self.enrich_lines();
+        self.refilter();
+        true
+    }
+
+    /// Whether reverse DNS has found host names since the lines were last
+    /// looked up.
+    pub fn enrichment_stale(&self) -> bool {
+        let answers = self.filter_state.enrich.as_ref().map(|enrich| enrich.answers());
+        answers.is_some() && answers != self.enrich_answers
+    }
+
+    fn enrich_lines(&mut self) {
+        match &self.filter_state.enrich {
+            Some(enrich) => {
+                self.enrich_answers = Some(enrich.answers());
+                enrich.annotate_lines(&mut self.lines);
+            }
+            None => {
+                self.enrich_answers = None;
+                for line in &mut self.lines {
+                    line.enrichment = None;
+                }
+            }
+        }
+    }
+
+    pub fn toggle_epochs(&mut self) {
+        self.filter_state.show_epochs = !self.filter_state.show_epochs;
+        self.refilter();
+    }
+
+    /// Recount the active and saved filters over the whole buffer.
+    pub fn rebuild_filter_stats(&mut self) {
+        let active = Some(&self.filter_text)
+            .filter(|text| !text.is_empty())
+            .map(|text| (None, text));
+        let saved = self.saved_filters.iter().map(|(name, text)| (Some(name.clone()), text));
+        self.filter_stats = active
+            .into_iter()
+            .chain(saved)
+            .filter_map(|(name, text)| FilterStat::new(name, text).ok())
+            .collect();
+        for idx in 0..self.lines.len() {
+            self.observe_filter_stats(idx);
+        }
+    }
+
+    fn observe_filter_stats(&mut self, idx: usize) {
+        let line = &self.lines[idx];
+        if line.system || self.filter_stats.is_empty() {
+            return;
+        }
+        let content = self.display_content(line);
+        let time = line.timestamp;
+        for stat in &mut self.filter_stats {
+            stat.observe(&content, time);
+        }
+    }
+
+    /// Keep `text` as a saved filter under `name`.
+    pub fn save_filter(&mut self, name: &str, text: String) {
+        self.saved_filters.insert(name.to_string(), text);
+        self.rebuild_filter_stats();
+    }
+
+    /// Returns whether a saved filter by that name existed.
+    pub fn drop_filter(&mut self, name: &str) -> bool {
+        self.filter_stats.retain(|stat| stat.name.as_deref() != Some(name));
+        self.saved_filters.remove(name).is_some()
+    }
+
+    fn marker_kinds(&self, idx: usize) -> u8 {
+        let line = &self.lines[idx];
+        if line.system {
+            return 0;
+        }
+        let mut kinds = 0;
+        if line.level == Some(Level::Error) {
+            kinds |= markers::MARK_ERROR;
+        }
+        if self.notes.get(idx).is_some() {
+            kinds |= markers::MARK_NOTE;
+        }
+        if self.matches_highlight(idx) {
+            kinds |= markers::MARK_MATCH;
+        }
+        kinds
+    }
+
+    fn matches_highlight(&self, idx: usize) -> bool {
+        let line = &self.lines[idx];
+        match &self.filter_state.highlight_expr {
+            Some(expr) if !line.system => expr.matches(&self.display_content(line)),
+            _ => false,
+        }
+    }
+
+    fn count_hidden_matches(&mut self) {
+        let mut shown = self.filtered_indices.iter().peekable();
+        let hidden = (0..self.lines.len())
+            .filter(|idx| shown.next_if_eq(&idx).is_none() && self.matches_highlight(*idx))
+            .count();
+        self.hidden_matches = hidden;
+    }
+
+    /// Recompute the minimap markers, e.g. after a note was added.
+    pub fn rebuild_markers(&mut self) {
+        self.markers.clear();
+        for pos in 0..self.filtered_indices.len() {
+            let kinds = self.marker_kinds(self.filtered_indices[pos]);
+            self.markers.push(pos, kinds);
+        }
+    }
+
+    /// Drop every line, returning how many there were.
+    pub fn clear(&mut self) -> usize {
+        let dropped = self.lines.len();
+        metrics::record_dropped(dropped);
+        self.reset();
+        dropped
+    }
+
+    /// Empty the buffer, keeping the settings.
+    fn reset(&mut self) {
         self.lines.clear();
         self.filtered_indices.clear();
+        self.markers.clear();
+        self.hidden_matches = 0;
+        self.outline.clear();
+        self.lanes.clear();
+        self.schema.clear();
+        self.sampler = Sampler::new(self.sampler.mode);
+        if let Some(dedup) = &mut self.dedup {
+            dedup.reset();
+        }
+        for watch in &mut self.watches {
+            watch.reset();
+        }
+        for stat in &mut self.filter_stats {
+            stat.reset();
+        }
+        metrics::record_buffer(0, &self.watches);
+        self.notes.clear();
+        self.pins.clear();
+        self.marks.clear();
         self.bottom_line_idx = 0;
+        self.held = None;
+        self.match_pos = None;
         self.last_update_time = None;
     }
 
+    pub fn start_follow_match(&mut self, expr: FilterExpr) {
+        self.follow_match = Some(expr);
+        self.follow_tail = false;
+        self.find_follow_match();
+    }
+
+    /// Stop following a match, leaving the view where it is.
+    pub fn stop_follow_match(&mut self) {
+        if self.follow_match.is_some() {
+            self.bottom_line_idx = self.get_bottom_line_idx();
+            self.follow_match = None;
+            self.match_pos = None;
+        }
+    }
+
     pub fn scroll_up(&mut self, amount: usize) {
+        self.stop_follow_match();
         if self.follow_tail {
             self.bottom_line_idx = self.filtered_indices.len().saturating_sub(1);
         }
@@ -109,6 +769,7 @@ impl LogState {
     }
 
     pub fn scroll_down(&mut self, amount: usize) {
+        self.stop_follow_match();
         let max_idx = self.filtered_indices.len().saturating_sub(1);
         if self.follow_tail {
             return;
@@ -120,17 +781,57 @@ impl LogState {
     }
 
     pub fn scroll_to_start(&mut self) {
+        self.stop_follow_match();
         self.bottom_line_idx = 0;
         self.follow_tail = false;
     }
 
+    /// Show filtered position `pos` at the bottom of the view.
+    pub fn scroll_to(&mut self, pos: usize) {
+        self.stop_follow_match();
+        let max_idx = self.filtered_indices.len().saturating_sub(1);
+        self.bottom_line_idx = pos.min(max_idx);
+        self.follow_tail = self.bottom_line_idx >= max_idx;
+    }
+
     pub fn scroll_to_end(&mut self) {
+        self.stop_follow_match();
         self.follow_tail = true;
         self.bottom_line_idx = self.filtered_indices.len().saturating_sub(1);
     }
 
+    /// Hold the view still while a source reads its history, then follow
+    /// the tail once it goes live.
+    pub fn backfill(&mut self, started: bool) {
+        if started {
+            self.follow_tail = false;
+        } else {
+            self.scroll_to_end();
+        }
+    }
+
+    /// Stop following the tail while the user reads or selects, without
+    /// counting as scrolling away from it.
+    pub fn hold_view(&mut self) {
+        if self.follow_tail && self.held.is_none() {
+            self.bottom_line_idx = self.filtered_indices.len().saturating_sub(1);
+            self.follow_tail = false;
+            self.held = Some(self.bottom_line_idx);
+        }
+    }
+
+    /// Follow the tail again after `hold_view`, unless the view moved in the
+    /// meantime. Returns whether it did.
+    pub fn release_view(&mut self) -> bool {
+        let resume = self.held.take().is_some_and(|pos| !self.follow_tail && self.bottom_line_idx == pos);
+        if resume {
+            self.scroll_to_end();
+        }
+        resume
+    }
+
     pub fn get_bottom_line_idx(&self) -> usize {
-        if self.follow_tail {
+        if self.follow_tail || (self.follow_match.is_some() && self.match_pos.is_none()) {
             self.filtered_indices.len().saturating_sub(1)
         } else {
             self.bottom_line_idx
@@ -138,3 +839,86 @@ impl LogState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_filter;
+
+    #[test]
+    fn test_take_lines_keeps_notes_pins_and_marks() {
+        let mut state = LogState::default();
+        for i in 0..6 {
+            state.ingest(format!("line {}", i), None);
+        }
+        state.notes.set(4, "look here");
+        assert_eq!(state.pins.toggle(5), Ok(true));
+        state.marks.toggle(1);
+        state.marks.toggle(3);
+        let (taken, new_idx) = state.take_lines(|idx| idx == 1 || idx == 2);
+        assert_eq!(taken.len(), 2);
+        assert_eq!(new_idx[4], Some(2));
+        assert_eq!(state.lines[2].content, "line 4");
+        assert_eq!(state.notes.get(2), Some("look here"));
+        assert_eq!(state.pins.lines(), [3]);
+        assert_eq!(state.marks.lines().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_scrolling_stops_follow_match() {
+        let mut state = LogState::default();
+        for i in 0..10 {
+            state.ingest(format!("line {}", i), None);
+        }
+        state.start_follow_match(parse_filter("\"REQUEST START\"").unwrap());
+        assert_eq!(state.get_bottom_line_idx(), 9);
+        state.match_pos = Some(4);
+        state.bottom_line_idx = 6;
+        state.scroll_up(1);
+        assert!(state.follow_match.is_none());
+        assert!(!state.follow_tail);
+        assert_eq!(state.get_bottom_line_idx(), 5);
+    }
+
+    #[test]
+    fn test_reading_lock_keeps_the_view() {
+        let mut state = LogState::default();
+        for i in 0..10 {
+            state.ingest(format!("{} line {}", if i % 2 == 0 { "even" } else { "odd" }, i), None);
+        }
+        state.scroll_to(6);
+        state.set_filter("\"odd\"").unwrap();
+        assert_eq!(state.filtered_indices[state.get_bottom_line_idx()], 5);
+        state.set_filter("").unwrap();
+        assert_eq!(state.get_bottom_line_idx(), 5);
+
+        state.scroll_to_end();
+        state.hold_view();
+        state.ingest("even line 10".to_string(), None);
+        assert_eq!(state.get_bottom_line_idx(), 9);
+        assert!(state.release_view());
+        assert_eq!(state.get_bottom_line_idx(), 10);
+
+        state.hold_view();
+        state.scroll_up(3);
+        assert!(!state.release_view());
+        assert!(!state.follow_tail);
+    }
+
+    #[test]
+    fn test_counts_highlight_matches_on_hidden_lines() {
+        let mut state = LogState::default();
+        state.set_highlight("\"boom\"").unwrap();
+        state.set_filter("\"INFO\"").unwrap();
+        state.ingest("INFO boom".to_string(), None);
+        state.ingest("ERROR boom".to_string(), None);
+        state.ingest("ERROR quiet".to_string(), None);
+        assert_eq!(state.hidden_matches, 1);
+        state.set_filter("").unwrap();
+        assert_eq!(state.hidden_matches, 0);
+        state.set_filter("\"quiet\"").unwrap();
+        assert_eq!(state.hidden_matches, 2);
+        state.set_highlight("").unwrap();
+        assert_eq!(state.hidden_matches, 0);
+    }
+}
@@ -0,0 +1,73 @@
+/// Kinds of minimap marker, as bits so a line can carry several.
+pub const MARK_ERROR: u8 = 1;
+pub const MARK_MATCH: u8 = 2;
+pub const MARK_NOTE: u8 = 4;
+
+/// Marked positions in the filtered lines, kept sparse so folding them into
+/// minimap rows costs only as much as there are markers.
+#[derive(Clone, Default)]
+pub struct MarkerIndex {
+    /// Filtered positions with at least one marker, ascending.
+    marks: Vec<(usize, u8)>,
+}
+
+impl MarkerIndex {
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Record the markers of the line at filtered position `pos`, which must
+    /// be past every position pushed so far.
+    pub fn push(&mut self, pos: usize, kinds: u8) {
+        if kinds != 0 {
+            self.marks.push((pos, kinds));
+        }
+    }
+
+    /// Markers of `total` positions folded into `rows` rows.
+    pub fn rows(&self, total: usize, rows: usize) -> Vec<u8> {
+        let mut out = vec![0; rows];
+        if total == 0 || rows == 0 {
+            return out;
+        }
+        for &(pos, kinds) in self.marks.iter().take_while(|(pos, _)| *pos < total) {
+            out[row_of(pos, total, rows)] |= kinds;
+        }
+        out
+    }
+}
+
+/// Minimap row showing filtered position `pos`.
+pub fn row_of(pos: usize, total: usize, rows: usize) -> usize {
+    (pos * rows / total.max(1)).min(rows.saturating_sub(1))
+}
+
+/// Filtered position in the middle of minimap row `row`.
+pub fn position_at(row: usize, total: usize, rows: usize) -> usize {
+    ((2 * row + 1) * total / (2 * rows.max(1))).min(total.saturating_sub(1))
+}
+
+/// The marker a row is drawn with: errors over notes over matches.
+pub fn dominant(kinds: u8) -> Option<u8> {
+    [MARK_ERROR, MARK_NOTE, MARK_MATCH].into_iter().find(|&k| kinds & k != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_fold_positions() {
+        let mut index = MarkerIndex::default();
+        index.push(0, MARK_MATCH);
+        index.push(1, MARK_ERROR);
+        index.push(50, 0);
+        index.push(99, MARK_NOTE);
+        let rows = index.rows(100, 10);
+        assert_eq!(rows[0], MARK_MATCH | MARK_ERROR);
+        assert_eq!(dominant(rows[0]), Some(MARK_ERROR));
+        assert_eq!(rows[5], 0);
+        assert_eq!(rows[9], MARK_NOTE);
+        assert_eq!(position_at(9, 100, 10), 95);
+    }
+}
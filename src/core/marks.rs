@@ -0,0 +1,84 @@
+use crate::core::snooze::template;
+use crate::filter::template_pattern;
+use std::collections::{BTreeSet, HashSet};
+
+/// Lines marked one by one for bulk actions, by buffer index.
+#[derive(Clone, Debug, Default)]
+pub struct Marks {
+    lines: BTreeSet<usize>,
+}
+
+impl Marks {
+    /// Mark or unmark a line; returns whether it is marked now.
+    pub fn toggle(&mut self, line_idx: usize) -> bool {
+        if !self.lines.remove(&line_idx) {
+            self.lines.insert(line_idx);
+            return true;
+        }
+        false
+    }
+
+    pub fn contains(&self, line_idx: usize) -> bool {
+        self.lines.contains(&line_idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Marked lines in buffer order.
+    pub fn lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.iter().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Move marks to the lines' new indices (`new_idx[old]`), dropping those
+    /// of lines gone.
+    pub fn remap(&mut self, new_idx: &[Option<usize>]) {
+        self.lines = self.lines.iter().filter_map(|&idx| new_idx.get(idx).copied().flatten()).collect();
+    }
+}
+
+/// A filter expression matching lines shaped like any of `contents`, with
+/// the words holding digits free (see `template()`).
+pub fn template_filter<'a>(contents: impl IntoIterator<Item = &'a str>) -> String {
+    let mut seen = HashSet::new();
+    contents
+        .into_iter()
+        .map(template)
+        .filter(|shape| !shape.is_empty() && seen.insert(shape.clone()))
+        .map(|shape| template_pattern(&shape))
+        .collect::<Vec<_>>()
+        .join(" || ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_filter;
+
+    #[test]
+    fn test_marks_and_template_filter() {
+        let mut marks = Marks::default();
+        assert!(marks.toggle(7));
+        assert!(marks.toggle(2));
+        assert!(!marks.toggle(7));
+        assert!(marks.toggle(9));
+        assert_eq!(marks.lines().collect::<Vec<_>>(), [2, 9]);
+
+        let text = template_filter(["retry 3 of 5 (job-17)", "retry 4 of 5 (job-2)", "cache miss"]);
+        let expr = parse_filter(&text).unwrap();
+        assert!(expr.matches("retry 1 of 9 (job-80)"));
+        assert!(expr.matches("cache  miss"));
+        assert!(!expr.matches("retry now of 5 (job-1)"));
+        assert!(!expr.matches("cache hit"));
+        assert_eq!(text.matches("||").count(), 1);
+    }
+}
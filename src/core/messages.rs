@@ -0,0 +1,44 @@
+use crate::core::Severity;
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+/// Oldest messages are forgotten past this many.
+const MAX_MESSAGES: usize = 1000;
+
+pub struct Message {
+    pub time: DateTime<Local>,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Internal warnings and errors, kept for `:messages` after their toast is
+/// gone.
+#[derive(Default)]
+pub struct Messages {
+    list: VecDeque<Message>,
+}
+
+impl Messages {
+    pub fn push(&mut self, severity: Severity, text: String) {
+        if self.list.len() >= MAX_MESSAGES {
+            self.list.pop_front();
+        }
+        self.list.push_back(Message {
+            time: Local::now(),
+            severity,
+            text,
+        });
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> + ExactSizeIterator {
+        self.list.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+}
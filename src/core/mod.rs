@@ -1,9 +1,41 @@
+pub mod buffers;
+pub mod detail_state;
+pub mod entity_menu;
 pub mod filter_state;
+pub mod lanes;
 pub mod input_state;
 pub mod listen_state;
 pub mod log_state;
+pub mod markers;
+pub mod marks;
+pub mod messages;
+pub mod notes;
+pub mod outline;
+pub mod pins;
+pub mod playground;
+pub mod schema;
+pub mod selection;
+pub mod snooze;
+pub mod toasts;
+pub mod why;
 
+pub use buffers::Buffers;
+pub use detail_state::DetailState;
+pub use entity_menu::EntityMenu;
 pub use filter_state::FilterState;
 pub use input_state::{InputFields, InputMode};
-pub use listen_state::{ListenAddrEntry, ListenDisplayMode, ListenState};
+pub use lanes::{thread_of, Lanes, MAX_LANES};
+pub use listen_state::{format_listen_addr, ListenAddrEntry, ListenDisplayMode, ListenState};
 pub use log_state::{format_relative_time, get_time_age, LogLine, LogState, TimeAge};
+pub use markers::MarkerIndex;
+pub use marks::Marks;
+pub use messages::Messages;
+pub use notes::{NoteEditor, Notes};
+pub use outline::{BucketSize, Outline};
+pub use pins::Pins;
+pub use playground::{Playground, PlaygroundTarget};
+pub use schema::Schema;
+pub use selection::Selection;
+pub use snooze::{Snooze, Snoozes};
+pub use toasts::{Severity, Toasts};
+pub use why::Why;
@@ -0,0 +1,138 @@
+use crate::core::LogLine;
+use crate::input::TextInput;
+use crate::session::SavedNote;
+use std::collections::{BTreeMap, HashSet};
+
+/// The note being written for a line in the TUI.
+#[derive(Clone)]
+pub struct NoteEditor {
+    pub line_idx: usize,
+    pub input: TextInput,
+}
+
+/// Free-text notes attached to log lines by index.
+#[derive(Clone, Debug, Default)]
+pub struct Notes {
+    by_line: BTreeMap<usize, String>,
+    /// Saved notes whose line hasn't been received yet.
+    pending: Vec<SavedNote>,
+    /// Lines whose note is shown inline below the line.
+    pub expanded: HashSet<usize>,
+}
+
+impl Notes {
+    pub fn from_saved(saved: Vec<SavedNote>) -> Self {
+        Self {
+            pending: saved,
+            ..Self::default()
+        }
+    }
+
+    pub fn get(&self, line_idx: usize) -> Option<&str> {
+        self.by_line.get(&line_idx).map(String::as_str)
+    }
+
+    /// Set the note on a line; empty text removes it.
+    pub fn set(&mut self, line_idx: usize, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.by_line.remove(&line_idx);
+            self.expanded.remove(&line_idx);
+        } else {
+            self.by_line.insert(line_idx, text.to_string());
+            self.expanded.insert(line_idx);
+        }
+    }
+
+    pub fn toggle_expanded(&mut self, line_idx: usize) {
+        if !self.expanded.remove(&line_idx) && self.by_line.contains_key(&line_idx) {
+            self.expanded.insert(line_idx);
+        }
+    }
+
+    pub fn is_expanded(&self, line_idx: usize) -> bool {
+        self.expanded.contains(&line_idx)
+    }
+
+    /// Attach a saved note to a newly received line when it matches.
+    pub fn attach(&mut self, line_idx: usize, content: &str) {
+        if let Some(pos) = self
+            .pending
+            .iter()
+            .position(|n| n.line == line_idx && n.content == content)
+        {
+            let note = self.pending.swap_remove(pos);
+            self.by_line.insert(line_idx, note.text);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.by_line.clear();
+        self.expanded.clear();
+    }
+
+    /// Move notes to the lines' new indices (`new_idx[old]`), dropping those
+    /// of lines gone.
+    pub fn remap(&mut self, new_idx: &[Option<usize>]) {
+        let moved = |idx: &usize| new_idx.get(*idx).copied().flatten();
+        self.by_line = std::mem::take(&mut self.by_line)
+            .into_iter()
+            .filter_map(|(idx, text)| Some((moved(&idx)?, text)))
+            .collect();
+        self.expanded = self.expanded.iter().filter_map(moved).collect();
+    }
+
+    /// Notes for saving: the attached ones plus any still pending.
+    pub fn to_saved(&self, lines: &[LogLine]) -> Vec<SavedNote> {
+        let mut saved: Vec<SavedNote> = self
+            .by_line
+            .iter()
+            .filter_map(|(&line, text)| {
+                lines.get(line).map(|l| SavedNote {
+                    line,
+                    content: l.content.clone(),
+                    text: text.clone(),
+                })
+            })
+            .collect();
+        saved.extend(self.pending.iter().cloned());
+        saved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(content: &str) -> LogLine {
+        LogLine::new(content.to_string(), None, chrono::Local::now())
+    }
+
+    #[test]
+    fn test_attach_only_matching_lines() {
+        let mut notes = Notes::from_saved(vec![SavedNote {
+            line: 1,
+            content: "db timeout".to_string(),
+            text: "root cause".to_string(),
+        }]);
+        notes.attach(0, "db timeout");
+        notes.attach(1, "something else");
+        assert_eq!(notes.get(1), None);
+        notes.attach(1, "db timeout");
+        assert_eq!(notes.get(1), Some("root cause"));
+
+        let lines = vec![line("start"), line("db timeout")];
+        assert_eq!(notes.to_saved(&lines).len(), 1);
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut notes = Notes::default();
+        notes.set(3, "  check this ");
+        assert_eq!(notes.get(3), Some("check this"));
+        assert!(notes.is_expanded(3));
+        notes.set(3, "");
+        assert_eq!(notes.get(3), None);
+        assert!(!notes.is_expanded(3));
+    }
+}
@@ -0,0 +1,138 @@
+use super::LogLine;
+use crate::level::Level;
+use chrono::{DateTime, Local, TimeZone};
+use std::collections::BTreeMap;
+
+/// How much time one outline bucket covers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BucketSize {
+    #[default]
+    Minute,
+    Hour,
+}
+
+impl BucketSize {
+    fn seconds(self) -> i64 {
+        match self {
+            BucketSize::Minute => 60,
+            BucketSize::Hour => 3600,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BucketSize::Minute => "minute",
+            BucketSize::Hour => "hour",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            BucketSize::Minute => BucketSize::Hour,
+            BucketSize::Hour => BucketSize::Minute,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bucket {
+    pub start: DateTime<Local>,
+    pub lines: usize,
+    pub errors: usize,
+    /// Filtered position of the bucket's earliest shown line.
+    pub first_pos: usize,
+}
+
+impl Bucket {
+    /// `01-31 14:05`, to the minute either way.
+    pub fn label(&self) -> String {
+        self.start.format("%m-%d %H:%M").to_string()
+    }
+}
+
+/// Shown lines counted per time bucket, by event time where the line has
+/// one and receipt time otherwise.
+#[derive(Clone, Default)]
+pub struct Outline {
+    pub size: BucketSize,
+    buckets: BTreeMap<i64, Bucket>,
+}
+
+impl Outline {
+    /// Count the line shown at filtered position `pos`.
+    pub fn observe(&mut self, pos: usize, line: &LogLine) {
+        if line.system {
+            return;
+        }
+        let time = line.event_time.unwrap_or(line.timestamp);
+        let seconds = self.size.seconds();
+        // Align to the local clock, so hours start at :00 with half-hour
+        // zones too.
+        let offset = time.offset().local_minus_utc() as i64;
+        let key = (time.timestamp() + offset).div_euclid(seconds) * seconds - offset;
+        let bucket = self.buckets.entry(key).or_insert_with(|| Bucket {
+            start: Local.timestamp_opt(key, 0).single().unwrap_or(time),
+            lines: 0,
+            errors: 0,
+            first_pos: pos,
+        });
+        bucket.lines += 1;
+        bucket.first_pos = bucket.first_pos.min(pos);
+        if line.level == Some(Level::Error) {
+            bucket.errors += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    /// Buckets in time order.
+    pub fn buckets(&self) -> impl DoubleEndedIterator<Item = &Bucket> + ExactSizeIterator {
+        self.buckets.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Bucket> {
+        self.buckets.values().nth(index)
+    }
+
+    /// The last bucket starting at or before filtered position `pos`.
+    pub fn index_at(&self, pos: usize) -> usize {
+        self.buckets().rposition(|b| b.first_pos <= pos).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(content: &str) -> LogLine {
+        LogLine::new(content.to_string(), None, Local::now())
+    }
+
+    #[test]
+    fn test_outline_buckets_by_event_time() {
+        let mut outline = Outline::default();
+        outline.observe(0, &line("2024-01-31T14:05:10 INFO started"));
+        outline.observe(1, &line("2024-01-31T14:07:00 ERROR failed"));
+        outline.observe(2, &line("2024-01-31T14:05:59 ERROR late arrival"));
+        outline.observe(3, &line("2024-01-31T15:00:00 INFO next hour"));
+        let counts: Vec<(String, usize, usize, usize)> = outline
+            .buckets()
+            .map(|b| (b.label(), b.lines, b.errors, b.first_pos))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("01-31 14:05".to_string(), 2, 1, 0),
+                ("01-31 14:07".to_string(), 1, 1, 1),
+                ("01-31 15:00".to_string(), 1, 0, 3),
+            ]
+        );
+        assert_eq!(outline.index_at(2), 1);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::core::LogLine;
+use crate::session::SavedPin;
+
+/// Most lines that can be pinned at once.
+pub const MAX_PINS: usize = 5;
+
+/// Lines kept in view above the log, like a startup banner, by buffer index.
+#[derive(Clone, Debug, Default)]
+pub struct Pins {
+    lines: Vec<usize>,
+    /// Saved pins whose line hasn't been received yet.
+    pending: Vec<SavedPin>,
+}
+
+impl Pins {
+    pub fn from_saved(saved: Vec<SavedPin>) -> Self {
+        Self {
+            pending: saved,
+            ..Self::default()
+        }
+    }
+
+    /// Pinned lines in buffer order.
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn contains(&self, line_idx: usize) -> bool {
+        self.lines.contains(&line_idx)
+    }
+
+    /// Pin or unpin a line; returns whether it is pinned now.
+    pub fn toggle(&mut self, line_idx: usize) -> Result<bool, String> {
+        if let Some(pos) = self.lines.iter().position(|&idx| idx == line_idx) {
+            self.lines.remove(pos);
+            return Ok(false);
+        }
+        if self.lines.len() >= MAX_PINS {
+            return Err(format!("At most {} lines can be pinned", MAX_PINS));
+        }
+        let pos = self.lines.partition_point(|&idx| idx < line_idx);
+        self.lines.insert(pos, line_idx);
+        Ok(true)
+    }
+
+    /// Pin a newly received line when a saved pin matches it, unless lines
+    /// pinned meanwhile have used up the room.
+    pub fn attach(&mut self, line_idx: usize, content: &str) {
+        if let Some(pos) = self.pending.iter().position(|p| p.line == line_idx && p.content == content) {
+            self.pending.swap_remove(pos);
+            if self.lines.len() >= MAX_PINS {
+                return;
+            }
+            let at = self.lines.partition_point(|&idx| idx < line_idx);
+            self.lines.insert(at, line_idx);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.pending.clear();
+    }
+
+    /// Move pins to the lines' new indices (`new_idx[old]`), dropping those
+    /// of lines gone.
+    pub fn remap(&mut self, new_idx: &[Option<usize>]) {
+        self.lines.retain_mut(|idx| match new_idx.get(*idx).copied().flatten() {
+            Some(new) => {
+                *idx = new;
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Pins for saving: the attached ones plus any still pending.
+    pub fn to_saved(&self, lines: &[LogLine]) -> Vec<SavedPin> {
+        let mut saved: Vec<SavedPin> = self
+            .lines
+            .iter()
+            .filter_map(|&line| {
+                lines.get(line).map(|l| SavedPin {
+                    line,
+                    content: l.content.clone(),
+                })
+            })
+            .collect();
+        saved.extend(self.pending.iter().cloned());
+        saved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_attach_and_cap() {
+        let banner = SavedPin {
+            line: 0,
+            content: "app v1.2 starting".to_string(),
+        };
+        let mut pins = Pins::from_saved(vec![banner.clone()]);
+        for idx in [1, 2, 3, 4, 5] {
+            assert_eq!(pins.toggle(idx), Ok(true));
+        }
+        pins.attach(0, "app v1.2 starting");
+        assert_eq!(pins.lines(), [1, 2, 3, 4, 5]);
+        pins.clear();
+
+        let mut pins = Pins::from_saved(vec![banner.clone()]);
+        pins.attach(0, "something else");
+        assert!(pins.is_empty());
+        pins.attach(0, "app v1.2 starting");
+        assert_eq!(pins.lines(), [0]);
+
+        for idx in [9, 4, 7, 2] {
+            assert_eq!(pins.toggle(idx), Ok(true));
+        }
+        assert_eq!(pins.lines(), [0, 2, 4, 7, 9]);
+        assert!(pins.toggle(5).is_err());
+        assert_eq!(pins.toggle(4), Ok(false));
+        assert!(!pins.contains(4));
+
+        let lines: Vec<LogLine> =
+            (0..10).map(|i| LogLine::new(format!("line {}", i), None, chrono::Local::now())).collect();
+        assert_eq!(pins.to_saved(&lines)[1], SavedPin { line: 2, content: "line 2".to_string() });
+    }
+}
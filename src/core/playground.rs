@@ -0,0 +1,89 @@
+use crate::filter::{parse_filter, ExplainNode};
+use crate::input::TextInput;
+
+/// Which input the playground's expression is loaded from and applied to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaygroundTarget {
+    Filter,
+    Highlight,
+}
+
+impl PlaygroundTarget {
+    pub fn label(self) -> &'static str {
+        match self {
+            PlaygroundTarget::Filter => "Filter",
+            PlaygroundTarget::Highlight => "Highlight",
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            PlaygroundTarget::Filter => PlaygroundTarget::Highlight,
+            PlaygroundTarget::Highlight => PlaygroundTarget::Filter,
+        }
+    }
+}
+
+pub struct Evaluation {
+    pub nodes: Vec<ExplainNode>,
+    /// Byte ranges of the sample that any pattern matches.
+    pub ranges: Vec<(usize, usize)>,
+    /// The expression fully parenthesized, as it was parsed.
+    pub grouped: String,
+}
+
+/// An expression tried against one sample line, sub-expression by
+/// sub-expression.
+#[derive(Clone)]
+pub struct Playground {
+    pub target: PlaygroundTarget,
+    pub expr: TextInput,
+    pub sample: TextInput,
+    pub editing_sample: bool,
+    /// Position in the filtered lines the sample was picked from.
+    pub sample_pos: Option<usize>,
+    /// Show how operators grouped above the tree.
+    pub show_grouping: bool,
+}
+
+impl Playground {
+    pub fn new(target: PlaygroundTarget, expr: &str) -> Self {
+        Self {
+            target,
+            expr: TextInput::new(expr.to_string()),
+            sample: TextInput::default(),
+            editing_sample: false,
+            sample_pos: None,
+            show_grouping: false,
+        }
+    }
+
+    pub fn active_input(&mut self) -> &mut TextInput {
+        if self.editing_sample {
+            &mut self.sample
+        } else {
+            &mut self.expr
+        }
+    }
+
+    pub fn set_sample(&mut self, pos: usize, text: &str) {
+        self.sample = TextInput::new(text.to_string());
+        self.sample_pos = Some(pos);
+    }
+
+    pub fn evaluate(&self) -> Result<Evaluation, String> {
+        if self.expr.text.trim().is_empty() {
+            return Ok(Evaluation {
+                nodes: Vec::new(),
+                ranges: Vec::new(),
+                grouped: String::new(),
+            });
+        }
+        let expr = parse_filter(&self.expr.text).map_err(|e| e.to_string())?;
+        Ok(Evaluation {
+            nodes: expr.explain(&self.sample.text),
+            ranges: expr.find_all_matches(&self.sample.text),
+            grouped: expr.grouped(),
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use crate::highlight::embedded_json;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Distinct field names kept; lines naming more are still counted for the
+/// ones already known.
+pub const MAX_FIELDS: usize = 200;
+const MAX_EXAMPLES: usize = 3;
+const MAX_EXAMPLE_LEN: usize = 40;
+
+/// `key=value` or `key="quoted value"` outside of any JSON.
+static LOGFMT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?:^|\s)([A-Za-z_][\w.-]*)=("(?:[^"\\]|\\.)*"|[^\s"]*)"#).unwrap());
+
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(members) => {
+            for (key, value) in members {
+                let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&name, value, out);
+            }
+        }
+        Value::String(s) => out.push((prefix.to_string(), s.clone())),
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+/// The logfmt and JSON fields of `content` with their values, nested JSON
+/// keys joined with dots like `user.id`.
+pub fn fields_of(content: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut last = 0;
+    let logfmt = |text: &str, fields: &mut Vec<(String, String)>| {
+        for caps in LOGFMT.captures_iter(text) {
+            let raw = &caps[2];
+            let value = match raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+                Some(inner) => serde_json::from_str(raw).unwrap_or_else(|_| inner.to_string()),
+                None => raw.to_string(),
+            };
+            fields.push((caps[1].to_string(), value));
+        }
+    };
+    for range in embedded_json(content) {
+        logfmt(&content[last..range.start], &mut fields);
+        if let Ok(value @ Value::Object(_)) = serde_json::from_str::<Value>(&content[range.clone()]) {
+            flatten("", &value, &mut fields);
+        }
+        last = range.end;
+    }
+    logfmt(&content[last..], &mut fields);
+    fields
+}
+
+/// Value of the field `name` in `content`, as `fields_of` names it.
+pub fn field_value(content: &str, name: &str) -> Option<String> {
+    fields_of(content).into_iter().find(|(key, _)| key == name).map(|(_, value)| value)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FieldInfo {
+    /// Lines the field appeared in.
+    pub count: usize,
+    /// First few distinct values, shortened.
+    pub examples: Vec<String>,
+}
+
+/// Every field seen in the buffer so far, learned as lines arrive.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    fields: HashMap<String, FieldInfo>,
+    /// Lines observed.
+    pub lines: usize,
+}
+
+impl Schema {
+    pub fn observe(&mut self, content: &str) {
+        self.lines += 1;
+        let mut seen: Vec<String> = Vec::new();
+        for (name, value) in fields_of(content) {
+            if seen.contains(&name) {
+                continue;
+            }
+            if !self.fields.contains_key(&name) && self.fields.len() >= MAX_FIELDS {
+                continue;
+            }
+            let info = self.fields.entry(name.clone()).or_default();
+            info.count += 1;
+            let example: String = value.chars().take(MAX_EXAMPLE_LEN).collect();
+            if !example.is_empty() && info.examples.len() < MAX_EXAMPLES && !info.examples.contains(&example) {
+                info.examples.push(example);
+            }
+            seen.push(name);
+        }
+    }
+
+    /// Fields, most common first.
+    pub fn fields(&self) -> Vec<(&str, &FieldInfo)> {
+        let mut fields: Vec<_> = self.fields.iter().map(|(name, info)| (name.as_str(), info)).collect();
+        fields.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+        fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+        self.lines = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_learns_fields() {
+        let line = r#"level=info msg="user logged in" {"user": {"id": 7, "name": "Ana"}, "tags": ["a"], "url": "/x?a=b"}"#;
+        assert_eq!(
+            fields_of(line),
+            [
+                ("level", "info"),
+                ("msg", "user logged in"),
+                ("tags", r#"["a"]"#),
+                ("url", "/x?a=b"),
+                ("user.id", "7"),
+                ("user.name", "Ana"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+        assert_eq!(field_value(line, "user.name").as_deref(), Some("Ana"));
+        assert_eq!(field_value("no fields here", "level"), None);
+
+        let mut schema = Schema::default();
+        schema.observe(line);
+        schema.observe("level=warn level=error retries=3");
+        schema.observe("level=info");
+        schema.observe("plain text");
+        let fields = schema.fields();
+        assert_eq!(fields[0].0, "level");
+        assert_eq!(fields[0].1.count, 3);
+        assert_eq!(fields[0].1.examples, ["info", "warn"]);
+        assert_eq!(schema.len(), 7);
+        assert_eq!(schema.lines, 4);
+    }
+}
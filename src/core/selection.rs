@@ -0,0 +1,59 @@
+use std::ops::RangeInclusive;
+
+/// A visual selection from an anchor to the cursor: whole lines, or with
+/// `block` the same columns of each line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Selection {
+    /// Line the selection started on, as an index into all lines so it
+    /// stays put while new lines arrive.
+    pub anchor: usize,
+    pub anchor_column: usize,
+    pub block: bool,
+}
+
+impl Selection {
+    /// The lines between the anchor and the cursor line.
+    pub fn lines(&self, cursor: usize) -> RangeInclusive<usize> {
+        self.anchor.min(cursor)..=self.anchor.max(cursor)
+    }
+
+    /// The character columns a block covers, both ends included.
+    pub fn columns(&self, cursor_column: usize) -> RangeInclusive<usize> {
+        self.anchor_column.min(cursor_column)..=self.anchor_column.max(cursor_column)
+    }
+
+    /// What the selection takes of one line's text.
+    pub fn cut<'a>(&self, content: &'a str, cursor_column: usize) -> &'a str {
+        if self.block {
+            char_slice(content, self.columns(cursor_column))
+        } else {
+            content
+        }
+    }
+}
+
+/// The characters of `text` in `columns`; short lines give what they have.
+pub fn char_slice(text: &str, columns: RangeInclusive<usize>) -> &str {
+    let byte = |col: usize| text.char_indices().nth(col).map_or(text.len(), |(i, _)| i);
+    &text[byte(*columns.start())..byte(columns.end() + 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_selection() {
+        let selection = Selection {
+            anchor: 7,
+            anchor_column: 9,
+            block: true,
+        };
+        assert_eq!(selection.lines(4), 4..=7);
+        let lines = ["12:00:01 req=a1b2 ok", "12:00:02 req=ü9x8 fail", "short"];
+        let cut: Vec<&str> = lines.iter().map(|l| selection.cut(l, 16)).collect();
+        assert_eq!(cut, ["req=a1b2", "req=ü9x8", ""]);
+        let lines_only = Selection { block: false, ..selection };
+        assert_eq!(lines_only.cut(lines[0], 16), lines[0]);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::filter::{parse_filter, FilterExpr};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// `text` with every word holding a digit (counts, IDs, times, addresses)
+/// turned into `#`, so messages differing only in those read the same.
+pub fn template(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| if word.chars().any(|c| c.is_ascii_digit()) { "#" } else { word })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Clone, Debug)]
+pub enum SnoozeMatch {
+    /// Lines whose `template()` is this.
+    Template(String),
+    /// Lines a filter expression matches, with its text.
+    Expr(String, FilterExpr),
+}
+
+/// A kind of message hidden for a while, without touching the hide regex.
+#[derive(Clone, Debug)]
+pub struct Snooze {
+    pub what: SnoozeMatch,
+    /// When it wakes up again; `None` lasts until restart.
+    pub until: Option<Instant>,
+}
+
+impl Snooze {
+    pub fn template(text: &str, duration: Option<Duration>) -> Self {
+        Self {
+            what: SnoozeMatch::Template(template(text)),
+            until: duration.map(|d| Instant::now() + d),
+        }
+    }
+
+    pub fn expr(text: &str, duration: Option<Duration>) -> Result<Self> {
+        Ok(Self {
+            what: SnoozeMatch::Expr(text.to_string(), parse_filter(text)?),
+            until: duration.map(|d| Instant::now() + d),
+        })
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        match &self.what {
+            SnoozeMatch::Template(t) => template(text) == *t,
+            SnoozeMatch::Expr(_, expr) => expr.matches(text),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.what {
+            SnoozeMatch::Template(t) => format!("like: {}", t),
+            SnoozeMatch::Expr(text, _) => format!("matching: {}", text),
+        }
+    }
+
+    /// `12m left`, or `until restart`.
+    pub fn remaining(&self, now: Instant) -> String {
+        match self.until {
+            Some(until) => {
+                let secs = until.saturating_duration_since(now).as_secs();
+                match secs {
+                    0..60 => format!("{}s left", secs),
+                    60..3600 => format!("{}m left", secs.div_ceil(60)),
+                    _ => format!("{}h{}m left", secs / 3600, secs % 3600 / 60),
+                }
+            }
+            None => "until restart".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Snoozes {
+    pub list: Vec<Snooze>,
+}
+
+impl Snoozes {
+    pub fn hides(&self, text: &str) -> bool {
+        self.list.iter().any(|s| s.matches(text))
+    }
+
+    /// Drop the snoozes that are over; true when any were.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.list.len();
+        self.list.retain(|s| s.until.is_none_or(|until| until > now));
+        self.list.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snooze_by_template_and_expr() {
+        assert_eq!(template("retry 3 of 5 for job-17 in 250ms"), "retry # of # for # in #");
+        let mut snoozes = Snoozes::default();
+        snoozes.list.push(Snooze::template("cache miss key=user:42", None));
+        snoozes.list.push(Snooze::expr("heartbeat && !error", Some(Duration::from_secs(600))).unwrap());
+        assert!(snoozes.hides("cache miss key=user:7"));
+        assert!(!snoozes.hides("cache miss everywhere"));
+        assert!(snoozes.hides("heartbeat ok"));
+        assert!(!snoozes.hides("heartbeat error"));
+
+        let now = Instant::now();
+        assert_eq!(snoozes.list[1].remaining(now), "10m left");
+        assert!(!snoozes.expire(now));
+        assert!(snoozes.expire(now + Duration::from_secs(601)));
+        assert_eq!(snoozes.list.len(), 1);
+        assert_eq!(snoozes.list[0].remaining(now), "until restart");
+    }
+}
@@ -0,0 +1,135 @@
+use crate::core::Messages;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Most toasts shown at once; older ones make room, errors last.
+const MAX_TOASTS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn lifetime(self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(15),
+        }
+    }
+}
+
+pub struct Toast {
+    pub severity: Severity,
+    pub text: String,
+    /// How many times in a row the same toast was raised.
+    pub repeats: usize,
+    raised: Instant,
+}
+
+impl Toast {
+    /// The text, with `(×3)` when it was raised repeatedly.
+    pub fn label(&self) -> String {
+        if self.repeats > 1 {
+            format!("{} (×{})", self.text, self.repeats)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// Transient notifications, oldest first.
+#[derive(Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+    /// Every warning and error raised, and those only logged.
+    pub messages: Messages,
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text.into(), Instant::now());
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warning, text.into(), Instant::now());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Severity::Error, text.into(), Instant::now());
+    }
+
+    /// Keep a message for `:messages` without showing a toast.
+    pub fn log(&mut self, severity: Severity, text: impl Into<String>) {
+        self.messages.push(severity, text.into());
+    }
+
+    fn push(&mut self, severity: Severity, text: String, now: Instant) {
+        if severity > Severity::Info {
+            self.messages.push(severity, text.clone());
+        }
+        if let Some(last) = self.queue.back_mut().filter(|t| t.severity == severity && t.text == text) {
+            last.repeats += 1;
+            last.raised = now;
+            return;
+        }
+        self.queue.push_back(Toast {
+            severity,
+            text,
+            repeats: 1,
+            raised: now,
+        });
+        if self.queue.len() > MAX_TOASTS {
+            let least = self.queue.iter().map(|t| t.severity).min().unwrap_or(Severity::Info);
+            if let Some(idx) = self.queue.iter().position(|t| t.severity == least) {
+                self.queue.remove(idx);
+            }
+        }
+    }
+
+    /// Drop the toasts that have been shown long enough.
+    pub fn expire(&mut self, now: Instant) {
+        self.queue
+            .retain(|t| now.duration_since(t.raised) < t.severity.lifetime());
+    }
+
+    pub fn dismiss(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.queue.iter()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.queue.iter().filter(|t| t.severity == Severity::Error).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_stack_and_keep_errors() {
+        let mut toasts = Toasts::default();
+        let start = Instant::now();
+        toasts.push(Severity::Error, "Source error: refused".to_string(), start);
+        for i in 0..MAX_TOASTS {
+            toasts.push(Severity::Info, format!("Copied {}", i), start);
+        }
+        toasts.push(Severity::Info, format!("Copied {}", MAX_TOASTS - 1), start);
+        let labels: Vec<String> = toasts.iter().map(Toast::label).collect();
+        assert_eq!(labels, ["Source error: refused", "Copied 1", "Copied 2", "Copied 3", "Copied 4 (×2)"]);
+
+        toasts.expire(start + Duration::from_secs(5));
+        assert_eq!(toasts.errors(), 1);
+        assert_eq!(toasts.messages.len(), 1);
+        assert_eq!(toasts.iter().count(), 1);
+        toasts.expire(start + Duration::from_secs(20));
+        assert_eq!(toasts.iter().count(), 0);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::core::LogState;
+use std::time::Instant;
+
+/// Matched pieces listed per row before the rest are counted.
+const MAX_SPANS: usize = 4;
+
+/// One finding about a line; `pass` when it lets the line through.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reason {
+    pub depth: usize,
+    pub text: String,
+    pub pass: bool,
+}
+
+/// Why a buffer line is shown or hidden: the hide regex, rewrites, every
+/// filter sub-expression with the spans its terms matched, snoozes and dedup.
+#[derive(Clone, Debug)]
+pub struct Why {
+    pub line_idx: usize,
+    pub shown: bool,
+    pub reasons: Vec<Reason>,
+}
+
+/// `"err"@12, "Err"@40 +3 more`.
+fn describe_spans(text: &str, spans: &[(usize, usize)]) -> String {
+    let mut parts: Vec<String> = spans
+        .iter()
+        .take(MAX_SPANS)
+        .map(|&(start, end)| format!("{:?}@{}", &text[start..end], start))
+        .collect();
+    if spans.len() > MAX_SPANS {
+        parts.push(format!("+{} more", spans.len() - MAX_SPANS));
+    }
+    parts.join(", ")
+}
+
+impl Why {
+    pub fn explain(log_state: &LogState, idx: usize) -> Option<Self> {
+        let line = log_state.lines.get(idx)?;
+        let shown = log_state.filtered_indices.binary_search(&idx).is_ok();
+        let mut reasons = Vec::new();
+        let mut push = |depth: usize, text: String, pass: bool| reasons.push(Reason { depth, text, pass });
+        if line.system {
+            push(0, "Message from the viewer itself, always shown".to_string(), true);
+            return Some(Self { line_idx: idx, shown, reasons });
+        }
+
+        let filter_state = &log_state.filter_state;
+        let structured = log_state.structured(line);
+        match filter_state.hidden_ranges(&structured) {
+            _ if filter_state.hide_regex.is_none() => {}
+            Ok(ranges) if ranges.is_empty() => push(0, "Hide regex removed nothing".to_string(), true),
+            Ok(ranges) => push(0, format!("Hide regex removed {}", describe_spans(&structured, &ranges)), true),
+            Err(e) => push(0, format!("Hide regex failed ({}), the raw text is used", e), true),
+        }
+        let text = log_state.display_content(line);
+        if filter_state.apply_hide(&structured).is_ok_and(|hidden| hidden != text) {
+            push(0, format!("Shown and filtered as: {}", text), true);
+        }
+
+        let filtered = match &filter_state.filter_expr {
+            None => {
+                push(0, "No filter".to_string(), true);
+                true
+            }
+            Some(expr) => {
+                let matched = expr.matches(&text);
+                let verdict = if matched { "matches" } else { "does not match" };
+                push(0, format!("Filter {}", verdict), matched);
+                for node in expr.explain(&text) {
+                    let label = match node.spans.is_empty() {
+                        true => node.label,
+                        false => format!("{} at {}", node.label, describe_spans(&text, &node.spans)),
+                    };
+                    push(node.depth + 1, label, node.matched);
+                }
+                matched
+            }
+        };
+
+        let now = Instant::now();
+        let mut snoozed = false;
+        for snooze in log_state.snoozes.list.iter().filter(|s| s.matches(&text)) {
+            push(0, format!("Snoozed {} ({})", snooze.label(), snooze.remaining(now)), false);
+            snoozed = true;
+        }
+
+        if let Some(dedup) = &log_state.dedup {
+            match dedup.suppressed(idx) {
+                _ if !shown && filtered && !snoozed => {
+                    push(0, "Folded into an earlier near-duplicate (dedup)".to_string(), false)
+                }
+                0 => {}
+                1 => push(0, "1 near-duplicate folded into it (dedup)".to_string(), true),
+                n => push(0, format!("{} near-duplicates folded into it (dedup)", n), true),
+            }
+        }
+        Some(Self { line_idx: idx, shown, reasons })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Snooze;
+    use crate::dedup::Dedup;
+
+    #[test]
+    fn test_why_lists_each_rule() {
+        let mut state = LogState::default();
+        state.dedup = Some(Dedup::new(60));
+        state.set_hide(r"token=\S+ ").unwrap();
+        state.set_filter("error && !debug").unwrap();
+        state.ingest("token=abc error: disk full".to_string(), None);
+        state.ingest("token=abc error: disk full".to_string(), None);
+        state.ingest("debug error".to_string(), None);
+        state.ingest("noise 42".to_string(), None);
+        state.snoozes.list.push(Snooze::expr("noise", None).unwrap());
+        state.refilter();
+        let rows = |idx: usize| -> Vec<(usize, String, bool)> {
+            Why::explain(&state, idx).unwrap().reasons.into_iter().map(|r| (r.depth, r.text, r.pass)).collect()
+        };
+
+        let shown = Why::explain(&state, 0).unwrap();
+        assert!(shown.shown);
+        assert_eq!(
+            rows(0),
+            [
+                (0, r#"Hide regex removed "token=abc "@0"#, true),
+                (0, "Filter matches", true),
+                (1, "AND", true),
+                (2, r#""error" at "error"@0"#, true),
+                (2, "NOT", true),
+                (3, "\"debug\"", false),
+                (0, "1 near-duplicate folded into it (dedup)", true),
+            ]
+            .map(|(d, t, p)| (d, t.to_string(), p))
+        );
+        assert!(!Why::explain(&state, 1).unwrap().shown);
+        assert_eq!(rows(1).last().unwrap().1, "Folded into an earlier near-duplicate (dedup)");
+        assert!(rows(2).contains(&(3, r#""debug" at "debug"@0"#.to_string(), true)));
+        assert_eq!(rows(3)[1], (0, "Filter does not match".to_string(), false));
+        assert_eq!(rows(3).last().unwrap(), &(0, "Snoozed matching: noise (until restart)".to_string(), false));
+        assert!(Why::explain(&state, 9).is_none());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::core::LogLine;
+use chrono::{DateTime, Local};
+use fancy_regex::Regex;
+
+/// Patterns finding a request or trace ID, which is their first capture
+/// group.
+pub fn default_patterns() -> Vec<String> {
+    vec![r#"(?i)\b(?:request|req|trace|correlation|span)[_-]?id["']?\s*[:=]\s*["']?([\w.:-]+)"#.to_string()]
+}
+
+/// Compile the `correlation_ids` setting, skipping patterns that don't parse.
+pub fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns.iter().filter_map(|p| Regex::new(p).ok()).collect()
+}
+
+/// The ID the first matching pattern captures in `content`.
+pub fn extract_id(patterns: &[Regex], content: &str) -> Option<String> {
+    patterns.iter().find_map(|re| {
+        let caps = re.captures(content).ok()??;
+        let id = caps.get(1).or_else(|| caps.get(0))?.as_str();
+        (!id.is_empty()).then(|| id.to_string())
+    })
+}
+
+/// Whether `content` has `id` not run into a longer word, so ID `12` doesn't
+/// match `412`.
+pub fn mentions(content: &str, id: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    content.match_indices(id).any(|(start, _)| {
+        let before = content[..start].chars().next_back();
+        let after = content[start + id.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+/// The lines sharing an ID and the time they span.
+#[derive(Debug, PartialEq)]
+pub struct Correlation {
+    pub id: String,
+    pub lines: usize,
+    pub first: DateTime<Local>,
+    pub last: DateTime<Local>,
+}
+
+impl Correlation {
+    /// Every line mentioning `id`, timed by event time where there is one.
+    pub fn find(lines: &[LogLine], id: &str) -> Option<Self> {
+        let mut times = lines
+            .iter()
+            .filter(|line| !line.system && mentions(&line.content, id))
+            .map(|line| line.event_time.unwrap_or(line.timestamp));
+        let first = times.next()?;
+        let (count, first, last) = times.fold((1, first, first), |(n, lo, hi), t| (n + 1, lo.min(t), hi.max(t)));
+        Some(Self {
+            id: id.to_string(),
+            lines: count,
+            first,
+            last,
+        })
+    }
+
+    /// `abc123: 14 lines over 2.350s (14:05:01.120 → 14:05:03.470)`.
+    pub fn summary(&self) -> String {
+        let span = (self.last - self.first).num_milliseconds() as f64 / 1000.0;
+        format!(
+            "{}: {} lines over {:.3}s ({} → {})",
+            self.id,
+            self.lines,
+            span,
+            self.first.format("%H:%M:%S%.3f"),
+            self.last.format("%H:%M:%S%.3f"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlate_request_ids() {
+        let patterns = compile(&default_patterns());
+        assert_eq!(extract_id(&patterns, "GET /a request_id=ab-12 took 3ms").as_deref(), Some("ab-12"));
+        assert_eq!(extract_id(&patterns, r#"{"traceId": "f00d", "msg": "x"}"#).as_deref(), Some("f00d"));
+        assert_eq!(extract_id(&patterns, "no ids here"), None);
+
+        let lines: Vec<LogLine> = [
+            "2024-01-31T14:05:01.120 start req_id=r7",
+            "2024-01-31T14:05:02.000 other req_id=r77",
+            "2024-01-31T14:05:03.470 done r7",
+        ]
+        .iter()
+        .map(|c| LogLine::new(c.to_string(), None, Local::now()))
+        .collect();
+        let found = Correlation::find(&lines, "r7").unwrap();
+        assert_eq!(found.summary(), "r7: 2 lines over 2.350s (14:05:01.120 → 14:05:03.470)");
+    }
+}
@@ -0,0 +1,60 @@
+use crate::bundle::Bundle;
+use crate::state::data_path;
+use anyhow::{anyhow, Context, Error, Result};
+use crossterm::{
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::PathBuf;
+use std::thread;
+
+/// Where a crashed TUI leaves its buffer, settings, notes and view position.
+const CRASH_FILE: &str = ".logviewer-crash.lvz";
+
+/// Restore the terminal before the panic message is printed, so it lands on
+/// the normal screen instead of vanishing with the alternate one. Panics on
+/// other threads (sources, plugins) leave the running TUI alone.
+pub fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some("main") {
+            restore_terminal();
+        }
+        previous(info);
+    }));
+}
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, Show);
+}
+
+/// Write the crash file and describe where it went.
+pub fn save(bundle: &Bundle) -> Error {
+    let path = crash_path();
+    match bundle.write(&path) {
+        Ok(()) => anyhow!(
+            "logviewer crashed; its buffer and state were saved to {}. Run `logviewer --resume-crash` to reopen it",
+            path.display()
+        ),
+        Err(e) => anyhow!("logviewer crashed, and saving the crash session failed: {:#}", e),
+    }
+}
+
+/// Read the crash file for `--resume-crash`, removing it so a later crash
+/// starts afresh.
+pub fn take() -> Result<Bundle> {
+    let path = crash_path();
+    let bundle = Bundle::read(&path).context("No crash session to resume")?;
+    let _ = fs::remove_file(path);
+    Ok(bundle)
+}
+
+fn crash_path() -> PathBuf {
+    data_path(CRASH_FILE, "crash.lvz")
+}
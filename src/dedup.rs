@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration, Local};
+use std::collections::{HashMap, VecDeque};
+
+/// Hashes differing in at most this many of their 64 bits are ≥95% similar.
+const MAX_DISTANCE: u32 = 3;
+
+/// Shown lines remembered for comparison; older ones age out early in a
+/// busy stream.
+const MAX_RECENT: usize = 256;
+
+pub const DEFAULT_WINDOW_SECS: u64 = 10;
+
+/// 64-bit SimHash of the line's character trigrams, with digit runs folded
+/// together so lines differing only by counters or ids hash alike.
+pub fn simhash(text: &str) -> u64 {
+    let mut normalized: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            if normalized.last() != Some(&'#') {
+                normalized.push('#');
+            }
+        } else {
+            normalized.extend(c.to_lowercase());
+        }
+    }
+    let mut votes = [0i32; 64];
+    for shingle in normalized.windows(normalized.len().clamp(1, 3)) {
+        let hash = fnv1a(shingle);
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    votes
+        .iter()
+        .enumerate()
+        .filter(|(_, &vote)| vote > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+fn fnv1a(chars: &[char]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &c in chars {
+        for byte in (c as u32).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Hides lines nearly identical to one shown within the last `window`,
+/// counting them against the line that was shown.
+#[derive(Clone)]
+pub struct Dedup {
+    pub window_secs: u64,
+    /// Hash, receipt time and index of recently shown lines, oldest first.
+    recent: VecDeque<(u64, DateTime<Local>, usize)>,
+    /// Lines hidden as repeats of each shown line.
+    counts: HashMap<usize, usize>,
+    pub total_suppressed: usize,
+}
+
+impl Dedup {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            recent: VecDeque::new(),
+            counts: HashMap::new(),
+            total_suppressed: 0,
+        }
+    }
+
+    /// Whether line `idx` should be shown. Lines must be offered in order.
+    pub fn admit(&mut self, idx: usize, text: &str, time: DateTime<Local>) -> bool {
+        let cutoff = time - Duration::seconds(self.window_secs as i64);
+        while self.recent.front().is_some_and(|&(_, shown, _)| shown < cutoff) {
+            self.recent.pop_front();
+        }
+        let hash = simhash(text);
+        if let Some(&(_, _, shown)) = self
+            .recent
+            .iter()
+            .rev()
+            .find(|(other, _, _)| (hash ^ other).count_ones() <= MAX_DISTANCE)
+        {
+            *self.counts.entry(shown).or_default() += 1;
+            self.total_suppressed += 1;
+            return false;
+        }
+        if self.recent.len() == MAX_RECENT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((hash, time, idx));
+        true
+    }
+
+    /// How many repeats of line `idx` were hidden.
+    pub fn suppressed(&self, idx: usize) -> usize {
+        self.counts.get(&idx).copied().unwrap_or(0)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.window_secs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppresses_retry_storm() {
+        let start = Local::now();
+        let mut dedup = Dedup::new(10);
+        let retry = |n: u32| format!("WARN retry attempt {} of 50 failed: connection refused (10.0.0.{})", n, n);
+        assert!(dedup.admit(0, &retry(1), start));
+        assert!(!dedup.admit(1, &retry(2), start + Duration::seconds(1)));
+        assert!(!dedup.admit(2, &retry(13), start + Duration::seconds(2)));
+        assert!(dedup.admit(3, "INFO request served in 12ms", start + Duration::seconds(3)));
+        assert_eq!(dedup.suppressed(0), 2);
+        // Once the shown line is older than the window, the next repeat shows.
+        assert!(dedup.admit(4, &retry(14), start + Duration::seconds(11)));
+        assert_eq!(dedup.total_suppressed, 2);
+    }
+}
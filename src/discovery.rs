@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+pub const SERVICE_TYPE: &str = "_logviewer._tcp.local.";
+
+/// A registered mDNS service; unregistered when dropped.
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+fn hostname() -> String {
+    std::process::Command::new("hostname")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|name| name.trim().trim_end_matches(".local").to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "logviewer".to_string())
+}
+
+/// Advertise `port` as a `_logviewer._tcp` service named after this host.
+pub fn advertise(port: u16) -> Result<Advertisement> {
+    let daemon = ServiceDaemon::new()?;
+    let host = hostname();
+    let instance = format!("{}-{}", host, port);
+    let info = ServiceInfo::new(SERVICE_TYPE, &instance, &format!("{}.local.", host), (), port, None)?
+        .enable_addr_auto();
+    let fullname = info.get_fullname().to_string();
+    daemon.register(info)?;
+    Ok(Advertisement { daemon, fullname })
+}
+
+/// Prefer addresses a peer can use without a zone ID.
+fn address_rank(ip: &IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(v4) if v4.is_link_local() => 2,
+        IpAddr::V4(_) => 0,
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xffc0) == 0xfe80 => 3,
+        IpAddr::V6(_) => 1,
+    }
+}
+
+/// Browse for a viewer on the LAN and return the first one that resolves.
+pub fn discover(timeout: Duration) -> Result<(String, SocketAddr)> {
+    let daemon = ServiceDaemon::new()?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    let deadline = Instant::now() + timeout;
+    let found = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match events.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                if let Some(ip) = info.get_addresses().iter().min_by_key(|ip| address_rank(ip)) {
+                    break Some((info.get_fullname().to_string(), SocketAddr::new(*ip, info.get_port())));
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break None,
+        }
+    };
+    let _ = daemon.shutdown();
+    found.ok_or_else(|| anyhow!("No logviewer found on the network within {}s", timeout.as_secs()))
+}
@@ -0,0 +1,240 @@
+use crate::source::{without_userinfo, Feed, Message};
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// Wait between polls when following, doubled while the cluster turns
+/// requests away up to the max.
+const POLL: Duration = Duration::from_secs(2);
+const POLL_MAX: Duration = Duration::from_secs(60);
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Documents asked for per page.
+const PAGE: usize = 1000;
+
+/// `--elasticsearch URL/INDEX`: what to search, Elasticsearch or
+/// OpenSearch alike, with `user:password@` in the URL for basic auth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EsQuery {
+    /// The cluster's URL, without the index.
+    pub url: String,
+    /// An index, alias or pattern like `logs-*`.
+    pub index: String,
+    /// A `query_string` query; `*` for every document.
+    pub query: String,
+    pub time_field: String,
+    /// Keep polling for new documents once the range is read.
+    pub follow: bool,
+}
+
+impl FromStr for EsQuery {
+    type Err = anyhow::Error;
+
+    /// `http://es:9200/logs-*` split into the cluster and the index.
+    fn from_str(target: &str) -> Result<Self> {
+        let (url, index) = target
+            .trim_end_matches('/')
+            .rsplit_once('/')
+            .filter(|(url, index)| url.contains("://") && !url.ends_with('/') && !index.is_empty())
+            .ok_or_else(|| anyhow!("Expected --elasticsearch http://HOST:9200/INDEX, got {}", without_userinfo(target)))?;
+        Ok(Self {
+            url: url.to_string(),
+            index: index.to_string(),
+            query: "*".to_string(),
+            time_field: "@timestamp".to_string(),
+            follow: false,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    hits: Hits,
+}
+
+#[derive(Deserialize)]
+struct Hits {
+    hits: Vec<Hit>,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+    #[serde(rename = "_index")]
+    index: String,
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "_source", default)]
+    source: Value,
+    #[serde(default)]
+    sort: Vec<Value>,
+}
+
+/// Pages through the documents in time order with `search_after`.
+pub struct Poller {
+    agent: ureq::Agent,
+    query: EsQuery,
+    /// The range searched, ms since the epoch: documents from `start_ms`
+    /// on, up to `end_ms` unless following.
+    start_ms: i64,
+    end_ms: Option<i64>,
+    /// Sort value (the time field's) of the newest document read.
+    after: Option<i64>,
+    /// IDs of the documents read at `after`; the next page starts there
+    /// again, as others may share the time.
+    seen: HashSet<String>,
+    interval: Duration,
+    /// The last page was full, so the next is asked for at once.
+    more: bool,
+    done: bool,
+}
+
+impl Poller {
+    /// Search from `start_ms`, carrying on after `after` when reconnecting.
+    pub fn open(query: &EsQuery, start_ms: i64, end_ms: Option<i64>, after: Option<i64>) -> Result<Self> {
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            query: query.clone(),
+            start_ms,
+            end_ms,
+            after,
+            seen: HashSet::new(),
+            interval: POLL,
+            more: true,
+            done: false,
+        })
+    }
+
+    fn body(&self) -> Value {
+        let mut range = json!({ "gte": self.start_ms, "format": "epoch_millis" });
+        if let Some(end) = self.end_ms {
+            range["lte"] = end.into();
+        }
+        let mut body = json!({
+            "size": PAGE,
+            "sort": [{ &self.query.time_field: { "order": "asc" } }],
+            "query": { "bool": { "filter": [
+                { "range": { &self.query.time_field: range } },
+                { "query_string": { "query": &self.query.query } }
+            ] } }
+        });
+        // One short of the newest, so documents sharing its time are found.
+        if let Some(after) = self.after {
+            body["search_after"] = json!([after - 1]);
+        }
+        body
+    }
+
+    /// The documents of a page not read yet, as JSON lines tagged with
+    /// their index.
+    fn take_new(&mut self, json: &str) -> Result<Vec<Message>> {
+        let hits = serde_json::from_str::<Response>(json)?.hits.hits;
+        let full = hits.len() >= PAGE;
+        let mut messages = Vec::new();
+        for hit in hits {
+            let Some(sort) = hit.sort.first().and_then(Value::as_i64) else {
+                continue;
+            };
+            if self.after == Some(sort) && self.seen.contains(&hit.id) {
+                continue;
+            }
+            if self.after != Some(sort) {
+                self.after = Some(sort);
+                self.seen.clear();
+            }
+            self.seen.insert(hit.id);
+            messages.push(Message {
+                source: hit.index,
+                content: hit.source.to_string(),
+            });
+        }
+        // A full page of documents all at one time would come back again
+        // and again; step past that time instead.
+        if full && messages.is_empty() {
+            self.after = self.after.map(|after| after + 1);
+            self.seen.clear();
+        }
+        self.more = full;
+        self.done = !full && !self.query.follow;
+        Ok(messages)
+    }
+}
+
+impl Feed for Poller {
+    /// Pages follow each other at once; once caught up, a poll waits its
+    /// interval. A poll turned away with 429 or 503 comes back empty and
+    /// doubles the interval.
+    fn next_batch(&mut self, wait: bool) -> Result<Vec<Message>> {
+        if wait && !self.more {
+            thread::sleep(self.interval);
+        }
+        let url = format!("{}/{}/_search", self.query.url, self.query.index);
+        let response = self
+            .agent
+            .post(&url)
+            .set("Content-Type", "application/json")
+            .send_string(&self.body().to_string());
+        let body = match response {
+            Ok(response) => response.into_string()?,
+            Err(ureq::Error::Status(429 | 503, _)) => {
+                self.interval = (self.interval * 2).min(POLL_MAX);
+                self.more = false;
+                return Ok(Vec::new());
+            }
+            Err(ureq::Error::Status(status, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                bail!("HTTP {}: {}", status, body.trim());
+            }
+            Err(e) => bail!("{}", without_userinfo(&e.to_string())),
+        };
+        self.interval = POLL;
+        self.take_new(&body)
+    }
+
+    fn position(&self) -> Option<String> {
+        self.after.map(|after| after.to_string())
+    }
+
+    fn done(&self) -> bool {
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_after_pages() {
+        let query: EsQuery = "http://elastic:pw@es:9200/logs-*".parse().unwrap();
+        assert_eq!((query.url.as_str(), query.index.as_str()), ("http://elastic:pw@es:9200", "logs-*"));
+        assert!("http://es:9200".parse::<EsQuery>().is_err());
+
+        let mut poller = Poller::open(&query, 0, Some(10_000), None).unwrap();
+        assert!(poller.body().get("search_after").is_none());
+        let page = r#"{"hits": {"hits": [
+            {"_index": "logs-a", "_id": "1", "_source": {"@timestamp": "2024-06-12T10:00:00Z", "level": "warn"}, "sort": [5000]},
+            {"_index": "logs-b", "_id": "2", "_source": {"msg": "x"}, "sort": [6000]}
+        ]}}"#;
+        let texts = |messages: Vec<Message>| messages.into_iter().map(|m| format!("{} {}", m.source, m.content)).collect::<Vec<_>>();
+        assert_eq!(
+            texts(poller.take_new(page).unwrap()),
+            [r#"logs-a {"@timestamp":"2024-06-12T10:00:00Z","level":"warn"}"#, r#"logs-b {"msg":"x"}"#]
+        );
+        assert!(poller.done());
+        assert_eq!(poller.body()["search_after"], json!([5999]));
+        assert_eq!(poller.body()["query"]["bool"]["filter"][0]["range"]["@timestamp"]["lte"], json!(10_000));
+
+        // The document at the newest time comes back; only the new one shows.
+        let next = r#"{"hits": {"hits": [
+            {"_index": "logs-b", "_id": "2", "_source": {"msg": "x"}, "sort": [6000]},
+            {"_index": "logs-b", "_id": "3", "_source": {"msg": "y"}, "sort": [6000]}
+        ]}}"#;
+        assert_eq!(texts(poller.take_new(next).unwrap()), [r#"logs-b {"msg":"y"}"#]);
+        assert_eq!(poller.position().as_deref(), Some("6000"));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::core::LogLine;
+use crate::entity::{find_entities, EntityKind};
+use crate::rdns::ReverseDns;
+use anyhow::{anyhow, Context, Result};
+use maxminddb::{geoip2, Reader};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// The IP addresses of a line as written, each with its `key=value` fields.
+pub type Annotations = Arc<[(String, String)]>;
+
+/// Annotates IP addresses in lines with what local GeoIP databases and
+/// reverse DNS know about them, as `key=value` fields.
+pub struct Enricher {
+    databases: Vec<Reader<Vec<u8>>>,
+    rdns: Option<ReverseDns>,
+}
+
+impl Enricher {
+    /// MaxMind City, Country or ASN databases (`.mmdb`), and whether to look
+    /// up host names.
+    pub fn open(paths: &[String], reverse_dns: bool) -> Result<Self> {
+        if paths.is_empty() && !reverse_dns {
+            return Err(anyhow!("Set geoip_databases or reverse_dns in the settings to enrich IPs"));
+        }
+        let databases = paths
+            .iter()
+            .map(|path| Reader::open_readfile(path).with_context(|| format!("Can't open GeoIP database {}", path)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            databases,
+            rdns: reverse_dns.then(ReverseDns::new),
+        })
+    }
+
+    /// `country`, `city`, `asn`, `org` and `host` of `ip`, where known.
+    pub fn describe(&self, ip: IpAddr) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        for db in &self.databases {
+            if db.metadata.database_type.contains("ASN") {
+                if let Ok(asn) = db.lookup::<geoip2::Asn>(ip) {
+                    fields.extend(asn.autonomous_system_number.map(|n| ("asn", n.to_string())));
+                    fields.extend(asn.autonomous_system_organization.map(|o| ("org", o.to_string())));
+                }
+            } else if let Ok(city) = db.lookup::<geoip2::City>(ip) {
+                fields.extend(city.country.and_then(|c| c.iso_code).map(|code| ("country", code.to_string())));
+                let name = city.city.and_then(|c| c.names).and_then(|names| names.get("en").copied());
+                fields.extend(name.map(|name| ("city", name.to_string())));
+            }
+        }
+        if let Some(host) = self.rdns.as_ref().and_then(|rdns| rdns.name(ip)) {
+            fields.push(("host", host));
+        }
+        fields
+    }
+
+    /// Host names found so far; lines annotated before the latest ones are
+    /// missing their `host=`.
+    pub fn answers(&self) -> usize {
+        self.rdns.as_ref().map_or(0, ReverseDns::answers)
+    }
+
+    /// Look up the addresses of `lines` afresh, each address once.
+    pub fn annotate_lines(&self, lines: &mut [LogLine]) {
+        let mut known = HashMap::new();
+        for line in lines.iter_mut().filter(|line| !line.system) {
+            line.enrichment = annotations_with(&line.content, |ip| {
+                known.entry(ip).or_insert_with(|| fields(self.describe(ip))).clone()
+            });
+        }
+    }
+
+    /// The fields of each address in `content`, looked up when a line arrives
+    /// so drawing it doesn't search it or touch the databases.
+    pub fn annotations(&self, content: &str) -> Option<Annotations> {
+        annotations_with(content, |ip| fields(self.describe(ip)))
+    }
+}
+
+/// `key=value` pairs joined by spaces, quoting values with spaces; `None`
+/// when there are none.
+fn fields(fields: Vec<(&'static str, String)>) -> Option<String> {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(key, value)| match value.contains(char::is_whitespace) {
+            true => format!("{}=\"{}\"", key, value),
+            false => format!("{}={}", key, value),
+        })
+        .collect();
+    (!fields.is_empty()).then(|| fields.join(" "))
+}
+
+fn annotations_with(content: &str, mut describe: impl FnMut(IpAddr) -> Option<String>) -> Option<Annotations> {
+    let mut found: Vec<(String, String)> = Vec::new();
+    for entity in find_entities(content).into_iter().filter(|e| e.kind == EntityKind::Ip) {
+        if found.iter().any(|(addr, _)| *addr == entity.text) {
+            continue;
+        }
+        if let Some(fields) = entity.text.parse().ok().and_then(&mut describe) {
+            found.push((entity.text, fields));
+        }
+    }
+    (!found.is_empty()).then(|| found.into())
+}
+
+/// `text` with `(key=value …)` after each address in `annotations`.
+pub fn annotate(text: &str, annotations: &[(String, String)]) -> String {
+    // Not part of a longer address: 10.0.0.1 in 10.0.0.12 or ::1 in fe80::1.
+    let part_of_address = |c: char| c.is_ascii_hexdigit() || c == '.' || c == ':';
+    let mut inserts: Vec<(usize, &str)> = Vec::new();
+    for (addr, fields) in annotations {
+        for (start, _) in text.match_indices(addr.as_str()) {
+            let end = start + addr.len();
+            let before = text[..start].chars().next_back();
+            let after = text[end..].chars().next();
+            if !before.is_some_and(part_of_address) && !after.is_some_and(part_of_address) {
+                inserts.push((end, fields));
+            }
+        }
+    }
+    if inserts.is_empty() {
+        return text.to_string();
+    }
+    inserts.sort_unstable_by_key(|(end, _)| *end);
+    let mut out = String::with_capacity(text.len() + inserts.len() * 32);
+    let mut last = 0;
+    for (end, fields) in inserts {
+        out.push_str(&text[last..end]);
+        out.push_str(" (");
+        out.push_str(fields);
+        out.push(')');
+        last = end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_known_addresses() {
+        let describe = |ip: IpAddr| match ip.to_string().as_str() {
+            "203.0.113.9" => fields(vec![("country", "DE".to_string()), ("org", "Example Net".to_string())]),
+            "2001:db8::1" => fields(vec![("host", "edge-1.example".to_string())]),
+            _ => None,
+        };
+        let line = "GET / from 203.0.113.9 via 10.0.0.1, [2001:db8::1]:443";
+        let annotations = annotations_with(line, describe).unwrap();
+        assert_eq!(
+            annotate(line, &annotations),
+            "GET / from 203.0.113.9 (country=DE org=\"Example Net\") via 10.0.0.1, [2001:db8::1 (host=edge-1.example)]:443"
+        );
+        // Rewritten text still gets the fields, but not longer addresses.
+        assert_eq!(
+            annotate("client=203.0.113.9 peer=203.0.113.90", &annotations),
+            "client=203.0.113.9 (country=DE org=\"Example Net\") peer=203.0.113.90"
+        );
+        assert!(annotations_with("no addresses", describe).is_none());
+    }
+}
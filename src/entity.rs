@@ -0,0 +1,109 @@
+use regex::Regex;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::LazyLock;
+
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\b(?:https?|wss?|ftp)://[^\s"'<>\]\)]+"#).unwrap());
+static UUID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b").unwrap()
+});
+static IPV4: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap());
+/// Candidate only; validated by `Ipv6Addr` parsing so timestamps don't match.
+/// A match spans the whole run of word characters, colons and dots around
+/// it, so `cache::add` is one candidate that fails, not `e::add` inside it.
+static IPV6: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\w:.]*:[\w:.]*:[\w:.]*").unwrap());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    Url,
+    Ip,
+    Uuid,
+}
+
+impl EntityKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EntityKind::Url => "URL",
+            EntityKind::Ip => "IP",
+            EntityKind::Uuid => "UUID",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// URLs, IPv4/IPv6 addresses and UUIDs in `text`, in line order. An entity
+/// inside an earlier one (the host of a URL) is not reported separately.
+pub fn find_entities(text: &str) -> Vec<Entity> {
+    let mut found: Vec<Entity> = Vec::new();
+    let mut push = |kind: EntityKind, start: usize, end: usize| {
+        if found.iter().all(|e| end <= e.start || start >= e.end) {
+            found.push(Entity {
+                kind,
+                start,
+                end,
+                text: text[start..end].to_string(),
+            });
+        }
+    };
+
+    for m in URL.find_iter(text) {
+        let trimmed = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        push(EntityKind::Url, m.start(), m.start() + trimmed.len());
+    }
+    for m in UUID.find_iter(text) {
+        push(EntityKind::Uuid, m.start(), m.end());
+    }
+    for m in IPV4.find_iter(text) {
+        if m.as_str().parse::<Ipv4Addr>().is_ok() {
+            push(EntityKind::Ip, m.start(), m.end());
+        }
+    }
+    for m in IPV6.find_iter(text) {
+        let candidate = m.as_str().trim_end_matches([':', '.']);
+        if candidate.parse::<Ipv6Addr>().is_ok() {
+            push(EntityKind::Ip, m.start(), m.start() + candidate.len());
+        }
+    }
+
+    found.sort_by_key(|e| e.start);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(line: &str) -> Vec<(EntityKind, String)> {
+        find_entities(line).into_iter().map(|e| (e.kind, e.text)).collect()
+    }
+
+    #[test]
+    fn test_finds_each_kind() {
+        let line = "GET https://10.0.0.1/api?id=5, from 192.168.1.20 req=123e4567-e89b-12d3-a456-426614174000 peer fe80::1";
+        assert_eq!(
+            texts(line),
+            vec![
+                (EntityKind::Url, "https://10.0.0.1/api?id=5".to_string()),
+                (EntityKind::Ip, "192.168.1.20".to_string()),
+                (EntityKind::Uuid, "123e4567-e89b-12d3-a456-426614174000".to_string()),
+                (EntityKind::Ip, "fe80::1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_times_and_versions_are_not_ips() {
+        assert!(find_entities("at 12:30:45 upgraded to 1.2.3.456").is_empty());
+        assert!(find_entities("cache::add failed in std::io::Error").is_empty());
+        assert_eq!(
+            texts("peer [::1]:8080, gateway fe80::1."),
+            vec![(EntityKind::Ip, "::1".to_string()), (EntityKind::Ip, "fe80::1".to_string())]
+        );
+    }
+}
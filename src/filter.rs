@@ -1,20 +1,233 @@
 use anyhow::{anyhow, Result};
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+
+/// Modifier letters allowed before a quoted term, e.g. `cw"Error"`.
+const MODIFIERS: &str = "ciwlr~";
+
+/// A fuzzy word may differ from the text by one edit per this many characters.
+const FUZZY_CHARS_PER_EDIT: usize = 4;
+
+/// How a term's text is matched, set by modifiers before its quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TermFlags {
+    /// `c` (`Some(true)`) or `i` (`Some(false)`); a term is case-sensitive
+    /// unless marked `i`.
+    pub case_sensitive: Option<bool>,
+    /// `w`: only match at word boundaries.
+    pub whole_word: bool,
+    /// `l`: the text is literal rather than a regex (`r`, the default). After
+    /// a modifier, `/…/` delimits a regex too, as in `i/err(or)?/`.
+    pub literal: bool,
+    /// `~`: each word of the text is matched approximately, in any order.
+    pub fuzzy: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex(Regex),
+    /// Query words, lowercased unless the term is case-sensitive.
+    Fuzzy { words: Vec<Vec<char>>, case_sensitive: bool },
+}
+
+/// One pattern of an expression with its modifiers.
+#[derive(Debug, Clone)]
+pub struct Term {
+    pub text: String,
+    pub flags: TermFlags,
+    matcher: Matcher,
+}
+
+impl Term {
+    pub fn new(text: &str, flags: TermFlags) -> Result<Self> {
+        if flags.fuzzy {
+            if flags.whole_word {
+                return Err(anyhow!("'w' doesn't apply to fuzzy terms"));
+            }
+            let case_sensitive = flags.case_sensitive.unwrap_or(false);
+            let words = text
+                .split_whitespace()
+                .map(|w| fold_chars(w, case_sensitive))
+                .collect::<Vec<_>>();
+            if words.is_empty() {
+                return Err(anyhow!("Empty fuzzy term"));
+            }
+            return Ok(Self {
+                text: text.to_string(),
+                flags,
+                matcher: Matcher::Fuzzy { words, case_sensitive },
+            });
+        }
+        let pattern = if flags.literal { regex::escape(text) } else { text.to_string() };
+        let pattern = if flags.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+        let case_sensitive = flags.case_sensitive.unwrap_or(true);
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| anyhow!("Invalid regex '{}': {}", text, e))?;
+        Ok(Self {
+            text: text.to_string(),
+            flags,
+            matcher: Matcher::Regex(regex),
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Fuzzy { words, case_sensitive } => {
+                let chars = fold_chars(text, *case_sensitive);
+                words.iter().all(|w| fuzzy_distance(w, &chars).0 <= max_edits(w))
+            }
+        }
+    }
+
+    /// Byte ranges to highlight: whole regex matches, or the characters each
+    /// fuzzy word lined up with.
+    fn collect_matches(&self, text: &str, matches: &mut Vec<(usize, usize)>) {
+        match &self.matcher {
+            Matcher::Regex(regex) => {
+                matches.extend(regex.find_iter(text).map(|m| (m.start(), m.end())));
+            }
+            Matcher::Fuzzy { words, case_sensitive } => {
+                let chars = fold_chars(text, *case_sensitive);
+                let Some(positions) = words
+                    .iter()
+                    .map(|w| fuzzy_positions(w, &chars))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return;
+                };
+                let offsets: Vec<(usize, usize)> =
+                    text.char_indices().map(|(i, c)| (i, i + c.len_utf8())).collect();
+                matches.extend(positions.into_iter().flatten().map(|p| offsets[p]));
+            }
+        }
+    }
+}
+
+fn fold_chars(text: &str, case_sensitive: bool) -> Vec<char> {
+    if case_sensitive {
+        text.chars().collect()
+    } else {
+        // One char out per char in, so indices still line up with `text`.
+        text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+    }
+}
+
+fn max_edits(word: &[char]) -> usize {
+    word.len() / FUZZY_CHARS_PER_EDIT
+}
+
+/// Fewest edits turning `word` into some substring of `text`, and where the
+/// first such substring ends. Keeps two rows, so memory doesn't grow with
+/// the line.
+fn fuzzy_distance(word: &[char], text: &[char]) -> (usize, usize) {
+    let mut prev: Vec<usize> = (0..=word.len()).collect();
+    let mut cur = vec![0; word.len() + 1];
+    let mut best = (prev[word.len()], 0);
+    for (j, &t) in text.iter().enumerate() {
+        for (i, &w) in word.iter().enumerate() {
+            cur[i + 1] = (prev[i] + usize::from(w != t)).min(prev[i + 1] + 1).min(cur[i] + 1);
+        }
+        if cur[word.len()] < best.0 {
+            best = (cur[word.len()], j + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    best
+}
+
+/// Indices of the `text` chars matching `word` in its closest substring,
+/// or `None` if that needs more than `max_edits`.
+fn fuzzy_positions(word: &[char], text: &[char]) -> Option<Vec<usize>> {
+    let (distance, end) = fuzzy_distance(word, text);
+    if distance > max_edits(word) {
+        return None;
+    }
+    // The substring is at most `max_edits` longer than the word, so only
+    // that window before `end` needs the full table for the traceback.
+    let start = end.saturating_sub(word.len() + max_edits(word));
+    let text = &text[start..end];
+    let (m, n) = (word.len(), text.len());
+    // d[i][j]: edits turning word[..i] into a substring of text ending at j.
+    let mut d = vec![vec![0; n + 1]; m + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..=m {
+        for j in 1..=n {
+            let substitute = d[i - 1][j - 1] + usize::from(word[i - 1] != text[j - 1]);
+            d[i][j] = substitute.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+        }
+    }
+    let mut positions = Vec::new();
+    let (mut i, mut j) = (m, n);
+    while i > 0 {
+        let same = j > 0 && word[i - 1] == text[j - 1];
+        if j > 0 && d[i][j] == d[i - 1][j - 1] + usize::from(!same) {
+            if same {
+                positions.push(start + j - 1);
+            }
+            i -= 1;
+            j -= 1;
+        } else if d[i][j] == d[i - 1][j] + 1 {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+    positions.reverse();
+    Some(positions)
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.flags.case_sensitive {
+            Some(true) => f.write_str("c")?,
+            Some(false) => f.write_str("i")?,
+            None => {}
+        }
+        if self.flags.whole_word {
+            f.write_str("w")?;
+        }
+        if self.flags.literal {
+            f.write_str("l")?;
+        }
+        if self.flags.fuzzy {
+            f.write_str("~")?;
+        }
+        f.write_str(&quote_pattern(&self.text))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum FilterExpr {
-    Pattern(Regex),
+    Pattern(Term),
     And(Box<FilterExpr>, Box<FilterExpr>),
     Or(Box<FilterExpr>, Box<FilterExpr>),
+    /// Exactly one side matches.
+    Xor(Box<FilterExpr>, Box<FilterExpr>),
     Not(Box<FilterExpr>),
 }
 
+/// A sub-expression and whether it matched, one row of the playground.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainNode {
+    pub depth: usize,
+    pub label: String,
+    pub matched: bool,
+    /// Byte ranges a term matched; empty for operators.
+    pub spans: Vec<(usize, usize)>,
+}
+
 impl FilterExpr {
     pub fn matches(&self, text: &str) -> bool {
         match self {
-            FilterExpr::Pattern(re) => re.is_match(text),
+            FilterExpr::Pattern(term) => term.is_match(text),
             FilterExpr::And(a, b) => a.matches(text) && b.matches(text),
             FilterExpr::Or(a, b) => a.matches(text) || b.matches(text),
+            FilterExpr::Xor(a, b) => a.matches(text) != b.matches(text),
             FilterExpr::Not(e) => !e.matches(text),
         }
     }
@@ -27,14 +240,62 @@ impl FilterExpr {
         matches
     }
 
-    fn collect_matches(&self, text: &str, matches: &mut Vec<(usize, usize)>) {
+    /// Every sub-expression, outermost first, with whether it matches `text`.
+    /// Chains like `a && b && c` are listed as one node with three children.
+    pub fn explain(&self, text: &str) -> Vec<ExplainNode> {
+        let mut nodes = Vec::new();
+        self.explain_into(text, 0, &mut nodes);
+        nodes
+    }
+
+    fn explain_into(&self, text: &str, depth: usize, nodes: &mut Vec<ExplainNode>) {
+        let label = match self {
+            FilterExpr::Pattern(term) => term.to_string(),
+            FilterExpr::And(..) => "AND".to_string(),
+            FilterExpr::Or(..) => "OR".to_string(),
+            FilterExpr::Xor(..) => "XOR".to_string(),
+            FilterExpr::Not(_) => "NOT".to_string(),
+        };
+        let spans = match self {
+            FilterExpr::Pattern(_) => self.find_all_matches(text),
+            _ => Vec::new(),
+        };
+        nodes.push(ExplainNode {
+            depth,
+            label,
+            matched: self.matches(text),
+            spans,
+        });
+        let mut children = Vec::new();
         match self {
-            FilterExpr::Pattern(re) => {
-                for m in re.find_iter(text) {
-                    matches.push((m.start(), m.end()));
+            FilterExpr::Pattern(_) => {}
+            FilterExpr::Not(e) => children.push(e.as_ref()),
+            FilterExpr::And(..) | FilterExpr::Or(..) | FilterExpr::Xor(..) => self.chain_operands(&mut children),
+        }
+        for child in children {
+            child.explain_into(text, depth + 1, nodes);
+        }
+    }
+
+    fn chain_operands<'a>(&'a self, out: &mut Vec<&'a FilterExpr>) {
+        match self {
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) | FilterExpr::Xor(a, b) => {
+                for side in [a, b] {
+                    if std::mem::discriminant(side.as_ref()) == std::mem::discriminant(self) {
+                        side.chain_operands(out);
+                    } else {
+                        out.push(side);
+                    }
                 }
             }
-            FilterExpr::And(a, b) | FilterExpr::Or(a, b) => {
+            _ => out.push(self),
+        }
+    }
+
+    fn collect_matches(&self, text: &str, matches: &mut Vec<(usize, usize)>) {
+        match self {
+            FilterExpr::Pattern(term) => term.collect_matches(text, matches),
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) | FilterExpr::Xor(a, b) => {
                 a.collect_matches(text, matches);
                 b.collect_matches(text, matches);
             }
@@ -43,6 +304,70 @@ impl FilterExpr {
     }
 }
 
+impl FilterExpr {
+    /// Binding strength: `||` < `xor` < `&&` < `!` < terms.
+    fn precedence(&self) -> u8 {
+        match self {
+            FilterExpr::Or(..) => 0,
+            FilterExpr::Xor(..) => 1,
+            FilterExpr::And(..) => 2,
+            FilterExpr::Not(_) => 3,
+            FilterExpr::Pattern(_) => 4,
+        }
+    }
+
+    /// The expression with every operator's operands in parentheses, showing
+    /// how it was grouped: `a || b && !c` is `(a || (b && (!c)))`.
+    pub fn grouped(&self) -> String {
+        match self {
+            FilterExpr::Pattern(term) => term.to_string(),
+            FilterExpr::Not(e) => format!("(!{})", e.grouped()),
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) | FilterExpr::Xor(a, b) => {
+                format!("({} {} {})", a.grouped(), self.operator(), b.grouped())
+            }
+        }
+    }
+
+    fn operator(&self) -> &'static str {
+        match self {
+            FilterExpr::Or(..) => "||",
+            FilterExpr::Xor(..) => "xor",
+            FilterExpr::And(..) => "&&",
+            FilterExpr::Not(_) => "!",
+            FilterExpr::Pattern(_) => "",
+        }
+    }
+
+    /// Write `self` in parentheses if it binds looser than `min`.
+    fn fmt_at(&self, f: &mut fmt::Formatter, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Formats as an expression that parses back to the same tree.
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterExpr::Pattern(term) => write!(f, "{}", term),
+            FilterExpr::Not(e) => {
+                f.write_str("!")?;
+                e.fmt_at(f, self.precedence())
+            }
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) | FilterExpr::Xor(a, b) => {
+                let prec = self.precedence();
+                a.fmt_at(f, prec)?;
+                write!(f, " {} ", self.operator())?;
+                // Chains nest to the left, so a right-hand chain needs parentheses.
+                b.fmt_at(f, prec + 1)
+            }
+        }
+    }
+}
+
 fn merge_overlapping(ranges: &mut Vec<(usize, usize)>) {
     if ranges.is_empty() {
         return;
@@ -59,17 +384,103 @@ fn merge_overlapping(ranges: &mut Vec<(usize, usize)>) {
     ranges.truncate(write + 1);
 }
 
+/// Quote a regex so the tokenizer passes it through unchanged.
+fn quote_pattern(re: &str) -> String {
+    format!("\"{}\"", re.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A quoted filter term matching `text` literally.
+pub fn literal_pattern(text: &str) -> String {
+    quote_pattern(&regex::escape(text))
+}
+
+/// A filter term matching `text` where it isn't part of a longer word or ID.
+pub fn word_pattern(text: &str) -> String {
+    // No look-around in the regex crate, so the boundaries match a character.
+    quote_pattern(&format!(r"(?:^|[^\w-]){}(?:$|[^\w-])", regex::escape(text)))
+}
+
+/// A filter term matching a whole line shaped like `template`: its words in
+/// order, with `#` standing for any word holding a digit.
+pub fn template_pattern(template: &str) -> String {
+    let words: Vec<String> = template
+        .split_whitespace()
+        .map(|word| if word == "#" { r"\S*\d\S*".to_string() } else { regex::escape(word) })
+        .collect();
+    quote_pattern(&format!(r"^\s*{}\s*$", words.join(r"\s+")))
+}
+
+/// A filter term matching a JSON `key: value` pair, whitespace-insensitive.
+pub fn field_pattern(key: &str, value: &str) -> String {
+    quote_pattern(&format!(r"{}\s*:\s*{}", regex::escape(key), regex::escape(value)))
+}
+
+/// A filter term matching logfmt `key=value` or JSON `"key": value`, the
+/// value possibly quoted but not run into a longer one.
+pub fn key_value_pattern(key: &str, value: &str) -> String {
+    quote_pattern(&format!(
+        r#"(?:^|[^\w.-])["']?{}["']?\s*[:=]\s*["']?{}(?:$|[^\w.-])"#,
+        regex::escape(key),
+        regex::escape(value)
+    ))
+}
+
+/// Narrow an existing filter expression by another term.
+pub fn and_clause(existing: &str, clause: &str) -> String {
+    if existing.trim().is_empty() {
+        clause.to_string()
+    } else {
+        format!("({}) && {}", existing.trim(), clause)
+    }
+}
+
+/// Deepest nesting of parentheses and `!` a filter may have.
+pub const MAX_NESTING: usize = 64;
+/// Most tokens a filter may have, which also bounds how deep `&&` / `||`
+/// chains make the tree.
+pub const MAX_TOKENS: usize = 1024;
+
+/// Parse a filter expression. The grammar, loosest binding first; whitespace
+/// separates tokens and is otherwise ignored:
+///
+/// ```text
+/// expr    = xor { "||" xor }
+/// xor     = and { "xor" and }
+/// and     = unary { "&&" unary }
+/// unary   = "!" unary | primary
+/// primary = "(" expr ")" | term
+/// term    = [ modifiers ] ( '"' text '"' | "'" text "'" ) | [ modifiers ] "/" text "/" | word
+/// modifiers = { "c" | "i" | "w" | "l" | "r" | "~" }
+/// word    = one or more characters other than whitespace and ( ) & | !
+/// ```
+///
+/// All binary operators group to the left, so `a xor b xor c` is
+/// `(a xor b) xor c`, true when an odd number of them match. `xor` is an
+/// operator only as a whole bare word, in any case; quote it (`"xor"`) to
+/// search for the word.
+///
+/// Inside quotes a backslash before the closing quote character or another
+/// backslash escapes it; any other backslash is kept for the regex. `/…/` is a regex only where it
+/// ends the term, so bare paths like `/api/users` are words. A quote with no
+/// closing one is an error, unless modifiers came before it, in which case
+/// the whole run is a word. `FilterExpr`'s `Display` writes a canonical form
+/// that parses back to the same tree.
 pub fn parse_filter(input: &str) -> Result<FilterExpr> {
     let input = input.trim();
     if input.is_empty() {
         return Err(anyhow!("Empty filter expression"));
     }
     let tokens = tokenize(input)?;
-    let (expr, pos) = parse_or(&tokens, 0)?;
-    if pos != tokens.len() {
-        return Err(anyhow!("Unexpected token at position {}", pos));
+    if tokens.len() > MAX_TOKENS {
+        return Err(anyhow!("Expression too long ({} tokens, at most {})", tokens.len(), MAX_TOKENS));
+    }
+    let mut parser = Parser { tokens, pos: 0, depth: 0 };
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.pos) {
+        Some((Token::RParen, col)) => Err(anyhow!("Unmatched ')' at column {}", col + 1)),
+        Some((_, col)) => Err(anyhow!("Expected '&&', '||' or 'xor' at column {}", col + 1)),
+        None => Ok(expr),
     }
-    Ok(expr)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -78,136 +489,216 @@ enum Token {
     RParen,
     And,
     Or,
+    Xor,
     Not,
-    Pattern(String),
+    Pattern(String, TermFlags),
 }
 
-fn tokenize(input: &str) -> Result<Vec<Token>> {
-    let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '(' | ')' | '&' | '|' | '!') || c.is_whitespace()
+}
+
+/// Read a string closed by `quote` starting after the opening quote. `\` +
+/// quote and `\\` are unescaped; other escapes are left for the regex.
+fn read_quoted(chars: &[char], mut i: usize, quote: char) -> Option<(String, usize)> {
+    let mut text = String::new();
+    while i < chars.len() {
+        match chars[i] {
+            c if c == quote => return Some((text, i + 1)),
+            '\\' if i + 1 < chars.len() => {
+                let escaped = chars[i + 1];
+                if escaped != quote && escaped != '\\' {
+                    text.push('\\');
+                }
+                text.push(escaped);
+                i += 2;
+            }
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    None
+}
 
-    while let Some(&c) = chars.peek() {
+fn parse_flags(letters: &str) -> TermFlags {
+    let mut flags = TermFlags::default();
+    for c in letters.chars() {
         match c {
-            ' ' | '\t' => {
-                chars.next();
+            'c' => flags.case_sensitive = Some(true),
+            'i' => flags.case_sensitive = Some(false),
+            'w' => flags.whole_word = true,
+            'l' => flags.literal = true,
+            'r' => flags.literal = false,
+            _ => flags.fuzzy = true,
+        }
+    }
+    flags
+}
+
+/// Tokens with the column (in characters) each starts at.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let token = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
             }
             '(' => {
-                tokens.push(Token::LParen);
-                chars.next();
+                i += 1;
+                Token::LParen
             }
             ')' => {
-                tokens.push(Token::RParen);
-                chars.next();
-            }
-            '&' => {
-                chars.next();
-                if chars.next() == Some('&') {
-                    tokens.push(Token::And);
-                } else {
-                    return Err(anyhow!("Expected '&&'"));
-                }
+                i += 1;
+                Token::RParen
             }
-            '|' => {
-                chars.next();
-                if chars.next() == Some('|') {
-                    tokens.push(Token::Or);
-                } else {
-                    return Err(anyhow!("Expected '||'"));
+            c @ ('&' | '|') => {
+                if chars.get(i + 1) != Some(&c) {
+                    return Err(anyhow!("Expected '{}{}' at column {}", c, c, i + 1));
                 }
+                i += 2;
+                if c == '&' { Token::And } else { Token::Or }
             }
             '!' => {
-                tokens.push(Token::Not);
-                chars.next();
-            }
-            '"' | '\'' => {
-                let quote = c;
-                chars.next();
-                let mut pattern = String::new();
-                loop {
-                    match chars.next() {
-                        Some(ch) if ch == quote => break,
-                        Some('\\') => {
-                            if let Some(escaped) = chars.next() {
-                                if escaped == quote || escaped == '\\' {
-                                    pattern.push(escaped);
-                                } else {
-                                    pattern.push('\\');
-                                    pattern.push(escaped);
-                                }
-                            }
-                        }
-                        Some(ch) => pattern.push(ch),
-                        None => return Err(anyhow!("Unterminated string")),
-                    }
-                }
-                tokens.push(Token::Pattern(pattern));
+                i += 1;
+                Token::Not
             }
             _ => {
-                let mut pattern = String::new();
-                while let Some(&ch) = chars.peek() {
-                    if ch == '(' || ch == ')' || ch == '&' || ch == '|' || ch == '!' || ch == ' ' {
-                        break;
-                    }
-                    pattern.push(ch);
-                    chars.next();
+                while i < chars.len() && !is_delimiter(chars[i]) && !matches!(chars[i], '"' | '\'' | '/') {
+                    i += 1;
                 }
-                if !pattern.is_empty() {
-                    tokens.push(Token::Pattern(pattern));
+                let prefix: String = chars[start..i].iter().collect();
+                let is_modifiers = prefix.chars().all(|c| MODIFIERS.contains(c));
+                let quote = chars.get(i).copied().filter(|_| is_modifiers);
+                let quoted = match quote {
+                    Some(q @ ('"' | '\'')) => match read_quoted(&chars, i + 1, q) {
+                        None if prefix.is_empty() => return Err(anyhow!("Unterminated string at column {}", i + 1)),
+                        quoted => quoted,
+                    },
+                    // A bare `/api/` stays a plain word.
+                    Some('/') if !prefix.is_empty() => read_quoted(&chars, i + 1, '/')
+                        .filter(|&(_, end)| chars.get(end).is_none_or(|&c| is_delimiter(c))),
+                    _ => None,
+                };
+                match quoted {
+                    Some((text, end)) => {
+                        i = end;
+                        Token::Pattern(text, parse_flags(&prefix))
+                    }
+                    None => {
+                        while i < chars.len() && !is_delimiter(chars[i]) {
+                            i += 1;
+                        }
+                        let word: String = chars[start..i].iter().collect();
+                        if word.eq_ignore_ascii_case("xor") {
+                            Token::Xor
+                        } else {
+                            Token::Pattern(word, TermFlags::default())
+                        }
+                    }
                 }
             }
-        }
+        };
+        tokens.push((token, start));
     }
     Ok(tokens)
 }
 
-fn parse_or(tokens: &[Token], pos: usize) -> Result<(FilterExpr, usize)> {
-    let (mut left, mut pos) = parse_and(tokens, pos)?;
-    while pos < tokens.len() && tokens[pos] == Token::Or {
-        let (right, new_pos) = parse_and(tokens, pos + 1)?;
-        left = FilterExpr::Or(Box::new(left), Box::new(right));
-        pos = new_pos;
-    }
-    Ok((left, pos))
+/// Recursive descent over the tokens, one method per grammar rule.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Parentheses and `!` currently open.
+    depth: usize,
 }
 
-fn parse_and(tokens: &[Token], pos: usize) -> Result<(FilterExpr, usize)> {
-    let (mut left, mut pos) = parse_unary(tokens, pos)?;
-    while pos < tokens.len() && tokens[pos] == Token::And {
-        let (right, new_pos) = parse_unary(tokens, pos + 1)?;
-        left = FilterExpr::And(Box::new(left), Box::new(right));
-        pos = new_pos;
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
     }
-    Ok((left, pos))
-}
 
-fn parse_unary(tokens: &[Token], pos: usize) -> Result<(FilterExpr, usize)> {
-    if pos >= tokens.len() {
-        return Err(anyhow!("Unexpected end of expression"));
+    /// Column of the next token, or just past the end.
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or_else(
+            || self.tokens.last().map_or(0, |(_, col)| col + 1),
+            |(_, col)| *col,
+        ) + 1
     }
-    if tokens[pos] == Token::Not {
-        let (expr, new_pos) = parse_unary(tokens, pos + 1)?;
-        return Ok((FilterExpr::Not(Box::new(expr)), new_pos));
+
+    fn nest(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(anyhow!("Nested deeper than {} levels at column {}", MAX_NESTING, self.column()));
+        }
+        Ok(())
     }
-    parse_primary(tokens, pos)
-}
 
-fn parse_primary(tokens: &[Token], pos: usize) -> Result<(FilterExpr, usize)> {
-    if pos >= tokens.len() {
-        return Err(anyhow!("Unexpected end of expression"));
+    fn or(&mut self) -> Result<FilterExpr> {
+        let mut left = self.xor()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = FilterExpr::Or(Box::new(left), Box::new(self.xor()?));
+        }
+        Ok(left)
     }
-    match &tokens[pos] {
-        Token::LParen => {
-            let (expr, new_pos) = parse_or(tokens, pos + 1)?;
-            if new_pos >= tokens.len() || tokens[new_pos] != Token::RParen {
-                return Err(anyhow!("Missing closing parenthesis"));
-            }
-            Ok((expr, new_pos + 1))
+
+    fn xor(&mut self) -> Result<FilterExpr> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Xor) {
+            self.pos += 1;
+            left = FilterExpr::Xor(Box::new(left), Box::new(self.and()?));
         }
-        Token::Pattern(p) => {
-            let re = Regex::new(p).map_err(|e| anyhow!("Invalid regex '{}': {}", p, e))?;
-            Ok((FilterExpr::Pattern(re), pos + 1))
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<FilterExpr> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = FilterExpr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<FilterExpr> {
+        if self.peek() != Some(&Token::Not) {
+            return self.primary();
+        }
+        self.pos += 1;
+        self.nest()?;
+        let expr = self.unary()?;
+        self.depth -= 1;
+        Ok(FilterExpr::Not(Box::new(expr)))
+    }
+
+    fn primary(&mut self) -> Result<FilterExpr> {
+        let column = self.column();
+        match self.tokens.get(self.pos).map(|(token, _)| token.clone()) {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                self.nest()?;
+                let expr = self.or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(anyhow!("Missing ')' for the '(' at column {}", column));
+                }
+                self.pos += 1;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            Some(Token::Pattern(text, flags)) => {
+                self.pos += 1;
+                Ok(FilterExpr::Pattern(Term::new(&text, flags)?))
+            }
+            Some(Token::RParen) => Err(anyhow!("Expected a term before ')' at column {}", column)),
+            Some(_) => Err(anyhow!("Expected a term at column {}", column)),
+            None => Err(anyhow!("Unexpected end of expression")),
         }
-        _ => Err(anyhow!("Unexpected token")),
     }
 }
 
@@ -263,6 +754,175 @@ mod tests {
         assert!(!filter.matches("debug mode error"));
     }
 
+    #[test]
+    fn test_literal_pattern_round_trips() {
+        let text = r#"GET /a.b?x="1"&y=\d (ok)"#;
+        let expr = and_clause("error || warn", &literal_pattern(text));
+        let filter = parse_filter(&expr).unwrap();
+        assert!(filter.matches(&format!("error {}", text)));
+        assert!(!filter.matches("error GET /axb?x=\"1\"&y=\\d (ok)"));
+        assert!(!filter.matches(text));
+    }
+
+    #[test]
+    fn test_explain_flattens_chains() {
+        let filter = parse_filter("(error || warn) && !debug && timeout").unwrap();
+        let rows: Vec<_> = filter
+            .explain("warn: debug timeout")
+            .into_iter()
+            .map(|n| (n.depth, n.label, n.matched))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0, "AND".to_string(), false),
+                (1, "OR".to_string(), true),
+                (2, "\"error\"".to_string(), false),
+                (2, "\"warn\"".to_string(), true),
+                (1, "NOT".to_string(), false),
+                (2, "\"debug\"".to_string(), true),
+                (1, "\"timeout\"".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let filter = parse_filter(r#"c"Error""#).unwrap();
+        assert!(filter.matches("Error: x"));
+        assert!(!filter.matches("error: x"));
+        assert!(!parse_filter("error").unwrap().matches("ERROR: x"));
+        assert!(parse_filter(r#"i"Error""#).unwrap().matches("ERROR: x"));
+        assert!(parse_filter("i/err(or)?/").unwrap().matches("ERR: x"));
+
+        let filter = parse_filter(r#"w"err""#).unwrap();
+        assert!(filter.matches("err: x"));
+        assert!(!filter.matches("error: x"));
+
+        let filter = parse_filter(r#"l"a.b" && r/\d+ms/"#).unwrap();
+        assert!(filter.matches("a.b took 12ms"));
+        assert!(!filter.matches("axb took 12ms"));
+        // A bare path is still a plain term, slashes included.
+        assert!(parse_filter("/api/users").unwrap().matches("GET /api/users"));
+        assert!(!parse_filter("/api/").unwrap().matches("GET api"));
+    }
+
+    #[test]
+    fn test_fuzzy_term() {
+        let filter = parse_filter(r#"~"paymnt gatway""#).unwrap();
+        assert!(filter.matches("Payment Gateway timeout"));
+        assert!(filter.matches("gateway rejected payment"));
+        assert!(!filter.matches("Payment service timeout"));
+        assert!(!parse_filter(r#"~"pymt""#).unwrap().matches("payment"));
+        assert_eq!(filter.to_string(), r#"~"paymnt gatway""#);
+
+        let text = "Paymant ok";
+        let ranges = parse_filter(r#"~"payment""#).unwrap().find_all_matches(text);
+        assert_eq!(ranges, vec![(0, 4), (5, 7)]);
+        let text = format!("{} Paymant ok", "x".repeat(500));
+        let ranges = parse_filter(r#"~"payment""#).unwrap().find_all_matches(&text);
+        assert_eq!(ranges, vec![(501, 505), (506, 508)]);
+        assert!(parse_filter(r#"w~"payment""#).is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for input in [
+            r#"(error || warn) && !debug"#,
+            r#"a && (b && c)"#,
+            r#"!(a || b) || cw"Err \"x\"""#,
+            r#"l"a.b" && /\d+/"#,
+        ] {
+            let expr = parse_filter(input).unwrap();
+            let text = expr.to_string();
+            assert_eq!(parse_filter(&text).unwrap().to_string(), text, "{}", input);
+        }
+        assert_eq!(parse_filter("a && (b && c)").unwrap().to_string(), r#""a" && ("b" && "c")"#);
+        assert_eq!(parse_filter("(a && b) && c").unwrap().to_string(), r#""a" && "b" && "c""#);
+        assert_eq!(parse_filter(r#"r/\d+/"#).unwrap().to_string(), r#""\\d+""#);
+    }
+
+    #[test]
+    fn test_parser_limits_and_errors() {
+        let error = |input: &str| parse_filter(input).unwrap_err().to_string();
+        assert_eq!(error("a & b"), "Expected '&&' at column 3");
+        assert_eq!(error("(a || b"), "Missing ')' for the '(' at column 1");
+        assert_eq!(error("a )"), "Unmatched ')' at column 3");
+        assert_eq!(error("a b"), "Expected '&&', '||' or 'xor' at column 3");
+        assert_eq!(error("a && ()"), "Expected a term before ')' at column 7");
+        assert_eq!(error("\"open"), "Unterminated string at column 1");
+        assert!(error(&format!("{}a{}", "(".repeat(100), ")".repeat(100))).starts_with("Nested deeper than 64"));
+        assert!(error(&format!("{}a", "!".repeat(500))).starts_with("Nested deeper than 64"));
+        assert!(error(&vec!["a"; 2000].join(" && ")).starts_with("Expression too long"));
+        assert!(parse_filter(&format!("{}a{}", "(".repeat(64), ")".repeat(64))).is_ok());
+    }
+
+    #[test]
+    fn test_xor_and_precedence() {
+        let filter = parse_filter("error xor warn").unwrap();
+        assert!(filter.matches("error"));
+        assert!(filter.matches("warn"));
+        assert!(!filter.matches("error warn"));
+        assert!(!filter.matches("info"));
+        // An odd number of matches.
+        assert!(parse_filter("a XOR b xor c").unwrap().matches("a b c"));
+        assert!(parse_filter(r#""xor""#).unwrap().matches("xor gate"));
+
+        let grouped = |input: &str| parse_filter(input).unwrap().grouped();
+        assert_eq!(grouped("a || b xor c && !d"), r#"("a" || ("b" xor ("c" && (!"d"))))"#);
+        assert_eq!(grouped("a && b || c"), r#"(("a" && "b") || "c")"#);
+        assert_eq!(grouped("!!a && ((b))"), r#"((!(!"a")) && "b")"#);
+        assert_eq!(grouped("a xor b xor c"), r#"(("a" xor "b") xor "c")"#);
+        assert_eq!(parse_filter("(a || b) xor c").unwrap().to_string(), r#"("a" || "b") xor "c""#);
+        assert_eq!(parse_filter("a xor (b && c)").unwrap().to_string(), r#""a" xor "b" && "c""#);
+        assert_eq!(parse_filter("!(a xor b)").unwrap().to_string(), r#"!("a" xor "b")"#);
+    }
+
+    /// Random inputs never panic, and whatever parses prints in a canonical
+    /// form that parses back to itself.
+    #[test]
+    fn test_parser_properties() {
+        const PIECES: [&str; 21] = [
+            "a", "Err", " ", "(", ")", "&&", "||", " xor ", "!", "&", "|", "\"", "'", "/", "\\", "c", "w~", "l\"x.y\"", "/\\d+/", "é", "\t",
+        ];
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = |below: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % below as u64) as usize
+        };
+        let mut parsed = 0;
+        for _ in 0..5_000 {
+            let input: String = (0..next(12) + 1).map(|_| PIECES[next(PIECES.len())]).collect();
+            let Ok(expr) = parse_filter(&input) else {
+                continue;
+            };
+            parsed += 1;
+            let canonical = expr.to_string();
+            let again = parse_filter(&canonical).unwrap_or_else(|e| panic!("{:?} -> {:?}: {}", input, canonical, e));
+            assert_eq!(again.to_string(), canonical, "{:?}", input);
+            for text in ["a Err", "x.y 42", "", "é/\"'"] {
+                assert_eq!(again.matches(text), expr.matches(text), "{:?} on {:?}", input, text);
+            }
+        }
+        assert!(parsed > 250);
+    }
+
+    #[test]
+    fn test_field_pattern() {
+        let filter = parse_filter(&field_pattern(r#""user""#, r#""bob""#)).unwrap();
+        assert!(filter.matches(r#"{"user": "bob", "id": 1}"#));
+        assert!(!filter.matches(r#"{"owner": "bob", "user": "al"}"#));
+    }
+
+    #[test]
+    fn test_word_pattern() {
+        let filter = parse_filter(&word_pattern("r7")).unwrap();
+        assert!(filter.matches("req_id=r7") && filter.matches("r7"));
+        assert!(!filter.matches("req_id=r77") && !filter.matches("xr7-2"));
+    }
+
     #[test]
     fn test_quoted_backslash_preserved() {
         // \[ and \] should be passed through to regex as literal bracket matchers
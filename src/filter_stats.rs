@@ -0,0 +1,108 @@
+use crate::filter::{parse_filter, FilterExpr};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+/// Seconds the recent hit rate covers.
+const RATE_WINDOW_SECS: i64 = 60;
+
+/// Match counts of one filter over the buffer, kept up to date as lines
+/// arrive.
+#[derive(Clone)]
+pub struct FilterStat {
+    /// Saved name, or `None` for the active filter.
+    pub name: Option<String>,
+    pub text: String,
+    expr: FilterExpr,
+    pub matches: usize,
+    pub seen: usize,
+    /// Matched and seen lines per receipt second, oldest first.
+    recent: VecDeque<(i64, usize, usize)>,
+}
+
+impl FilterStat {
+    pub fn new(name: Option<String>, text: &str) -> Result<Self> {
+        Ok(Self {
+            name,
+            text: text.to_string(),
+            expr: parse_filter(text)?,
+            matches: 0,
+            seen: 0,
+            recent: VecDeque::new(),
+        })
+    }
+
+    pub fn observe(&mut self, content: &str, time: DateTime<Local>) {
+        let matched = self.expr.matches(content);
+        self.seen += 1;
+        self.matches += usize::from(matched);
+        let sec = time.timestamp();
+        match self.recent.back_mut() {
+            Some((last, m, s)) if *last == sec => {
+                *m += usize::from(matched);
+                *s += 1;
+            }
+            _ => self.recent.push_back((sec, usize::from(matched), 1)),
+        }
+        while self.recent.front().is_some_and(|&(s, _, _)| s + RATE_WINDOW_SECS <= sec) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// The expression as `parse_filter` understood it, in canonical form.
+    pub fn canonical(&self) -> String {
+        self.expr.to_string()
+    }
+
+    pub fn reset(&mut self) {
+        self.matches = 0;
+        self.seen = 0;
+        self.recent.clear();
+    }
+
+    /// Share of all lines that matched.
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.seen > 0).then(|| self.matches as f64 / self.seen as f64)
+    }
+
+    /// Lines matched in the last minute, and their share of the lines seen.
+    pub fn last_minute(&self, now: DateTime<Local>) -> (usize, Option<f64>) {
+        let cutoff = now.timestamp() - RATE_WINDOW_SECS;
+        let (matched, seen) = self
+            .recent
+            .iter()
+            .filter(|&&(sec, _, _)| sec > cutoff)
+            .fold((0, 0), |(m, s), &(_, dm, ds)| (m + dm, s + ds));
+        (matched, (seen > 0).then(|| matched as f64 / seen as f64))
+    }
+
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("(active)")
+    }
+}
+
+pub fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or_else(|| "-".to_string(), |r| format!("{:.1}%", r * 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_counts_and_last_minute() {
+        let start = Local::now();
+        let mut stat = FilterStat::new(Some("errors".to_string()), "error").unwrap();
+        stat.observe("error: disk full", start);
+        stat.observe("info: ok", start);
+        stat.observe("error: retry", start + Duration::seconds(90));
+        stat.observe("info: ok", start + Duration::seconds(91));
+        assert_eq!((stat.matches, stat.seen), (2, 4));
+        assert_eq!(stat.hit_rate(), Some(0.5));
+        let (recent, rate) = stat.last_minute(start + Duration::seconds(100));
+        assert_eq!(recent, 1);
+        assert_eq!(format_rate(rate), "50.0%");
+        assert_eq!(stat.last_minute(start + Duration::seconds(200)), (0, None));
+    }
+}
@@ -0,0 +1,34 @@
+/// Whether `text` matches a shell-style glob: `*` and `?` stay within a
+/// path component, `**` crosses them.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p {
+            [] => t.is_empty(),
+            ['*', '*', rest @ ..] => {
+                let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+                (0..=t.len()).any(|i| matches(rest, &t[i..]))
+            }
+            ['*', rest @ ..] => (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != '/')
+                .any(|i| matches(rest, &t[i..])),
+            ['?', rest @ ..] => t.first().is_some_and(|&c| c != '/') && matches(rest, &t[1..]),
+            [c, rest @ ..] => t.first() == Some(c) && matches(rest, &t[1..]),
+        }
+    }
+    matches(&pattern, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.log", "app.log"));
+        assert!(!glob_match("*.log", "logs/app.log"));
+        assert!(glob_match("**/*.log", "logs/app.log"));
+        assert!(glob_match("worker-?.log", "worker-3.log"));
+        assert!(!glob_match("worker-?.log", "worker-12.log"));
+    }
+}
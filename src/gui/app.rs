@@ -1,6 +1,16 @@
-use crate::core::{format_relative_time, get_time_age, ListenState, LogLine, TimeAge};
-use crate::source::{start_source, LogSource, SourceEvent};
+use crate::bundle;
+use crate::checkpoint;
+use crate::columns::{cell_text, visible_columns, Column, ColumnKind, TimeFormat};
+use crate::core::{format_relative_time, get_time_age, thread_of, DetailState, Lanes, ListenState, LogLine, TimeAge, MAX_LANES};
+use crate::metrics;
+use crate::plugin;
+use crate::rdns::PeerNames;
+use crate::replay::{start_replay, Replay};
+use crate::sample::Sampling;
+use crate::source::{self, start_source, CommandSource, FileOptions, ListenPort, LogSource, SourceEvent};
+use crate::stacktrace;
 use crate::state::AppState;
+use crate::time_display::TimeDisplay;
 use async_channel::Receiver;
 use dioxus::html::MountedData;
 use dioxus::prelude::*;
@@ -8,10 +18,12 @@ use std::rc::Rc;
 use std::sync::Arc;
 use fancy_regex::Regex;
 use std::path::PathBuf;
-use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
-use super::components::{ListenPopup, LogLineContent};
+use super::components::{
+    DetailPopup, FilterStatsPopup, ListenPopup, LogLineContent, Minimap, OutlinePanel, PlaygroundPopup, PluginsPopup,
+    WatchPanel, WhyPopup,
+};
 use super::state::GuiAppState;
 use super::style::CSS;
 
@@ -20,27 +32,42 @@ const BASE_RENDER_THRESHOLD_MS: f64 = 50.0;
 const MIN_RENDER_THRESHOLD_MS: f64 = 5.0;
 const THRESHOLD_DECAY_FACTOR: f64 = 0.7;
 
+/// (filtered index, line index, offset, line, content, folded stack trace as
+/// ((hidden frame count, highlight matches in them), text after the fold),
+/// near-duplicates hidden).
+/// A note on the line and whether it is shown inline.
+type LineNote = Option<(String, bool)>;
+type VisibleLine = (usize, usize, f64, LogLine, Vec<(String, String)>, String, Option<((usize, usize), String)>, LineNote, usize);
+
 #[derive(Props, Clone, PartialEq)]
 pub struct GuiAppProps {
     pub file: Option<PathBuf>,
-    pub port: Option<u16>,
+    pub file_options: FileOptions,
+    pub ports: Vec<ListenPort>,
+    pub replay: bool,
+    pub command: Option<CommandSource>,
+    pub watch_dir: Option<(PathBuf, String)>,
 }
 
 #[component]
 pub fn GuiApp(props: GuiAppProps) -> Element {
-    let mut app_state = use_signal(|| GuiAppState::new());
+    let mut app_state = use_signal(GuiAppState::new);
     let mut source_rx: Signal<Option<Receiver<SourceEvent>>> = use_signal(|| None);
     let mut container_element: Signal<Option<Rc<MountedData>>> = use_signal(|| None);
-    let mut listen_state = use_signal(|| ListenState::new(props.port));
+    let mut listen_state = use_signal(|| ListenState::new(props.ports.iter().map(|listen| listen.port).collect()));
     let mut pending_scroll_to_bottom = use_signal(|| false);
-    let mut pending_scroll_to_top = use_signal(|| false);
+    let mut pending_scroll_to_match = use_signal(|| false);
 
     use_effect({
         let file = props.file.clone();
-        let port = props.port;
+        let file_options = props.file_options;
+        let ports = props.ports.clone();
+        let replay = props.replay;
+        let command = props.command.clone();
+        let watch_dir = props.watch_dir.clone();
         move || {
-            let (sync_tx, sync_rx) = mpsc::channel::<SourceEvent>();
-            let (async_tx, async_rx) = async_channel::unbounded::<SourceEvent>();
+            let (sync_tx, sync_rx) = source::channel();
+            let (async_tx, async_rx) = async_channel::bounded::<SourceEvent>(source::SOURCE_CAPACITY);
 
             std::thread::spawn(move || {
                 while let Ok(event) = sync_rx.recv() {
@@ -50,10 +77,23 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 }
             });
 
-            let source = if let Some(port) = port {
-                LogSource::Network(port)
+            if let Some(path) = file.as_deref().filter(|p| bundle::is_bundle(p) && !replay) {
+                match bundle::Bundle::read(path) {
+                    Ok(b) => app_state.write().load_bundle(b),
+                    Err(e) => app_state.write().status_message = Some(format!("{:#}", e)),
+                }
+                return;
+            }
+
+            let sync_tx = plugin::attach(sync_tx);
+            let source = if !ports.is_empty() {
+                LogSource::Network(ports.clone(), super::listen_options())
+            } else if let Some(command) = command.clone() {
+                LogSource::Command(command)
+            } else if let Some((dir, pattern)) = watch_dir.clone() {
+                LogSource::WatchDir(dir, pattern, file_options)
             } else if let Some(ref path) = file {
-                LogSource::File(path.clone())
+                LogSource::File(path.clone(), file_options)
             } else {
                 LogSource::Stdin
             };
@@ -68,7 +108,22 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 }
             };
 
-            if let Err(e) = start_source(source, sync_tx, line_start_regex) {
+            if let (true, LogSource::File(path, _)) = (replay, &source) {
+                match start_replay(path, sync_tx, line_start_regex) {
+                    Ok(handle) => {
+                        app_state.write().replay = Some(handle);
+                        source_rx.set(Some(async_rx));
+                    }
+                    Err(e) => {
+                        app_state.write().status_message = Some(format!("Failed to start replay: {:#}", e));
+                    }
+                }
+                return;
+            }
+
+            if matches!(source, LogSource::Stdin) && plugin::has_source() {
+                source_rx.set(Some(async_rx));
+            } else if let Err(e) = start_source(source, sync_tx, line_start_regex) {
                 app_state.write().status_message = Some(format!("Failed to start source: {}", e));
             } else {
                 source_rx.set(Some(async_rx));
@@ -85,7 +140,7 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
             async_std::task::sleep(Duration::from_millis(10)).await;
         };
 
-        let mut pending_lines: Vec<String> = Vec::new();
+        let mut pending_lines: Vec<SourceEvent> = Vec::new();
         let mut last_data_time: Option<Instant> = None;
         let mut current_threshold_ms: f64 = BASE_RENDER_THRESHOLD_MS;
 
@@ -97,15 +152,13 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 match async_std::future::timeout(wait_duration, rx.recv()).await {
                     Ok(Ok(event)) => {
                         match event {
-                            SourceEvent::Line(content) => {
-                                pending_lines.push(content);
+                            SourceEvent::Line(content, source) => {
+                                pending_lines.push(SourceEvent::Line(content, source));
                                 current_threshold_ms = (current_threshold_ms * THRESHOLD_DECAY_FACTOR)
                                     .max(MIN_RENDER_THRESHOLD_MS);
                             }
-                            SourceEvent::SystemLine(content) => {
-                                let mut state = app_state.write();
-                                state.add_line_with_update(content, false);
-                                state.version += 1;
+                            event @ (SourceEvent::SystemLine(_) | SourceEvent::Backfill(_) | SourceEvent::Checkpoint(..)) => {
+                                pending_lines.push(event);
                             }
                             SourceEvent::Error(e) => {
                                 app_state.write().status_message = Some(format!("Error: {}", e));
@@ -114,24 +167,40 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                                 listen_state.write().has_connection = true;
                                 let mut state = app_state.write();
                                 state.is_connected = true;
+                                let peer = state.peer_names.tag(&peer).into_owned();
                                 state.status_message = Some(format!("Connected: {}", peer));
                             }
                             SourceEvent::Disconnected(peer) => {
                                 let mut state = app_state.write();
                                 state.is_connected = false;
+                                let peer = state.peer_names.tag(&peer).into_owned();
                                 state.status_message = Some(format!("Disconnected: {}", peer));
                             }
                         }
                     }
                     Ok(Err(_)) => break,
                     Err(_) => {
-                        let lines_to_add: Vec<String> = pending_lines.drain(..).collect();
+                        let lines_to_add = std::mem::take(&mut pending_lines);
                         let mut state = app_state.write();
-                        let was_at_bottom = state.follow_tail;
-                        for line in lines_to_add {
-                            state.add_line(line);
+                        let was_at_bottom = state.log_state.follow_tail;
+                        let mut went_live = false;
+                        for event in lines_to_add {
+                            match event {
+                                SourceEvent::Line(line, source) => state.add_line(line, source),
+                                SourceEvent::SystemLine(content) => state.add_system_line(content),
+                                SourceEvent::Backfill(started) => {
+                                    state.log_state.backfill(started);
+                                    went_live = !started;
+                                }
+                                SourceEvent::Checkpoint(path, offset) => checkpoint::save(&path, offset),
+                                _ => {}
+                            }
                         }
-                        if was_at_bottom {
+                        metrics::record_buffer(state.log_state.lines.len(), &state.log_state.watches);
+                        if let Some(y) = state.match_scroll_y() {
+                            state.scroll_y = y;
+                            pending_scroll_to_match.set(true);
+                        } else if (was_at_bottom && state.log_state.follow_tail) || went_live {
                             state.scroll_to_bottom();
                             pending_scroll_to_bottom.set(true);
                         }
@@ -146,16 +215,15 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 match rx.recv().await {
                     Ok(event) => {
                         match event {
-                            SourceEvent::Line(content) => {
-                                pending_lines.push(content);
+                            SourceEvent::Line(content, source) => {
+                                pending_lines.push(SourceEvent::Line(content, source));
                                 last_data_time = Some(Instant::now());
                                 current_threshold_ms = (current_threshold_ms * THRESHOLD_DECAY_FACTOR)
                                     .max(MIN_RENDER_THRESHOLD_MS);
                             }
-                            SourceEvent::SystemLine(content) => {
-                                let mut state = app_state.write();
-                                state.add_line_with_update(content, false);
-                                state.version += 1;
+                            event @ (SourceEvent::SystemLine(_) | SourceEvent::Backfill(_) | SourceEvent::Checkpoint(..)) => {
+                                pending_lines.push(event);
+                                last_data_time = Some(Instant::now());
                             }
                             SourceEvent::Error(e) => {
                                 app_state.write().status_message = Some(format!("Error: {}", e));
@@ -164,11 +232,13 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                                 listen_state.write().has_connection = true;
                                 let mut state = app_state.write();
                                 state.is_connected = true;
+                                let peer = state.peer_names.tag(&peer).into_owned();
                                 state.status_message = Some(format!("Connected: {}", peer));
                             }
                             SourceEvent::Disconnected(peer) => {
                                 let mut state = app_state.write();
                                 state.is_connected = false;
+                                let peer = state.peer_names.tag(&peer).into_owned();
                                 state.status_message = Some(format!("Disconnected: {}", peer));
                             }
                         }
@@ -182,21 +252,20 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
     use_future(move || async move {
         loop {
             async_std::task::sleep(Duration::from_secs(1)).await;
+            if app_state.read().log_state.sampler.mode != Sampling::Off {
+                app_state.write().flush_sample();
+            }
             if app_state.read().show_time {
                 app_state.write().version += 1;
             }
+            if app_state.read().log_state.enrichment_stale() {
+                app_state.write().refresh_enrichment();
+            }
         }
     });
 
     use_future(move || async move {
         loop {
-            if *pending_scroll_to_top.read() {
-                pending_scroll_to_top.set(false);
-                if let Some(ref el) = *container_element.read() {
-                    let coords = dioxus::html::geometry::PixelsVector2D::new(0.0, 0.0);
-                    let _ = el.scroll(coords, ScrollBehavior::Instant).await;
-                }
-            }
             if *pending_scroll_to_bottom.read() {
                 pending_scroll_to_bottom.set(false);
                 if let Some(ref el) = *container_element.read() {
@@ -205,18 +274,33 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                     let _ = el.scroll(coords, ScrollBehavior::Instant).await;
                 }
             }
+            if *pending_scroll_to_match.read() {
+                pending_scroll_to_match.set(false);
+                if let Some(ref el) = *container_element.read() {
+                    let (x, y) = {
+                        let s = app_state.read();
+                        (s.scroll_x, s.scroll_y)
+                    };
+                    let coords = dioxus::html::geometry::PixelsVector2D::new(x, y);
+                    let _ = el.scroll(coords, ScrollBehavior::Instant).await;
+                }
+            }
             async_std::task::sleep(Duration::from_millis(16)).await;
         }
     });
 
     let state = app_state.read();
-    let total_lines = state.lines.len();
-    let filtered_count = state.filtered_indices.len();
+    let total_lines = state.log_state.lines.len();
+    let filtered_count = state.log_state.filtered_indices.len();
     let scroll_y = state.scroll_y;
     let scroll_x = state.scroll_x;
     let container_height = state.container_height;
-    let follow_tail = state.follow_tail;
+    let follow_tail = state.log_state.follow_tail;
     let show_time = state.show_time;
+    let time_format = state.time_format;
+    let time_display = state.time_display.clone();
+    let zone_label = time_display.zone_label().to_string();
+    let columns: Vec<Column> = visible_columns(&state.columns, show_time).cloned().collect();
     let wrap_lines = state.wrap_lines;
     let hide_text = state.hide_text.clone();
     let filter_text = state.filter_text.clone();
@@ -225,27 +309,56 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
     let filter_error = state.filter_error.clone();
     let line_start_text = state.line_start_text.clone();
     let line_start_error = state.line_start_error.clone();
+    let rewrite_text = state.rewrite_text.clone();
+    let rewrite_error = state.rewrite_error.clone();
+    let show_raw = state.log_state.filter_state.show_raw;
+    let show_epochs = state.log_state.filter_state.show_epochs;
+    let enriched = state.log_state.filter_state.enrich.is_some();
+    let numeric_peers = state.peer_names.numeric;
+    let level_tint = state.log_state.filter_state.highlight.level_tint;
+    let humanized = state.log_state.filter_state.humanize.is_some();
+    let show_watches = state.show_watches;
+    let show_outline = state.show_outline;
+    let layout_name = state.layout.clone().unwrap_or_else(|| "—".to_string());
+    let pinned: Vec<(usize, String)> = state
+        .log_state
+        .pins
+        .lines()
+        .iter()
+        .filter_map(|&idx| state.log_state.lines.get(idx).map(|line| (idx, state.log_state.display_content(line))))
+        .collect();
+    let lanes = state.show_lanes.then(|| state.log_state.lanes.clone());
+    let dedup_hidden = state.log_state.dedup.as_ref().map(|d| d.total_suppressed);
+    let hidden_matches = state.log_state.hidden_matches;
+    let sampled = (state.log_state.sampler.mode != Sampling::Off).then(|| state.log_state.sampler.label());
+    let follow_match_text = state.follow_match_text.clone();
+    let follow_match_error = state.follow_match_error.clone();
+    let following_match = state.match_scroll_y().is_some();
+    let replay_label = state.replay_label();
     let status_message = state.status_message.clone();
     let is_connected = state.is_connected;
-    let highlight_expr = state.filter_state.highlight_expr.clone();
+    let highlight_expr = state.log_state.filter_state.highlight_expr.clone();
+    let highlight_settings = state.log_state.filter_state.highlight.clone();
     let total_height = state.total_height();
     let (start_idx, end_idx) = state.find_visible_range(scroll_y, container_height + LINE_HEIGHT * 3.0);
     let version = state.version;
-    let last_update_time = state.last_update_time;
+    let last_update_time = state.log_state.last_update_time;
     drop(state);
 
-    let (visible_lines, runtime_hide_error): (Vec<(usize, usize, f64, LogLine, String)>, Option<String>) = {
+    let (visible_lines, runtime_hide_error): (Vec<VisibleLine>, Option<String>) = {
         let state = app_state.read();
         let mut error: Option<String> = None;
         let lines: Vec<_> = (start_idx..end_idx)
             .filter_map(|filter_idx| {
                 let offset = state.get_line_offset(filter_idx);
                 state
+                    .log_state
                     .filtered_indices
                     .get(filter_idx)
                     .and_then(|&line_idx| {
-                        state.lines.get(line_idx).map(|line| {
+                        state.log_state.lines.get(line_idx).map(|line| {
                             let content = match state.get_display_content(line) {
+                                _ if line.system => line.content.clone(),
                                 Ok(c) => c,
                                 Err(e) => {
                                     if error.is_none() {
@@ -254,7 +367,29 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                                     line.content.clone()
                                 }
                             };
-                            (filter_idx, line_idx, offset, line.clone(), content)
+                            let folded = stacktrace::detect(&content, &state.log_state.filter_state.highlight.user_frames)
+                                .filter(|_| !state.expanded_traces.contains(&line_idx))
+                                .map(|trace| {
+                                    let collapsed = trace.collapse(&content);
+                                    let inside = state.log_state.filter_state.highlight_count(collapsed.folded);
+                                    (collapsed.head.to_string(), (collapsed.hidden_frames, inside), collapsed.tail.to_string())
+                                });
+                            let note = state
+                                .log_state
+                                .notes
+                                .get(line_idx)
+                                .map(|text| (text.to_string(), state.log_state.notes.is_expanded(line_idx)));
+                            let repeats = state.log_state.dedup.as_ref().map_or(0, |d| d.suppressed(line_idx));
+                            let cells = columns
+                                .iter()
+                                .map(|column| column_cell(column, &state.log_state.lines, &state.log_state.lanes, line_idx, time_format, &time_display, &state.peer_names))
+                                .collect();
+                            match folded {
+                                Some((head, hidden, tail)) => {
+                                    (filter_idx, line_idx, offset, line.clone(), cells, head, Some((hidden, tail)), note, repeats)
+                                }
+                                None => (filter_idx, line_idx, offset, line.clone(), cells, content, None, note, repeats),
+                            }
                         })
                     })
             })
@@ -282,7 +417,7 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         onkeydown: move |e| {
                             if e.key() == Key::Enter {
                                 app_state.write().apply_hide();
-                                pending_scroll_to_top.set(true);
+                                pending_scroll_to_match.set(true);
                             }
                         },
                     }
@@ -299,7 +434,7 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         onkeydown: move |e| {
                             if e.key() == Key::Enter {
                                 app_state.write().apply_filter();
-                                pending_scroll_to_top.set(true);
+                                pending_scroll_to_match.set(true);
                             }
                         },
                     }
@@ -335,6 +470,45 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         },
                     }
                 }
+                div { class: "filter-group",
+                    label { "Rewrite:" }
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        class: if rewrite_error.is_some() { "error" } else { "" },
+                        placeholder: "s/regex/replacement/; ...",
+                        value: "{rewrite_text}",
+                        oninput: move |e| app_state.write().rewrite_text = e.value(),
+                        onkeydown: move |e| {
+                            if e.key() == Key::Enter {
+                                app_state.write().apply_rewrite();
+                            }
+                        },
+                    }
+                }
+                div { class: "filter-group",
+                    label { "Follow match:" }
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        class: if follow_match_error.is_some() { "error" } else { "" },
+                        placeholder: "pin the latest matching line...",
+                        title: follow_match_error.unwrap_or_default(),
+                        value: "{follow_match_text}",
+                        oninput: move |e| app_state.write().follow_match_text = e.value(),
+                        onkeydown: move |e| {
+                            if e.key() == Key::Enter {
+                                let mut s = app_state.write();
+                                s.apply_follow_match();
+                                if let Some(y) = s.match_scroll_y() {
+                                    s.scroll_y = y;
+                                    s.version += 1;
+                                    pending_scroll_to_match.set(true);
+                                }
+                            }
+                        },
+                    }
+                }
                 div { class: "toolbar-actions",
                     button {
                         class: if show_time { "active" } else { "" },
@@ -345,6 +519,16 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         },
                         "Time"
                     }
+                    button {
+                        title: "Show receipt times as age, clock time with milliseconds, or time since the previous line",
+                        onclick: move |_| app_state.write().cycle_time_format(),
+                        "Time: {time_format.label()}"
+                    }
+                    button {
+                        title: "Show clock and event times in local time or UTC",
+                        onclick: move |_| app_state.write().toggle_utc(),
+                        "Zone: {zone_label}"
+                    }
                     button {
                         class: if wrap_lines { "active" } else { "" },
                         onclick: move |_| {
@@ -357,12 +541,103 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         },
                         "Wrap"
                     }
+                    button {
+                        class: if show_raw { "active" } else { "" },
+                        onclick: move |_| app_state.write().toggle_raw(),
+                        "Raw"
+                    }
+                    button {
+                        class: if show_epochs { "active" } else { "" },
+                        title: "Write the local time after epoch timestamps like 1718123456789",
+                        onclick: move |_| app_state.write().toggle_epochs(),
+                        "Epochs"
+                    }
+                    button {
+                        class: if enriched { "active" } else { "" },
+                        title: "Write GeoIP and host name fields like country=DE after IP addresses",
+                        onclick: move |_| app_state.write().toggle_enrich(),
+                        "Enrich"
+                    }
+                    button {
+                        class: if numeric_peers { "active" } else { "" },
+                        title: "Show listen-mode peers by IP address instead of host name",
+                        onclick: move |_| {
+                            let mut state = app_state.write();
+                            state.peer_names.numeric = !state.peer_names.numeric;
+                        },
+                        "Numeric"
+                    }
+                    button {
+                        class: if level_tint { "active" } else { "" },
+                        title: "Color whole lines by level: dim DEBUG, yellow WARN, red ERROR",
+                        onclick: move |_| {
+                            let mut s = app_state.write();
+                            s.log_state.filter_state.highlight.level_tint ^= true;
+                            s.version += 1;
+                        },
+                        "Tint"
+                    }
+                    button {
+                        class: if humanized { "active" } else { "" },
+                        title: "Show byte counts and durations like size=10485760 as 10 MiB",
+                        onclick: move |_| app_state.write().toggle_humanize(),
+                        "Humanize"
+                    }
+                    button {
+                        class: if dedup_hidden.is_some() { "active" } else { "" },
+                        title: "Hide lines nearly identical to one shown in the last 10 seconds",
+                        onclick: move |_| {
+                            app_state.write().toggle_dedup();
+                            pending_scroll_to_match.set(true);
+                        },
+                        "Dedup"
+                    }
+                    button {
+                        class: if show_watches { "active" } else { "" },
+                        onclick: move |_| {
+                            let mut s = app_state.write();
+                            s.show_watches = !s.show_watches;
+                        },
+                        "Watch"
+                    }
+                    button {
+                        class: if show_outline { "active" } else { "" },
+                        title: "Lines and errors per minute or hour; click one to jump there",
+                        onclick: move |_| {
+                            let mut s = app_state.write();
+                            s.show_outline = !s.show_outline;
+                        },
+                        "Outline"
+                    }
+                    button {
+                        class: if lanes.is_some() { "active" } else { "" },
+                        title: "A colored lane per thread or process, to untangle interleaved lines",
+                        onclick: move |_| {
+                            let mut s = app_state.write();
+                            s.show_lanes = !s.show_lanes;
+                            let shown = s.show_lanes;
+                            s.log_state.set_lanes(shown);
+                            s.version += 1;
+                        },
+                        "Lanes"
+                    }
+                    button {
+                        title: "Switch to the next saved or built-in layout of columns, panes and toggles",
+                        onclick: move |_| app_state.write().next_layout(),
+                        "Layout: {layout_name}"
+                    }
+                    button {
+                        title: "Save the columns, panes and toggles as the current layout",
+                        onclick: move |_| app_state.write().save_layout(),
+                        "Save layout"
+                    }
                     button {
                         class: if follow_tail { "active" } else { "" },
                         onclick: move |_| {
                             let mut s = app_state.write();
-                            s.follow_tail = !s.follow_tail;
-                            if s.follow_tail {
+                            s.log_state.follow_tail = !s.log_state.follow_tail;
+                            if s.log_state.follow_tail {
+                                s.stop_follow_match();
                                 s.scroll_to_bottom();
                                 pending_scroll_to_bottom.set(true);
                             }
@@ -370,6 +645,45 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         },
                         "Follow"
                     }
+                    if let Some(label) = replay_label {
+                        span { class: "replay-status", "{label}" }
+                        button {
+                            onclick: move |_| app_state.write().with_replay(Replay::toggle_pause),
+                            "⏯"
+                        }
+                        button {
+                            title: "Cycle speed 1×/2×/10×",
+                            onclick: move |_| app_state.write().with_replay(Replay::cycle_speed),
+                            "Speed"
+                        }
+                        button {
+                            title: "Skip to the next line",
+                            onclick: move |_| app_state.write().with_replay(Replay::skip_gap),
+                            "Skip"
+                        }
+                    }
+                    button {
+                        title: "Match counts and hit rates of the active and saved filters",
+                        onclick: move |_| app_state.write().show_filter_stats = true,
+                        "Stats"
+                    }
+                    if !plugin::plugins().is_empty() {
+                        button {
+                            title: "Loaded plugins and their health",
+                            onclick: move |_| app_state.write().show_plugins = true,
+                            "Plugins"
+                        }
+                    }
+                    button {
+                        title: "Try an expression against a sample line, sub-expression by sub-expression",
+                        onclick: move |_| app_state.write().open_playground(None),
+                        "Playground"
+                    }
+                    button {
+                        title: "Save buffer, settings and notes as a .lvz bundle",
+                        onclick: move |_| app_state.write().export_bundle(),
+                        "Bundle"
+                    }
                     button {
                         onclick: move |_| {
                             app_state.write().clear();
@@ -379,8 +693,27 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 }
             }
 
+            if !pinned.is_empty() {
+                div { class: "pinned-lines",
+                    for (idx, text) in pinned {
+                        div { class: "pinned-line",
+                            span { class: "pinned-number", "{idx + 1}" }
+                            span { class: "pinned-text", "{text}" }
+                            button {
+                                title: "Unpin",
+                                onclick: move |_| app_state.write().toggle_pin(idx),
+                                "✕"
+                            }
+                        }
+                    }
+                }
+            }
+
             div { class: "log-wrapper",
                 div { class: "log-main",
+                    if show_outline {
+                        OutlinePanel { app_state, on_jump: move |_| pending_scroll_to_match.set(true) }
+                    }
                     div {
                         class: if wrap_lines { "log-container wrap-mode" } else { "log-container nowrap-mode" },
                         tabindex: "0",
@@ -409,30 +742,45 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                         },
                         onscroll: move |e| {
                             let data = e.data();
-                            let new_scroll_y = data.scroll_top() as f64;
-                            let new_scroll_x = data.scroll_left() as f64;
+                            let new_scroll_y = data.scroll_top();
+                            let new_scroll_x = data.scroll_left();
                             let mut s = app_state.write();
                             let (old_start, old_end) = s.find_visible_range(s.scroll_y, s.container_height + LINE_HEIGHT * 3.0);
                             let (new_start, new_end) = s.find_visible_range(new_scroll_y, s.container_height + LINE_HEIGHT * 3.0);
                             s.scroll_y = new_scroll_y;
                             s.scroll_x = new_scroll_x;
                             if !s.is_at_bottom() {
-                                s.follow_tail = false;
+                                s.log_state.follow_tail = false;
+                            }
+                            if !s.log_state.follow_tail {
+                                let (_, end) = s.find_visible_range(new_scroll_y, s.container_height);
+                                s.log_state.bottom_line_idx = end.saturating_sub(1);
+                            }
+                            if s.match_scroll_y().is_some_and(|y| (new_scroll_y - y).abs() > 1.0) {
+                                s.stop_follow_match();
                             }
                             if old_start != new_start || old_end != new_end {
                                 s.version += 1;
                             }
                         },
+                        onmousedown: move |_| app_state.write().log_state.hold_view(),
+                        onmouseup: move |_| {
+                            let mut s = app_state.write();
+                            if s.log_state.release_view() {
+                                s.scroll_to_bottom();
+                                pending_scroll_to_bottom.set(true);
+                            }
+                        },
                         onkeydown: move |e| {
                             let mut s = app_state.write();
                             match e.key() {
                                 Key::ArrowUp => {
                                     s.scroll_y -= LINE_HEIGHT;
-                                    s.follow_tail = false;
+                                    s.log_state.follow_tail = false;
                                 }
                                 Key::ArrowDown => {
                                     s.scroll_y += LINE_HEIGHT;
-                                    s.follow_tail = s.is_at_bottom();
+                                    s.log_state.follow_tail = s.is_at_bottom();
                                 }
                                 Key::ArrowLeft => {
                                     if !s.wrap_lines {
@@ -448,20 +796,20 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                                 }
                                 Key::PageUp => {
                                     s.scroll_y -= s.container_height;
-                                    s.follow_tail = false;
+                                    s.log_state.follow_tail = false;
                                 }
                                 Key::PageDown => {
                                     s.scroll_y += s.container_height;
-                                    s.follow_tail = s.is_at_bottom();
+                                    s.log_state.follow_tail = s.is_at_bottom();
                                 }
                                 Key::Home => {
                                     s.scroll_y = 0.0;
                                     s.scroll_x = 0.0;
-                                    s.follow_tail = false;
+                                    s.log_state.follow_tail = false;
                                 }
                                 Key::End => {
                                     s.scroll_to_bottom();
-                                    s.follow_tail = true;
+                                    s.log_state.follow_tail = true;
                                 }
                                 _ => return,
                             }
@@ -472,46 +820,102 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                             class: "log-list",
                             key: "{version}",
                             style: "height: {total_height}px; position: relative;",
-                            for (filter_idx, line_idx, offset, line, content) in visible_lines {
+                            for (filter_idx, line_idx, offset, line, cells, content, folded, note, repeats) in visible_lines {
                                 div {
-                                    class: "log-line",
+                                    class: if line.system { "log-line system" } else { "log-line" },
                                     key: "{line_idx}-{version}-{wrap_lines}",
                                     style: if wrap_lines {
                                         format!("position: absolute; top: {offset}px; left: 0; right: 0;")
                                     } else {
                                         format!("position: absolute; top: {offset}px; left: -{scroll_x}px; right: 0;")
                                     },
-                                    onmounted: {
-                                        let filter_idx = filter_idx;
-                                        move |e| async move {
-                                            if let Ok(rect) = e.get_client_rect().await {
-                                                app_state.write().set_line_height(filter_idx, rect.size.height);
-                                            }
+                                    onmounted: move |e| async move {
+                                        if let Ok(rect) = e.get_client_rect().await {
+                                            app_state.write().set_line_height(filter_idx, rect.size.height);
                                         }
                                     },
-                                    if show_time {
+                                    ondoubleclick: move |_| {
+                                        app_state.write().detail = Some(DetailState::new(line_idx));
+                                    },
+                                    for (column, (class, text)) in columns.iter().zip(cells) {
                                         {
-                                            let time_age = get_time_age(line.timestamp);
-                                            let age_class = match time_age {
-                                                TimeAge::VeryRecent => "timestamp very-recent",
-                                                TimeAge::Recent => "timestamp recent",
-                                                TimeAge::Minutes => "timestamp minutes",
-                                                TimeAge::Hours => "timestamp hours",
-                                                TimeAge::Days => "timestamp days",
-                                            };
-                                            rsx! { span { class: "{age_class}", "{format_relative_time(line.timestamp)}" } }
+                                            let width = column.width;
+                                            rsx! { span { class: "{class}", style: "width: {width}ch;", "{text}" } }
+                                        }
+                                    }
+                                    if let Some(lanes) = &lanes {
+                                        {
+                                            let own = lanes.lane(&line.content);
+                                            rsx! {
+                                                for i in 0..lanes.len() {
+                                                    span {
+                                                        class: if own == Some(i) { "lane lane-{i} own" } else { "lane lane-{i}" },
+                                                        if own == Some(i) { "●" } else { "│" }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                    span {
+                                        class: if note.is_some() { "note-gutter has-note" } else { "note-gutter" },
+                                        title: "Click to show or hide the note; add one from the detail view",
+                                        onclick: move |_| app_state.write().toggle_note(line_idx),
+                                        if note.is_some() { "✎" }
+                                    }
+                                    if line.system {
+                                        span { class: "system-text", "{content}" }
+                                    } else {
+                                        LogLineContent {
+                                            content: content.clone(),
+                                            level: line.level,
+                                            highlight_text: highlight_text.clone(),
+                                            highlight_expr: highlight_expr.clone(),
+                                            settings: highlight_settings.clone(),
+                                            on_token_click: move |pos| {
+                                                app_state.write().filter_by_token(&content, pos);
+                                                pending_scroll_to_match.set(true);
+                                            },
+                                        }
+                                    }
+                                    if repeats > 0 {
+                                        span { class: "repeat-badge", title: "Near-duplicates hidden", "+{repeats}" }
+                                    }
+                                    if let Some(((hidden, inside), tail)) = folded {
+                                        div {
+                                            class: "trace-marker",
+                                            onclick: move |_| app_state.write().toggle_trace(line_idx),
+                                            "▶ {hidden} frames"
+                                            if inside > 0 {
+                                                span { class: "concealed-matches", " · {inside} matches inside" }
+                                            }
+                                        }
+                                        if !tail.is_empty() {
+                                            LogLineContent {
+                                                content: tail.clone(),
+                                                level: line.level,
+                                                highlight_text: highlight_text.clone(),
+                                                highlight_expr: highlight_expr.clone(),
+                                                settings: highlight_settings.clone(),
+                                                on_token_click: move |pos| {
+                                                    app_state.write().filter_by_token(&tail, pos);
+                                                    pending_scroll_to_match.set(true);
+                                                },
+                                            }
                                         }
                                     }
-                                    span { class: "line-num", "{line_idx + 1}" }
-                                    LogLineContent {
-                                        content: content,
-                                        highlight_text: highlight_text.clone(),
-                                        highlight_expr: highlight_expr.clone(),
+                                    if let Some((ref text, true)) = note {
+                                        div { class: "note-inline", "✎ {text}" }
                                     }
                                 }
                             }
                         }
                     }
+                    if filtered_count > 0 {
+                        Minimap { app_state, on_jump: move |_| pending_scroll_to_match.set(true) }
+                    }
+                    if show_watches {
+                        WatchPanel { app_state }
+                    }
                 }
             }
 
@@ -520,6 +924,10 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 span { class: "status-info",
                     "{filtered_count} / {total_lines} lines"
                     if follow_tail { " • Following" }
+                    if following_match { " • Following match" }
+                    if let Some(hidden) = dedup_hidden { " • {hidden} near-duplicates hidden" }
+                    if hidden_matches > 0 { " • {hidden_matches} matches hidden" }
+                    if let Some(ref sampled) = sampled { " • Sampled {sampled}" }
                 }
                 span { class: "status-info",
                     if let Some(ref msg) = status_message {
@@ -530,9 +938,67 @@ pub fn GuiApp(props: GuiAppProps) -> Element {
                 }
             }
 
+            if app_state.read().detail.is_some() {
+                DetailPopup { app_state }
+            }
+
+            if app_state.read().playground.is_some() {
+                PlaygroundPopup { app_state }
+            }
+
+            if app_state.read().why.is_some() {
+                WhyPopup { app_state }
+            }
+
+            if app_state.read().show_filter_stats {
+                FilterStatsPopup { app_state }
+            }
+
+            if app_state.read().show_plugins {
+                PluginsPopup { app_state }
+            }
+
             if listen_state.read().show_popup() {
                 ListenPopup { listen_state }
             }
         }
     }
 }
+
+fn column_cell(
+    column: &Column,
+    lines: &[LogLine],
+    lanes: &Lanes,
+    line_idx: usize,
+    time_format: TimeFormat,
+    time_display: &TimeDisplay,
+    peers: &PeerNames,
+) -> (String, String) {
+    let line = &lines[line_idx];
+    let class = match &column.kind {
+        ColumnKind::ReceiptTime => match get_time_age(line.timestamp) {
+            TimeAge::VeryRecent => "timestamp very-recent".to_string(),
+            TimeAge::Recent => "timestamp recent".to_string(),
+            TimeAge::Minutes => "timestamp minutes".to_string(),
+            TimeAge::Hours => "timestamp hours".to_string(),
+            TimeAge::Days => "timestamp days".to_string(),
+        },
+        ColumnKind::LineNumber => "line-num".to_string(),
+        ColumnKind::EventTime => "col col-event-time".to_string(),
+        ColumnKind::Level => match line.level {
+            Some(level) => format!("col col-level level-{}", level.badge().to_lowercase()),
+            None => "col col-level".to_string(),
+        },
+        ColumnKind::Source => "col col-source".to_string(),
+        ColumnKind::Thread => match thread_of(&line.content) {
+            Some(_) => format!("col col-thread lane-{}", lanes.lane(&line.content).unwrap_or(MAX_LANES)),
+            None => "col col-thread".to_string(),
+        },
+        ColumnKind::Field(_) => "col col-field".to_string(),
+    };
+    let text = cell_text(&column.kind, lines, line_idx, time_format, time_display);
+    match column.kind {
+        ColumnKind::Source => (class, peers.tag(&text).into_owned()),
+        _ => (class, text),
+    }
+}
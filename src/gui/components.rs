@@ -1,52 +1,64 @@
-use crate::core::{ListenDisplayMode, ListenState};
+use crate::blame;
+use crate::clipboard;
+use crate::core::markers::{self, MARK_ERROR, MARK_MATCH, MARK_NOTE};
+use crate::core::{format_listen_addr, ListenDisplayMode, ListenState, PlaygroundTarget, Why};
 use crate::filter::FilterExpr;
-use super::state::highlight_content;
+use crate::filter_stats::format_rate;
+use crate::highlight::{apply_highlights, highlight_line, pretty_print, HighlightSettings};
+use crate::input::TextInput;
+use crate::level::Level;
+use crate::plugin::{self, Capability, Status};
+use crate::qr::QrMatrix;
+use crate::watch::format_value;
+use super::state::{highlight_content, GuiAppState};
 use dioxus::prelude::*;
 
-fn format_addr_display(ip: &std::net::IpAddr, port: u16, is_v6: bool, mode: ListenDisplayMode) -> String {
-    match mode {
-        ListenDisplayMode::AddrPort => {
-            if is_v6 {
-                format!("[{}]:{}", ip, port)
-            } else {
-                format!("{}:{}", ip, port)
-            }
-        }
-        ListenDisplayMode::NcCommand => {
-            if is_v6 {
-                format!("nc -6 {} {}", ip, port)
-            } else {
-                format!("nc {} {}", ip, port)
-            }
-        }
-    }
-}
-
 #[derive(Props, Clone)]
 pub struct LogLineContentProps {
     pub content: String,
+    pub level: Option<Level>,
     pub highlight_text: String,
     pub highlight_expr: Option<FilterExpr>,
+    pub settings: HighlightSettings,
+    /// Ctrl/Cmd-click on a highlighted run, with the byte offset clicked.
+    pub on_token_click: EventHandler<usize>,
 }
 
 impl PartialEq for LogLineContentProps {
     fn eq(&self, other: &Self) -> bool {
-        self.content == other.content && self.highlight_text == other.highlight_text
+        self.content == other.content
+            && self.level == other.level
+            && self.highlight_text == other.highlight_text
+            && self.settings == other.settings
     }
 }
 
 #[component]
 pub fn LogLineContent(props: LogLineContentProps) -> Element {
-    let parts = highlight_content(&props.content, &props.highlight_expr);
+    let parts = highlight_content(&props.content, props.level, &props.highlight_expr, &props.settings);
+    let mut offset = 0;
     rsx! {
         span { class: "content",
             for (text, style) in parts {
                 {
                     let class = style.css_class();
+                    let start = offset;
+                    offset += text.len();
                     if class.is_empty() {
                         rsx! { "{text}" }
                     } else {
-                        rsx! { span { class: "{class}", "{text}" } }
+                        rsx! {
+                            span {
+                                class: "{class}",
+                                onclick: move |e: MouseEvent| {
+                                    let m = e.modifiers();
+                                    if m.ctrl() || m.meta() {
+                                        props.on_token_click.call(start);
+                                    }
+                                },
+                                "{text}"
+                            }
+                        }
                     }
                 }
             }
@@ -54,52 +66,706 @@ pub fn LogLineContent(props: LogLineContentProps) -> Element {
     }
 }
 
-fn get_copy_text_from_interfaces(state: &ListenState) -> Option<String> {
-    let port = state.port?;
-    let mut addr_idx = 0usize;
-    for iface in &state.network_interfaces {
-        for addr_info in &iface.addresses {
-            if addr_idx == state.selected_idx {
-                let is_v6 = addr_info.ip.is_ipv6();
-                return Some(format_addr_display(&addr_info.ip, port, is_v6, state.display_mode));
+#[component]
+pub fn DetailPopup(app_state: Signal<GuiAppState>) -> Element {
+    let state = app_state.read();
+    let Some(detail) = state.detail.clone() else {
+        return rsx! {};
+    };
+    let (content, level) = state
+        .log_state
+        .lines
+        .get(detail.line_idx)
+        .map(|l| (l.content.clone(), l.level))
+        .unwrap_or_default();
+    let blames = if state.git_blame { blame::describe_refs(&content) } else { Vec::new() };
+    let text = if detail.pretty { pretty_print(&content) } else { None }.unwrap_or(content);
+    let spans = highlight_line(
+        &text,
+        level,
+        state.log_state.filter_state.highlight_expr.as_ref(),
+        true,
+        true,
+        &state.log_state.filter_state.highlight,
+    );
+    let note = state.log_state.notes.get(detail.line_idx).unwrap_or("").to_string();
+    let pinned = state.log_state.pins.contains(detail.line_idx);
+    drop(state);
+    let parts = apply_highlights(&text, &spans);
+    let line_num = detail.line_idx + 1;
+
+    rsx! {
+        div { class: "popup-overlay",
+            tabindex: "0",
+            onclick: move |_| app_state.write().detail = None,
+            onkeydown: move |e| {
+                match e.key() {
+                    Key::Escape | Key::Enter => app_state.write().detail = None,
+                    Key::Character(c) if c == "p" => {
+                        if let Some(d) = app_state.write().detail.as_mut() {
+                            d.toggle_pretty();
+                        }
+                    }
+                    _ => {}
+                }
+            },
+            div { class: "popup detail-popup",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "popup-header",
+                    span { "Line " }
+                    span { class: "popup-port", "{line_num}" }
+                }
+                div { class: "popup-mode",
+                    span { class: "popup-label", "Pretty (p): " }
+                    span { class: "popup-mode-value", if detail.pretty { "ON" } else { "OFF" } }
+                }
+                div { class: "popup-hint", "Esc:Close" }
+                button {
+                    title: "Try the filter expression against this line",
+                    onclick: move |_| {
+                        let mut s = app_state.write();
+                        s.detail = None;
+                        s.open_playground(Some(detail.line_idx));
+                    },
+                    "Playground"
+                }
+                button {
+                    title: "Keep this line in view above the log",
+                    onclick: move |_| app_state.write().toggle_pin(detail.line_idx),
+                    if pinned { "Unpin" } else { "Pin" }
+                }
+                button {
+                    title: "Explain why this line is shown: filter terms, hide regex, snoozes and dedup",
+                    onclick: move |_| {
+                        let mut s = app_state.write();
+                        s.detail = None;
+                        s.why = Why::explain(&s.log_state, detail.line_idx);
+                    },
+                    "Why?"
+                }
+                button {
+                    title: "Filter to the lines sharing this line's request or trace ID",
+                    onclick: move |_| {
+                        let mut s = app_state.write();
+                        s.detail = None;
+                        s.correlate(detail.line_idx);
+                    },
+                    "Correlate"
+                }
+                input {
+                    class: "detail-note",
+                    r#type: "text",
+                    placeholder: "Add a note to this line...",
+                    value: "{note}",
+                    onkeydown: move |e| e.stop_propagation(),
+                    onchange: move |e| app_state.write().set_note(detail.line_idx, &e.value()),
+                }
+                div { class: "detail-body",
+                    for (text, style) in parts {
+                        {
+                            let class = style.css_class();
+                            if class.is_empty() {
+                                rsx! { "{text}" }
+                            } else {
+                                rsx! { span { class: "{class}", "{text}" } }
+                            }
+                        }
+                    }
+                }
+                if !blames.is_empty() {
+                    div { class: "detail-blame",
+                        for blame in blames {
+                            div { "{blame}" }
+                        }
+                    }
+                }
             }
-            addr_idx += 1;
         }
     }
-    None
-}
-
-#[cfg(target_os = "macos")]
-fn copy_to_clipboard(text: &str) {
-    let _ = std::process::Command::new("pbcopy")
-        .stdin(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            use std::io::Write;
-            if let Some(stdin) = child.stdin.as_mut() {
-                stdin.write_all(text.as_bytes())?;
+}
+
+#[component]
+pub fn PlaygroundPopup(app_state: Signal<GuiAppState>) -> Element {
+    let state = app_state.read();
+    let Some(playground) = state.playground.clone() else {
+        return rsx! {};
+    };
+    let count = state.log_state.filtered_indices.len();
+    drop(state);
+    let (nodes, parts, grouped, error) = match playground.evaluate() {
+        Ok(eval) => {
+            let sample = &playground.sample.text;
+            let mut parts = Vec::new();
+            let mut pos = 0;
+            for (start, end) in eval.ranges {
+                parts.push((sample[pos..start].to_string(), false));
+                parts.push((sample[start..end].to_string(), true));
+                pos = end;
             }
-            child.wait()
-        });
+            parts.push((sample[pos..].to_string(), false));
+            (eval.nodes, parts, eval.grouped, None)
+        }
+        Err(e) => (Vec::new(), Vec::new(), String::new(), Some(e)),
+    };
+    let expr_text = playground.expr.text.clone();
+    let sample_text = playground.sample.text.clone();
+    let position = match playground.sample_pos {
+        Some(pos) => format!("line {}/{}", pos + 1, count),
+        None => "pasted".to_string(),
+    };
+
+    rsx! {
+        div { class: "popup-overlay",
+            tabindex: "0",
+            onclick: move |_| app_state.write().playground = None,
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    app_state.write().playground = None;
+                }
+            },
+            div { class: "popup playground-popup",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "popup-header", "Expression playground" }
+                div { class: "popup-mode",
+                    for target in [PlaygroundTarget::Filter, PlaygroundTarget::Highlight] {
+                        button {
+                            class: if playground.target == target { "active" } else { "" },
+                            onclick: move |_| app_state.write().set_playground_target(target),
+                            "{target.label()}"
+                        }
+                    }
+                    button {
+                        class: if playground.show_grouping { "active" } else { "" },
+                        title: "Show how the operators grouped",
+                        onclick: move |_| {
+                            if let Some(p) = app_state.write().playground.as_mut() {
+                                p.show_grouping = !p.show_grouping;
+                            }
+                        },
+                        "Grouping"
+                    }
+                }
+                input {
+                    r#type: "text",
+                    spellcheck: "false",
+                    class: if error.is_some() { "error" } else { "" },
+                    placeholder: "expression...",
+                    value: "{expr_text}",
+                    onkeydown: move |e| {
+                        e.stop_propagation();
+                        if e.key() == Key::Enter {
+                            app_state.write().apply_playground();
+                        }
+                    },
+                    oninput: move |e| {
+                        if let Some(p) = app_state.write().playground.as_mut() {
+                            p.expr = TextInput::new(e.value());
+                        }
+                    },
+                }
+                div { class: "popup-mode",
+                    span { class: "popup-label", "Sample ({position}): " }
+                    button { onclick: move |_| app_state.write().pick_playground_sample(-1), "↑" }
+                    button { onclick: move |_| app_state.write().pick_playground_sample(1), "↓" }
+                }
+                textarea {
+                    class: "playground-sample",
+                    spellcheck: "false",
+                    placeholder: "paste a line...",
+                    value: "{sample_text}",
+                    onkeydown: move |e| e.stop_propagation(),
+                    oninput: move |e| {
+                        if let Some(p) = app_state.write().playground.as_mut() {
+                            p.sample = TextInput::new(e.value());
+                            p.sample_pos = None;
+                        }
+                    },
+                }
+                div { class: "detail-body",
+                    for (text, matched) in parts {
+                        if matched {
+                            span { class: "hl-custom", "{text}" }
+                        } else {
+                            "{text}"
+                        }
+                    }
+                }
+                if let Some(e) = &error {
+                    div { class: "popup-error", "{e}" }
+                }
+                if playground.show_grouping && !grouped.is_empty() {
+                    div { class: "playground-grouping", "Parsed as {grouped}" }
+                }
+                div { class: "playground-tree",
+                    for node in nodes {
+                        div {
+                            class: if node.matched { "playground-node matched" } else { "playground-node" },
+                            style: "padding-left: {node.depth * 16}px;",
+                            if node.matched { "✔ " } else { "✘ " }
+                            "{node.label}"
+                        }
+                    }
+                }
+                div { class: "popup-hint", "Enter:Apply  Esc:Close" }
+            }
+        }
+    }
 }
 
-#[cfg(not(target_os = "macos"))]
-fn copy_to_clipboard(_text: &str) {}
+#[component]
+pub fn WhyPopup(app_state: Signal<GuiAppState>) -> Element {
+    let state = app_state.read();
+    let Some(why) = state.why.clone() else {
+        return rsx! {};
+    };
+    let content = state.log_state.lines.get(why.line_idx).map(|line| line.content.clone()).unwrap_or_default();
+    drop(state);
+    let line_num = why.line_idx + 1;
+    let verdict = if why.shown { "shown" } else { "hidden" };
+
+    rsx! {
+        div { class: "popup-overlay",
+            tabindex: "0",
+            onclick: move |_| app_state.write().why = None,
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    app_state.write().why = None;
+                }
+            },
+            div { class: "popup playground-popup",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "popup-header", "Line {line_num} is {verdict}" }
+                div { class: "detail-body", "{content}" }
+                div { class: "playground-tree",
+                    for reason in why.reasons {
+                        div {
+                            class: if reason.pass { "playground-node matched" } else { "playground-node" },
+                            style: "padding-left: {reason.depth * 16}px;",
+                            if reason.pass { "✔ " } else { "✘ " }
+                            "{reason.text}"
+                        }
+                    }
+                }
+                div { class: "popup-hint", "Esc:Close" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn FilterStatsPopup(app_state: Signal<GuiAppState>) -> Element {
+    let mut name = use_signal(String::new);
+    let now = chrono::Local::now();
+    let rows: Vec<_> = app_state
+        .read()
+        .log_state
+        .filter_stats
+        .iter()
+        .map(|stat| {
+            let (recent, recent_rate) = stat.last_minute(now);
+            (
+                stat.name.clone(),
+                stat.label().to_string(),
+                stat.canonical(),
+                stat.matches,
+                format_rate(stat.hit_rate()),
+                recent,
+                format_rate(recent_rate),
+            )
+        })
+        .collect();
+    let mut close = move || app_state.write().show_filter_stats = false;
+
+    rsx! {
+        div { class: "popup-overlay",
+            tabindex: "0",
+            onclick: move |_| close(),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    close();
+                }
+            },
+            div { class: "popup filter-stats-popup",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "popup-header", "Filter statistics" }
+                table { class: "filter-stats",
+                    tr {
+                        th { "Filter" }
+                        th { "Matches" }
+                        th { "Rate" }
+                        th { "Last min" }
+                        th { "Rate" }
+                        th { "Expression" }
+                        th {}
+                    }
+                    for (saved_name, label, text, matches, rate, recent, recent_rate) in rows {
+                        tr {
+                            td { "{label}" }
+                            td { class: "num", "{matches}" }
+                            td { class: "num", "{rate}" }
+                            td { class: "num", "{recent}" }
+                            td { class: "num", "{recent_rate}" }
+                            td { class: "expr", "{text}" }
+                            td {
+                                if let Some(saved_name) = saved_name {
+                                    button {
+                                        onclick: move |_| {
+                                            app_state.write().use_filter(&text);
+                                            close();
+                                        },
+                                        "Use"
+                                    }
+                                    button { onclick: move |_| app_state.write().drop_filter(&saved_name), "Drop" }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "popup-mode",
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        placeholder: "name...",
+                        value: "{name}",
+                        oninput: move |e| name.set(e.value()),
+                        onkeydown: move |e| {
+                            e.stop_propagation();
+                            if e.key() == Key::Enter {
+                                app_state.write().save_filter(&name.read());
+                                name.set(String::new());
+                            }
+                        },
+                    }
+                    button {
+                        onclick: move |_| {
+                            app_state.write().save_filter(&name.read());
+                            name.set(String::new());
+                        },
+                        "Save active filter"
+                    }
+                }
+                div { class: "popup-hint", "Esc:Close" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn PluginsPopup(app_state: Signal<GuiAppState>) -> Element {
+    let rows: Vec<_> = plugin::plugins()
+        .iter()
+        .map(|plugin| {
+            let health = plugin.health();
+            let capabilities: Vec<_> = plugin.capabilities().iter().map(|c| c.label()).collect();
+            let status_class = match health.status {
+                Status::Ready => "ready",
+                Status::Starting => "starting",
+                Status::Failed(_) | Status::Exited(_) => "failed",
+            };
+            let detail: Vec<String> = [
+                health.last_error.clone().map(|e| format!("error: {}", e)),
+                health.last_stderr.clone().map(|e| format!("stderr: {}", e)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            (
+                plugin.name().to_string(),
+                plugin.command.clone(),
+                capabilities.join(", "),
+                plugin.capabilities().contains(&Capability::Exporter) && health.status == Status::Ready,
+                health,
+                status_class,
+                detail.join("  "),
+            )
+        })
+        .collect();
+    let mut close = move || app_state.write().show_plugins = false;
+
+    rsx! {
+        div { class: "popup-overlay",
+            tabindex: "0",
+            onclick: move |_| close(),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    close();
+                }
+            },
+            div { class: "popup filter-stats-popup",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "popup-header", "Plugins" }
+                table { class: "filter-stats",
+                    tr {
+                        th { "Plugin" }
+                        th { "Capabilities" }
+                        th { "Lines" }
+                        th { "Calls" }
+                        th { "Errors" }
+                        th { "Status" }
+                        th {}
+                    }
+                    for (name, command, capabilities, exporter, health, status_class, detail) in rows {
+                        tr {
+                            td { title: "{command}", "{name}" }
+                            td { "{capabilities}" }
+                            td { class: "num", "{health.lines}" }
+                            td { class: "num", "{health.calls}" }
+                            td { class: "num", "{health.errors}" }
+                            td { class: "plugin-status {status_class}", title: "{detail}", "{health.status.label()}" }
+                            td {
+                                if exporter {
+                                    button {
+                                        title: "Send the filtered lines to this plugin",
+                                        onclick: move |_| {
+                                            let rx = app_state.write().export_to_plugin(&name);
+                                            async move {
+                                                let Some(rx) = rx else {
+                                                    return;
+                                                };
+                                                let result = async_std::task::spawn_blocking(move || rx.recv())
+                                                    .await
+                                                    .unwrap_or_else(|_| Err(anyhow::anyhow!("exporter thread stopped")));
+                                                app_state.write().finish_export(result);
+                                            }
+                                        },
+                                        "Export"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "popup-hint", "Esc:Close" }
+            }
+        }
+    }
+}
+
+/// Pixels of minimap height per marker row.
+const MINIMAP_ROW_PX: f64 = 3.0;
+
+/// A strip beside the log showing the whole filtered buffer, with ticks at
+/// errors, notes and highlight matches; clicking jumps there.
+#[component]
+pub fn Minimap(app_state: Signal<GuiAppState>, on_jump: EventHandler<()>) -> Element {
+    let state = app_state.read();
+    let total = state.log_state.filtered_indices.len();
+    let height = state.container_height;
+    let rows = (height / MINIMAP_ROW_PX).max(1.0) as usize;
+    let ticks: Vec<(f64, &str)> = state
+        .log_state
+        .markers
+        .rows(total, rows)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(row, kinds)| {
+            let class = match markers::dominant(kinds)? {
+                MARK_ERROR => "minimap-tick error",
+                MARK_NOTE => "minimap-tick note",
+                MARK_MATCH => "minimap-tick match",
+                _ => return None,
+            };
+            Some((row as f64 * 100.0 / rows as f64, class))
+        })
+        .collect();
+    let total_height = state.total_height().max(1.0);
+    let thumb_top = state.scroll_y / total_height * 100.0;
+    let thumb_height = (height / total_height * 100.0).min(100.0);
+    drop(state);
+
+    rsx! {
+        div {
+            class: "minimap",
+            title: "Errors (red), notes (yellow) and highlight matches (blue); click to jump",
+            onclick: move |e| {
+                let frac = e.element_coordinates().y / height.max(1.0);
+                let pos = markers::position_at((frac * rows as f64) as usize, total, rows);
+                app_state.write().scroll_to_position(pos);
+                on_jump.call(());
+            },
+            div { class: "minimap-thumb", style: "top: {thumb_top}%; height: {thumb_height}%;" }
+            for (top, class) in ticks {
+                div { class: "{class}", style: "top: {top}%;" }
+            }
+        }
+    }
+}
+
+/// SVG polyline points for `values`, scaled to a `width` x `height` box.
+fn sparkline_points(values: &[f64], width: f64, height: f64) -> String {
+    let (lo, hi) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let step = if values.len() > 1 { width / (values.len() - 1) as f64 } else { 0.0 };
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let y = if hi > lo { height - (v - lo) / (hi - lo) * height } else { height / 2.0 };
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[component]
+pub fn WatchPanel(app_state: Signal<GuiAppState>) -> Element {
+    let state = app_state.read();
+    let watch_text = state.watch_text.clone();
+    let watch_error = state.watch_error.clone();
+    let watches = state.log_state.watches.clone();
+    drop(state);
+
+    rsx! {
+        div { class: "watch-panel",
+            input {
+                r#type: "text",
+                spellcheck: "false",
+                class: if watch_error.is_some() { "error" } else { "" },
+                placeholder: "latency_ms=(\\d+) ...",
+                title: watch_error.unwrap_or_default(),
+                value: "{watch_text}",
+                oninput: move |e| app_state.write().watch_text = e.value(),
+                onkeydown: move |e| {
+                    if e.key() == Key::Enter {
+                        app_state.write().apply_watch();
+                    }
+                },
+            }
+            for watch in watches {
+                {
+                    let stats = watch.stats;
+                    let values: Vec<f64> = watch.history.iter().copied().collect();
+                    let points = sparkline_points(&values, 200.0, 40.0);
+                    rsx! {
+                        div { class: "watch",
+                            div { class: "watch-pattern", "{watch.pattern}" }
+                            if let (Some(latest), Some(avg)) = (stats.latest, stats.avg()) {
+                                div { class: "watch-latest",
+                                    "{format_value(latest)}"
+                                    span { class: "watch-count", "  n={stats.count}" }
+                                }
+                                div { class: "watch-stats",
+                                    "min {format_value(stats.min)}  max {format_value(stats.max)}  avg {format_value(avg)}"
+                                }
+                                svg {
+                                    class: "watch-chart",
+                                    view_box: "0 0 200 40",
+                                    preserve_aspect_ratio: "none",
+                                    polyline { points: "{points}" }
+                                }
+                            } else {
+                                div { class: "watch-stats", "no values yet" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lines and errors per time bucket; clicking one scrolls to its first line.
+#[component]
+pub fn OutlinePanel(app_state: Signal<GuiAppState>, on_jump: EventHandler<()>) -> Element {
+    let state = app_state.read();
+    let outline = &state.log_state.outline;
+    let size = outline.size;
+    let busiest = outline.buckets().map(|b| b.lines).max().unwrap_or(1).max(1);
+    let buckets: Vec<(String, usize, usize, usize, f64)> = outline
+        .buckets()
+        .map(|b| (b.label(), b.lines, b.errors, b.first_pos, b.lines as f64 * 100.0 / busiest as f64))
+        .collect();
+    drop(state);
+
+    rsx! {
+        div { class: "outline-panel",
+            div { class: "outline-header",
+                "Per {size.label()}"
+                button {
+                    onclick: move |_| app_state.write().log_state.set_outline_size(size.next()),
+                    "Per {size.next().label()}"
+                }
+            }
+            if buckets.is_empty() {
+                div { class: "watch-stats", "No lines yet" }
+            }
+            for (label, lines, errors, first_pos, width) in buckets {
+                div { class: "outline-bucket",
+                    onclick: move |_| {
+                        app_state.write().scroll_to_position(first_pos);
+                        on_jump.call(());
+                    },
+                    span { class: "outline-time", "{label}" }
+                    span { class: "outline-bar",
+                        div { style: "width: {width}%;" }
+                    }
+                    span { class: "outline-count", "{lines}" }
+                    if errors > 0 {
+                        span { class: "outline-errors", "{errors}E" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// SVG path data drawing every dark module as a unit square.
+fn qr_svg_path(qr: &QrMatrix) -> String {
+    let size = qr.size();
+    let mut path = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            if qr.is_dark(x, y) {
+                path.push_str(&format!("M{} {}h1v1h-1z", x, y));
+            }
+        }
+    }
+    path
+}
+
+fn get_copy_text_from_interfaces(state: &ListenState) -> Option<String> {
+    let port = state.port()?;
+    let interfaces = state.visible_interfaces();
+    let addr_info = interfaces
+        .iter()
+        .flat_map(|(_, addresses)| addresses)
+        .nth(state.selected_idx)?;
+    Some(format_listen_addr(&addr_info.host(), addr_info.ip.is_ipv6(), port, state.display_mode))
+}
+
+/// Copy `text` and describe how, for the popup to show.
+fn copy_to_clipboard(text: &str) -> String {
+    match clipboard::copy(text, false) {
+        Ok(method) => format!("Copied via {}", method),
+        Err(e) => format!("Copy failed: {:#}", e),
+    }
+}
 
 #[component]
 pub fn ListenPopup(listen_state: Signal<ListenState>) -> Element {
     let state = listen_state.read();
-    let port = state.port.unwrap_or(0);
-    let interfaces = state.network_interfaces.clone();
+    let port = state.port().unwrap_or(0);
+    let ports = state.ports.iter().map(u16::to_string).collect::<Vec<_>>().join(", ");
+    let port_label = (state.ports.len() > 1).then(|| state.port_label());
+    let interfaces: Vec<_> = state
+        .visible_interfaces()
+        .into_iter()
+        .map(|(iface, addresses)| (iface.clone(), addresses.into_iter().cloned().collect::<Vec<_>>()))
+        .collect();
     let display_mode = state.display_mode;
     let selected_idx = state.selected_idx;
+    let show_all = state.show_all;
     drop(state);
+    let mut copied: Signal<Option<String>> = use_signal(|| None);
 
     let mut addr_idx = 0usize;
-    let mode_str = match display_mode {
-        ListenDisplayMode::AddrPort => "[addr:port]  nc command",
-        ListenDisplayMode::NcCommand => " addr:port  [nc command]",
-    };
+    let mode_str = display_mode.label();
+    let qr = (display_mode == ListenDisplayMode::Qr)
+        .then(|| get_copy_text_from_interfaces(&listen_state.read()))
+        .flatten()
+        .and_then(|text| QrMatrix::new(&text).ok())
+        .map(|qr| (qr.size(), qr_svg_path(&qr)));
 
     rsx! {
         div { class: "popup-overlay",
@@ -110,6 +776,12 @@ pub fn ListenPopup(listen_state: Signal<ListenState>) -> Element {
                     Key::Tab => {
                         listen_state.write().toggle_display_mode();
                     }
+                    Key::Character(c) if c == "a" => {
+                        listen_state.write().toggle_show_all();
+                    }
+                    Key::Character(c) if c == "p" => {
+                        listen_state.write().next_port();
+                    }
                     Key::ArrowUp => {
                         listen_state.write().select_prev();
                     }
@@ -118,7 +790,7 @@ pub fn ListenPopup(listen_state: Signal<ListenState>) -> Element {
                     }
                     Key::Enter => {
                         if let Some(text) = get_copy_text_from_interfaces(&listen_state.read()) {
-                            copy_to_clipboard(&text);
+                            copied.set(Some(copy_to_clipboard(&text)));
                         }
                     }
                     _ => {}
@@ -127,45 +799,58 @@ pub fn ListenPopup(listen_state: Signal<ListenState>) -> Element {
             div { class: "popup",
                 onclick: move |e| e.stop_propagation(),
                 div { class: "popup-header",
-                    span { "Listening on port " }
-                    span { class: "popup-port", "{port}" }
+                    span { if port_label.is_some() { "Listening on ports " } else { "Listening on port " } }
+                    span { class: "popup-port", "{ports}" }
+                }
+                if let Some(port_label) = port_label {
+                    div { class: "popup-mode",
+                        span { class: "popup-label", "Port (p): " }
+                        span { class: "popup-mode-value", "{port_label}" }
+                    }
                 }
                 div { class: "popup-mode",
                     span { class: "popup-label", "Mode (Tab): " }
                     span { class: "popup-mode-value", "{mode_str}" }
                 }
+                div { class: "popup-mode",
+                    span { class: "popup-label", "Local-only (a): " }
+                    span { class: "popup-mode-value", if show_all { "shown" } else { "hidden" } }
+                }
                 div { class: "popup-hint", "↑↓:Select  Enter/Click:Copy" }
                 div { class: "popup-interfaces",
                     if interfaces.is_empty() {
                         div { class: "popup-error", "No network interfaces found" }
                     } else {
-                        for iface in interfaces.iter() {
+                        for (iface, addresses) in interfaces.iter() {
                             div { class: "popup-interface",
                                 div {
                                     class: if iface.is_default { "popup-iface-name default" } else { "popup-iface-name" },
                                     "{iface.name}"
                                     if iface.is_default { " (default)" }
                                 }
-                                for addr_info in iface.addresses.iter() {
+                                for addr_info in addresses.iter() {
                                     {
                                         let current_idx = addr_idx;
                                         addr_idx += 1;
                                         let is_selected = current_idx == selected_idx;
-                                        let ip = addr_info.ip;
-                                        let is_v6 = ip.is_ipv6();
-                                        let is_self_assigned = addr_info.is_self_assigned;
-                                        let display_text = format_addr_display(&ip, port, is_v6, display_mode);
+                                        let host = addr_info.host();
+                                        let is_v6 = addr_info.ip.is_ipv6();
+                                        let note = addr_info.note();
+                                        let display_text = format_listen_addr(&host, is_v6, port, display_mode);
                                         rsx! {
                                             div {
-                                                class: if is_selected { "popup-addr selected" } else if is_self_assigned { "popup-addr self-assigned" } else { "popup-addr" },
+                                                class: if is_selected { "popup-addr selected" } else if !note.is_empty() { "popup-addr local" } else { "popup-addr" },
                                                 onclick: move |_| {
                                                     listen_state.write().selected_idx = current_idx;
                                                     let mode = listen_state.read().display_mode;
-                                                    let text = format_addr_display(&ip, port, is_v6, mode);
-                                                    copy_to_clipboard(&text);
+                                                    let text = format_listen_addr(&host, is_v6, port, mode);
+                                                    copied.set(Some(copy_to_clipboard(&text)));
                                                 },
                                                 span { class: "popup-addr-indicator", if is_selected { "▶ " } else { "  " } }
                                                 span { class: "popup-addr-text", "{display_text}" }
+                                                if !note.is_empty() {
+                                                    span { class: "popup-addr-note", " ({note})" }
+                                                }
                                             }
                                         }
                                     }
@@ -174,6 +859,18 @@ pub fn ListenPopup(listen_state: Signal<ListenState>) -> Element {
                         }
                     }
                 }
+                if let Some(copied) = copied() {
+                    div { class: "popup-hint", "{copied}" }
+                }
+                if let Some((size, path)) = qr {
+                    svg {
+                        class: "popup-qr",
+                        view_box: "0 0 {size} {size}",
+                        shape_rendering: "crispEdges",
+                        rect { width: "{size}", height: "{size}", fill: "#fff" }
+                        path { d: "{path}", fill: "#000" }
+                    }
+                }
             }
         }
     }
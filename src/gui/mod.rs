@@ -8,14 +8,40 @@ use dioxus::desktop::{Config, WindowBuilder};
 use dioxus::prelude::*;
 use std::path::PathBuf;
 
+use crate::source::{CommandSource, FileOptions, ListenOptions, ListenPort};
+
 use app::GuiApp;
 
 static INIT_FILE: std::sync::OnceLock<Option<PathBuf>> = std::sync::OnceLock::new();
-static INIT_PORT: std::sync::OnceLock<Option<u16>> = std::sync::OnceLock::new();
+static INIT_FILE_OPTIONS: std::sync::OnceLock<FileOptions> = std::sync::OnceLock::new();
+static INIT_PORTS: std::sync::OnceLock<Vec<ListenPort>> = std::sync::OnceLock::new();
+static INIT_REPLAY: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+static INIT_COMMAND: std::sync::OnceLock<Option<CommandSource>> = std::sync::OnceLock::new();
+static INIT_WATCH_DIR: std::sync::OnceLock<Option<(PathBuf, String)>> = std::sync::OnceLock::new();
+static INIT_LISTEN: std::sync::OnceLock<ListenOptions> = std::sync::OnceLock::new();
+
+/// Token and TLS for the listen port; kept out of the props since
+/// `ServerConfig` can't be compared.
+pub fn listen_options() -> ListenOptions {
+    INIT_LISTEN.get().cloned().unwrap_or_default()
+}
 
-pub fn run_with_args(file: Option<PathBuf>, port: Option<u16>) -> Result<()> {
+pub fn run_with_args(
+    file: Option<PathBuf>,
+    file_options: FileOptions,
+    ports: Vec<ListenPort>,
+    listen: ListenOptions,
+    replay: bool,
+    command: Option<CommandSource>,
+    watch_dir: Option<(PathBuf, String)>,
+) -> Result<()> {
     INIT_FILE.set(file).ok();
-    INIT_PORT.set(port).ok();
+    INIT_FILE_OPTIONS.set(file_options).ok();
+    INIT_PORTS.set(ports).ok();
+    INIT_REPLAY.set(replay).ok();
+    INIT_COMMAND.set(command).ok();
+    INIT_WATCH_DIR.set(watch_dir).ok();
+    INIT_LISTEN.set(listen).ok();
 
     let window = WindowBuilder::new().with_always_on_top(false);
     let config = Config::default().with_window(window);
@@ -26,12 +52,20 @@ pub fn run_with_args(file: Option<PathBuf>, port: Option<u16>) -> Result<()> {
 
 fn app_with_args() -> Element {
     let file = INIT_FILE.get().cloned().flatten();
-    let port = INIT_PORT.get().cloned().flatten();
+    let file_options = INIT_FILE_OPTIONS.get().copied().unwrap_or_default();
+    let ports = INIT_PORTS.get().cloned().unwrap_or_default();
+    let replay = INIT_REPLAY.get().copied().unwrap_or(false);
+    let command = INIT_COMMAND.get().cloned().flatten();
+    let watch_dir = INIT_WATCH_DIR.get().cloned().flatten();
 
     rsx! {
         GuiApp {
             file: file,
-            port: port,
+            file_options: file_options,
+            ports: ports,
+            replay: replay,
+            command: command,
+            watch_dir: watch_dir,
         }
     }
 }
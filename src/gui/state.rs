@@ -1,38 +1,69 @@
-use crate::core::{FilterState, LogLine};
-use crate::filter::{parse_filter, FilterExpr};
-use crate::highlight::{apply_highlights, highlight_line, HighlightStyle};
+use crate::columns::{normalize, set_event_width, set_receipt_width, visible_columns, Column, TimeFormat};
+use crate::time_display::TimeDisplay;
+use crate::core::{DetailState, LogLine, LogState, Pins, Playground, PlaygroundTarget, Why};
+use crate::correlate::{self, Correlation};
+use crate::dedup::{Dedup, DEFAULT_WINDOW_SECS};
+use crate::enrich::Enricher;
+use crate::rdns::PeerNames;
+use crate::filter::{and_clause, field_pattern, literal_pattern, parse_filter, word_pattern, FilterExpr};
+use crate::highlight::{apply_highlights, highlight_line, token_at, ClickToken, ComposedStyle, HighlightSettings};
+use crate::plugin;
+use crate::replay::{Replay, ReplayHandle};
+use crate::bundle::{bundle_path, Bundle};
+use crate::session::{SavedNote, Session};
+use crate::layout::{self, Layout};
+use crate::level::{self, Level, LevelRule};
+use crate::prefix::{self, PrefixRule};
 use crate::state::AppState;
+use crate::tui::status::Segment;
 use fancy_regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 
 const LINE_HEIGHT: f64 = 20.0;
 
-pub fn highlight_content(content: &str, highlight_expr: &Option<FilterExpr>) -> Vec<(String, HighlightStyle)> {
+pub fn highlight_content(
+    content: &str,
+    level: Option<Level>,
+    highlight_expr: &Option<FilterExpr>,
+    settings: &HighlightSettings,
+) -> Vec<(String, ComposedStyle)> {
     let enable_highlight = content.len() <= 500;
     let spans = highlight_line(
         content,
+        level,
         if enable_highlight { highlight_expr.as_ref() } else { None },
         enable_highlight,
         enable_highlight,
+        settings,
     );
     apply_highlights(content, &spans)
 }
 
 #[derive(Clone)]
 pub struct GuiAppState {
-    pub lines: Vec<LogLine>,
-    pub filtered_indices: Vec<usize>,
-    pub filter_state: FilterState,
-    pub follow_tail: bool,
+    /// The buffer, filters and everything derived from them, shared with
+    /// the TUI.
+    pub log_state: LogState,
     pub show_time: bool,
     pub wrap_lines: bool,
     pub hide_text: String,
     pub filter_text: String,
     pub highlight_text: String,
     pub line_start_text: String,
+    pub rewrite_text: String,
+    pub watch_text: String,
+    pub follow_match_text: String,
     pub hide_error: Option<String>,
     pub filter_error: Option<String>,
     pub line_start_error: Option<String>,
+    pub rewrite_error: Option<String>,
+    pub watch_error: Option<String>,
+    pub follow_match_error: Option<String>,
     pub status_message: Option<String>,
+    /// A plugin export is still running.
+    pub exporting: bool,
     pub is_connected: bool,
     pub scroll_y: f64,
     pub scroll_x: f64,
@@ -42,27 +73,84 @@ pub struct GuiAppState {
     pub version: u64,
     pub line_heights: Vec<f64>,
     pub line_offsets: Vec<f64>,
-    pub last_update_time: Option<chrono::DateTime<chrono::Local>>,
+    pub detail: Option<DetailState>,
+    pub playground: Option<Playground>,
+    /// Why a line is shown or hidden, from the detail popup.
+    pub why: Option<Why>,
+    pub expanded_traces: HashSet<usize>,
+    pub columns: Vec<Column>,
+    pub show_watches: bool,
+    pub show_outline: bool,
+    pub show_lanes: bool,
+    /// Saved layouts, and the name of the one last switched to.
+    pub layouts: BTreeMap<String, Layout>,
+    pub layout: Option<String>,
+    /// Viewing a bundle: settings and notes are not written back.
+    pub read_only: bool,
+    pub replay: Option<ReplayHandle>,
+    /// Keyboard macros recorded in the TUI, kept so saving doesn't drop them.
+    pub macros: BTreeMap<char, String>,
+    /// TUI key bindings, kept the same way.
+    pub keymap: BTreeMap<String, String>,
+    /// The TUI's `gf` editor command, kept the same way.
+    pub editor_command: String,
+    /// The TUI's `gi` issue URL template, kept the same way.
+    pub issue_url: String,
+    pub git_blame: bool,
+    pub geoip_databases: Vec<String>,
+    pub reverse_dns: bool,
+    pub peer_names: PeerNames,
+    pub level_rules: Vec<LevelRule>,
+    pub prefix_rules: Vec<PrefixRule>,
+    pub status_segments: Vec<Segment>,
+    pub correlation_ids: Vec<String>,
+    pub time_format: TimeFormat,
+    pub time_display: TimeDisplay,
+    pub show_filter_stats: bool,
+    pub show_plugins: bool,
 }
 
 impl GuiAppState {
     pub fn new() -> Self {
-        let state = AppState::load();
+        let session = Session::load();
+        let mut s = Self::from_state(AppState::load(), session.notes);
+        s.log_state.pins = Pins::from_saved(session.pins);
+        s
+    }
+
+    fn from_state(state: AppState, notes: Vec<SavedNote>) -> Self {
+        let rule_errors = level::set_rules(&state.level_rules);
+        let prefix_errors = prefix::set_rules(&state.prefix_rules);
+        let time_display = TimeDisplay::new(&state.time_zone, &state.clock_format, &state.event_time_format);
+        let mut problems: Vec<String> = Vec::new();
+        if !rule_errors.is_empty() {
+            problems.push(format!("Bad level rule patterns: {}", rule_errors.join(", ")));
+        }
+        if !prefix_errors.is_empty() {
+            problems.push(format!("Bad prefix rule patterns: {}", prefix_errors.join(", ")));
+        }
+        if let Err(e) = &time_display {
+            problems.push(format!("{:#}; showing local times", e));
+        }
         let mut s = Self {
-            lines: Vec::new(),
-            filtered_indices: Vec::new(),
-            filter_state: FilterState::default(),
-            follow_tail: true,
+            log_state: LogState::from_state(&state, notes),
             show_time: true,
             wrap_lines: state.wrap_lines,
             hide_text: state.hide_input.clone(),
             filter_text: state.filter_input.clone(),
             highlight_text: state.highlight_input.clone(),
             line_start_text: state.line_start_regex.clone(),
+            rewrite_text: state.rewrite_input.clone(),
+            watch_text: state.watch_input.clone(),
+            follow_match_text: String::new(),
             hide_error: None,
             filter_error: None,
             line_start_error: None,
-            status_message: None,
+            rewrite_error: None,
+            watch_error: None,
+            follow_match_error: None,
+            status_message: (!problems.is_empty()).then(|| problems.join("; ")),
+            exporting: false,
             is_connected: false,
             scroll_y: 0.0,
             scroll_x: 0.0,
@@ -72,50 +160,100 @@ impl GuiAppState {
             version: 0,
             line_heights: Vec::new(),
             line_offsets: Vec::new(),
-            last_update_time: None,
+            detail: None,
+            playground: None,
+            why: None,
+            expanded_traces: HashSet::new(),
+            columns: normalize(state.columns.clone()),
+            show_watches: false,
+            show_outline: false,
+            show_lanes: false,
+            layouts: state.layouts.clone(),
+            layout: None,
+            read_only: false,
+            replay: None,
+            macros: state.macros.clone(),
+            keymap: state.keymap.clone(),
+            editor_command: state.editor_command.clone(),
+            issue_url: state.issue_url.clone(),
+            git_blame: state.git_blame,
+            geoip_databases: state.geoip_databases.clone(),
+            reverse_dns: state.reverse_dns,
+            peer_names: PeerNames::new(state.numeric_peers),
+            level_rules: state.level_rules.clone(),
+            prefix_rules: state.prefix_rules.clone(),
+            status_segments: state.status_segments.clone(),
+            correlation_ids: state.correlation_ids.clone(),
+            time_format: state.time_format,
+            time_display: time_display.unwrap_or_default(),
+            show_filter_stats: false,
+            show_plugins: false,
         };
-        if !s.hide_text.trim().is_empty() {
-            if let Ok(re) = Regex::new(&s.hide_text) {
-                s.filter_state.hide_regex = Some(re);
-            }
-        }
-        if !s.filter_text.trim().is_empty() {
-            if let Ok(expr) = parse_filter(&s.filter_text) {
-                s.filter_state.filter_expr = Some(expr);
-            }
-        }
-        if !s.highlight_text.trim().is_empty() {
-            if let Ok(expr) = parse_filter(&s.highlight_text) {
-                s.filter_state.highlight_expr = Some(expr);
-            }
-        }
+        s.hide_error = s.log_state.set_hide(&s.hide_text).err();
+        s.watch_error = s.log_state.set_watches(&s.watch_text).err();
+        s.show_watches = !s.log_state.watches.is_empty();
+        s.rewrite_error = s.log_state.set_rewrite(&s.rewrite_text).err();
+        s.filter_error = s.log_state.set_filter(&s.filter_text).err();
+        let _ = s.log_state.set_highlight(&s.highlight_text);
         s
     }
 
     pub fn get_display_content(&self, line: &LogLine) -> Result<String, String> {
-        self.filter_state.apply_hide(&line.content)
-    }
-
-    fn matches_filter(&self, line: &LogLine) -> bool {
-        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
-        self.filter_state.matches_filter(&content)
+        let text = self.log_state.filter_state.display(&line.content)?;
+        Ok(self.log_state.enriched(line, text))
     }
 
-    fn rebuild_filtered_indices(&mut self) {
-        self.filtered_indices.clear();
-        for (i, line) in self.lines.iter().enumerate() {
-            if self.matches_filter(line) {
-                self.filtered_indices.push(i);
-            }
+    /// Lay the view out afresh after the filtered lines changed, keeping the
+    /// line that was at the bottom of the view there.
+    fn reset_view(&mut self) {
+        self.max_content_width = 0.0;
+        for pos in 0..self.log_state.filtered_indices.len() {
+            self.fit_width(self.log_state.filtered_indices[pos]);
         }
         self.reset_line_heights();
-        self.scroll_y = 0.0;
+        self.scroll_y = match self.match_scroll_y() {
+            Some(y) => y,
+            None if self.log_state.follow_tail => self.max_scroll(),
+            None => {
+                let bottom = self.get_line_offset(self.log_state.get_bottom_line_idx() + 1);
+                (bottom - self.container_height).clamp(0.0, self.max_scroll())
+            }
+        };
         self.scroll_x = 0.0;
         self.version += 1;
     }
 
+    pub fn apply_follow_match(&mut self) {
+        if self.follow_match_text.trim().is_empty() {
+            self.stop_follow_match();
+            self.follow_match_error = None;
+            return;
+        }
+        match parse_filter(&self.follow_match_text) {
+            Ok(expr) => {
+                self.log_state.start_follow_match(expr);
+                self.follow_match_error = None;
+                if self.log_state.match_pos.is_none() {
+                    self.log_state.follow_tail = true;
+                }
+            }
+            Err(e) => self.follow_match_error = Some(e.to_string()),
+        }
+    }
+
+    pub fn stop_follow_match(&mut self) {
+        self.log_state.follow_match = None;
+        self.log_state.match_pos = None;
+    }
+
+    /// Scroll offset that puts the latest match at the top of the view.
+    pub fn match_scroll_y(&self) -> Option<f64> {
+        self.log_state.follow_match.as_ref()?;
+        self.log_state.match_pos.map(|pos| self.get_line_offset(pos).min(self.max_scroll()))
+    }
+
     fn reset_line_heights(&mut self) {
-        let count = self.filtered_indices.len();
+        let count = self.log_state.filtered_indices.len();
         self.line_heights = vec![LINE_HEIGHT; count];
         self.rebuild_offsets();
     }
@@ -145,7 +283,7 @@ impl GuiAppState {
     pub fn find_visible_range(&self, scroll_y: f64, viewport_height: f64) -> (usize, usize) {
         let start = self.line_offsets.partition_point(|&o| o <= scroll_y).saturating_sub(1);
         let end_scroll = scroll_y + viewport_height;
-        let end = self.line_offsets.partition_point(|&o| o < end_scroll).min(self.filtered_indices.len());
+        let end = self.line_offsets.partition_point(|&o| o < end_scroll).min(self.log_state.filtered_indices.len());
         (start, end)
     }
 
@@ -154,14 +292,90 @@ impl GuiAppState {
     }
 
     fn save_state(&self) {
-        let state = AppState {
+        if !self.read_only {
+            self.current_state().save();
+        }
+    }
+
+    fn current_state(&self) -> AppState {
+        AppState {
             hide_input: self.hide_text.clone(),
             filter_input: self.filter_text.clone(),
             highlight_input: self.highlight_text.clone(),
             wrap_lines: self.wrap_lines,
             line_start_regex: self.line_start_text.clone(),
-        };
-        state.save();
+            rewrite_input: self.rewrite_text.clone(),
+            watch_input: self.watch_text.clone(),
+            highlight_priorities: self.log_state.filter_state.highlight.priorities,
+            slow_query_ms: self.log_state.filter_state.highlight.slow_query_ms,
+            user_frames: self.log_state.filter_state.highlight.user_frames.clone(),
+            level_tint: self.log_state.filter_state.highlight.level_tint,
+            columns: self.columns.clone(),
+            macros: self.macros.clone(),
+            dedup_window_secs: self.log_state.dedup.as_ref().map(|d| d.window_secs),
+            saved_filters: self.log_state.saved_filters.clone(),
+            time_format: self.time_format,
+            time_zone: self.time_display.zone_name.clone(),
+            clock_format: self.time_display.clock_format.clone(),
+            event_time_format: self.time_display.event_format.clone(),
+            keymap: self.keymap.clone(),
+            status_segments: self.status_segments.clone(),
+            correlation_ids: self.correlation_ids.clone(),
+            humanize: self.log_state.filter_state.humanize.as_ref().map(|h| h.spec.clone()),
+            editor_command: self.editor_command.clone(),
+            issue_url: self.issue_url.clone(),
+            git_blame: self.git_blame,
+            geoip_databases: self.geoip_databases.clone(),
+            reverse_dns: self.reverse_dns,
+            numeric_peers: self.peer_names.numeric,
+            level_rules: self.level_rules.clone(),
+            prefix_rules: self.prefix_rules.clone(),
+            layouts: self.layouts.clone(),
+        }
+    }
+
+    /// Replace the buffer and settings with a bundle, read-only.
+    pub fn load_bundle(&mut self, bundle: Bundle) {
+        level::set_rules(&bundle.settings.level_rules);
+        let lines = bundle.log_lines();
+        *self = Self::from_state(bundle.settings, bundle.notes);
+        self.read_only = true;
+        self.log_state.load(lines);
+        self.reset_view();
+        self.status_message = Some("Read-only bundle".to_string());
+    }
+
+    /// Save the buffer, settings and notes as a bundle in the working directory.
+    pub fn export_bundle(&mut self) {
+        let name = chrono::Local::now().format("logviewer-%Y%m%d-%H%M%S").to_string();
+        let path = bundle_path(&name);
+        let bundle = Bundle::new(&self.log_state.lines, self.current_state(), self.log_state.notes.to_saved(&self.log_state.lines));
+        self.status_message = Some(match bundle.write(&path) {
+            Ok(()) => format!("Saved bundle {}", path.display()),
+            Err(e) => format!("Bundle failed: {:#}", e),
+        });
+    }
+
+    /// Hand the filtered lines to an exporter plugin.
+    /// Hand the filtered lines to an exporter plugin; the receiver gets its
+    /// result for `finish_export`. `None` while another export runs.
+    pub fn export_to_plugin(&mut self, name: &str) -> Option<Receiver<anyhow::Result<String>>> {
+        if self.exporting {
+            self.status_message = Some("An export is still running".to_string());
+            return None;
+        }
+        self.exporting = true;
+        self.status_message = Some(format!("Exporting to {}…", name));
+        let lines = self.log_state.filtered_indices.iter().filter_map(|&idx| self.log_state.lines.get(idx));
+        Some(plugin::export(name, lines))
+    }
+
+    pub fn finish_export(&mut self, result: anyhow::Result<String>) {
+        self.exporting = false;
+        self.status_message = Some(match result {
+            Ok(message) => message,
+            Err(e) => format!("Export failed: {:#}", e),
+        });
     }
 
     pub fn max_scroll_x(&self) -> f64 {
@@ -174,57 +388,94 @@ impl GuiAppState {
     }
 
     pub fn apply_hide(&mut self) {
-        if self.hide_text.trim().is_empty() {
-            self.filter_state.hide_regex = None;
-            self.hide_error = None;
-        } else {
-            match Regex::new(&self.hide_text) {
-                Ok(re) => {
-                    self.filter_state.hide_regex = Some(re);
-                    self.hide_error = None;
-                }
-                Err(e) => {
-                    self.hide_error = Some(e.to_string());
-                    return;
-                }
-            }
+        self.hide_error = self.log_state.set_hide(&self.hide_text).err();
+        self.refiltered(self.hide_error.is_none());
+    }
+
+    pub fn apply_rewrite(&mut self) {
+        self.rewrite_error = self.log_state.set_rewrite(&self.rewrite_text).err();
+        self.refiltered(self.rewrite_error.is_none());
+    }
+
+    pub fn apply_watch(&mut self) {
+        self.watch_error = self.log_state.set_watches(&self.watch_text).err();
+        if self.watch_error.is_none() {
+            self.show_watches = !self.log_state.watches.is_empty();
+            self.save_state();
         }
-        self.rebuild_filtered_indices();
-        self.save_state();
     }
 
-    pub fn apply_filter(&mut self) {
-        if self.filter_text.trim().is_empty() {
-            self.filter_state.filter_expr = None;
-            self.filter_error = None;
-        } else {
-            match parse_filter(&self.filter_text) {
-                Ok(expr) => {
-                    self.filter_state.filter_expr = Some(expr);
-                    self.filter_error = None;
-                }
+    pub fn toggle_raw(&mut self) {
+        self.log_state.toggle_raw();
+        self.reset_view();
+    }
+
+    pub fn toggle_epochs(&mut self) {
+        self.log_state.toggle_epochs();
+        self.reset_view();
+    }
+
+    pub fn toggle_enrich(&mut self) {
+        let enrich = match self.log_state.filter_state.enrich {
+            Some(_) => None,
+            None => match Enricher::open(&self.geoip_databases, self.reverse_dns) {
+                Ok(enricher) => Some(Arc::new(enricher)),
                 Err(e) => {
-                    self.filter_error = Some(e.to_string());
+                    self.status_message = Some(format!("{:#}", e));
                     return;
                 }
-            }
+            },
+        };
+        self.log_state.set_enrich(enrich);
+        self.reset_view();
+    }
+
+    /// Show host names reverse DNS found since the lines were looked up.
+    pub fn refresh_enrichment(&mut self) {
+        if self.log_state.refresh_enrichment() {
+            self.reset_view();
         }
-        self.rebuild_filtered_indices();
-        self.save_state();
+    }
+
+    pub fn toggle_dedup(&mut self) {
+        let dedup = match self.log_state.dedup {
+            Some(_) => None,
+            None => Some(Dedup::new(DEFAULT_WINDOW_SECS)),
+        };
+        self.log_state.set_dedup(dedup);
+        self.refiltered(true);
+    }
+
+    /// Humanize byte and duration fields with the default rules, or stop.
+    pub fn toggle_humanize(&mut self) {
+        let humanize = match self.log_state.filter_state.humanize {
+            Some(_) => None,
+            None => "".parse().ok(),
+        };
+        self.log_state.set_humanize(humanize);
+        self.refiltered(true);
+    }
+
+    pub fn apply_filter(&mut self) {
+        self.filter_error = self.log_state.set_filter(&self.filter_text).err();
+        self.refiltered(self.filter_error.is_none());
     }
 
     pub fn apply_highlight(&mut self) {
-        if self.highlight_text.trim().is_empty() {
-            self.filter_state.highlight_expr = None;
-        } else {
-            if let Ok(expr) = parse_filter(&self.highlight_text) {
-                self.filter_state.highlight_expr = Some(expr);
-            }
-        }
+        let _ = self.log_state.set_highlight(&self.highlight_text);
         self.version += 1;
         self.save_state();
     }
 
+    /// After a setting was applied: show the new filtered lines and keep
+    /// the setting, unless it failed to parse.
+    fn refiltered(&mut self, ok: bool) {
+        if ok {
+            self.reset_view();
+            self.save_state();
+        }
+    }
+
     pub fn apply_line_start(&mut self) {
         if self.line_start_text.trim().is_empty() {
             self.line_start_error = None;
@@ -243,52 +494,159 @@ impl GuiAppState {
         self.status_message = Some("Line start regex saved. Restart to apply.".to_string());
     }
 
-    pub fn add_line(&mut self, content: String) {
-        self.add_line_with_update(content, true);
+    /// Switch to the next layout by name; the TUI-only ruler is ignored.
+    pub fn next_layout(&mut self) {
+        let (name, layout) = layout::next(&self.layouts, self.layout.as_deref());
+        self.columns = normalize(layout.columns);
+        self.time_format = layout.time_format;
+        set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+        self.show_time = layout.show_time;
+        self.wrap_lines = layout.wrap_lines;
+        if self.wrap_lines {
+            self.scroll_x = 0.0;
+        }
+        self.show_watches = layout.show_watches;
+        self.show_outline = layout.show_outline;
+        self.show_lanes = layout.show_lanes;
+        self.log_state.set_lanes(self.show_lanes);
+        self.status_message = Some(format!("Layout: {}", name));
+        self.layout = Some(name);
+        if self.log_state.filter_state.show_raw != layout.show_raw {
+            self.toggle_raw();
+        } else {
+            self.version += 1;
+        }
+        self.save_state();
     }
 
-    pub fn add_line_with_update(&mut self, content: String, update_time: bool) {
-        let now = chrono::Local::now();
-        let line = LogLine {
-            content: content
-                .trim_end_matches('\n')
-                .trim_end_matches('\r')
-                .to_string(),
-            timestamp: now,
+    /// Save the view as the current layout, or as `custom` before any was
+    /// switched to.
+    pub fn save_layout(&mut self) {
+        let name = self.layout.clone().unwrap_or_else(|| "custom".to_string());
+        let show_ruler = layout::available(&self.layouts).get(&name).is_some_and(|l| l.show_ruler);
+        let layout = Layout {
+            columns: self.columns.clone(),
+            time_format: self.time_format,
+            show_time: self.show_time,
+            wrap_lines: self.wrap_lines,
+            show_raw: self.log_state.filter_state.show_raw,
+            show_watches: self.show_watches,
+            show_outline: self.show_outline,
+            show_lanes: self.show_lanes,
+            show_ruler,
         };
-        let idx = self.lines.len();
-        let matches = self.matches_filter(&line);
-        let estimated_width = self.estimate_line_width(&line);
-        if estimated_width > self.max_content_width {
-            self.max_content_width = estimated_width;
-        }
-        self.lines.push(line);
-        if update_time {
-            self.last_update_time = Some(now);
-        }
-        if matches {
-            self.filtered_indices.push(idx);
-            if self.line_offsets.is_empty() {
-                self.line_offsets.push(0.0);
-            }
+        self.layouts.insert(name.clone(), layout);
+        self.status_message = Some(format!("Saved layout {}", name));
+        self.layout = Some(name);
+        self.save_state();
+    }
+
+    pub fn cycle_time_format(&mut self) {
+        self.time_format = self.time_format.next();
+        set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+        self.version += 1;
+        self.save_state();
+    }
+
+    /// Switch clock and event times between local time and UTC.
+    pub fn toggle_utc(&mut self) {
+        let zone = if self.time_display.zone_label() == "local" { "UTC" } else { "local" };
+        if self.time_display.set_zone(zone).is_ok() {
+            set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+            self.version += 1;
+            self.save_state();
+        }
+    }
+
+    pub fn add_line(&mut self, content: String, source: Option<Arc<str>>) {
+        let content = content
+            .trim_end_matches('\n')
+            .trim_end_matches('\r')
+            .to_string();
+        if self.log_state.ingest(content, source) {
+            self.line_shown();
+        }
+        if self.time_display.check_rollover(&self.log_state.lines) {
+            set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+            set_event_width(&mut self.columns, &self.time_display);
+            self.version += 1;
+        }
+    }
+
+    pub fn add_system_line(&mut self, content: String) {
+        if self.log_state.ingest_system(content) {
+            self.line_shown();
+        }
+    }
+
+    /// Keep the active filter under a name.
+    pub fn save_filter(&mut self, name: &str) {
+        let name = name.trim();
+        let text = self.filter_text.trim().to_string();
+        if name.is_empty() || text.is_empty() {
+            return;
+        }
+        self.log_state.save_filter(name, text);
+        self.save_state();
+    }
+
+    pub fn drop_filter(&mut self, name: &str) {
+        self.log_state.drop_filter(name);
+        self.save_state();
+    }
+
+    pub fn use_filter(&mut self, text: &str) {
+        self.filter_text = text.to_string();
+        self.apply_filter();
+    }
+
+    /// Scroll so filtered position `pos` is in the middle of the view.
+    pub fn scroll_to_position(&mut self, pos: usize) {
+        self.scroll_y = self.get_line_offset(pos) - self.container_height / 2.0;
+        self.log_state.follow_tail = false;
+        self.stop_follow_match();
+        self.clamp_scroll();
+        self.version += 1;
+    }
+
+    /// Lay out the lines just appended to the filtered ones; a per-second
+    /// sample can let in several at once.
+    fn line_shown(&mut self) {
+        if self.line_offsets.is_empty() {
+            self.line_offsets.push(0.0);
+        }
+        for pos in self.line_heights.len()..self.log_state.filtered_indices.len() {
+            self.fit_width(self.log_state.filtered_indices[pos]);
             let current_total = self.line_offsets.last().copied().unwrap_or(0.0);
             self.line_heights.push(LINE_HEIGHT);
             self.line_offsets.push(current_total + LINE_HEIGHT);
         }
     }
 
-    fn estimate_line_width(&self, line: &LogLine) -> f64 {
-        let content = self.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+    /// Let in the per-second sample of a second that is over.
+    pub fn flush_sample(&mut self) {
+        if self.log_state.flush_sample() {
+            self.line_shown();
+            self.version += 1;
+        }
+    }
+
+    /// Widen the horizontal scroll range to fit line `idx`.
+    fn fit_width(&mut self, idx: usize) {
+        let content = self.log_state.display_content(&self.log_state.lines[idx]);
         let char_width = 7.2;
-        let timestamp_width = if self.show_time { 32.0 } else { 0.0 };
-        let line_num_width = 62.0;
-        let padding = 24.0;
-        timestamp_width + line_num_width + (content.len() as f64 * char_width) + padding
+        let column_margin = 12.0;
+        let columns_width: f64 = visible_columns(&self.columns, self.show_time)
+            .map(|c| c.width as f64 * char_width + column_margin)
+            .sum();
+        let lanes_width = if self.show_lanes { self.log_state.lanes.len() as f64 * 2.0 * char_width } else { 0.0 };
+        let padding = 24.0 + lanes_width;
+        let width = columns_width + (content.len() as f64 * char_width) + padding;
+        self.max_content_width = self.max_content_width.max(width);
     }
 
     pub fn clear(&mut self) {
-        self.lines.clear();
-        self.filtered_indices.clear();
+        self.log_state.clear();
         self.line_heights.clear();
         self.line_offsets.clear();
         self.line_offsets.push(0.0);
@@ -296,7 +654,155 @@ impl GuiAppState {
         self.scroll_x = 0.0;
         self.max_content_width = 0.0;
         self.version += 1;
-        self.last_update_time = None;
+        self.expanded_traces.clear();
+        if self.time_display.dated {
+            self.time_display.dated = false;
+            set_receipt_width(&mut self.columns, self.time_format, &self.time_display);
+            set_event_width(&mut self.columns, &self.time_display);
+        }
+    }
+
+    /// Add the highlighted token at byte `pos` of `segment` to the filter.
+    /// Filter to the lines sharing line `idx`'s request or trace ID and
+    /// report their time span.
+    pub fn correlate(&mut self, idx: usize) {
+        let patterns = correlate::compile(&self.correlation_ids);
+        let Some(id) = self.log_state.lines.get(idx).and_then(|l| correlate::extract_id(&patterns, &l.content)) else {
+            self.status_message = Some("No request or trace ID on this line".to_string());
+            return;
+        };
+        self.filter_text = word_pattern(&id);
+        self.apply_filter();
+        self.status_message = Correlation::find(&self.log_state.lines, &id).map(|found| found.summary());
+    }
+
+    pub fn filter_by_token(&mut self, segment: &str, pos: usize) {
+        let token = token_at(
+            segment,
+            pos,
+            self.log_state.filter_state.highlight_expr.as_ref(),
+            &self.log_state.filter_state.highlight,
+        );
+        let term = match token {
+            Some(ClickToken::Literal(text)) => literal_pattern(&text),
+            Some(ClickToken::Field { key, value }) => field_pattern(&key, &value),
+            None => return,
+        };
+        self.filter_text = and_clause(&self.filter_text, &term);
+        self.apply_filter();
+    }
+
+    /// Change the replay; bumps the version so the status redraws.
+    pub fn with_replay(&mut self, f: impl FnOnce(&mut Replay)) {
+        if let Some(mut replay) = self.replay.as_ref().and_then(|r| r.lock().ok()) {
+            f(&mut replay);
+        }
+        self.version += 1;
+    }
+
+    pub fn replay_label(&self) -> Option<String> {
+        self.replay.as_ref()?.lock().ok().map(|r| r.label())
+    }
+
+    /// Try the filter expression against a filtered line, by default the last.
+    pub fn open_playground(&mut self, line_idx: Option<usize>) {
+        let mut playground = Playground::new(PlaygroundTarget::Filter, &self.filter_text);
+        let pos = match line_idx {
+            Some(idx) => self.log_state.filtered_indices.iter().position(|&i| i == idx),
+            None => self.log_state.filtered_indices.len().checked_sub(1),
+        };
+        if let Some(pos) = pos {
+            playground.set_sample(pos, &self.sample_text(pos));
+        }
+        self.playground = Some(playground);
+    }
+
+    fn sample_text(&self, pos: usize) -> String {
+        let line = &self.log_state.lines[self.log_state.filtered_indices[pos]];
+        self.get_display_content(line).unwrap_or_else(|_| line.content.clone())
+    }
+
+    pub fn pick_playground_sample(&mut self, delta: isize) {
+        let count = self.log_state.filtered_indices.len();
+        let Some(playground) = &self.playground else {
+            return;
+        };
+        if count == 0 {
+            return;
+        }
+        let pos = match playground.sample_pos {
+            Some(pos) => pos.saturating_add_signed(delta).min(count - 1),
+            None => count - 1,
+        };
+        let text = self.sample_text(pos);
+        if let Some(playground) = &mut self.playground {
+            playground.set_sample(pos, &text);
+        }
+    }
+
+    pub fn set_playground_target(&mut self, target: PlaygroundTarget) {
+        let text = match target {
+            PlaygroundTarget::Filter => self.filter_text.clone(),
+            PlaygroundTarget::Highlight => self.highlight_text.clone(),
+        };
+        if let Some(playground) = &mut self.playground {
+            playground.target = target;
+            playground.expr = crate::input::TextInput::new(text);
+        }
+    }
+
+    pub fn apply_playground(&mut self) {
+        let Some(playground) = self.playground.take() else {
+            return;
+        };
+        match playground.target {
+            PlaygroundTarget::Filter => {
+                self.filter_text = playground.expr.text;
+                self.apply_filter();
+            }
+            PlaygroundTarget::Highlight => {
+                self.highlight_text = playground.expr.text;
+                self.apply_highlight();
+            }
+        }
+    }
+
+    /// Set the note on a line (empty removes it) and save the session.
+    pub fn set_note(&mut self, line_idx: usize, text: &str) {
+        self.log_state.notes.set(line_idx, text);
+        self.log_state.rebuild_markers();
+        self.save_session();
+        self.version += 1;
+    }
+
+    fn save_session(&self) {
+        if !self.read_only {
+            Session {
+                notes: self.log_state.notes.to_saved(&self.log_state.lines),
+                pins: self.log_state.pins.to_saved(&self.log_state.lines),
+            }
+            .save();
+        }
+    }
+
+    /// Pin a line above the log view, or unpin it.
+    pub fn toggle_pin(&mut self, line_idx: usize) {
+        if let Err(e) = self.log_state.pins.toggle(line_idx) {
+            self.status_message = Some(e);
+        }
+        self.save_session();
+    }
+
+    pub fn toggle_note(&mut self, line_idx: usize) {
+        self.log_state.notes.toggle_expanded(line_idx);
+        self.version += 1;
+    }
+
+    pub fn toggle_trace(&mut self, line_idx: usize) {
+        if !self.expanded_traces.remove(&line_idx) {
+            self.expanded_traces.insert(line_idx);
+        }
+        self.version += 1;
     }
 
     pub fn max_scroll(&self) -> f64 {
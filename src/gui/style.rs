@@ -93,6 +93,37 @@ html, body {
     color: #ffffff;
 }
 
+.pinned-lines {
+    font-family: monospace;
+    font-size: 12px;
+    border-bottom: 1px solid light-dark(#d0d0d0, #3c3c3c);
+    background: light-dark(#fffbe6, #2a2718);
+    padding: 2px 8px;
+}
+
+.pinned-line {
+    display: flex;
+    gap: 8px;
+    align-items: center;
+    white-space: nowrap;
+}
+
+.pinned-number {
+    color: #858585;
+    min-width: 4em;
+    text-align: right;
+}
+
+.pinned-text {
+    flex: 1;
+    overflow: hidden;
+    text-overflow: ellipsis;
+}
+
+.pinned-line button {
+    padding: 0 4px;
+}
+
 .log-wrapper {
     flex: 1;
     display: flex;
@@ -108,6 +139,140 @@ html, body {
     overflow: hidden;
 }
 
+.minimap {
+    width: 10px;
+    flex-shrink: 0;
+    position: relative;
+    cursor: pointer;
+    background: light-dark(#f3f3f3, #252526);
+    border-left: 1px solid light-dark(#e0e0e0, #333333);
+}
+
+.minimap-thumb {
+    position: absolute;
+    left: 0;
+    right: 0;
+    min-height: 4px;
+    background: light-dark(rgba(0, 0, 0, 0.12), rgba(255, 255, 255, 0.12));
+}
+
+.minimap-tick {
+    position: absolute;
+    left: 1px;
+    right: 1px;
+    height: 3px;
+}
+
+.minimap-tick.error {
+    background: #f44747;
+}
+
+.minimap-tick.note {
+    background: #d7ba7d;
+}
+
+.minimap-tick.match {
+    background: #3794ff;
+}
+
+.watch-panel {
+    width: 260px;
+    flex-shrink: 0;
+    overflow-y: auto;
+    padding: 8px;
+    border-left: 1px solid light-dark(#e0e0e0, #3c3c3c);
+    background: light-dark(#f8f8f8, #252526);
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+}
+
+.outline-panel {
+    width: 240px;
+    flex-shrink: 0;
+    overflow-y: auto;
+    padding: 8px;
+    border-right: 1px solid light-dark(#e0e0e0, #3c3c3c);
+    background: light-dark(#f8f8f8, #252526);
+    font-family: monospace;
+    font-size: 12px;
+}
+
+.outline-header {
+    display: flex;
+    justify-content: space-between;
+    align-items: center;
+    margin-bottom: 6px;
+}
+
+.outline-bucket {
+    display: flex;
+    align-items: center;
+    gap: 6px;
+    padding: 1px 2px;
+    cursor: pointer;
+}
+
+.outline-bucket:hover {
+    background: light-dark(#e8e8e8, #2a2d2e);
+}
+
+.outline-bar {
+    flex: 1;
+    height: 8px;
+}
+
+.outline-bar div {
+    height: 100%;
+    background: light-dark(#0070c1, #4fc1ff);
+}
+
+.outline-count {
+    min-width: 4em;
+    text-align: right;
+}
+
+.outline-errors {
+    color: #f14c4c;
+}
+
+.watch {
+    font-family: monospace;
+    font-size: 12px;
+}
+
+.watch-pattern {
+    color: light-dark(#af00db, #c586c0);
+    overflow: hidden;
+    text-overflow: ellipsis;
+    white-space: nowrap;
+}
+
+.watch-latest {
+    font-size: 18px;
+    font-weight: bold;
+    color: light-dark(#bf8803, #dcdcaa);
+}
+
+.watch-count,
+.watch-stats {
+    font-size: 12px;
+    font-weight: normal;
+    color: light-dark(#858585, #858585);
+}
+
+.watch-chart {
+    width: 100%;
+    height: 40px;
+}
+
+.watch-chart polyline {
+    fill: none;
+    stroke: light-dark(#0070c1, #4fc1ff);
+    stroke-width: 1.5;
+    vector-effect: non-scaling-stroke;
+}
+
 .log-container {
     flex: 1;
     overflow-y: auto;
@@ -196,6 +361,129 @@ html, body {
     flex-shrink: 0;
 }
 
+.note-gutter {
+    width: 1.2em;
+    flex-shrink: 0;
+    cursor: pointer;
+    color: light-dark(#bf8803, #dcdcaa);
+}
+
+.note-inline {
+    padding-left: 16px;
+    font-style: italic;
+    color: light-dark(#bf8803, #dcdcaa);
+    white-space: pre-wrap;
+}
+
+.detail-note {
+    width: 100%;
+    margin: 6px 0;
+}
+
+.replay-status {
+    font-family: monospace;
+    font-size: 12px;
+    color: light-dark(#af00db, #c586c0);
+    align-self: center;
+}
+
+.col {
+    margin-right: 12px;
+    flex-shrink: 0;
+    overflow: hidden;
+    white-space: nowrap;
+    text-overflow: ellipsis;
+}
+
+.col-event-time {
+    color: light-dark(#af00db, #c586c0);
+}
+
+.col-source {
+    color: light-dark(#0070c1, #4fc1ff);
+}
+
+.col-thread {
+    color: light-dark(#616161, #9d9d9d);
+}
+
+.col-field {
+    color: light-dark(#267f99, #4ec9b0);
+}
+
+.lane {
+    width: 2ch;
+    flex-shrink: 0;
+    opacity: 0.45;
+}
+
+.lane.own {
+    opacity: 1;
+    font-weight: bold;
+}
+
+.lane-0 {
+    color: light-dark(#0070c1, #4fc1ff);
+}
+
+.lane-1 {
+    color: light-dark(#af00db, #d670d6);
+}
+
+.lane-2 {
+    color: light-dark(#098658, #4ec9b0);
+}
+
+.lane-3 {
+    color: light-dark(#bf8803, #dcdcaa);
+}
+
+.lane-4 {
+    color: light-dark(#007acc, #9cdcfe);
+}
+
+.lane-5 {
+    color: light-dark(#cd3131, #f48771);
+}
+
+.lane-6 {
+    color: light-dark(#c15f00, #ce9178);
+}
+
+.lane-7 {
+    color: light-dark(#5a5ad6, #a0a0ff);
+}
+
+.col-level {
+    box-sizing: content-box;
+    padding: 0 3px;
+    border-radius: 3px;
+    text-align: center;
+    font-weight: bold;
+    color: #ffffff;
+}
+
+.col-level.level-err {
+    background: light-dark(#cd3131, #c72e2e);
+}
+
+.col-level.level-wrn {
+    background: light-dark(#bf8803, #a07800);
+    color: #1e1e1e;
+}
+
+.col-level.level-inf {
+    background: light-dark(#098658, #2e7d5b);
+}
+
+.col-level.level-dbg {
+    background: light-dark(#0070c1, #1f6fb2);
+}
+
+.col-level.level-trc {
+    background: light-dark(#858585, #5a5a5a);
+}
+
 .content {
     color: light-dark(#1e1e1e, #d4d4d4);
 }
@@ -240,12 +528,26 @@ html, body {
 
 .hl-custom {
     background: light-dark(#ffff00, #ffcc00);
-    color: light-dark(#000000, #000000);
-    padding: 0 2px;
     border-radius: 2px;
     font-weight: bold;
 }
 
+.hl-tint-debug {
+    color: light-dark(#8a8a8a, #6e7681);
+}
+
+.hl-tint-warn {
+    color: light-dark(#a86f00, #d29922);
+}
+
+.hl-tint-error {
+    color: light-dark(#c62828, #f85149);
+}
+
+.hl-bg-only {
+    color: light-dark(#000000, #000000);
+}
+
 .hl-json-key {
     color: light-dark(#17a2b8, #58a6ff);
 }
@@ -266,6 +568,99 @@ html, body {
     color: light-dark(#dc3545, #f85149);
 }
 
+.hl-json-punct {
+    color: light-dark(#858585, #6e7681);
+}
+
+.hl-xml-tag {
+    color: light-dark(#0066cc, #79c0ff);
+}
+
+.hl-xml-attr {
+    color: light-dark(#17a2b8, #58a6ff);
+}
+
+.hl-xml-attr-value {
+    color: light-dark(#28a745, #3fb950);
+}
+
+.hl-xml-text {
+    font-weight: bold;
+}
+
+.hl-xml-comment {
+    color: light-dark(#858585, #6e7681);
+}
+
+.hl-sql-keyword {
+    color: light-dark(#af00db, #c586c0);
+    font-weight: bold;
+}
+
+.hl-sql-string {
+    color: light-dark(#28a745, #3fb950);
+}
+
+.hl-sql-number {
+    color: light-dark(#b08800, #d29922);
+}
+
+.hl-user-frame {
+    color: light-dark(#0066cc, #79c0ff);
+    font-weight: bold;
+}
+
+.hl-library-frame {
+    color: light-dark(#858585, #6e7681);
+}
+
+.log-line.system .system-text {
+    color: light-dark(#858585, #6e7681);
+    font-style: italic;
+}
+
+.trace-marker {
+    color: light-dark(#858585, #6e7681);
+    cursor: pointer;
+    padding-left: 16px;
+}
+
+.trace-marker:hover {
+    color: light-dark(#1e1e1e, #d4d4d4);
+}
+
+.concealed-matches {
+    color: #3794ff;
+}
+
+.repeat-badge {
+    margin-left: 8px;
+    padding: 0 5px;
+    border-radius: 8px;
+    font-size: 11px;
+    background: light-dark(#e0e0e0, #3a3d41);
+    color: light-dark(#555555, #a0a0a0);
+}
+
+.hl-url {
+    color: light-dark(#0066cc, #58a6ff);
+    text-decoration: underline;
+}
+
+.hl-ip {
+    color: light-dark(#af00db, #d2a8ff);
+}
+
+.hl-uuid {
+    color: light-dark(#b08800, #e3b341);
+}
+
+.hl-slow-query {
+    background: #f44747;
+    border-radius: 2px;
+    font-weight: bold;
+}
+
 .scrollbar {
     width: 14px;
     background: light-dark(#f0f0f0, #1e1e1e);
@@ -424,7 +819,7 @@ html, body {
     background: light-dark(#007acc33, #007acc44);
 }
 
-.popup-addr.self-assigned {
+.popup-addr.local {
     opacity: 0.5;
 }
 
@@ -437,4 +832,127 @@ html, body {
 .popup-addr-text {
     color: light-dark(#1e1e1e, #d4d4d4);
 }
+
+.popup-qr {
+    display: block;
+    width: 200px;
+    height: 200px;
+    margin: 12px auto 0;
+}
+
+.popup-addr-note {
+    color: #808080;
+    font-style: italic;
+}
+
+.popup button {
+    background: light-dark(#e0e0e0, #3c3c3c);
+    border: 1px solid light-dark(#c4c4c4, #4c4c4c);
+    border-radius: 3px;
+    color: light-dark(#1e1e1e, #d4d4d4);
+    padding: 2px 8px;
+    margin: 0 4px 8px 0;
+    font-size: 12px;
+    cursor: pointer;
+}
+
+.popup button.active {
+    background: #007acc;
+    border-color: #007acc;
+    color: #ffffff;
+}
+
+.popup.playground-popup {
+    max-width: 80vw;
+    width: 60vw;
+}
+
+.playground-popup input,
+.playground-sample {
+    width: 100%;
+    box-sizing: border-box;
+    font-family: inherit;
+    margin-bottom: 8px;
+}
+
+.playground-sample {
+    min-height: 48px;
+    resize: vertical;
+}
+
+.playground-grouping {
+    font-family: monospace;
+    color: light-dark(#0070c1, #9cdcfe);
+    margin-bottom: 6px;
+    overflow-wrap: anywhere;
+}
+
+.playground-node {
+    font-family: monospace;
+    color: #f44747;
+}
+
+.playground-node.matched {
+    color: light-dark(#098658, #4ec9b0);
+}
+
+.popup.filter-stats-popup {
+    max-width: 80vw;
+}
+
+.filter-stats {
+    border-collapse: collapse;
+    margin-bottom: 8px;
+    font-size: 12px;
+}
+
+.filter-stats th,
+.filter-stats td {
+    padding: 2px 8px;
+    text-align: left;
+}
+
+.filter-stats td.num {
+    text-align: right;
+    font-variant-numeric: tabular-nums;
+}
+
+.filter-stats td.plugin-status.ready {
+    color: light-dark(#098658, #4ec9b0);
+}
+
+.filter-stats td.plugin-status.starting {
+    color: light-dark(#b58900, #dcdcaa);
+}
+
+.filter-stats td.plugin-status.failed {
+    color: light-dark(#cd3131, #f48771);
+}
+
+.filter-stats td.expr {
+    font-family: monospace;
+    color: light-dark(#555555, #a0a0a0);
+}
+
+.popup.detail-popup {
+    max-width: 80vw;
+    width: 80vw;
+}
+
+.detail-blame {
+    margin-top: 6px;
+    font-family: 'SF Mono', Menlo, Monaco, 'Courier New', monospace;
+    font-size: 11px;
+    color: light-dark(#6a6a6a, #9d9d9d);
+}
+
+.detail-body {
+    max-height: 70vh;
+    overflow: auto;
+    font-family: 'SF Mono', Menlo, Monaco, 'Courier New', monospace;
+    font-size: 12px;
+    white-space: pre-wrap;
+    word-break: break-all;
+    color: light-dark(#1e1e1e, #d4d4d4);
+}
 "#;
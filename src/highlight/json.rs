@@ -0,0 +1,368 @@
+use super::{HighlightStyle, Span};
+use std::ops::Range;
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonTokenKind {
+    Key,
+    String,
+    Number,
+    Bool,
+    Null,
+    Punct,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct JsonToken {
+    pub kind: JsonTokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find every complete JSON object or array embedded in `text` and return its
+/// tokens with exact byte offsets. Text that only looks like JSON (an
+/// unterminated `{`, `[INFO]`) produces no tokens.
+pub fn tokenize_embedded(text: &str) -> Vec<JsonToken> {
+    scan_embedded(text)
+        .into_iter()
+        .flat_map(|(_, tokens)| tokens)
+        .collect()
+}
+
+pub(super) fn scan_embedded(text: &str) -> Vec<(Range<usize>, Vec<JsonToken>)> {
+    let bytes = text.as_bytes();
+    let mut values = Vec::new();
+    let mut pos = 0;
+
+    while let Some(rel) = bytes[pos..].iter().position(|&b| b == b'{' || b == b'[') {
+        let start = pos + rel;
+        let mut scanner = Scanner {
+            bytes,
+            pos: start,
+            tokens: Vec::new(),
+        };
+        if scanner.value(0).is_some() {
+            pos = scanner.pos;
+            values.push((start..pos, scanner.tokens));
+        } else {
+            pos = start + 1;
+        }
+    }
+    values
+}
+
+/// Re-indent every embedded JSON value, keeping key order and the original
+/// spelling of numbers and strings. Surrounding text stays on its own lines.
+pub fn pretty_print_json(text: &str) -> Option<String> {
+    let values = scan_embedded(text);
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut last = 0;
+    for (range, tokens) in values {
+        let between = text[last..range.start].trim();
+        if !between.is_empty() {
+            out.push_str(between);
+            out.push('\n');
+        }
+        write_pretty(text, &tokens, &mut out);
+        out.push('\n');
+        last = range.end;
+    }
+    let tail = text[last..].trim();
+    if tail.is_empty() {
+        out.pop();
+    } else {
+        out.push_str(tail);
+    }
+    Some(out)
+}
+
+fn write_pretty(text: &str, tokens: &[JsonToken], out: &mut String) {
+    let mut depth = 0usize;
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+
+    for (i, tok) in tokens.iter().enumerate() {
+        let s = &text[tok.start..tok.end];
+        match (tok.kind, s) {
+            (JsonTokenKind::Punct, "{" | "[") => {
+                out.push_str(s);
+                let empty = tokens
+                    .get(i + 1)
+                    .is_some_and(|next| matches!(&text[next.start..next.end], "}" | "]"));
+                if !empty {
+                    depth += 1;
+                    newline(out, depth);
+                }
+            }
+            (JsonTokenKind::Punct, "}" | "]") => {
+                let empty = i > 0 && matches!(&text[tokens[i - 1].start..tokens[i - 1].end], "{" | "[");
+                if !empty {
+                    depth = depth.saturating_sub(1);
+                    newline(out, depth);
+                }
+                out.push_str(s);
+            }
+            (JsonTokenKind::Punct, ",") => {
+                out.push(',');
+                newline(out, depth);
+            }
+            (JsonTokenKind::Punct, ":") => out.push_str(": "),
+            _ => out.push_str(s),
+        }
+    }
+}
+
+pub(super) fn token_style(kind: JsonTokenKind) -> HighlightStyle {
+    match kind {
+        JsonTokenKind::Key => HighlightStyle::JsonKey,
+        JsonTokenKind::String => HighlightStyle::JsonString,
+        JsonTokenKind::Number => HighlightStyle::JsonNumber,
+        JsonTokenKind::Bool => HighlightStyle::JsonBool,
+        JsonTokenKind::Null => HighlightStyle::JsonNull,
+        JsonTokenKind::Punct => HighlightStyle::JsonPunct,
+    }
+}
+
+pub fn highlight_json(text: &str, priority: u8) -> Option<Vec<Span>> {
+    let tokens = tokenize_embedded(text);
+    if tokens.is_empty() {
+        return None;
+    }
+    Some(
+        tokens
+            .into_iter()
+            .map(|t| Span {
+                start: t.start,
+                end: t.end,
+                style: token_style(t.kind),
+                priority,
+            })
+            .collect(),
+    )
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<JsonToken>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn push(&mut self, kind: JsonTokenKind, start: usize) {
+        self.tokens.push(JsonToken {
+            kind,
+            start,
+            end: self.pos,
+        });
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn punct(&mut self, expected: u8) -> Option<()> {
+        if self.peek() != Some(expected) {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += 1;
+        self.push(JsonTokenKind::Punct, start);
+        Some(())
+    }
+
+    fn value(&mut self, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match self.peek()? {
+            b'{' => self.object(depth),
+            b'[' => self.array(depth),
+            b'"' => self.string(JsonTokenKind::String),
+            b't' => self.literal(b"true", JsonTokenKind::Bool),
+            b'f' => self.literal(b"false", JsonTokenKind::Bool),
+            b'n' => self.literal(b"null", JsonTokenKind::Null),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => None,
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Option<()> {
+        self.punct(b'{')?;
+        self.skip_ws();
+        if self.punct(b'}').is_some() {
+            return Some(());
+        }
+        loop {
+            self.skip_ws();
+            self.string(JsonTokenKind::Key)?;
+            self.skip_ws();
+            self.punct(b':')?;
+            self.skip_ws();
+            self.value(depth + 1)?;
+            self.skip_ws();
+            if self.punct(b',').is_some() {
+                continue;
+            }
+            return self.punct(b'}');
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Option<()> {
+        self.punct(b'[')?;
+        self.skip_ws();
+        if self.punct(b']').is_some() {
+            return Some(());
+        }
+        loop {
+            self.skip_ws();
+            self.value(depth + 1)?;
+            self.skip_ws();
+            if self.punct(b',').is_some() {
+                continue;
+            }
+            return self.punct(b']');
+        }
+    }
+
+    fn string(&mut self, kind: JsonTokenKind) -> Option<()> {
+        if self.peek() != Some(b'"') {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    self.push(kind, start);
+                    return Some(());
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    match self.peek()? {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => self.pos += 1,
+                        b'u' => {
+                            let hex = self.bytes.get(self.pos + 1..self.pos + 5)?;
+                            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                                return None;
+                            }
+                            self.pos += 5;
+                        }
+                        _ => return None,
+                    }
+                }
+                0x00..=0x1f => return None,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn literal(&mut self, word: &[u8], kind: JsonTokenKind) -> Option<()> {
+        if !self.bytes[self.pos..].starts_with(word) {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += word.len();
+        self.push(kind, start);
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<()> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => self.digits(),
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return None;
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return None;
+            }
+            self.digits();
+        }
+        self.push(JsonTokenKind::Number, start);
+        Some(())
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens_of(text: &str, kind: JsonTokenKind) -> Vec<&str> {
+        tokenize_embedded(text)
+            .into_iter()
+            .filter(|t| t.kind == kind)
+            .map(|t| &text[t.start..t.end])
+            .collect()
+    }
+
+    #[test]
+    fn test_repeated_values_get_exact_offsets() {
+        let text = r#"x {"a":1,"b":1,"c":"a"}"#;
+        let numbers: Vec<usize> = tokenize_embedded(text)
+            .into_iter()
+            .filter(|t| t.kind == JsonTokenKind::Number)
+            .map(|t| t.start)
+            .collect();
+        assert_eq!(numbers, vec![7, 13]);
+        assert_eq!(tokens_of(text, JsonTokenKind::Key), vec![r#""a""#, r#""b""#, r#""c""#]);
+        assert_eq!(tokens_of(text, JsonTokenKind::String), vec![r#""a""#]);
+    }
+
+    #[test]
+    fn test_rejects_non_json_brackets() {
+        assert!(tokenize_embedded("[INFO] starting {unterminated").is_empty());
+    }
+
+    #[test]
+    fn test_pretty_print_keeps_order() {
+        let text = r#"req {"z":1,"a":[],"o":{"k":2.50}} ok"#;
+        assert_eq!(
+            pretty_print_json(text).unwrap(),
+            "req\n{\n  \"z\": 1,\n  \"a\": [],\n  \"o\": {\n    \"k\": 2.50\n  }\n}\nok"
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_nesting() {
+        let text = r#"{"k":["a\"b",true,null,-1.5e3,{}]} tail [2]"#;
+        assert_eq!(tokens_of(text, JsonTokenKind::String), vec![r#""a\"b""#]);
+        assert_eq!(tokens_of(text, JsonTokenKind::Bool), vec!["true"]);
+        assert_eq!(tokens_of(text, JsonTokenKind::Null), vec!["null"]);
+        assert_eq!(tokens_of(text, JsonTokenKind::Number), vec!["-1.5e3", "2"]);
+    }
+}
@@ -0,0 +1,264 @@
+use super::json::{scan_embedded, token_style, JsonToken, JsonTokenKind};
+use super::HighlightStyle;
+use std::collections::HashSet;
+
+#[derive(Clone)]
+enum Node {
+    Scalar(JsonToken),
+    Object(Vec<(JsonToken, Node)>),
+    Array(Vec<Node>),
+}
+
+/// Build the node starting at `tokens[*pos]`; the scanner already checked
+/// the tokens are well-formed JSON.
+fn parse(text: &str, tokens: &[JsonToken], pos: &mut usize) -> Option<Node> {
+    let token = *tokens.get(*pos)?;
+    *pos += 1;
+    let punct = |pos: usize| tokens.get(pos).map(|t| &text[t.start..t.end]);
+    match punct(*pos - 1) {
+        Some("{") if token.kind == JsonTokenKind::Punct => {
+            let mut members = Vec::new();
+            while punct(*pos) != Some("}") {
+                let key = *tokens.get(*pos)?;
+                *pos += 2;
+                members.push((key, parse(text, tokens, pos)?));
+                if punct(*pos) == Some(",") {
+                    *pos += 1;
+                }
+            }
+            *pos += 1;
+            Some(Node::Object(members))
+        }
+        Some("[") if token.kind == JsonTokenKind::Punct => {
+            let mut items = Vec::new();
+            while punct(*pos) != Some("]") {
+                items.push(parse(text, tokens, pos)?);
+                if punct(*pos) == Some(",") {
+                    *pos += 1;
+                }
+            }
+            *pos += 1;
+            Some(Node::Array(items))
+        }
+        _ => Some(Node::Scalar(token)),
+    }
+}
+
+/// `.name`, or `["odd key"]` when it isn't an identifier.
+fn key_step(raw: &str) -> String {
+    let key: String = serde_json::from_str(raw).unwrap_or_else(|_| raw.trim_matches('"').to_string());
+    let mut chars = key.chars();
+    let identifier = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if identifier {
+        format!(".{}", key)
+    } else {
+        format!("[{}]", serde_json::to_string(&key).unwrap_or_default())
+    }
+}
+
+/// One row of the tree: a member, an element, a closing bracket, or text
+/// around the JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeRow {
+    /// JSONPath of the member, or of the container a closing bracket ends.
+    pub path: Option<String>,
+    /// Which embedded value of the line it belongs to.
+    value: usize,
+    pub container: bool,
+    pub parts: Vec<(String, HighlightStyle)>,
+}
+
+/// The JSON of a log line as a tree whose objects and arrays fold.
+#[derive(Clone)]
+pub struct JsonTree {
+    text: String,
+    values: Vec<(usize, usize, Node)>,
+    /// Folded containers by embedded value and path.
+    collapsed: HashSet<(usize, String)>,
+    pub selected: usize,
+}
+
+struct Flatten<'a> {
+    text: &'a str,
+    value: usize,
+    collapsed: &'a HashSet<(usize, String)>,
+    rows: Vec<TreeRow>,
+}
+
+impl Flatten<'_> {
+    fn node(&mut self, node: &Node, key: Option<&JsonToken>, path: String, depth: usize, comma: bool) {
+        let mut parts = vec![("  ".repeat(depth), HighlightStyle::None)];
+        if let Some(key) = key {
+            parts.push((self.text[key.start..key.end].to_string(), HighlightStyle::JsonKey));
+            parts.push((": ".to_string(), HighlightStyle::JsonPunct));
+        }
+        let comma_part = || (if comma { "," } else { "" }.to_string(), HighlightStyle::JsonPunct);
+        let (open, close, count, noun) = match node {
+            Node::Scalar(token) => {
+                parts.push((self.text[token.start..token.end].to_string(), token_style(token.kind)));
+                parts.push(comma_part());
+                self.push(Some(path), false, parts);
+                return;
+            }
+            Node::Object(members) => ("{", "}", members.len(), "keys"),
+            Node::Array(items) => ("[", "]", items.len(), "items"),
+        };
+        if count == 0 {
+            parts.push((format!("{}{}", open, close), HighlightStyle::JsonPunct));
+            parts.push(comma_part());
+            self.push(Some(path), false, parts);
+            return;
+        }
+        if self.collapsed.contains(&(self.value, path.clone())) {
+            parts.push((format!("{}…{}", open, close), HighlightStyle::JsonPunct));
+            parts.push(comma_part());
+            parts.push((format!("  {} {}", count, noun), HighlightStyle::None));
+            self.push(Some(path), true, parts);
+            return;
+        }
+        parts.push((open.to_string(), HighlightStyle::JsonPunct));
+        self.push(Some(path.clone()), true, parts);
+        match node {
+            Node::Object(members) => {
+                for (i, (key, child)) in members.iter().enumerate() {
+                    let child_path = path.clone() + &key_step(&self.text[key.start..key.end]);
+                    self.node(child, Some(key), child_path, depth + 1, i + 1 < count);
+                }
+            }
+            Node::Array(items) => {
+                for (i, child) in items.iter().enumerate() {
+                    self.node(child, None, format!("{}[{}]", path, i), depth + 1, i + 1 < count);
+                }
+            }
+            Node::Scalar(_) => {}
+        }
+        let parts = vec![("  ".repeat(depth), HighlightStyle::None), (close.to_string(), HighlightStyle::JsonPunct), comma_part()];
+        self.push(Some(path), true, parts);
+    }
+
+    fn push(&mut self, path: Option<String>, container: bool, parts: Vec<(String, HighlightStyle)>) {
+        let parts = parts.into_iter().filter(|(text, _)| !text.is_empty()).collect();
+        self.rows.push(TreeRow {
+            path,
+            value: self.value,
+            container,
+            parts,
+        });
+    }
+
+    fn plain(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.push(None, false, vec![(text.to_string(), HighlightStyle::None)]);
+        }
+    }
+}
+
+impl JsonTree {
+    /// The tree of the JSON objects and arrays embedded in `text`, if any.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut values = Vec::new();
+        for (range, tokens) in scan_embedded(text) {
+            let node = parse(text, &tokens, &mut 0)?;
+            values.push((range.start, range.end, node));
+        }
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            text: text.to_string(),
+            values,
+            collapsed: HashSet::new(),
+            selected: 0,
+        })
+    }
+
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let mut flatten = Flatten {
+            text: &self.text,
+            value: 0,
+            collapsed: &self.collapsed,
+            rows: Vec::new(),
+        };
+        let mut last = 0;
+        for (i, (start, end, node)) in self.values.iter().enumerate() {
+            flatten.value = i;
+            flatten.plain(self.text[last..*start].trim());
+            flatten.node(node, None, "$".to_string(), 0, false);
+            last = *end;
+        }
+        flatten.plain(self.text[last..].trim());
+        flatten.rows
+    }
+
+    pub fn select(&mut self, by: isize) {
+        let count = self.rows().len();
+        self.selected = self.selected.saturating_add_signed(by).min(count.saturating_sub(1));
+    }
+
+    /// Fold or unfold the container at the selected row, keeping its
+    /// opening row selected.
+    pub fn toggle(&mut self) {
+        let rows = self.rows();
+        let Some(row) = rows.get(self.selected).filter(|row| row.container) else {
+            return;
+        };
+        let key = (row.value, row.path.clone().unwrap_or_default());
+        if !self.collapsed.remove(&key) {
+            self.collapsed.insert(key.clone());
+        }
+        if let Some(open) = rows.iter().position(|r| r.container && (r.value, r.path.clone().unwrap_or_default()) == key) {
+            self.selected = open;
+        }
+    }
+
+    pub fn selected_path(&self) -> Option<String> {
+        self.rows().get(self.selected)?.path.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(tree: &JsonTree) -> Vec<String> {
+        tree.rows().iter().map(|row| row.parts.iter().map(|(text, _)| text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn test_json_tree_folds_and_paths() {
+        let line = r#"sent {"user": {"id": 7, "first name": "Ana"}, "tags": ["a", "b"], "empty": {}} ok"#;
+        let mut tree = JsonTree::parse(line).unwrap();
+        assert_eq!(
+            texts(&tree),
+            [
+                "sent",
+                "{",
+                "  \"user\": {",
+                "    \"id\": 7,",
+                "    \"first name\": \"Ana\"",
+                "  },",
+                "  \"tags\": [",
+                "    \"a\",",
+                "    \"b\"",
+                "  ],",
+                "  \"empty\": {}",
+                "}",
+                "ok",
+            ]
+        );
+        tree.selected = 4;
+        assert_eq!(tree.selected_path().unwrap(), r#"$.user["first name"]"#);
+        tree.selected = 7;
+        assert_eq!(tree.selected_path().unwrap(), "$.tags[0]");
+
+        tree.selected = 5;
+        tree.toggle();
+        assert_eq!(tree.selected, 2);
+        assert_eq!(texts(&tree)[2], "  \"user\": {…},  2 keys");
+        assert_eq!(tree.rows().len(), 10);
+        tree.toggle();
+        assert_eq!(tree.rows().len(), 13);
+        assert!(JsonTree::parse("no json [here").is_none());
+    }
+}
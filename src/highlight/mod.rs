@@ -0,0 +1,604 @@
+mod json;
+pub mod json_tree;
+mod sql;
+mod xml;
+
+use crate::entity::{find_entities, EntityKind};
+use crate::filter::FilterExpr;
+use crate::level::Level;
+use crate::stacktrace;
+use json::{highlight_json, pretty_print_json};
+use sql::highlight_sql;
+use xml::{highlight_xml, pretty_print_xml};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HighlightStyle {
+    None,
+    Error,
+    Warning,
+    Info,
+    Debug,
+    Bracket,
+    Timestamp,
+    CustomHighlight,
+    JsonKey,
+    JsonString,
+    JsonNumber,
+    JsonBool,
+    JsonNull,
+    JsonPunct,
+    XmlTag,
+    XmlAttr,
+    XmlAttrValue,
+    XmlText,
+    XmlComment,
+    SqlKeyword,
+    SqlString,
+    SqlNumber,
+    SlowQuery,
+    UserFrame,
+    LibraryFrame,
+    Url,
+    IpAddress,
+    Uuid,
+    /// The whole line's color by its level, under every other span.
+    TintDebug,
+    TintWarn,
+    TintError,
+}
+
+impl HighlightStyle {
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn css_class(self) -> &'static str {
+        match self {
+            HighlightStyle::None => "",
+            HighlightStyle::Error => "hl-error",
+            HighlightStyle::Warning => "hl-warn",
+            HighlightStyle::Info => "hl-info",
+            HighlightStyle::Debug => "hl-debug",
+            HighlightStyle::Bracket => "hl-bracket",
+            HighlightStyle::Timestamp => "hl-timestamp",
+            HighlightStyle::CustomHighlight => "hl-custom",
+            HighlightStyle::JsonKey => "hl-json-key",
+            HighlightStyle::JsonString => "hl-json-string",
+            HighlightStyle::JsonNumber => "hl-json-number",
+            HighlightStyle::JsonBool => "hl-json-bool",
+            HighlightStyle::JsonNull => "hl-json-null",
+            HighlightStyle::JsonPunct => "hl-json-punct",
+            HighlightStyle::XmlTag => "hl-xml-tag",
+            HighlightStyle::XmlAttr => "hl-xml-attr",
+            HighlightStyle::XmlAttrValue => "hl-xml-attr-value",
+            HighlightStyle::XmlText => "hl-xml-text",
+            HighlightStyle::XmlComment => "hl-xml-comment",
+            HighlightStyle::SqlKeyword => "hl-sql-keyword",
+            HighlightStyle::SqlString => "hl-sql-string",
+            HighlightStyle::SqlNumber => "hl-sql-number",
+            HighlightStyle::SlowQuery => "hl-slow-query",
+            HighlightStyle::UserFrame => "hl-user-frame",
+            HighlightStyle::LibraryFrame => "hl-library-frame",
+            HighlightStyle::Url => "hl-url",
+            HighlightStyle::IpAddress => "hl-ip",
+            HighlightStyle::Uuid => "hl-uuid",
+            HighlightStyle::TintDebug => "hl-tint-debug",
+            HighlightStyle::TintWarn => "hl-tint-warn",
+            HighlightStyle::TintError => "hl-tint-error",
+        }
+    }
+
+    pub fn to_ratatui_style(self) -> ratatui::style::Style {
+        use ratatui::style::{Color, Modifier, Style};
+        match self {
+            HighlightStyle::None => Style::default(),
+            HighlightStyle::Error => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            HighlightStyle::Warning => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            HighlightStyle::Info => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            HighlightStyle::Debug => Style::default().fg(Color::Cyan),
+            HighlightStyle::Bracket => Style::default().fg(Color::Blue),
+            HighlightStyle::Timestamp => Style::default().fg(Color::Magenta),
+            HighlightStyle::CustomHighlight => Style::default().bg(Color::Yellow).add_modifier(Modifier::BOLD),
+            HighlightStyle::JsonKey => Style::default().fg(Color::Cyan),
+            HighlightStyle::JsonString => Style::default().fg(Color::Green),
+            HighlightStyle::JsonNumber => Style::default().fg(Color::Yellow),
+            HighlightStyle::JsonBool => Style::default().fg(Color::Magenta),
+            HighlightStyle::JsonNull => Style::default().fg(Color::Red),
+            HighlightStyle::JsonPunct => Style::default().fg(Color::DarkGray),
+            HighlightStyle::XmlTag => Style::default().fg(Color::Blue),
+            HighlightStyle::XmlAttr => Style::default().fg(Color::Cyan),
+            HighlightStyle::XmlAttrValue => Style::default().fg(Color::Green),
+            HighlightStyle::XmlText => Style::default().add_modifier(Modifier::BOLD),
+            HighlightStyle::XmlComment => Style::default().fg(Color::DarkGray),
+            HighlightStyle::SqlKeyword => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            HighlightStyle::SqlString => Style::default().fg(Color::Green),
+            HighlightStyle::SqlNumber => Style::default().fg(Color::Yellow),
+            HighlightStyle::SlowQuery => Style::default().bg(Color::Red).add_modifier(Modifier::BOLD),
+            HighlightStyle::UserFrame => Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+            HighlightStyle::LibraryFrame => Style::default().fg(Color::DarkGray),
+            HighlightStyle::Url => Style::default().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED),
+            HighlightStyle::IpAddress => Style::default().fg(Color::LightMagenta),
+            HighlightStyle::Uuid => Style::default().fg(Color::LightYellow),
+            HighlightStyle::TintDebug => Style::default().fg(Color::DarkGray),
+            HighlightStyle::TintWarn => Style::default().fg(Color::Yellow),
+            HighlightStyle::TintError => Style::default().fg(Color::Red),
+        }
+    }
+
+    pub fn layer(self) -> StyleLayer {
+        match self {
+            HighlightStyle::CustomHighlight | HighlightStyle::SlowQuery => StyleLayer::Background,
+            _ => StyleLayer::Foreground,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StyleLayer {
+    Foreground,
+    Background,
+}
+
+/// The effective style of a run of text: one foreground style (text color)
+/// composited with one background style, so a search highlight can sit on top
+/// of a level color instead of replacing it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ComposedStyle {
+    pub fg: HighlightStyle,
+    pub bg: HighlightStyle,
+}
+
+impl Default for ComposedStyle {
+    fn default() -> Self {
+        Self {
+            fg: HighlightStyle::None,
+            bg: HighlightStyle::None,
+        }
+    }
+}
+
+impl ComposedStyle {
+    pub fn to_ratatui_style(self) -> ratatui::style::Style {
+        use ratatui::style::Color;
+        let mut style = self.fg.to_ratatui_style().patch(self.bg.to_ratatui_style());
+        if self.bg != HighlightStyle::None && (self.fg == HighlightStyle::None || style.fg == style.bg) {
+            style = style.fg(Color::Black);
+        }
+        style
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn css_class(self) -> String {
+        match (self.fg, self.bg) {
+            (HighlightStyle::None, HighlightStyle::None) => String::new(),
+            (fg, HighlightStyle::None) => fg.css_class().to_string(),
+            (HighlightStyle::None, bg) => format!("{} hl-bg-only", bg.css_class()),
+            (fg, bg) => format!("{} {}", fg.css_class(), bg.css_class()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct HeuristicRule {
+    regex: Regex,
+    style: HighlightStyle,
+}
+
+static HEURISTIC_RULES: LazyLock<Vec<HeuristicRule>> = LazyLock::new(|| {
+    vec![
+        HeuristicRule {
+            regex: Regex::new(r"(?i)\b(error|err|fatal|fail(ed)?|panic)\b").unwrap(),
+            style: HighlightStyle::Error,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"(?i)\b(warn(ing)?)\b").unwrap(),
+            style: HighlightStyle::Warning,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"(?i)\b(info)\b").unwrap(),
+            style: HighlightStyle::Info,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"(?i)\b(debug|trace)\b").unwrap(),
+            style: HighlightStyle::Debug,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"\[[^\]]+\]").unwrap(),
+            style: HighlightStyle::Bracket,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}").unwrap(),
+            style: HighlightStyle::Timestamp,
+        },
+        HeuristicRule {
+            regex: Regex::new(r"\d{2}:\d{2}:\d{2}").unwrap(),
+            style: HighlightStyle::Timestamp,
+        },
+    ]
+});
+
+/// An HTTP status field, colored by its class.
+static STATUS_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:status|status_code|code)["']?\s*[:=]\s*["']?([1-5]\d\d)\b"#).unwrap());
+
+/// A styled byte range of a line.
+#[derive(Clone)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub style: HighlightStyle,
+    pub priority: u8,
+}
+
+/// Priority of each highlight source. Where spans overlap, the higher priority
+/// wins; on a tie the source that runs first (custom, then JSON, XML, SQL,
+/// stack frames, then the heuristic rules in order) wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighlightPriorities {
+    pub custom: u8,
+    pub json: u8,
+    pub xml: u8,
+    pub sql: u8,
+    pub stack: u8,
+    pub heuristic: u8,
+}
+
+impl Default for HighlightPriorities {
+    fn default() -> Self {
+        Self {
+            custom: 100,
+            json: 50,
+            xml: 50,
+            sql: 50,
+            stack: 20,
+            heuristic: 10,
+        }
+    }
+}
+
+/// User-tunable inputs to `highlight_line`, persisted in `AppState`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HighlightSettings {
+    pub priorities: HighlightPriorities,
+    pub slow_query_ms: Option<u64>,
+    /// Package or path fragment identifying the user's own stack frames.
+    pub user_frames: String,
+    /// Color the whole line by its level.
+    pub level_tint: bool,
+}
+
+/// `level` is the line's stored level, which drives the level tint.
+pub fn highlight_line(
+    text: &str,
+    level: Option<Level>,
+    custom_filter: Option<&FilterExpr>,
+    heuristic_enabled: bool,
+    structured_enabled: bool,
+    settings: &HighlightSettings,
+) -> Vec<Span> {
+    let priorities = &settings.priorities;
+    let mut spans = Vec::new();
+
+    if let Some(filter) = custom_filter {
+        let matches = filter.find_all_matches(text);
+        for (start, end) in matches {
+            spans.push(Span {
+                start,
+                end,
+                style: HighlightStyle::CustomHighlight,
+                priority: priorities.custom,
+            });
+        }
+    }
+
+    if structured_enabled {
+        if let Some(json_spans) = highlight_json(text, priorities.json) {
+            spans.extend(json_spans);
+        }
+        if let Some(xml_spans) = highlight_xml(text, priorities.xml) {
+            spans.extend(xml_spans);
+        }
+        if let Some(sql_spans) = highlight_sql(text, priorities.sql, settings.slow_query_ms) {
+            spans.extend(sql_spans);
+        }
+    }
+
+    if !settings.user_frames.is_empty() {
+        if let Some(trace) = stacktrace::detect(text, &settings.user_frames) {
+            for frame in trace.frames {
+                spans.push(Span {
+                    start: frame.line.start,
+                    end: frame.line.end,
+                    style: if frame.user {
+                        HighlightStyle::UserFrame
+                    } else {
+                        HighlightStyle::LibraryFrame
+                    },
+                    priority: priorities.stack,
+                });
+            }
+        }
+    }
+
+    if heuristic_enabled {
+        for entity in find_entities(text) {
+            spans.push(Span {
+                start: entity.start,
+                end: entity.end,
+                style: match entity.kind {
+                    EntityKind::Url => HighlightStyle::Url,
+                    EntityKind::Ip => HighlightStyle::IpAddress,
+                    EntityKind::Uuid => HighlightStyle::Uuid,
+                },
+                priority: priorities.heuristic,
+            });
+        }
+        for caps in STATUS_FIELD.captures_iter(text) {
+            let status = caps.get(1).unwrap();
+            spans.push(Span {
+                start: status.start(),
+                end: status.end(),
+                style: match status.as_str().as_bytes()[0] {
+                    b'5' => HighlightStyle::Error,
+                    b'4' => HighlightStyle::Warning,
+                    b'3' => HighlightStyle::Debug,
+                    _ => HighlightStyle::Info,
+                },
+                priority: priorities.heuristic,
+            });
+        }
+        for rule in HEURISTIC_RULES.iter() {
+            for m in rule.regex.find_iter(text) {
+                spans.push(Span {
+                    start: m.start(),
+                    end: m.end(),
+                    style: rule.style,
+                    priority: priorities.heuristic,
+                });
+            }
+        }
+    }
+
+    // Stable sort: equal priorities keep their source order, so the result
+    // does not depend on where in the line each span starts.
+    spans.sort_by_key(|s| std::cmp::Reverse(s.priority));
+    // Last, so any other span keeps its bytes.
+    if settings.level_tint {
+        spans.extend(level_tint(text, level));
+    }
+    spans
+}
+
+/// A span over all of `text` in its level's tint; none for INFO or no level.
+fn level_tint(text: &str, level: Option<Level>) -> Option<Span> {
+    let style = match level? {
+        Level::Trace | Level::Debug => HighlightStyle::TintDebug,
+        Level::Info => return None,
+        Level::Warn => HighlightStyle::TintWarn,
+        Level::Error => HighlightStyle::TintError,
+    };
+    Some(Span {
+        start: 0,
+        end: text.len(),
+        style,
+        priority: 0,
+    })
+}
+
+pub fn apply_highlights(text: &str, spans: &[Span]) -> Vec<(String, ComposedStyle)> {
+    if spans.is_empty() {
+        return vec![(text.to_string(), ComposedStyle::default())];
+    }
+
+    // Spans arrive ordered by precedence, so the first span to claim a byte
+    // keeps it. Foreground and background are claimed independently, except
+    // that a background does not show under a foreground of higher priority.
+    let mut style_at: Vec<ComposedStyle> = vec![ComposedStyle::default(); text.len()];
+    let mut fg_priority: Vec<Option<u8>> = vec![None; text.len()];
+
+    for span in spans {
+        let end = span.end.min(text.len());
+        let start = span.start.min(end);
+
+        for (slot, fg_priority) in style_at[start..end].iter_mut().zip(&mut fg_priority[start..end]) {
+            match span.style.layer() {
+                StyleLayer::Foreground if slot.fg == HighlightStyle::None => {
+                    slot.fg = span.style;
+                    *fg_priority = Some(span.priority);
+                }
+                StyleLayer::Background if slot.bg == HighlightStyle::None && fg_priority.is_none_or(|p| p <= span.priority) => {
+                    slot.bg = span.style;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut pos = 0;
+    
+    while pos < text.len() {
+        let current_style = style_at[pos];
+        let mut end = pos + 1;
+
+        while end < text.len() && style_at[end] == current_style {
+            end += 1;
+        }
+        
+        result.push((text[pos..end].to_string(), current_style));
+        pos = end;
+    }
+
+    result
+}
+
+pub fn apply_highlights_ratatui(text: &str, spans: &[Span]) -> Vec<(String, ratatui::style::Style)> {
+    apply_highlights(text, spans)
+        .into_iter()
+        .map(|(s, style)| (s, style.to_ratatui_style()))
+        .collect()
+}
+
+/// What a click on a highlighted token should filter by.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClickToken {
+    Literal(String),
+    /// A JSON value together with its key.
+    Field { key: String, value: String },
+}
+
+/// The highlighted token covering byte `pos` of `text`, if any.
+pub fn token_at(
+    text: &str,
+    pos: usize,
+    custom_filter: Option<&FilterExpr>,
+    settings: &HighlightSettings,
+) -> Option<ClickToken> {
+    let tokens = json::tokenize_embedded(text);
+    if let Some(i) = tokens.iter().position(|t| t.start <= pos && pos < t.end) {
+        let tok = tokens[i];
+        let token_text = |t: json::JsonToken| text[t.start..t.end].to_string();
+        return match tok.kind {
+            json::JsonTokenKind::Punct => None,
+            json::JsonTokenKind::Key => Some(ClickToken::Literal(token_text(tok))),
+            _ => match (i.checked_sub(2).map(|k| tokens[k]), i.checked_sub(1).map(|k| tokens[k])) {
+                (Some(key), Some(colon)) if key.kind == json::JsonTokenKind::Key && &text[colon.start..colon.end] == ":" => {
+                    Some(ClickToken::Field {
+                        key: token_text(key),
+                        value: token_text(tok),
+                    })
+                }
+                _ => Some(ClickToken::Literal(token_text(tok))),
+            },
+        };
+    }
+
+    highlight_line(text, None, custom_filter, true, true, settings)
+        .into_iter()
+        .find(|s| s.start <= pos && pos < s.end && s.style != HighlightStyle::None)
+        .map(|s| ClickToken::Literal(text[s.start..s.end].to_string()))
+}
+
+/// Byte ranges of the complete JSON objects and arrays embedded in `text`.
+pub fn embedded_json(text: &str) -> Vec<Range<usize>> {
+    json::scan_embedded(text).into_iter().map(|(range, _)| range).collect()
+}
+
+/// Pretty-print embedded JSON, or failing that an XML fragment.
+pub fn pretty_print(text: &str) -> Option<String> {
+    pretty_print_json(text).or_else(|| pretty_print_xml(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_filter;
+
+    fn style_of(parts: &[(String, ComposedStyle)], needle: &str) -> ComposedStyle {
+        parts.iter().find(|(t, _)| t == needle).unwrap().1
+    }
+
+    #[test]
+    fn test_custom_above_heuristic_by_default() {
+        let expr = parse_filter("error").unwrap();
+        let text = "an error here";
+        let spans = highlight_line(text, None, Some(&expr), true, true, &HighlightSettings::default());
+        let parts = apply_highlights(text, &spans);
+        assert_eq!(style_of(&parts, "error").bg, HighlightStyle::CustomHighlight);
+    }
+
+    #[test]
+    fn test_custom_below_heuristic_when_configured() {
+        let expr = parse_filter("error").unwrap();
+        let text = "an error here";
+        let settings = HighlightSettings {
+            priorities: HighlightPriorities {
+                custom: 5,
+                ..HighlightPriorities::default()
+            },
+            ..HighlightSettings::default()
+        };
+        let spans = highlight_line(text, None, Some(&expr), true, true, &settings);
+        let parts = apply_highlights(text, &spans);
+        assert_eq!(style_of(&parts, "error"), ComposedStyle { fg: HighlightStyle::Error, bg: HighlightStyle::None });
+
+        // Where nothing outranks it, the highlight still shows.
+        let expr = parse_filter("here").unwrap();
+        let spans = highlight_line(text, None, Some(&expr), true, true, &settings);
+        assert_eq!(style_of(&apply_highlights(text, &spans), "here").bg, HighlightStyle::CustomHighlight);
+    }
+
+    #[test]
+    fn test_equal_priority_first_rule_wins() {
+        // "[error]" matches both the error keyword rule and the bracket rule;
+        // the keyword rule comes first, so it keeps the overlapping bytes.
+        let text = "[error]";
+        let spans = highlight_line(text, None, None, true, false, &HighlightSettings::default());
+        let parts = apply_highlights(text, &spans);
+        assert_eq!(style_of(&parts, "error").fg, HighlightStyle::Error);
+        assert_eq!(style_of(&parts, "[").fg, HighlightStyle::Bracket);
+    }
+
+    #[test]
+    fn test_level_tint_under_other_spans() {
+        let expr = parse_filter("disk").unwrap();
+        let text = "WARN disk at 91% on 10.0.0.7";
+        let settings = HighlightSettings {
+            level_tint: true,
+            ..HighlightSettings::default()
+        };
+        let parts = apply_highlights(text, &highlight_line(text, Some(Level::Warn), Some(&expr), true, true, &settings));
+        assert_eq!(style_of(&parts, "WARN").fg, HighlightStyle::Warning);
+        assert_eq!(style_of(&parts, "10.0.0.7").fg, HighlightStyle::IpAddress);
+        assert_eq!(style_of(&parts, "disk"), ComposedStyle { fg: HighlightStyle::TintWarn, bg: HighlightStyle::CustomHighlight });
+        assert_eq!(style_of(&parts, " at 91% on ").fg, HighlightStyle::TintWarn);
+
+        let info = "INFO ready";
+        assert!(highlight_line(info, Some(Level::Info), None, false, false, &settings).is_empty());
+        // The stored level decides, even when the text no longer shows it.
+        let hidden = "cache miss";
+        assert_eq!(apply_highlights(hidden, &highlight_line(hidden, Some(Level::Debug), None, false, false, &settings))[0].1.fg, HighlightStyle::TintDebug);
+        assert!(highlight_line("ERROR but unclassified", None, None, false, false, &settings).is_empty());
+    }
+
+    #[test]
+    fn test_offsets_are_bytes_with_multibyte_text() {
+        let expr = parse_filter("error").unwrap();
+        let text = "héllo → error";
+        let spans = highlight_line(text, None, Some(&expr), false, false, &HighlightSettings::default());
+        let parts = apply_highlights(text, &spans);
+        assert_eq!(style_of(&parts, "error").bg, HighlightStyle::CustomHighlight);
+    }
+
+    #[test]
+    fn test_token_at_json_field_and_bracket() {
+        let text = r#"[api] done {"user":"bob","n":1}"#;
+        let settings = HighlightSettings::default();
+        assert_eq!(
+            token_at(text, text.find("bob").unwrap(), None, &settings),
+            Some(ClickToken::Field {
+                key: r#""user""#.to_string(),
+                value: r#""bob""#.to_string()
+            })
+        );
+        assert_eq!(
+            token_at(text, 2, None, &settings),
+            Some(ClickToken::Literal("[api]".to_string()))
+        );
+        assert_eq!(token_at(text, text.find("done").unwrap(), None, &settings), None);
+    }
+
+    #[test]
+    fn test_custom_background_keeps_level_foreground() {
+        let expr = parse_filter("err").unwrap();
+        let text = "fatal error";
+        let spans = highlight_line(text, None, Some(&expr), true, false, &HighlightSettings::default());
+        let parts = apply_highlights(text, &spans);
+        let composed = style_of(&parts, "err");
+        assert_eq!(composed.fg, HighlightStyle::Error);
+        assert_eq!(composed.bg, HighlightStyle::CustomHighlight);
+        assert_eq!(style_of(&parts, "or").bg, HighlightStyle::None);
+    }
+}
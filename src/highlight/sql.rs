@@ -0,0 +1,168 @@
+use super::{HighlightStyle, Span};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+static STATEMENT_START: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(select|insert|update|delete|with|create|alter|drop|replace|merge)\b").unwrap()
+});
+
+/// `duration=12.5ms`, `took 2s`, `elapsed: 800 us`, ...
+static DURATION_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:duration|elapsed|took|time|latency)\s*[=:]?\s*(\d+(?:\.\d+)?)\s*(ms|us|µs|s)\b").unwrap()
+});
+
+const KEYWORDS: &[&str] = &[
+    "ALTER", "AND", "AS", "ASC", "BETWEEN", "BY", "CASE", "CREATE", "DELETE", "DESC", "DISTINCT",
+    "DROP", "ELSE", "END", "EXISTS", "FROM", "FULL", "GROUP", "HAVING", "IN", "INDEX", "INNER",
+    "INSERT", "INTO", "IS", "JOIN", "LEFT", "LIKE", "LIMIT", "MERGE", "NOT", "NULL", "OFFSET", "ON",
+    "OR", "ORDER", "OUTER", "REPLACE", "RETURNING", "RIGHT", "SELECT", "SET", "TABLE", "THEN",
+    "UNION", "UPDATE", "VALUES", "WHEN", "WHERE", "WITH",
+];
+
+/// Keywords that must follow the leading verb before we believe a line holds
+/// SQL rather than prose that happens to say "select" or "update".
+const COMPANIONS: &[&str] = &["FROM", "INTO", "SET", "WHERE", "TABLE", "VALUES", "JOIN", "INDEX", "AS"];
+
+fn is_keyword(word: &str, list: &[&str]) -> bool {
+    list.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+/// Byte range of the first SQL statement in `text`, ending at `;` or the end
+/// of the line.
+pub fn find_statement(text: &str) -> Option<Range<usize>> {
+    for m in STATEMENT_START.find_iter(text) {
+        let end = statement_end(text, m.start());
+        let body = &text[m.end()..end];
+        let has_companion = body
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .any(|w| is_keyword(w, COMPANIONS));
+        if has_companion {
+            return Some(m.start()..end);
+        }
+    }
+    None
+}
+
+fn statement_end(text: &str, start: usize) -> usize {
+    let mut in_string = false;
+    for (i, b) in text.bytes().enumerate().skip(start) {
+        match b {
+            b'\'' => in_string = !in_string,
+            b';' if !in_string => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+pub fn highlight_sql(text: &str, priority: u8, slow_query_ms: Option<u64>) -> Option<Vec<Span>> {
+    let range = find_statement(text)?;
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut push = |start: usize, end: usize, style: HighlightStyle| {
+        spans.push(Span {
+            start,
+            end,
+            style,
+            priority,
+        });
+    };
+
+    let mut pos = range.start;
+    while pos < range.end {
+        let b = bytes[pos];
+        if b == b'\'' {
+            let start = pos;
+            pos += 1;
+            while pos < range.end {
+                if bytes[pos] == b'\'' {
+                    // '' is an escaped quote inside the literal
+                    if bytes.get(pos + 1) == Some(&b'\'') {
+                        pos += 2;
+                        continue;
+                    }
+                    pos += 1;
+                    break;
+                }
+                pos += 1;
+            }
+            push(start, pos, HighlightStyle::SqlString);
+        } else if b == b'"' || b == b'`' {
+            let close = text[pos + 1..range.end].find(b as char);
+            pos = close.map_or(range.end, |c| pos + c + 2);
+        } else if b.is_ascii_digit() {
+            let start = pos;
+            while pos < range.end && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                pos += 1;
+            }
+            push(start, pos, HighlightStyle::SqlNumber);
+        } else if b.is_ascii_alphabetic() || b == b'_' {
+            let start = pos;
+            while pos < range.end && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            if is_keyword(&text[start..pos], KEYWORDS) {
+                push(start, pos, HighlightStyle::SqlKeyword);
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    if let Some(threshold) = slow_query_ms {
+        for caps in DURATION_FIELD.captures_iter(text) {
+            let value: f64 = caps[1].parse().unwrap_or(0.0);
+            let ms = match caps[2].to_ascii_lowercase().as_str() {
+                "s" => value * 1000.0,
+                "ms" => value,
+                _ => value / 1000.0,
+            };
+            if ms >= threshold as f64 {
+                let m = caps.get(0).unwrap();
+                push(m.start(), m.end(), HighlightStyle::SlowQuery);
+            }
+        }
+    }
+
+    Some(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(text: &str, spans: &[Span], style: HighlightStyle) -> Vec<String> {
+        spans
+            .iter()
+            .filter(|s| s.style == style)
+            .map(|s| text[s.start..s.end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_prose_is_not_sql() {
+        assert!(find_statement("please select a profile to continue").is_none());
+        assert!(find_statement("update available: v2.1").is_none());
+    }
+
+    #[test]
+    fn test_keywords_strings_numbers() {
+        let text = "db: SELECT id FROM users WHERE name = 'o''brien' AND age > 30; done in 3ms";
+        let spans = highlight_sql(text, 50, None).unwrap();
+        assert_eq!(styled(text, &spans, HighlightStyle::SqlKeyword), vec!["SELECT", "FROM", "WHERE", "AND"]);
+        assert_eq!(styled(text, &spans, HighlightStyle::SqlString), vec!["'o''brien'"]);
+        assert_eq!(styled(text, &spans, HighlightStyle::SqlNumber), vec!["30"]);
+    }
+
+    #[test]
+    fn test_slow_query_threshold() {
+        let text = "select * from orders where id = 1 duration=1.5s";
+        let spans = highlight_sql(text, 50, Some(1000)).unwrap();
+        assert_eq!(styled(text, &spans, HighlightStyle::SlowQuery), vec!["duration=1.5s"]);
+
+        let fast = "select * from orders where id = 1 duration=12ms";
+        let spans = highlight_sql(fast, 50, Some(1000)).unwrap();
+        assert!(styled(fast, &spans, HighlightStyle::SlowQuery).is_empty());
+    }
+}
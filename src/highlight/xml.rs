@@ -0,0 +1,327 @@
+use super::{HighlightStyle, Span};
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum XmlTagKind {
+    Open,
+    Close,
+    SelfClosing,
+    Comment,
+    Declaration,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct XmlAttr {
+    pub name: Range<usize>,
+    pub value: Option<Range<usize>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum XmlItem {
+    Tag {
+        kind: XmlTagKind,
+        range: Range<usize>,
+        /// `<name` or `</name`, including the angle bracket.
+        head: Range<usize>,
+        attrs: Vec<XmlAttr>,
+    },
+    Text(Range<usize>),
+}
+
+/// Scan `text` for an XML-ish fragment. Returns the tags and non-blank text
+/// between the first and last tag, or `None` when the line doesn't look like
+/// markup (a lone `<` comparison or a single unmatched tag).
+pub fn scan_fragment(text: &str) -> Option<Vec<XmlItem>> {
+    let bytes = text.as_bytes();
+    let mut items = Vec::new();
+    let mut pos = 0;
+    let mut text_start: Option<usize> = None;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'<' {
+            if let Some(tag) = scan_tag(text, pos) {
+                if let Some(start) = text_start.take() {
+                    if !items.is_empty() {
+                        push_text(text, start..pos, &mut items);
+                    }
+                }
+                pos = match &tag {
+                    XmlItem::Tag { range, .. } => range.end,
+                    XmlItem::Text(_) => unreachable!(),
+                };
+                items.push(tag);
+                continue;
+            }
+        }
+        if text_start.is_none() {
+            text_start = Some(pos);
+        }
+        pos += 1;
+    }
+
+    let tag_kinds: Vec<XmlTagKind> = items
+        .iter()
+        .filter_map(|item| match item {
+            XmlItem::Tag { kind, .. } => Some(*kind),
+            XmlItem::Text(_) => None,
+        })
+        .collect();
+    let structural = tag_kinds
+        .iter()
+        .any(|k| matches!(k, XmlTagKind::Close | XmlTagKind::SelfClosing | XmlTagKind::Declaration));
+    if tag_kinds.len() >= 2 && structural {
+        Some(items)
+    } else {
+        None
+    }
+}
+
+fn push_text(text: &str, range: Range<usize>, items: &mut Vec<XmlItem>) {
+    let slice = &text[range.clone()];
+    let trimmed = slice.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    items.push(XmlItem::Text(start..start + trimmed.len()));
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_name_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b':' | b'.' | b'-')
+}
+
+fn scan_tag(text: &str, start: usize) -> Option<XmlItem> {
+    let bytes = text.as_bytes();
+    let rest = &text[start..];
+
+    if rest.starts_with("<!--") {
+        let end = start + rest.find("-->")? + 3;
+        return Some(simple_tag(XmlTagKind::Comment, start..end));
+    }
+    if rest.starts_with("<?") || rest.starts_with("<!") {
+        let end = start + rest.find('>')? + 1;
+        return Some(simple_tag(XmlTagKind::Declaration, start..end));
+    }
+
+    let mut pos = start + 1;
+    let closing = bytes.get(pos) == Some(&b'/');
+    if closing {
+        pos += 1;
+    }
+    if !is_name_start(*bytes.get(pos)?) {
+        return None;
+    }
+    while bytes.get(pos).is_some_and(|&b| is_name_char(b)) {
+        pos += 1;
+    }
+    let head = start..pos;
+
+    let mut attrs = Vec::new();
+    loop {
+        while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+            pos += 1;
+        }
+        match *bytes.get(pos)? {
+            b'>' => {
+                let kind = if closing { XmlTagKind::Close } else { XmlTagKind::Open };
+                return Some(XmlItem::Tag {
+                    kind,
+                    range: start..pos + 1,
+                    head,
+                    attrs,
+                });
+            }
+            b'/' if !closing && bytes.get(pos + 1) == Some(&b'>') => {
+                return Some(XmlItem::Tag {
+                    kind: XmlTagKind::SelfClosing,
+                    range: start..pos + 2,
+                    head,
+                    attrs,
+                });
+            }
+            b if !closing && is_name_start(b) => {
+                let name_start = pos;
+                while bytes.get(pos).is_some_and(|&b| is_name_char(b)) {
+                    pos += 1;
+                }
+                let name = name_start..pos;
+                let mut value = None;
+                if bytes.get(pos) == Some(&b'=') {
+                    pos += 1;
+                    let quote = *bytes.get(pos)?;
+                    if quote != b'"' && quote != b'\'' {
+                        return None;
+                    }
+                    let close = text[pos + 1..].find(quote as char)?;
+                    value = Some(pos..pos + close + 2);
+                    pos += close + 2;
+                }
+                attrs.push(XmlAttr { name, value });
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn simple_tag(kind: XmlTagKind, range: Range<usize>) -> XmlItem {
+    XmlItem::Tag {
+        kind,
+        head: range.clone(),
+        range,
+        attrs: Vec::new(),
+    }
+}
+
+pub fn highlight_xml(text: &str, priority: u8) -> Option<Vec<Span>> {
+    let items = scan_fragment(text)?;
+    let mut spans = Vec::new();
+    let mut push = |range: Range<usize>, style: HighlightStyle| {
+        spans.push(Span {
+            start: range.start,
+            end: range.end,
+            style,
+            priority,
+        });
+    };
+
+    for item in items {
+        match item {
+            XmlItem::Tag {
+                kind: XmlTagKind::Comment | XmlTagKind::Declaration,
+                range,
+                ..
+            } => push(range, HighlightStyle::XmlComment),
+            XmlItem::Tag {
+                range, head, attrs, ..
+            } => {
+                push(head.clone(), HighlightStyle::XmlTag);
+                for attr in attrs {
+                    push(attr.name, HighlightStyle::XmlAttr);
+                    if let Some(value) = attr.value {
+                        push(value, HighlightStyle::XmlAttrValue);
+                    }
+                }
+                let close_start = if text[..range.end].ends_with("/>") {
+                    range.end - 2
+                } else {
+                    range.end - 1
+                };
+                push(close_start..range.end, HighlightStyle::XmlTag);
+            }
+            XmlItem::Text(range) => push(range, HighlightStyle::XmlText),
+        }
+    }
+    Some(spans)
+}
+
+/// Re-indent an XML fragment one element per line. Text outside the fragment
+/// is kept on its own lines before and after.
+pub fn pretty_print_xml(text: &str) -> Option<String> {
+    let items = scan_fragment(text)?;
+    let first = item_range(items.first()?).start;
+    let last = item_range(items.last()?).end;
+
+    let mut out: Vec<String> = Vec::new();
+    let prefix = text[..first].trim();
+    if !prefix.is_empty() {
+        out.push(prefix.to_string());
+    }
+
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < items.len() {
+        let indent = "  ".repeat(depth);
+        match &items[i] {
+            XmlItem::Tag {
+                kind: XmlTagKind::Open,
+                range,
+                ..
+            } => {
+                // Keep `<a>text</a>` and `<a></a>` on one line.
+                let inline_end = match (items.get(i + 1), items.get(i + 2)) {
+                    (Some(XmlItem::Text(_)), Some(close @ XmlItem::Tag { kind: XmlTagKind::Close, .. })) => {
+                        Some((item_range(close).end, 3))
+                    }
+                    (Some(close @ XmlItem::Tag { kind: XmlTagKind::Close, .. }), _) => {
+                        Some((item_range(close).end, 2))
+                    }
+                    _ => None,
+                };
+                if let Some((end, consumed)) = inline_end {
+                    out.push(format!("{}{}", indent, &text[range.start..end]));
+                    i += consumed;
+                    continue;
+                }
+                out.push(format!("{}{}", indent, &text[range.clone()]));
+                depth += 1;
+            }
+            XmlItem::Tag {
+                kind: XmlTagKind::Close,
+                range,
+                ..
+            } => {
+                depth = depth.saturating_sub(1);
+                out.push(format!("{}{}", "  ".repeat(depth), &text[range.clone()]));
+            }
+            item => {
+                out.push(format!("{}{}", indent, &text[item_range(item)]));
+            }
+        }
+        i += 1;
+    }
+
+    let suffix = text[last..].trim();
+    if !suffix.is_empty() {
+        out.push(suffix.to_string());
+    }
+    Some(out.join("\n"))
+}
+
+fn item_range(item: &XmlItem) -> Range<usize> {
+    match item {
+        XmlItem::Tag { range, .. } => range.clone(),
+        XmlItem::Text(range) => range.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignores_comparisons_and_lone_tags() {
+        assert!(scan_fragment("if a < b then retry").is_none());
+        assert!(scan_fragment("got <html> back").is_none());
+    }
+
+    #[test]
+    fn test_highlights_tags_attrs_and_text() {
+        let text = r#"resp: <user id="7">bob</user>"#;
+        let spans = highlight_xml(text, 50).unwrap();
+        let styled = |style| {
+            spans
+                .iter()
+                .filter(|s| s.style == style)
+                .map(|s| &text[s.start..s.end])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(styled(HighlightStyle::XmlAttr), vec!["id"]);
+        assert_eq!(styled(HighlightStyle::XmlAttrValue), vec![r#""7""#]);
+        assert_eq!(styled(HighlightStyle::XmlText), vec!["bob"]);
+        assert_eq!(styled(HighlightStyle::XmlTag), vec!["<user", ">", "</user", ">"]);
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let text = r#"SOAP <env><body><op a="1"/><name>x</name></body></env> done"#;
+        let pretty = pretty_print_xml(text).unwrap();
+        assert_eq!(
+            pretty,
+            "SOAP\n<env>\n  <body>\n    <op a=\"1\"/>\n    <name>x</name>\n  </body>\n</env>\ndone"
+        );
+    }
+}
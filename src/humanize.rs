@@ -0,0 +1,175 @@
+use crate::glob::glob_match;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Field names humanized when `:humanize` names none.
+const DEFAULT_RULES: &str = "bytes=bytes,size,*_bytes,*_size,content_length ms=*_ms,latency,duration,elapsed";
+
+/// `key=123`, `"key": 123` or `"key": "123"`.
+static NUMERIC_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:^|[^\w.-])["']?([A-Za-z_][\w.-]*)["']?\s*[:=]\s*["']?(\d+(?:\.\d+)?)\b"#).unwrap()
+});
+
+/// What a field's number counts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Bytes,
+    Nanos,
+    Micros,
+    Millis,
+    Secs,
+}
+
+impl Unit {
+    fn format(self, value: f64) -> String {
+        match self {
+            Unit::Bytes => format_bytes(value),
+            Unit::Nanos => format_duration(value / 1e9),
+            Unit::Micros => format_duration(value / 1e6),
+            Unit::Millis => format_duration(value / 1e3),
+            Unit::Secs => format_duration(value),
+        }
+    }
+}
+
+/// Which fields `:humanize` rewrites and whether the raw number stays.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Humanize {
+    rules: Vec<(Unit, Vec<String>)>,
+    /// Show only the humanized value instead of adding it after the number.
+    pub replace: bool,
+    /// The spec as given, for saving.
+    pub spec: String,
+}
+
+impl FromStr for Humanize {
+    type Err = anyhow::Error;
+
+    /// `[replace|alongside] [UNIT=GLOB,GLOB ...]`, units being bytes, ns,
+    /// us, ms and s.
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut replace = false;
+        let mut rules = Vec::new();
+        for word in spec.split_whitespace() {
+            match word {
+                "replace" => replace = true,
+                "alongside" => replace = false,
+                _ => {
+                    let (unit, globs) = word.split_once('=').ok_or_else(|| anyhow!("Expected UNIT=FIELDS, got {}", word))?;
+                    let unit = match unit {
+                        "bytes" | "b" => Unit::Bytes,
+                        "ns" => Unit::Nanos,
+                        "us" => Unit::Micros,
+                        "ms" => Unit::Millis,
+                        "s" => Unit::Secs,
+                        _ => return Err(anyhow!("Unknown unit {} (expected bytes, ns, us, ms or s)", unit)),
+                    };
+                    rules.push((unit, globs.split(',').filter(|g| !g.is_empty()).map(String::from).collect()));
+                }
+            }
+        }
+        if rules.is_empty() {
+            rules = DEFAULT_RULES.parse::<Humanize>()?.rules;
+        }
+        Ok(Self {
+            rules,
+            replace,
+            spec: spec.trim().to_string(),
+        })
+    }
+}
+
+impl Humanize {
+    fn unit(&self, key: &str) -> Option<Unit> {
+        let key = key.to_ascii_lowercase();
+        self.rules
+            .iter()
+            .find(|(_, globs)| globs.iter().any(|glob| glob_match(glob, &key)))
+            .map(|(unit, _)| *unit)
+    }
+
+    /// `content` with the values of matching fields humanized.
+    pub fn apply(&self, content: &str) -> String {
+        let mut out = String::with_capacity(content.len());
+        let mut last = 0;
+        for caps in NUMERIC_FIELD.captures_iter(content) {
+            let (Some(key), Some(value)) = (caps.get(1), caps.get(2)) else {
+                continue;
+            };
+            let Some(unit) = self.unit(key.as_str()) else {
+                continue;
+            };
+            let Ok(number) = value.as_str().parse::<f64>() else {
+                continue;
+            };
+            let human = unit.format(number);
+            out.push_str(&content[last..value.start()]);
+            if self.replace {
+                out.push_str(&human);
+            } else {
+                out.push_str(&format!("{} ({})", value.as_str(), human));
+            }
+            last = value.end();
+        }
+        out.push_str(&content[last..]);
+        out
+    }
+}
+
+/// One decimal at most, without a trailing `.0`.
+fn short(value: f64) -> String {
+    let text = format!("{:.1}", value);
+    text.strip_suffix(".0").map_or(text.clone(), String::from)
+}
+
+/// `10485760` -> `10 MiB`.
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", short(value), UNITS[unit])
+}
+
+/// `93` -> `1m33s`, `0.35` -> `350ms`.
+pub fn format_duration(secs: f64) -> String {
+    if secs < 1e-3 {
+        return format!("{}µs", short(secs * 1e6));
+    }
+    if secs < 1.0 {
+        return format!("{}ms", short(secs * 1e3));
+    }
+    if secs < 60.0 {
+        return format!("{}s", short(secs));
+    }
+    let total = secs.round() as u64;
+    let parts = [(total / 86400, "d"), (total / 3600 % 24, "h"), (total / 60 % 60, "m"), (total % 60, "s")];
+    parts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize_fields() {
+        let humanize: Humanize = "".parse().unwrap();
+        assert_eq!(
+            humanize.apply(r#"size=10485760 took_ms=93000 {"content_length": "1536", "latency": 350} port=8080"#),
+            r#"size=10485760 (10 MiB) took_ms=93000 (1m33s) {"content_length": "1536 (1.5 KiB)", "latency": 350 (350ms)} port=8080"#
+        );
+        let humanize: Humanize = "replace s=uptime".parse().unwrap();
+        assert_eq!(humanize.apply("uptime=90061 size=5"), "uptime=1d1h1m1s size=5");
+        assert_eq!(format_duration(0.0004), "400µs");
+        assert!("minutes=x".parse::<Humanize>().is_err());
+    }
+}
@@ -21,6 +21,15 @@ impl TextInput {
         self.cursor += 1;
     }
 
+    /// Insert a paste at the cursor in one go. Line breaks become spaces,
+    /// since every field is a single line.
+    pub fn insert_paste(&mut self, text: &str) {
+        let text = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+        let byte_idx = self.char_to_byte_index(self.cursor);
+        self.text.insert_str(byte_idx, &text);
+        self.cursor += text.chars().count();
+    }
+
     pub fn delete_char_before_cursor(&mut self) {
         if self.cursor > 0 {
             let byte_idx = self.char_to_byte_index(self.cursor - 1);
@@ -0,0 +1,85 @@
+use crate::level::Level;
+use crate::source_ref::SourceRef;
+use chrono::{DateTime, Local, SecondsFormat};
+
+/// Characters of the record kept for `{message}`, so the URL stays short
+/// enough for browsers and issue trackers.
+const MESSAGE_CHARS: usize = 2000;
+
+/// Characters of the record's first line kept for `{title}`.
+const TITLE_CHARS: usize = 80;
+
+/// What a record fills into an issue URL template.
+pub struct Record<'a> {
+    pub message: &'a str,
+    pub time: DateTime<Local>,
+    pub level: Option<Level>,
+    pub source: Option<&'a SourceRef>,
+}
+
+/// Percent-encode everything but unreserved characters, so a value is safe
+/// anywhere in a query or path.
+fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The first `max` characters of `text`, with `…` when cut.
+fn shorten(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// The URL `template` makes for `record`: `{message}`, `{title}` (its
+/// first line), `{timestamp}` (RFC 3339), `{level}`, `{file}` and `{line}`
+/// are filled in percent-encoded, empty when the record has none.
+pub fn issue_url(template: &str, record: &Record) -> String {
+    let title = record.message.lines().next().unwrap_or_default().trim();
+    let level = record.level.map(|level| format!("{:?}", level).to_lowercase()).unwrap_or_default();
+    let (file, line) = match record.source {
+        Some(source) => (source.path.clone(), source.line.to_string()),
+        None => (String::new(), String::new()),
+    };
+    template
+        .replace("{message}", &encode(&shorten(record.message, MESSAGE_CHARS)))
+        .replace("{title}", &encode(&shorten(title, TITLE_CHARS)))
+        .replace("{timestamp}", &encode(&record.time.to_rfc3339_opts(SecondsFormat::Millis, false)))
+        .replace("{level}", &level)
+        .replace("{file}", &encode(&file))
+        .replace("{line}", &line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_ref::find_refs;
+
+    #[test]
+    fn test_issue_url() {
+        let message = "panicked at src/main.rs:10:5: index out of bounds\n  at worker #3";
+        let source = &find_refs(message)[0];
+        let record = Record {
+            message,
+            time: DateTime::parse_from_rfc3339("2024-06-12T10:00:00.250+02:00").unwrap().with_timezone(&Local),
+            level: Some(Level::Error),
+            source: Some(source),
+        };
+        let url = issue_url("https://github.com/o/r/issues/new?title=[{level}]%20{title}&body={message}%0A%0A{file}:{line}%20{timestamp}", &record);
+        let (title, body) = url.split_once("&body=").unwrap();
+        assert_eq!(title, "https://github.com/o/r/issues/new?title=[error]%20panicked%20at%20src%2Fmain.rs%3A10%3A5%3A%20index%20out%20of%20bounds");
+        assert!(body.starts_with("panicked%20at%20src%2Fmain.rs%3A10%3A5%3A%20index%20out%20of%20bounds%0A%20%20at%20worker%20%233%0A%0Asrc%2Fmain.rs:10%20"));
+        assert!(body.ends_with(&encode(&record.time.to_rfc3339_opts(SecondsFormat::Millis, false))));
+
+        let long = "x".repeat(TITLE_CHARS + 5);
+        let record = Record { message: &long, level: None, source: None, ..record };
+        assert_eq!(issue_url("{title}|{level}|{file}:{line}", &record), format!("{}%E2%80%A6||:", "x".repeat(TITLE_CHARS)));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::macros::{encode_key, parse_keys};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// How long keys that are a binding and also start a longer one wait for
+/// the rest before the shorter binding runs.
+const CHORD_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// What a normal-mode key sequence does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    EditHide,
+    EditFilter,
+    EditHighlight,
+    EditLineStart,
+    EditRewrite,
+    ToggleRaw,
+    ToggleDedup,
+    FilterStats,
+    EditWatches,
+    ToggleWatches,
+    EditNote,
+    ToggleNote,
+    Command,
+    FollowMatch,
+    Playground,
+    RecordMacro,
+    PlayMacro,
+    ReplayPause,
+    ReplaySpeed,
+    ReplaySkip,
+    Clear,
+    ToggleTime,
+    CycleTimeFormat,
+    ToggleWrap,
+    Top,
+    Bottom,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    Detail,
+    ToggleTrace,
+    EntityMenu,
+    Columns,
+    DismissToasts,
+    Outline,
+    Correlate,
+    ToggleLanes,
+    ToggleEpochs,
+    ToggleRuler,
+    ColumnLeft,
+    ColumnRight,
+    SelectLines,
+    SelectBlock,
+    Yank,
+    OpenSource,
+    FileIssue,
+    CopyIssueUrl,
+    Snooze,
+    Fields,
+    Why,
+    NextLayout,
+    ToggleSplit,
+    SplitFocus,
+    TogglePin,
+    ToggleMark,
+}
+
+/// Names used for actions in the `keymap` setting.
+const ACTIONS: [(&str, Action); 59] = [
+    ("quit", Action::Quit),
+    ("edit-hide", Action::EditHide),
+    ("edit-filter", Action::EditFilter),
+    ("edit-highlight", Action::EditHighlight),
+    ("edit-line-start", Action::EditLineStart),
+    ("edit-rewrite", Action::EditRewrite),
+    ("toggle-raw", Action::ToggleRaw),
+    ("toggle-dedup", Action::ToggleDedup),
+    ("filter-stats", Action::FilterStats),
+    ("edit-watches", Action::EditWatches),
+    ("toggle-watches", Action::ToggleWatches),
+    ("edit-note", Action::EditNote),
+    ("toggle-note", Action::ToggleNote),
+    ("command", Action::Command),
+    ("follow-match", Action::FollowMatch),
+    ("playground", Action::Playground),
+    ("record-macro", Action::RecordMacro),
+    ("play-macro", Action::PlayMacro),
+    ("replay-pause", Action::ReplayPause),
+    ("replay-speed", Action::ReplaySpeed),
+    ("replay-skip", Action::ReplaySkip),
+    ("clear", Action::Clear),
+    ("toggle-time", Action::ToggleTime),
+    ("cycle-time-format", Action::CycleTimeFormat),
+    ("toggle-wrap", Action::ToggleWrap),
+    ("top", Action::Top),
+    ("bottom", Action::Bottom),
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("page-up", Action::PageUp),
+    ("page-down", Action::PageDown),
+    ("half-page-up", Action::HalfPageUp),
+    ("half-page-down", Action::HalfPageDown),
+    ("detail", Action::Detail),
+    ("toggle-trace", Action::ToggleTrace),
+    ("entity-menu", Action::EntityMenu),
+    ("columns", Action::Columns),
+    ("dismiss-toasts", Action::DismissToasts),
+    ("outline", Action::Outline),
+    ("correlate", Action::Correlate),
+    ("toggle-lanes", Action::ToggleLanes),
+    ("toggle-epochs", Action::ToggleEpochs),
+    ("toggle-ruler", Action::ToggleRuler),
+    ("column-left", Action::ColumnLeft),
+    ("column-right", Action::ColumnRight),
+    ("select-lines", Action::SelectLines),
+    ("select-block", Action::SelectBlock),
+    ("yank", Action::Yank),
+    ("open-source", Action::OpenSource),
+    ("file-issue", Action::FileIssue),
+    ("copy-issue-url", Action::CopyIssueUrl),
+    ("snooze", Action::Snooze),
+    ("fields", Action::Fields),
+    ("why", Action::Why),
+    ("next-layout", Action::NextLayout),
+    ("split", Action::ToggleSplit),
+    ("split-focus", Action::SplitFocus),
+    ("pin", Action::TogglePin),
+    ("mark", Action::ToggleMark),
+];
+
+const DEFAULT_BINDINGS: [(&str, Action); 63] = [
+    ("q", Action::Quit),
+    ("<C-c>", Action::Quit),
+    ("d", Action::EditHide),
+    ("f", Action::EditFilter),
+    ("h", Action::EditHighlight),
+    ("s", Action::EditLineStart),
+    ("r", Action::EditRewrite),
+    ("R", Action::ToggleRaw),
+    ("D", Action::ToggleDedup),
+    ("S", Action::FilterStats),
+    ("W", Action::EditWatches),
+    ("m", Action::ToggleWatches),
+    ("n", Action::EditNote),
+    ("N", Action::ToggleNote),
+    (":", Action::Command),
+    ("F", Action::FollowMatch),
+    ("P", Action::Playground),
+    ("Q", Action::RecordMacro),
+    ("@", Action::PlayMacro),
+    ("<Space>", Action::ToggleMark),
+    (">", Action::ReplaySpeed),
+    (".", Action::ReplaySkip),
+    ("c", Action::Clear),
+    ("t", Action::ToggleTime),
+    ("T", Action::CycleTimeFormat),
+    ("w", Action::ToggleWrap),
+    ("gg", Action::Top),
+    ("<Home>", Action::Top),
+    ("G", Action::Bottom),
+    ("<End>", Action::Bottom),
+    ("k", Action::Up),
+    ("<Up>", Action::Up),
+    ("j", Action::Down),
+    ("<Down>", Action::Down),
+    ("<PageUp>", Action::PageUp),
+    ("<PageDown>", Action::PageDown),
+    ("<C-u>", Action::HalfPageUp),
+    ("<C-d>", Action::HalfPageDown),
+    ("<Enter>", Action::Detail),
+    ("z", Action::ToggleTrace),
+    ("e", Action::EntityMenu),
+    ("L", Action::Columns),
+    ("<Esc>", Action::DismissToasts),
+    ("O", Action::Outline),
+    ("C", Action::Correlate),
+    ("|", Action::ToggleLanes),
+    ("E", Action::ToggleEpochs),
+    ("#", Action::ToggleRuler),
+    ("<Left>", Action::ColumnLeft),
+    ("<Right>", Action::ColumnRight),
+    ("V", Action::SelectLines),
+    ("<C-v>", Action::SelectBlock),
+    ("y", Action::Yank),
+    ("gf", Action::OpenSource),
+    ("gi", Action::FileIssue),
+    ("gI", Action::CopyIssueUrl),
+    ("X", Action::Snooze),
+    ("K", Action::Fields),
+    ("?", Action::Why),
+    ("gl", Action::NextLayout),
+    ("gs", Action::ToggleSplit),
+    ("<C-w>", Action::SplitFocus),
+    ("p", Action::TogglePin),
+];
+
+/// Bindings that take over while replaying, unless the `keymap` setting
+/// binds the same keys.
+const REPLAY_BINDINGS: [(&str, Action); 1] = [("<Space>", Action::ReplayPause)];
+
+/// Normal-mode key bindings, and the chord and count typed so far.
+pub struct Keymap {
+    bindings: HashMap<Vec<String>, Action>,
+    replay_bindings: HashMap<Vec<String>, Action>,
+    replaying: bool,
+    pending: Vec<String>,
+    count: Option<usize>,
+    /// When the pending keys became a complete binding that a longer one
+    /// also starts with.
+    ambiguous_since: Option<Instant>,
+}
+
+impl Keymap {
+    /// The default bindings with `overrides` (keys in macro notation to an
+    /// action name, or `""` to unbind) applied, and any entries that didn't
+    /// parse.
+    pub fn new(overrides: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut bindings = HashMap::new();
+        for (keys, action) in DEFAULT_BINDINGS {
+            if let Some(keys) = key_names(keys) {
+                bindings.insert(keys, action);
+            }
+        }
+        let mut errors = Vec::new();
+        for (keys, name) in overrides {
+            let Some(sequence) = key_names(keys) else {
+                errors.push(format!("bad keys '{}'", keys));
+                continue;
+            };
+            if name.is_empty() {
+                bindings.remove(&sequence);
+            } else if let Some((_, action)) = ACTIONS.iter().find(|(n, _)| n == name) {
+                bindings.insert(sequence, *action);
+            } else {
+                errors.push(format!("unknown action '{}' for '{}'", name, keys));
+            }
+        }
+        let replay_bindings = REPLAY_BINDINGS
+            .iter()
+            .filter_map(|(keys, action)| Some((key_names(keys)?, *action)))
+            .filter(|(keys, _)| !overrides.keys().any(|k| key_names(k).as_ref() == Some(keys)))
+            .collect();
+        let keymap = Self {
+            bindings,
+            replay_bindings,
+            replaying: false,
+            pending: Vec::new(),
+            count: None,
+            ambiguous_since: None,
+        };
+        (keymap, errors)
+    }
+
+    /// Switch to the replay bindings, e.g. `<Space>` pausing instead of
+    /// marking.
+    pub fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    fn binding(&self, keys: &[String]) -> Option<Action> {
+        let replay = self.replaying.then(|| self.replay_bindings.get(keys)).flatten();
+        replay.or_else(|| self.bindings.get(keys)).copied()
+    }
+
+    /// Feed a key, returning the actions it completes with their counts.
+    pub fn press(&mut self, key: KeyEvent) -> Vec<(Action, Option<usize>)> {
+        self.press_at(key, Instant::now())
+    }
+
+    fn press_at(&mut self, key: KeyEvent, now: Instant) -> Vec<(Action, Option<usize>)> {
+        // Terminals differ on whether uppercase letters carry Shift.
+        let key = KeyEvent::new(key.code, key.modifiers & KeyModifiers::CONTROL);
+        let Some(name) = encode_key(key) else {
+            self.reset();
+            return Vec::new();
+        };
+        if let KeyCode::Char(digit @ '0'..='9') = key.code {
+            let starts_count = digit != '0' || self.count.is_some();
+            if self.pending.is_empty() && starts_count && !self.starts_binding(std::slice::from_ref(&name)) {
+                let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                return Vec::new();
+            }
+        }
+
+        self.pending.push(name);
+        let exact = self.binding(&self.pending);
+        let longer = self.starts_longer(&self.pending);
+        match (exact, longer) {
+            (Some(action), false) => vec![self.finish(action)],
+            (_, true) => {
+                self.ambiguous_since = exact.map(|_| now);
+                Vec::new()
+            }
+            (None, false) => {
+                // The keys before this one may have been waiting to see
+                // whether a longer binding follows.
+                self.pending.pop();
+                let shorter = self.binding(&self.pending);
+                let retry = !self.pending.is_empty();
+                match shorter {
+                    Some(action) => {
+                        let mut actions = vec![self.finish(action)];
+                        actions.extend(self.press_at(key, now));
+                        actions
+                    }
+                    None => {
+                        self.reset();
+                        if retry {
+                            self.press_at(key, now)
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The shorter binding of an ambiguous chord, once nothing followed it
+    /// for `CHORD_TIMEOUT`.
+    pub fn expire(&mut self, now: Instant) -> Option<(Action, Option<usize>)> {
+        let since = self.ambiguous_since?;
+        if now.duration_since(since) < CHORD_TIMEOUT {
+            return None;
+        }
+        let action = self.binding(&self.pending)?;
+        Some(self.finish(action))
+    }
+
+    /// The count and keys typed so far, e.g. `10g`.
+    pub fn pending(&self) -> String {
+        let count = self.count.map(|c| c.to_string()).unwrap_or_default();
+        let keys: String = self
+            .pending
+            .iter()
+            .map(|key| if key == " " { "<Space>" } else { key })
+            .collect();
+        count + &keys
+    }
+
+    fn finish(&mut self, action: Action) -> (Action, Option<usize>) {
+        let count = self.count;
+        self.reset();
+        (action, count)
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+        self.ambiguous_since = None;
+    }
+
+    fn starts_binding(&self, keys: &[String]) -> bool {
+        self.bindings.keys().any(|b| b.starts_with(keys))
+    }
+
+    fn starts_longer(&self, keys: &[String]) -> bool {
+        self.bindings.keys().any(|b| b.len() > keys.len() && b.starts_with(keys))
+    }
+}
+
+/// `"<Space>f"` as `[" ", "f"]`, each key in `encode_key` form.
+fn key_names(keys: &str) -> Option<Vec<String>> {
+    let names: Option<Vec<String>> = parse_keys(keys).ok()?.into_iter().map(encode_key).collect();
+    names.filter(|names| !names.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keymap: &mut Keymap, keys: &str, now: Instant) -> Vec<(Action, Option<usize>)> {
+        parse_keys(keys)
+            .unwrap()
+            .into_iter()
+            .flat_map(|key| keymap.press_at(key, now))
+            .collect()
+    }
+
+    #[test]
+    fn test_chords_counts_and_overrides() {
+        let overrides = BTreeMap::from([
+            ("<Space>f".to_string(), "filter-stats".to_string()),
+            ("c".to_string(), String::new()),
+            ("x".to_string(), "nope".to_string()),
+        ]);
+        let (mut keymap, errors) = Keymap::new(&overrides);
+        assert_eq!(errors, ["unknown action 'nope' for 'x'"]);
+        let now = Instant::now();
+
+        assert_eq!(press(&mut keymap, "g", now), []);
+        assert_eq!(keymap.pending(), "g");
+        assert_eq!(press(&mut keymap, "g", now), [(Action::Top, None)]);
+        assert_eq!(press(&mut keymap, "10j", now), [(Action::Down, Some(10))]);
+        assert_eq!(press(&mut keymap, "c", now), []);
+        assert_eq!(press(&mut keymap, "<Space>f", now), [(Action::FilterStats, None)]);
+
+        // A lone Space runs after the next key, or once the chord times out.
+        assert_eq!(
+            press(&mut keymap, "<Space>j", now),
+            [(Action::ToggleMark, None), (Action::Down, None)]
+        );
+        assert_eq!(press(&mut keymap, "2<Space>", now), []);
+        assert_eq!(keymap.expire(now), None);
+        assert_eq!(keymap.expire(now + CHORD_TIMEOUT), Some((Action::ToggleMark, Some(2))));
+        assert_eq!(keymap.pending(), "");
+
+        keymap.set_replaying(true);
+        assert_eq!(press(&mut keymap, "<Space>j", now), [(Action::ReplayPause, None), (Action::Down, None)]);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::columns::{default_columns, Column, ColumnKind, TimeFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// Columns, panes and view toggles, saved under a name and switched as one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub columns: Vec<Column>,
+    pub time_format: TimeFormat,
+    pub show_time: bool,
+    pub wrap_lines: bool,
+    pub show_raw: bool,
+    pub show_watches: bool,
+    pub show_outline: bool,
+    pub show_lanes: bool,
+    pub show_ruler: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            columns: default_columns(),
+            time_format: TimeFormat::default(),
+            show_time: true,
+            wrap_lines: true,
+            show_raw: false,
+            show_watches: false,
+            show_outline: false,
+            show_lanes: false,
+            show_ruler: false,
+        }
+    }
+}
+
+/// `triage`: a table of level, clock time and source with the watch and
+/// outline panes; `raw`: the lines as received, wrapped, nothing around them.
+fn builtin() -> BTreeMap<String, Layout> {
+    let shown = |kinds: &[ColumnKind]| -> Vec<Column> {
+        default_columns()
+            .into_iter()
+            .map(|column| Column {
+                visible: kinds.contains(&column.kind),
+                ..column
+            })
+            .collect()
+    };
+    let triage = Layout {
+        columns: shown(&[ColumnKind::ReceiptTime, ColumnKind::Level, ColumnKind::Source]),
+        time_format: TimeFormat::Clock,
+        wrap_lines: false,
+        show_watches: true,
+        show_outline: true,
+        ..Layout::default()
+    };
+    let raw = Layout {
+        columns: shown(&[]),
+        show_time: false,
+        show_raw: true,
+        ..Layout::default()
+    };
+    BTreeMap::from([("triage".to_string(), triage), ("raw".to_string(), raw)])
+}
+
+/// The built-in layouts with the saved ones over them, by name.
+pub fn available(saved: &BTreeMap<String, Layout>) -> BTreeMap<String, Layout> {
+    let mut layouts = builtin();
+    layouts.extend(saved.iter().map(|(name, layout)| (name.clone(), layout.clone())));
+    layouts
+}
+
+/// The layout after `current` in name order, wrapping around; the first one
+/// when none is current.
+pub fn next(saved: &BTreeMap<String, Layout>, current: Option<&str>) -> (String, Layout) {
+    let layouts = available(saved);
+    let after = current.and_then(|current| layouts.range::<str, _>((Bound::Excluded(current), Bound::Unbounded)).next());
+    let (name, layout) = after.or_else(|| layouts.iter().next()).expect("built-in layouts");
+    (name.clone(), layout.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_cycle_by_name() {
+        let mut saved = BTreeMap::new();
+        assert_eq!(next(&saved, None).0, "raw");
+        assert_eq!(next(&saved, Some("raw")).0, "triage");
+        assert_eq!(next(&saved, Some("triage")).0, "raw");
+
+        let mine = Layout {
+            wrap_lines: false,
+            ..Layout::default()
+        };
+        saved.insert("mine".to_string(), mine.clone());
+        saved.insert("raw".to_string(), Layout::default());
+        assert_eq!(next(&saved, Some("gone")), ("mine".to_string(), mine));
+        assert_eq!(next(&saved, Some("mine")), ("raw".to_string(), Layout::default()));
+        assert!(available(&saved)["triage"].show_watches);
+        assert!(!available(&BTreeMap::new())["raw"].columns.iter().any(|c| c.visible));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::access;
+use crate::logcat;
+use crate::oslog;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{LazyLock, RwLock};
+
+static LEVEL_WORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(fatal|panic|crit(?:ical)?|error|err|warn(?:ing)?|info|debug|trace)\b").unwrap()
+});
+
+static SYSLOG_PRI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<(\d{1,3})>").unwrap());
+
+/// Severity fields of journald (`journalctl -o json`), OTLP and Docker
+/// json-file records; a line without one isn't parsed as JSON.
+static SEVERITY_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""(PRIORITY|severityNumber|severity_number|stream)"\s*:"#).unwrap());
+
+/// Severity of a log line, ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Three-letter badge for the level column.
+    pub fn badge(self) -> &'static str {
+        match self {
+            Level::Trace => "TRC",
+            Level::Debug => "DBG",
+            Level::Info => "INF",
+            Level::Warn => "WRN",
+            Level::Error => "ERR",
+        }
+    }
+}
+
+/// A syslog severity (0 emerg … 7 debug), from a PRI header or journald.
+fn from_syslog_severity(severity: u32) -> Option<Level> {
+    Some(match severity {
+        0..=3 => Level::Error,
+        4 => Level::Warn,
+        5 | 6 => Level::Info,
+        7 => Level::Debug,
+        _ => return None,
+    })
+}
+
+/// A severity the record's format states outright rather than one guessed
+/// from its words: a syslog `<PRI>` header, journald `PRIORITY`, OTLP
+/// `severityNumber`, an access log's status, or a logcat priority.
+pub fn structured_level(content: &str) -> Option<Level> {
+    if let Some(caps) = SYSLOG_PRI.captures(content) {
+        let pri: u32 = caps[1].parse().ok()?;
+        return (pri <= 191).then(|| from_syslog_severity(pri % 8)).flatten();
+    }
+    if let Some(request) = access::parse(content).filter(|_| access::enabled()) {
+        return Some(request.level());
+    }
+    if let Some(entry) = logcat::parse(content).filter(|_| logcat::enabled()) {
+        return entry.level();
+    }
+    if let Some(entry) = oslog::parse(content).filter(|_| oslog::enabled()) {
+        return entry.level();
+    }
+    let fields = severity_fields(content)?;
+    if let Some(priority) = fields.get("PRIORITY") {
+        return from_syslog_severity(json_number(priority)?);
+    }
+    let number = fields.get("severityNumber").or_else(|| fields.get("severity_number"))?;
+    Some(match json_number(number)? {
+        1..=4 => Level::Trace,
+        5..=8 => Level::Debug,
+        9..=12 => Level::Info,
+        13..=16 => Level::Warn,
+        17..=24 => Level::Error,
+        _ => return None,
+    })
+}
+
+/// The top-level fields of a JSON record that has a severity field
+/// anywhere, so fields of nested objects and strings don't count.
+fn severity_fields(content: &str) -> Option<Map<String, Value>> {
+    if !content.trim_start().starts_with('{') || !SEVERITY_FIELD.is_match(content) {
+        return None;
+    }
+    match serde_json::Deserializer::from_str(content).into_iter::<Value>().next()? {
+        Ok(Value::Object(fields)) => Some(fields),
+        _ => None,
+    }
+}
+
+/// `9` or `"9"`.
+fn json_number(value: &Value) -> Option<u32> {
+    match value {
+        Value::String(text) => text.parse().ok(),
+        _ => value.as_u64()?.try_into().ok(),
+    }
+}
+
+/// Docker's `"stream":"stderr"` as error, for records whose text names no
+/// level.
+fn stream_level(content: &str) -> Option<Level> {
+    (severity_fields(content)?.get("stream")?.as_str()? == "stderr").then_some(Level::Error)
+}
+
+/// A `level_rules` entry: lines matching `pattern` (and detected at `from`,
+/// when given) are treated as `to`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub from: Option<Level>,
+    pub to: Level,
+}
+
+type CompiledRule = (Regex, Option<Level>, Level);
+
+static RULES: RwLock<Vec<CompiledRule>> = RwLock::new(Vec::new());
+
+/// Classify lines by `rules` from now on. Returns the patterns that didn't
+/// compile, which are left out.
+pub fn set_rules(rules: &[LevelRule]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let compiled = rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(re) => Some((re, rule.from, rule.to)),
+            Err(_) => {
+                errors.push(rule.pattern.clone());
+                None
+            }
+        })
+        .collect();
+    if let Ok(mut current) = RULES.write() {
+        *current = compiled;
+    }
+    errors
+}
+
+/// The first rule matching the line decides; otherwise the detected level.
+fn apply_rules(rules: &[CompiledRule], content: &str, detected: Option<Level>) -> Option<Level> {
+    rules
+        .iter()
+        .find(|(re, from, _)| from.is_none_or(|from| detected == Some(from)) && re.is_match(content))
+        .map_or(detected, |(_, _, to)| Some(*to))
+}
+
+/// A line's level: stated by its format, else guessed from its words, else
+/// error for Docker's stderr, then adjusted by the `level_rules`.
+pub fn classify(content: &str) -> Option<Level> {
+    let detected = structured_level(content)
+        .or_else(|| detect_level(content))
+        .or_else(|| stream_level(content));
+    match RULES.read() {
+        Ok(rules) if !rules.is_empty() => apply_rules(&rules, content, detected),
+        _ => detected,
+    }
+}
+
+/// The first level keyword in the line, e.g. `ERROR`, `[warn]`, `"level":"info"`.
+pub fn detect_level(content: &str) -> Option<Level> {
+    let word = LEVEL_WORD.find(content)?.as_str().to_ascii_lowercase();
+    Some(match word.as_str() {
+        "trace" => Level::Trace,
+        "debug" => Level::Debug,
+        "info" => Level::Info,
+        "warn" | "warning" => Level::Warn,
+        _ => Level::Error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_level() {
+        assert_eq!(detect_level("2024-01-01 [WARN] disk low"), Some(Level::Warn));
+        assert_eq!(detect_level(r#"{"level":"debug","msg":"x"}"#), Some(Level::Debug));
+        assert_eq!(detect_level("thread panicked"), None);
+        assert_eq!(detect_level("FATAL: out of memory"), Some(Level::Error));
+        assert_eq!(detect_level("informational message"), None);
+    }
+
+    #[test]
+    fn test_level_rules() {
+        let rules = vec![
+            (Regex::new("timeout").unwrap(), Some(Level::Info), Level::Warn),
+            (Regex::new("cache miss").unwrap(), None, Level::Debug),
+        ];
+        assert_eq!(apply_rules(&rules, "INFO upstream timeout", Some(Level::Info)), Some(Level::Warn));
+        assert_eq!(apply_rules(&rules, "ERROR upstream timeout", Some(Level::Error)), Some(Level::Error));
+        assert_eq!(apply_rules(&rules, "ERROR cache miss for key", Some(Level::Error)), Some(Level::Debug));
+        assert_eq!(apply_rules(&rules, "plain", None), None);
+    }
+
+    #[test]
+    fn test_structured_level() {
+        assert_eq!(structured_level("<11>Jan  1 00:00:00 host app: info ok"), Some(Level::Error));
+        assert_eq!(structured_level("<190>1 2024-01-01T00:00:00Z host app - - - error?"), Some(Level::Info));
+        assert_eq!(structured_level(r#"{"MESSAGE":"disk low","PRIORITY":"4"}"#), Some(Level::Warn));
+        assert_eq!(structured_level(r#"{"severityNumber":9,"body":"ERROR in text"}"#), Some(Level::Info));
+        assert_eq!(structured_level(r#"{"log":"{\"PRIORITY\":\"3\"}","stream":"stdout"}"#), None);
+        assert_eq!(structured_level(r#"{"msg":"x","ctx":{"severityNumber":21}}"#), None);
+        // stderr only counts when the text names no level.
+        assert_eq!(classify(r#"{"log":"starting\n","stream":"stderr"}"#), Some(Level::Error));
+        assert_eq!(classify(r#"{"log":"INFO listening\n","stream":"stderr"}"#), Some(Level::Info));
+        assert_eq!(structured_level("ERROR plain text"), None);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::level::Level;
+use crate::table::{self, Format};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `adb logcat -v threadtime`: date, time, PID, TID, priority, tag, message.
+static THREADTIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3})\s+(\d+)\s+(\d+)\s+([VDIWEFS])\s+(.*?)\s*: (.*)$").unwrap()
+});
+
+/// One entry of an Android log.
+#[derive(Debug, PartialEq)]
+pub struct Entry<'a> {
+    /// `01-31 14:05:01.123`, as `adb logcat -T` takes it.
+    pub time: &'a str,
+    pub pid: &'a str,
+    pub tid: &'a str,
+    pub priority: char,
+    pub tag: &'a str,
+    pub message: &'a str,
+}
+
+/// Whether `--format` lets lines be read as logcat entries.
+pub fn enabled() -> bool {
+    matches!(table::format(), Format::Auto | Format::Logcat)
+}
+
+pub fn parse(line: &str) -> Option<Entry<'_>> {
+    let caps = THREADTIME.captures(line)?;
+    Some(Entry {
+        time: caps.get(1)?.as_str(),
+        pid: caps.get(2)?.as_str(),
+        tid: caps.get(3)?.as_str(),
+        priority: caps[4].chars().next()?,
+        tag: caps.get(5)?.as_str(),
+        message: caps.get(6)?.as_str(),
+    })
+}
+
+impl Entry<'_> {
+    /// Fatal counts as an error; silent has no level.
+    pub fn level(&self) -> Option<Level> {
+        match self.priority {
+            'V' => Some(Level::Trace),
+            'D' => Some(Level::Debug),
+            'I' => Some(Level::Info),
+            'W' => Some(Level::Warn),
+            'E' | 'F' => Some(Level::Error),
+            _ => None,
+        }
+    }
+
+    /// The entry with its PID, TID and tag as `name=value` fields.
+    pub fn fields(&self) -> String {
+        let tag = if self.tag.contains(' ') {
+            format!("\"{}\"", self.tag)
+        } else {
+            self.tag.to_string()
+        };
+        format!(
+            "{} pid={} tid={} {} tag={}: {}",
+            self.time, self.pid, self.tid, self.priority, tag, self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_threadtime() {
+        let entry = parse("01-31 14:05:01.123  1234  5678 W ActivityManager: Slow operation: 120ms").unwrap();
+        assert_eq!((entry.pid, entry.tid, entry.tag, entry.message), ("1234", "5678", "ActivityManager", "Slow operation: 120ms"));
+        assert_eq!(entry.level(), Some(Level::Warn));
+        assert_eq!(
+            entry.fields(),
+            "01-31 14:05:01.123 pid=1234 tid=5678 W tag=ActivityManager: Slow operation: 120ms"
+        );
+        let entry = parse("01-31 14:05:02.000   99   99 F libc    : Fatal signal 11").unwrap();
+        assert_eq!((entry.tag, entry.level()), ("libc", Some(Level::Error)));
+        assert!(parse("2024-01-31 14:05:01 INFO started").is_none());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::source::{without_userinfo, Feed, Message};
+use crate::timestamp::with_time;
+use crate::tls::client_config;
+use anyhow::{anyhow, bail, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::Local;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConnection, StreamOwned};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::WebSocket;
+
+/// Wait between polls, doubled while Loki turns requests away up to the max.
+const POLL: Duration = Duration::from_secs(2);
+const POLL_MAX: Duration = Duration::from_secs(60);
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a read of the tail waits before coming back empty.
+const TAIL_WAIT: Duration = Duration::from_secs(5);
+
+/// How far back each poll and the tail look again for entries ingested
+/// late, which are told apart from ones already shown by time, labels and
+/// text.
+const LATE_NS: i64 = 30_000_000_000;
+
+/// Entries asked for per query; a full page is followed by the next at once.
+const LIMIT: usize = 1000;
+
+/// Labels whose value tags a stream's lines, first found wins.
+const TAG_LABELS: [&str; 4] = ["service_name", "app", "job", "container"];
+
+/// `--loki URL --query LOGQL`: a Loki server, with `user:password@` for
+/// basic auth, and the log query to run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LokiQuery {
+    pub url: String,
+    pub query: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    data: Data,
+}
+
+#[derive(Deserialize)]
+struct Data {
+    #[serde(default)]
+    result: Vec<Stream>,
+}
+
+/// A message from the `tail` WebSocket.
+#[derive(Deserialize)]
+struct Tail {
+    #[serde(default)]
+    streams: Vec<Stream>,
+}
+
+/// The WebSocket's connection, plain or TLS.
+trait Duplex: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Duplex for T {}
+
+#[derive(Deserialize)]
+struct Stream {
+    #[serde(default)]
+    stream: BTreeMap<String, String>,
+    #[serde(default)]
+    values: Vec<(String, String)>,
+}
+
+/// A line with the stream labels it lacks as fields: keys of a JSON object,
+/// else `key=value` pairs after the text.
+fn with_labels(line: &str, labels: &BTreeMap<String, String>) -> String {
+    if let Ok(serde_json::Value::Object(mut object)) = serde_json::from_str(line) {
+        for (name, value) in labels {
+            object.entry(name.clone()).or_insert_with(|| value.clone().into());
+        }
+        return serde_json::Value::Object(object).to_string();
+    }
+    let fields: Vec<String> = labels
+        .iter()
+        .filter(|(name, _)| !line.contains(&format!("{}=", name)))
+        .map(|(name, value)| match value.contains([' ', '"']) {
+            true => format!("{}={:?}", name, value),
+            false => format!("{}={}", name, value),
+        })
+        .collect();
+    match fields.is_empty() {
+        true => line.to_string(),
+        false => format!("{} {}", line, fields.join(" ")),
+    }
+}
+
+fn tag(labels: &BTreeMap<String, String>) -> String {
+    TAG_LABELS
+        .iter()
+        .find_map(|name| labels.get(*name))
+        .cloned()
+        .unwrap_or_else(|| "loki".to_string())
+}
+
+/// Reads a query's entries: `query_range` pages up to now, then the `tail`
+/// WebSocket, or `query_range` polls when Loki or a proxy in front of it
+/// won't open one.
+pub struct Poller {
+    agent: ureq::Agent,
+    query: LokiQuery,
+    /// The newest entry time shown (ns since the epoch).
+    start_ns: i64,
+    /// Entries before this were read before this poller was opened.
+    floor_ns: i64,
+    /// The entries shown in the last `LATE_NS` before `start_ns`, by time,
+    /// labels and line.
+    seen: HashSet<(i64, String, String)>,
+    interval: Duration,
+    first: bool,
+    /// The last page was full, so the next is asked for at once.
+    more: bool,
+    /// `query_range` has read up to now, so the tail can take over.
+    caught_up: bool,
+    tail: Option<WebSocket<Box<dyn Duplex>>>,
+    /// The tail couldn't be opened; poll instead.
+    no_tail: bool,
+}
+
+impl Poller {
+    pub fn open(query: &LokiQuery, start_ns: i64) -> Result<Self> {
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            query: query.clone(),
+            start_ns,
+            floor_ns: start_ns,
+            seen: HashSet::new(),
+            interval: POLL,
+            first: true,
+            more: false,
+            caught_up: false,
+            tail: None,
+            no_tail: false,
+        })
+    }
+
+    fn endpoint(&self, name: &str) -> String {
+        format!("{}/loki/api/v1/{}", self.query.url.trim_end_matches('/'), name)
+    }
+
+    /// Where a query starts: back `LATE_NS` for entries ingested late,
+    /// unless a full page is being followed.
+    fn query_start(&self) -> i64 {
+        match self.more {
+            true => self.start_ns,
+            false => (self.start_ns.saturating_sub(LATE_NS)).max(self.floor_ns),
+        }
+    }
+
+    /// The entries of `streams` not shown yet, oldest first, moving the
+    /// start up to the newest.
+    fn take_new(&mut self, streams: &[Stream]) -> Result<Vec<Message>> {
+        let mut entries = Vec::new();
+        for stream in streams {
+            for (ns, line) in &stream.values {
+                let ns: i64 = ns.parse().map_err(|_| anyhow!("bad Loki timestamp {}", ns))?;
+                entries.push((ns, stream, line));
+            }
+        }
+        entries.sort_by_key(|(ns, _, _)| *ns);
+        let mut messages = Vec::new();
+        for (ns, stream, line) in entries {
+            let old = ns < self.floor_ns || ns < self.start_ns.saturating_sub(LATE_NS);
+            if old || !self.seen.insert((ns, format!("{:?}", stream.stream), line.clone())) {
+                continue;
+            }
+            self.start_ns = self.start_ns.max(ns);
+            messages.push(Message {
+                source: tag(&stream.stream),
+                content: with_time(&with_labels(line, &stream.stream), ns / 1_000_000),
+            });
+        }
+        let oldest = self.start_ns.saturating_sub(LATE_NS);
+        self.seen.retain(|(ns, _, _)| *ns >= oldest);
+        Ok(messages)
+    }
+
+    /// Connect to the `tail` WebSocket from `query_start()` on, with the
+    /// URL's `user:password@` as basic auth.
+    fn open_tail(&self) -> Result<WebSocket<Box<dyn Duplex>>> {
+        let request = self
+            .agent
+            .get(&self.endpoint("tail"))
+            .query("query", &self.query.query)
+            .query("start", &self.query_start().to_string())
+            .query("limit", &LIMIT.to_string())
+            .request_url()?;
+        let url = request.as_url();
+        let host = url.host_str().ok_or_else(|| anyhow!("No host in {}", without_userinfo(&self.query.url)))?;
+        let tls = url.scheme() == "https";
+        let port = url.port_or_known_default().unwrap_or(if tls { 443 } else { 80 });
+        let scheme = if tls { "wss" } else { "ws" };
+        let query = url.query().unwrap_or_default();
+        let mut ws_request = format!("{}://{}:{}{}?{}", scheme, host, port, url.path(), query).into_client_request()?;
+        if !url.username().is_empty() || url.password().is_some() {
+            let credentials = format!("{}:{}", url.username(), url.password().unwrap_or_default());
+            let auth = format!("Basic {}", BASE64_STANDARD.encode(credentials)).parse()?;
+            ws_request.headers_mut().insert("Authorization", auth);
+        }
+
+        let addr = url.socket_addrs(|| None)?.into_iter().next().ok_or_else(|| anyhow!("No address for {}", host))?;
+        let tcp = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        tcp.set_read_timeout(Some(TAIL_WAIT))?;
+        let stream: Box<dyn Duplex> = match tls {
+            true => {
+                let name = ServerName::try_from(host.trim_matches(['[', ']']).to_string())?;
+                Box::new(StreamOwned::new(ClientConnection::new(client_config(None)?, name)?, tcp))
+            }
+            false => Box::new(tcp),
+        };
+        let (socket, _) =
+            tungstenite::client(ws_request, stream).map_err(|e| anyhow!("Cannot tail: {}", without_userinfo(&e.to_string())))?;
+        Ok(socket)
+    }
+
+    /// The next message from the tail; empty after `TAIL_WAIT` without one.
+    fn read_tail(&mut self) -> Result<Vec<Message>> {
+        let Some(socket) = self.tail.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let text = match socket.read() {
+            Ok(tungstenite::Message::Text(text)) => text,
+            Ok(tungstenite::Message::Close(_)) => bail!("Loki closed the tail"),
+            Ok(_) => return Ok(Vec::new()),
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let tail: Tail = serde_json::from_str(text.as_str())?;
+        self.take_new(&tail.streams)
+    }
+}
+
+impl Feed for Poller {
+    /// A poll turned away with 429 or 503 comes back empty and doubles the
+    /// wait before the next; a good one resets it.
+    fn next_batch(&mut self, wait: bool) -> Result<Vec<Message>> {
+        if wait && self.caught_up && !self.no_tail {
+            if self.tail.is_none() {
+                match self.open_tail() {
+                    Ok(socket) => self.tail = Some(socket),
+                    Err(_) => self.no_tail = true,
+                }
+            }
+            if self.tail.is_some() {
+                return self.read_tail();
+            }
+        }
+        if wait && !self.first && !self.more {
+            thread::sleep(self.interval);
+        }
+        self.first = false;
+        let now_ns = Local::now().timestamp_nanos_opt().unwrap_or(i64::MAX);
+        let response = self
+            .agent
+            .get(&self.endpoint("query_range"))
+            .query("query", &self.query.query)
+            .query("start", &self.query_start().to_string())
+            .query("end", &now_ns.to_string())
+            .query("limit", &LIMIT.to_string())
+            .query("direction", "forward")
+            .call();
+        let body = match response {
+            Ok(response) => response.into_string()?,
+            Err(ureq::Error::Status(429 | 503, _)) => {
+                self.interval = (self.interval * 2).min(POLL_MAX);
+                return Ok(Vec::new());
+            }
+            Err(ureq::Error::Status(status, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                bail!("HTTP {}: {}", status, body.trim());
+            }
+            Err(e) => bail!("{}", without_userinfo(&e.to_string())),
+        };
+        self.interval = POLL;
+        let response: Response = serde_json::from_str(&body)?;
+        self.more = response.data.result.iter().map(|s| s.values.len()).sum::<usize>() >= LIMIT;
+        self.caught_up = !self.more;
+        self.take_new(&response.data.result)
+    }
+
+    fn position(&self) -> Option<String> {
+        Some(self.start_ns.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loki_entries() {
+        let mut poller = Poller::open(&LokiQuery { url: String::new(), query: String::new() }, 1_718_186_400_000_000_000).unwrap();
+        let json = r#"{"status": "success", "data": {"resultType": "streams", "result": [
+            {"stream": {"app": "api", "pod": "api-7"}, "values": [
+                ["1718186400200000000", "{\"level\":\"warn\",\"msg\":\"slow\"}"],
+                ["1718186400100000000", "GET /health 200"]
+            ]},
+            {"stream": {"job": "db", "env": "prod eu"}, "values": [["1718186400200000000", "checkpoint done"]]}
+        ]}}"#;
+        let texts = |messages: Vec<Message>| messages.into_iter().map(|m| format!("{} | {}", m.source, m.content)).collect::<Vec<_>>();
+        let streams = |json: &str| serde_json::from_str::<Response>(json).unwrap().data.result;
+        assert_eq!(
+            texts(poller.take_new(&streams(json)).unwrap()),
+            [
+                "api | 2024-06-12T10:00:00.100Z GET /health 200 app=api pod=api-7",
+                r#"api | {"app":"api","level":"warn","msg":"slow","pod":"api-7","time":"2024-06-12T10:00:00.200Z"}"#,
+                r#"db | 2024-06-12T10:00:00.200Z checkpoint done env="prod eu" job=db"#,
+            ]
+        );
+        // The next poll looks back again, getting those entries again and
+        // one ingested late.
+        let again = r#"{"data": {"result": [{"stream": {"job": "db", "env": "prod eu"}, "values": [
+            ["1718186400150000000", "late"], ["1718186400200000000", "checkpoint done"], ["1718186400300000000", "vacuum"]
+        ]}]}}"#;
+        assert_eq!(
+            texts(poller.take_new(&streams(again)).unwrap()),
+            [
+                r#"db | 2024-06-12T10:00:00.150Z late env="prod eu" job=db"#,
+                r#"db | 2024-06-12T10:00:00.300Z vacuum env="prod eu" job=db"#
+            ]
+        );
+        let tail: Tail = serde_json::from_str(r#"{"streams": [{"stream": {"app": "api"}, "values": [["1718186400300000000", "tailed"]]}]}"#).unwrap();
+        assert_eq!(texts(poller.take_new(&tail.streams).unwrap()), ["api | 2024-06-12T10:00:00.300Z tailed app=api"]);
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Deepest a macro may play other macros, so `@a` inside `a` stops.
+pub const MAX_MACRO_DEPTH: usize = 8;
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("BS", KeyCode::Backspace),
+    ("Del", KeyCode::Delete),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+/// What the next key press names a register for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacroPrompt {
+    Record,
+    Play,
+}
+
+/// Write a key in vim notation: plain characters as themselves, others as
+/// `<Enter>`, `<C-c>`, `<lt>`; `parse_keys` also reads `<Space>`. `None` for keys a macro can't hold.
+pub fn encode_key(key: KeyEvent) -> Option<String> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let name = match key.code {
+        KeyCode::Char('<') if !ctrl => return Some("<lt>".to_string()),
+        KeyCode::Char(c) if !ctrl => return Some(c.to_string()),
+        KeyCode::Char(c) => c.to_string(),
+        code => NAMED_KEYS.iter().find(|(_, k)| *k == code)?.0.to_string(),
+    };
+    Some(if ctrl { format!("<C-{}>", name) } else { format!("<{}>", name) })
+}
+
+/// Parse keys written by `encode_key`.
+pub fn parse_keys(text: &str) -> Result<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            continue;
+        }
+        // A `>` right after `C-` is the key itself, as in `<C->>`.
+        let mut name = String::new();
+        for c in chars.by_ref() {
+            if c == '>' && name != "C-" {
+                break;
+            }
+            name.push(c);
+        }
+        let (name, modifiers) = match name.strip_prefix("C-") {
+            Some(rest) => (rest, KeyModifiers::CONTROL),
+            None => (name.as_str(), KeyModifiers::NONE),
+        };
+        let code = match name {
+            "lt" => KeyCode::Char('<'),
+            "Space" => KeyCode::Char(' '),
+            _ => match NAMED_KEYS.iter().find(|(n, _)| *n == name) {
+                Some((_, code)) => *code,
+                None => {
+                    let mut name_chars = name.chars();
+                    match (name_chars.next(), name_chars.next()) {
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(anyhow!("Unknown key <{}>", name)),
+                    }
+                }
+            },
+        };
+        keys.push(KeyEvent::new(code, modifiers));
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let keys = vec![
+            KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('>'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Char('>'), KeyModifiers::NONE),
+        ];
+        let text: String = keys.iter().filter_map(|k| encode_key(*k)).collect();
+        assert_eq!(text, "f<lt><Enter><C-c><PageUp><C->>>");
+        assert_eq!(parse_keys(&text).unwrap(), keys);
+        assert!(parse_keys("<F13>").is_err());
+    }
+}
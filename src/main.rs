@@ -1,77 +1,553 @@
+mod aggregate;
+mod bench;
+mod access;
 mod app;
+mod blame;
+mod bundle;
+mod capture;
+mod checkpoint;
+mod clipboard;
+mod cloudwatch;
+mod columns;
 mod constants;
 mod core;
+mod correlate;
+mod crash;
+mod dedup;
+mod discovery;
+mod elastic;
+mod enrich;
+mod entity;
 mod filter;
+mod filter_stats;
+mod glob;
 #[cfg(feature = "gui")]
 mod gui;
 mod highlight;
+mod humanize;
 mod input;
+mod issue;
+mod keymap;
+mod layout;
+mod level;
+mod logcat;
+mod loki;
+mod macros;
+mod metrics;
+mod nats;
 mod netinfo;
+mod oslog;
+mod picker;
+mod pipe;
+mod plugin;
+mod prefix;
+mod qr;
+mod rdns;
+mod redis;
+mod replay;
+mod rewrite;
+mod sample;
+mod send;
+mod session;
+mod shutdown;
 mod source;
+mod source_ref;
+mod split;
+mod stacktrace;
 mod state;
+mod table;
+mod time_display;
+mod timestamp;
+mod tls;
 mod tui;
+mod watch;
 
 use anyhow::Result;
-use app::App;
-use clap::Parser;
-use constants::POLL_INTERVAL_MS;
+use app::{App, Suspend};
+use capture::Capture;
+use clap::builder::TypedValueParser;
+use clap::error::ErrorKind;
+use clap::{Parser, Subcommand};
+use constants::{MAX_PASTE_CHARS, POLL_INTERVAL_MS};
 use core::InputMode;
+use entity::EntityKind;
+use keymap::Action;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton,
         MouseEventKind,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use fancy_regex::Regex;
+use macros::{parse_keys, MacroPrompt, MAX_MACRO_DEPTH};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use source::{start_source, LogSource, SourceEvent};
+use picker::{Choice, Picker};
+use replay::Replay;
+use cloudwatch::CloudWatchTarget;
+use elastic::EsQuery;
+use loki::LokiQuery;
+use nats::NatsUrl;
+use redis::RedisUrl;
+use source::{start_source, url_without_userinfo, CommandSource, FileOptions, ListenOptions, ListenPort, LogSource, Program, ReadFrom, Since};
+use shutdown::Summary;
 use state::AppState;
-use std::io;
+use split::Split;
+use table::Format;
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
 #[command(name = "logviewer")]
 #[command(about = "Interactive log viewer with filtering and highlighting")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(help = "Log file to view (reads from stdin if not provided)")]
     file: Option<PathBuf>,
 
     #[arg(
         short = 'l',
         long = "listen",
-        help = "Listen on TCP port for incoming logs"
+        visible_alias = "port",
+        value_name = "PORT[=NAME]",
+        help = "Listen on TCP port for incoming logs; repeat to listen on several, naming each to tag its lines"
+    )]
+    port: Vec<ListenPort>,
+
+    #[arg(
+        long = "advertise",
+        requires = "port",
+        help = "Advertise the listen port on the local network over mDNS"
+    )]
+    advertise: bool,
+
+    #[arg(
+        long = "token",
+        requires = "port",
+        help = "Only accept clients that send this token first (see `send --token`)"
+    )]
+    token: Option<String>,
+
+    #[arg(
+        long = "tls-cert",
+        requires_all = ["port", "tls_key"],
+        help = "Serve the listen port over TLS with this PEM certificate chain"
+    )]
+    tls_cert: Option<PathBuf>,
+
+    #[arg(long = "tls-key", requires = "tls_cert", help = "PEM private key for --tls-cert")]
+    tls_key: Option<PathBuf>,
+
+    #[arg(
+        long = "capture",
+        value_name = "FILE",
+        requires = "port",
+        help = "Record every chunk clients send, with its receive time and size, to FILE (play it back with --replay)"
+    )]
+    capture: Option<PathBuf>,
+
+    #[arg(
+        long = "metrics",
+        value_name = "ADDR",
+        help = "Serve Prometheus metrics about the viewer at http://ADDR/metrics, e.g. 127.0.0.1:9900"
+    )]
+    metrics: Option<SocketAddr>,
+
+    #[arg(
+        long = "plugin",
+        value_name = "COMMAND",
+        help = "Run a plugin speaking JSON-RPC over stdin/stdout as a line source, parser or exporter; repeatable"
+    )]
+    plugin: Vec<String>,
+
+    #[arg(
+        long = "replay",
+        requires = "file",
+        help = "Replay FILE at the pace of its timestamps, or a --capture file at the pace it was received, instead of loading it at once"
+    )]
+    replay: bool,
+
+    #[arg(
+        long = "resume-crash",
+        conflicts_with = "replay",
+        help = "Reopen the buffer, settings and position a crashed TUI saved, then keep reading any FILE or port given"
+    )]
+    resume_crash: bool,
+
+    #[arg(
+        long = "rotated",
+        requires = "file",
+        conflicts_with = "replay",
+        help = "First read FILE's rotated predecessors (FILE.1, FILE.2.gz, ...), oldest first"
+    )]
+    rotated: bool,
+
+    #[arg(
+        long = "from-start",
+        conflicts_with = "from_end",
+        help = "Read files from the start instead of where the last run left off"
+    )]
+    from_start: bool,
+
+    #[arg(long = "from-end", help = "Only show what is written to files from now on")]
+    from_end: bool,
+
+    #[arg(
+        long = "docker",
+        value_name = "CONTAINER",
+        group = "command",
+        conflicts_with_all = ["file", "port"],
+        help = "Follow a Docker container's logs (docker logs -f)"
+    )]
+    docker: Option<String>,
+
+    #[arg(
+        long = "adb",
+        value_name = "SERIAL",
+        group = "command",
+        num_args = 0..=1,
+        conflicts_with_all = ["file", "port", "docker"],
+        help = "Follow an Android device's log (adb logcat), waiting for it to reconnect"
+    )]
+    adb: Option<Option<String>>,
+
+    #[arg(
+        long = "oslog",
+        value_name = "PREDICATE",
+        group = "command",
+        num_args = 0..=1,
+        conflicts_with_all = ["file", "port", "docker", "adb"],
+        help = "Follow the macOS unified log (log stream), optionally only events matching PREDICATE"
+    )]
+    oslog: Option<Option<String>>,
+
+    #[arg(
+        long = "redis",
+        value_name = "URL",
+        group = "command",
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog"],
+        value_parser = UrlParser::<RedisUrl>::new(),
+        help = "Follow a Redis stream or pub/sub channel: redis://[:PASSWORD@]HOST[:PORT][/DB]/KEY, reconnecting where it left off"
+    )]
+    redis: Option<RedisUrl>,
+
+    #[arg(
+        long = "nats",
+        value_name = "URL",
+        group = "command",
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog", "redis"],
+        value_parser = UrlParser::<NatsUrl>::new(),
+        help = "Follow messages published to a NATS server: nats://[USER:PASSWORD@|TOKEN@]HOST[:PORT], reconnecting when it drops"
+    )]
+    nats: Option<NatsUrl>,
+
+    #[arg(
+        long = "subject",
+        value_name = "SUBJECT",
+        requires = "nats",
+        default_value = ">",
+        help = "With --nats, the subject to subscribe to; * matches one token and > the rest, e.g. logs.>"
+    )]
+    subject: String,
+
+    #[arg(
+        long = "cloudwatch",
+        value_name = "GROUP[:STREAM_PREFIX]",
+        group = "command",
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog", "redis", "nats"],
+        help = "Tail a CloudWatch Logs group, optionally only streams starting with STREAM_PREFIX, by polling `aws logs filter-log-events`; requires the AWS CLI, installed and configured"
+    )]
+    cloudwatch: Option<CloudWatchTarget>,
+
+    #[arg(long = "aws-profile", value_name = "PROFILE", requires = "cloudwatch", help = "With --cloudwatch, the AWS profile to use")]
+    aws_profile: Option<String>,
+
+    #[arg(long = "aws-region", value_name = "REGION", requires = "cloudwatch", help = "With --cloudwatch, the AWS region to use")]
+    aws_region: Option<String>,
+
+    #[arg(
+        long = "loki",
+        value_name = "URL",
+        group = "command",
+        requires = "query",
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog", "redis", "nats", "cloudwatch"],
+        help = "Tail what a --query finds on a Grafana Loki server, e.g. http://loki:3100 (user:password@ for basic auth)"
+    )]
+    loki: Option<String>,
+
+    #[arg(long = "query", value_name = "LOGQL", requires = "loki", help = "With --loki, the LogQL query to run, e.g. '{app=\"api\"} |= \"error\"'")]
+    query: Option<String>,
+
+    #[arg(
+        long = "elasticsearch",
+        visible_alias = "opensearch",
+        value_name = "URL/INDEX",
+        group = "command",
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog", "redis", "nats", "cloudwatch", "loki"],
+        value_parser = UrlParser::<EsQuery>::new(),
+        help = "Read what an Elasticsearch or OpenSearch index holds, e.g. http://es:9200/logs-* (user:password@ for basic auth)"
+    )]
+    elasticsearch: Option<EsQuery>,
+
+    #[arg(
+        long = "es-query",
+        value_name = "QUERY",
+        requires = "elasticsearch",
+        help = "With --elasticsearch, only documents matching this query_string query, e.g. 'level:error AND service:api'"
+    )]
+    es_query: Option<String>,
+
+    #[arg(
+        long = "es-time-field",
+        value_name = "FIELD",
+        requires = "elasticsearch",
+        default_value = "@timestamp",
+        help = "With --elasticsearch, the date field documents are ordered and ranged by"
+    )]
+    es_time_field: String,
+
+    #[arg(long = "es-follow", requires = "elasticsearch", help = "With --elasticsearch, keep polling for new documents")]
+    es_follow: bool,
+
+    #[arg(
+        long = "since",
+        value_name = "DURATION",
+        requires = "command",
+        help = "With --docker, --adb, --oslog, --cloudwatch, --loki, --elasticsearch or a --redis stream, first read what was logged in the last DURATION (90s, 30m, 1h, 2d), then follow live"
+    )]
+    since: Option<Since>,
+
+    #[arg(
+        long = "watch-dir",
+        value_names = ["DIR", "GLOB"],
+        num_args = 1..=2,
+        conflicts_with_all = ["file", "port", "docker", "adb", "oslog", "redis", "nats", "cloudwatch", "loki", "elasticsearch"],
+        help = "Tail every file in DIR matching GLOB (default *), including new ones"
     )]
-    port: Option<u16>,
+    watch_dir: Vec<String>,
+
+    #[arg(
+        long = "format",
+        value_name = "FORMAT",
+        default_value = "auto",
+        help = "How to read lines: csv or tsv (first line is the header), nginx/combined/common access logs, logcat, oslog (log stream ndjson), text, or auto to detect them"
+    )]
+    format: Format,
+
+    #[arg(
+        long = "sample",
+        value_name = "RATE",
+        default_value = "off",
+        help = "Keep only some lines of firehose sources: 1/N keeps every Nth, N/s a random N per second; what is dropped is counted"
+    )]
+    sample: sample::Sampling,
+
+    #[arg(
+        long = "split",
+        value_name = "HOW",
+        default_value = "newline",
+        help = "How to cut input into records: newline, nul (find -print0), delim:STRING, fixed:BYTES, or json (one record per JSON value, even pretty-printed)"
+    )]
+    split: Split,
 
     #[cfg(feature = "gui")]
     #[arg(long = "tui", help = "Use TUI instead of GUI")]
     tui: bool,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Send a file or stdin to a viewer listening with -l
+    Send(send::SendArgs),
+    /// Time ingestion, filtering and highlighting of a file with the saved settings
+    Bench(bench::BenchArgs),
+}
+
+/// Parses a service URL argument, quoting it in errors without its
+/// `user:password@`: clap's own error would echo the password.
+#[derive(Clone)]
+struct UrlParser<T>(PhantomData<T>);
+
+impl<T> UrlParser<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> TypedValueParser for UrlParser<T>
+where
+    T: FromStr<Err = anyhow::Error> + Clone + Send + Sync + 'static,
+{
+    type Value = T;
+
+    fn parse_ref(&self, cmd: &clap::Command, arg: Option<&clap::Arg>, value: &OsStr) -> Result<T, clap::Error> {
+        let value = value.to_string_lossy();
+        value.parse().map_err(|e: anyhow::Error| {
+            let arg = arg.map_or_else(|| "...".to_string(), ToString::to_string);
+            let message = format!("invalid value '{}' for '{}': {}\n", url_without_userinfo(&value), arg, e);
+            clap::Error::raw(ErrorKind::ValueValidation, message).with_cmd(cmd)
+        })
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    match cli.command {
+        Some(Command::Send(args)) => return send::run(args),
+        Some(Command::Bench(args)) => return bench::run(args),
+        None => {}
+    }
+
+    let _advertisements: Vec<_> = cli
+        .port
+        .iter()
+        .filter(|_| cli.advertise)
+        .filter_map(|listen| {
+            discovery::advertise(listen.port)
+                .inspect_err(|e| eprintln!("mDNS advertisement failed: {}", e))
+                .ok()
+        })
+        .collect();
+
+    let listen = listen_options(&cli)?;
+    if let Some(addr) = cli.metrics {
+        metrics::serve(addr)?;
+    }
+    plugin::load(&cli.plugin)?;
+    table::set_format(cli.format);
+    split::set_split(cli.split.clone());
+    sample::set_sampling(cli.sample);
+
     #[cfg(feature = "gui")]
-    if !cli.tui {
-        return gui::run_with_args(cli.file, cli.port);
+    if !cli.tui && !cli.resume_crash {
+        let file_options = file_options(&cli);
+        let watch_dir = watch_dir(&cli);
+        let command = command_source(&cli);
+        return gui::run_with_args(cli.file, file_options, cli.port, listen, cli.replay, command, watch_dir);
     }
 
-    run_tui(cli)
+    run_tui(cli, listen)
+}
+
+/// `--docker`, `--adb`, `--oslog`, `--redis`, `--nats`, `--cloudwatch`,
+/// `--loki` or `--elasticsearch`, whichever was given.
+fn command_source(cli: &Cli) -> Option<CommandSource> {
+    let program = if let Some(container) = &cli.docker {
+        Program::Docker(container.clone())
+    } else if let Some(serial) = &cli.adb {
+        Program::Adb(serial.clone())
+    } else if let Some(predicate) = &cli.oslog {
+        Program::OsLog(predicate.clone())
+    } else if let Some(url) = &cli.redis {
+        Program::Redis(url.clone())
+    } else if let Some(url) = &cli.nats {
+        Program::Nats(url.clone(), cli.subject.clone())
+    } else if let Some(target) = &cli.cloudwatch {
+        Program::CloudWatch(CloudWatchTarget {
+            profile: cli.aws_profile.clone(),
+            region: cli.aws_region.clone(),
+            ..target.clone()
+        })
+    } else if let (Some(url), Some(query)) = (&cli.loki, &cli.query) {
+        Program::Loki(LokiQuery {
+            url: url.clone(),
+            query: query.clone(),
+        })
+    } else if let Some(query) = &cli.elasticsearch {
+        Program::Elastic(EsQuery {
+            query: cli.es_query.clone().unwrap_or_else(|| query.query.clone()),
+            time_field: cli.es_time_field.clone(),
+            follow: cli.es_follow,
+            ..query.clone()
+        })
+    } else {
+        return None;
+    };
+    Some(CommandSource { program, since: cli.since })
+}
+
+/// `--watch-dir DIR [GLOB]` as the directory and the glob.
+fn watch_dir(cli: &Cli) -> Option<(PathBuf, String)> {
+    let dir = cli.watch_dir.first()?;
+    let pattern = cli.watch_dir.get(1).map_or("*", String::as_str);
+    Some((PathBuf::from(dir), pattern.to_string()))
+}
+
+fn file_options(cli: &Cli) -> FileOptions {
+    let from = if cli.from_start {
+        ReadFrom::Start
+    } else if cli.from_end {
+        ReadFrom::End
+    } else {
+        ReadFrom::Checkpoint
+    };
+    FileOptions {
+        rotated: cli.rotated,
+        from,
+    }
+}
+
+fn listen_options(cli: &Cli) -> Result<ListenOptions> {
+    let tls = match (&cli.tls_cert, &cli.tls_key) {
+        (Some(cert), Some(key)) => Some(tls::server_config(cert, key)?),
+        _ => None,
+    };
+    let capture = cli.capture.as_deref().map(Capture::create).transpose()?;
+    Ok(ListenOptions {
+        token: cli.token.clone(),
+        tls,
+        capture: capture.map(Arc::new),
+    })
 }
 
-fn run_tui(cli: Cli) -> Result<()> {
-    let (tx, rx) = mpsc::channel::<SourceEvent>();
+fn run_tui(mut cli: Cli, listen: ListenOptions) -> Result<()> {
+    let nothing_to_read = cli.file.is_none() && cli.port.is_empty() && command_source(&cli).is_none() && cli.watch_dir.is_empty();
+    if nothing_to_read && !cli.resume_crash && !plugin::has_source() && io::stdin().is_terminal() {
+        match pick_source()? {
+            Choice::File(path) => cli.file = Some(path),
+            Choice::Replay(path) => {
+                cli.file = Some(path);
+                cli.replay = true;
+            }
+            Choice::Listen(port) => cli.port = vec![ListenPort { port, name: None }],
+            Choice::Docker(container) => cli.docker = Some(container),
+            Choice::Quit => return Ok(()),
+        }
+    }
+
+    let (tx, rx) = source::channel();
+
+    if let Some(path) = cli.file.as_deref().filter(|p| bundle::is_bundle(p) && !cli.replay) {
+        let bundle = bundle::Bundle::read(path)?;
+        drop(tx);
+        return with_terminal(|terminal| run_app(terminal, App::from_bundle(rx, bundle)));
+    }
 
-    let source = if let Some(port) = cli.port {
-        eprintln!("Listening on port {}...", port);
-        LogSource::Network(port)
-    } else if let Some(path) = cli.file {
-        LogSource::File(path)
+    let crashed = if cli.resume_crash { Some(crash::take()?) } else { None };
+    let tx = plugin::attach(tx);
+    let ports: Vec<u16> = cli.port.iter().map(|listen| listen.port).collect();
+    let source = if !cli.port.is_empty() {
+        let list: Vec<String> = ports.iter().map(u16::to_string).collect();
+        eprintln!("Listening on port {}...", list.join(", "));
+        LogSource::Network(cli.port, listen)
+    } else if let Some(command) = command_source(&cli) {
+        LogSource::Command(command)
+    } else if let Some((dir, pattern)) = watch_dir(&cli) {
+        LogSource::WatchDir(dir, pattern, file_options(&cli))
+    } else if let Some(path) = cli.file.clone() {
+        LogSource::File(path, file_options(&cli))
     } else {
         LogSource::Stdin
     };
@@ -89,44 +565,101 @@ fn run_tui(cli: Cli) -> Result<()> {
         }
     };
 
-    start_source(source, tx, line_start_regex)?;
+    if let (true, LogSource::File(path, _)) = (cli.replay, &source) {
+        let replay = replay::start_replay(path, tx, line_start_regex)?;
+        let mut app = App::new(rx, Vec::new());
+        app.replay = Some(replay);
+        app.keymap.set_replaying(true);
+        return with_terminal(|terminal| run_app(terminal, app));
+    }
+
+    let source_tx = tx.clone();
+    if !matches!(source, LogSource::Stdin) || !(crashed.is_some() || plugin::has_source()) {
+        start_source(source, tx, line_start_regex)?;
+    }
+
+    let mut app = match crashed {
+        Some(bundle) => App::resume(rx, ports, bundle),
+        None => App::new(rx, ports),
+    };
+    app.source_tx = Some(source_tx);
+    with_terminal(|terminal| run_app(terminal, app))
+}
+
+/// Ask where logs should come from, on the startup screen.
+fn pick_source() -> Result<Choice> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let result = (|| {
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        let mut picker = Picker::new();
+        loop {
+            terminal.draw(|f| tui::picker::draw(f, &picker))?;
+            if let Event::Key(key) = event::read()? {
+                if let Some(choice) = picker.handle_key(key) {
+                    return Ok(choice);
+                }
+            }
+        }
+    })();
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    result
+}
 
+fn with_terminal(
+    run: impl FnOnce(&mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<Summary>,
+) -> Result<()> {
+    crash::install_hook();
+    shutdown::install_signal_handlers();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal, rx, cli.port);
+    let result = run(&mut terminal);
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
-    if let Err(e) = result {
-        eprintln!("Error: {}", e);
+    match result {
+        Ok(summary) => eprintln!("{}", summary),
+        Err(e) => eprintln!("Error: {}", e),
     }
 
     Ok(())
 }
 
-fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    rx: mpsc::Receiver<SourceEvent>,
-    listen_port: Option<u16>,
-) -> Result<()> {
-    let mut app = App::new(rx, listen_port);
+/// Run the TUI, saving a crash session if it panics.
+fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: App) -> Result<Summary> {
+    match panic::catch_unwind(AssertUnwindSafe(|| event_loop(terminal, &mut app))) {
+        Ok(result) => result,
+        Err(_) => Err(crash::save(&app.crash_bundle())),
+    }
+}
 
+fn event_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<Summary> {
     loop {
+        if app.quit || shutdown::requested() {
+            return Ok(app.shutdown());
+        }
         app.poll_source();
 
-        let visible_height = terminal.size()?.height.saturating_sub(9) as usize;
+        let visible_height = terminal.size()?.height.saturating_sub(12) as usize;
 
-        terminal.draw(|f| tui::draw(f, &mut app))?;
+        terminal.draw(|f| tui::draw(f, app))?;
+
+        app.toasts.expire(Instant::now());
+        if let Some((action, count)) = app.keymap.expire(Instant::now()) {
+            run_action(app, action, count)?;
+        }
 
         if event::poll(Duration::from_millis(POLL_INTERVAL_MS))? {
             let ev = event::read()?;
@@ -136,43 +669,251 @@ fn run_app(
                     && app.listen_state.show_popup()
                 {
                     if let Some(text) = app.listen_state.handle_click(mouse.column, mouse.row) {
-                        copy_to_clipboard(&text);
-                        app.status_message = Some(format!("Copied: {}", text));
+                        copy_to_clipboard(app, &text);
+                    }
+                } else if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                    && app.input_mode == InputMode::Normal
+                {
+                    if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                        app.filter_by_click(mouse.column, mouse.row);
+                    } else {
+                        app.minimap_click(mouse.column, mouse.row);
+                        app.cursor_column_click(mouse.column, mouse.row);
                     }
                 }
             }
 
-            if let Event::Key(key) = ev {
-                app.status_message = None;
+            if let Event::Paste(text) = &ev {
+                handle_paste(app, text);
+            }
 
-                if app.show_quit_confirm {
-                    handle_quit_confirm(&mut app, key.code)?;
-                    continue;
+            if let Event::Key(key) = ev {
+                if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.suspend = Some(Suspend::Shell);
+                } else {
+                    handle_key(app, key, visible_height, 0)?;
                 }
+            }
+        }
+
+        if let Some(how) = app.suspend.take() {
+            suspend(terminal, app, how)?;
+        }
+    }
+}
 
-                if app.listen_state.show_popup() {
-                    handle_listen_popup(&mut app, key.code, key.modifiers);
-                    continue;
+/// Give the terminal back for Ctrl-Z, `:sh` or the editor, then take it
+/// over again.
+fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App, how: Suspend) -> Result<()> {
+    app.poll_source();
+    let before = app.log_state.lines.len();
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste)?;
+    terminal.show_cursor()?;
+
+    let result = match how {
+        Suspend::Edit(command) => run_editor(app, &command),
+        Suspend::Shell => run_shell(app),
+    };
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    terminal.clear()?;
+    app.poll_source();
+    match result {
+        Ok(()) => app.toasts.info(format!(
+            "Resumed; {} lines arrived meanwhile",
+            app.log_state.lines.len().saturating_sub(before)
+        )),
+        Err(e) => app.toasts.error(format!("Suspend failed: {:#}", e)),
+    }
+    Ok(())
+}
+
+/// Wait for `command`, run on the terminal, while ingesting what the sources
+/// send meanwhile.
+fn run_on_terminal(app: &mut App, mut command: std::process::Command) -> std::io::Result<std::process::ExitStatus> {
+    // Logs may be piped into our stdin; the command reads from the terminal.
+    #[cfg(unix)]
+    if let Ok(tty) = std::fs::File::open("/dev/tty") {
+        command.stdin(tty);
+    }
+    let mut child = command.spawn()?;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        app.poll_source();
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Run `$SHELL` (`%COMSPEC%` on Windows) until the user exits it.
+fn run_shell(app: &mut App) -> Result<()> {
+    #[cfg(windows)]
+    let shell = std::env::var("COMSPEC").unwrap_or_else(|_| "cmd".to_string());
+    #[cfg(not(windows))]
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    println!("logviewer keeps reading in the background; type `exit` to return.");
+    run_on_terminal(app, std::process::Command::new(&shell))
+        .map_err(|e| anyhow::anyhow!("cannot run {}: {}", shell, e))?;
+    Ok(())
+}
+
+/// Run an editor command through the shell, on the terminal.
+fn run_editor(app: &mut App, command: &str) -> Result<()> {
+    let status = run_on_terminal(app, plugin::shell(command))
+        .map_err(|e| anyhow::anyhow!("cannot run {}: {}", command, e))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", command, status);
+    }
+    Ok(())
+}
+
+/// Dispatch a key press, recording it into the current macro. `depth` is how
+/// many macros deep the key comes from.
+fn handle_key(app: &mut App, key: KeyEvent, visible_height: usize, depth: usize) -> Result<()> {
+    if let Some(prompt) = app.macro_prompt.take() {
+        let KeyCode::Char(register) = key.code else {
+            return Ok(());
+        };
+        match prompt {
+            MacroPrompt::Record => app.start_recording(register),
+            MacroPrompt::Play => {
+                if depth == 0 {
+                    app.record_key(key);
                 }
+                play_macro(app, register, visible_height, depth)?;
+            }
+        }
+        return Ok(());
+    }
 
-                match app.input_mode {
-                    InputMode::Normal => {
-                        handle_normal_mode(&mut app, key.code, key.modifiers, visible_height)?
-                    }
-                    _ => {
-                        if app.handle_input_key(key.code) {
-                            app.apply_current_input();
-                        }
-                    }
+    if app.show_quit_confirm {
+        handle_quit_confirm(app, key.code)?;
+    } else if let Some(text) = app.paste_prompt.take() {
+        handle_paste_prompt(app, &text, key.code, visible_height)?;
+    } else if app.listen_state.show_popup() {
+        handle_listen_popup(app, key.code, key.modifiers);
+    } else if app.column_editor.is_some() {
+        handle_column_editor(app, key.code);
+    } else if app.filter_stats_popup.is_some() {
+        handle_filter_stats(app, key.code);
+    } else if app.messages_popup.is_some() {
+        handle_messages(app, key.code, visible_height);
+    } else if app.stats_popup.is_some() {
+        handle_stats(app, key.code, visible_height);
+    } else if app.pipe_popup.is_some() {
+        handle_pipe_popup(app, key.code, visible_height);
+    } else if app.snooze_popup.is_some() {
+        handle_snoozes(app, key.code);
+    } else if app.top_popup.is_some() {
+        handle_top_values(app, key.code, visible_height);
+    } else if app.fields_popup.is_some() {
+        handle_fields(app, key.code, visible_height);
+    } else if app.why.is_some() {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') | KeyCode::Enter) {
+            app.why = None;
+        }
+    } else if app.outline_panel.is_some() {
+        handle_outline(app, key.code, visible_height);
+    } else if app.file_picker.is_some() {
+        app.handle_file_picker(key);
+    } else if app.show_plugins {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+            app.show_plugins = false;
+        }
+    } else if app.note_editor.is_some() {
+        handle_note_editor(app, key.code);
+    } else if app.playground.is_some() {
+        handle_playground(app, key.code, key.modifiers);
+    } else if app.entity_menu.is_some() {
+        handle_entity_menu(app, key.code);
+    } else if app.detail.is_some() {
+        handle_detail_popup(app, key.code, visible_height);
+    } else {
+        match app.input_mode {
+            InputMode::Normal => {
+                for (action, count) in app.keymap.press(key) {
+                    run_action(app, action, count)?;
+                }
+            }
+            _ => {
+                if app.handle_input_key(key.code) {
+                    app.apply_current_input();
                 }
             }
         }
     }
+    if depth == 0 {
+        app.record_key(key);
+    }
+    Ok(())
+}
+
+fn play_macro(app: &mut App, register: char, visible_height: usize, depth: usize) -> Result<()> {
+    if depth >= MAX_MACRO_DEPTH {
+        app.toasts.error("Macros nested too deep");
+        return Ok(());
+    }
+    let Some(text) = app.macros.get(&register) else {
+        app.toasts.warn(format!("No macro in @{}", register));
+        return Ok(());
+    };
+    let keys = match parse_keys(text) {
+        Ok(keys) => keys,
+        Err(e) => {
+            app.toasts.error(format!("Macro @{}: {}", register, e));
+            return Ok(());
+        }
+    };
+    for key in keys {
+        handle_key(app, key, visible_height, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// A bracketed paste: into the field being edited all at once, or, in
+/// normal mode, held until the user says whether it is log lines or keys.
+fn handle_paste(app: &mut App, text: &str) {
+    let mut text = text;
+    if text.chars().count() > MAX_PASTE_CHARS && app.input_mode != InputMode::Normal {
+        let end = text.char_indices().nth(MAX_PASTE_CHARS).map_or(text.len(), |(i, _)| i);
+        text = &text[..end];
+        app.toasts.warn(format!("Paste cut to {} characters", MAX_PASTE_CHARS));
+    }
+    if let Some(editor) = app.note_editor.as_mut() {
+        editor.input.insert_paste(text);
+    } else if let Some(playground) = app.playground.as_mut() {
+        playground.active_input().insert_paste(text);
+    } else if let Some(input) = app.input_fields.get_active_mut(app.input_mode) {
+        input.insert_paste(text);
+    } else if app.input_mode == InputMode::Normal && !app.show_quit_confirm && !text.is_empty() {
+        app.paste_prompt = Some(text.to_string());
+    }
+}
+
+fn handle_paste_prompt(app: &mut App, text: &str, key_code: KeyCode, visible_height: usize) -> Result<()> {
+    match key_code {
+        KeyCode::Char('l') | KeyCode::Enter => app.ingest_paste(text),
+        KeyCode::Char('k') => {
+            for c in text.chars() {
+                let code = match c {
+                    '\n' | '\r' => KeyCode::Enter,
+                    '\t' => KeyCode::Tab,
+                    _ => KeyCode::Char(c),
+                };
+                handle_key(app, KeyEvent::from(code), visible_height, 0)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 fn handle_quit_confirm(app: &mut App, key_code: KeyCode) -> Result<()> {
     match key_code {
-        KeyCode::Char('y') | KeyCode::Char('Y') => std::process::exit(0),
+        KeyCode::Char('y') | KeyCode::Char('Y') => app.quit = true,
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
             app.show_quit_confirm = false;
         }
@@ -188,95 +929,389 @@ fn handle_listen_popup(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers
             app.show_quit_confirm = true
         }
         KeyCode::Tab => app.listen_state.toggle_display_mode(),
+        KeyCode::Char('p') => app.listen_state.next_port(),
+        KeyCode::Char('a') => app.listen_state.toggle_show_all(),
         KeyCode::Up | KeyCode::Char('k') => app.listen_state.select_prev(),
         KeyCode::Down | KeyCode::Char('j') => app.listen_state.select_next(),
         KeyCode::Enter => {
             if let Some(text) = app.listen_state.get_selected_copy_text() {
-                copy_to_clipboard(&text);
-                app.status_message = Some(format!("Copied: {}", text));
+                copy_to_clipboard(app, &text);
             }
         }
         _ => {}
     }
 }
 
-fn handle_normal_mode(
-    app: &mut App,
-    key_code: KeyCode,
-    modifiers: KeyModifiers,
-    visible_height: usize,
-) -> Result<()> {
+fn handle_column_editor(app: &mut App, key_code: KeyCode) {
     match key_code {
-        KeyCode::Char('q') => app.show_quit_confirm = true,
-        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
-            app.show_quit_confirm = true
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('L') => app.toggle_column_editor(),
+        KeyCode::Up | KeyCode::Char('k') => app.select_column(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_column(1),
+        KeyCode::Char('K') => app.move_column(-1),
+        KeyCode::Char('J') => app.move_column(1),
+        KeyCode::Char(' ') => app.toggle_column(),
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Right => app.resize_column(1),
+        KeyCode::Char('-') | KeyCode::Left => app.resize_column(-1),
+        _ => {}
+    }
+}
+
+fn handle_filter_stats(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('S') => app.toggle_filter_stats(),
+        KeyCode::Up | KeyCode::Char('k') => app.select_filter_stat(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_filter_stat(1),
+        KeyCode::Enter => app.apply_filter_stat(),
+        KeyCode::Char('x') | KeyCode::Delete => app.drop_selected_filter(),
+        _ => {}
+    }
+}
+
+fn handle_outline(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('O') => app.outline_panel = None,
+        KeyCode::Up | KeyCode::Char('k') => app.select_bucket(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_bucket(1),
+        KeyCode::PageUp => app.select_bucket(-(visible_height as isize)),
+        KeyCode::PageDown => app.select_bucket(visible_height as isize),
+        KeyCode::Home | KeyCode::Char('g') => app.select_bucket(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.select_bucket(isize::MAX),
+        KeyCode::Tab => app.cycle_bucket_size(),
+        KeyCode::Enter => app.jump_to_bucket(visible_height),
+        _ => {}
+    }
+}
+
+fn handle_messages(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.messages_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_messages(1),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_messages(-1),
+        KeyCode::PageUp => app.scroll_messages(visible_height as isize),
+        KeyCode::PageDown => app.scroll_messages(-(visible_height as isize)),
+        KeyCode::Home | KeyCode::Char('g') => app.scroll_messages(isize::MAX),
+        KeyCode::End | KeyCode::Char('G') => app.messages_popup = Some(0),
+        KeyCode::Char('c') => {
+            app.toasts.messages.clear();
+            app.messages_popup = Some(0);
         }
-        KeyCode::Char('d') => app.input_mode = InputMode::HideEdit,
-        KeyCode::Char('f') => app.input_mode = InputMode::FilterEdit,
-        KeyCode::Char('h') => app.input_mode = InputMode::HighlightEdit,
-        KeyCode::Char('s') => app.input_mode = InputMode::LineStartEdit,
-        KeyCode::Char('c') => app.clear(),
-        KeyCode::Char('t') => app.toggle_time(),
-        KeyCode::Char('w') => app.toggle_wrap(),
-        KeyCode::Char('g') => app.log_state.scroll_to_start(),
-        KeyCode::Char('G') => app.log_state.scroll_to_end(),
-        KeyCode::Up | KeyCode::Char('k') => app.log_state.scroll_up(1),
-        KeyCode::Down | KeyCode::Char('j') => app.log_state.scroll_down(1),
-        KeyCode::PageUp => app.log_state.scroll_up(visible_height),
-        KeyCode::PageDown => app.log_state.scroll_down(visible_height),
-        KeyCode::Home => app.log_state.scroll_to_start(),
-        KeyCode::End => app.log_state.scroll_to_end(),
         _ => {}
     }
-    Ok(())
 }
 
-fn copy_to_clipboard(text: &str) {
-    #[cfg(target_os = "macos")]
-    {
-        use std::io::Write;
-        use std::process::{Command, Stdio};
-        if let Ok(mut child) = Command::new("pbcopy").stdin(Stdio::piped()).spawn() {
-            if let Some(stdin) = child.stdin.as_mut() {
-                let _ = stdin.write_all(text.as_bytes());
+fn handle_pipe_popup(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.pipe_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_pipe(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_pipe(1),
+        KeyCode::PageUp => app.scroll_pipe(-(visible_height as isize)),
+        KeyCode::PageDown => app.scroll_pipe(visible_height as isize),
+        KeyCode::Home | KeyCode::Char('g') => app.scroll_pipe(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.scroll_pipe(isize::MAX),
+        KeyCode::Char('y') => {
+            if let Some((output, _)) = &app.pipe_popup {
+                let text = output.lines.join("\n");
+                match clipboard::copy(&text, true) {
+                    Ok(method) => app.toasts.info(format!("Copied {} lines via {}", output.lines.len(), method)),
+                    Err(e) => app.toasts.error(format!("Copy failed: {:#}", e)),
+                }
             }
-            let _ = child.wait();
         }
+        _ => {}
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::io::Write;
-        use std::process::{Command, Stdio};
-        let cmds = ["xclip", "xsel"];
-        for cmd in cmds {
-            if let Ok(mut child) = Command::new(cmd)
-                .args(if cmd == "xclip" {
-                    &["-selection", "clipboard"][..]
-                } else {
-                    &["--clipboard", "--input"][..]
-                })
-                .stdin(Stdio::piped())
-                .spawn()
-            {
-                if let Some(stdin) = child.stdin.as_mut() {
-                    let _ = stdin.write_all(text.as_bytes());
+fn handle_stats(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.stats_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_stats(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_stats(1),
+        KeyCode::PageUp => app.scroll_stats(-(visible_height as isize)),
+        KeyCode::PageDown => app.scroll_stats(visible_height as isize),
+        KeyCode::Home | KeyCode::Char('g') => app.stats_scroll = 0,
+        KeyCode::End | KeyCode::Char('G') => app.scroll_stats(isize::MAX),
+        _ => {}
+    }
+}
+
+fn handle_top_values(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.top_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.select_top_value(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_top_value(1),
+        KeyCode::PageUp => app.select_top_value(-(visible_height as isize)),
+        KeyCode::PageDown => app.select_top_value(visible_height as isize),
+        KeyCode::Home | KeyCode::Char('g') => app.select_top_value(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.select_top_value(isize::MAX),
+        KeyCode::Enter | KeyCode::Char('f') => app.filter_by_top_value(),
+        _ => {}
+    }
+}
+
+fn handle_fields(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.fields_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.select_field(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_field(1),
+        KeyCode::PageUp => app.select_field(-(visible_height as isize)),
+        KeyCode::PageDown => app.select_field(visible_height as isize),
+        KeyCode::Home | KeyCode::Char('g') => app.select_field(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.select_field(isize::MAX),
+        KeyCode::Char('c') => app.toggle_field_column(),
+        KeyCode::Enter | KeyCode::Char('f') => app.insert_field_in_filter(),
+        _ => {}
+    }
+}
+
+fn handle_snoozes(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.snooze_popup = None,
+        KeyCode::Up | KeyCode::Char('k') => app.select_snooze(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.select_snooze(1),
+        KeyCode::Delete | KeyCode::Char('d') => app.unsnooze_selected(),
+        _ => {}
+    }
+}
+
+fn handle_entity_menu(app: &mut App, key_code: KeyCode) {
+    let Some(menu) = app.entity_menu.as_mut() else {
+        return;
+    };
+    let entity = menu.selected().clone();
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.entity_menu = None,
+        KeyCode::Up | KeyCode::Char('k') => menu.select_prev(),
+        KeyCode::Down | KeyCode::Char('j') => menu.select_next(),
+        KeyCode::Enter | KeyCode::Char('c') => {
+            copy_to_clipboard(app, &entity.text);
+            app.entity_menu = None;
+        }
+        KeyCode::Char('f') => {
+            app.entity_menu = None;
+            app.filter_by(&entity.text);
+        }
+        KeyCode::Char('o') => {
+            if entity.kind == EntityKind::Url {
+                open_url(&entity.text);
+                app.toasts.info(format!("Opened: {}", entity.text));
+                app.entity_menu = None;
+            } else {
+                app.toasts.warn(format!("Not a URL: {}", entity.text));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_note_editor(app: &mut App, key_code: KeyCode) {
+    let Some(editor) = app.note_editor.as_mut() else {
+        return;
+    };
+    let input = &mut editor.input;
+    match key_code {
+        KeyCode::Enter => app.save_note(),
+        KeyCode::Esc => app.note_editor = None,
+        KeyCode::Left => input.move_cursor_left(),
+        KeyCode::Right => input.move_cursor_right(),
+        KeyCode::Home => input.move_cursor_to_start(),
+        KeyCode::End => input.move_cursor_to_end(),
+        KeyCode::Char(c) => input.insert_char(c),
+        KeyCode::Backspace => input.delete_char_before_cursor(),
+        KeyCode::Delete => input.delete_char_at_cursor(),
+        _ => {}
+    }
+}
+
+fn handle_playground(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
+    let Some(playground) = app.playground.as_mut() else {
+        return;
+    };
+    match key_code {
+        KeyCode::Enter => app.apply_playground(),
+        KeyCode::Esc => app.playground = None,
+        KeyCode::Tab => playground.editing_sample = !playground.editing_sample,
+        KeyCode::Up => app.pick_playground_sample(-1),
+        KeyCode::Down => app.pick_playground_sample(1),
+        KeyCode::Char('t') if modifiers.contains(KeyModifiers::CONTROL) => app.switch_playground_target(),
+        KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) => {
+            playground.show_grouping = !playground.show_grouping
+        }
+        KeyCode::Left => playground.active_input().move_cursor_left(),
+        KeyCode::Right => playground.active_input().move_cursor_right(),
+        KeyCode::Home => playground.active_input().move_cursor_to_start(),
+        KeyCode::End => playground.active_input().move_cursor_to_end(),
+        KeyCode::Char(c) => playground.active_input().insert_char(c),
+        KeyCode::Backspace => playground.active_input().delete_char_before_cursor(),
+        KeyCode::Delete => playground.active_input().delete_char_at_cursor(),
+        _ => {}
+    }
+}
+
+fn handle_detail_popup(app: &mut App, key_code: KeyCode, visible_height: usize) {
+    let Some(detail) = app.detail.as_mut() else {
+        return;
+    };
+    if let Some(input) = detail.search.as_mut() {
+        match key_code {
+            KeyCode::Enter => detail.finish_search(true),
+            KeyCode::Esc => detail.finish_search(false),
+            KeyCode::Char(c) => {
+                input.insert_char(c);
+                detail.search_changed();
+            }
+            KeyCode::Backspace => {
+                input.delete_char_before_cursor();
+                detail.search_changed();
+            }
+            KeyCode::Left => input.move_cursor_left(),
+            KeyCode::Right => input.move_cursor_right(),
+            _ => {}
+        }
+        return;
+    }
+    if let Some(tree) = detail.tree.as_mut() {
+        match key_code {
+            KeyCode::Up | KeyCode::Char('k') => return tree.select(-1),
+            KeyCode::Down | KeyCode::Char('j') => return tree.select(1),
+            KeyCode::PageUp => return tree.select(-(visible_height as isize)),
+            KeyCode::PageDown => return tree.select(visible_height as isize),
+            KeyCode::Home | KeyCode::Char('g') => return tree.select(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => return tree.select(isize::MAX),
+            KeyCode::Enter | KeyCode::Char(' ') => return tree.toggle(),
+            KeyCode::Char('y') => {
+                if let Some(path) = tree.selected_path() {
+                    copy_to_clipboard(app, &path);
                 }
-                let _ = child.wait();
-                break;
+                return;
             }
+            _ => {}
         }
     }
+    match key_code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.detail = None,
+        KeyCode::Char('p') => app.toggle_detail_pretty(),
+        KeyCode::Char('/') => detail.start_search(),
+        KeyCode::Char('n') => detail.next_match(1),
+        KeyCode::Char('N') => detail.next_match(-1),
+        KeyCode::Up | KeyCode::Char('k') => detail.scroll_up(1),
+        KeyCode::Down | KeyCode::Char('j') => detail.scroll_down(1),
+        KeyCode::PageUp => detail.scroll_up(visible_height as u16),
+        KeyCode::PageDown => detail.scroll_down(visible_height as u16),
+        KeyCode::Home | KeyCode::Char('g') => detail.scroll = 0,
+        _ => {}
+    }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        use std::io::Write;
-        use std::process::{Command, Stdio};
-        if let Ok(mut child) = Command::new("clip").stdin(Stdio::piped()).spawn() {
-            if let Some(stdin) = child.stdin.as_mut() {
-                let _ = stdin.write_all(text.as_bytes());
+/// Run a normal-mode action; `count` repeats motions, and jumps to that
+/// line with `gg` / `G`.
+fn run_action(app: &mut App, action: Action, count: Option<usize>) -> Result<()> {
+    let times = count.unwrap_or(1);
+    match action {
+        Action::Quit => app.show_quit_confirm = true,
+        Action::EditHide => app.input_mode = InputMode::HideEdit,
+        Action::EditFilter => app.input_mode = InputMode::FilterEdit,
+        Action::EditHighlight => app.input_mode = InputMode::HighlightEdit,
+        Action::EditLineStart => app.input_mode = InputMode::LineStartEdit,
+        Action::EditRewrite => app.input_mode = InputMode::RewriteEdit,
+        Action::ToggleRaw => app.toggle_raw(),
+        Action::ToggleDedup => app.toggle_dedup(),
+        Action::FilterStats => app.toggle_filter_stats(),
+        Action::EditWatches => app.edit_watches(),
+        Action::ToggleWatches => app.toggle_watches(),
+        Action::Outline => app.toggle_outline(),
+        Action::Correlate => app.correlate(),
+        Action::ToggleLanes => app.toggle_lanes(),
+        Action::ToggleEpochs => app.toggle_epochs(),
+        Action::ToggleRuler => app.show_ruler = !app.show_ruler,
+        Action::ColumnLeft => app.cursor_column = app.cursor_column.saturating_sub(times),
+        Action::ColumnRight => app.move_cursor_column(times),
+        Action::SelectLines => app.toggle_selection(false),
+        Action::SelectBlock => app.toggle_selection(true),
+        Action::Yank => yank(app),
+        Action::OpenSource => app.open_source_ref(),
+        Action::FileIssue => {
+            if let Some(url) = app.issue_url() {
+                open_url(&url);
+                app.toasts.info(format!("Opened: {}", url));
             }
-            let _ = child.wait();
         }
+        Action::CopyIssueUrl => {
+            if let Some(url) = app.issue_url() {
+                copy_to_clipboard(app, &url);
+            }
+        }
+        Action::Snooze => app.snooze(""),
+        Action::Fields => app.show_fields(),
+        Action::Why => app.show_why(""),
+        Action::NextLayout => app.next_layout(),
+        Action::ToggleSplit => app.toggle_split(),
+        Action::SplitFocus => app.switch_split_focus(),
+        Action::TogglePin => app.toggle_pin(),
+        Action::ToggleMark => app.toggle_mark(),
+        Action::EditNote => app.edit_note(),
+        Action::ToggleNote => app.toggle_note(),
+        Action::Command => app.input_mode = InputMode::Command,
+        Action::FollowMatch => app.edit_follow_match(),
+        Action::Playground => app.open_playground(),
+        Action::RecordMacro => app.toggle_recording(),
+        Action::PlayMacro => app.macro_prompt = Some(MacroPrompt::Play),
+        Action::ReplayPause => app.with_replay(Replay::toggle_pause),
+        Action::ReplaySpeed => app.with_replay(Replay::cycle_speed),
+        Action::ReplaySkip => app.with_replay(Replay::skip_gap),
+        Action::Clear => app.clear(),
+        Action::ToggleTime => app.toggle_time(),
+        Action::CycleTimeFormat => app.cycle_time_format(),
+        Action::ToggleWrap => app.toggle_wrap(),
+        Action::Top | Action::Bottom if count.is_some() => app.log_state.scroll_to(times.saturating_sub(1)),
+        Action::Top => app.log_state.scroll_to_start(),
+        Action::Bottom => app.log_state.scroll_to_end(),
+        Action::Up => app.log_state.scroll_up(times),
+        Action::Down => app.log_state.scroll_down(times),
+        Action::PageUp => app.scroll_half_pages(-2 * times as isize),
+        Action::PageDown => app.scroll_half_pages(2 * times as isize),
+        Action::HalfPageUp => app.scroll_half_pages(-(times as isize)),
+        Action::HalfPageDown => app.scroll_half_pages(times as isize),
+        Action::Detail => app.open_detail(),
+        Action::ToggleTrace => app.toggle_trace(),
+        Action::EntityMenu => app.open_entity_menu(),
+        Action::Columns => app.toggle_column_editor(),
+        Action::DismissToasts => {
+            if app.selection.take().is_none() && !app.cancel_pipe() {
+                app.toasts.dismiss();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copy `text`, saying in a toast how it was copied.
+fn copy_to_clipboard(app: &mut App, text: &str) {
+    match clipboard::copy(text, true) {
+        Ok(method) => app.toasts.info(format!("Copied via {}: {}", method, text)),
+        Err(e) => app.toasts.error(format!("Copy failed: {:#}", e)),
+    }
+}
+
+/// Copy the selection, or the cursor line when nothing is selected.
+fn yank(app: &mut App) {
+    let Some((text, lines)) = app.selected_text() else {
+        return;
+    };
+    app.selection = None;
+    match clipboard::copy(&text, true) {
+        Ok(method) => app.toasts.info(format!("Copied {} lines via {}", lines, method)),
+        Err(e) => app.toasts.error(format!("Copy failed: {:#}", e)),
     }
 }
+
+fn open_url(url: &str) {
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg(url).spawn();
+    // Not `cmd /C start`: cmd would take the `&` between query parameters
+    // as a command separator.
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("rundll32").args(["url.dll,FileProtocolHandler", url]).spawn();
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = std::process::Command::new("xdg-open").arg(url).spawn();
+    let _ = result;
+}
@@ -0,0 +1,228 @@
+use crate::core::LogLine;
+use crate::level::Level;
+use crate::watch::Watch;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds `logviewer_ingest_lines_per_second` averages over.
+const RATE_WINDOW_SECS: u64 = 60;
+
+/// How long a scrape may take to send its request or read the answer.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Counters {
+    lines: u64,
+    bytes: u64,
+    dropped: u64,
+    sampled_out: u64,
+    buffer_lines: usize,
+    levels: BTreeMap<Option<Level>, u64>,
+    watch_hits: Vec<(String, usize)>,
+    /// Lines received per Unix second, oldest first.
+    recent: VecDeque<(u64, u64)>,
+}
+
+/// Only set with `--metrics`; recording is a no-op otherwise.
+static METRICS: OnceLock<Mutex<Counters>> = OnceLock::new();
+
+fn update(f: impl FnOnce(&mut Counters)) {
+    if let Some(Ok(mut counters)) = METRICS.get().map(Mutex::lock) {
+        f(&mut counters);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Count a line as it arrives, before `--sample` may keep it out.
+pub fn record_line(line: &LogLine) {
+    update(|c| {
+        c.lines += 1;
+        c.bytes += line.content.len() as u64;
+        *c.levels.entry(line.level).or_default() += 1;
+        let now = now_secs();
+        match c.recent.back_mut() {
+            Some((sec, count)) if *sec == now => *count += 1,
+            _ => c.recent.push_back((now, 1)),
+        }
+        while c.recent.front().is_some_and(|(sec, _)| sec + RATE_WINDOW_SECS <= now) {
+            c.recent.pop_front();
+        }
+    });
+}
+
+/// Lines discarded from the buffer by clearing it.
+pub fn record_dropped(count: usize) {
+    update(|c| c.dropped += count as u64);
+}
+
+pub fn record_sampled_out(count: u64) {
+    update(|c| c.sampled_out += count);
+}
+
+pub fn record_buffer(len: usize, watches: &[Watch]) {
+    update(|c| {
+        c.buffer_lines = len;
+        c.watch_hits = watches.iter().map(|w| (w.pattern.clone(), w.stats.count)).collect();
+    });
+}
+
+fn level_name(level: Option<Level>) -> &'static str {
+    match level {
+        Some(Level::Trace) => "trace",
+        Some(Level::Debug) => "debug",
+        Some(Level::Info) => "info",
+        Some(Level::Warn) => "warn",
+        Some(Level::Error) => "error",
+        None => "none",
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn render(c: &Counters, now: u64) -> String {
+    let recent: u64 = c
+        .recent
+        .iter()
+        .filter(|(sec, _)| sec + RATE_WINDOW_SECS > now)
+        .map(|(_, count)| count)
+        .sum();
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    };
+    metric(
+        "logviewer_lines_ingested_total",
+        "counter",
+        "Log lines received from the source.",
+        vec![(String::new(), c.lines.to_string())],
+    );
+    metric(
+        "logviewer_bytes_ingested_total",
+        "counter",
+        "Bytes of log content received from the source.",
+        vec![(String::new(), c.bytes.to_string())],
+    );
+    metric(
+        "logviewer_ingest_lines_per_second",
+        "gauge",
+        "Lines received per second over the last minute.",
+        vec![(String::new(), format!("{}", recent as f64 / RATE_WINDOW_SECS as f64))],
+    );
+    metric(
+        "logviewer_buffer_lines",
+        "gauge",
+        "Lines currently held in the buffer.",
+        vec![(String::new(), c.buffer_lines.to_string())],
+    );
+    metric(
+        "logviewer_dropped_lines_total",
+        "counter",
+        "Lines discarded from the buffer by clearing it.",
+        vec![(String::new(), c.dropped.to_string())],
+    );
+    metric(
+        "logviewer_sampled_out_lines_total",
+        "counter",
+        "Lines received but kept out of the buffer by sampling.",
+        vec![(String::new(), c.sampled_out.to_string())],
+    );
+    metric(
+        "logviewer_level_lines_total",
+        "counter",
+        "Lines received by detected level, sampled out or not.",
+        c.levels
+            .iter()
+            .map(|(level, count)| (format!("{{level=\"{}\"}}", level_name(*level)), count.to_string()))
+            .collect(),
+    );
+    metric(
+        "logviewer_watch_hits_total",
+        "counter",
+        "Lines matched by each watch since the buffer was last cleared.",
+        c.watch_hits
+            .iter()
+            .map(|(pattern, hits)| (format!("{{watch=\"{}\"}}", escape_label(pattern)), hits.to_string()))
+            .collect(),
+    );
+    out
+}
+
+fn respond(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let (status, body) = match (path, METRICS.get().map(Mutex::lock)) {
+        ("/metrics", Some(Ok(counters))) => ("200 OK", render(&counters, now_secs())),
+        ("/metrics", _) => ("500 Internal Server Error", String::new()),
+        _ => ("404 Not Found", "Metrics are served at /metrics\n".to_string()),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Serve Prometheus metrics about the viewer itself at `http://addr/metrics`,
+/// each scrape on its own thread so a stalled client holds up no one else.
+pub fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("Cannot serve metrics on {}", addr))?;
+    METRICS.get_or_init(Mutex::default);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || respond(stream));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut c = Counters {
+            lines: 3,
+            sampled_out: 2,
+            watch_hits: vec![("ms=\"(\\d+)\"".to_string(), 2)],
+            ..Counters::default()
+        };
+        c.levels.insert(Some(Level::Error), 2);
+        c.levels.insert(None, 1);
+        c.recent.push_back((100, 30));
+        c.recent.push_back((10, 99));
+        let text = render(&c, 120);
+        assert!(text.contains("logviewer_lines_ingested_total 3\n"));
+        assert!(text.contains("logviewer_ingest_lines_per_second 0.5\n"));
+        assert!(text.contains("logviewer_sampled_out_lines_total 2\n"));
+        assert!(text.contains("logviewer_level_lines_total{level=\"none\"} 1\n"));
+        assert!(text.contains("logviewer_level_lines_total{level=\"error\"} 2\n"));
+        assert!(text.contains("logviewer_watch_hits_total{watch=\"ms=\\\"(\\\\d+)\\\"\"} 2\n"));
+    }
+}
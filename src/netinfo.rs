@@ -1,9 +1,62 @@
 use std::net::IpAddr;
 
+/// Where an address can be connected to from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reach {
+    /// Loopback: only this machine.
+    Host,
+    /// Link-local or self-assigned: only machines on the same link.
+    Link,
+    Network,
+}
+
+impl Reach {
+    pub fn label(self) -> &'static str {
+        match self {
+            Reach::Host => "this host only",
+            Reach::Link => "same link only",
+            Reach::Network => "",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AddressInfo {
     pub ip: IpAddr,
     pub is_self_assigned: bool,
+    /// Zone ID of a link-local IPv6 address: the interface name, or its
+    /// index on Windows.
+    pub zone: Option<String>,
+    pub reach: Reach,
+}
+
+impl AddressInfo {
+    pub fn new(ip: IpAddr, zone: &str) -> Self {
+        let reach = reach(&ip);
+        Self {
+            ip,
+            is_self_assigned: is_self_assigned(&ip),
+            zone: (ip.is_ipv6() && reach == Reach::Link).then(|| zone.to_string()),
+            reach,
+        }
+    }
+
+    /// Why the address may not work from another machine; empty if it does.
+    pub fn note(&self) -> &'static str {
+        if self.is_self_assigned {
+            "self-assigned"
+        } else {
+            self.reach.label()
+        }
+    }
+
+    /// The address as a peer has to type it, e.g. `fe80::1%eth0`.
+    pub fn host(&self) -> String {
+        match &self.zone {
+            Some(zone) => format!("{}%{}", self.ip, zone),
+            None => self.ip.to_string(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -28,10 +81,7 @@ mod unix_impl {
                 if let Some(addr) = ifaddr.address {
                     if let Some(ip) = sockaddr_to_ip(&addr) {
                         if is_valid_address(&ip) {
-                            let addr_info = AddressInfo {
-                                ip,
-                                is_self_assigned: is_self_assigned(&ip),
-                            };
+                            let addr_info = AddressInfo::new(ip, &ifaddr.interface_name);
                             iface_map
                                 .entry(ifaddr.interface_name)
                                 .or_default()
@@ -63,11 +113,9 @@ mod unix_impl {
 
     fn sockaddr_to_ip(addr: &nix::sys::socket::SockaddrStorage) -> Option<IpAddr> {
         if let Some(v4) = addr.as_sockaddr_in() {
-            Some(IpAddr::V4(std::net::Ipv4Addr::from(v4.ip())))
-        } else if let Some(v6) = addr.as_sockaddr_in6() {
-            Some(IpAddr::V6(v6.ip()))
+            Some(IpAddr::V4(v4.ip()))
         } else {
-            None
+            addr.as_sockaddr_in6().map(|v6| IpAddr::V6(v6.ip()))
         }
     }
 
@@ -127,6 +175,7 @@ mod windows_impl {
                     .FriendlyName
                     .to_string()
                     .unwrap_or_else(|_| "Unknown".to_string());
+                let zone = (*adapter).Ipv6IfIndex.to_string();
 
                 let mut unicast = (*adapter).FirstUnicastAddress;
                 while !unicast.is_null() {
@@ -147,10 +196,7 @@ mod windows_impl {
 
                         if let Some(ip) = ip {
                             if is_valid_address(&ip) {
-                                let addr_info = AddressInfo {
-                                    ip,
-                                    is_self_assigned: is_self_assigned(&ip),
-                                };
+                                let addr_info = AddressInfo::new(ip, &zone);
                                 iface_map.entry(name.clone()).or_default().push(addr_info);
                             }
                         }
@@ -200,9 +246,20 @@ pub fn get_network_interfaces() -> Vec<InterfaceInfo> {
 }
 
 fn is_valid_address(ip: &IpAddr) -> bool {
-    match ip {
-        IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local(),
-        IpAddr::V6(v6) => !v6.is_loopback() && !is_link_local_v6(v6),
+    !ip.is_unspecified() && !ip.is_multicast()
+}
+
+fn reach(ip: &IpAddr) -> Reach {
+    let link_local = match ip {
+        IpAddr::V4(v4) => v4.is_link_local(),
+        IpAddr::V6(v6) => is_link_local_v6(v6),
+    };
+    if ip.is_loopback() {
+        Reach::Host
+    } else if link_local {
+        Reach::Link
+    } else {
+        Reach::Network
     }
 }
 
@@ -220,3 +277,23 @@ fn is_link_local_v6(ip: &std::net::Ipv6Addr) -> bool {
     let segments = ip.segments();
     (segments[0] & 0xffc0) == 0xfe80
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reach_and_zone() {
+        let link = AddressInfo::new("fe80::1".parse().unwrap(), "eth0");
+        assert_eq!(link.reach, Reach::Link);
+        assert_eq!(link.host(), "fe80::1%eth0");
+
+        let global = AddressInfo::new("2001:db8::1".parse().unwrap(), "eth0");
+        assert_eq!((global.reach, global.host()), (Reach::Network, "2001:db8::1".to_string()));
+
+        let self_assigned = AddressInfo::new("169.254.3.4".parse().unwrap(), "en0");
+        assert!(self_assigned.is_self_assigned && self_assigned.zone.is_none());
+        assert_eq!(self_assigned.reach, Reach::Link);
+        assert_eq!(AddressInfo::new("::1".parse().unwrap(), "lo").reach, Reach::Host);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::level::Level;
+use crate::table::{self, Format};
+use serde::Deserialize;
+
+/// One event from `log stream --style ndjson` on macOS or iOS.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Entry {
+    pub timestamp: String,
+    /// `Default`, `Info`, `Debug`, `Error` or `Fault`.
+    pub message_type: String,
+    pub subsystem: String,
+    pub category: String,
+    pub process_image_path: String,
+    #[serde(rename = "processID")]
+    pub process_id: u64,
+    #[serde(rename = "threadID")]
+    pub thread_id: u64,
+    pub event_message: String,
+}
+
+/// Whether `--format` lets lines be read as unified log events.
+pub fn enabled() -> bool {
+    matches!(table::format(), Format::Auto | Format::OsLog)
+}
+
+pub fn parse(line: &str) -> Option<Entry> {
+    let line = line.trim();
+    if !line.starts_with('{') || !line.contains("\"eventMessage\"") {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+impl Entry {
+    /// Faults count as errors; `Default` is the unified log's info.
+    pub fn level(&self) -> Option<Level> {
+        match self.message_type.as_str() {
+            "Fault" | "Error" => Some(Level::Error),
+            "Default" | "Info" => Some(Level::Info),
+            "Debug" => Some(Level::Debug),
+            _ => None,
+        }
+    }
+
+    /// The process's executable name, without its path.
+    pub fn process(&self) -> &str {
+        self.process_image_path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// The event as `name=value` fields, empty subsystem and category left out.
+    pub fn fields(&self) -> String {
+        let mut fields = vec![
+            self.timestamp.clone(),
+            self.message_type.clone(),
+            format!("process={}", self.process()),
+            format!("pid={}", self.process_id),
+            format!("tid={:#x}", self.thread_id),
+        ];
+        if !self.subsystem.is_empty() {
+            fields.push(format!("subsystem={}", self.subsystem));
+        }
+        if !self.category.is_empty() {
+            fields.push(format!("category={}", self.category));
+        }
+        format!("{}: {}", fields.join(" "), self.event_message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ndjson_event() {
+        let line = r#"{"traceID":1,"eventMessage":"Connection failed","eventType":"logEvent","timestamp":"2024-01-31 14:05:01.123456-0800","messageType":"Error","processImagePath":"/usr/libexec/nsurlsessiond","processID":412,"threadID":3344,"subsystem":"com.apple.network","category":"connection"}"#;
+        let entry = parse(line).unwrap();
+        assert_eq!((entry.process(), entry.level()), ("nsurlsessiond", Some(Level::Error)));
+        assert_eq!(
+            entry.fields(),
+            "2024-01-31 14:05:01.123456-0800 Error process=nsurlsessiond pid=412 tid=0xd10 subsystem=com.apple.network category=connection: Connection failed"
+        );
+        let bare = r#"{"eventMessage":"hi","messageType":"Default","timestamp":"2024-01-31 14:05:02.0-0800"}"#;
+        assert_eq!(parse(bare).unwrap().level(), Some(Level::Info));
+        assert!(parse(r#"{"level":"info","msg":"x"}"#).is_none());
+    }
+}
@@ -0,0 +1,366 @@
+use crate::glob::glob_match;
+use crate::input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files listed for the fuzzy finder at most, and how deep it looks.
+const MAX_FILES: usize = 20_000;
+const MAX_DEPTH: usize = 6;
+
+/// Directories the fuzzy finder doesn't descend into, besides hidden ones.
+const SKIPPED_DIRS: [&str; 2] = ["target", "node_modules"];
+
+pub const OPTIONS: [(&str, &str); 4] = [
+    ("Open a file", "fuzzy-find a log file under the working directory"),
+    ("Listen on a port", "accept logs over TCP, e.g. from `logviewer send`"),
+    ("Attach to a Docker container", "follow `docker logs` of a running container"),
+    ("Replay a session", "play a log file or .lvz bundle back at its own pace"),
+];
+
+/// What the startup screen settled on.
+pub enum Choice {
+    File(PathBuf),
+    Listen(u16),
+    Docker(String),
+    Replay(PathBuf),
+    Quit,
+}
+
+pub enum Step {
+    Menu,
+    /// Finding a file to open, or to replay.
+    Files { replay: bool },
+    Port,
+    /// Running containers, or why they couldn't be listed.
+    Docker(Result<Vec<String>, String>),
+}
+
+/// The startup screen shown when there is nothing to read.
+pub struct Picker {
+    pub step: Step,
+    pub selected: usize,
+    /// The fuzzy query or the port being typed.
+    pub input: TextInput,
+    files: Vec<PathBuf>,
+    pub matches: Vec<PathBuf>,
+    /// Leave out files `.gitignore` excludes; toggled with Ctrl-G.
+    pub gitignore: bool,
+    /// Opened by `:open` rather than at startup, so Esc closes it.
+    pub standalone: bool,
+}
+
+impl Picker {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Menu,
+            selected: 0,
+            input: TextInput::default(),
+            files: Vec::new(),
+            matches: Vec::new(),
+            gitignore: false,
+            standalone: false,
+        }
+    }
+
+    /// Just the file finder, for opening another file at runtime.
+    pub fn files() -> Self {
+        let mut picker = Self::new();
+        picker.standalone = true;
+        picker.open(Step::Files { replay: false });
+        picker
+    }
+
+    /// Rows the current step lists.
+    pub fn len(&self) -> usize {
+        match &self.step {
+            Step::Menu => OPTIONS.len(),
+            Step::Files { .. } => self.matches.len(),
+            Step::Port => 0,
+            Step::Docker(containers) => containers.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Choice> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') => return Some(Choice::Quit),
+                KeyCode::Char('g') if matches!(self.step, Step::Files { .. }) => {
+                    self.gitignore = !self.gitignore;
+                    self.files = list_files(Path::new("."), self.gitignore);
+                    self.refilter();
+                }
+                _ => {}
+            }
+            return None;
+        }
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.len().saturating_sub(1)),
+            KeyCode::Esc if self.standalone || matches!(self.step, Step::Menu) => return Some(Choice::Quit),
+            KeyCode::Esc => self.open(Step::Menu),
+            KeyCode::Enter => return self.choose(),
+            _ => return self.type_key(key.code),
+        }
+        None
+    }
+
+    fn type_key(&mut self, code: KeyCode) -> Option<Choice> {
+        match (&self.step, code) {
+            (Step::Menu, KeyCode::Char('q')) => return Some(Choice::Quit),
+            (Step::Menu, KeyCode::Char('k')) => self.selected = self.selected.saturating_sub(1),
+            (Step::Menu, KeyCode::Char('j')) => self.selected = (self.selected + 1).min(OPTIONS.len() - 1),
+            (Step::Menu, KeyCode::Char(c @ '1'..='4')) => {
+                self.selected = c as usize - '1' as usize;
+                return self.choose();
+            }
+            (Step::Docker(_), KeyCode::Char('k')) => self.selected = self.selected.saturating_sub(1),
+            (Step::Docker(_), KeyCode::Char('j')) => {
+                self.selected = (self.selected + 1).min(self.len().saturating_sub(1))
+            }
+            (Step::Files { .. } | Step::Port, KeyCode::Char(c)) => {
+                if !matches!(self.step, Step::Port) || c.is_ascii_digit() {
+                    self.input.insert_char(c);
+                    self.input.clear_error();
+                }
+            }
+            (Step::Files { .. } | Step::Port, KeyCode::Backspace) => self.input.delete_char_before_cursor(),
+            _ => return None,
+        }
+        if matches!(self.step, Step::Files { .. }) {
+            self.refilter();
+        }
+        None
+    }
+
+    fn choose(&mut self) -> Option<Choice> {
+        match &self.step {
+            Step::Menu => {
+                let step = match self.selected {
+                    0 => Step::Files { replay: false },
+                    1 => Step::Port,
+                    2 => Step::Docker(docker_containers()),
+                    _ => Step::Files { replay: true },
+                };
+                self.open(step);
+                None
+            }
+            Step::Files { replay } => {
+                let path = self.matches.get(self.selected)?.clone();
+                Some(if *replay { Choice::Replay(path) } else { Choice::File(path) })
+            }
+            Step::Port => match self.input.text.parse() {
+                Ok(port) => Some(Choice::Listen(port)),
+                Err(_) => {
+                    self.input.set_error(Some("Enter a port from 1 to 65535".to_string()));
+                    None
+                }
+            },
+            Step::Docker(containers) => {
+                let name = containers.as_ref().ok()?.get(self.selected)?;
+                Some(Choice::Docker(name.clone()))
+            }
+        }
+    }
+
+    fn open(&mut self, step: Step) {
+        if matches!(step, Step::Files { .. }) && self.files.is_empty() {
+            self.files = list_files(Path::new("."), self.gitignore);
+        }
+        self.step = step;
+        self.selected = 0;
+        self.input = TextInput::default();
+        self.refilter();
+    }
+
+    fn refilter(&mut self) {
+        let query = self.input.text.as_str();
+        let mut scored: Vec<(i64, &PathBuf)> = self
+            .files
+            .iter()
+            .filter_map(|path| Some((fuzzy_score(query, &path.to_string_lossy())?, path)))
+            .collect();
+        scored.sort_by(|(a, pa), (b, pb)| b.cmp(a).then(pa.as_os_str().len().cmp(&pb.as_os_str().len())));
+        self.matches = scored.into_iter().map(|(_, path)| path.clone()).collect();
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+    }
+}
+
+/// How well `candidate` matches `query` typed as a subsequence, ignoring
+/// case; `None` when it doesn't. Runs of characters and matches at the
+/// start of a path component or word score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut from = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let idx = (from..chars.len()).find(|&i| chars[i].to_lowercase().eq(std::iter::once(q)))?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == idx) {
+            score += 4;
+        }
+        if idx == 0 || matches!(chars[idx - 1], '/' | '\\' | '_' | '-' | '.' | ' ') {
+            score += 3;
+        }
+        last = Some(idx);
+        from = idx + 1;
+    }
+    Some(score)
+}
+
+/// Regular files under `root`, relative to it, skipping hidden and build
+/// directories, and what `.gitignore` files exclude when `gitignore` is set.
+fn list_files(root: &Path, gitignore: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut ignore = GitIgnore::default();
+    let mut dirs = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let relative_dir = dir.strip_prefix(root).unwrap_or(&dir).to_path_buf();
+        if gitignore {
+            if let Ok(text) = fs::read_to_string(dir.join(".gitignore")) {
+                ignore.add(&relative_dir, &text);
+            }
+        }
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if gitignore && ignore.is_ignored(&relative_dir.join(name.as_ref()), file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                if depth < MAX_DEPTH && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    dirs.push((path, depth + 1));
+                }
+            } else {
+                files.push(path.strip_prefix(root).map(Path::to_path_buf).unwrap_or(path));
+                if files.len() >= MAX_FILES {
+                    return files;
+                }
+            }
+        }
+    }
+    files
+}
+
+struct Rule {
+    /// Directory of the `.gitignore`, relative to the listing root.
+    base: PathBuf,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole path below `base` rather than the name.
+    anchored: bool,
+}
+
+/// `.gitignore` rules seen so far; the last matching one decides.
+#[derive(Default)]
+struct GitIgnore {
+    rules: Vec<Rule>,
+}
+
+impl GitIgnore {
+    fn add(&mut self, base: &Path, text: &str) {
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            self.rules.push(Rule {
+                base: base.to_path_buf(),
+                pattern: line.trim_start_matches('/').to_string(),
+                negated,
+                dir_only,
+                anchored: line.contains('/'),
+            });
+        }
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(below) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let below = below.to_string_lossy().replace('\\', "/");
+            let subject = if rule.anchored {
+                below.as_str()
+            } else {
+                below.rsplit('/').next().unwrap_or_default()
+            };
+            if glob_match(&rule.pattern, subject) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Names of the running containers, from `docker ps`.
+fn docker_containers() -> Result<Vec<String>, String> {
+    let output = Command::new("docker")
+        .args(["ps", "--format", "{{.Names}}"])
+        .output()
+        .map_err(|e| format!("Cannot run docker: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let names: Vec<String> = String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect();
+    if names.is_empty() {
+        return Err("No running containers".to_string());
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "app.log"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "app.log"), None);
+        assert!(fuzzy_score("APL", "logs/app.log").is_some());
+        // A run at a component start beats letters scattered through.
+        let tight = fuzzy_score("app", "logs/app.log").unwrap();
+        let loose = fuzzy_score("app", "a/pile/of/paper").unwrap();
+        assert!(tight > loose);
+    }
+
+    #[test]
+    fn test_gitignore_rules() {
+        let mut ignore = GitIgnore::default();
+        ignore.add(Path::new(""), "# logs\n*.log\n!keep.log\nbuild/\n/docs/**/*.md\n");
+        ignore.add(Path::new("sub"), "a?c\n");
+        assert!(ignore.is_ignored(Path::new("deep/app.log"), false));
+        assert!(!ignore.is_ignored(Path::new("deep/keep.log"), false));
+        assert!(ignore.is_ignored(Path::new("x/build"), true));
+        assert!(!ignore.is_ignored(Path::new("x/build"), false));
+        assert!(ignore.is_ignored(Path::new("docs/a/b/readme.md"), false));
+        assert!(!ignore.is_ignored(Path::new("other/docs/readme.md"), false));
+        assert!(ignore.is_ignored(Path::new("sub/abc"), false));
+        assert!(!ignore.is_ignored(Path::new("abc"), false));
+    }
+}
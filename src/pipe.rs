@@ -0,0 +1,138 @@
+use crate::plugin::shell;
+use anyhow::{anyhow, Context, Result};
+use std::io::{Read, Write};
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often a running command is checked for having exited or been
+/// cancelled.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What a `:pipe` command printed, stdout then stderr.
+pub struct PipeOutput {
+    pub command: String,
+    pub lines: Vec<String>,
+    /// The exit code when it wasn't 0; `None` when killed by a signal.
+    pub failed: Option<Option<i32>>,
+}
+
+/// A `:pipe` command still running.
+pub struct PipeJob {
+    pub command: String,
+    cancelled: Arc<AtomicBool>,
+    output: Receiver<Result<PipeOutput>>,
+}
+
+impl PipeJob {
+    /// The output, once the command has finished.
+    pub fn poll(&self) -> Option<Result<PipeOutput>> {
+        self.output.try_recv().ok()
+    }
+
+    /// Kill the command; nothing is reported for it afterwards.
+    pub fn cancel(self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Run `command` through the shell with `input` on its stdin, killing it
+/// if `cancelled` gets set.
+pub fn run(command: &str, input: String, cancelled: &AtomicBool) -> Result<PipeOutput> {
+    let mut cmd = shell(command);
+    // Its own process group, so cancelling takes down a whole pipeline.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("starting `{}`", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written from another thread so a command that prints before it has
+        // read everything can't fill its stdout pipe and stall both sides.
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stdout = child.stdout.take().map(read_all);
+    let stderr = child.stderr.take().map(read_all);
+    let status = loop {
+        if cancelled.load(Ordering::SeqCst) {
+            kill(&mut child);
+            return Err(anyhow!("`{}` cancelled", command));
+        }
+        match child.try_wait()? {
+            Some(status) => break status,
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    };
+    let lines = [stdout, stderr]
+        .into_iter()
+        .flatten()
+        .flat_map(|reader| {
+            let bytes = reader.join().unwrap_or_default();
+            String::from_utf8_lossy(&bytes).lines().map(String::from).collect::<Vec<_>>()
+        })
+        .collect();
+    Ok(PipeOutput {
+        command: command.to_string(),
+        lines,
+        failed: (!status.success()).then(|| status.code()),
+    })
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{killpg, Signal};
+        use nix::unistd::Pid;
+        let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// `run()` on a thread, so a slow command doesn't hold up the viewer.
+pub fn spawn(command: &str, input: String) -> PipeJob {
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let flag = cancelled.clone();
+    let owned = command.to_string();
+    thread::spawn(move || {
+        let _ = tx.send(run(&owned, input, &flag));
+    });
+    PipeJob {
+        command: command.to_string(),
+        cancelled,
+        output: rx,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipe_through_command() {
+        let running = AtomicBool::new(false);
+        let output = run("sort | uniq -c | sort -rn | head -1", "b\na\nb\n".to_string(), &running).unwrap();
+        assert_eq!(output.lines.len(), 1);
+        assert_eq!(output.lines[0].split_whitespace().collect::<Vec<_>>(), ["2", "b"]);
+        assert!(output.failed.is_none());
+        assert_eq!(run("echo oops >&2; exit 3", String::new(), &running).unwrap().failed, Some(Some(3)));
+        assert!(run("sleep 30 | cat", String::new(), &AtomicBool::new(true)).is_err());
+    }
+}
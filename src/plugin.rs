@@ -0,0 +1,490 @@
+use crate::core::LogLine;
+use crate::source::{self, SourceEvent};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+/// Version of the JSON-RPC protocol sent in `initialize`.
+const PROTOCOL_VERSION: u32 = 1;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const PARSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Parse requests in a row that may time out before the parser is turned
+/// off, so a hung plugin doesn't hold every line up for `PARSE_TIMEOUT`.
+const MAX_PARSE_TIMEOUTS: u32 = 3;
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// Sends `log` notifications with lines of its own.
+    Source,
+    /// Answers `parse` requests, rewriting, splitting or dropping lines.
+    Parser,
+    /// Answers `export` requests with the filtered lines.
+    Exporter,
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    pub fn label(self) -> &'static str {
+        match self {
+            Capability::Source => "source",
+            Capability::Parser => "parser",
+            Capability::Exporter => "exporter",
+            Capability::Unknown => "?",
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    Starting,
+    Ready,
+    Failed(String),
+    Exited(String),
+}
+
+impl Status {
+    pub fn label(&self) -> String {
+        match self {
+            Status::Starting => "starting".to_string(),
+            Status::Ready => "ready".to_string(),
+            Status::Failed(e) => format!("failed: {}", e),
+            Status::Exited(e) => e.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Health {
+    pub status: Status,
+    /// Lines received through `log` notifications.
+    pub lines: u64,
+    /// Requests sent, including the handshake.
+    pub calls: u64,
+    /// Failed or timed-out requests and malformed messages.
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub last_stderr: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+}
+
+type Reply = std::result::Result<Value, String>;
+
+/// A line from a source plugin and the source it named, if any.
+type PluginLine = (Arc<Plugin>, String, Option<String>);
+
+/// A plugin process speaking newline-delimited JSON-RPC 2.0 over its stdin
+/// and stdout. Its stderr is kept only for the manager's health view.
+pub struct Plugin {
+    pub command: String,
+    /// Name and capabilities from the `initialize` handshake.
+    info: OnceLock<Info>,
+    stdin: Mutex<ChildStdin>,
+    pending: Mutex<HashMap<u64, Sender<Reply>>>,
+    next_id: AtomicU64,
+    health: Mutex<Health>,
+    /// `parse` requests timed out since the last one answered.
+    parse_timeouts: AtomicU32,
+}
+
+/// A request the plugin didn't answer in time.
+#[derive(Debug)]
+struct TimedOut(String);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "`{}` timed out", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+struct Plugins {
+    list: Vec<Arc<Plugin>>,
+    /// Lines from source plugins, until `attach` forwards them.
+    lines: Mutex<Option<Receiver<PluginLine>>>,
+}
+
+/// Only set with `--plugin`.
+static PLUGINS: OnceLock<Plugins> = OnceLock::new();
+
+enum Message {
+    Response(u64, Reply),
+    Log(String, Option<String>),
+}
+
+#[derive(Deserialize)]
+struct Incoming {
+    id: Option<u64>,
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+fn parse_message(text: &str) -> Result<Message> {
+    let msg: Incoming = serde_json::from_str(text)?;
+    match (msg.id, msg.method.as_deref()) {
+        (_, Some("log")) => {
+            let field = |key| msg.params.get(key).and_then(Value::as_str).map(str::to_string);
+            let line = field("line").context("log notification without a line")?;
+            Ok(Message::Log(line, field("source")))
+        }
+        (_, Some(other)) => bail!("unknown method `{}`", other),
+        (Some(id), None) => Ok(Message::Response(
+            id,
+            match msg.error {
+                Some(e) => Err(e.message),
+                None => Ok(msg.result.unwrap_or(Value::Null)),
+            },
+        )),
+        (None, None) => bail!("neither a response nor a notification"),
+    }
+}
+
+pub fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    }
+    #[cfg(not(windows))]
+    {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+impl Plugin {
+    fn spawn(command: &str, lines: Sender<PluginLine>) -> Result<Arc<Self>> {
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("starting plugin `{}`", command))?;
+        let (Some(stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            bail!("plugin `{}` has no pipes", command);
+        };
+        let plugin = Arc::new(Self {
+            command: command.to_string(),
+            info: OnceLock::new(),
+            stdin: Mutex::new(stdin),
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            health: Mutex::new(Health::default()),
+            parse_timeouts: AtomicU32::new(0),
+        });
+
+        let reader = plugin.clone();
+        thread::spawn(move || reader.read_stdout(BufReader::new(stdout), child, lines));
+        let reader = plugin.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                reader.update(|h| h.last_stderr = Some(line));
+            }
+        });
+        Ok(plugin)
+    }
+
+    fn read_stdout(self: Arc<Self>, stdout: impl BufRead, mut child: Child, lines: Sender<PluginLine>) {
+        for text in stdout.lines().map_while(|l| l.ok()) {
+            if text.trim().is_empty() {
+                continue;
+            }
+            match parse_message(&text) {
+                Ok(Message::Response(id, reply)) => {
+                    if let Some(tx) = self.pending.lock().ok().and_then(|mut p| p.remove(&id)) {
+                        let _ = tx.send(reply);
+                    }
+                }
+                Ok(Message::Log(line, source)) => {
+                    self.update(|h| h.lines += 1);
+                    let _ = lines.send((self.clone(), line, source));
+                }
+                Err(e) => self.record_error(format!("bad message: {}", e)),
+            }
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.clear();
+        }
+        let status = match child.wait() {
+            Ok(status) => format!("exited ({})", status),
+            Err(e) => format!("exited ({})", e),
+        };
+        self.update(|h| h.status = Status::Exited(status));
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Health)) {
+        if let Ok(mut health) = self.health.lock() {
+            f(&mut health);
+        }
+    }
+
+    fn record_error(&self, error: String) {
+        self.update(|h| {
+            h.errors += 1;
+            h.last_error = Some(error);
+        });
+    }
+
+    pub fn health(&self) -> Health {
+        self.health.lock().map(|h| h.clone()).unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &str {
+        self.info.get().map_or(&self.command, |info| &info.name)
+    }
+
+    pub fn capabilities(&self) -> &[Capability] {
+        self.info.get().map_or(&[], |info| &info.capabilities)
+    }
+
+    fn can(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability) && self.health().status == Status::Ready
+    }
+
+    fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().map_err(|_| anyhow!("plugin state poisoned"))?.insert(id, tx);
+        self.update(|h| h.calls += 1);
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let result = self
+            .stdin
+            .lock()
+            .map_err(|_| anyhow!("plugin state poisoned"))
+            .and_then(|mut stdin| Ok(writeln!(stdin, "{}", message).and_then(|_| stdin.flush())?))
+            .and_then(|_| match rx.recv_timeout(timeout) {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err(message)) => Err(anyhow!(message)),
+                Err(RecvTimeoutError::Timeout) => Err(TimedOut(method.to_string()).into()),
+                Err(RecvTimeoutError::Disconnected) => Err(anyhow!("plugin exited")),
+            });
+        if let Err(e) = &result {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.remove(&id);
+            }
+            self.record_error(format!("{:#}", e));
+        }
+        result
+    }
+
+    fn initialize(&self) {
+        let params = json!({"protocol": PROTOCOL_VERSION, "version": env!("CARGO_PKG_VERSION")});
+        let status = match self
+            .request("initialize", params, HANDSHAKE_TIMEOUT)
+            .and_then(|value| Ok(serde_json::from_value::<Info>(value)?))
+        {
+            Ok(info) => {
+                let _ = self.info.set(info);
+                Status::Ready
+            }
+            Err(e) => Status::Failed(format!("{:#}", e)),
+        };
+        self.update(|h| {
+            if h.status == Status::Starting {
+                h.status = status;
+            }
+        });
+    }
+
+    /// The lines `line` becomes, or `None` to keep it as it is. After
+    /// `MAX_PARSE_TIMEOUTS` timeouts in a row the plugin is marked failed and
+    /// lines pass through untouched.
+    fn parse(&self, line: &str, source: Option<&str>) -> Option<Vec<String>> {
+        if !self.can(Capability::Parser) {
+            return None;
+        }
+        let value = match self.request("parse", json!({"line": line, "source": source}), PARSE_TIMEOUT) {
+            Ok(value) => {
+                self.parse_timeouts.store(0, Ordering::Relaxed);
+                value
+            }
+            Err(e) => {
+                if e.is::<TimedOut>() && self.parse_timeouts.fetch_add(1, Ordering::Relaxed) + 1 >= MAX_PARSE_TIMEOUTS {
+                    let reason = format!("parser off after {} timeouts", MAX_PARSE_TIMEOUTS);
+                    self.update(|h| h.status = Status::Failed(reason));
+                }
+                return None;
+            }
+        };
+        let lines = value.get("lines")?.as_array()?;
+        Some(lines.iter().filter_map(|l| l.as_str().map(str::to_string)).collect())
+    }
+}
+
+/// Start the `--plugin` commands and wait for their handshakes. A plugin
+/// that fails the handshake stays listed in the manager with its error.
+pub fn load(commands: &[String]) -> Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let (tx, rx) = mpsc::channel();
+    let list = commands
+        .iter()
+        .map(|command| Plugin::spawn(command, tx.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    let handshakes: Vec<_> = list
+        .iter()
+        .cloned()
+        .map(|plugin| thread::spawn(move || plugin.initialize()))
+        .collect();
+    for handshake in handshakes {
+        let _ = handshake.join();
+    }
+    PLUGINS
+        .set(Plugins {
+            list,
+            lines: Mutex::new(Some(rx)),
+        })
+        .map_err(|_| anyhow!("plugins already loaded"))
+}
+
+pub fn plugins() -> &'static [Arc<Plugin>] {
+    PLUGINS.get().map_or(&[], |p| &p.list)
+}
+
+/// Whether a ready plugin supplies lines, so stdin need not be read.
+pub fn has_source() -> bool {
+    plugins().iter().any(|p| p.can(Capability::Source))
+}
+
+/// Route lines from `Sender`s returned here through the parser plugins on
+/// their way to `tx`, and start forwarding lines from source plugins.
+pub fn attach(tx: SyncSender<SourceEvent>) -> SyncSender<SourceEvent> {
+    let Some(loaded) = PLUGINS.get() else {
+        return tx;
+    };
+    let parsers: Vec<_> = loaded.list.iter().filter(|p| p.can(Capability::Parser)).cloned().collect();
+    let input = if parsers.is_empty() {
+        tx
+    } else {
+        let (input, rx) = source::channel();
+        thread::spawn(move || {
+            for event in rx {
+                for event in run_parsers(&parsers, event) {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        input
+    };
+    if let Some(lines) = loaded.lines.lock().ok().and_then(|mut l| l.take()) {
+        let input = input.clone();
+        thread::spawn(move || {
+            // Forwarded only once the handshakes are over, so unnamed lines
+            // get the plugin's name rather than its command.
+            for (plugin, line, source) in lines {
+                let source = source.unwrap_or_else(|| plugin.name().to_string());
+                if input.send(SourceEvent::Line(line, Some(source.into()))).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    input
+}
+
+fn run_parsers(parsers: &[Arc<Plugin>], event: SourceEvent) -> Vec<SourceEvent> {
+    let SourceEvent::Line(line, source) = event else {
+        return vec![event];
+    };
+    let mut lines = vec![line];
+    for parser in parsers {
+        lines = lines
+            .into_iter()
+            .flat_map(|line| parser.parse(&line, source.as_deref()).unwrap_or_else(|| vec![line]))
+            .collect();
+    }
+    lines.into_iter().map(|line| SourceEvent::Line(line, source.clone())).collect()
+}
+
+/// Hand `lines` to the exporter plugin called `name` on a thread, so a slow
+/// exporter doesn't hold up the viewer. The receiver gets its message.
+pub fn export<'a>(name: &str, lines: impl Iterator<Item = &'a LogLine>) -> Receiver<Result<String>> {
+    let lines: Vec<Value> = lines
+        .filter(|line| !line.system)
+        .map(|line| {
+            json!({
+                "line": line.content,
+                "source": line.source.as_deref(),
+                "received": line.timestamp.to_rfc3339(),
+                "level": line.level.map(|l| l.badge()),
+            })
+        })
+        .collect();
+    let (tx, rx) = mpsc::channel();
+    let name = name.to_string();
+    thread::spawn(move || {
+        let _ = tx.send(export_lines(&name, lines));
+    });
+    rx
+}
+
+fn export_lines(name: &str, lines: Vec<Value>) -> Result<String> {
+    let plugin = plugins()
+        .iter()
+        .find(|p| p.name() == name && p.can(Capability::Exporter))
+        .with_context(|| format!("no ready exporter plugin '{}'", name))?;
+    let count = lines.len();
+    let result = plugin.request("export", json!({ "lines": lines }), EXPORT_TIMEOUT)?;
+    Ok(match result.get("message").and_then(Value::as_str) {
+        Some(message) => message.to_string(),
+        None => format!("Exported {} lines to {}", count, name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message() {
+        let Ok(Message::Log(line, source)) =
+            parse_message(r#"{"jsonrpc":"2.0","method":"log","params":{"line":"hello","source":"k8s"}}"#)
+        else {
+            panic!("expected a log notification");
+        };
+        assert_eq!((line.as_str(), source.as_deref()), ("hello", Some("k8s")));
+        assert!(matches!(
+            parse_message(r#"{"jsonrpc":"2.0","id":3,"result":null}"#),
+            Ok(Message::Response(3, Ok(Value::Null)))
+        ));
+        assert!(matches!(
+            parse_message(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-1,"message":"nope"}}"#),
+            Ok(Message::Response(4, Err(e))) if e == "nope"
+        ));
+        assert!(parse_message(r#"{"jsonrpc":"2.0","method":"log","params":{}}"#).is_err());
+        assert!(parse_message("not json").is_err());
+    }
+}
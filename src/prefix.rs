@@ -0,0 +1,80 @@
+use crate::glob::glob_match;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// A `prefix_rules` entry: cut a start matching `pattern` off the lines of
+/// sources whose tag matches the `source` glob (all lines when empty), e.g.
+/// the `2024-06-12T10:00:00Z myhost myapp[123]: ` a shipper wraps lines in.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrefixRule {
+    #[serde(default)]
+    pub source: String,
+    pub pattern: String,
+}
+
+type CompiledRule = (String, Regex);
+
+static RULES: RwLock<Vec<CompiledRule>> = RwLock::new(Vec::new());
+
+/// Strip by `rules` from now on. Returns the patterns that didn't compile,
+/// which are left out.
+pub fn set_rules(rules: &[PrefixRule]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let compiled = rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&format!("^(?:{})", rule.pattern)) {
+            Ok(re) => Some((rule.source.clone(), re)),
+            Err(_) => {
+                errors.push(rule.pattern.clone());
+                None
+            }
+        })
+        .collect();
+    if let Ok(mut current) = RULES.write() {
+        *current = compiled;
+    }
+    errors
+}
+
+/// `content` without the prefix of the first rule for `source` that matches
+/// it, before it is parsed.
+pub fn strip(mut content: String, source: Option<&str>) -> String {
+    let cut = match RULES.read() {
+        Ok(rules) => apply_rules(&rules, &content, source).map(|rest| content.len() - rest.len()),
+        Err(_) => None,
+    };
+    if let Some(cut) = cut {
+        content.drain(..cut);
+    }
+    content
+}
+
+/// The rest of the line after the first matching prefix; `None` when no
+/// rule applies.
+fn apply_rules<'a>(rules: &[CompiledRule], content: &'a str, source: Option<&str>) -> Option<&'a str> {
+    rules
+        .iter()
+        .filter(|(glob, _)| glob.is_empty() || source.is_some_and(|source| glob_match(glob, source)))
+        .find_map(|(_, re)| re.find(content))
+        .map(|prefix| &content[prefix.end()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_rules() {
+        let rules = vec![
+            ("shipper *".to_string(), Regex::new(r"^(?:\S+ \S+ \w+\[\d+\]: )").unwrap()),
+            (String::new(), Regex::new(r"^(?:>> )").unwrap()),
+        ];
+        let wrapped = r#"2024-06-12T10:00:00Z myhost myapp[123]: {"level":"warn"}"#;
+        assert_eq!(apply_rules(&rules, wrapped, Some("shipper 10.0.0.3:5678")), Some(r#"{"level":"warn"}"#));
+        assert_eq!(apply_rules(&rules, wrapped, Some("app.log")), None);
+        assert_eq!(apply_rules(&rules, wrapped, None), None);
+        assert_eq!(apply_rules(&rules, ">> started", None), Some("started"));
+        assert_eq!(apply_rules(&rules, "started >> ", Some("shipper x")), None);
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{anyhow, Result};
+use qrcode::{Color, QrCode};
+
+/// Light modules around the code; scanners need some margin to find it.
+const QUIET_ZONE: usize = 2;
+
+pub struct QrMatrix {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn new(data: &str) -> Result<Self> {
+        let code = QrCode::new(data).map_err(|e| anyhow!("QR code: {}", e))?;
+        Ok(Self {
+            width: code.width(),
+            dark: code.into_colors().into_iter().map(|c| c == Color::Dark).collect(),
+        })
+    }
+
+    /// Side length in modules, quiet zone included.
+    pub fn size(&self) -> usize {
+        self.width + 2 * QUIET_ZONE
+    }
+
+    /// Whether the module at `(x, y)` is dark, counting the quiet zone.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        let (Some(x), Some(y)) = (x.checked_sub(QUIET_ZONE), y.checked_sub(QUIET_ZONE)) else {
+            return false;
+        };
+        x < self.width && y < self.width && self.dark[y * self.width + x]
+    }
+
+    /// Terminal rows where each cell stacks two modules, `(top, bottom)`,
+    /// for drawing with `▀` in half the height.
+    pub fn half_block_rows(&self) -> Vec<Vec<(bool, bool)>> {
+        let size = self.size();
+        (0..size)
+            .step_by(2)
+            .map(|y| (0..size).map(|x| (self.is_dark(x, y), self.is_dark(x, y + 1))).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_blocks_cover_code_and_quiet_zone() {
+        let qr = QrMatrix::new("192.168.1.5:9999").unwrap();
+        assert_eq!(qr.size(), 21 + 2 * QUIET_ZONE);
+        let rows = qr.half_block_rows();
+        assert_eq!(rows.len(), qr.size().div_ceil(2));
+        assert!(rows.iter().all(|row| row.len() == qr.size()));
+        // Quiet zone, then the top-left finder pattern.
+        assert_eq!(rows[0][0], (false, false));
+        assert_eq!(rows[1][QUIET_ZONE], (true, true));
+    }
+}
@@ -0,0 +1,115 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+/// Host names of IP addresses, looked up one at a time on a background
+/// thread and kept for the rest of the run.
+#[derive(Clone)]
+pub struct ReverseDns {
+    /// `None` while the lookup runs or when the address has no name.
+    names: Arc<Mutex<HashMap<IpAddr, Option<String>>>>,
+    /// Lookups that found a name.
+    answers: Arc<AtomicUsize>,
+    tx: Sender<IpAddr>,
+}
+
+impl ReverseDns {
+    pub fn new() -> Self {
+        let names = Arc::new(Mutex::new(HashMap::new()));
+        let (tx, rx) = mpsc::channel::<IpAddr>();
+        let answers = Arc::new(AtomicUsize::new(0));
+        let found = Arc::clone(&names);
+        let answered = Arc::clone(&answers);
+        thread::spawn(move || {
+            for ip in rx {
+                let name = dns_lookup::lookup_addr(&ip).ok().filter(|name| *name != ip.to_string());
+                let named = name.is_some();
+                if let Ok(mut names) = found.lock() {
+                    names.insert(ip, name);
+                }
+                if named {
+                    answered.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        Self { names, answers, tx }
+    }
+
+    /// How many lookups have found a name so far.
+    pub fn answers(&self) -> usize {
+        self.answers.load(Ordering::Relaxed)
+    }
+
+    /// The host name of `ip` once it is known; asks for it the first time.
+    pub fn name(&self, ip: IpAddr) -> Option<String> {
+        let mut names = self.names.lock().ok()?;
+        match names.get(&ip) {
+            Some(name) => name.clone(),
+            None => {
+                names.insert(ip, None);
+                let _ = self.tx.send(ip);
+                None
+            }
+        }
+    }
+}
+
+/// Host names for the `addr:port` source tags of listen-mode peers.
+#[derive(Clone, Default)]
+pub struct PeerNames {
+    /// Started on the first tag that carries an address.
+    rdns: OnceLock<ReverseDns>,
+    /// Show the addresses as received.
+    pub numeric: bool,
+}
+
+impl PeerNames {
+    pub fn new(numeric: bool) -> Self {
+        Self {
+            rdns: OnceLock::new(),
+            numeric,
+        }
+    }
+
+    /// `tag` with its address replaced by the host name once known.
+    pub fn tag<'a>(&self, tag: &'a str) -> Cow<'a, str> {
+        if self.numeric {
+            return Cow::Borrowed(tag);
+        }
+        name_peer(tag, |ip| self.rdns.get_or_init(ReverseDns::new).name(ip))
+    }
+}
+
+/// `tag` (`addr:port`, optionally after a label) with the address swapped
+/// for what `lookup` names it; other tags unchanged.
+fn name_peer(tag: &str, lookup: impl FnOnce(IpAddr) -> Option<String>) -> Cow<'_, str> {
+    let (label, addr) = tag.rsplit_once(' ').map_or(("", tag), |(label, addr)| (label, addr));
+    let Ok(addr) = addr.parse::<SocketAddr>() else {
+        return Cow::Borrowed(tag);
+    };
+    match lookup(addr.ip().to_canonical()) {
+        Some(host) if label.is_empty() => Cow::Owned(format!("{}:{}", host, addr.port())),
+        Some(host) => Cow::Owned(format!("{} {}:{}", label, host, addr.port())),
+        None => Cow::Borrowed(tag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_peer_keeps_label_and_port() {
+        let known = |ip: IpAddr| (ip.to_string() == "10.0.0.3").then(|| "build-agent-3".to_string());
+        assert_eq!(name_peer("10.0.0.3:5678", known), "build-agent-3:5678");
+        assert_eq!(name_peer("api 10.0.0.3:41000", known), "api build-agent-3:41000");
+        assert_eq!(name_peer("[::ffff:10.0.0.3]:5678", known), "build-agent-3:5678");
+        assert_eq!(name_peer("10.0.0.4:5678", known), "10.0.0.4:5678");
+        assert_eq!(name_peer("[::1]:80", |_| Some("localhost".to_string())), "localhost:80");
+        assert_eq!(name_peer("app.log", |_| panic!("not an address")), "app.log");
+    }
+}
@@ -0,0 +1,350 @@
+use crate::source::{unescape, url_without_userinfo, Feed, Message};
+use anyhow::{anyhow, bail, Result};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 6379;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long one `XREAD` waits for new entries, and a channel for messages
+/// before the connection is pinged.
+const BLOCK: Duration = Duration::from_secs(5);
+
+/// Stream entries asked for per `XREAD`.
+const BATCH: usize = 500;
+
+/// Longest bulk reply accepted, Redis's own limit for a string.
+const MAX_BULK: i64 = 512 * 1024 * 1024;
+
+/// How `--redis` reads a key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// A stream if the key is one, else a pub/sub channel.
+    Auto,
+    Stream,
+    Channel,
+}
+
+/// `--redis redis://[user:password@]host[:port][/db]/KEY[?stream|?pubsub]`:
+/// a stream or pub/sub channel to follow. A channel with `*`, `?` or `[` is
+/// a pattern; a key really ending in `?stream` or `?pubsub` needs `%3F`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedisUrl {
+    pub host: String,
+    pub port: u16,
+    pub db: Option<u32>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub key: String,
+    pub kind: Kind,
+}
+
+impl FromStr for RedisUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rest = s.strip_prefix("redis://").ok_or_else(|| anyhow!("Expected redis://host/KEY, got {}", url_without_userinfo(s)))?;
+        // Any other `?` belongs to the key, as in a pattern like `app?`.
+        let (rest, kind) = match (rest.strip_suffix("?stream"), rest.strip_suffix("?pubsub")) {
+            (Some(rest), _) => (rest, Kind::Stream),
+            (_, Some(rest)) => (rest, Kind::Channel),
+            _ => (rest, Kind::Auto),
+        };
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (credentials, hostport) = match authority.rsplit_once('@') {
+            Some((credentials, hostport)) => (Some(credentials), hostport),
+            None => (None, authority),
+        };
+        let (user, password) = match credentials.map(|c| c.split_once(':').unwrap_or(("", c))) {
+            Some((user, password)) => ((!user.is_empty()).then(|| unescape(user)), Some(unescape(password))),
+            None => (None, None),
+        };
+        let (host, port) = match hostport.rsplit_once(':').filter(|(_, port)| !port.contains(']')) {
+            Some((host, port)) => (host, port.parse().map_err(|_| anyhow!("Bad redis port: {}", port))?),
+            None => (hostport, DEFAULT_PORT),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let (db, key) = match path.split_once('/') {
+            Some((db, key)) if db.parse::<u32>().is_ok() => (db.parse().ok(), key),
+            _ => (None, path),
+        };
+        if host.is_empty() || key.is_empty() {
+            bail!("Name the stream or channel to read: redis://host/KEY");
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            db,
+            user,
+            password,
+            key: unescape(key),
+            kind,
+        })
+    }
+}
+
+/// A RESP reply.
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Status(String),
+    Error(String),
+    Int(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+impl Reply {
+    fn text(&self) -> Option<String> {
+        match self {
+            Reply::Status(s) => Some(s.clone()),
+            Reply::Bulk(Some(bytes)) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            Reply::Int(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    fn items(self) -> Vec<Reply> {
+        match self {
+            Reply::Array(Some(items)) => items,
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn encode(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+fn read_reply(reader: &mut impl BufRead) -> io::Result<Reply> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("bad reply: {}", line));
+    let (kind, body) = line.split_at_checked(1).ok_or_else(bad)?;
+    let len = || body.parse::<i64>().map_err(|_| bad());
+    Ok(match kind {
+        "+" => Reply::Status(body.to_string()),
+        "-" => Reply::Error(body.to_string()),
+        ":" => Reply::Int(len()?),
+        "$" if len()? < 0 => Reply::Bulk(None),
+        "$" if len()? > MAX_BULK => return Err(bad()),
+        "$" => {
+            let mut bytes = vec![0; len()? as usize + 2];
+            reader.read_exact(&mut bytes)?;
+            bytes.truncate(bytes.len() - 2);
+            Reply::Bulk(Some(bytes))
+        }
+        "*" if len()? < 0 => Reply::Array(None),
+        "*" => Reply::Array(Some((0..len()?).map(|_| read_reply(reader)).collect::<io::Result<_>>()?)),
+        _ => return Err(bad()),
+    })
+}
+
+/// A stream entry as one line: the value when it has a single field, else
+/// the fields as a JSON object.
+fn entry_line(fields: Vec<Reply>) -> String {
+    let mut texts = fields.iter().filter_map(Reply::text);
+    let mut pairs = Vec::new();
+    while let (Some(name), Some(value)) = (texts.next(), texts.next()) {
+        pairs.push((name, value));
+    }
+    match pairs.as_slice() {
+        [(_, value)] => value.clone(),
+        _ => {
+            let object: serde_json::Map<String, serde_json::Value> =
+                pairs.into_iter().map(|(name, value)| (name, serde_json::Value::String(value))).collect();
+            serde_json::Value::Object(object).to_string()
+        }
+    }
+}
+
+/// The `(ID, line)` of each entry in an `XREAD` reply for one stream.
+fn stream_entries(reply: Reply) -> Vec<(String, String)> {
+    let Some(stream) = reply.items().into_iter().next() else {
+        return Vec::new();
+    };
+    let Some(entries) = stream.items().into_iter().nth(1) else {
+        return Vec::new();
+    };
+    entries
+        .items()
+        .into_iter()
+        .filter_map(|entry| {
+            let mut parts = entry.items().into_iter();
+            let id = parts.next()?.text()?;
+            Some((id, entry_line(parts.next()?.items())))
+        })
+        .collect()
+}
+
+/// A connection following `url`'s key.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    key: String,
+    /// The last stream entry ID read; `None` for channels.
+    position: Option<String>,
+    pinged: bool,
+}
+
+impl Connection {
+    /// Connect and start reading: a stream after entry `position` (`$` for
+    /// new entries only), a channel from now.
+    pub fn open(url: &RedisUrl, position: &str) -> Result<Self> {
+        let addr = (url.host.as_str(), url.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("Cannot resolve {}", url.host))?;
+        let writer = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        writer.set_read_timeout(Some(BLOCK * 2))?;
+        let mut feed = Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            key: url.key.clone(),
+            position: None,
+            pinged: false,
+        };
+        match (&url.user, &url.password) {
+            (Some(user), Some(password)) => feed.call(&["AUTH", user, password])?,
+            (None, Some(password)) => feed.call(&["AUTH", password])?,
+            _ => Reply::Status(String::new()),
+        };
+        if let Some(db) = url.db {
+            feed.call(&["SELECT", &db.to_string()])?;
+        }
+        let stream = match url.kind {
+            Kind::Auto => feed.call(&["TYPE", &url.key])?.text().as_deref() == Some("stream"),
+            kind => kind == Kind::Stream,
+        };
+        if stream {
+            let position = match position {
+                "$" => feed.last_id()?.unwrap_or_else(|| "0".to_string()),
+                position => position.to_string(),
+            };
+            feed.position = Some(position);
+        } else {
+            let pattern = url.key.contains(['*', '?', '[']);
+            feed.send(&[if pattern { "PSUBSCRIBE" } else { "SUBSCRIBE" }, &url.key])?;
+            feed.writer.set_read_timeout(Some(BLOCK))?;
+        }
+        Ok(feed)
+    }
+
+    fn send(&mut self, args: &[&str]) -> Result<()> {
+        self.writer.write_all(&encode(args))?;
+        Ok(())
+    }
+
+    fn call(&mut self, args: &[&str]) -> Result<Reply> {
+        self.send(args)?;
+        match read_reply(&mut self.reader)? {
+            Reply::Error(e) => Err(anyhow!("{}: {}", args[0], e)),
+            reply => Ok(reply),
+        }
+    }
+
+    /// ID of the newest entry of the stream, so reading starts after it.
+    fn last_id(&mut self) -> Result<Option<String>> {
+        let newest = self.call(&["XREVRANGE", &self.key.clone(), "+", "-", "COUNT", "1"])?;
+        Ok(newest.items().into_iter().next().and_then(|entry| entry.items().into_iter().next()?.text()))
+    }
+
+    /// The next channel message; `None` when a quiet spell passed, after
+    /// which the server is pinged. A second quiet spell without the pong
+    /// means the connection is gone.
+    fn next_message(&mut self) -> Result<Option<Message>> {
+        match self.reader.fill_buf() {
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                if self.pinged {
+                    bail!("no answer from the server");
+                }
+                self.pinged = true;
+                self.send(&["PING"])?;
+                return Ok(None);
+            }
+            result => {
+                result?;
+            }
+        }
+        self.pinged = false;
+        let items = match read_reply(&mut self.reader)? {
+            Reply::Error(e) => bail!("{}", e),
+            reply => reply.items(),
+        };
+        let texts: Vec<String> = items.iter().filter_map(Reply::text).collect();
+        Ok(match texts.as_slice() {
+            [kind, channel, payload] if kind == "message" => Some(Message {
+                source: channel.clone(),
+                content: payload.clone(),
+            }),
+            [kind, _, channel, payload] if kind == "pmessage" => Some(Message {
+                source: channel.clone(),
+                content: payload.clone(),
+            }),
+            _ => None,
+        })
+    }
+}
+
+impl Feed for Connection {
+    fn position(&self) -> Option<String> {
+        self.position.clone()
+    }
+
+    fn next_batch(&mut self, wait: bool) -> Result<Vec<Message>> {
+        let Some(position) = self.position.clone() else {
+            return if wait { self.next_message().map(|m| m.into_iter().collect()) } else { Ok(Vec::new()) };
+        };
+        let (batch, block) = (BATCH.to_string(), BLOCK.as_millis().to_string());
+        let mut args = vec!["XREAD", "COUNT", &batch];
+        if wait {
+            args.extend(["BLOCK", &block]);
+        }
+        let key = self.key.clone();
+        args.extend(["STREAMS", &key, &position]);
+        let entries = stream_entries(self.call(&args)?);
+        if let Some((id, _)) = entries.last() {
+            self.position = Some(id.clone());
+        }
+        Ok(entries.into_iter().map(|(_, content)| Message { source: key.clone(), content }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redis_url_and_replies() {
+        let url: RedisUrl = "redis://:p%40ss@cache:6380/2/app:logs".parse().unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.db), ("cache", 6380, Some(2)));
+        assert_eq!((url.user, url.password.as_deref(), url.key.as_str()), (None, Some("p@ss"), "app:logs"));
+        let url: RedisUrl = "redis://[::1]/logs.*?pubsub".parse().unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.kind), ("::1", DEFAULT_PORT, Kind::Channel));
+        assert!("redis://host".parse::<RedisUrl>().is_err());
+        let url: RedisUrl = "redis://host/app-?.log".parse().unwrap();
+        assert_eq!((url.key.as_str(), url.kind), ("app-?.log", Kind::Auto));
+        assert!(read_reply(&mut &b"$999999999999\r\n"[..]).is_err());
+
+        let xread = b"*1\r\n*2\r\n$4\r\nlogs\r\n*2\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$4\r\nline\r\n$5\r\nhello\r\n\
+            *2\r\n$3\r\n2-0\r\n*4\r\n$5\r\nlevel\r\n$4\r\nwarn\r\n$3\r\nmsg\r\n$4\r\ndisk\r\n";
+        let reply = read_reply(&mut &xread[..]).unwrap();
+        assert_eq!(
+            stream_entries(reply),
+            [("1-0".to_string(), "hello".to_string()), ("2-0".to_string(), r#"{"level":"warn","msg":"disk"}"#.to_string())]
+        );
+        assert_eq!(read_reply(&mut &b"*-1\r\n"[..]).unwrap(), Reply::Array(None));
+        assert_eq!(encode(&["GET", "k"]), b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n");
+    }
+}
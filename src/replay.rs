@@ -0,0 +1,200 @@
+use crate::bundle::{is_bundle, Bundle};
+use crate::capture;
+use crate::source::{read_records, SourceEvent};
+use crate::timestamp::parse_event_time;
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeDelta};
+use fancy_regex::Regex;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const SPEEDS: [u32; 3] = [1, 2, 10];
+const TICK: Duration = Duration::from_millis(20);
+
+/// Re-emits a saved capture on a virtual clock that follows the records'
+/// timestamps, scaled by the playback speed.
+pub struct Replay {
+    records: VecDeque<(String, Option<DateTime<Local>>)>,
+    clock: Option<DateTime<Local>>,
+    speed_idx: usize,
+    pub paused: bool,
+}
+
+pub type ReplayHandle = Arc<Mutex<Replay>>;
+
+impl Replay {
+    /// Records without a timestamp (continuations, untimed lines) play
+    /// together with the record before them.
+    pub fn new(records: Vec<(String, Option<DateTime<Local>>)>) -> Self {
+        let first = records.iter().find_map(|(_, t)| *t);
+        let mut last = first;
+        let records = records
+            .into_iter()
+            .map(|(content, time)| {
+                if time.is_some() {
+                    last = time;
+                }
+                (content, last)
+            })
+            .collect();
+        Self {
+            records,
+            clock: first,
+            speed_idx: 0,
+            paused: false,
+        }
+    }
+
+    pub fn speed(&self) -> u32 {
+        SPEEDS[self.speed_idx]
+    }
+
+    pub fn cycle_speed(&mut self) {
+        self.speed_idx = (self.speed_idx + 1) % SPEEDS.len();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Jump the clock to the next record, skipping a quiet gap.
+    pub fn skip_gap(&mut self) {
+        if let Some((_, Some(time))) = self.records.front() {
+            self.clock = Some(*time);
+        }
+    }
+
+    /// Move the clock forward by `elapsed` wall time and return the records
+    /// that became due.
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<String> {
+        if self.paused {
+            return Vec::new();
+        }
+        let scaled = TimeDelta::from_std(elapsed * self.speed()).unwrap_or(TimeDelta::zero());
+        if let Some(clock) = self.clock.as_mut() {
+            *clock += scaled;
+        }
+        let mut due = Vec::new();
+        while let Some((_, time)) = self.records.front() {
+            match (time, self.clock) {
+                (Some(time), Some(clock)) if *time > clock => break,
+                _ => due.push(self.records.pop_front().unwrap().0),
+            }
+        }
+        due
+    }
+
+    pub fn label(&self) -> String {
+        if self.paused {
+            format!("[REPLAY ⏸ {} left]", self.remaining())
+        } else if self.records.is_empty() {
+            "[REPLAY done]".to_string()
+        } else {
+            format!("[REPLAY {}× {} left]", self.speed(), self.remaining())
+        }
+    }
+}
+
+fn load_records(path: &Path, line_start_regex: Option<Arc<Regex>>) -> Result<Vec<(String, Option<DateTime<Local>>)>> {
+    if is_bundle(path) {
+        let bundle = Bundle::read(path)?;
+        return Ok(bundle
+            .log_lines()
+            .into_iter()
+            .map(|l| (l.content, Some(l.timestamp)))
+            .collect());
+    }
+    if capture::is_capture(path) {
+        return capture::read_lines(path);
+    }
+    Ok(read_records(path, line_start_regex)?
+        .into_iter()
+        .map(|content| {
+            let time = parse_event_time(&content);
+            (content, time)
+        })
+        .collect())
+}
+
+/// Load `path` (a log file, using its event times, or a bundle or
+/// `--capture` file, using its receipt times) and play it into `tx`.
+pub fn start_replay(
+    path: &Path,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<ReplayHandle> {
+    let records = load_records(path, line_start_regex)?;
+    let name: Option<Arc<str>> = path.file_name().map(|n| Arc::from(n.to_string_lossy().as_ref()));
+    let handle = Arc::new(Mutex::new(Replay::new(records)));
+    let replay = handle.clone();
+
+    thread::spawn(move || {
+        let mut last = Instant::now();
+        loop {
+            thread::sleep(TICK);
+            let now = Instant::now();
+            let (due, done) = {
+                let Ok(mut replay) = replay.lock() else {
+                    return;
+                };
+                let due = replay.advance(now - last);
+                (due, replay.remaining() == 0)
+            };
+            last = now;
+            for content in due {
+                if tx.send(SourceEvent::Line(content, name.clone())).is_err() {
+                    return;
+                }
+            }
+            if done {
+                let _ = tx.send(SourceEvent::SystemLine("── replay finished ──".to_string()));
+                return;
+            }
+        }
+    });
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> Option<DateTime<Local>> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).map(|t| t.with_timezone(&Local))
+    }
+
+    #[test]
+    fn test_plays_at_scaled_speed() {
+        let mut replay = Replay::new(vec![
+            ("a".to_string(), at(0)),
+            ("a2".to_string(), None),
+            ("b".to_string(), at(10)),
+            ("c".to_string(), at(30)),
+        ]);
+        assert_eq!(replay.advance(Duration::ZERO), vec!["a", "a2"]);
+        assert!(replay.advance(Duration::from_secs(5)).is_empty());
+        replay.cycle_speed();
+        assert_eq!(replay.advance(Duration::from_secs(3)), vec!["b"]);
+
+        replay.toggle_pause();
+        assert!(replay.advance(Duration::from_secs(60)).is_empty());
+        replay.toggle_pause();
+        replay.skip_gap();
+        assert_eq!(replay.advance(Duration::ZERO), vec!["c"]);
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    #[test]
+    fn test_untimed_capture_plays_at_once() {
+        let mut replay = Replay::new(vec![("x".to_string(), None), ("y".to_string(), None)]);
+        assert_eq!(replay.advance(Duration::ZERO).len(), 2);
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone};
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+static TIME_REF: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{(\w+):time\}").unwrap());
+
+/// A display-time rewrite: every match of `regex` is replaced by `template`.
+#[derive(Clone, Debug)]
+pub struct RewriteRule {
+    regex: Regex,
+    template: String,
+}
+
+/// Parse sed-style rules: `s/regex/replacement/`, separated by `;` or spaces.
+/// Any character after `s` may be the delimiter (`s|a/b|c|`). The replacement
+/// may use `$1` / `${name}`, and `${1:time}` to show a Unix epoch (seconds or
+/// milliseconds) capture as local time.
+pub fn parse_rules(input: &str) -> Result<Vec<RewriteRule>> {
+    let mut rules = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ';').is_some() {}
+        let Some(c) = chars.next() else {
+            break;
+        };
+        if c != 's' {
+            return Err(anyhow!("Expected s/regex/replacement/, found '{}'", c));
+        }
+        let delim = chars.next().ok_or_else(|| anyhow!("Missing delimiter after 's'"))?;
+        if delim.is_alphanumeric() || delim.is_whitespace() || delim == '\\' {
+            return Err(anyhow!("Invalid delimiter '{}'", delim));
+        }
+        let pattern = read_part(&mut chars, delim)?;
+        let template = read_part(&mut chars, delim)?;
+        let regex = Regex::new(&pattern).map_err(|e| anyhow!("{}", e))?;
+        rules.push(RewriteRule { regex, template });
+    }
+    Ok(rules)
+}
+
+fn read_part(chars: &mut std::iter::Peekable<std::str::Chars>, delim: char) -> Result<String> {
+    let mut part = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&delim) => {
+                part.push(delim);
+                chars.next();
+            }
+            '\\' => {
+                part.push('\\');
+                if let Some(next) = chars.next() {
+                    part.push(next);
+                }
+            }
+            c if c == delim => return Ok(part),
+            c => part.push(c),
+        }
+    }
+    Err(anyhow!("Unterminated rule, expected '{}'", delim))
+}
+
+pub fn apply_rules(content: &str, rules: &[RewriteRule]) -> String {
+    let mut text = content.to_string();
+    for rule in rules {
+        if !rule.regex.is_match(&text) {
+            continue;
+        }
+        text = rule
+            .regex
+            .replace_all(&text, |caps: &Captures| expand(caps, &rule.template))
+            .into_owned();
+    }
+    text
+}
+
+fn expand(caps: &Captures, template: &str) -> String {
+    let template = TIME_REF.replace_all(template, |r: &Captures| {
+        let name = &r[1];
+        let value = match name.parse::<usize>() {
+            Ok(i) => caps.get(i),
+            Err(_) => caps.name(name),
+        };
+        value
+            .map(|m| format_epoch(m.as_str()).unwrap_or_else(|| m.as_str().to_string()))
+            .unwrap_or_default()
+            .replace('$', "$$")
+    });
+    let mut out = String::new();
+    caps.expand(&template, &mut out);
+    out
+}
+
+fn format_epoch(digits: &str) -> Option<String> {
+    let value: i64 = digits.parse().ok()?;
+    match digits.len() {
+        10 => Local
+            .timestamp_opt(value, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+        13 => Local
+            .timestamp_millis_opt(value)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shorten_package_and_strip_prefix() {
+        let rules = parse_rules(r"s/com\.example\.internal\.service\./c.e.i.s./; s|^\[pod-\w+\] ||").unwrap();
+        assert_eq!(
+            apply_rules("[pod-ab12] com.example.internal.service.Foo started", &rules),
+            "c.e.i.s.Foo started"
+        );
+    }
+
+    #[test]
+    fn test_epoch_to_time() {
+        let rules = parse_rules(r"s/ts=(\d{13})/ts=${1:time}/").unwrap();
+        let expected = Local
+            .timestamp_millis_opt(1_700_000_000_123)
+            .unwrap()
+            .format("%Y-%m-%d %H:%M:%S%.3f")
+            .to_string();
+        assert_eq!(apply_rules("ts=1700000000123 ok", &rules), format!("ts={} ok", expected));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_rules("s/a/b").is_err());
+        assert!(parse_rules("x/a/b/").is_err());
+        assert!(parse_rules("s/(/b/").is_err());
+        assert!(parse_rules("  ").unwrap().is_empty());
+        let rules = parse_rules(r"s/a\/b/c/").unwrap();
+        assert_eq!(apply_rules("a/b", &rules), "c");
+    }
+}
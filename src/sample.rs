@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Which incoming lines make it into the buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    #[default]
+    Off,
+    /// Every Nth line: `1/N`.
+    OneIn(u64),
+    /// A uniform random N of each second's lines: `N/s`.
+    PerSecond(usize),
+}
+
+impl FromStr for Sampling {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let number = |n: &str| n.trim().parse::<u64>().ok().filter(|&n| n > 0);
+        if s.is_empty() || s == "off" {
+            return Ok(Sampling::Off);
+        }
+        if let Some(n) = s.strip_suffix("/s").and_then(number) {
+            return Ok(Sampling::PerSecond(n as usize));
+        }
+        match number(s.strip_prefix("1/").unwrap_or(s)) {
+            Some(1) => Ok(Sampling::Off),
+            Some(n) => Ok(Sampling::OneIn(n)),
+            None => Err(anyhow!("Expected 1/N, N/s or off, not {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sampling::Off => write!(f, "off"),
+            Sampling::OneIn(n) => write!(f, "1/{}", n),
+            Sampling::PerSecond(n) => write!(f, "{}/s", n),
+        }
+    }
+}
+
+static SAMPLING: OnceLock<Sampling> = OnceLock::new();
+
+/// Sampling new buffers start with, from `--sample`.
+pub fn set_sampling(sampling: Sampling) {
+    let _ = SAMPLING.set(sampling);
+}
+
+pub fn sampling() -> Sampling {
+    SAMPLING.get().copied().unwrap_or_default()
+}
+
+/// Thins a stream of lines per `Sampling`, counting exactly what it drops.
+/// Per-second samples are held until their second is over.
+#[derive(Clone, Debug)]
+pub struct Sampler<T> {
+    pub mode: Sampling,
+    /// Lines offered.
+    pub seen: u64,
+    /// Lines dropped.
+    pub dropped: u64,
+    /// The second being sampled and how many of its lines came.
+    second: i64,
+    offered: usize,
+    /// Kept lines of `second` with their arrival order.
+    reservoir: Vec<(usize, T)>,
+    rng: u64,
+}
+
+impl<T> Sampler<T> {
+    pub fn new(mode: Sampling) -> Self {
+        Self {
+            mode,
+            seen: 0,
+            dropped: 0,
+            second: i64::MIN,
+            offered: 0,
+            reservoir: Vec::new(),
+            rng: RandomState::new().hash_one(0u64) | 1,
+        }
+    }
+
+    /// xorshift64; good enough to pick lines.
+    fn random(&mut self, below: usize) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng % below as u64) as usize
+    }
+
+    /// Take a line that arrived in `second`; returns the lines to keep now.
+    pub fn offer(&mut self, item: T, second: i64) -> Vec<T> {
+        self.seen += 1;
+        match self.mode {
+            Sampling::Off => vec![item],
+            Sampling::OneIn(n) => {
+                if (self.seen - 1).is_multiple_of(n) {
+                    vec![item]
+                } else {
+                    self.dropped += 1;
+                    Vec::new()
+                }
+            }
+            Sampling::PerSecond(size) => {
+                let done = self.flush(second);
+                self.offered += 1;
+                if self.reservoir.len() < size {
+                    self.reservoir.push((self.offered, item));
+                } else {
+                    self.dropped += 1;
+                    let slot = self.random(self.offered);
+                    if slot < size {
+                        self.reservoir[slot] = (self.offered, item);
+                    }
+                }
+                done
+            }
+        }
+    }
+
+    /// The sample of the second before `second`, in arrival order, once that
+    /// second is over.
+    pub fn flush(&mut self, second: i64) -> Vec<T> {
+        if second == self.second {
+            return Vec::new();
+        }
+        self.second = second;
+        self.offered = 0;
+        let mut sample = std::mem::take(&mut self.reservoir);
+        sample.sort_by_key(|(order, _)| *order);
+        sample.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// `1/10, 1234 dropped`.
+    pub fn label(&self) -> String {
+        format!("{}, {} dropped", self.mode, self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_modes() {
+        assert_eq!("1/10".parse::<Sampling>().unwrap(), Sampling::OneIn(10));
+        assert_eq!("25".parse::<Sampling>().unwrap(), Sampling::OneIn(25));
+        assert_eq!("500/s".parse::<Sampling>().unwrap(), Sampling::PerSecond(500));
+        assert_eq!("off".parse::<Sampling>().unwrap(), Sampling::Off);
+        assert!("1/0".parse::<Sampling>().is_err());
+
+        let mut every = Sampler::new(Sampling::OneIn(3));
+        let kept: Vec<i32> = (0..10).flat_map(|i| every.offer(i, 0)).collect();
+        assert_eq!(kept, [0, 3, 6, 9]);
+        assert_eq!((every.seen, every.dropped), (10, 6));
+
+        let mut reservoir = Sampler::new(Sampling::PerSecond(4));
+        let early: Vec<i32> = (0..100).flat_map(|i| reservoir.offer(i, 7)).collect();
+        assert!(early.is_empty());
+        let sample = reservoir.offer(100, 8);
+        assert_eq!(sample.len(), 4);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(reservoir.dropped, 96);
+        assert_eq!(reservoir.flush(9), [100]);
+        assert!(reservoir.flush(9).is_empty());
+        assert_eq!(reservoir.label(), "4/s, 96 dropped");
+    }
+}
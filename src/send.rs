@@ -0,0 +1,288 @@
+use crate::discovery::discover;
+use crate::source::TOKEN_PREFIX;
+use crate::tls::client_config;
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DISCOVER_TIMEOUT: Duration = Duration::from_secs(5);
+/// A batch is written early once it grows this large.
+const MAX_BATCH_BYTES: usize = 64 * 1024;
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Args)]
+pub struct SendArgs {
+    #[arg(help = "Viewer to send to, as host:port")]
+    target: Option<String>,
+
+    #[arg(short = 'f', long = "file", help = "File to send (reads from stdin if not provided)")]
+    file: Option<PathBuf>,
+
+    #[arg(
+        long = "discover",
+        conflicts_with = "target",
+        help = "Find a viewer started with --advertise on the local network"
+    )]
+    discover: bool,
+
+    #[arg(long = "token", help = "Token the viewer was started with")]
+    token: Option<String>,
+
+    #[arg(long = "tls", help = "Connect over TLS")]
+    tls: bool,
+
+    #[arg(
+        long = "ca",
+        requires = "tls",
+        help = "PEM certificate to trust, e.g. the viewer's self-signed one"
+    )]
+    ca: Option<PathBuf>,
+
+    #[arg(
+        long = "batch-ms",
+        default_value_t = 50,
+        help = "Collect lines for up to this long before writing them"
+    )]
+    batch_ms: u64,
+
+    #[arg(long = "reconnect", help = "Keep retrying when the viewer is down or the connection drops")]
+    reconnect: bool,
+}
+
+enum Connection {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Connection {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Connection::Plain(stream) => stream,
+            Connection::Tls(stream) => stream.as_mut(),
+        }
+    }
+
+    /// End the stream cleanly so the viewer doesn't report a read error.
+    fn close(mut self) -> io::Result<()> {
+        if let Connection::Tls(stream) = &mut self {
+            stream.conn.send_close_notify();
+        }
+        self.writer().flush()
+    }
+}
+
+/// Strip the port and IPv6 brackets: `[fe80::1]:9999` -> `fe80::1`.
+fn host_of(target: &str) -> &str {
+    let host = match target.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => host,
+        _ => target,
+    };
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn connect(args: &SendArgs, tls: Option<&Arc<ClientConfig>>) -> Result<Connection> {
+    let (host, addr) = if args.discover {
+        let (name, addr) = discover(DISCOVER_TIMEOUT)?;
+        eprintln!("Found {} at {}", name, addr);
+        (addr.ip().to_string(), addr)
+    } else {
+        let target = args
+            .target
+            .as_deref()
+            .ok_or_else(|| anyhow!("Give a host:port to send to, or --discover"))?;
+        let addr = target
+            .to_socket_addrs()
+            .with_context(|| format!("Cannot resolve {}", target))?
+            .next()
+            .ok_or_else(|| anyhow!("No address for {}", target))?;
+        (host_of(target).to_string(), addr)
+    };
+
+    let stream = TcpStream::connect(addr).with_context(|| format!("Cannot connect to {}", addr))?;
+    stream.set_nodelay(true)?;
+    let mut conn = match tls {
+        Some(config) => {
+            let name = ServerName::try_from(host)?;
+            Connection::Tls(Box::new(StreamOwned::new(
+                ClientConnection::new(config.clone(), name)?,
+                stream,
+            )))
+        }
+        None => Connection::Plain(stream),
+    };
+    if let Some(token) = &args.token {
+        writeln!(conn.writer(), "{}{}", TOKEN_PREFIX, token)?;
+    }
+    Ok(conn)
+}
+
+/// Read the input on its own thread so lines keep queueing while
+/// reconnecting.
+fn read_lines(file: Option<&PathBuf>) -> Result<Receiver<String>> {
+    let input: Box<dyn BufRead + Send> = match file {
+        Some(path) => Box::new(BufReader::new(
+            File::open(path).with_context(|| format!("Cannot open {}", path.display()))?,
+        )),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in input.split(b'\n') {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = String::from_utf8_lossy(&line).trim_end_matches('\r').to_string();
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// Write lines in batches until the input ends. When a write fails, the
+/// lines written whole are dropped from `batch` and the rest stays to be
+/// sent on the next connection; a line cut off mid-write is sent again in
+/// full.
+fn pump(out: &mut dyn Write, lines: &Receiver<String>, batch: &mut String, window: Duration) -> io::Result<()> {
+    loop {
+        let mut done = false;
+        if batch.is_empty() {
+            match lines.recv() {
+                Ok(line) => {
+                    batch.push_str(&line);
+                    batch.push('\n');
+                }
+                Err(_) => return Ok(()),
+            }
+        }
+        let deadline = Instant::now() + window;
+        while batch.len() < MAX_BATCH_BYTES {
+            match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => {
+                    batch.push_str(&line);
+                    batch.push('\n');
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        let mut written = 0;
+        let result = write_counted(out, batch.as_bytes(), &mut written).and_then(|_| out.flush());
+        if let Err(e) = result {
+            let sent = batch[..written].rfind('\n').map_or(0, |i| i + 1);
+            batch.drain(..sent);
+            return Err(e);
+        }
+        batch.clear();
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// `write_all`, counting the bytes written in `written` even when it fails.
+fn write_counted(out: &mut dyn Write, mut bytes: &[u8], written: &mut usize) -> io::Result<()> {
+    while !bytes.is_empty() {
+        match out.write(bytes) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => {
+                *written += n;
+                bytes = &bytes[n..];
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// `logviewer send`: ship a file or stdin to a viewer listening with `-l`.
+pub fn run(args: SendArgs) -> Result<()> {
+    let tls = if args.tls {
+        Some(client_config(args.ca.as_deref())?)
+    } else {
+        None
+    };
+    let lines = read_lines(args.file.as_ref())?;
+    let window = Duration::from_millis(args.batch_ms);
+    let mut batch = String::new();
+    let mut delay = Duration::from_secs(1);
+
+    loop {
+        let mut conn = match connect(&args, tls.as_ref()) {
+            Ok(conn) => {
+                delay = Duration::from_secs(1);
+                conn
+            }
+            Err(e) if args.reconnect => {
+                eprintln!("{:#}; retrying in {}s", e, delay.as_secs());
+                thread::sleep(delay);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        match pump(conn.writer(), &lines, &mut batch, window) {
+            Ok(()) => return Ok(conn.close()?),
+            Err(e) if args.reconnect => eprintln!("Connection lost: {}; reconnecting", e),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_of() {
+        assert_eq!(host_of("example.com:9999"), "example.com");
+        assert_eq!(host_of("[fe80::1]:9999"), "fe80::1");
+        assert_eq!(host_of("192.168.1.5:9999"), "192.168.1.5");
+    }
+
+    /// Takes `room` bytes, then fails.
+    struct Broken {
+        room: usize,
+    }
+
+    impl Write for Broken {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let n = bytes.len().min(self.room);
+            self.room -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_write_keeps_unsent_lines() {
+        let (tx, rx) = mpsc::channel();
+        for line in ["one", "two", "three"] {
+            tx.send(line.to_string()).unwrap();
+        }
+        drop(tx);
+        let mut batch = String::new();
+        assert!(pump(&mut Broken { room: 6 }, &rx, &mut batch, Duration::ZERO).is_err());
+        assert_eq!(batch, "two\nthree\n");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::state::data_path;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const SESSION_FILE: &str = ".logviewer-session";
+
+/// A note as stored on disk. The line's content is kept so the note is only
+/// re-attached when the same line shows up at the same position again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedNote {
+    pub line: usize,
+    pub content: String,
+    pub text: String,
+}
+
+/// A pinned line as stored on disk, re-pinned like a note is re-attached.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedPin {
+    pub line: usize,
+    pub content: String,
+}
+
+/// Investigation data tied to the log buffer rather than to the viewer's
+/// settings.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default)]
+    pub notes: Vec<SavedNote>,
+    #[serde(default)]
+    pub pins: Vec<SavedPin>,
+}
+
+impl Session {
+    pub fn load() -> Self {
+        let path = session_path();
+        if path.exists() {
+            if let Ok(content) = fs::read_to_string(&path) {
+                if let Ok(session) = serde_json::from_str(&content) {
+                    return session;
+                }
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        if let Ok(content) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(session_path(), content);
+        }
+    }
+}
+
+fn session_path() -> PathBuf {
+    data_path(SESSION_FILE, "session.json")
+}
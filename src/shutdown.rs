@@ -0,0 +1,76 @@
+use crate::source::group_digits;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Set from the SIGINT / SIGTERM handler; the event loop checks it every
+/// poll and quits the same way `q` does.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_signal(_: nix::libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Turn SIGINT and SIGTERM into a graceful quit. Ctrl-C in the TUI arrives as
+/// a key instead, since raw mode disables the terminal's signal keys.
+pub fn install_signal_handlers() {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+        let action = SigAction::new(SigHandler::Handler(on_signal), SaFlags::SA_RESTART, SigSet::empty());
+        for signal in [Signal::SIGINT, Signal::SIGTERM] {
+            // SAFETY: the handler only stores to an atomic.
+            let _ = unsafe { sigaction(signal, &action) };
+        }
+    }
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Printed once the terminal is restored.
+pub struct Summary {
+    pub ingested: usize,
+    /// Lines discarded by clearing the buffer.
+    pub dropped: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "logviewer: {} lines ingested, {} dropped in {}",
+            group_digits(self.ingested),
+            group_digits(self.dropped),
+            format_duration(self.elapsed)
+        )
+    }
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary {
+            ingested: 12430,
+            dropped: 200,
+            elapsed: Duration::from_secs(192),
+        };
+        assert_eq!(summary.to_string(), "logviewer: 12,430 lines ingested, 200 dropped in 3m12s");
+        assert_eq!(format_duration(Duration::from_secs(7260)), "2h01m");
+    }
+}
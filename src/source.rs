@@ -1,41 +1,254 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, TimeDelta};
+use crate::capture::{Capture, Recorder};
+use crate::checkpoint;
+use crate::cloudwatch::{self, CloudWatchTarget};
+use crate::elastic::{self, EsQuery};
+use crate::glob::glob_match;
+use crate::logcat;
+use crate::loki::{self, LokiQuery};
+use crate::nats::{self, NatsUrl};
+use crate::redis::{self, RedisUrl};
+use crate::split;
 use fancy_regex::Regex;
+use flate2::read::GzDecoder;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use rustls::ServerConfig;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::Arc;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, OnceLock};
 use std::thread;
 use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead};
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio_rustls::TlsAcceptor;
+
+/// Events queued for the UI before sources stop reading, so a busy UI
+/// slows senders down instead of growing the buffer without bound.
+pub const SOURCE_CAPACITY: usize = 10_000;
+
+/// Network events queued between the clients and the UI's channel.
+const NETWORK_CAPACITY: usize = 1024;
+
+/// Wait before opening a pipe again after it failed to open.
+const PIPE_RETRY: Duration = Duration::from_secs(1);
+
+/// Time a client gets to finish the TLS handshake and send its token.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First line a client sends when the listener requires a token.
+pub const TOKEN_PREFIX: &str = "logviewer-token ";
 
 pub enum LogSource {
-    File(PathBuf),
+    File(PathBuf, FileOptions),
     Stdin,
-    Network(u16),
+    Network(Vec<ListenPort>, ListenOptions),
+    /// The output of a program that follows some logs.
+    Command(CommandSource),
+    /// Every file in a directory whose name matches a glob, including ones
+    /// created later.
+    WatchDir(PathBuf, String, FileOptions),
+}
+
+/// A program or service whose logs are followed, from `since` ago if given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandSource {
+    pub program: Program,
+    pub since: Option<Since>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Program {
+    /// A Docker container's logs, followed with `docker logs -f`.
+    Docker(String),
+    /// An Android device's log, `adb logcat`, from the device with this
+    /// serial or the only one connected.
+    Adb(Option<String>),
+    /// The macOS unified log, `log stream --style ndjson`, optionally
+    /// narrowed by a `--predicate`.
+    OsLog(Option<String>),
+    /// A Redis stream or pub/sub channel, resumed after the last entry read
+    /// when the connection drops.
+    Redis(RedisUrl),
+    /// Messages published to a NATS subject.
+    Nats(NatsUrl, String),
+    /// A CloudWatch Logs group, polled through the AWS CLI.
+    CloudWatch(CloudWatchTarget),
+    /// The entries a Grafana Loki query finds, polled for new ones.
+    Loki(LokiQuery),
+    /// The documents an Elasticsearch or OpenSearch query finds, optionally
+    /// polled for new ones.
+    Elastic(EsQuery),
+}
+
+/// Furthest back `--since` reaches: ten years.
+const MAX_SINCE_SECS: u64 = 10 * 365 * 86400;
+
+/// How far back `--since` reads a command's history: `90s`, `30m`, `1h`
+/// or `2d`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Since(pub Duration);
+
+impl FromStr for Since {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number.parse().map_err(|_| anyhow!("Invalid duration: {}", s))?;
+        let unit_secs = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            _ => return Err(anyhow!("Invalid duration: {} (expected e.g. 90s, 30m, 1h or 2d)", s)),
+        };
+        match number.checked_mul(unit_secs) {
+            Some(secs) if secs <= MAX_SINCE_SECS => Ok(Self(Duration::from_secs(secs))),
+            _ => Err(anyhow!("Duration too long: {} (at most {}d)", s, MAX_SINCE_SECS / 86400)),
+        }
+    }
+}
+
+impl Since {
+    /// The time that long before now.
+    pub fn start(&self) -> DateTime<Local> {
+        let now = Local::now();
+        TimeDelta::from_std(self.0)
+            .ok()
+            .and_then(|delta| now.checked_sub_signed(delta))
+            .unwrap_or(now)
+    }
+
+    fn docker_arg(&self) -> String {
+        format!("{}s", self.0.as_secs())
+    }
+}
+
+/// A port to listen on, optionally named to tag its lines: `5000` or
+/// `5001=access`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListenPort {
+    pub port: u16,
+    pub name: Option<String>,
+}
+
+impl FromStr for ListenPort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (port, name) = match s.split_once('=') {
+            Some((port, name)) if !name.is_empty() => (port, Some(name.to_string())),
+            Some(_) => return Err(anyhow!("Missing name after '=' in {}", s)),
+            None => (s, None),
+        };
+        let port = port.parse().map_err(|_| anyhow!("Invalid port: {}", port))?;
+        Ok(Self { port, name })
+    }
+}
+
+/// How a file source reads what was written before it started.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FileOptions {
+    /// Read the file's rotated predecessors (`app.log.2.gz`, `app.log.1`)
+    /// oldest first before the file itself, when reading it from the start.
+    pub rotated: bool,
+    pub from: ReadFrom,
+}
+
+/// Where a file source starts reading.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReadFrom {
+    /// Where the last run left off (`.logviewer-offsets`), or the start.
+    #[default]
+    Checkpoint,
+    Start,
+    End,
+}
+
+/// Who may send to a network source, and how.
+#[derive(Clone, Default)]
+pub struct ListenOptions {
+    /// Clients must open with `TOKEN_PREFIX` followed by this token.
+    pub token: Option<String>,
+    pub tls: Option<Arc<ServerConfig>>,
+    /// Where every chunk received is recorded with its time (`--capture`).
+    pub capture: Option<Arc<Capture>>,
 }
 
 pub enum SourceEvent {
-    Line(String),
+    /// A log record and the file name or peer address it came from.
+    Line(String, Option<Arc<str>>),
+    /// A marker like a client connecting, kept in the buffer between records.
     SystemLine(String),
     Error(String),
     Connected(String),
     Disconnected(String),
+    /// A source starts (true) or finishes (false) reading history before
+    /// going live; the view doesn't follow the tail in between.
+    Backfill(bool),
+    /// The records before this one end at this offset of the file; the
+    /// consumer saves it when it gets here, so a restart resumes after the
+    /// lines it took and no further.
+    Checkpoint(Arc<Path>, u64),
+}
+
+/// The merged stream every source sends into.
+pub fn channel() -> (SyncSender<SourceEvent>, Receiver<SourceEvent>) {
+    mpsc::sync_channel(SOURCE_CAPACITY)
+}
+
+/// Runs the network clients, plus the file, pipe and stdin readers on its
+/// blocking pool.
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("logviewer-source")
+            .enable_all()
+            .build()
+            .expect("failed to start the source runtime")
+    })
 }
 
-pub fn start_source(source: LogSource, tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
+pub fn start_source(source: LogSource, tx: SyncSender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
     match source {
-        LogSource::File(path) => start_file_source(path, tx, line_start_regex),
+        LogSource::File(path, options) => start_file_source(path, options, tx, line_start_regex),
         LogSource::Stdin => start_stdin_source(tx, line_start_regex),
-        LogSource::Network(port) => start_network_source(port, tx, line_start_regex),
+        LogSource::Network(ports, options) => start_network_source(ports, options, tx, line_start_regex),
+        LogSource::Command(CommandSource { program, since }) => match program {
+            Program::Docker(container) => start_docker_source(container, since, tx, line_start_regex),
+            Program::Adb(serial) => start_adb_source(serial, since, tx, line_start_regex),
+            Program::OsLog(predicate) => start_oslog_source(predicate, since, tx, line_start_regex),
+            Program::Redis(url) => start_redis_source(url, since, tx),
+            Program::Nats(url, subject) => start_nats_source(url, subject, tx),
+            Program::CloudWatch(target) => start_cloudwatch_source(target, since, tx),
+            Program::Loki(query) => start_loki_source(query, since, tx),
+            Program::Elastic(query) => start_elastic_source(query, since, tx),
+        },
+        LogSource::WatchDir(dir, pattern, options) => start_watch_dir_source(dir, pattern, options, tx, line_start_regex),
     }
 }
 
-fn start_file_source(path: PathBuf, tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
+fn start_file_source(
+    path: PathBuf,
+    options: FileOptions,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    if is_pipe(&path) {
+        return start_pipe_source(path, tx, line_start_regex);
+    }
     let path_clone = path.clone();
-    thread::spawn(move || {
-        if let Err(e) = run_file_source(path_clone, tx.clone(), line_start_regex) {
+    runtime().spawn_blocking(move || {
+        if let Err(e) = run_file_source(path_clone, options, tx.clone(), line_start_regex) {
             let _ = tx.send(SourceEvent::Error(e.to_string()));
         }
     });
@@ -45,68 +258,222 @@ fn start_file_source(path: PathBuf, tx: Sender<SourceEvent>, line_start_regex: O
 struct MultilineAggregator {
     regex: Option<Arc<Regex>>,
     pending: Option<String>,
+    /// Raw lines joined into `pending`.
+    pending_lines: usize,
+    source: Option<Arc<str>>,
 }
 
 impl MultilineAggregator {
-    fn new(regex: Option<Arc<Regex>>) -> Self {
-        Self { regex, pending: None }
+    fn new(regex: Option<Arc<Regex>>, source: Option<Arc<str>>) -> Self {
+        Self {
+            regex,
+            pending: None,
+            pending_lines: 0,
+            source,
+        }
     }
 
-    fn process_line(&mut self, line: &str, tx: &Sender<SourceEvent>) -> bool {
-        let trimmed = line.trim_end_matches(['\n', '\r']);
-        match &self.regex {
-            None => {
-                if tx.send(SourceEvent::Line(trimmed.to_string())).is_err() {
-                    return false;
-                }
+    fn line(&self, content: String) -> SourceEvent {
+        SourceEvent::Line(content, self.source.clone())
+    }
+
+    /// Take one raw line, returning the record it completes, if any.
+    fn next_record(&mut self, line: &str) -> Option<SourceEvent> {
+        // CRLF endings, and the byte order mark Windows tools often start
+        // files with.
+        let trimmed = line.trim_end_matches(['\n', '\r']).trim_start_matches('\u{feff}');
+        let Some(re) = &self.regex else {
+            return Some(self.line(trimmed.to_string()));
+        };
+        self.pending_lines += 1;
+        if re.is_match(trimmed).unwrap_or(false) {
+            self.pending_lines = 1;
+            let done = self.pending.replace(trimmed.to_string());
+            return done.map(|pending| self.line(pending));
+        }
+        match &mut self.pending {
+            Some(p) => {
+                p.push('\n');
+                p.push_str(trimmed);
             }
-            Some(re) => {
-                let is_start = re.is_match(trimmed).unwrap_or(false);
-                if is_start {
-                    if let Some(pending) = self.pending.take() {
-                        if tx.send(SourceEvent::Line(pending)).is_err() {
-                            return false;
+            None => self.pending = Some(trimmed.to_string()),
+        }
+        None
+    }
+
+    fn take_pending(&mut self) -> Option<SourceEvent> {
+        self.pending_lines = 0;
+        self.pending.take().map(|pending| self.line(pending))
+    }
+
+    /// Returns false once the receiver is gone.
+    fn process_line(&mut self, line: &str, tx: &SyncSender<SourceEvent>) -> bool {
+        self.next_record(line).is_none_or(|event| tx.send(event).is_ok())
+    }
+
+    fn flush(&mut self, tx: &SyncSender<SourceEvent>) {
+        if let Some(event) = self.take_pending() {
+            let _ = tx.send(event);
+        }
+    }
+}
+
+/// Read one record, cut the way `--split` says: a line unless told otherwise.
+fn read_record(reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+    split::split().read(reader, buf)
+}
+
+/// A FIFO made with `mkfifo`, or a Windows named pipe (`\\.\pipe\NAME`):
+/// read as a stream rather than tailed.
+fn is_pipe(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        path.metadata().is_ok_and(|m| m.file_type().is_fifo())
+    }
+    #[cfg(windows)]
+    {
+        path.to_string_lossy().to_ascii_lowercase().starts_with(r"\\.\pipe\")
+    }
+}
+
+/// Read a pipe, opening it again whenever the writer goes away.
+fn start_pipe_source(path: PathBuf, tx: SyncSender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
+    let name = path.file_name().map(|n| Arc::from(n.to_string_lossy().as_ref()));
+    runtime().spawn_blocking(move || {
+        let mut aggregator = MultilineAggregator::new(line_start_regex, name);
+        let mut reported = false;
+        loop {
+            match File::open(&path) {
+                Ok(file) => {
+                    reported = false;
+                    let mut reader = BufReader::new(file);
+                    let mut buf = Vec::new();
+                    while let Ok(Some(line)) = read_record(&mut reader, &mut buf) {
+                        if !aggregator.process_line(&line, &tx) {
+                            return;
                         }
                     }
-                    self.pending = Some(trimmed.to_string());
-                } else {
-                    match &mut self.pending {
-                        Some(p) => {
-                            p.push('\n');
-                            p.push_str(trimmed);
-                        }
-                        None => {
-                            self.pending = Some(trimmed.to_string());
-                        }
+                    aggregator.flush(&tx);
+                }
+                Err(e) => {
+                    let error = format!("Cannot open {}: {}", path.display(), e);
+                    if !reported && tx.send(SourceEvent::Error(error)).is_err() {
+                        return;
                     }
+                    reported = true;
+                    thread::sleep(PIPE_RETRY);
                 }
             }
         }
-        true
+    });
+    Ok(())
+}
+
+/// Feed a rotated file, gzipped or not, through `aggregator`. `false` once
+/// the receiver is gone.
+fn read_rotated(
+    rotated: &Path,
+    buf: &mut Vec<u8>,
+    aggregator: &mut MultilineAggregator,
+    tx: &SyncSender<SourceEvent>,
+) -> io::Result<bool> {
+    let file = File::open(rotated)?;
+    let mut reader: Box<dyn BufRead> = if rotated.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    while let Some(line) = read_record(&mut reader, buf)? {
+        if !aggregator.process_line(&line, tx) {
+            return Ok(false);
+        }
     }
+    Ok(true)
+}
 
-    fn flush(&mut self, tx: &Sender<SourceEvent>) {
-        if let Some(pending) = self.pending.take() {
-            let _ = tx.send(SourceEvent::Line(pending));
+/// Feed `reader`, at byte `pos` of the file, through `aggregator` to its
+/// end. `pos` moves past what was read, and `sent` to where the records
+/// handed on end: the start of the one the aggregator still holds, if any.
+/// `false` once the receiver is gone.
+fn read_file_records(
+    reader: &mut impl BufRead,
+    buf: &mut Vec<u8>,
+    aggregator: &mut MultilineAggregator,
+    tx: &SyncSender<SourceEvent>,
+    pos: &mut u64,
+    sent: &mut u64,
+) -> io::Result<bool> {
+    while let Some(line) = read_record(reader, buf)? {
+        let start = *pos;
+        *pos += buf.len() as u64;
+        if !aggregator.process_line(&line, tx) {
+            return Ok(false);
+        }
+        match aggregator.pending_lines {
+            0 => *sent = *pos,
+            1 => *sent = start,
+            _ => {}
         }
     }
+    Ok(true)
 }
 
-fn run_file_source(path: PathBuf, tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
-    let mut file = File::open(&path)?;
-    let mut reader = BufReader::new(&file);
-    let mut line = String::new();
-    let mut aggregator = MultilineAggregator::new(line_start_regex);
+fn run_file_source(
+    path: PathBuf,
+    options: FileOptions,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    let name = path.file_name().map(|n| Arc::from(n.to_string_lossy().as_ref()));
+    let mut aggregator = MultilineAggregator::new(line_start_regex, name);
+    let mut tail = checkpoint::Tail::new(&path);
 
-    while reader.read_line(&mut line)? > 0 {
-        if !aggregator.process_line(&line, &tx) {
+    let start = match options.from {
+        ReadFrom::Start => 0,
+        ReadFrom::End => std::fs::metadata(&path)?.len(),
+        ReadFrom::Checkpoint => checkpoint::resume_offset(&path).unwrap_or(0),
+    };
+    if start > 0 && options.from == ReadFrom::Checkpoint {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let resumed = format!("── resumed {} at byte {} ──", name, group_digits(start as usize));
+        if tx.send(SourceEvent::SystemLine(resumed)).is_err() {
             return Ok(());
         }
-        line.clear();
     }
-    aggregator.flush(&tx);
 
-    let mut pos = file.seek(SeekFrom::Current(0))?;
+    if options.rotated && start == 0 {
+        // One that vanished or is corrupt is reported and skipped, so the
+        // rest of the history and the live file still show.
+        for rotated in rotated_files(&path) {
+            match read_rotated(&rotated, &mut buf, &mut aggregator, &tx) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    buf.clear();
+                    let error = format!("Cannot read {}: {}", rotated.display(), e);
+                    if tx.send(SourceEvent::Error(error)).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut file = File::open(&path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(&file);
+    let (mut pos, mut sent) = (start, start);
+
+    if !read_file_records(&mut reader, &mut buf, &mut aggregator, &tx, &mut pos, &mut sent)? {
+        return Ok(());
+    }
+    aggregator.flush(&tx);
+    sent = pos;
+    if !tail.advance(sent, &tx) {
+        return Ok(());
+    }
 
     let (notify_tx, notify_rx): (Sender<notify::Result<Event>>, Receiver<notify::Result<Event>>) =
         mpsc::channel();
@@ -125,13 +492,11 @@ fn run_file_source(path: PathBuf, tx: Sender<SourceEvent>, line_start_regex: Opt
                 file.seek(SeekFrom::Start(pos))?;
                 reader = BufReader::new(&file);
 
-                while reader.read_line(&mut line)? > 0 {
-                    if !aggregator.process_line(&line, &tx) {
-                        return Ok(());
-                    }
-                    line.clear();
+                if !read_file_records(&mut reader, &mut buf, &mut aggregator, &tx, &mut pos, &mut sent)?
+                    || !tail.advance(sent, &tx)
+                {
+                    return Ok(());
                 }
-                pos = file.seek(SeekFrom::Current(0))?;
             }
             Ok(Err(e)) => {
                 let _ = tx.send(SourceEvent::Error(e.to_string()));
@@ -144,87 +509,964 @@ fn run_file_source(path: PathBuf, tx: Sender<SourceEvent>, line_start_regex: Opt
     }
 }
 
-fn start_stdin_source(tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
-    thread::spawn(move || {
-        let stdin = std::io::stdin();
-        let reader = BufReader::new(stdin.lock());
-        let mut aggregator = MultilineAggregator::new(line_start_regex);
-        for line in reader.lines() {
-            match line {
-                Ok(l) => {
-                    if !aggregator.process_line(&l, &tx) {
-                        break;
+fn start_watch_dir_source(
+    dir: PathBuf,
+    pattern: String,
+    options: FileOptions,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(anyhow!("Not a directory: {}", dir.display()));
+    }
+    let (notify_tx, notify_rx) = mpsc::channel();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<Event>| {
+            let _ = notify_tx.send(res);
+        },
+        notify::Config::default().with_poll_interval(Duration::from_millis(500)),
+    )?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    runtime().spawn_blocking(move || {
+        let _watcher = watcher;
+        let mut tailed = HashSet::new();
+        let mut first = true;
+        loop {
+            for path in matching_files(&dir, &pattern) {
+                if !tailed.insert(path.clone()) {
+                    continue;
+                }
+                if !first {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    if tx.send(SourceEvent::SystemLine(format!("── new file {} ──", name))).is_err() {
+                        return;
                     }
                 }
-                Err(e) => {
+                // Files that show up later are read whole.
+                let options = if first {
+                    options
+                } else {
+                    FileOptions {
+                        from: ReadFrom::Start,
+                        ..options
+                    }
+                };
+                if let Err(e) = start_file_source(path, options, tx.clone(), line_start_regex.clone()) {
                     let _ = tx.send(SourceEvent::Error(e.to_string()));
-                    break;
                 }
             }
+            first = false;
+            // Rescan on any change in the directory, and now and then in
+            // case an event was missed.
+            match notify_rx.recv_timeout(Duration::from_secs(2)) {
+                Ok(Ok(_)) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Ok(Err(e)) => {
+                    if tx.send(SourceEvent::Error(e.to_string())).is_err() {
+                        return;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
         }
-        aggregator.flush(&tx);
     });
     Ok(())
 }
 
-fn start_network_source(port: u16, tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
-    let listener = TcpListener::bind(format!("[::]:{}", port))
-        .or_else(|_| TcpListener::bind(format!("0.0.0.0:{}", port)))?;
-    thread::spawn(move || {
-        for stream in listener.incoming() {
-            match stream {
-                Ok(s) => {
-                    let tx_clone = tx.clone();
-                    let regex_clone = line_start_regex.clone();
-                    thread::spawn(move || handle_client(s, tx_clone, regex_clone));
+/// Regular files directly in `dir` whose names match `pattern`, by name.
+fn matching_files(dir: &Path, pattern: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| glob_match(pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Rotated predecessors of `path` in its directory, oldest first.
+fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(active)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    rotation_order(&active.to_string_lossy(), &names)
+        .into_iter()
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+/// Which of `names` are rotations of `active` (`app.log.1`, `app.log.2.gz`,
+/// `app.log-20240131`), oldest first: numbered ones count up with age, dated
+/// ones down.
+fn rotation_order<'a>(active: &str, names: &'a [String]) -> Vec<&'a str> {
+    let mut rotated: Vec<((u8, i64), &str)> = names
+        .iter()
+        .filter_map(|name| {
+            let suffix = name.strip_prefix(active)?;
+            let suffix = suffix.strip_suffix(".gz").unwrap_or(suffix);
+            let digits = suffix.strip_prefix(['.', '-', '_'])?;
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '-') {
+                return None;
+            }
+            let number: i64 = digits.replace('-', "").parse().ok()?;
+            // Dated suffixes have at least the eight digits of YYYYMMDD.
+            let key = if digits.len() >= 8 { (0, number) } else { (1, -number) };
+            Some((key, name.as_str()))
+        })
+        .collect();
+    rotated.sort();
+    rotated.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Read a whole file as records, joining continuation lines the same way the
+/// live sources do.
+pub fn read_records(path: &Path, line_start_regex: Option<Arc<Regex>>) -> Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut aggregator = MultilineAggregator::new(line_start_regex, None);
+    let mut events = Vec::new();
+    let mut buf = Vec::new();
+    while let Some(line) = read_record(&mut reader, &mut buf)? {
+        events.extend(aggregator.next_record(&line));
+    }
+    events.extend(aggregator.take_pending());
+    Ok(events
+        .into_iter()
+        .filter_map(|event| match event {
+            SourceEvent::Line(content, _) => Some(content),
+            _ => None,
+        })
+        .collect())
+}
+
+fn start_stdin_source(tx: SyncSender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) -> Result<()> {
+    runtime().spawn_blocking(move || {
+        let mut aggregator = MultilineAggregator::new(line_start_regex, None);
+        read_lines(io::stdin().lock(), &mut aggregator, &tx);
+    });
+    Ok(())
+}
+
+/// Lines a container logged before attaching that are shown too, without
+/// `--since`.
+const DOCKER_TAIL: &str = "1000";
+
+/// Marks where a backfill ends and live output starts.
+const LIVE_DIVIDER: &str = "── backfill done, live from here ──";
+
+fn spawn_docker_logs(container: &str, args: &[&str]) -> Result<Child> {
+    Command::new("docker")
+        .arg("logs")
+        .args(args)
+        .arg(container)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Cannot run docker: {}", e))
+}
+
+/// Read `docker logs` until it ends: the container's stdout and stderr are
+/// written to its own, so both are read, tagged with the container's name.
+fn read_docker_logs(mut child: Child, name: &Arc<str>, tx: &SyncSender<SourceEvent>, line_start_regex: &Option<Arc<Regex>>) -> String {
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return "no output".to_string();
+    };
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut aggregator = MultilineAggregator::new(line_start_regex.clone(), Some(name.clone()));
+            read_lines(BufReader::new(stderr), &mut aggregator, tx);
+        });
+        let mut aggregator = MultilineAggregator::new(line_start_regex.clone(), Some(name.clone()));
+        read_lines(BufReader::new(stdout), &mut aggregator, tx);
+        // Ends when the container stops or nobody is reading any more.
+        let _ = child.kill();
+        child.wait().map(|s| s.to_string()).unwrap_or_else(|e| e.to_string())
+    })
+}
+
+/// Follow `docker logs`. With `since`, what the container logged in that
+/// time up to now is read first, then everything after now.
+fn start_docker_source(
+    container: String,
+    since: Option<Since>,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    let name: Arc<str> = Arc::from(container.as_str());
+    let now = Local::now().timestamp().to_string();
+    let backfill = match since {
+        Some(since) => Some(spawn_docker_logs(&container, &["--since", &since.docker_arg(), "--until", &now])?),
+        None => None,
+    };
+    let follow = match &backfill {
+        Some(_) => None,
+        None => Some(spawn_docker_logs(&container, &["--follow", "--tail", DOCKER_TAIL])?),
+    };
+    runtime().spawn_blocking(move || {
+        let follow = match (backfill, follow) {
+            (Some(backfill), _) => {
+                let _ = tx.send(SourceEvent::Backfill(true));
+                read_docker_logs(backfill, &name, &tx, &line_start_regex);
+                let _ = tx.send(SourceEvent::SystemLine(LIVE_DIVIDER.to_string()));
+                let _ = tx.send(SourceEvent::Backfill(false));
+                match spawn_docker_logs(&container, &["--follow", "--since", &now]) {
+                    Ok(child) => child,
+                    Err(e) => {
+                        let _ = tx.send(SourceEvent::Error(e.to_string()));
+                        return;
+                    }
                 }
+            }
+            (None, Some(follow)) => follow,
+            (None, None) => return,
+        };
+        let status = read_docker_logs(follow, &name, &tx, &line_start_regex);
+        let _ = tx.send(SourceEvent::SystemLine(format!("── docker logs {} ended ({}) ──", container, status)));
+    });
+    Ok(())
+}
+
+fn log_command(predicate: &Option<String>, args: &[&str]) -> Command {
+    let mut command = Command::new("log");
+    command.args(args).args(["--style", "ndjson"]);
+    if let Some(predicate) = predicate {
+        command.args(["--predicate", predicate]);
+    }
+    command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null());
+    command
+}
+
+/// Follow `log stream`, one JSON event per line, after `log show` for the
+/// `since` before it if given.
+fn start_oslog_source(
+    predicate: Option<String>,
+    since: Option<Since>,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    let backfill = match since {
+        Some(since) => {
+            let start = since.start().format("%Y-%m-%d %H:%M:%S").to_string();
+            let child = log_command(&predicate, &["show", "--start", &start])
+                .spawn()
+                .map_err(|e| anyhow!("Cannot run log show: {}", e))?;
+            Some(child)
+        }
+        None => None,
+    };
+    let mut stream = log_command(&predicate, &["stream"]);
+    let mut child = match backfill {
+        Some(_) => None,
+        None => Some(stream.spawn().map_err(|e| anyhow!("Cannot run log stream: {}", e))?),
+    };
+    runtime().spawn_blocking(move || {
+        let mut aggregator = MultilineAggregator::new(line_start_regex, None);
+        if let Some(mut backfill) = backfill {
+            let _ = tx.send(SourceEvent::Backfill(true));
+            if let Some(stdout) = backfill.stdout.take() {
+                read_lines(BufReader::new(stdout), &mut aggregator, &tx);
+            }
+            let _ = backfill.wait();
+            let _ = tx.send(SourceEvent::SystemLine(LIVE_DIVIDER.to_string()));
+            let _ = tx.send(SourceEvent::Backfill(false));
+            match stream.spawn() {
+                Ok(stream) => child = Some(stream),
                 Err(e) => {
-                    let _ = tx.send(SourceEvent::Error(format!("Accept error: {}", e)));
+                    let _ = tx.send(SourceEvent::Error(format!("Cannot run log stream: {}", e)));
+                    return;
                 }
             }
         }
+        let Some(mut child) = child else {
+            return;
+        };
+        if let Some(stdout) = child.stdout.take() {
+            read_lines(BufReader::new(stdout), &mut aggregator, &tx);
+        }
+        let _ = child.kill();
+        let status = child.wait().map(|s| s.to_string()).unwrap_or_else(|e| e.to_string());
+        let _ = tx.send(SourceEvent::SystemLine(format!("── log stream ended ({}) ──", status)));
     });
     Ok(())
 }
 
-fn handle_client(stream: TcpStream, tx: Sender<SourceEvent>, line_start_regex: Option<Arc<Regex>>) {
-    let peer = stream
-        .peer_addr()
-        .map(|a| a.to_string())
-        .unwrap_or_else(|_| "unknown".to_string());
-    
+/// Wait before looking for the device again after `adb logcat` ends,
+/// doubled up to the max while it ends without reading anything.
+const ADB_RETRY: Duration = Duration::from_secs(1);
+const ADB_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Time of the last logcat entry read and the entries logged at that time.
+type LogcatPosition = Option<(String, Vec<String>)>;
+
+/// Read `adb logcat` output until it ends, noting where it got to in `last`.
+/// Entries up to `resume` were already shown and are skipped; what adb said
+/// on stderr is sent as an error. Returns how many lines were read, or
+/// `None` once nobody is reading.
+fn read_logcat(
+    child: &mut Child,
+    resume: LogcatPosition,
+    last: &mut LogcatPosition,
+    aggregator: &mut MultilineAggregator,
+    tx: &SyncSender<SourceEvent>,
+) -> Option<usize> {
+    let Some(stdout) = child.stdout.take() else {
+        return Some(0);
+    };
+    let stderr = child.stderr.take().map(|stderr| {
+        thread::spawn(move || {
+            let mut text = String::new();
+            let _ = io::Read::read_to_string(&mut BufReader::new(stderr), &mut text);
+            text
+        })
+    });
+    // `-T` starts at the last time seen, so skip up to what was shown.
+    let mut resume = resume;
+    let mut reader = BufReader::new(stdout);
+    let mut buf = Vec::new();
+    let mut read = 0;
+    while let Ok(Some(line)) = read_record(&mut reader, &mut buf) {
+        read += 1;
+        if let Some(entry) = logcat::parse(line.trim_end_matches(['\r', '\n'])) {
+            if let Some((time, seen)) = &resume {
+                if entry.time < time.as_str() || (entry.time == time && seen.contains(&line)) {
+                    continue;
+                }
+                if entry.time > time.as_str() {
+                    resume = None;
+                }
+            }
+            match last {
+                Some((time, seen)) if time == entry.time => seen.push(line.clone()),
+                _ => *last = Some((entry.time.to_string(), vec![line.clone()])),
+            }
+        }
+        if !aggregator.process_line(&line, tx) {
+            let _ = child.kill();
+            return None;
+        }
+    }
+    aggregator.flush(tx);
+    let _ = child.kill();
+    let _ = child.wait();
+    let said = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    if !said.trim().is_empty() && tx.send(SourceEvent::Error(format!("adb logcat: {}", said.trim()))).is_err() {
+        return None;
+    }
+    Some(read)
+}
+
+/// Follow `adb logcat -v threadtime`. When the device goes away, wait for it
+/// and carry on from the last entry's time, skipping entries already shown.
+/// With `since`, the log from then on is dumped first.
+fn start_adb_source(
+    serial: Option<String>,
+    since: Option<Since>,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    Command::new("adb")
+        .arg("version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| anyhow!("Cannot run adb: {}", e))?;
+    let device: Vec<String> = serial.iter().flat_map(|s| ["-s".to_string(), s.clone()]).collect();
+    let name: Option<Arc<str>> = serial.as_deref().map(Arc::from);
+    let label = serial.unwrap_or_else(|| "device".to_string());
+    let wait_for_device = {
+        let device = device.clone();
+        move || {
+            Command::new("adb")
+                .args(&device)
+                .arg("wait-for-device")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+        }
+    };
+    let logcat = move |extra: &[&str]| {
+        let mut command = Command::new("adb");
+        command
+            .args(&device)
+            .args(["logcat", "-v", "threadtime"])
+            .args(extra)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    };
+    runtime().spawn_blocking(move || {
+        let mut aggregator = MultilineAggregator::new(line_start_regex, name);
+        let mut last: LogcatPosition = None;
+        if let Some(since) = since {
+            let _ = tx.send(SourceEvent::Backfill(true));
+            let start = since.start().format("%m-%d %H:%M:%S%.3f").to_string();
+            let _ = wait_for_device();
+            if let Ok(mut dump) = logcat(&["-d", "-T", &start]).spawn() {
+                if read_logcat(&mut dump, None, &mut last, &mut aggregator, &tx).is_none() {
+                    return;
+                }
+            }
+            let _ = tx.send(SourceEvent::SystemLine(LIVE_DIVIDER.to_string()));
+            let _ = tx.send(SourceEvent::Backfill(false));
+            last.get_or_insert((start, Vec::new()));
+        }
+        let mut connected = false;
+        let mut retry = ADB_RETRY;
+        loop {
+            let _ = wait_for_device();
+            let resume = last.clone();
+            let mut command = match &resume {
+                Some((time, _)) => logcat(&["-T", time]),
+                None => logcat(&[]),
+            };
+            let Ok(mut child) = command.spawn() else {
+                let _ = tx.send(SourceEvent::Error("Cannot run adb logcat".to_string()));
+                return;
+            };
+            let what = if connected { "reconnected" } else { "connected" };
+            connected = true;
+            if tx.send(SourceEvent::SystemLine(format!("── adb logcat {} {} ──", label, what))).is_err() {
+                let _ = child.kill();
+                return;
+            }
+            let Some(read) = read_logcat(&mut child, resume, &mut last, &mut aggregator, &tx) else {
+                return;
+            };
+            let ended = format!("── adb logcat {} disconnected, waiting for it ──", label);
+            if tx.send(SourceEvent::SystemLine(ended)).is_err() {
+                return;
+            }
+            if read > 0 {
+                retry = ADB_RETRY;
+            }
+            thread::sleep(retry);
+            retry = (retry * 2).min(ADB_RETRY_MAX);
+        }
+    });
+    Ok(())
+}
+
+/// `text` with the `user:password@` of any URL in it left out, for errors
+/// that quote a service's URL.
+pub fn without_userinfo(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find("://") {
+        let (head, tail) = rest.split_at(i + 3);
+        out.push_str(head);
+        let authority = tail.split(|c: char| c == '/' || c.is_whitespace()).next().unwrap_or_default();
+        rest = &tail[authority.rfind('@').map_or(0, |at| at + 1)..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `url` without its `user:password@`, whether or not it has a scheme.
+pub fn url_without_userinfo(url: &str) -> String {
+    if url.contains("://") {
+        return without_userinfo(url);
+    }
+    let authority = url.split('/').next().unwrap_or_default();
+    url[authority.rfind('@').map_or(0, |at| at + 1)..].to_string()
+}
+
+/// Percent escapes in a URL, e.g. a password's `%40` for `@`.
+pub fn unescape(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (b, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A message from a log service, tagged with the stream, channel or
+/// subject it came from.
+pub struct Message {
+    pub source: String,
+    pub content: String,
+}
+
+/// A connection to a log service, read a batch of messages at a time.
+pub trait Feed {
+    /// The messages that arrived, waiting a while for some when `wait`;
+    /// empty when none did.
+    fn next_batch(&mut self, wait: bool) -> Result<Vec<Message>>;
+
+    /// Where the next connection carries on from, for services that keep
+    /// positions.
+    fn position(&self) -> Option<String>;
+
+    /// Everything there is has been read, for services read once through.
+    fn done(&self) -> bool {
+        false
+    }
+}
+
+/// Wait before connecting to a service again, doubled after each failure.
+const FEED_RETRY: Duration = Duration::from_secs(1);
+const FEED_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Read a log service through connections `open` makes, the first with no
+/// position, each message a record. When `backfill`, what is there already
+/// is read first. When the connection drops, reconnect with backoff at
+/// the last position read.
+fn follow_feed<F: Feed>(
+    label: String,
+    backfill: bool,
+    tx: SyncSender<SourceEvent>,
+    open: impl Fn(Option<&str>) -> Result<F> + Send + 'static,
+) {
+    runtime().spawn_blocking(move || {
+        let mut backfill = backfill;
+        if backfill {
+            let _ = tx.send(SourceEvent::Backfill(true));
+        }
+        let mut position: Option<String> = None;
+        let mut connected = false;
+        let mut retry = FEED_RETRY;
+        loop {
+            let ended = match open(position.as_deref()) {
+                Ok(mut feed) => {
+                    let what = if connected { "reconnected" } else { "connected" };
+                    connected = true;
+                    retry = FEED_RETRY;
+                    if tx.send(SourceEvent::SystemLine(format!("── {} {} ──", label, what))).is_err() {
+                        return;
+                    }
+                    let error = loop {
+                        let messages = match feed.next_batch(!backfill) {
+                            Ok(messages) => messages,
+                            Err(e) => break e,
+                        };
+                        if backfill && messages.is_empty() {
+                            backfill = false;
+                            let _ = tx.send(SourceEvent::SystemLine(LIVE_DIVIDER.to_string()));
+                            let _ = tx.send(SourceEvent::Backfill(false));
+                        }
+                        for Message { source, content } in messages {
+                            if tx.send(SourceEvent::Line(content, Some(Arc::from(source)))).is_err() {
+                                return;
+                            }
+                        }
+                        if feed.done() {
+                            let _ = tx.send(SourceEvent::SystemLine(format!("── {} ended ──", label)));
+                            return;
+                        }
+                    };
+                    position = feed.position().or(position);
+                    Some(format!("── {} disconnected ({}), reconnecting ──", label, error))
+                }
+                Err(e) if !connected => {
+                    let _ = tx.send(SourceEvent::Error(format!("Cannot read {}: {:#}", label, e)));
+                    return;
+                }
+                Err(_) => None,
+            };
+            if ended.is_some_and(|ended| tx.send(SourceEvent::SystemLine(ended)).is_err()) {
+                return;
+            }
+            thread::sleep(retry);
+            retry = (retry * 2).min(FEED_RETRY_MAX);
+        }
+    });
+}
+
+/// Follow a Redis stream or channel, tagged with its name. With `since`,
+/// stream entries from then on are read first.
+fn start_redis_source(url: RedisUrl, since: Option<Since>, tx: SyncSender<SourceEvent>) -> Result<()> {
+    let label = format!("redis {}:{}/{}", url.host, url.port, url.key);
+    let start = match since {
+        Some(since) => format!("{}-0", since.start().timestamp_millis()),
+        None => "$".to_string(),
+    };
+    follow_feed(label, since.is_some(), tx, move |position| {
+        redis::Connection::open(&url, position.unwrap_or(&start))
+    });
+    Ok(())
+}
+
+/// Follow the messages published to a NATS subject, each tagged with the
+/// subject it was published to. NATS keeps no history, so there is nothing
+/// to read from before.
+fn start_nats_source(url: NatsUrl, subject: String, tx: SyncSender<SourceEvent>) -> Result<()> {
+    let label = format!("nats {}:{} {}", url.host, url.port, subject);
+    follow_feed(label, false, tx, move |_| nats::Connection::open(&url, &subject));
+    Ok(())
+}
+
+/// Tail a CloudWatch Logs group, tagged with each event's log stream. With
+/// `since`, the events from then on are read first.
+fn start_cloudwatch_source(target: CloudWatchTarget, since: Option<Since>, tx: SyncSender<SourceEvent>) -> Result<()> {
+    let label = format!("cloudwatch {}", target.group);
+    let start = since.map_or_else(Local::now, |since| since.start()).timestamp_millis();
+    follow_feed(label, since.is_some(), tx, move |position| {
+        let start = position.and_then(|p| p.parse().ok()).unwrap_or(start);
+        cloudwatch::Poller::open(&target, start)
+    });
+    Ok(())
+}
+
+/// Tail what a LogQL query finds, tagged with a label of each stream. With
+/// `since`, the entries from then on are read first.
+fn start_loki_source(query: LokiQuery, since: Option<Since>, tx: SyncSender<SourceEvent>) -> Result<()> {
+    let label = format!("loki {}", query.query);
+    let start = since.map_or_else(Local::now, |since| since.start());
+    let start = start.timestamp_nanos_opt().ok_or_else(|| anyhow!("--since is too far back"))?;
+    follow_feed(label, since.is_some(), tx, move |position| {
+        let start = position.and_then(|p| p.parse().ok()).unwrap_or(start);
+        loki::Poller::open(&query, start)
+    });
+    Ok(())
+}
+
+/// Read what an Elasticsearch query finds, tagged with each document's
+/// index: from `since` (else the first document) up to now, or when
+/// following, from `since` (else now) on with the range read first.
+fn start_elastic_source(query: EsQuery, since: Option<Since>, tx: SyncSender<SourceEvent>) -> Result<()> {
+    let label = format!("elasticsearch {}", query.index);
+    let now = Local::now().timestamp_millis();
+    let start = match (since, query.follow) {
+        (Some(since), _) => since.start().timestamp_millis(),
+        (None, true) => now,
+        (None, false) => 0,
+    };
+    let end = (!query.follow).then_some(now);
+    follow_feed(label, query.follow && since.is_some(), tx, move |position| {
+        elastic::Poller::open(&query, start, end, position.and_then(|p| p.parse().ok()))
+    });
+    Ok(())
+}
+
+/// Feed `reader` through `aggregator` until it ends or `tx` is closed.
+fn read_lines(mut reader: impl BufRead, aggregator: &mut MultilineAggregator, tx: &SyncSender<SourceEvent>) {
+    let mut buf = Vec::new();
+    loop {
+        match read_record(&mut reader, &mut buf) {
+            Ok(None) => break,
+            Ok(Some(line)) => {
+                if !aggregator.process_line(&line, tx) {
+                    return;
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(SourceEvent::Error(e.to_string()));
+                break;
+            }
+        }
+    }
+    aggregator.flush(tx);
+}
+
+type NetSender = tokio::sync::mpsc::Sender<SourceEvent>;
+
+fn start_network_source(
+    ports: Vec<ListenPort>,
+    options: ListenOptions,
+    tx: SyncSender<SourceEvent>,
+    line_start_regex: Option<Arc<Regex>>,
+) -> Result<()> {
+    // With one unnamed port the peer address alone identifies a client.
+    let tag_ports = ports.len() > 1;
+    let mut listeners = Vec::new();
+    for listen in ports {
+        let listener = TcpListener::bind(format!("[::]:{}", listen.port))
+            .or_else(|_| TcpListener::bind(format!("0.0.0.0:{}", listen.port)))
+            .map_err(|e| anyhow!("Cannot listen on port {}: {}", listen.port, e))?;
+        listener.set_nonblocking(true)?;
+        let label = listen.name.or_else(|| tag_ports.then(|| format!(":{}", listen.port)));
+        listeners.push((listener, label));
+    }
+
+    let runtime = runtime();
+    let (net_tx, mut net_rx) = tokio::sync::mpsc::channel(NETWORK_CAPACITY);
+    // Once the UI stops receiving this ends, closing `net_rx`, and every
+    // listener and client winds down with it.
+    runtime.spawn_blocking(move || {
+        while let Some(event) = net_rx.blocking_recv() {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    let _entered = runtime.enter();
+    for (listener, label) in listeners {
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        runtime.spawn(accept_clients(
+            listener,
+            label,
+            options.clone(),
+            net_tx.clone(),
+            line_start_regex.clone(),
+        ));
+    }
+    Ok(())
+}
+
+async fn accept_clients(
+    listener: tokio::net::TcpListener,
+    label: Option<String>,
+    options: ListenOptions,
+    tx: NetSender,
+    line_start_regex: Option<Arc<Regex>>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            _ = tx.closed() => return,
+            accepted = listener.accept() => accepted,
+        };
+        match accepted {
+            Ok((stream, addr)) => {
+                tokio::spawn(handle_client(
+                    stream,
+                    addr,
+                    label.clone(),
+                    options.clone(),
+                    tx.clone(),
+                    line_start_regex.clone(),
+                ));
+            }
+            Err(e) => {
+                if tx.send(SourceEvent::Error(format!("Accept error: {}", e))).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+type ClientReader = Box<dyn AsyncBufRead + Unpin + Send>;
+
+/// Buffer the client's stream, recording it first with `--capture`.
+fn client_reader(stream: impl AsyncRead + Unpin + Send + 'static, options: &ListenOptions, peer: &str) -> ClientReader {
+    match &options.capture {
+        Some(capture) => {
+            let recorder = Recorder::new(stream, capture.clone(), peer, options.token.is_some());
+            Box::new(tokio::io::BufReader::new(recorder))
+        }
+        None => Box::new(tokio::io::BufReader::new(stream)),
+    }
+}
+
+/// Finish the TLS handshake and check the token, returning the error to
+/// report when the client is turned away.
+async fn open_client(stream: TcpStream, options: &ListenOptions, peer: &str) -> Result<ClientReader, String> {
+    let mut reader: ClientReader = match &options.tls {
+        Some(config) => match TlsAcceptor::from(config.clone()).accept(stream).await {
+            Ok(stream) => client_reader(stream, options, peer),
+            Err(e) => return Err(format!("TLS error from {}: {}", peer, e)),
+        },
+        None => client_reader(stream, options, peer),
+    };
+
+    if let Some(token) = &options.token {
+        let mut first = String::new();
+        let accepted = reader.read_line(&mut first).await.is_ok()
+            && first.trim_end_matches(['\n', '\r']).strip_prefix(TOKEN_PREFIX) == Some(token.as_str());
+        if !accepted {
+            return Err(format!("Rejected {}: missing or wrong token", peer));
+        }
+    }
+    Ok(reader)
+}
+
+async fn handle_client(
+    stream: TcpStream,
+    addr: SocketAddr,
+    label: Option<String>,
+    options: ListenOptions,
+    tx: NetSender,
+    line_start_regex: Option<Arc<Regex>>,
+) {
+    let peer = match label {
+        Some(label) => format!("{} {}", label, addr),
+        None => addr.to_string(),
+    };
+
     if let Err(e) = stream.set_nodelay(true) {
-        let _ = tx.send(SourceEvent::Error(format!("Failed to set TCP_NODELAY: {}", e)));
+        let _ = tx.send(SourceEvent::Error(format!("Failed to set TCP_NODELAY: {}", e))).await;
     }
-    
+
     let keepalive = socket2::TcpKeepalive::new()
         .with_time(Duration::from_secs(10))
         .with_interval(Duration::from_secs(5));
-    
+
     let socket_ref = socket2::SockRef::from(&stream);
     if let Err(e) = socket_ref.set_tcp_keepalive(&keepalive) {
-        let _ = tx.send(SourceEvent::Error(format!("Failed to set TCP keepalive: {}", e)));
+        let _ = tx.send(SourceEvent::Error(format!("Failed to set TCP keepalive: {}", e))).await;
     }
-    
-    let _ = tx.send(SourceEvent::Connected(peer.clone()));
-    let _ = tx.send(SourceEvent::SystemLine(format!("[connected: {}]", peer)));
 
-    let reader = BufReader::new(&stream);
-    let mut aggregator = MultilineAggregator::new(line_start_regex);
-    for line in reader.lines() {
-        match line {
-            Ok(l) => {
-                if !aggregator.process_line(&l, &tx) {
-                    break;
+    let mut reader = match tokio::time::timeout(HANDSHAKE_TIMEOUT, open_client(stream, &options, &peer)).await {
+        Ok(Ok(reader)) => reader,
+        Ok(Err(error)) => {
+            let _ = tx.send(SourceEvent::Error(error)).await;
+            return;
+        }
+        Err(_) => {
+            let error = format!("Rejected {}: no handshake within {}s", peer, HANDSHAKE_TIMEOUT.as_secs());
+            let _ = tx.send(SourceEvent::Error(error)).await;
+            return;
+        }
+    };
+
+    let _ = tx.send(SourceEvent::Connected(peer.clone())).await;
+    let _ = tx
+        .send(SourceEvent::SystemLine(format!("── client {} connected ──", peer)))
+        .await;
+
+    let mut aggregator = MultilineAggregator::new(line_start_regex, Some(Arc::from(peer.as_str())));
+    let mut read = 0;
+    let mut buf = Vec::new();
+    loop {
+        let result = tokio::select! {
+            _ = tx.closed() => return,
+            result = split::split().read_async(&mut reader, &mut buf) => result,
+        };
+        match result {
+            Ok(None) => break,
+            Ok(Some(record)) => {
+                read += 1;
+                if let Some(event) = aggregator.next_record(&record) {
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
                 }
             }
             Err(e) => {
-                let _ = tx.send(SourceEvent::Error(format!("Read error from {}: {}", peer, e)));
+                let _ = tx
+                    .send(SourceEvent::Error(format!("Read error from {}: {}", peer, e)))
+                    .await;
                 break;
             }
         }
     }
-    aggregator.flush(&tx);
-    let _ = tx.send(SourceEvent::SystemLine(format!("[disconnected: {}]", peer)));
-    let _ = tx.send(SourceEvent::Disconnected(peer));
+    if let Some(event) = aggregator.take_pending() {
+        let _ = tx.send(event).await;
+    }
+    let _ = tx
+        .send(SourceEvent::SystemLine(format!(
+            "── client {} disconnected (read {} lines) ──",
+            peer,
+            group_digits(read)
+        )))
+        .await;
+    let _ = tx.send(SourceEvent::Disconnected(peer)).await;
+}
+
+/// `12430` -> `12,430`.
+pub fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits(0), "0");
+        assert_eq!(group_digits(999), "999");
+        assert_eq!(group_digits(12430), "12,430");
+        assert_eq!(group_digits(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn test_parse_listen_port() {
+        assert_eq!(
+            "5001=access".parse::<ListenPort>().unwrap(),
+            ListenPort {
+                port: 5001,
+                name: Some("access".to_string())
+            }
+        );
+        assert_eq!("5000".parse::<ListenPort>().unwrap().name, None);
+        assert!("5000=".parse::<ListenPort>().is_err());
+        assert!("app".parse::<ListenPort>().is_err());
+    }
+
+    #[test]
+    fn test_without_userinfo() {
+        assert_eq!(
+            without_userinfo("http://elastic:pw@es:9200/logs/_search: Connection refused"),
+            "http://es:9200/logs/_search: Connection refused"
+        );
+        assert_eq!(without_userinfo("got http://loki:3100 and https://a@b/c"), "got http://loki:3100 and https://b/c");
+        assert_eq!(url_without_userinfo("ops:pw@bus:4222"), "bus:4222");
+        assert_eq!(url_without_userinfo("redis://:pw@cache/app@v2"), "redis://cache/app@v2");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!("30m".parse::<Since>().unwrap(), Since(Duration::from_secs(1800)));
+        assert!("3650d".parse::<Since>().is_ok());
+        assert!("3651d".parse::<Since>().is_err());
+        assert!("99999999999999999d".parse::<Since>().is_err());
+    }
+
+    #[test]
+    fn test_windows_line_endings() {
+        let mut reader = &b"\xef\xbb\xbfstarted\r\ncaf\xe9 opened\r\n"[..];
+        let mut buf = Vec::new();
+        let mut aggregator = MultilineAggregator::new(None, None);
+        let mut lines = Vec::new();
+        while let Some(line) = read_record(&mut reader, &mut buf).unwrap() {
+            if let Some(SourceEvent::Line(content, _)) = aggregator.next_record(&line) {
+                lines.push(content);
+            }
+        }
+        assert_eq!(lines, ["started", "caf\u{fffd} opened"]);
+    }
+
+    #[test]
+    fn test_checkpoint_stops_before_held_record() {
+        let (tx, rx) = channel();
+        let mut aggregator = MultilineAggregator::new(Some(Arc::new(Regex::new(r"^\d").unwrap())), None);
+        let mut reader = &b"1 start\n  at x\n2 next\n  at y\n"[..];
+        let (mut pos, mut sent) = (100, 100);
+        assert!(read_file_records(&mut reader, &mut Vec::new(), &mut aggregator, &tx, &mut pos, &mut sent).unwrap());
+        assert_eq!((pos, sent), (100 + 29, 100 + 15));
+        assert!(matches!(rx.try_recv(), Ok(SourceEvent::Line(line, _)) if line == "1 start\n  at x"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_rotation_order() {
+        let names: Vec<String> = ["app.log", "app.log.1", "app.log.10.gz", "app.log.2.gz", "app.log.bak", "api.log.1"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(rotation_order("app.log", &names), ["app.log.10.gz", "app.log.2.gz", "app.log.1"]);
+        let dated: Vec<String> = ["app.log-20240202.gz", "app.log-20240131"].map(String::from).to_vec();
+        assert_eq!(rotation_order("app.log", &dated), ["app.log-20240131", "app.log-20240202.gz"]);
+    }
 }
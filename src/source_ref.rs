@@ -0,0 +1,118 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `src/main.rs:10:5`, `(Foo.java:42)`, `/app/x.js:10:15`: a path ending in
+/// a source file extension, then a line and maybe a column.
+static PATH_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:^|[\s(\[<'\x22=@])((?:[A-Za-z]:)?[\w./\\~+-]*\.(?:rs|go|py|pyx|js|mjs|cjs|jsx|ts|tsx|java|kt|kts|scala|groovy|c|h|cc|cpp|cxx|hpp|hh|m|mm|cs|fs|swift|rb|php|pl|pm|ex|exs|erl|hrl|clj|dart|lua|zig|nim|hs|ml|vue|svelte|sh|sql)):(\d+)(?::(\d+))?",
+    )
+    .unwrap()
+});
+/// Python tracebacks: `File "app/views.py", line 42`.
+static PYTHON_FRAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"File "([^"]+)", line (\d+)"#).unwrap());
+
+/// A `file:line` reference in a log line, with where it is in the text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceRef {
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    /// Byte range of the reference in the text.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Every reference in `text`, in order.
+pub fn find_refs(text: &str) -> Vec<SourceRef> {
+    let mut refs: Vec<SourceRef> = Vec::new();
+    for caps in PATH_LINE.captures_iter(text).chain(PYTHON_FRAME.captures_iter(text)) {
+        let (Some(path), Some(line)) = (caps.get(1), caps.get(2)) else {
+            continue;
+        };
+        let Ok(number) = line.as_str().parse() else {
+            continue;
+        };
+        let end = caps.get(3).unwrap_or(line).end();
+        refs.push(SourceRef {
+            path: path.as_str().to_string(),
+            line: number,
+            column: caps.get(3).and_then(|c| c.as_str().parse().ok()),
+            start: path.start(),
+            end,
+        });
+    }
+    refs.sort_by_key(|r| r.start);
+    refs
+}
+
+/// The reference under byte offset `at`, else the first one.
+pub fn ref_at(text: &str, at: usize) -> Option<SourceRef> {
+    let refs = find_refs(text);
+    refs.iter().find(|r| (r.start..r.end).contains(&at)).or(refs.first()).cloned()
+}
+
+/// `$VISUAL`, `$EDITOR`, or `vi`.
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Quote `text` as one shell word.
+fn quote(text: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", text)
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+/// The shell command opening `source` per `template`, whose `{editor}`,
+/// `{file}`, `{line}` and `{column}` are filled in; an empty template is
+/// `{editor} +{line} {file}`, which vi, vim, nano, emacs and helix follow.
+pub fn editor_command(template: &str, source: &SourceRef) -> String {
+    let template = if template.trim().is_empty() {
+        "{editor} +{line} {file}"
+    } else {
+        template
+    };
+    template
+        .replace("{editor}", &editor())
+        .replace("{file}", &quote(&source.path))
+        .replace("{line}", &source.line.to_string())
+        .replace("{column}", &source.column.unwrap_or(1).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(text: &str) -> Vec<(String, u32, Option<u32>)> {
+        find_refs(text).into_iter().map(|r| (r.path, r.line, r.column)).collect()
+    }
+
+    #[test]
+    fn test_find_source_refs() {
+        assert_eq!(
+            refs("thread 'main' panicked at src/main.rs:10:5:"),
+            [("src/main.rs".to_string(), 10, Some(5))]
+        );
+        assert_eq!(
+            refs("at com.acme.Job.run(Job.java:42) via http://example.com:8080/x"),
+            [("Job.java".to_string(), 42, None)]
+        );
+        assert_eq!(
+            refs(r#"  File "app/views.py", line 17, in index"#),
+            [("app/views.py".to_string(), 17, None)]
+        );
+        assert!(refs("connected to db.internal:5432 at 12:00:01").is_empty());
+
+        let text = "a.go:1 b.go:2";
+        assert_eq!(ref_at(text, 9).unwrap().path, "b.go");
+        assert_eq!(ref_at(text, 6).unwrap().path, "a.go");
+        let source = &find_refs("x/a.rs:3")[0];
+        assert_eq!(editor_command("code -g {file}:{line}:{column}", source), "code -g 'x/a.rs':3:1");
+    }
+}
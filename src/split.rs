@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// How sources' bytes are cut into records, set once by `--split`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Split {
+    #[default]
+    Newline,
+    /// Records end with this byte string, which is dropped; `nul` is `\0`.
+    Delimiter(Vec<u8>),
+    /// Records of exactly this many bytes.
+    Fixed(usize),
+    /// One record per top-level JSON value, however many lines it spans.
+    Json,
+}
+
+impl FromStr for Split {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let usage = || anyhow!("Unknown split {} (expected newline, nul, json, delim:STRING or fixed:BYTES)", s);
+        match s {
+            "newline" | "lines" => Ok(Split::Newline),
+            "nul" | "null" => Ok(Split::Delimiter(vec![0])),
+            "json" => Ok(Split::Json),
+            _ => match s.split_once(':') {
+                Some(("delim" | "delimiter", text)) if !text.is_empty() => Ok(Split::Delimiter(unescape(text))),
+                Some(("fixed" | "length", bytes)) => match bytes.parse() {
+                    Ok(0) | Err(_) => Err(usage()),
+                    Ok(n) => Ok(Split::Fixed(n)),
+                },
+                _ => Err(usage()),
+            },
+        }
+    }
+}
+
+/// `\n`, `\t`, `\r`, `\0` and `\\` in a delimiter given on the command line.
+fn unescape(text: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let c = match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('n' | 't' | 'r' | '0' | '\\'))) => {
+                chars.next();
+                match next {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    _ => '\\',
+                }
+            }
+            _ => c,
+        };
+        let mut bytes = [0; 4];
+        out.extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+    }
+    out
+}
+
+static SPLIT: OnceLock<Split> = OnceLock::new();
+
+pub fn set_split(split: Split) {
+    let _ = SPLIT.set(split);
+}
+
+pub fn split() -> &'static Split {
+    SPLIT.get_or_init(Split::default)
+}
+
+/// Where a JSON value ends: nesting depth and whether we are in a string.
+#[derive(Default)]
+struct JsonScan {
+    started: bool,
+    /// A number, string or literal at the top level, which ends with its line.
+    scalar: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl JsonScan {
+    fn end(&mut self, chunk: &[u8]) -> Option<usize> {
+        for (i, &b) in chunk.iter().enumerate() {
+            if !self.started {
+                if b.is_ascii_whitespace() {
+                    continue;
+                }
+                self.started = true;
+                self.scalar = !matches!(b, b'{' | b'[');
+            }
+            if self.scalar {
+                if b == b'\n' {
+                    return Some(i + 1);
+                }
+            } else if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+            } else {
+                match b {
+                    b'"' => self.in_string = true,
+                    b'{' | b'[' => self.depth += 1,
+                    b'}' | b']' => {
+                        self.depth = self.depth.saturating_sub(1);
+                        if self.depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Finds where one record ends as its bytes come in.
+struct Scanner<'a> {
+    split: &'a Split,
+    json: JsonScan,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(split: &'a Split) -> Self {
+        Self {
+            split,
+            json: JsonScan::default(),
+        }
+    }
+
+    /// How many bytes of `chunk` finish the record begun with `record`, if
+    /// it ends within them.
+    fn end(&mut self, record: &[u8], chunk: &[u8]) -> Option<usize> {
+        match self.split {
+            Split::Newline => chunk.iter().position(|&b| b == b'\n').map(|i| i + 1),
+            Split::Delimiter(delimiter) => {
+                let last = *delimiter.last()?;
+                chunk.iter().enumerate().find_map(|(i, &b)| {
+                    let ends = b == last && {
+                        let head = delimiter.len().saturating_sub(i + 1);
+                        record.len() >= head
+                            && record[record.len() - head..] == delimiter[..head]
+                            && chunk[..=i].ends_with(&delimiter[head..])
+                    };
+                    ends.then_some(i + 1)
+                })
+            }
+            Split::Fixed(len) => {
+                let needed = len.saturating_sub(record.len());
+                (chunk.len() >= needed).then_some(needed)
+            }
+            Split::Json => self.json.end(chunk),
+        }
+    }
+
+    /// Move the bytes of `chunk` that belong to the record into `buf`.
+    /// Returns how many were taken and whether the record is complete.
+    fn take(&mut self, buf: &mut Vec<u8>, chunk: &[u8]) -> (usize, bool) {
+        let (taken, done) = match self.end(buf, chunk) {
+            Some(n) => (n, true),
+            None => (chunk.len(), false),
+        };
+        buf.extend_from_slice(&chunk[..taken]);
+        (taken, done)
+    }
+}
+
+impl Split {
+    /// A record's text: invalid UTF-8 (a legacy code page, say) decoded
+    /// lossily, the delimiter dropped, a JSON value trimmed. None for a JSON
+    /// stream's trailing whitespace.
+    fn text(&self, record: &[u8]) -> Option<String> {
+        let record = match self {
+            Split::Delimiter(delimiter) => record.strip_suffix(delimiter.as_slice()).unwrap_or(record),
+            Split::Json => record.trim_ascii(),
+            Split::Newline | Split::Fixed(_) => record,
+        };
+        (!record.is_empty() || *self != Split::Json).then(|| String::from_utf8_lossy(record).into_owned())
+    }
+
+    /// Read one record; at the end of the input, whatever is left.
+    pub fn read(&self, reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+        buf.clear();
+        let mut scanner = Scanner::new(self);
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                return Ok(if buf.is_empty() { None } else { self.text(buf) });
+            }
+            let (taken, done) = scanner.take(buf, chunk);
+            reader.consume(taken);
+            if done {
+                return Ok(self.text(buf).or_else(|| Some(String::new())));
+            }
+        }
+    }
+
+    /// `read()` for a network client.
+    pub async fn read_async(&self, reader: &mut (impl AsyncBufRead + Unpin), buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+        buf.clear();
+        let mut scanner = Scanner::new(self);
+        loop {
+            let chunk = reader.fill_buf().await?;
+            if chunk.is_empty() {
+                return Ok(if buf.is_empty() { None } else { self.text(buf) });
+            }
+            let (taken, done) = scanner.take(buf, chunk);
+            reader.consume(taken);
+            if done {
+                return Ok(self.text(buf).or_else(|| Some(String::new())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(split: &str, input: &[u8]) -> Vec<String> {
+        let split: Split = split.parse().unwrap();
+        // One byte at a time, so records and delimiters span reads.
+        let mut reader = io::BufReader::with_capacity(1, input);
+        let mut buf = Vec::new();
+        std::iter::from_fn(|| split.read(&mut reader, &mut buf).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_split_strategies() {
+        assert_eq!(records("nul", b"a\nb\0c\0d"), ["a\nb", "c", "d"]);
+        assert_eq!(records("delim:||", b"x|y||z||"), ["x|y", "z"]);
+        assert_eq!(records("delim:\\t", b"1\t2"), ["1", "2"]);
+        assert_eq!(records("fixed:3", b"abcdefgh"), ["abc", "def", "gh"]);
+        let json = b"{\n  \"a\": \"}\\\"\",\n  \"b\": [1, 2]\n}\n{\"c\": 3}{\"d\": {}}\n42\n\n";
+        assert_eq!(records("json", json), ["{\n  \"a\": \"}\\\"\",\n  \"b\": [1, 2]\n}", "{\"c\": 3}", "{\"d\": {}}", "42"]);
+        assert!("fixed:0".parse::<Split>().is_err() && "tabs".parse::<Split>().is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+static JAVA_FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+at\s+[\w$.<>/-]+\(.*\)\s*$").unwrap());
+static JAVA_MORE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s+\.\.\. \d+ more\s*$").unwrap());
+static PYTHON_FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s+File ".+", line \d+"#).unwrap());
+static RUST_FRAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*\d+:\s+\S+").unwrap());
+static RUST_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+at\s+\S+:\d+(:\d+)?\s*$").unwrap());
+static GO_FUNCTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\w./*()-]+\(.*\)$").unwrap());
+static GO_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+\S+\.go:\d+( \+0x[0-9a-f]+)?$").unwrap());
+
+/// One stack frame: the frame line plus any lines that belong to it (source
+/// location, quoted code).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Byte range of the frame line itself, without its continuations.
+    pub line: Range<usize>,
+    /// Byte range of the frame line and its continuations.
+    pub range: Range<usize>,
+    pub user: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackTrace {
+    pub frames: Vec<Frame>,
+}
+
+/// A trace with every frame after the first folded away.
+pub struct Collapsed<'a> {
+    pub head: &'a str,
+    /// The folded frames, between `head` and `tail`.
+    pub folded: &'a str,
+    pub tail: &'a str,
+    pub hidden_frames: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Frame,
+    Continuation,
+    Other,
+}
+
+fn classify(lines: &[&str], i: usize, prev: Kind) -> Kind {
+    let line = lines[i];
+    if JAVA_FRAME.is_match(line) || PYTHON_FRAME.is_match(line) || RUST_FRAME.is_match(line) {
+        return Kind::Frame;
+    }
+    if GO_FUNCTION.is_match(line) && lines.get(i + 1).is_some_and(|next| GO_LOCATION.is_match(next)) {
+        return Kind::Frame;
+    }
+    if prev != Kind::Other && (RUST_LOCATION.is_match(line) || GO_LOCATION.is_match(line) || JAVA_MORE.is_match(line)) {
+        return Kind::Continuation;
+    }
+    // Python quotes the source line under each `File "...", line N`.
+    if prev == Kind::Frame && PYTHON_FRAME.is_match(lines[i - 1]) && line.starts_with("    ") {
+        return Kind::Continuation;
+    }
+    Kind::Other
+}
+
+/// Recognise a Java, Python, Rust or Go stack trace in a multi-line record.
+/// Frames whose text contains `user_frames` (a package or path fragment) are
+/// marked as belonging to the user's own code.
+pub fn detect(content: &str, user_frames: &str) -> Option<StackTrace> {
+    if !content.contains('\n') {
+        return None;
+    }
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut prev = Kind::Other;
+    let mut offset = 0;
+
+    for i in 0..lines.len() {
+        let range = offset..offset + lines[i].len();
+        offset = range.end + 1;
+        if i == 0 {
+            continue;
+        }
+        let kind = classify(&lines, i, prev);
+        match kind {
+            Kind::Frame => frames.push(Frame {
+                line: range.clone(),
+                range,
+                user: false,
+            }),
+            Kind::Continuation => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.range.end = range.end;
+                }
+            }
+            Kind::Other => {}
+        }
+        prev = kind;
+    }
+
+    if frames.len() < 2 {
+        return None;
+    }
+    if !user_frames.is_empty() {
+        for frame in &mut frames {
+            frame.user = content[frame.range.clone()].contains(user_frames);
+        }
+    }
+    Some(StackTrace { frames })
+}
+
+impl StackTrace {
+    /// Keep the lines up to the first frame and whatever follows the last
+    /// frame (e.g. Python's exception message); fold the frames between.
+    pub fn collapse<'a>(&self, content: &'a str) -> Collapsed<'a> {
+        let first = &self.frames[0];
+        let last = self.frames.last().unwrap_or(first);
+        let tail = content.get(last.range.end + 1..).unwrap_or("");
+        let head = &content[..first.range.end];
+        Collapsed {
+            head,
+            folded: &content[head.len()..content.len() - tail.len()],
+            tail,
+            hidden_frames: self.frames.len() - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_java_trace_with_user_frames() {
+        let content = "Exception in thread \"main\" java.lang.IllegalStateException: boom\n\
+            \tat com.acme.app.Service.run(Service.java:42)\n\
+            \tat org.springframework.Foo.invoke(Foo.java:10)\n\
+            \tat com.acme.app.Main.main(Main.java:5)";
+        let trace = detect(content, "com.acme").unwrap();
+        let user: Vec<bool> = trace.frames.iter().map(|f| f.user).collect();
+        assert_eq!(user, vec![true, false, true]);
+
+        let collapsed = trace.collapse(content);
+        assert!(collapsed.head.ends_with("(Service.java:42)"));
+        assert_eq!(collapsed.hidden_frames, 2);
+        assert!(collapsed.folded.contains("Foo.invoke") && collapsed.folded.ends_with("(Main.java:5)"));
+        assert_eq!(collapsed.tail, "");
+    }
+
+    #[test]
+    fn test_python_trace_keeps_exception_line() {
+        let content = "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    main()\n  File \"app.py\", line 2, in main\n    raise ValueError('bad')\nValueError: bad";
+        let trace = detect(content, "").unwrap();
+        assert_eq!(trace.frames.len(), 2);
+        let collapsed = trace.collapse(content);
+        assert!(collapsed.head.ends_with("    main()"));
+        assert_eq!(collapsed.tail, "ValueError: bad");
+    }
+
+    #[test]
+    fn test_go_and_rust_frames() {
+        let go = "panic: runtime error\n\ngoroutine 1 [running]:\nmain.handler(0x1)\n\t/src/app/main.go:12 +0x1d\nmain.main()\n\t/src/app/main.go:20 +0x25";
+        assert_eq!(detect(go, "").unwrap().frames.len(), 2);
+
+        let rust = "thread 'main' panicked at src/main.rs:4:5:\nstack backtrace:\n   0: std::panicking::begin_panic\n             at /rustc/library/std/src/panicking.rs:616:12\n   1: app::main\n             at ./src/main.rs:4:5";
+        let trace = detect(rust, "app::").unwrap();
+        assert_eq!(trace.frames.iter().map(|f| f.user).collect::<Vec<_>>(), vec![false, true]);
+    }
+
+    #[test]
+    fn test_plain_multiline_is_not_a_trace() {
+        assert!(detect("first line\n  continued\n  more", "").is_none());
+    }
+}
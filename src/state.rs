@@ -1,6 +1,15 @@
+use crate::columns::{default_columns, Column, TimeFormat};
+use crate::layout::Layout;
+use crate::correlate::default_patterns;
+use crate::level::LevelRule;
+use crate::prefix::PrefixRule;
+use crate::highlight::{HighlightPriorities, HighlightSettings};
+use crate::tui::status::{default_segments, Segment};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::PathBuf;
 
 const STATE_FILE: &str = ".logviewer-state";
 
@@ -13,12 +22,106 @@ pub struct AppState {
     pub wrap_lines: bool,
     #[serde(default)]
     pub line_start_regex: String,
+    /// Display-time rewrite rules, `s/regex/replacement/; ...`.
+    #[serde(default)]
+    pub rewrite_input: String,
+    /// Watch patterns; the first capture group of each is tracked as a number.
+    #[serde(default)]
+    pub watch_input: String,
+    #[serde(default)]
+    pub highlight_priorities: HighlightPriorities,
+    /// SQL lines whose duration field reaches this many milliseconds are
+    /// flagged as slow; `null` turns the check off.
+    #[serde(default = "default_slow_query_ms")]
+    pub slow_query_ms: Option<u64>,
+    /// Package or path fragment marking the user's own stack frames.
+    #[serde(default)]
+    pub user_frames: String,
+    /// Color whole lines by their level: dim DEBUG, yellow WARN, red ERROR.
+    #[serde(default)]
+    pub level_tint: bool,
+    /// Prefix columns of the log view, in display order.
+    #[serde(default = "default_columns")]
+    pub columns: Vec<Column>,
+    /// Recorded keyboard macros by register, in vim key notation.
+    #[serde(default)]
+    pub macros: BTreeMap<char, String>,
+    /// Seconds near-duplicate lines are hidden for; `null` when off.
+    #[serde(default)]
+    pub dedup_window_secs: Option<u64>,
+    /// Filter expressions saved by name, counted alongside the active one.
+    #[serde(default)]
+    pub saved_filters: BTreeMap<String, String>,
+    /// How the receipt time column is shown.
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// Zone clock times are shown in: `local`, `UTC`, an offset like
+    /// `+05:30` or a tz database name like `Europe/Berlin`. Empty is local.
+    #[serde(default)]
+    pub time_zone: String,
+    /// strftime format of clock receipt times; empty is `%H:%M:%S%.3f`.
+    #[serde(default)]
+    pub clock_format: String,
+    /// strftime format of event times; empty is `%H:%M:%S`.
+    #[serde(default)]
+    pub event_time_format: String,
+    /// TUI key bindings over the defaults: keys in macro notation to an
+    /// action name, `""` to unbind.
+    #[serde(default)]
+    pub keymap: BTreeMap<String, String>,
+    /// TUI status bar segments in display order; left out ones are hidden.
+    #[serde(default = "default_segments")]
+    pub status_segments: Vec<Segment>,
+    /// Regexes finding a line's request or trace ID in their first capture
+    /// group, tried in order.
+    #[serde(default = "default_patterns")]
+    pub correlation_ids: Vec<String>,
+    /// `:humanize` spec for byte and duration fields; `null` when off.
+    #[serde(default)]
+    pub humanize: Option<String>,
+    /// Shell command `gf` opens a `file:line` reference with; `{editor}`,
+    /// `{file}`, `{line}` and `{column}` are filled in. Empty is
+    /// `{editor} +{line} {file}`.
+    #[serde(default)]
+    pub editor_command: String,
+    /// URL `gi` opens and `gI` copies for the cursor record, to file an
+    /// issue; `{message}`, `{title}`, `{timestamp}`, `{level}`, `{file}` and
+    /// `{line}` are filled in.
+    #[serde(default)]
+    pub issue_url: String,
+    /// Show who last changed the code behind `file:line` references in the
+    /// detail popup, from `git blame`.
+    #[serde(default)]
+    pub git_blame: bool,
+    /// Rules re-classifying lines' levels by their text, first match wins.
+    #[serde(default)]
+    pub level_rules: Vec<LevelRule>,
+    /// Layouts saved with `:layout save NAME`, over the built-in ones.
+    #[serde(default)]
+    pub layouts: BTreeMap<String, Layout>,
+    /// MaxMind databases (`.mmdb`: City, Country or ASN) that `:enrich`
+    /// looks IP addresses up in.
+    #[serde(default)]
+    pub geoip_databases: Vec<String>,
+    /// Whether `:enrich` also looks up the host names of IP addresses.
+    #[serde(default)]
+    pub reverse_dns: bool,
+    /// Show listen-mode peers by address instead of host name.
+    #[serde(default)]
+    pub numeric_peers: bool,
+    /// Rules cutting wrappers off the start of lines before they are parsed.
+    #[serde(default)]
+    pub prefix_rules: Vec<PrefixRule>,
 }
 
 fn default_wrap_lines() -> bool {
     true
 }
 
+fn default_slow_query_ms() -> Option<u64> {
+    Some(1000)
+}
+
 impl Default for AppState {
     fn default() -> Self {
         Self {
@@ -27,27 +130,90 @@ impl Default for AppState {
             highlight_input: String::new(),
             wrap_lines: true,
             line_start_regex: String::new(),
+            rewrite_input: String::new(),
+            watch_input: String::new(),
+            highlight_priorities: HighlightPriorities::default(),
+            slow_query_ms: default_slow_query_ms(),
+            user_frames: String::new(),
+            level_tint: false,
+            columns: default_columns(),
+            macros: BTreeMap::new(),
+            dedup_window_secs: None,
+            saved_filters: BTreeMap::new(),
+            time_format: TimeFormat::default(),
+            time_zone: String::new(),
+            clock_format: String::new(),
+            event_time_format: String::new(),
+            keymap: BTreeMap::new(),
+            status_segments: default_segments(),
+            correlation_ids: default_patterns(),
+            humanize: None,
+            editor_command: String::new(),
+            issue_url: String::new(),
+            git_blame: false,
+            level_rules: Vec::new(),
+            layouts: BTreeMap::new(),
+            geoip_databases: Vec::new(),
+            reverse_dns: false,
+            numeric_peers: false,
+            prefix_rules: Vec::new(),
         }
     }
 }
 
 impl AppState {
+    pub fn highlight_settings(&self) -> HighlightSettings {
+        HighlightSettings {
+            priorities: self.highlight_priorities,
+            slow_query_ms: self.slow_query_ms,
+            user_frames: self.user_frames.clone(),
+            level_tint: self.level_tint,
+        }
+    }
+
     pub fn load() -> Self {
-        let path = Path::new(STATE_FILE);
-        if path.exists() {
-            if let Ok(content) = fs::read_to_string(path) {
-                if let Ok(state) = serde_json::from_str(&content) {
-                    return state;
-                }
-            }
+        Self::load_reporting().0
+    }
+
+    /// The saved settings, or the defaults and why the file was ignored.
+    pub fn load_reporting() -> (Self, Option<String>) {
+        let path = state_path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return (Self::default(), None),
+            Err(e) => return (Self::default(), Some(format!("Cannot read {}: {}", path.display(), e))),
+        };
+        match serde_json::from_str(&content) {
+            Ok(state) => (state, None),
+            Err(e) => (Self::default(), Some(format!("Ignored {}: {}", path.display(), e))),
         }
-        Self::default()
     }
 
     pub fn save(&self) {
-        let path = Path::new(STATE_FILE);
+        let path = state_path();
         if let Ok(content) = serde_json::to_string_pretty(self) {
             let _ = fs::write(path, content);
         }
     }
 }
+
+fn state_path() -> PathBuf {
+    data_path(STATE_FILE, "state.json")
+}
+
+/// Where a per-user file such as `.logviewer-state` lives: the working
+/// directory. On Windows, where programs started from Explorer run in a
+/// system directory, it is `%APPDATA%\logviewer\<windows_name>` (created
+/// here) unless the working directory has its own.
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn data_path(name: &str, windows_name: &str) -> PathBuf {
+    #[cfg(windows)]
+    if !std::path::Path::new(name).exists() {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            let dir = PathBuf::from(appdata).join("logviewer");
+            let _ = fs::create_dir_all(&dir);
+            return dir.join(windows_name);
+        }
+    }
+    PathBuf::from(name)
+}
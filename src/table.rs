@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+/// Widest a column is padded to; longer values just push the rest along.
+const MAX_WIDTH: usize = 32;
+
+/// How sources' lines are read, set once by `--format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// A header row that looks like CSV or TSV column names makes a table,
+    /// and access log, logcat and unified log lines are read as such.
+    #[default]
+    Auto,
+    Csv,
+    Tsv,
+    /// Apache or nginx access logs (`access.rs`), no tables.
+    Access,
+    /// Android `logcat -v threadtime` (`logcat.rs`), no tables.
+    Logcat,
+    /// macOS `log stream --style ndjson` (`oslog.rs`), no tables.
+    OsLog,
+    /// Neither tables nor access logs.
+    Text,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Format::Auto),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "nginx" | "combined" | "common" => Ok(Format::Access),
+            "logcat" => Ok(Format::Logcat),
+            "oslog" => Ok(Format::OsLog),
+            "text" => Ok(Format::Text),
+            _ => Err(anyhow!("Unknown format {} (expected auto, csv, tsv, nginx, combined, common, logcat, oslog or text)", s)),
+        }
+    }
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> Format {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Split a CSV or TSV row, honoring double quotes and `""` inside them.
+pub fn split_row(row: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.trim_end_matches(['\r', '\n']).chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Values that wouldn't read back as one logfmt value, in double quotes.
+fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains([' ', '"', '=']) {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Column names from a source's first line, and how wide their values have
+/// been so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub delimiter: char,
+    pub headers: Vec<String>,
+    widths: Vec<usize>,
+    /// The header line as read, shown as it is when it stays in the buffer.
+    header_row: String,
+}
+
+/// What `Tables::observe` made of a line.
+pub enum Row<'a> {
+    /// The header row of `--format csv|tsv`, replaced by a columns marker.
+    Header(&'a Table),
+    /// The row that confirmed the header line before it; a columns marker
+    /// goes before it.
+    Confirmed(&'a Table),
+    Plain,
+}
+
+impl Table {
+    fn new(header_row: &str, delimiter: char) -> Self {
+        let headers: Vec<String> = split_row(header_row, delimiter).into_iter().map(|h| h.trim().to_string()).collect();
+        let widths = headers.iter().map(|h| h.chars().count()).collect();
+        Self {
+            delimiter,
+            headers,
+            widths,
+            header_row: header_row.to_string(),
+        }
+    }
+
+    /// `line` as a header row, if it looks like one: three or more comma
+    /// separated names (two for tabs), each a single identifier-like word.
+    pub fn detect(line: &str) -> Option<Self> {
+        let (delimiter, min_fields) = if line.contains('\t') { ('\t', 2) } else { (',', 3) };
+        let headers = split_row(line, delimiter);
+        let is_name = |h: &str| {
+            let mut chars = h.trim().chars();
+            chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        };
+        (headers.len() >= min_fields && headers.iter().all(|h| is_name(h))).then(|| Self::new(line, delimiter))
+    }
+
+    /// Whether `row` has as many fields as there are columns.
+    fn fits(&self, row: &str) -> bool {
+        split_row(row, self.delimiter).len() == self.headers.len()
+    }
+
+    fn observe(&mut self, row: &str) {
+        for (i, value) in split_row(row, self.delimiter).iter().enumerate() {
+            let width = quote(value).chars().count().min(MAX_WIDTH);
+            match self.widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => self.widths.push(width),
+            }
+        }
+    }
+
+    /// `row` as aligned `name=value` fields, so key filters, `:stats` and
+    /// `:top` see named fields.
+    pub fn display(&self, row: &str) -> String {
+        if row == self.header_row {
+            return row.to_string();
+        }
+        let values = split_row(row, self.delimiter);
+        let count = values.len();
+        let mut out = String::new();
+        for (i, value) in values.iter().enumerate() {
+            let name = self.headers.get(i).cloned().unwrap_or_else(|| format!("col{}", i + 1));
+            let field = format!("{}={}", name, quote(value));
+            if i + 1 == count {
+                out.push_str(&field);
+            } else {
+                let width = name.chars().count() + 1 + self.widths.get(i).copied().unwrap_or(0);
+                out.push_str(&format!("{:<width$}  ", field, width = width));
+            }
+        }
+        out
+    }
+}
+
+/// The table each source started with, if it did.
+#[derive(Clone, Default)]
+pub struct Tables {
+    checked: HashSet<Option<Arc<str>>>,
+    /// A first line that looks like a header, until the next line from its
+    /// source shows whether the columns line up.
+    candidates: HashMap<Option<Arc<str>>, Table>,
+    tables: HashMap<Option<Arc<str>>, Table>,
+}
+
+impl Tables {
+    /// Note a line from `source`. With `--format auto` a header-like first
+    /// line is kept as it is and only makes a table once the second line
+    /// has as many fields.
+    pub fn observe(&mut self, content: &str, source: &Option<Arc<str>>) -> Row<'_> {
+        if let Some(table) = self.tables.get_mut(source) {
+            table.observe(content);
+            return Row::Plain;
+        }
+        if let Some(candidate) = self.candidates.remove(source) {
+            if !candidate.fits(content) {
+                return Row::Plain;
+            }
+            let table = self.tables.entry(source.clone()).or_insert(candidate);
+            table.observe(content);
+            return Row::Confirmed(table);
+        }
+        if content.trim().is_empty() || !self.checked.insert(source.clone()) {
+            return Row::Plain;
+        }
+        let table = match format() {
+            Format::Auto => {
+                if let Some(candidate) = Table::detect(content) {
+                    self.candidates.insert(source.clone(), candidate);
+                }
+                return Row::Plain;
+            }
+            Format::Csv => Table::new(content, ','),
+            Format::Tsv => Table::new(content, '\t'),
+            Format::Access | Format::Logcat | Format::OsLog | Format::Text => return Row::Plain,
+        };
+        Row::Header(self.tables.entry(source.clone()).or_insert(table))
+    }
+
+    pub fn get(&self, source: &Option<Arc<str>>) -> Option<&Table> {
+        self.tables.get(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_rows_as_fields() {
+        assert_eq!(split_row(r#"1,"a, ""b""",,x"#, ','), ["1", r#"a, "b""#, "", "x"]);
+        assert!(Table::detect("Starting server, port 8080, ok").is_none());
+        assert!(Table::detect("ts,level,msg").is_some());
+
+        // A header-like line needs a second with as many fields.
+        let mut tables = Tables::default();
+        let source = None;
+        assert!(matches!(tables.observe("main\tstarted", &source), Row::Plain));
+        assert!(matches!(tables.observe("worker 2 ready", &source), Row::Plain));
+        assert!(tables.get(&source).is_none());
+
+        let mut tables = Tables::default();
+        assert!(matches!(tables.observe("time\tstatus\tpath", &source), Row::Plain));
+        let Row::Confirmed(table) = tables.observe("12:00\t200\t/index.html", &source) else {
+            panic!("expected the header to be confirmed");
+        };
+        assert_eq!(table.headers, ["time", "status", "path"]);
+        tables.observe("12:01\t404\t/a b", &source);
+        let table = tables.get(&source).unwrap();
+        assert_eq!(table.display("time\tstatus\tpath"), "time\tstatus\tpath");
+        assert_eq!(table.display("12:01\t404\t/a b"), r#"time=12:01  status=404     path="/a b""#);
+        assert_eq!(table.display("12:00\t200\t/x\textra"), "time=12:00  status=200     path=/x           col4=extra");
+    }
+}
@@ -0,0 +1,214 @@
+use crate::core::LogLine;
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+pub const DEFAULT_CLOCK_FORMAT: &str = "%H:%M:%S%.3f";
+pub const DEFAULT_EVENT_FORMAT: &str = "%H:%M:%S";
+
+/// The time zone times are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Utc,
+    /// An offset like `+05:30`.
+    Fixed(FixedOffset),
+    /// A tz database zone like `Europe/Berlin`.
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "" | "local" => Ok(Zone::Local),
+            "utc" | "UTC" | "Z" => Ok(Zone::Utc),
+            s => s
+                .parse()
+                .map(Zone::Named)
+                .or_else(|_| s.parse().map(Zone::Fixed))
+                .map_err(|_| anyhow!("Unknown time zone: {} (expected local, UTC, +05:30 or e.g. Europe/Berlin)", s)),
+        }
+    }
+}
+
+/// How clock times of both the receipt and event columns are rendered: a
+/// strftime format each, in one zone. Empty strings mean the defaults.
+#[derive(Clone, Debug, Default)]
+pub struct TimeDisplay {
+    pub zone_name: String,
+    zone: Zone,
+    pub clock_format: String,
+    pub event_format: String,
+    /// The lines span more than one day, so formats without a date get
+    /// `%m-%d` put in front.
+    pub dated: bool,
+}
+
+/// Whether `format` already shows the day.
+fn has_date(format: &str) -> bool {
+    ["%d", "%e", "%j", "%D", "%F", "%x", "%c", "%+", "%s"].iter().any(|spec| format.contains(spec))
+}
+
+fn check_format(format: &str) -> Result<()> {
+    match StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        true => Err(anyhow!("Invalid time format: {}", format)),
+        false => Ok(()),
+    }
+}
+
+impl TimeDisplay {
+    pub fn new(zone: &str, clock_format: &str, event_format: &str) -> Result<Self> {
+        check_format(clock_format)?;
+        check_format(event_format)?;
+        Ok(Self {
+            zone_name: zone.trim().to_string(),
+            zone: zone.parse()?,
+            clock_format: clock_format.to_string(),
+            event_format: event_format.to_string(),
+            dated: false,
+        })
+    }
+
+    pub fn set_zone(&mut self, name: &str) -> Result<()> {
+        self.zone = name.parse()?;
+        self.zone_name = name.trim().to_string();
+        Ok(())
+    }
+
+    /// Set the receipt (`clock`) or `event` format; empty restores the
+    /// default.
+    pub fn set_format(&mut self, which: &str, format: &str) -> Result<()> {
+        check_format(format)?;
+        match which {
+            "clock" | "receipt" => self.clock_format = format.to_string(),
+            "event" => self.event_format = format.to_string(),
+            _ => return Err(anyhow!("Expected clock or event, not {}", which)),
+        }
+        Ok(())
+    }
+
+    pub fn zone_label(&self) -> &str {
+        match self.zone {
+            Zone::Local => "local",
+            _ => &self.zone_name,
+        }
+    }
+
+    fn render(&self, time: DateTime<Local>, format: &str) -> String {
+        let format = match self.dated && !has_date(format) {
+            true => Cow::Owned(format!("%m-%d {}", format)),
+            false => Cow::Borrowed(format),
+        };
+        fn show<Z: TimeZone>(time: DateTime<Local>, zone: &Z, format: &str) -> String
+        where
+            Z::Offset: Display,
+        {
+            time.with_timezone(zone).format(format).to_string()
+        }
+        match self.zone {
+            Zone::Local => time.format(&format).to_string(),
+            Zone::Utc => show(time, &Utc, &format),
+            Zone::Fixed(offset) => show(time, &offset, &format),
+            Zone::Named(tz) => show(time, &tz, &format),
+        }
+    }
+
+    /// The day `line` is from in the zone, by its event time when it has one.
+    pub fn day(&self, line: &LogLine) -> NaiveDate {
+        let time = line.event_time.unwrap_or(line.timestamp);
+        match self.zone {
+            Zone::Local => time.date_naive(),
+            Zone::Utc => time.with_timezone(&Utc).date_naive(),
+            Zone::Fixed(offset) => time.with_timezone(&offset).date_naive(),
+            Zone::Named(tz) => time.with_timezone(&tz).date_naive(),
+        }
+    }
+
+    /// Turn on `dated` once the first and last of `lines` are from
+    /// different days; true when it just did.
+    pub fn check_rollover(&mut self, lines: &[LogLine]) -> bool {
+        let (Some(first), Some(last)) = (lines.first(), lines.last()) else {
+            return false;
+        };
+        if self.dated || self.day(first) == self.day(last) {
+            return false;
+        }
+        self.dated = true;
+        true
+    }
+
+    fn clock_format(&self) -> &str {
+        if self.clock_format.is_empty() { DEFAULT_CLOCK_FORMAT } else { &self.clock_format }
+    }
+
+    fn event_format(&self) -> &str {
+        if self.event_format.is_empty() { DEFAULT_EVENT_FORMAT } else { &self.event_format }
+    }
+
+    /// A receipt time in the clock format.
+    pub fn clock(&self, time: DateTime<Local>) -> String {
+        self.render(time, self.clock_format())
+    }
+
+    pub fn event(&self, time: DateTime<Local>) -> String {
+        self.render(time, self.event_format())
+    }
+
+    /// Width of a late-December time in `format`, which is about as wide as
+    /// the format gets.
+    fn width(&self, format: &str) -> usize {
+        let sample = Local.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).earliest().unwrap_or_else(Local::now);
+        self.render(sample, format).chars().count()
+    }
+
+    pub fn clock_width(&self) -> usize {
+        self.width(self.clock_format())
+    }
+
+    pub fn event_width(&self) -> usize {
+        self.width(self.event_format())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_display_zones_and_formats() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 5, 22, 30, 15).unwrap().with_timezone(&Local);
+        let utc = TimeDisplay::new("UTC", "", "%Y-%m-%d %H:%M:%S %Z").unwrap();
+        assert_eq!(utc.clock(time), "22:30:15.000");
+        assert_eq!(utc.event(time), "2024-03-05 22:30:15 UTC");
+        assert_eq!(utc.event_width(), 23);
+
+        let mut display = TimeDisplay::new("Asia/Tokyo", "%H:%M", "").unwrap();
+        assert_eq!(display.clock(time), "07:30");
+        display.set_zone("-02:00").unwrap();
+        assert_eq!(display.event(time), "20:30:15");
+        assert_eq!(display.zone_label(), "-02:00");
+        assert!(display.set_zone("Mars/Olympus").is_err());
+        assert!(display.set_format("event", "%Q").is_err());
+        display.set_format("clock", "").unwrap();
+        assert_eq!(display.clock_width(), 12);
+
+        let lines = [
+            LogLine::new("2024-03-05T23:59:58Z closing".to_string(), None, time),
+            LogLine::new("2024-03-06T00:00:01Z rotated".to_string(), None, time),
+        ];
+        let mut utc = TimeDisplay::new("UTC", "", "").unwrap();
+        assert!(utc.check_rollover(&lines));
+        assert!(!utc.check_rollover(&lines));
+        assert_eq!(utc.event(lines[1].event_time.unwrap()), "03-06 00:00:01");
+        assert_eq!(utc.clock_width(), 18);
+        utc.set_format("event", "%F %T").unwrap();
+        assert_eq!(utc.event(lines[1].event_time.unwrap()), "2024-03-06 00:00:01");
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static ISO: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d{4}-\d{2}-\d{2})[T ](\d{2}:\d{2}:\d{2})(\.\d+)?\s?(Z|[+-]\d{2}:?\d{2})?").unwrap()
+});
+static SYSLOG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Z][a-z]{2}) +(\d{1,2}) (\d{2}:\d{2}:\d{2})").unwrap());
+static CLF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[(\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4})\]").unwrap());
+static LOGCAT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2}-\d{2}) (\d{2}:\d{2}:\d{2}\.\d{3})\s").unwrap());
+static EPOCH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\[?(\d{10}|\d{13})(\.\d+)?\b").unwrap());
+/// Seconds, milliseconds, microseconds or nanoseconds since the epoch,
+/// anywhere in a line.
+static EPOCH_ANYWHERE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{10}|\d{13}|\d{16}|\d{19})\b").unwrap());
+/// Only this century's epoch values are taken for times, not any long number.
+const EPOCH_RANGE: std::ops::Range<i64> = 946_684_800..4_102_444_800;
+
+/// Parse the time an event happened from the line itself: ISO 8601 /
+/// RFC 3339 or an access log's `[31/Jan/2024:14:05:01 +0000]` anywhere in
+/// the line, or a syslog (`Jan  2 03:04:05`), logcat (`01-31 14:05:01.123`)
+/// or Unix epoch (seconds or milliseconds) timestamp at its start.
+pub fn parse_event_time(content: &str) -> Option<DateTime<Local>> {
+    if let Some(caps) = ISO.captures(content) {
+        let frac = caps.get(3).map_or("", |m| m.as_str());
+        let naive = NaiveDateTime::parse_from_str(&format!("{} {}{}", &caps[1], &caps[2], frac), "%Y-%m-%d %H:%M:%S%.f").ok()?;
+        return match caps.get(4).map(|m| m.as_str()) {
+            None => Local.from_local_datetime(&naive).earliest(),
+            Some("Z") => Some(naive.and_utc().with_timezone(&Local)),
+            Some(offset) => {
+                let offset = offset.replace(':', "");
+                let text = format!("{} {}", naive.format("%Y-%m-%d %H:%M:%S%.f"), offset);
+                DateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S%.f %z")
+                    .ok()
+                    .map(|t| t.with_timezone(&Local))
+            }
+        };
+    }
+
+    if let Some(caps) = CLF.captures(content) {
+        return DateTime::parse_from_str(&caps[1], "%d/%b/%Y:%H:%M:%S %z")
+            .ok()
+            .map(|t| t.with_timezone(&Local));
+    }
+
+    if let Some(caps) = SYSLOG.captures(content) {
+        let year = Local::now().year();
+        let text = format!("{} {} {} {}", year, &caps[1], &caps[2], &caps[3]);
+        let naive = NaiveDateTime::parse_from_str(&text, "%Y %b %d %H:%M:%S").ok()?;
+        return Local.from_local_datetime(&naive).earliest();
+    }
+
+    if let Some(caps) = LOGCAT.captures(content) {
+        let text = format!("{}-{} {}", Local::now().year(), &caps[1], &caps[2]);
+        let naive = NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S%.3f").ok()?;
+        return Local.from_local_datetime(&naive).earliest();
+    }
+
+    if let Some(caps) = EPOCH.captures(content) {
+        let digits = &caps[1];
+        let secs: i64 = digits.parse().ok()?;
+        let millis = if digits.len() == 13 { secs } else { secs * 1000 };
+        return Local.timestamp_millis_opt(millis).single();
+    }
+
+    None
+}
+
+/// `content` with the local time written after each epoch timestamp in it:
+/// `1718123456789 (2024-06-11 18:30:56.789)`.
+pub fn annotate_epochs(content: &str) -> String {
+    EPOCH_ANYWHERE
+        .replace_all(content, |caps: &regex::Captures| {
+            let digits = &caps[1];
+            let Ok(value) = digits.parse::<i64>() else {
+                return digits.to_string();
+            };
+            let (nanos, format) = match digits.len() {
+                10 => (value.saturating_mul(1_000_000_000), "%Y-%m-%d %H:%M:%S"),
+                13 => (value.saturating_mul(1_000_000), "%Y-%m-%d %H:%M:%S%.3f"),
+                16 => (value.saturating_mul(1_000), "%Y-%m-%d %H:%M:%S%.6f"),
+                _ => (value, "%Y-%m-%d %H:%M:%S%.9f"),
+            };
+            if !EPOCH_RANGE.contains(&(nanos / 1_000_000_000)) {
+                return digits.to_string();
+            }
+            let time = Local.timestamp_nanos(nanos);
+            format!("{} ({})", digits, time.format(format))
+        })
+        .into_owned()
+}
+
+/// `message` with the time a log service recorded for it (ms since the
+/// epoch) in front, or as a `time` key of a JSON object, unless it states a
+/// time of its own.
+pub fn with_time(message: &str, timestamp_ms: i64) -> String {
+    let Some(time) = DateTime::<Utc>::from_timestamp_millis(timestamp_ms).filter(|_| parse_event_time(message).is_none()) else {
+        return message.to_string();
+    };
+    let time = time.to_rfc3339_opts(SecondsFormat::Millis, true);
+    match serde_json::from_str(message) {
+        Ok(serde_json::Value::Object(mut object)) => {
+            object.insert("time".to_string(), time.into());
+            serde_json::Value::Object(object).to_string()
+        }
+        _ => format!("{} {}", time, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Timelike, Utc};
+
+    #[test]
+    fn test_iso_with_offset() {
+        let t = parse_event_time("2024-03-05T10:20:30.250+02:00 started").unwrap();
+        let utc = t.with_timezone(&Utc);
+        assert_eq!((utc.hour(), utc.minute(), utc.second()), (8, 20, 30));
+        assert_eq!(utc.timestamp_subsec_millis(), 250);
+    }
+
+    #[test]
+    fn test_access_log_time() {
+        let t = parse_event_time(r#"10.0.0.1 - - [31/Jan/2024:14:05:01 -0130] "GET / HTTP/1.1" 200 5"#).unwrap();
+        let utc = t.with_timezone(&Utc);
+        assert_eq!((utc.hour(), utc.minute(), utc.second()), (15, 35, 1));
+    }
+
+    #[test]
+    fn test_syslog_and_epoch() {
+        let t = parse_event_time("Mar  5 10:20:30 host sshd[1]: ok").unwrap();
+        assert_eq!((t.month(), t.day(), t.hour()), (3, 5, 10));
+
+        let t = parse_event_time("1700000000123 request").unwrap();
+        assert_eq!(t.timestamp_millis(), 1_700_000_000_123);
+    }
+
+    #[test]
+    fn test_annotate_epochs() {
+        let annotated = annotate_epochs("sent=1718123456789 id=12345678901234567 seq=4200000000");
+        let expected = Local.timestamp_millis_opt(1_718_123_456_789).unwrap().format("%Y-%m-%d %H:%M:%S%.3f");
+        assert_eq!(annotated, format!("sent=1718123456789 ({}) id=12345678901234567 seq=4200000000", expected));
+        assert!(annotate_epochs("ts=1718123456789000000").contains(".789000000)"));
+        assert_eq!(annotate_epochs("abc1718123456 port 8080"), "abc1718123456 port 8080");
+    }
+
+    #[test]
+    fn test_no_timestamp() {
+        assert!(parse_event_time("plain message 12:00").is_none());
+    }
+}
@@ -1,17 +1,50 @@
-use crate::app::App;
+pub mod picker;
+pub mod status;
+mod view;
+
+use crate::aggregate::{format_number, FieldStats, TopValues};
+use crate::app::{App, Row, RowHit};
 use crate::constants::{
     HELP_POPUP_HEIGHT, HELP_POPUP_WIDTH, INPUT_FIELD_HEIGHT, QUIT_POPUP_HEIGHT, QUIT_POPUP_WIDTH,
-    STATUS_BAR_HEIGHT,
+    OUTLINE_PANEL_WIDTH, PASTE_POPUP_WIDTH, STATUS_BAR_HEIGHT, TOAST_MAX_WIDTH, WATCH_PANEL_WIDTH,
+};
+use crate::columns::{cell_text, fit, visible_columns, ColumnKind};
+use crate::core::buffers;
+use crate::core::markers::{self, MARK_ERROR, MARK_MATCH, MARK_NOTE};
+use crate::core::detail_state::find_matches;
+use crate::core::{
+    format_listen_addr, get_time_age, Severity, InputMode, ListenAddrEntry, ListenDisplayMode, Snoozes, TimeAge, Why, MAX_LANES,
 };
-use crate::core::{format_relative_time, InputMode, ListenAddrEntry, ListenDisplayMode};
+use crate::filter_stats::format_rate;
+use crate::level::Level;
+use crate::netinfo::{AddressInfo, Reach};
+use crate::pipe::PipeOutput;
+use crate::plugin::{self, Status};
+use crate::qr::QrMatrix;
 use crate::input::TextInput;
+use crate::watch::{format_value, Watch};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Sparkline, Wrap},
     Frame,
 };
+use std::ops::{Range, RangeInclusive};
+use std::time::Instant;
+use view::{Record, ViewModel};
+
+/// One color per lane of the lane view, also used for the thread column.
+const LANE_COLORS: [Color; MAX_LANES] = [
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::Rgb(255, 165, 0),
+    Color::Rgb(160, 160, 255),
+];
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -21,6 +54,7 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             Constraint::Length(INPUT_FIELD_HEIGHT),
             Constraint::Length(INPUT_FIELD_HEIGHT),
             Constraint::Length(INPUT_FIELD_HEIGHT),
+            Constraint::Length(INPUT_FIELD_HEIGHT),
             Constraint::Min(1),
             Constraint::Length(STATUS_BAR_HEIGHT),
         ])
@@ -54,17 +88,116 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         " Line Start (s) ",
         app.input_mode == InputMode::LineStartEdit,
     );
-    draw_log_view(frame, app, chunks[4]);
-    draw_status_bar(frame, app, chunks[5]);
+    draw_text_input(
+        frame,
+        &app.input_fields.rewrite,
+        chunks[4],
+        " Rewrite (r) ",
+        app.input_mode == InputMode::RewriteEdit,
+    );
+    let mut log_area = chunks[5];
+    if !app.log_state.pins.is_empty() {
+        let height = app.log_state.pins.lines().len() as u16 + 2;
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(height), Constraint::Min(1)])
+            .split(log_area);
+        draw_pins(frame, app, parts[0]);
+        log_area = parts[1];
+    }
+    if let Some(selected) = app.outline_panel {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(OUTLINE_PANEL_WIDTH), Constraint::Min(1)])
+            .split(log_area);
+        draw_outline(frame, app, selected, panes[0]);
+        log_area = panes[1];
+    }
+    if app.show_watches {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Length(WATCH_PANEL_WIDTH)])
+            .split(log_area);
+        draw_log_view(frame, app, panes[0]);
+        draw_watch_panel(frame, app, panes[1]);
+    } else {
+        draw_log_view(frame, app, log_area);
+    }
+    draw_status_bar(frame, app, chunks[6]);
+    draw_toasts(frame, app, chunks[5]);
 
-    if app.input_mode != InputMode::Normal {
+    if !matches!(app.input_mode, InputMode::Normal | InputMode::Command) {
         draw_help_popup(frame);
     }
 
+    if app.detail.is_some() {
+        draw_detail_popup(frame, app);
+    }
+
+    if app.entity_menu.is_some() {
+        draw_entity_menu(frame, app);
+    }
+
+    if app.column_editor.is_some() {
+        draw_column_editor(frame, app);
+    }
+
+    if app.filter_stats_popup.is_some() {
+        draw_filter_stats(frame, app);
+    }
+
+    if app.show_plugins {
+        draw_plugins(frame);
+    }
+
+    if let Some(offset) = app.messages_popup {
+        draw_messages(frame, app, offset);
+    }
+
+    if let Some(stats) = &app.stats_popup {
+        draw_field_stats(frame, stats, app.stats_scroll);
+    }
+
+    if let Some((output, scroll)) = &app.pipe_popup {
+        draw_pipe_output(frame, output, *scroll);
+    }
+
+    if let Some(selected) = app.snooze_popup {
+        draw_snoozes(frame, &app.log_state.snoozes, selected);
+    }
+
+    if let Some((top, selected)) = &app.top_popup {
+        draw_top_values(frame, top, *selected);
+    }
+
+    if let Some(selected) = app.fields_popup {
+        draw_fields(frame, app, selected);
+    }
+
+    if let Some(why) = &app.why {
+        draw_why(frame, app, why);
+    }
+
+    if let Some(picker) = &app.file_picker {
+        picker::draw(frame, picker);
+    }
+
+    if app.note_editor.is_some() {
+        draw_note_editor(frame, app);
+    }
+
+    if app.playground.is_some() {
+        draw_playground(frame, app);
+    }
+
     if app.listen_state.show_popup() {
         draw_listen_popup(frame, app);
     }
 
+    if let Some(text) = &app.paste_prompt {
+        draw_paste_prompt(frame, text);
+    }
+
     if app.show_quit_confirm {
         draw_quit_confirm(frame);
     }
@@ -83,202 +216,1350 @@ fn draw_text_input(frame: &mut Frame, input: &TextInput, area: Rect, label: &str
         label.to_string()
     };
 
-    let border_style = if input.has_error() {
-        Style::default().fg(Color::Red)
-    } else {
-        style
+    let border_style = if input.has_error() {
+        Style::default().fg(Color::Red)
+    } else {
+        style
+    };
+
+    let widget = Paragraph::new(input.text.as_str())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(border_style),
+        )
+        .style(style);
+    frame.render_widget(widget, area);
+
+    if is_active {
+        frame.set_cursor_position((area.x + input.cursor as u16 + 1, area.y + 1));
+    }
+}
+
+fn draw_log_view(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.row_hits.clear();
+    app.minimap = None;
+    let Some(split) = app.split else {
+        draw_log_pane(frame, app, area, None);
+        return;
+    };
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let (focused, other) = if split.focus_top { (panes[0], panes[1]) } else { (panes[1], panes[0]) };
+    draw_log_pane(frame, app, other, app.split_bottom());
+    draw_log_pane(frame, app, focused, None);
+}
+
+/// One pane of the log view: the one the keys move, or with `other` the
+/// other pane of the split view, whose bottom is at that filtered position.
+fn draw_log_pane(frame: &mut Frame, app: &mut App, area: Rect, other: Option<usize>) {
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let inner_width = area.width.saturating_sub(2) as usize;
+
+    let mut title = format!(
+        " Logs{} [{}/{}] {}{}{}{}{}{}{} ",
+        match app.buffers.current.as_str() {
+            buffers::MAIN => String::new(),
+            name => format!(" ({})", name),
+        },
+        app.log_state.filtered_indices.len(),
+        app.log_state.lines.len(),
+        app.log_state.dedup
+            .as_ref()
+            .map(|d| format!("[DEDUP {}s, {} hidden]", d.window_secs, d.total_suppressed))
+            .unwrap_or_default(),
+        match app.log_state.hidden_matches {
+            0 => String::new(),
+            n => format!("[{} matches hidden]", n),
+        },
+        match app.log_state.marks.len() {
+            0 => String::new(),
+            n => format!("[{} marked]", n),
+        },
+        if app.read_only { "[READ-ONLY]" } else { "" },
+        app.replay_label().unwrap_or_default(),
+        if app.log_state.follow_tail {
+            "[FOLLOW]"
+        } else if app.log_state.follow_match.is_some() {
+            "[FOLLOW MATCH]"
+        } else {
+            ""
+        },
+        if app.wrap_lines { "[WRAP]" } else { "" }
+    );
+    let mut border = Color::Cyan;
+    if let (Some(pos), Some(split)) = (other, app.split) {
+        let total = app.log_state.filtered_indices.len();
+        let held = if split.line_idx.is_some() { "[PINNED]" } else { "[FOLLOW]" };
+        title = format!(" Logs [{}/{}] {} (C-w:Switch pane) ", (pos + 1).min(total), total, held);
+        border = Color::DarkGray;
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(border));
+
+    if app.log_state.filtered_indices.is_empty() {
+        let list = List::new(Vec::<ListItem>::new()).block(block);
+        frame.render_widget(list, area);
+        return;
+    }
+
+    // The rightmost column holds the minimap.
+    let prefix_width = app.prefix_width();
+    let content_width = inner_width.saturating_sub(prefix_width + 1);
+    let mut lines = Vec::with_capacity(inner_height);
+    if app.show_ruler && inner_height > 1 {
+        lines.push(ruler_line(prefix_width, content_width, app.cursor_column));
+    }
+    let model = ViewModel {
+        height: inner_height - lines.len(),
+        content_width,
+        prefix_width,
+        wrap: app.wrap_lines,
+    };
+    if let Some(bottom) = other {
+        let view = model.layout(bottom, |pos| record(app, pos, false));
+        lines.extend(view.rows.into_iter().map(|row| row.line));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        return;
+    }
+    let total = app.log_state.filtered_indices.len();
+    if let (Some(_), Some(match_pos)) = (&app.log_state.follow_match, app.log_state.match_pos) {
+        app.log_state.bottom_line_idx = model.bottom_for_top(match_pos, total, |pos| record(app, pos, false));
+    }
+    let halves = std::mem::take(&mut app.page_scroll);
+    if halves != 0 {
+        let rows = halves.saturating_mul((model.height / 2).max(1) as isize);
+        let bottom = app.log_state.get_bottom_line_idx();
+        let pos = model.scroll(bottom, rows, total, |pos| record(app, pos, false));
+        match halves < 0 {
+            true => app.log_state.scroll_up(bottom - pos),
+            false => app.log_state.scroll_down(pos - bottom),
+        }
+    }
+    let bottom_idx = app.log_state.get_bottom_line_idx();
+    let view = model.layout(bottom_idx, |pos| record(app, pos, pos == bottom_idx));
+
+    let ruler_rows = lines.len();
+    for (i, row) in view.rows.into_iter().enumerate() {
+        if let Some((line_idx, segment, offset, text)) = row.source {
+            app.row_hits.push(RowHit {
+                y: area.y + 1 + (ruler_rows + i) as u16,
+                x: area.x + 1 + prefix_width as u16,
+                line_idx,
+                segment,
+                offset,
+                text,
+            });
+        }
+        lines.push(row.line);
+    }
+
+    let para = Paragraph::new(lines).block(block);
+    frame.render_widget(para, area);
+
+    if inner_width > 0 && inner_height > 0 {
+        let minimap = Rect {
+            x: area.x + area.width - 2,
+            y: area.y + 1,
+            width: 1,
+            height: inner_height as u16,
+        };
+        draw_minimap(frame, app, minimap, view.top..view.bottom + 1);
+        app.minimap = Some(minimap);
+    }
+}
+
+/// The pinned lines, whatever the filter and scroll position.
+fn draw_pins(frame: &mut Frame, app: &mut App, area: Rect) {
+    let pins = app.log_state.pins.lines().to_vec();
+    let lines: Vec<Line> = pins
+        .into_iter()
+        .map(|line_idx| {
+            let mut spans = vec![Span::styled(format!("{:>6} ", line_idx + 1), Style::default().fg(Color::DarkGray))];
+            if let Some(row) = app.render_line(line_idx).into_iter().next() {
+                spans.extend(row.runs.into_iter().map(|(text, style)| Span::styled(text, style)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    let cursor_pinned = app.current_line_idx().is_some_and(|idx| app.log_state.pins.contains(idx));
+    let title = if cursor_pinned { " Pinned (p:Unpin this line, :unpin all) " } else { " Pinned (p:Pin, :unpin all) " };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Yellow));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// The column ruler over the log text, with the cursor's column picked out.
+fn ruler_line(prefix_width: usize, width: usize, column: usize) -> Line<'static> {
+    let ruler = view::ruler(width);
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![Span::raw(" ".repeat(prefix_width))];
+    match ruler.get(column..column + 1) {
+        Some(mark) => {
+            spans.push(Span::styled(ruler[..column].to_string(), dim));
+            spans.push(Span::styled(mark.to_string(), Style::default().fg(Color::Black).bg(Color::Yellow)));
+            spans.push(Span::styled(ruler[column + 1..].to_string(), dim));
+        }
+        None => spans.push(Span::styled(ruler, dim)),
+    }
+    Line::from(spans)
+}
+
+/// One column showing the whole filtered buffer: colored ticks where errors,
+/// notes and highlight matches are, and a brighter track over what is in view.
+fn draw_minimap(frame: &mut Frame, app: &App, area: Rect, visible: Range<usize>) {
+    let total = app.log_state.filtered_indices.len();
+    let rows = area.height as usize;
+    let in_view = markers::row_of(visible.start, total, rows)..=markers::row_of(visible.end - 1, total, rows);
+    let lines: Vec<Line> = app
+        .log_state
+        .markers
+        .rows(total, rows)
+        .into_iter()
+        .enumerate()
+        .map(|(row, kinds)| {
+            let track = if in_view.contains(&row) { Color::Gray } else { Color::DarkGray };
+            let span = match markers::dominant(kinds) {
+                Some(MARK_ERROR) => Span::styled("█", Style::default().fg(Color::Red)),
+                Some(MARK_NOTE) => Span::styled("█", Style::default().fg(Color::Yellow)),
+                Some(MARK_MATCH) => Span::styled("█", Style::default().fg(Color::Cyan)),
+                _ if in_view.contains(&row) => Span::styled("┃", Style::default().fg(track)),
+                _ => Span::styled("│", Style::default().fg(track)),
+            };
+            Line::from(span)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// The prefix and rendered rows of the line at filtered position `pos`.
+fn record(app: &mut App, pos: usize, is_cursor: bool) -> Record {
+    let line_idx = app.log_state.filtered_indices[pos];
+    let mut rows = app.render_line(line_idx);
+    if let (Some(selection), Some(cursor)) = (app.selection, app.current_line_idx()) {
+        if selection.lines(cursor).contains(&line_idx) {
+            let columns = selection.block.then(|| selection.columns(app.cursor_column));
+            mark_selection(app, line_idx, &mut rows, columns);
+        }
+    }
+    Record {
+        line_idx,
+        prefix: build_prefix(app, line_idx, is_cursor),
+        rows,
+        divider: day_divider(app, pos),
+    }
+}
+
+/// `── 2025-06-12 ──` when the line at filtered position `pos` is from
+/// another day than the one before it, once the lines span midnight.
+fn day_divider(app: &App, pos: usize) -> Option<String> {
+    if !app.time_display.dated {
+        return None;
+    }
+    let lines = &app.log_state.lines;
+    let day = app.time_display.day(&lines[app.log_state.filtered_indices[pos]]);
+    let previous = pos.checked_sub(1).map(|prev| app.time_display.day(&lines[app.log_state.filtered_indices[prev]]));
+    (previous != Some(day)).then(|| format!("── {} ──", day.format("%Y-%m-%d")))
+}
+
+/// Reverse the selected part of a line's rows: all of it, or the block's
+/// columns of the text.
+fn mark_selection(app: &App, line_idx: usize, rows: &mut [Row], columns: Option<RangeInclusive<usize>>) {
+    let style = Style::default().add_modifier(Modifier::REVERSED);
+    let Some(columns) = columns else {
+        for row in rows.iter_mut() {
+            row.runs = view::mark_runs(std::mem::take(&mut row.runs), None, style);
+        }
+        return;
+    };
+    let line = &app.log_state.lines[line_idx];
+    let content = app.get_display_content(line).unwrap_or_else(|_| line.content.clone());
+    for row in rows.iter_mut() {
+        let Some(start) = row.source.as_ref().and_then(|(segment, offset)| content.get(..segment.start + offset)) else {
+            continue;
+        };
+        let start = start.chars().count();
+        let cols = columns.start().saturating_sub(start)..(columns.end() + 1).saturating_sub(start);
+        row.runs = view::mark_runs(std::mem::take(&mut row.runs), Some(cols), style);
+    }
+}
+
+fn build_prefix(app: &App, line_idx: usize, is_cursor: bool) -> Vec<Span<'static>> {
+    let line = &app.log_state.lines[line_idx];
+    let lane = app.log_state.lanes.lane(&line.content);
+    let mut spans = Vec::new();
+    for column in visible_columns(&app.columns, app.show_time) {
+        let text = cell_text(&column.kind, &app.log_state.lines, line_idx, app.time_format, &app.time_display);
+        let text = match column.kind {
+            ColumnKind::Source => fit(&app.peer_names.tag(&text), column),
+            _ => fit(&text, column),
+        };
+        let style = match &column.kind {
+            ColumnKind::LineNumber if is_cursor => Style::default().fg(Color::Yellow),
+            ColumnKind::LineNumber => Style::default().fg(Color::DarkGray),
+            ColumnKind::ReceiptTime => {
+                let (time_color, is_bold) = match get_time_age(line.timestamp) {
+                    TimeAge::VeryRecent => (Color::LightGreen, true),
+                    TimeAge::Recent => (Color::Green, false),
+                    TimeAge::Minutes => (Color::Rgb(136, 136, 136), false),
+                    TimeAge::Hours => (Color::Rgb(102, 102, 102), false),
+                    TimeAge::Days => (Color::Rgb(85, 85, 85), false),
+                };
+                let style = Style::default().fg(time_color);
+                if is_bold {
+                    style.add_modifier(Modifier::BOLD)
+                } else {
+                    style
+                }
+            }
+            ColumnKind::EventTime => Style::default().fg(Color::Magenta),
+            ColumnKind::Level => match line.level {
+                Some(level) => Style::default()
+                    .fg(Color::Black)
+                    .bg(match level {
+                        Level::Error => Color::Red,
+                        Level::Warn => Color::Yellow,
+                        Level::Info => Color::Green,
+                        Level::Debug => Color::Cyan,
+                        Level::Trace => Color::DarkGray,
+                    })
+                    .add_modifier(Modifier::BOLD),
+                None => Style::default(),
+            },
+            ColumnKind::Source => Style::default().fg(Color::Cyan),
+            ColumnKind::Thread => match lane {
+                Some(lane) => Style::default().fg(LANE_COLORS[lane]),
+                None => Style::default().fg(Color::Gray),
+            },
+            ColumnKind::Field(_) => Style::default().fg(Color::LightBlue),
+        };
+        spans.push(Span::styled(text, style));
+        spans.push(Span::raw(" "));
+    }
+    if app.show_lanes {
+        for (i, color) in LANE_COLORS.iter().enumerate().take(app.log_state.lanes.len()) {
+            if lane == Some(i) {
+                spans.push(Span::styled("● ", Style::default().fg(*color).add_modifier(Modifier::BOLD)));
+            } else {
+                spans.push(Span::styled("│ ", Style::default().fg(*color).add_modifier(Modifier::DIM)));
+            }
+        }
+    }
+    if app.log_state.marks.contains(line_idx) {
+        spans.push(Span::styled("◆ ", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)));
+    } else if app.log_state.notes.get(line_idx).is_some() {
+        spans.push(Span::styled("✎ ", Style::default().fg(Color::Yellow)));
+    } else {
+        spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
+    }
+    spans
+}
+
+/// Time buckets with their line and error counts, a bar scaled to the
+/// busiest bucket, and the selected one marked.
+fn draw_outline(frame: &mut Frame, app: &App, selected: usize, area: Rect) {
+    const BAR_WIDTH: usize = 6;
+    let outline = &app.log_state.outline;
+    let rows = area.height.saturating_sub(3) as usize;
+    let busiest = outline.buckets().map(|b| b.lines).max().unwrap_or(1).max(1);
+    let start = selected.saturating_sub(rows.saturating_sub(1));
+
+    let mut lines = vec![Line::from(Span::styled(
+        "Enter:Jump Tab:Size Esc:Close",
+        Style::default().fg(Color::Gray),
+    ))];
+    if outline.len() == 0 {
+        lines.push(Line::from(Span::styled("No lines yet", Style::default().fg(Color::DarkGray))));
+    }
+    for (i, bucket) in outline.buckets().enumerate().skip(start).take(rows) {
+        let bar = "█".repeat((bucket.lines * BAR_WIDTH).div_ceil(busiest));
+        let style = if i == selected {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let mut spans = vec![
+            Span::styled(format!("{} {:>6} ", bucket.label(), bucket.lines), style),
+            Span::styled(format!("{:<width$}", bar, width = BAR_WIDTH), Style::default().fg(Color::Cyan)),
+        ];
+        if bucket.errors > 0 {
+            spans.push(Span::styled(format!(" {}E", bucket.errors), Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let panel = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Outline per {} ", outline.size.label()))
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(panel, area);
+}
+
+fn draw_watch_panel(frame: &mut Frame, app: &App, area: Rect) {
+    const WATCH_HEIGHT: u16 = 6;
+    let mut constraints = vec![Constraint::Length(INPUT_FIELD_HEIGHT)];
+    constraints.extend(app.log_state.watches.iter().map(|_| Constraint::Length(WATCH_HEIGHT)));
+    constraints.push(Constraint::Min(0));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    draw_text_input(
+        frame,
+        &app.input_fields.watch,
+        chunks[0],
+        " Watch (W) ",
+        app.input_mode == InputMode::WatchEdit,
+    );
+    for (watch, &chunk) in app.log_state.watches.iter().zip(&chunks[1..]) {
+        draw_watch(frame, watch, chunk);
+    }
+}
+
+fn draw_watch(frame: &mut Frame, watch: &Watch, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", watch.pattern))
+        .border_style(Style::default().fg(Color::Magenta));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let stats = &watch.stats;
+    let summary = match (stats.latest, stats.avg()) {
+        (Some(latest), Some(avg)) => vec![
+            Line::from(vec![
+                Span::styled(format_value(latest), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  n={}", stats.count), Style::default().fg(Color::DarkGray)),
+            ]),
+            Line::from(Span::styled(
+                format!(
+                    "min {}  max {}  avg {}",
+                    format_value(stats.min),
+                    format_value(stats.max),
+                    format_value(avg)
+                ),
+                Style::default().fg(Color::Gray),
+            )),
+        ],
+        _ => vec![Line::from(Span::styled("no values yet", Style::default().fg(Color::DarkGray)))],
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(inner);
+    frame.render_widget(Paragraph::new(summary), rows[0]);
+
+    // Sparkline wants u64 bars; scale the visible window onto 0..=100.
+    let width = rows[1].width as usize;
+    let values: Vec<f64> = watch.history.iter().rev().take(width).rev().copied().collect();
+    let (lo, hi) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let bars: Vec<u64> = values
+        .iter()
+        .map(|&v| if hi > lo { ((v - lo) / (hi - lo) * 100.0) as u64 + 1 } else { 50 })
+        .collect();
+    let sparkline = Sparkline::default()
+        .data(&bars)
+        .max(101)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(sparkline, rows[1]);
+}
+
+fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    if app.input_mode == InputMode::Command {
+        let input = &app.input_fields.command;
+        let paragraph = Paragraph::new(format!(":{}", input.text))
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        frame.render_widget(paragraph, area);
+        frame.set_cursor_position((area.x + input.cursor as u16 + 1, area.y));
+        return;
+    }
+
+    let paragraph = Paragraph::new(status::status_line(app, area.width as usize))
+        .style(Style::default().fg(Color::White).bg(Color::Blue));
+    frame.render_widget(paragraph, area);
+}
+
+/// Stack the live toasts in the bottom right of `area`, newest lowest.
+fn draw_toasts(frame: &mut Frame, app: &App, area: Rect) {
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    let max_width = (inner.width as usize).min(TOAST_MAX_WIDTH);
+    for (i, toast) in app.toasts.iter().rev().enumerate() {
+        let Some(y) = (inner.y + inner.height).checked_sub(i as u16 + 1).filter(|&y| y >= inner.y) else {
+            break;
+        };
+        let text: String = format!(" {} ", toast.label()).chars().take(max_width).collect();
+        let width = text.chars().count() as u16;
+        let style = match toast.severity {
+            Severity::Info => Style::default().fg(Color::White).bg(Color::DarkGray),
+            Severity::Warning => Style::default().fg(Color::Black).bg(Color::Yellow),
+            Severity::Error => Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        };
+        let rect = Rect {
+            x: inner.x + inner.width - width,
+            y,
+            width,
+            height: 1,
+        };
+        frame.render_widget(Clear, rect);
+        frame.render_widget(Paragraph::new(text).style(style), rect);
+    }
+}
+
+fn draw_detail_popup(frame: &mut Frame, app: &mut App) {
+    let Some(rows) = app.render_detail() else {
+        return;
+    };
+    let Some(detail) = app.detail.as_mut() else {
+        return;
+    };
+
+    let area = frame.area();
+    let popup_area = Rect {
+        x: area.width / 10,
+        y: area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let width = popup_area.width.saturating_sub(2) as usize;
+    let height = popup_area.height.saturating_sub(2) as usize;
+
+    // Wrapped here rather than by the paragraph so the row of the current
+    // match is known for scrolling to it.
+    let query = detail.active_query().to_string();
+    let found = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let current = Style::default().fg(Color::Black).bg(Color::LightRed).add_modifier(Modifier::BOLD);
+    let selected = detail.tree.as_ref().map(|tree| tree.selected);
+    let mut lines: Vec<Line> = Vec::new();
+    let mut count = 0;
+    let mut current_line = None;
+    let mut selected_line = None;
+    for (i, row) in rows.into_iter().enumerate() {
+        let text: String = row.runs.iter().map(|(t, _)| t.as_str()).collect();
+        let mut runs = row.runs;
+        if selected == Some(i) {
+            selected_line = Some(lines.len());
+            runs = view::mark_runs(runs, None, Style::default().bg(Color::DarkGray));
+        }
+        for m in find_matches(&text, &query) {
+            let cols = text[..m.start].chars().count()..text[..m.end].chars().count();
+            let style = if count == detail.current {
+                current_line = Some(lines.len() + cols.start / width.max(1));
+                current
+            } else {
+                found
+            };
+            runs = view::mark_runs(runs, Some(cols), style);
+            count += 1;
+        }
+        lines.extend(view::wrap_highlighted(&runs, width).into_iter().map(Line::from));
+    }
+    detail.matches = count;
+    if detail.current >= count {
+        detail.current = 0;
+    }
+    if std::mem::take(&mut detail.reveal) {
+        if let Some(line) = current_line {
+            let scroll = detail.scroll as usize;
+            if line < scroll || line >= scroll + height {
+                detail.scroll = line.saturating_sub(height / 3) as u16;
+            }
+        }
+    } else if let Some(line) = selected_line {
+        let scroll = detail.scroll as usize;
+        if line < scroll {
+            detail.scroll = line as u16;
+        } else if line >= scroll + height {
+            detail.scroll = (line + 1).saturating_sub(height) as u16;
+        }
+    }
+
+    let keys = match detail.tree {
+        Some(_) => "Enter:Fold y:Path /:Search n/N:Match j/k:Move q:Close",
+        None => "/:Search n/N:Match j/k:Scroll Esc:Close",
+    };
+    let title = format!(
+        " Line {} | p:Pretty({}) {} ",
+        detail.line_idx + 1,
+        if detail.pretty { "ON" } else { "OFF" },
+        keys
+    );
+    let search = match (&detail.search, count) {
+        (Some(_), _) => format!(" /{}█ ", query),
+        (None, _) if query.is_empty() => String::new(),
+        (None, 0) => format!(" /{}: no matches ", query),
+        (None, _) => format!(" /{} {}/{} ", query, detail.current + 1, count),
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(search)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black))
+        .scroll((detail.scroll, 0));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn draw_note_editor(frame: &mut Frame, app: &App) {
+    let Some(editor) = &app.note_editor else {
+        return;
+    };
+    let area = frame.area();
+    let width = 70.min(area.width.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(width) / 2,
+        y: area.height.saturating_sub(INPUT_FIELD_HEIGHT) / 2,
+        width,
+        height: INPUT_FIELD_HEIGHT.min(area.height),
+    };
+    frame.render_widget(Clear, popup_area);
+    draw_text_input(
+        frame,
+        &editor.input,
+        popup_area,
+        &format!(" Note for line {} (Enter:Save, empty removes) ", editor.line_idx + 1),
+        true,
+    );
+}
+
+fn draw_playground(frame: &mut Frame, app: &App) {
+    let Some(playground) = &app.playground else {
+        return;
+    };
+    let area = frame.area();
+    let width = 90.min(area.width.saturating_sub(4));
+    let height = 24.min(area.height.saturating_sub(2));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(width) / 2,
+        y: area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Expression playground (Tab:Field ↑↓:Pick line Ctrl-T:Filter/Highlight Ctrl-G:Grouping Enter:Apply Esc:Close) ")
+        .border_style(Style::default().fg(Color::Magenta))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(INPUT_FIELD_HEIGHT),
+            Constraint::Length(INPUT_FIELD_HEIGHT),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+    let sample_label = match playground.sample_pos {
+        Some(pos) => format!(" Sample (line {}/{}) ", pos + 1, app.log_state.filtered_indices.len()),
+        None => " Sample ".to_string(),
+    };
+    draw_text_input(
+        frame,
+        &playground.expr,
+        chunks[0],
+        &format!(" {} expression ", playground.target.label()),
+        !playground.editing_sample,
+    );
+
+    let (nodes, ranges, grouped, error) = match playground.evaluate() {
+        Ok(eval) => (eval.nodes, eval.ranges, eval.grouped, None),
+        Err(e) => (Vec::new(), Vec::new(), String::new(), Some(e)),
+    };
+    let sample = &playground.sample.text;
+    let mut sample_spans = Vec::new();
+    let mut pos = 0;
+    for (start, end) in ranges {
+        sample_spans.push(Span::raw(sample[pos..start].to_string()));
+        sample_spans.push(Span::styled(
+            sample[start..end].to_string(),
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+        pos = end;
+    }
+    sample_spans.push(Span::raw(sample[pos..].to_string()));
+    let sample_style = if playground.editing_sample {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(sample_spans)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(sample_label)
+                .border_style(sample_style),
+        ),
+        chunks[1],
+    );
+    if playground.editing_sample {
+        let cursor = playground.sample.cursor as u16;
+        frame.set_cursor_position((chunks[1].x + cursor + 1, chunks[1].y + 1));
+    }
+
+    let mut lines: Vec<Line> = Vec::new();
+    if playground.show_grouping && !grouped.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("Parsed as ", Style::default().fg(Color::Gray)),
+            Span::styled(grouped, Style::default().fg(Color::Cyan)),
+        ]));
+        lines.push(Line::from(""));
+    }
+    lines.extend(match error {
+        Some(e) => vec![Line::from(Span::styled(e, Style::default().fg(Color::Red)))],
+        None => nodes
+            .into_iter()
+            .map(|node| {
+                let (mark, color) = if node.matched { ("✔", Color::Green) } else { ("✘", Color::Red) };
+                Line::from(vec![
+                    Span::raw("  ".repeat(node.depth)),
+                    Span::styled(format!("{} ", mark), Style::default().fg(color)),
+                    Span::styled(node.label, Style::default().fg(color)),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    });
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[2]);
+}
+
+fn draw_column_editor(frame: &mut Frame, app: &App) {
+    let Some(selected) = app.column_editor else {
+        return;
+    };
+
+    let mut lines: Vec<Line> = vec![
+        Line::from(Span::styled(
+            "↑↓:Select  Space:Show/Hide  J/K:Move  +/-:Width",
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+    ];
+    for (i, column) in app.columns.iter().enumerate() {
+        let is_selected = i == selected;
+        let label_style = if is_selected {
+            Style::default().fg(Color::Yellow)
+        } else if column.visible {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, Style::default().fg(Color::Yellow)),
+            Span::styled(if column.visible { "[x] " } else { "[ ] " }, label_style),
+            Span::styled(format!("{:<20}", column.kind.label()), label_style),
+            Span::styled(format!("{:>3}", column.width), Style::default().fg(Color::Gray)),
+        ]));
+    }
+
+    let area = frame.area();
+    let popup_width = 52.min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Columns ")
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn draw_filter_stats(frame: &mut Frame, app: &App) {
+    let Some(selected) = app.filter_stats_popup else {
+        return;
+    };
+
+    let mut lines: Vec<Line> = vec![
+        Line::from(Span::styled(
+            "↑↓:Select  Enter:Apply  x:Drop saved  :savefilter NAME saves the active filter",
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  {:<16} {:>9} {:>7} {:>9} {:>7}  Expression", "Filter", "Matches", "Rate", "Last min", "Rate"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    if app.log_state.filter_stats.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No active or saved filters",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let now = chrono::Local::now();
+    for (i, stat) in app.log_state.filter_stats.iter().enumerate() {
+        let is_selected = i == selected;
+        let style = if is_selected {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let (recent, recent_rate) = stat.last_minute(now);
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!(
+                    "{:<16} {:>9} {:>7} {:>9} {:>7}  ",
+                    stat.label(),
+                    stat.matches,
+                    format_rate(stat.hit_rate()),
+                    recent,
+                    format_rate(recent_rate)
+                ),
+                style,
+            ),
+            Span::styled(stat.canonical(), Style::default().fg(Color::Gray)),
+        ]));
+    }
+
+    let area = frame.area();
+    let popup_width = 100.min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Filter statistics ")
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn draw_plugins(frame: &mut Frame) {
+    let mut lines: Vec<Line> = vec![
+        Line::from(Span::styled(
+            "Esc:Close  :export NAME hands the filtered lines to an exporter",
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{:<20} {:<24} {:>8} {:>7} {:>7}  Status", "Plugin", "Capabilities", "Lines", "Calls", "Errors"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    if plugin::plugins().is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No plugins loaded (start with --plugin COMMAND)",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for plugin in plugin::plugins() {
+        let health = plugin.health();
+        let capabilities: Vec<_> = plugin.capabilities().iter().map(|c| c.label()).collect();
+        let status_color = match health.status {
+            Status::Ready => Color::Green,
+            Status::Starting => Color::Yellow,
+            Status::Failed(_) | Status::Exited(_) => Color::Red,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{:<20} {:<24} {:>8} {:>7} {:>7}  ",
+                    plugin.name(),
+                    capabilities.join(","),
+                    health.lines,
+                    health.calls,
+                    health.errors
+                ),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(health.status.label(), Style::default().fg(status_color)),
+        ]));
+        let detail = [
+            Some(format!("$ {}", plugin.command)),
+            health.last_error.map(|e| format!("error: {}", e)),
+            health.last_stderr.map(|e| format!("stderr: {}", e)),
+        ];
+        for text in detail.into_iter().flatten() {
+            lines.push(Line::from(Span::styled(format!("  {}", text), Style::default().fg(Color::DarkGray))));
+        }
+    }
+
+    let area = frame.area();
+    let popup_width = 100.min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Plugins ")
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+/// The `:messages` panel, newest at the bottom, `offset` rows scrolled up.
+fn draw_messages(frame: &mut Frame, app: &App, offset: usize) {
+    let area = frame.area();
+    let popup_width = 110.min(area.width.saturating_sub(4));
+    let popup_height = area.height.saturating_sub(4);
+    let rows = popup_height.saturating_sub(3) as usize;
+
+    let messages = &app.toasts.messages;
+    let end = messages.len().saturating_sub(offset);
+    let mut lines = vec![Line::from(Span::styled(
+        "↑↓:Scroll  g/G:Oldest/Newest  c:Clear  Esc:Close",
+        Style::default().fg(Color::Gray),
+    ))];
+    if messages.len() == 0 {
+        lines.push(Line::from(Span::styled("No warnings or errors", Style::default().fg(Color::DarkGray))));
+    }
+    for message in messages.iter().take(end).skip(end.saturating_sub(rows)) {
+        let (label, color) = match message.severity {
+            Severity::Error => ("ERROR", Color::Red),
+            Severity::Warning => ("WARN ", Color::Yellow),
+            Severity::Info => ("INFO ", Color::Gray),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(message.time.format("%H:%M:%S%.3f ").to_string(), Style::default().fg(Color::DarkGray)),
+            Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" {}", message.text), Style::default().fg(Color::White)),
+        ]));
+    }
+
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Messages ({}) ", messages.len()))
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn draw_pipe_output(frame: &mut Frame, output: &PipeOutput, scroll: usize) {
+    let area = frame.area();
+    let popup_width = 110.min(area.width.saturating_sub(4));
+    let popup_height = area.height.saturating_sub(4);
+    let rows = popup_height.saturating_sub(3) as usize;
+
+    let mut lines = vec![Line::from(Span::styled(
+        "↑↓:Scroll  g/G:Top/Bottom  y:Copy  Esc:Close",
+        Style::default().fg(Color::Gray),
+    ))];
+    if output.lines.is_empty() {
+        lines.push(Line::from(Span::styled("No output", Style::default().fg(Color::DarkGray))));
+    }
+    lines.extend(output.lines.iter().skip(scroll).take(rows).map(|l| Line::from(l.clone())));
+
+    let status = match output.failed {
+        None => String::new(),
+        Some(Some(code)) => format!(", exit {}", code),
+        Some(None) => ", killed".to_string(),
+    };
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" | {} ({} lines{}) ", output.command, output.lines.len(), status))
+                .border_style(Style::default().fg(if output.failed.is_some() { Color::Red } else { Color::Cyan })),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+/// Count, range, mean and percentiles of a `:stats` field, per group when
+/// grouped.
+fn draw_field_stats(frame: &mut Frame, stats: &FieldStats, scroll: usize) {
+    let area = frame.area();
+    let popup_width = 110.min(area.width.saturating_sub(4));
+    let rows = area.height.saturating_sub(10) as usize;
+    let grouped = stats.groups.len() > 1 || stats.groups.iter().any(|(group, _)| !group.is_empty());
+
+    let mut lines = vec![
+        Line::from(Span::styled("↑↓:Scroll  g/G:Top/Bottom  Esc:Close", Style::default().fg(Color::Gray))),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "{:<24} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                if grouped { "Group" } else { "" },
+                "Count",
+                "Min",
+                "Mean",
+                "p50",
+                "p95",
+                "p99",
+                "Max"
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    for (group, summary) in stats.groups.iter().skip(scroll).take(rows) {
+        let group: String = group.chars().take(24).collect();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<24} ", group), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!(
+                    "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                    summary.count,
+                    format_number(summary.min),
+                    format_number(summary.mean),
+                    format_number(summary.p50),
+                    format_number(summary.p95),
+                    format_number(summary.p99),
+                    format_number(summary.max)
+                ),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+    if stats.unparsed > 0 {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("{} lines had a value that isn't a number", stats.unparsed),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
     };
-
-    let widget = Paragraph::new(input.text.as_str())
+    let popup = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(title)
-                .border_style(border_style),
+                .title(format!(" Stats: {} ", stats.title))
+                .border_style(Style::default().fg(Color::Magenta)),
         )
-        .style(style);
-    frame.render_widget(widget, area);
+        .style(Style::default().bg(Color::Black));
 
-    if is_active {
-        frame.set_cursor_position((area.x + input.cursor as u16 + 1, area.y + 1));
-    }
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
 }
 
-fn draw_log_view(frame: &mut Frame, app: &mut App, area: Rect) {
-    let inner_height = area.height.saturating_sub(2) as usize;
-    let inner_width = area.width.saturating_sub(2) as usize;
+/// The most frequent values of a `:top` field with their share of the lines
+/// that have it.
+fn draw_snoozes(frame: &mut Frame, snoozes: &Snoozes, selected: usize) {
+    let area = frame.area();
+    let popup_width = 90.min(area.width.saturating_sub(4));
+    let rows = area.height.saturating_sub(10) as usize;
+    let start = selected.saturating_sub(rows.saturating_sub(1));
+    let now = Instant::now();
 
-    let title = format!(
-        " Logs [{}/{}] {}{} ",
-        app.log_state.filtered_indices.len(),
-        app.log_state.lines.len(),
-        if app.log_state.follow_tail {
-            "[FOLLOW]"
+    let mut lines = vec![
+        Line::from(Span::styled("↑↓:Select  d:Wake  Esc:Close", Style::default().fg(Color::Gray))),
+        Line::from(""),
+    ];
+    if snoozes.list.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing snoozed; X on a line or :snooze [30m] [EXPR]",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (i, snooze) in snoozes.list.iter().enumerate().skip(start).take(rows) {
+        let is_selected = i == selected;
+        let style = if is_selected {
+            Style::default().fg(Color::Yellow)
         } else {
-            ""
-        },
-        if app.wrap_lines { "[WRAP]" } else { "" }
-    );
-
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(title)
-        .border_style(Style::default().fg(Color::Cyan));
-
-    if app.log_state.filtered_indices.is_empty() {
-        let list = List::new(Vec::<ListItem>::new()).block(block);
-        frame.render_widget(list, area);
-        return;
+            Style::default().fg(Color::White)
+        };
+        let remaining = snooze.remaining(now);
+        let label_width = (popup_width as usize).saturating_sub(remaining.len() + 6);
+        let label: String = snooze.label().chars().take(label_width).collect();
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<width$} ", label, width = label_width), style),
+            Span::styled(remaining, Style::default().fg(Color::Cyan)),
+        ]));
     }
 
-    let prefix_width = app.prefix_width();
-    let content_width = inner_width.saturating_sub(prefix_width);
-    let bottom_idx = app.log_state.get_bottom_line_idx();
-
-    let mut collected_lines: Vec<Line> = Vec::new();
-    let mut current_filtered_idx = bottom_idx as i64;
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Snoozed ({}) ", snoozes.list.len()))
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
 
-    while collected_lines.len() < inner_height && current_filtered_idx >= 0 {
-        let filtered_idx = current_filtered_idx as usize;
-        if filtered_idx >= app.log_state.filtered_indices.len() {
-            current_filtered_idx -= 1;
-            continue;
-        }
-        let line_idx = app.log_state.filtered_indices[filtered_idx];
-        let log_line = app.log_state.lines[line_idx].clone();
-
-        let mut prefix_spans = Vec::new();
-        if app.show_time {
-            let time_age = crate::core::get_time_age(log_line.timestamp);
-            let (time_color, is_bold) = match time_age {
-                crate::core::TimeAge::VeryRecent => (Color::LightGreen, true),
-                crate::core::TimeAge::Recent => (Color::Green, false),
-                crate::core::TimeAge::Minutes => (Color::Rgb(136, 136, 136), false),
-                crate::core::TimeAge::Hours => (Color::Rgb(102, 102, 102), false),
-                crate::core::TimeAge::Days => (Color::Rgb(85, 85, 85), false),
-            };
-            let mut style = Style::default().fg(time_color);
-            if is_bold {
-                style = style.add_modifier(ratatui::style::Modifier::BOLD);
-            }
-            prefix_spans.push(Span::styled(
-                format!("{:>6} ", format_relative_time(log_line.timestamp)),
-                style,
-            ));
-        }
-        prefix_spans.push(Span::styled(
-            format!("{:>6} │ ", line_idx + 1),
-            Style::default().fg(Color::DarkGray),
-        ));
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
 
-        let highlighted = app.render_line(&log_line);
+fn draw_fields(frame: &mut Frame, app: &App, selected: usize) {
+    const NAME_WIDTH: usize = 24;
+    let schema = &app.log_state.schema;
+    let area = frame.area();
+    let popup_width = 100.min(area.width.saturating_sub(4));
+    let rows = area.height.saturating_sub(10) as usize;
+    let start = selected.saturating_sub(rows.saturating_sub(1));
+    let example_width = (popup_width as usize).saturating_sub(NAME_WIDTH + 26);
 
-        if app.wrap_lines && content_width > 0 {
-            let wrapped = wrap_highlighted(&highlighted, content_width);
-            let mut line_group: Vec<Line> = Vec::new();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "↑↓:Select  c:Column  Enter/f:Filter  Esc:Close",
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+    ];
+    for (i, (name, info)) in schema.fields().into_iter().enumerate().skip(start).take(rows) {
+        let is_selected = i == selected;
+        let style = if is_selected {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let is_column = app.columns.iter().any(|c| c.kind == ColumnKind::Field(name.to_string()));
+        let percent = info.count as f64 * 100.0 / schema.lines.max(1) as f64;
+        let name: String = name.chars().take(NAME_WIDTH).collect();
+        let examples: String = info.examples.join(", ").chars().take(example_width).collect();
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, Style::default().fg(Color::Yellow)),
+            Span::styled(if is_column { "▦ " } else { "  " }, Style::default().fg(Color::LightBlue)),
+            Span::styled(format!("{:<width$} {:>7} {:>5.1}%  ", name, info.count, percent, width = NAME_WIDTH), style),
+            Span::styled(examples, Style::default().fg(Color::DarkGray)),
+        ]));
+    }
 
-            for (i, wrap_line) in wrapped.into_iter().enumerate() {
-                let mut line_spans = Vec::new();
-                if i == 0 {
-                    line_spans.extend(prefix_spans.clone());
-                } else {
-                    line_spans.push(Span::styled(" ".repeat(prefix_width), Style::default()));
-                }
-                line_spans.extend(wrap_line);
-                line_group.push(Line::from(line_spans));
-            }
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Fields: {} in {} lines ", schema.len(), schema.lines))
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
 
-            for line in line_group.into_iter().rev() {
-                collected_lines.push(line);
-                if collected_lines.len() >= inner_height {
-                    break;
-                }
-            }
-        } else {
-            let mut spans = prefix_spans;
-            for (text, style) in highlighted {
-                spans.push(Span::styled(text, style));
-            }
-            collected_lines.push(Line::from(spans));
-        }
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
 
-        current_filtered_idx -= 1;
+fn draw_why(frame: &mut Frame, app: &App, why: &Why) {
+    let area = frame.area();
+    let popup_width = 100.min(area.width.saturating_sub(4));
+    let content = app.log_state.lines.get(why.line_idx).map_or("", |line| line.content.as_str());
+    let mut lines = vec![
+        Line::from(Span::styled(content.to_string(), Style::default().fg(Color::White))),
+        Line::from(""),
+    ];
+    for reason in &why.reasons {
+        let (mark, color) = if reason.pass { ("✔", Color::Green) } else { ("✘", Color::Red) };
+        lines.push(Line::from(vec![
+            Span::raw("  ".repeat(reason.depth)),
+            Span::styled(format!("{} ", mark), Style::default().fg(color)),
+            Span::styled(reason.text.clone(), Style::default().fg(color)),
+        ]));
     }
 
-    collected_lines.reverse();
+    let inner_width = popup_width.saturating_sub(2).max(1) as usize;
+    let rows: usize = lines.iter().map(|line| line.width().max(1).div_ceil(inner_width)).sum();
+    let popup_height = (rows as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let verdict = if why.shown { "shown" } else { "hidden" };
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Line {} is {} (Esc:Close) ", why.line_idx + 1, verdict))
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
 
-    let para = Paragraph::new(collected_lines).block(block);
-    frame.render_widget(para, area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
 }
 
-fn wrap_highlighted(spans: &[(String, Style)], width: usize) -> Vec<Vec<Span<'static>>> {
-    if width == 0 {
-        return vec![spans
-            .iter()
-            .map(|(t, s)| Span::styled(t.clone(), *s))
-            .collect()];
+fn draw_top_values(frame: &mut Frame, top: &TopValues, selected: usize) {
+    const BAR_WIDTH: usize = 20;
+    let area = frame.area();
+    let popup_width = 90.min(area.width.saturating_sub(4));
+    let rows = area.height.saturating_sub(10) as usize;
+    let value_width = (popup_width as usize).saturating_sub(BAR_WIDTH + 24).max(8);
+    let busiest = top.values.first().map_or(1, |(_, count)| *count).max(1);
+    let start = selected.saturating_sub(rows.saturating_sub(1));
+
+    let mut lines = vec![
+        Line::from(Span::styled("↑↓:Select  Enter/f:Filter  Esc:Close", Style::default().fg(Color::Gray))),
+        Line::from(""),
+    ];
+    for (i, (value, count)) in top.values.iter().enumerate().skip(start).take(rows) {
+        let is_selected = i == selected;
+        let style = if is_selected {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let value: String = value.chars().take(value_width).collect();
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<width$} {:>7} {:>5.1}% ", value, count, top.percent(*count), width = value_width), style),
+            Span::styled("█".repeat((count * BAR_WIDTH).div_ceil(busiest)), Style::default().fg(Color::Cyan)),
+        ]));
     }
 
-    let mut result: Vec<Vec<Span<'static>>> = vec![Vec::new()];
-    let mut current_width = 0;
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Top {}: {} values in {} lines ",
+                    top.field.name,
+                    top.values.len(),
+                    top.total
+                ))
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .style(Style::default().bg(Color::Black));
 
-    for (text, style) in spans {
-        let mut remaining = text.as_str();
-        while !remaining.is_empty() {
-            let available = width.saturating_sub(current_width);
-            if available == 0 {
-                result.push(Vec::new());
-                current_width = 0;
-                continue;
-            }
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
 
-            let take_chars: usize = remaining.chars().take(available).count();
-            let byte_end = remaining
-                .char_indices()
-                .nth(take_chars)
-                .map(|(i, _)| i)
-                .unwrap_or(remaining.len());
-
-            let (chunk, rest) = remaining.split_at(byte_end);
-            if !chunk.is_empty() {
-                result
-                    .last_mut()
-                    .unwrap()
-                    .push(Span::styled(chunk.to_string(), *style));
-                current_width += chunk.chars().count();
-            }
-            remaining = rest;
+fn draw_entity_menu(frame: &mut Frame, app: &App) {
+    let Some(menu) = &app.entity_menu else {
+        return;
+    };
 
-            if !remaining.is_empty() {
-                result.push(Vec::new());
-                current_width = 0;
-            }
-        }
+    let mut lines: Vec<Line> = vec![Line::from(Span::styled(
+        "↑↓:Select  c/Enter:Copy  f:Filter  o:Open URL",
+        Style::default().fg(Color::Gray),
+    ))];
+    lines.push(Line::from(""));
+    let mut max_width = lines[0].width();
+    for (i, entity) in menu.entities.iter().enumerate() {
+        let is_selected = i == menu.selected;
+        let line = Line::from(vec![
+            Span::styled(
+                if is_selected { "▶ " } else { "  " },
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(format!("{:<5}", entity.kind.label()), Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                entity.text.clone(),
+                if is_selected {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                },
+            ),
+        ]);
+        max_width = max_width.max(line.width());
+        lines.push(line);
     }
 
-    result
-}
+    let area = frame.area();
+    let popup_width = (max_width as u16 + 4).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
 
-fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
-    let status = if let Some(msg) = &app.status_message {
-        msg.clone()
-    } else {
-        let last_update = if let Some(time) = app.log_state.last_update_time {
-            format!(" | Last: {}", format_relative_time(time))
-        } else {
-            String::new()
-        };
-        format!(
-            "q:Quit d:Hide f:Filter h:Highlight s:LineStart c:Clear t:Time({}) w:Wrap({}){}",
-            if app.show_time { "ON" } else { "OFF" },
-            if app.wrap_lines { "ON" } else { "OFF" },
-            last_update
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Entities ")
+                .border_style(Style::default().fg(Color::Magenta)),
         )
-    };
+        .style(Style::default().bg(Color::Black));
 
-    let paragraph =
-        Paragraph::new(status).style(Style::default().fg(Color::White).bg(Color::Blue));
-    frame.render_widget(paragraph, area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
 }
 
 fn draw_help_popup(frame: &mut Frame) {
@@ -310,41 +1591,56 @@ fn draw_help_popup(frame: &mut Frame) {
 }
 
 fn draw_listen_popup(frame: &mut Frame, app: &mut App) {
-    let port = app.listen_state.port.unwrap_or(0);
-    let interfaces = &app.listen_state.network_interfaces;
+    let port = app.listen_state.port().unwrap_or(0);
+    let port_label = app.listen_state.port_label();
+    let many_ports = app.listen_state.ports.len() > 1;
+    let interfaces = app.listen_state.visible_interfaces();
     let display_mode = app.listen_state.display_mode;
 
     let mut max_addr_width: usize = 0;
-    for iface in interfaces {
+    for (iface, addresses) in &interfaces {
         let iface_width = iface.name.len() + if iface.is_default { 10 } else { 0 };
         max_addr_width = max_addr_width.max(iface_width);
 
-        for addr_info in &iface.addresses {
-            let is_v6 = addr_info.ip.is_ipv6();
-            let addr_width = calc_addr_line_width(&addr_info.ip, port, is_v6, display_mode);
-            max_addr_width = max_addr_width.max(addr_width);
+        for addr_info in addresses {
+            max_addr_width = max_addr_width.max(calc_addr_line_width(addr_info, port, display_mode));
         }
     }
 
-    let header_width = "Mode (Tab): [addr:port]  nc command ".len();
-    let max_content_width = max_addr_width.max(header_width);
+    let header_width = "Mode (Tab): ".len() + display_mode.label().len();
+    let port_width = "Port (p): ".len() + port_label.len();
+    let mut max_content_width = max_addr_width.max(header_width).max(port_width);
 
     let mut lines: Vec<Line> = Vec::new();
     let mut addr_entries: Vec<ListenAddrEntry> = Vec::new();
 
+    let ports: Vec<String> = app.listen_state.ports.iter().map(u16::to_string).collect();
     lines.push(Line::from(vec![
-        Span::styled("Listening on port ", Style::default().fg(Color::White)),
-        Span::styled(format!("{}", port), Style::default().fg(Color::Yellow)),
+        Span::styled(
+            if many_ports { "Listening on ports " } else { "Listening on port " },
+            Style::default().fg(Color::White),
+        ),
+        Span::styled(ports.join(", "), Style::default().fg(Color::Yellow)),
     ]));
     lines.push(Line::from(""));
 
-    let mode_str = match display_mode {
-        ListenDisplayMode::AddrPort => "[addr:port]  nc command ",
-        ListenDisplayMode::NcCommand => " addr:port  [nc command]",
-    };
+    if many_ports {
+        lines.push(Line::from(vec![
+            Span::styled("Port (p): ", Style::default().fg(Color::Gray)),
+            Span::styled(port_label, Style::default().fg(Color::Yellow)),
+        ]));
+    }
+
     lines.push(Line::from(vec![
         Span::styled("Mode (Tab): ", Style::default().fg(Color::Gray)),
-        Span::styled(mode_str, Style::default().fg(Color::Yellow)),
+        Span::styled(display_mode.label(), Style::default().fg(Color::Yellow)),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("Local-only (a): ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            if app.listen_state.show_all { "shown" } else { "hidden" },
+            Style::default().fg(Color::Yellow),
+        ),
     ]));
     lines.push(Line::from(Span::styled(
         "↑↓:Select  Enter/Click:Copy",
@@ -359,7 +1655,7 @@ fn draw_listen_popup(frame: &mut Frame, app: &mut App) {
         )));
     } else {
         let mut addr_idx = 0;
-        for iface in interfaces {
+        for (iface, addresses) in &interfaces {
             let name_style = if iface.is_default {
                 Style::default().fg(Color::Green)
             } else {
@@ -373,32 +1669,38 @@ fn draw_listen_popup(frame: &mut Frame, app: &mut App) {
                 name_style,
             )]));
 
-            for addr_info in &iface.addresses {
-                let is_v6 = addr_info.ip.is_ipv6();
+            for addr_info in addresses {
                 let is_selected = addr_idx == app.listen_state.selected_idx;
                 let current_row = lines.len() as u16 + 1;
 
                 addr_entries.push(ListenAddrEntry {
-                    ip: addr_info.ip,
-                    is_v6,
-                    is_self_assigned: addr_info.is_self_assigned,
+                    host: addr_info.host(),
+                    is_v6: addr_info.ip.is_ipv6(),
                     row: current_row,
                 });
 
-                let line = build_addr_line(
-                    &addr_info.ip,
-                    port,
-                    is_v6,
-                    addr_info.is_self_assigned,
-                    is_selected,
-                    display_mode,
-                );
-                lines.push(line);
+                lines.push(build_addr_line(addr_info, port, is_selected, display_mode));
                 addr_idx += 1;
             }
         }
     }
 
+    if display_mode == ListenDisplayMode::Qr {
+        if let Some(text) = addr_entries
+            .get(app.listen_state.selected_idx)
+            .map(|entry| format_listen_addr(&entry.host, entry.is_v6, port, display_mode))
+        {
+            lines.push(Line::from(""));
+            match QrMatrix::new(&text) {
+                Ok(qr) => {
+                    max_content_width = max_content_width.max(qr.size());
+                    lines.extend(qr.half_block_rows().into_iter().map(qr_line));
+                }
+                Err(e) => lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))),
+            }
+        }
+    }
+
     let content_height = lines.len() as u16 + 2;
     let max_width = (max_content_width + 4) as u16;
 
@@ -434,51 +1736,45 @@ fn draw_listen_popup(frame: &mut Frame, app: &mut App) {
     ));
 }
 
-fn calc_addr_line_width(
-    ip: &std::net::IpAddr,
-    port: u16,
-    is_v6: bool,
-    display_mode: ListenDisplayMode,
-) -> usize {
-    let prefix_len = 2;
-    let ip_str = ip.to_string();
-    let port_str = port.to_string();
+/// One row of half-block cells; colors are explicit so the code scans on
+/// any terminal background.
+fn qr_line(cells: Vec<(bool, bool)>) -> Line<'static> {
+    let color = |dark| if dark { Color::Black } else { Color::White };
+    Line::from(
+        cells
+            .into_iter()
+            .map(|(top, bottom)| Span::styled("▀", Style::default().fg(color(top)).bg(color(bottom))))
+            .collect::<Vec<_>>(),
+    )
+}
 
-    match display_mode {
-        ListenDisplayMode::AddrPort => {
-            if is_v6 {
-                prefix_len + 1 + ip_str.len() + 1 + 1 + port_str.len()
-            } else {
-                prefix_len + ip_str.len() + 1 + port_str.len()
-            }
-        }
-        ListenDisplayMode::NcCommand => {
-            if is_v6 {
-                prefix_len + 3 + 3 + ip_str.len() + 1 + port_str.len()
-            } else {
-                prefix_len + 3 + ip_str.len() + 1 + port_str.len()
-            }
-        }
-    }
+fn calc_addr_line_width(addr_info: &AddressInfo, port: u16, display_mode: ListenDisplayMode) -> usize {
+    let prefix_len = 2;
+    let text = format_listen_addr(&addr_info.host(), addr_info.ip.is_ipv6(), port, display_mode);
+    let note = addr_info.note();
+    let label_len = if note.is_empty() { 0 } else { note.len() + 3 };
+    prefix_len + text.len() + label_len
 }
 
 fn build_addr_line<'a>(
-    ip: &std::net::IpAddr,
+    addr_info: &AddressInfo,
     port: u16,
-    is_v6: bool,
-    is_self_assigned: bool,
     is_selected: bool,
     display_mode: ListenDisplayMode,
 ) -> Line<'a> {
-    let base_addr_style = if is_self_assigned {
-        Style::default().fg(Color::DarkGray)
-    } else if is_selected {
+    let is_local = addr_info.reach != Reach::Network;
+    let is_v6 = addr_info.ip.is_ipv6();
+    let host = addr_info.host();
+
+    let base_addr_style = if is_selected {
         Style::default().fg(Color::Yellow)
+    } else if is_local {
+        Style::default().fg(Color::DarkGray)
     } else {
         Style::default().fg(Color::White)
     };
 
-    let dim_style = if is_self_assigned {
+    let dim_style = if is_local {
         Style::default().fg(Color::DarkGray)
     } else {
         Style::default().fg(Color::Gray)
@@ -491,43 +1787,72 @@ fn build_addr_line<'a>(
         Style::default()
     };
 
+    let mut spans = vec![Span::styled(prefix, prefix_style)];
     match display_mode {
-        ListenDisplayMode::AddrPort => {
+        ListenDisplayMode::AddrPort | ListenDisplayMode::Qr => {
             if is_v6 {
-                Line::from(vec![
-                    Span::styled(prefix, prefix_style),
-                    Span::styled("[", dim_style),
-                    Span::styled(ip.to_string(), base_addr_style),
-                    Span::styled("]", dim_style),
-                    Span::styled(format!(":{}", port), dim_style),
-                ])
+                spans.push(Span::styled("[", dim_style));
+                spans.push(Span::styled(host, base_addr_style));
+                spans.push(Span::styled("]", dim_style));
             } else {
-                Line::from(vec![
-                    Span::styled(prefix, prefix_style),
-                    Span::styled(ip.to_string(), base_addr_style),
-                    Span::styled(format!(":{}", port), dim_style),
-                ])
+                spans.push(Span::styled(host, base_addr_style));
             }
+            spans.push(Span::styled(format!(":{}", port), dim_style));
         }
         ListenDisplayMode::NcCommand => {
+            spans.push(Span::styled("nc ", dim_style));
             if is_v6 {
-                Line::from(vec![
-                    Span::styled(prefix, prefix_style),
-                    Span::styled("nc ", dim_style),
-                    Span::styled("-6 ", dim_style),
-                    Span::styled(ip.to_string(), base_addr_style),
-                    Span::styled(format!(" {}", port), dim_style),
-                ])
-            } else {
-                Line::from(vec![
-                    Span::styled(prefix, prefix_style),
-                    Span::styled("nc ", dim_style),
-                    Span::styled(ip.to_string(), base_addr_style),
-                    Span::styled(format!(" {}", port), dim_style),
-                ])
+                spans.push(Span::styled("-6 ", dim_style));
             }
+            spans.push(Span::styled(host, base_addr_style));
+            spans.push(Span::styled(format!(" {}", port), dim_style));
+        }
+        ListenDisplayMode::PowerShell => {
+            let text = format_listen_addr(&host, is_v6, port, display_mode);
+            let (before, after) = text.split_once(&format!("'{}'", host)).unwrap_or((&text, ""));
+            spans.push(Span::styled(format!("{}'", before), dim_style));
+            spans.push(Span::styled(host, base_addr_style));
+            spans.push(Span::styled(format!("'{}", after), dim_style));
         }
     }
+    if is_local {
+        spans.push(Span::styled(
+            format!(" ({})", addr_info.note()),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ));
+    }
+    Line::from(spans)
+}
+
+fn draw_paste_prompt(frame: &mut Frame, text: &str) {
+    let area = frame.area();
+    let popup_width = PASTE_POPUP_WIDTH.min(area.width.saturating_sub(4));
+    let popup_height = QUIT_POPUP_HEIGHT.min(area.height.saturating_sub(4));
+    let popup_area = Rect {
+        x: area.width.saturating_sub(popup_width) / 2,
+        y: area.height.saturating_sub(popup_height) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    let lines = text.lines().filter(|l| !l.trim().is_empty()).count();
+    let text = vec![
+        Line::from(format!("  {} lines, {} bytes", lines, text.len())),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  l: add as log lines  k: type as keys  Esc: drop",
+            Style::default().fg(Color::White),
+        )),
+    ];
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Pasted text ")
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
 }
 
 fn draw_quit_confirm(frame: &mut Frame) {
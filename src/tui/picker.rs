@@ -0,0 +1,126 @@
+use crate::picker::{Picker, Step, OPTIONS};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const PICKER_WIDTH: u16 = 90;
+
+pub fn draw(frame: &mut Frame, picker: &Picker) {
+    let area = frame.area();
+    let width = PICKER_WIDTH.min(area.width.saturating_sub(2));
+    let height = area.height.saturating_sub(2);
+    let popup_area = Rect {
+        x: area.width.saturating_sub(width) / 2,
+        y: 1.min(area.height),
+        width,
+        height,
+    };
+    // Rows left for a list after the prompt, blank line and borders.
+    let rows = height.saturating_sub(6) as usize;
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    let (title, hint) = match &picker.step {
+        Step::Menu => {
+            lines.push(Line::from("No file given and nothing piped in. Where should logs come from?"));
+            lines.push(Line::from(""));
+            for (i, (label, description)) in OPTIONS.iter().enumerate() {
+                let style = row_style(i == picker.selected);
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{} {}. {:<30}", marker(i == picker.selected), i + 1, label), style),
+                    Span::styled(*description, dim),
+                ]));
+            }
+            (" logviewer ", "↑↓/1-4: Choose  Enter: Open  q/Esc: Quit")
+        }
+        Step::Files { replay } => {
+            let mut find = prompt("Find: ", &picker.input.text);
+            if picker.gitignore {
+                find.push_span(Span::styled("  (skipping .gitignored files)", dim));
+            }
+            lines.push(find);
+            lines.push(Line::from(""));
+            let start = picker.selected.saturating_sub(rows.saturating_sub(1));
+            for (i, path) in picker.matches.iter().enumerate().skip(start).take(rows) {
+                let selected = i == picker.selected;
+                lines.push(Line::from(Span::styled(
+                    format!("{} {}", marker(selected), path.display()),
+                    row_style(selected),
+                )));
+            }
+            if picker.matches.is_empty() {
+                lines.push(Line::from(Span::styled("No matching files", dim)));
+            }
+            let title = if *replay {
+                " Replay a session "
+            } else if picker.standalone {
+                " Open a file into this view "
+            } else {
+                " Open a file "
+            };
+            let hint = if picker.standalone {
+                "Type to filter  ↑↓: Select  Enter: Merge into view  Ctrl-G: .gitignore  Esc: Close"
+            } else {
+                "Type to filter  ↑↓: Select  Enter: Open  Ctrl-G: .gitignore  Esc: Back"
+            };
+            (title, hint)
+        }
+        Step::Port => {
+            lines.push(prompt("Port to listen on: ", &picker.input.text));
+            if let Some(error) = &picker.input.error {
+                lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+            }
+            (" Listen on a port ", "Enter: Listen  Esc: Back")
+        }
+        Step::Docker(Ok(containers)) => {
+            for (i, name) in containers.iter().enumerate().take(rows) {
+                let selected = i == picker.selected;
+                lines.push(Line::from(Span::styled(format!("{} {}", marker(selected), name), row_style(selected))));
+            }
+            (" Attach to a Docker container ", "↑↓: Select  Enter: Attach  Esc: Back")
+        }
+        Step::Docker(Err(error)) => {
+            lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+            (" Attach to a Docker container ", "Esc: Back")
+        }
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(hint, Style::default().fg(Color::Gray))));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn marker(selected: bool) -> &'static str {
+    if selected {
+        "▶"
+    } else {
+        " "
+    }
+}
+
+fn row_style(selected: bool) -> Style {
+    if selected {
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    }
+}
+
+fn prompt(label: &str, text: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(label.to_string(), Style::default().fg(Color::Cyan)),
+        Span::raw(text.to_string()),
+        Span::styled("█", Style::default().fg(Color::Gray)),
+    ])
+}
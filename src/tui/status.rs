@@ -0,0 +1,272 @@
+use crate::app::App;
+use crate::core::format_relative_time;
+use crate::macros::MacroPrompt;
+use crate::sample::Sampling;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+
+const SEPARATOR: &str = " │ ";
+
+/// Narrowest an elastic segment is cut to before it is dropped instead.
+const MIN_ELASTIC_WIDTH: usize = 8;
+
+/// A part of the TUI status bar; `status_segments` lists them in the order
+/// drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Segment {
+    Mode,
+    Source,
+    Filter,
+    Matches,
+    Follow,
+    Position,
+    Alerts,
+    Hints,
+}
+
+impl Segment {
+    /// Lower is kept longer when the bar is too narrow.
+    fn priority(self) -> u8 {
+        match self {
+            Segment::Alerts => 0,
+            Segment::Mode => 1,
+            Segment::Matches => 2,
+            Segment::Follow => 3,
+            Segment::Position => 4,
+            Segment::Source => 5,
+            Segment::Filter => 6,
+            Segment::Hints => 7,
+        }
+    }
+
+    /// Cut short rather than dropped while there is room for some of it.
+    fn elastic(self) -> bool {
+        matches!(self, Segment::Filter | Segment::Hints)
+    }
+}
+
+pub fn default_segments() -> Vec<Segment> {
+    vec![
+        Segment::Mode,
+        Segment::Alerts,
+        Segment::Matches,
+        Segment::Follow,
+        Segment::Position,
+        Segment::Source,
+        Segment::Filter,
+        Segment::Hints,
+    ]
+}
+
+pub struct Part {
+    pub segment: Segment,
+    pub text: String,
+    pub style: Style,
+}
+
+/// The status bar for `app`, fitted to `width` columns.
+pub fn status_line(app: &App, width: usize) -> Line<'static> {
+    let parts = app
+        .status_segments
+        .iter()
+        .filter_map(|&segment| {
+            let (text, style) = segment_text(app, segment);
+            (!text.is_empty()).then_some(Part { segment, text, style })
+        })
+        .collect();
+    let mut spans = Vec::new();
+    for (i, part) in fit(parts, width).into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(SEPARATOR));
+        }
+        spans.push(Span::styled(part.text, part.style));
+    }
+    Line::from(spans)
+}
+
+fn segment_text(app: &App, segment: Segment) -> (String, Style) {
+    let plain = Style::default();
+    match segment {
+        Segment::Mode => {
+            let mut text = app.input_mode.label().to_string();
+            if let Some((register, _)) = &app.recording {
+                text.push_str(&format!(" rec @{}", register));
+            }
+            if let Some(selection) = &app.selection {
+                text.push_str(if selection.block { " BLOCK" } else { " LINES" });
+            }
+            let pending = app.keymap.pending();
+            if !pending.is_empty() {
+                text.push(' ');
+                text.push_str(&pending);
+            }
+            let bg = if app.recording.is_some() { Color::Red } else { Color::Cyan };
+            (text, Style::default().fg(Color::Black).bg(bg).add_modifier(Modifier::BOLD))
+        }
+        Segment::Alerts => {
+            let text = match app.macro_prompt {
+                Some(MacroPrompt::Record) => "Record macro into register: a-z".to_string(),
+                Some(MacroPrompt::Play) => "Play macro from register: a-z".to_string(),
+                None if app.pipe_job.is_some() => {
+                    format!("Running :pipe {}", app.pipe_job.as_ref().map_or("", |job| job.command.as_str()))
+                }
+                None => match app.toasts.errors() {
+                    0 => String::new(),
+                    1 => "1 error (:messages)".to_string(),
+                    n => format!("{} errors (:messages)", n),
+                },
+            };
+            (text, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        }
+        Segment::Matches => {
+            let state = &app.log_state;
+            (format!("{}/{} lines", state.filtered_indices.len(), state.lines.len()), plain)
+        }
+        Segment::Follow => {
+            let text = if app.log_state.follow_tail {
+                "FOLLOW"
+            } else if app.log_state.follow_match.is_some() {
+                "FOLLOW MATCH"
+            } else {
+                "PAUSED"
+            };
+            (text.to_string(), plain)
+        }
+        Segment::Position => {
+            // Columns count from 1 like editors'; the byte offset from 0.
+            let text = app
+                .cursor_position()
+                .map(|(line_idx, column, byte, _)| format!("Ln {}, Col {} (byte {})", line_idx + 1, column + 1, byte))
+                .unwrap_or_default();
+            (text, plain)
+        }
+        Segment::Source => {
+            let mut text = if let Some(label) = app.replay_label() {
+                label
+            } else if app.read_only {
+                "bundle (read-only)".to_string()
+            } else if !app.listen_state.ports.is_empty() {
+                let ports: Vec<String> = app.listen_state.ports.iter().map(|p| format!(":{}", p)).collect();
+                match app.listen_state.peers.as_slice() {
+                    [peer] => format!("{} {}", ports.join(" "), app.peer_names.tag(peer)),
+                    peers => format!("{} {} clients", ports.join(" "), peers.len()),
+                }
+            } else {
+                String::new()
+            };
+            if let Some(time) = app.log_state.last_update_time {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(&format!("Last: {}", format_relative_time(time)));
+            }
+            (text, plain)
+        }
+        Segment::Filter => {
+            let state = &app.log_state;
+            let mut parts = Vec::new();
+            for (name, text) in [("filter", &app.input_fields.filter.text), ("hide", &app.input_fields.hide.text)] {
+                if !text.trim().is_empty() {
+                    parts.push(format!("{} {}", name, text.trim()));
+                }
+            }
+            if let Some(dedup) = &state.dedup {
+                parts.push(format!("dedup {}s", dedup.window_secs));
+            }
+            if state.sampler.mode != Sampling::Off {
+                parts.push(format!("sampled {}", state.sampler.label()));
+            }
+            if !state.snoozes.list.is_empty() {
+                parts.push(format!("snoozed {}", state.snoozes.list.len()));
+            }
+            if state.filter_state.show_raw {
+                parts.push("raw".to_string());
+            }
+            if state.filter_state.show_epochs {
+                parts.push("epochs".to_string());
+            }
+            if state.filter_state.humanize.is_some() {
+                parts.push("humanize".to_string());
+            }
+            if state.filter_state.enrich.is_some() {
+                parts.push("enrich".to_string());
+            }
+            (parts.join(", "), plain)
+        }
+        Segment::Hints => (
+            format!(
+                "q:Quit d:Hide f:Filter h:Highlight s:LineStart r:Rewrite c:Clear t:Time T:TimeFmt({}) w:Wrap R:Raw E:Epochs #:Ruler V:Select C-v:Block y:Yank gf:OpenFile gi:Issue X:Snooze K:Fields ?:Why gl:Layout gs:Split C-w:Pane p:Pin Space:Mark D:Dedup S:FilterStats n:Note N:ShowNote W:Watch m:Panel O:Outline C:Correlate |:Lanes ::Command F:FollowMatch P:Playground Q:Record @:Play Enter:Detail e:Entity z:Trace L:Columns{}",
+                app.time_format.label(),
+                if app.replay.is_some() { " Space:Pause >:Speed .:Skip" } else { "" },
+            ),
+            Style::default().fg(Color::Gray),
+        ),
+    }
+}
+
+fn width(parts: &[Part]) -> usize {
+    let text: usize = parts.iter().map(|p| p.text.chars().count()).sum();
+    text + SEPARATOR.chars().count() * parts.len().saturating_sub(1)
+}
+
+/// Keep the parts that fit in `max`: the lowest-priority part is cut short
+/// if elastic and enough of it is left, otherwise dropped, until the rest
+/// fits. A lone part that still doesn't fit is cut.
+pub fn fit(mut parts: Vec<Part>, max: usize) -> Vec<Part> {
+    loop {
+        let overflow = width(&parts).saturating_sub(max);
+        if overflow == 0 {
+            return parts;
+        }
+        let Some(idx) = (0..parts.len()).max_by_key(|&i| parts[i].segment.priority()) else {
+            return parts;
+        };
+        let alone = parts.len() == 1;
+        let part = &mut parts[idx];
+        let len = part.text.chars().count();
+        if alone || (part.segment.elastic() && len >= overflow + MIN_ELASTIC_WIDTH) {
+            let keep = len.saturating_sub(overflow + 1);
+            part.text = part.text.chars().take(keep).collect::<String>() + "…";
+            return parts;
+        }
+        parts.remove(idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(segment: Segment, text: &str) -> Part {
+        Part {
+            segment,
+            text: text.to_string(),
+            style: Style::default(),
+        }
+    }
+
+    fn texts(parts: &[Part]) -> Vec<&str> {
+        parts.iter().map(|p| p.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fit_drops_lowest_priority_first() {
+        let parts = || {
+            vec![
+                part(Segment::Mode, "NORMAL"),
+                part(Segment::Source, ":5000 2 clients"),
+                part(Segment::Matches, "10/20 lines"),
+                part(Segment::Hints, "q:Quit d:Hide f:Filter"),
+            ]
+        };
+        assert_eq!(width(&parts()), 6 + 15 + 11 + 22 + 9);
+        assert_eq!(texts(&fit(parts(), 63)).len(), 4);
+        // Hints lose their tail while at least eight columns are left.
+        assert_eq!(texts(&fit(parts(), 50))[3], "q:Quit d…");
+        assert_eq!(texts(&fit(parts(), 40)), ["NORMAL", ":5000 2 clients", "10/20 lines"]);
+        assert_eq!(texts(&fit(parts(), 20)), ["NORMAL", "10/20 lines"]);
+        assert_eq!(texts(&fit(parts(), 4)), ["NOR…"]);
+    }
+}
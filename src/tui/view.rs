@@ -0,0 +1,302 @@
+use crate::app::Row;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use std::ops::Range;
+
+/// A filtered line ready to lay out: its prefix columns and the rows its
+/// text renders to before wrapping (folded traces, notes and the repeat
+/// badge already applied).
+pub struct Record {
+    pub line_idx: usize,
+    pub prefix: Vec<Span<'static>>,
+    pub rows: Vec<Row>,
+    /// `── 2025-06-12 ──` above the line when it starts a new day.
+    pub divider: Option<String>,
+}
+
+/// Log text a screen row shows: the line, the highlighted segment, the
+/// row's byte offset in it and the row's text.
+pub type RowSource = (usize, Range<usize>, usize, String);
+
+pub struct ScreenRow {
+    pub line: Line<'static>,
+    pub source: Option<RowSource>,
+}
+
+/// The rows to draw and the filtered positions they span.
+pub struct View {
+    pub rows: Vec<ScreenRow>,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+/// Turns records into exactly the screen rows of the log view, so layout
+/// can be worked out (and tested) without a terminal.
+pub struct ViewModel {
+    pub height: usize,
+    /// Width left for the text after the prefix.
+    pub content_width: usize,
+    pub prefix_width: usize,
+    pub wrap: bool,
+}
+
+impl ViewModel {
+    /// Screen rows of one record: wrapped when wrapping is on, with the
+    /// prefix on the first row and blank space under it on the rest.
+    pub fn record_rows(&self, record: Record) -> Vec<ScreenRow> {
+        let mut out = Vec::new();
+        if let Some(divider) = record.divider {
+            out.push(ScreenRow {
+                line: Line::styled(divider, Style::default().fg(Color::DarkGray)),
+                source: None,
+            });
+        }
+        let mut prefix = Some(record.prefix);
+        for row in record.rows {
+            let pieces = if self.wrap && self.content_width > 0 {
+                wrap_highlighted(&row.runs, self.content_width)
+            } else {
+                vec![row.runs.into_iter().map(|(t, s)| Span::styled(t, s)).collect()]
+            };
+            let mut offset = 0;
+            for piece in pieces {
+                let text: String = piece.iter().map(|s| s.content.as_ref()).collect();
+                let source = row
+                    .source
+                    .clone()
+                    .map(|(segment, start)| (record.line_idx, segment, start + offset, text.clone()));
+                offset += text.len();
+                let mut spans = prefix
+                    .take()
+                    .unwrap_or_else(|| vec![Span::styled(" ".repeat(self.prefix_width), Style::default())]);
+                spans.extend(piece);
+                out.push(ScreenRow {
+                    line: Line::from(spans),
+                    source,
+                });
+            }
+        }
+        out
+    }
+
+    /// Fill the view upwards from filtered position `bottom`, cutting the
+    /// top record when it doesn't fit whole.
+    pub fn layout(&self, bottom: usize, mut record: impl FnMut(usize) -> Record) -> View {
+        let mut rows: Vec<ScreenRow> = Vec::new();
+        let mut top = bottom;
+        for pos in (0..=bottom).rev() {
+            if rows.len() >= self.height {
+                break;
+            }
+            let record_rows = self.record_rows(record(pos));
+            top = pos;
+            for row in record_rows.into_iter().rev() {
+                rows.push(row);
+                if rows.len() >= self.height {
+                    break;
+                }
+            }
+        }
+        rows.reverse();
+        View { rows, top, bottom }
+    }
+
+    /// The last filtered position that still fits when `top` is drawn
+    /// first, out of `total`.
+    pub fn bottom_for_top(&self, top: usize, total: usize, mut record: impl FnMut(usize) -> Record) -> usize {
+        let mut used = 0;
+        let mut bottom = top;
+        for pos in top..total {
+            used += self.record_rows(record(pos)).len();
+            if used > self.height && pos > top {
+                break;
+            }
+            bottom = pos;
+        }
+        bottom
+    }
+
+    /// The filtered position at the bottom after scrolling `rows` screen
+    /// rows from `bottom`, down when positive, by at least one line.
+    pub fn scroll(&self, bottom: usize, rows: isize, total: usize, mut record: impl FnMut(usize) -> Record) -> usize {
+        let limit = rows.unsigned_abs();
+        let mut pos = bottom;
+        let mut used = 0;
+        if rows > 0 {
+            while pos + 1 < total {
+                used += self.record_rows(record(pos + 1)).len();
+                if used > limit && pos > bottom {
+                    break;
+                }
+                pos += 1;
+            }
+        } else {
+            while pos > 0 {
+                used += self.record_rows(record(pos)).len();
+                if used > limit && pos < bottom {
+                    break;
+                }
+                pos -= 1;
+            }
+        }
+        pos
+    }
+}
+
+pub fn wrap_highlighted(spans: &[(String, Style)], width: usize) -> Vec<Vec<Span<'static>>> {
+    if width == 0 {
+        return vec![spans
+            .iter()
+            .map(|(t, s)| Span::styled(t.clone(), *s))
+            .collect()];
+    }
+
+    let mut result: Vec<Vec<Span<'static>>> = vec![Vec::new()];
+    let mut current_width = 0;
+
+    for (text, style) in spans {
+        let mut remaining = text.as_str();
+        while !remaining.is_empty() {
+            let available = width.saturating_sub(current_width);
+            if available == 0 {
+                result.push(Vec::new());
+                current_width = 0;
+                continue;
+            }
+
+            let take_chars: usize = remaining.chars().take(available).count();
+            let byte_end = remaining
+                .char_indices()
+                .nth(take_chars)
+                .map(|(i, _)| i)
+                .unwrap_or(remaining.len());
+
+            let (chunk, rest) = remaining.split_at(byte_end);
+            if !chunk.is_empty() {
+                result
+                    .last_mut()
+                    .unwrap()
+                    .push(Span::styled(chunk.to_string(), *style));
+                current_width += chunk.chars().count();
+            }
+            remaining = rest;
+
+            if !remaining.is_empty() {
+                result.push(Vec::new());
+                current_width = 0;
+            }
+        }
+    }
+
+    result
+}
+
+/// `runs` with the characters in `columns` (every one for `None`) patched
+/// with `style`.
+pub fn mark_runs(runs: Vec<(String, Style)>, columns: Option<Range<usize>>, style: Style) -> Vec<(String, Style)> {
+    let mut out = Vec::with_capacity(runs.len());
+    let mut col = 0;
+    for (text, base) in runs {
+        let len = text.chars().count();
+        let (start, end) = match &columns {
+            Some(cols) => (cols.start.clamp(col, col + len) - col, cols.end.clamp(col, col + len) - col),
+            None => (0, len),
+        };
+        col += len;
+        if start == end {
+            out.push((text, base));
+            continue;
+        }
+        let byte = |c: usize| text.char_indices().nth(c).map_or(text.len(), |(i, _)| i);
+        let (start, end) = (byte(start), byte(end));
+        for (part, part_style) in [(&text[..start], base), (&text[start..end], base.patch(style)), (&text[end..], base)] {
+            if !part.is_empty() {
+                out.push((part.to_string(), part_style));
+            }
+        }
+    }
+    out
+}
+
+/// `....:....1....:....2` for `width` columns, counting from 1.
+pub fn ruler(width: usize) -> String {
+    (1..=width)
+        .map(|col| match col {
+            _ if col % 10 == 0 => char::from_digit((col / 10 % 10) as u32, 10).unwrap_or('0'),
+            _ if col % 5 == 0 => ':',
+            _ => '.',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(line_idx: usize, text: &str) -> Record {
+        Record {
+            line_idx,
+            prefix: vec![Span::raw(format!("{:>2} ", line_idx))],
+            rows: vec![Row {
+                runs: vec![(text.to_string(), Style::default())],
+                source: Some((0..text.len(), 0)),
+            }],
+            divider: None,
+        }
+    }
+
+    fn texts(view: &View) -> Vec<String> {
+        view.rows.iter().map(|row| row.line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_layout_wraps_and_clips_top() {
+        let model = ViewModel {
+            height: 4,
+            content_width: 5,
+            prefix_width: 3,
+            wrap: true,
+        };
+        let lines = ["abcdefgh", "xyz", "0123456789"];
+        let view = model.layout(2, |pos| record(pos, lines[pos]));
+        assert_eq!(texts(&view), ["   fgh", " 1 xyz", " 2 01234", "   56789"]);
+        assert_eq!(view.top, 0);
+        let (line_idx, _, offset, text) = view.rows[3].source.clone().unwrap();
+        assert_eq!((line_idx, offset, text.as_str()), (2, 5, "56789"));
+        assert_eq!(model.bottom_for_top(0, lines.len(), |pos| record(pos, lines[pos])), 1);
+
+        let dated = |pos| Record {
+            divider: (pos == 1).then(|| "── 2025-06-12 ──".to_string()),
+            ..record(pos, lines[pos])
+        };
+        let view = model.layout(2, dated);
+        assert_eq!(texts(&view), ["── 2025-06-12 ──", " 1 xyz", " 2 01234", "   56789"]);
+        assert_eq!(view.top, 1);
+        assert_eq!(model.bottom_for_top(1, lines.len(), dated), 2);
+    }
+
+    #[test]
+    fn test_scroll_by_screen_rows() {
+        let model = ViewModel {
+            height: 4,
+            content_width: 5,
+            prefix_width: 3,
+            wrap: true,
+        };
+        let lines = ["a", "0123456789", "b", "c", "0123456789abc", "d"];
+        let rows = |pos| record(pos, lines[pos]);
+        assert_eq!(model.scroll(0, 2, lines.len(), rows), 1);
+        assert_eq!(model.scroll(1, 2, lines.len(), rows), 3);
+        assert_eq!(model.scroll(3, 2, lines.len(), rows), 4);
+        assert_eq!(model.scroll(4, 4, lines.len(), rows), 5);
+        assert_eq!(model.scroll(5, -4, lines.len(), rows), 3);
+        assert_eq!(model.scroll(3, -2, lines.len(), rows), 1);
+        assert_eq!(model.scroll(1, -1, lines.len(), rows), 0);
+    }
+
+    #[test]
+    fn test_ruler() {
+        assert_eq!(ruler(12), "....:....1..");
+        assert_eq!(&ruler(110)[95..], "....0....:....1");
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::collections::VecDeque;
+
+/// Values kept per watch for the chart.
+pub const HISTORY_LEN: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WatchStats {
+    pub latest: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: usize,
+}
+
+impl WatchStats {
+    fn record(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.latest = Some(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+/// A regex whose first capture group is read as a number from every line.
+#[derive(Clone, Debug)]
+pub struct Watch {
+    pub pattern: String,
+    regex: Regex,
+    pub stats: WatchStats,
+    pub history: VecDeque<f64>,
+}
+
+impl Watch {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|e| anyhow!("{}", e))?;
+        if regex.captures_len() < 2 {
+            return Err(anyhow!("Watch '{}' needs a capture group", pattern));
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+            stats: WatchStats::default(),
+            history: VecDeque::new(),
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.stats = WatchStats::default();
+        self.history.clear();
+    }
+
+    pub fn observe(&mut self, content: &str) {
+        let Some(value) = self
+            .regex
+            .captures(content)
+            .and_then(|caps| caps.get(1))
+            .and_then(|m| m.as_str().parse::<f64>().ok())
+        else {
+            return;
+        };
+        self.stats.record(value);
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(value);
+    }
+}
+
+/// Parse whitespace-separated watch patterns. Quote a pattern that contains
+/// spaces: `latency_ms=(\d+) "took (\d+) ms"`.
+pub fn parse_watches(input: &str) -> Result<Vec<Watch>> {
+    let mut watches = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+        let mut pattern = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') if chars.peek() == Some(&'"') => {
+                        pattern.push('"');
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(c) => pattern.push(c),
+                    None => return Err(anyhow!("Unterminated string")),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                pattern.push(c);
+            }
+        }
+        watches.push(Watch::new(&pattern)?);
+    }
+    Ok(watches)
+}
+
+/// Integers without a fractional part, everything else to two decimals.
+pub fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_stats() {
+        let mut watch = Watch::new(r"latency_ms=(\d+(?:\.\d+)?)").unwrap();
+        for line in ["req latency_ms=10", "no match", "req latency_ms=30", "req latency_ms=2.5"] {
+            watch.observe(line);
+        }
+        assert_eq!(watch.stats.count, 3);
+        assert_eq!(watch.stats.latest, Some(2.5));
+        assert_eq!((watch.stats.min, watch.stats.max), (2.5, 30.0));
+        assert_eq!(format_value(watch.stats.avg().unwrap()), "14.17");
+        assert_eq!(watch.history, [10.0, 30.0, 2.5]);
+    }
+
+    #[test]
+    fn test_parse_watches() {
+        let watches = parse_watches(r#"latency_ms=(\d+) "took (\d+) ms""#).unwrap();
+        let patterns: Vec<&str> = watches.iter().map(|w| w.pattern.as_str()).collect();
+        assert_eq!(patterns, vec![r"latency_ms=(\d+)", r"took (\d+) ms"]);
+        assert!(parse_watches("latency_ms=\\d+").is_err());
+        assert!(parse_watches("\"unterminated (\\d+)").is_err());
+    }
+}